@@ -5,10 +5,11 @@
 mod resolver;
 pub use self::resolver::{ChipInfo, Resolver};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::{Parser, ValueEnum};
-use gpiocdev::chip::{chips, is_chip, Chip};
-use gpiocdev::line::{Bias, Drive, EdgeDetection};
+use gpiocdev::chip::{chips, is_chip, path_from_id, Chip};
+use gpiocdev::diagnostic::diagnose_line;
+use gpiocdev::line::{self, Bias, Drive, EdgeDetection, Offset};
 use gpiocdev::request::Config;
 use gpiocdev::AbiVersion;
 use std::path::{Path, PathBuf};
@@ -54,55 +55,51 @@ pub fn actual_abi_version(_opts: &UapiOpts) -> Result<AbiVersion> {
     Ok(AbiVersion::V2)
 }
 
-fn chip_path_from_id(id: &str) -> PathBuf {
-    if id.chars().all(char::is_numeric) {
-        // from number
-        return format!("/dev/gpiochip{}", id).into();
-    }
-    if !id.chars().any(|x| x == '/') {
-        // from name
-        let mut p: PathBuf = "/dev".into();
-        p.push(id);
-        return p;
-    }
-    // from raw path
-    id.into()
+pub fn wait_for_chip(id: &str, timeout: Duration) -> Result<()> {
+    Chip::wait_for(path_from_id(id), timeout)
+        .map(|_| ())
+        .with_context(|| format!("timed out waiting for GPIO chip '{}'", id))
 }
 
 pub fn chip_lookup_from_id(id: &str) -> Result<PathBuf> {
-    is_chip(chip_path_from_id(id))
+    is_chip(path_from_id(id))
         .with_context(|| format!("cannot find GPIO chip character device '{}'", id))
 }
 
-#[derive(Debug, Eq, PartialEq, thiserror::Error)]
-pub enum ParseDurationError {
-    #[error("'{0}' unknown units - use 's', 'ms' or 'us'.")]
-    Units(String),
-    #[error("'{0}' must start with a digit")]
-    NoDigits(String),
-}
-
-pub fn parse_duration(s: &str) -> std::result::Result<Duration, ParseDurationError> {
-    if s == "0" {
-        return Ok(Duration::ZERO);
-    }
-    let t = match s.find(|c: char| !c.is_ascii_digit()) {
-        Some(0) => return Err(ParseDurationError::NoDigits(s.to_string())),
-        Some(n) => {
-            let (num, units) = s.split_at(n);
-            let t = num.parse::<u64>().unwrap();
-            t * match units {
-                "us" => 1000,
-                "ms" => 1000000,
-                "s" => 1000000000,
-                _ => return Err(ParseDurationError::Units(s.to_string())),
-            }
-        }
-        None => s.parse::<u64>().unwrap() * 1000000,
+// A hint as to why lines could not be requested, for appending to the error.
+//
+// Empty if no hint can be determined.
+pub fn conflict_hint(chip: &Path, offsets: &[Offset]) -> String {
+    let chip = match Chip::from_path(chip) {
+        Ok(chip) => chip,
+        Err(_) => return String::new(),
     };
-    Ok(Duration::from_nanos(t))
+    let hints: Vec<String> = offsets
+        .iter()
+        .filter_map(|offset| {
+            diagnose_line(&chip, *offset)
+                .ok()?
+                .hint()
+                .map(|hint| format!("line {}: {}", offset, hint))
+        })
+        .collect();
+    if hints.is_empty() {
+        String::new()
+    } else {
+        format!(" - {}", hints.join("; "))
+    }
 }
 
+// Parse a duration, such as "20ms" or "1m30s".
+//
+// A bare number is in milliseconds.
+pub use gpiocdev::line::parse_period as parse_duration;
+
+// Format a duration using the largest units that represent it exactly.
+//
+// The result can be parsed by parse_duration to return the original duration.
+pub use gpiocdev::line::format_period as format_duration;
+
 // common command line parser options
 
 #[derive(Debug, Parser)]
@@ -114,6 +111,9 @@ pub struct LineOpts {
     ///
     /// If not specified then the named lines are searched for on all chips in the system.
     ///
+    /// Lines may also be identified by chip and offset, in the form <chip>:<offset>,
+    /// e.g. gpiochip0:5 or 0:5
+    ///
     /// The chip may be identified by number, name, or path.
     /// e.g. the following all select the same chip:
     ///     --chip 0
@@ -137,6 +137,19 @@ pub struct LineOpts {
     /// With --by-name set the lines are always identified by name, never as offsets.
     #[arg(long)]
     pub by_name: bool,
+
+    /// Wait up to this period for the chip to become available
+    ///
+    /// Allows for the chip driver not having been loaded yet, such as during boot.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(
+        long,
+        value_name = "period",
+        requires = "chip",
+        value_parser = parse_duration
+    )]
+    pub wait_for_chip: Option<Duration>,
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -178,6 +191,17 @@ pub fn emit_error(opts: &EmitOpts, e: &anyhow::Error) {
     eprintln!("{}", e_str);
 }
 
+#[cfg(feature = "uapi_v2")]
+#[allow(unused_variables)]
+pub fn emit_warning(opts: &EmitOpts, msg: &str) {
+    #[cfg(feature = "json")]
+    if opts.json {
+        println!("{}", serde_json::json!({ "warning": msg }));
+        return;
+    }
+    eprintln!("warning: {}", msg);
+}
+
 pub fn format_error(opts: &EmitOpts, e: &anyhow::Error) -> String {
     if opts.verbose {
         format!("{:#}", e)
@@ -318,6 +342,188 @@ impl EdgeOpts {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum EventClockFlags {
+    Monotonic,
+    Realtime,
+    Hte,
+}
+impl EventClockFlags {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventClockFlags::Monotonic => "monotonic",
+            EventClockFlags::Realtime => "realtime",
+            EventClockFlags::Hte => "hte",
+        }
+    }
+}
+impl From<EventClockFlags> for line::EventClock {
+    fn from(c: EventClockFlags) -> Self {
+        match c {
+            EventClockFlags::Monotonic => line::EventClock::Monotonic,
+            EventClockFlags::Realtime => line::EventClock::Realtime,
+            EventClockFlags::Hte => line::EventClock::Hte,
+        }
+    }
+}
+
+// Check that the event clock can be selected with the uAPI version
+// and is supported by the running kernel.
+#[cfg(feature = "uapi_v2")]
+pub fn check_event_clock(clock: EventClockFlags, abiv: AbiVersion) -> Result<()> {
+    if abiv == AbiVersion::V1 {
+        bail!("uAPI v1 does not support selecting the event clock");
+    }
+    let (major, minor) = match clock {
+        EventClockFlags::Monotonic => return Ok(()),
+        EventClockFlags::Realtime => (5, 11),
+        EventClockFlags::Hte => (5, 19),
+    };
+    if let Some(release) = kernel_release() {
+        if let Some(version) = parse_kernel_version(&release) {
+            if version < (major, minor) {
+                bail!(
+                    "kernel {} does not support the {} event clock (added in {}.{})",
+                    release,
+                    clock.name(),
+                    major,
+                    minor
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// The event clock to request, falling back if the clock is not supported.
+//
+// Falls back to the monotonic clock, or the kernel default clock for uAPI v1,
+// with a warning.
+#[cfg(feature = "uapi_v2")]
+pub fn select_event_clock(
+    clock: EventClockFlags,
+    abiv: AbiVersion,
+    opts: &EmitOpts,
+) -> Option<EventClockFlags> {
+    match check_event_clock(clock, abiv) {
+        Ok(()) => Some(clock),
+        Err(e) if abiv == AbiVersion::V1 => {
+            emit_warning(opts, &format!("{}, so using the kernel default clock", e));
+            None
+        }
+        Err(e) => {
+            emit_warning(opts, &format!("{}, so using the monotonic clock", e));
+            Some(EventClockFlags::Monotonic)
+        }
+    }
+}
+
+// Request lines with the event clock, falling back to the monotonic clock
+// if the request fails with the hte clock, as the lines may not have an hte provider.
+//
+// Returns the request and the event clock used.
+#[allow(unused_variables)]
+pub fn request_with_clock_fallback<F>(
+    clock: Option<EventClockFlags>,
+    lines: &str,
+    opts: &EmitOpts,
+    request: F,
+) -> (gpiocdev::Result<gpiocdev::Request>, Option<EventClockFlags>)
+where
+    F: Fn(Option<EventClockFlags>) -> gpiocdev::Result<gpiocdev::Request>,
+{
+    let req = request(clock);
+    #[cfg(feature = "uapi_v2")]
+    if req.is_err() && clock == Some(EventClockFlags::Hte) {
+        let mono = Some(EventClockFlags::Monotonic);
+        if let Ok(req) = request(mono) {
+            emit_warning(
+                opts,
+                &format!(
+                    "the hte event clock is not supported by {}, so using the monotonic clock",
+                    lines
+                ),
+            );
+            return (Ok(req), mono);
+        }
+    }
+    (req, clock)
+}
+
+// The release of the running kernel, e.g. "6.1.21-v8+".
+pub fn kernel_release() -> Option<String> {
+    let version = std::fs::read_to_string("/proc/version").ok()?;
+    version.split_ascii_whitespace().nth(2).map(|v| v.into())
+}
+
+// The major and minor version from a kernel release.
+pub fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut f = release.split('.');
+    let major = f.next()?.parse().ok()?;
+    let minor = f.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+#[derive(Clone, Debug, Parser)]
+pub struct LineConfigOpts {
+    /// Override the configuration of a particular line
+    ///
+    /// The config is a comma separated list of attributes that are
+    /// applied to the line on top of the other options, e.g.
+    ///     --line-config GPIO17:pull-up,active-low
+    ///     --line-config GPIO22:edges=rising,debounce=5ms
+    ///
+    /// The line must be one of the requested lines.
+    /// May be repeated to configure multiple lines.
+    #[arg(
+        long,
+        value_name = "line:config",
+        value_parser = parse_line_config,
+        verbatim_doc_comment
+    )]
+    pub line_config: Vec<(String, String)>,
+}
+impl LineConfigOpts {
+    // Check that all the configured lines are being requested.
+    pub fn check(&self, r: &Resolver) -> Result<()> {
+        for (id, _) in &self.line_config {
+            if !r.lines.contains_key(id) {
+                bail!("line '{}' in --line-config is not a requested line", id);
+            }
+        }
+        Ok(())
+    }
+
+    // Apply the line configs for lines on the chip.
+    //
+    // Each attribute overrides the existing setting, so a line config may also
+    // clear settings, e.g. with active-high or edges=none.
+    //
+    // The lines must already have been added to the config.
+    pub fn apply(&self, r: &Resolver, chip_idx: usize, cfg: &mut Config) {
+        for (id, attrs) in &self.line_config {
+            let co = match r.lines.get(id) {
+                Some(co) if co.chip_idx == chip_idx => co,
+                _ => continue,
+            };
+            let mut merged = cfg.line_config(co.offset).cloned().unwrap_or_default();
+            merged
+                .apply_str(attrs)
+                .expect("line config should be validated when parsed");
+            cfg.with_line(co.offset).from_line_config(&merged);
+        }
+    }
+}
+
+fn parse_line_config(s: &str) -> Result<(String, String)> {
+    let pos = s
+        .rfind(':')
+        .ok_or_else(|| anyhow!("invalid line:config: no ':' found in '{}'", s))?;
+    let attrs = &s[pos + 1..];
+    attrs.parse::<line::Config>()?;
+    Ok((s[..pos].to_string(), attrs.to_string()))
+}
+
 #[derive(Clone, Debug, Parser)]
 pub struct LinesOpts {}
 
@@ -357,8 +563,8 @@ pub fn stringify_attrs(li: &gpiocdev::line::Info, quoted: bool) -> String {
         Some(EventClock::Hte) => attrs.push("event-clock=hte"),
     }
     let db;
-    if li.debounce_period.is_some() {
-        db = format!("debounce-period={:?}", li.debounce_period.unwrap());
+    if let Some(dp) = li.debounce_period {
+        db = format!("debounce-period={}", format_duration(dp));
         attrs.push(&db);
     }
     let consumer;
@@ -434,23 +640,90 @@ mod tests {
 
     mod parse {
         #[test]
-        fn duration() {
-            use super::{parse_duration, ParseDurationError};
+        fn kernel_version() {
+            use super::parse_kernel_version;
+
+            assert_eq!(parse_kernel_version("6.1.21-v8+"), Some((6, 1)));
+            assert_eq!(parse_kernel_version("5.10.0"), Some((5, 10)));
+            assert_eq!(parse_kernel_version("5.19"), Some((5, 19)));
+            assert_eq!(parse_kernel_version("5"), None);
+            assert_eq!(parse_kernel_version("unknown"), None);
+        }
+
+        #[test]
+        fn stringify_attrs() {
+            use super::stringify_attrs;
+            use gpiocdev::line::Info;
             use std::time::Duration;
 
-            assert_eq!(parse_duration("0").unwrap(), Duration::ZERO);
-            assert_eq!(parse_duration("1").unwrap(), Duration::from_millis(1));
-            assert_eq!(parse_duration("2ms").unwrap(), Duration::from_millis(2));
-            assert_eq!(parse_duration("3us").unwrap(), Duration::from_micros(3));
-            assert_eq!(parse_duration("4s").unwrap(), Duration::new(4, 0));
+            let mut li = Info {
+                debounce_period: Some(Duration::from_micros(1500)),
+                ..Default::default()
+            };
             assert_eq!(
-                parse_duration("5ns").unwrap_err(),
-                ParseDurationError::Units("5ns".to_string())
+                stringify_attrs(&li, false),
+                "input debounce-period=1ms500us"
             );
-            assert_eq!(
-                parse_duration("bad").unwrap_err(),
-                ParseDurationError::NoDigits("bad".to_string())
+            li.debounce_period = Some(Duration::from_millis(20));
+            assert_eq!(stringify_attrs(&li, false), "input debounce-period=20ms");
+        }
+
+        #[test]
+        fn line_config() {
+            use super::parse_line_config;
+
+            let (id, attrs) = parse_line_config("GPIO17:pull-up,active-low").unwrap();
+            assert_eq!(id, "GPIO17");
+            assert_eq!(attrs, "pull-up,active-low");
+            let (id, attrs) = parse_line_config("a:b:input").unwrap();
+            assert_eq!(id, "a:b");
+            assert_eq!(attrs, "input");
+        }
+
+        #[test]
+        fn line_config_overrides() {
+            use super::resolver::ChipOffset;
+            use super::{LineConfigOpts, Resolver};
+            use gpiocdev::line::{Bias, EdgeDetection};
+            use gpiocdev::request::Config;
+            use std::collections::HashMap;
+
+            let mut lines = HashMap::new();
+            lines.insert(
+                "GPIO17".to_string(),
+                ChipOffset {
+                    chip_idx: 0,
+                    offset: 17,
+                },
             );
+            let r = Resolver {
+                lines,
+                ..Default::default()
+            };
+            let opts = LineConfigOpts {
+                line_config: vec![(
+                    "GPIO17".to_string(),
+                    "active-high,bias=as-is,edges=none".to_string(),
+                )],
+            };
+            let mut cfg = Config::default();
+            cfg.with_line(17)
+                .as_active_low()
+                .with_bias(Bias::PullUp)
+                .with_edge_detection(EdgeDetection::BothEdges);
+            opts.apply(&r, 0, &mut cfg);
+            let lc = cfg.line_config(17).unwrap();
+            assert!(!lc.active_low);
+            assert_eq!(lc.bias, None);
+            assert_eq!(lc.edge_detection, None);
+        }
+
+        #[test]
+        fn line_config_errors() {
+            use super::parse_line_config;
+
+            assert!(parse_line_config("GPIO17").is_err());
+            assert!(parse_line_config("GPIO17:sideways").is_err());
         }
     }
 }
@@ -5,7 +5,7 @@
 use super::{actual_abi_version, Error, LineOpts, UapiOpts};
 use anyhow::anyhow;
 use gpiocdev::chip::Chip;
-use gpiocdev::line::{Info, Offset};
+use gpiocdev::line::{Info, LineRef, Offset};
 use gpiocdev::AbiVersion;
 use nohash_hasher::IntMap;
 use std::collections::{HashMap, VecDeque};
@@ -80,7 +80,11 @@ impl Resolver {
             ..Default::default()
         };
         let chips = match &line_opts.chip {
-            Some(chip_id) => match super::chip_lookup_from_id(chip_id) {
+            Some(chip_id) => match line_opts
+                .wait_for_chip
+                .map_or(Ok(()), |timeout| super::wait_for_chip(chip_id, timeout))
+                .and_then(|_| super::chip_lookup_from_id(chip_id))
+            {
                 Ok(p) => vec![p],
                 Err(e) => {
                     r.errors.push(e);
@@ -105,6 +109,17 @@ impl Resolver {
         let mut uniq_lines = lines.to_vec();
         uniq_lines.sort_unstable();
         uniq_lines.dedup();
+        // lines identified by chip and offset, e.g. gpiochip0:5
+        let mut chip_offsets = Vec::new();
+        if !line_opts.by_name {
+            for id in &uniq_lines {
+                if let Ok(LineRef::Offset(chip, offset)) = id.parse() {
+                    if let Ok(path) = super::chip_lookup_from_id(&chip) {
+                        chip_offsets.push((id.to_owned(), path, offset));
+                    }
+                }
+            }
+        }
         let mut chip_idx = 0;
         let done =
             |r: &Resolver| !exhaustive && !uniq_lines.is_empty() && r.lines.len() == lines.len();
@@ -134,8 +149,24 @@ impl Resolver {
                 named_lines: IntMap::default(),
             };
 
-            // first match line by offset - but only when id by offset is possible
-            let mut offsets = VecDeque::new();
+            // first match lines identified by chip and offset
+            let mut offsets = Vec::new();
+            for (id, _, offset) in chip_offsets.iter().filter(|(_, p, _)| p == path) {
+                if *offset < kci.num_lines {
+                    r.lines.insert(
+                        id.to_owned(),
+                        ChipOffset {
+                            chip_idx,
+                            offset: *offset,
+                        },
+                    );
+                    chip_used = true;
+                    if with_info {
+                        offsets.push(*offset);
+                    }
+                }
+            }
+            // then match line by offset - but only when id by offset is possible
             if idx == 0 && chips.len() == 1 && !line_opts.by_name {
                 for id in &uniq_lines {
                     if let Ok(offset) = id.parse::<u32>() {
@@ -143,19 +174,20 @@ impl Resolver {
                             r.lines
                                 .insert(id.to_owned(), ChipOffset { chip_idx, offset });
                             if with_info {
-                                offsets.push_back(offset);
+                                offsets.push(offset);
                                 chip_used = true;
                             }
                         }
                     }
                 }
-                if done(&r) {
-                    if with_info {
-                        r.get_offset_info(&chip, &offsets);
-                    }
-                    r.chips.push(ci);
-                    break;
-                }
+            }
+            offsets.sort_unstable();
+            offsets.dedup();
+            let mut offsets = VecDeque::from(offsets);
+            if done(&r) {
+                r.get_offset_info(&chip, chip_idx, &offsets);
+                r.chips.push(ci);
+                break;
             }
             // match by name
             for offset in 0..kci.num_lines {
@@ -204,7 +236,7 @@ impl Resolver {
                 }
             }
             // might still have some offsets that need info, so fill those in
-            r.get_offset_info(&chip, &offsets);
+            r.get_offset_info(&chip, chip_idx, &offsets);
 
             if chip_used {
                 r.chips.push(ci);
@@ -218,12 +250,12 @@ impl Resolver {
         r
     }
 
-    fn get_offset_info(&mut self, chip: &Chip, offsets: &VecDeque<Offset>) {
+    fn get_offset_info(&mut self, chip: &Chip, chip_idx: usize, offsets: &VecDeque<Offset>) {
         for offset in offsets {
             match chip.line_info(*offset) {
                 Ok(li) => {
                     self.info.push(LineInfo {
-                        chip: 0,
+                        chip: chip_idx,
                         info: li.clone(),
                     });
                 }
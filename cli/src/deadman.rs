@@ -0,0 +1,178 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use gpiocdev::deadman::{Heartbeat, Supervisor};
+use gpiocdev::line::Value;
+use gpiocdev::request::{Config, Request};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The socket to receive heartbeats on
+    ///
+    /// Any datagram sent to the socket is a heartbeat.
+    #[arg(value_name = "socket")]
+    socket: PathBuf,
+
+    /// The line to drive to the safe value if heartbeats stop
+    ///
+    /// The line is identified by name or optionally by offset if
+    /// the --chip option is specified.
+    #[arg(value_name = "line", required_unless_present = "alive")]
+    line: Option<String>,
+
+    /// Send a heartbeat to the socket and exit
+    #[arg(short, long, conflicts_with = "line")]
+    alive: bool,
+
+    /// The maximum period between heartbeats
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(
+        short,
+        long,
+        value_name = "period",
+        default_value = "1s",
+        value_parser = common::parse_duration
+    )]
+    timeout: Duration,
+
+    /// The safe value of the line
+    ///
+    /// The line is driven to the opposite value until heartbeats stop.
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "value",
+        value_enum,
+        default_value = "inactive",
+        ignore_case = true
+    )]
+    safe: ValueFlags,
+
+    /// Exit once the line has been driven to the safe value
+    ///
+    /// By default the line is held at the safe value until the process is killed.
+    #[arg(short = 'x', long)]
+    exit_on_trip: bool,
+
+    #[command(flatten)]
+    line_opts: common::LineOpts,
+
+    #[command(flatten)]
+    active_low_opts: common::ActiveLowOpts,
+
+    #[command(flatten)]
+    bias_opts: common::BiasOpts,
+
+    #[command(flatten)]
+    drive_opts: common::DriveOpts,
+
+    /// The consumer label applied to the requested line.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "name",
+        default_value = "gpiocdev-deadman"
+    )]
+    consumer: String,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+impl Opts {
+    // mutate the config to match the configuration
+    fn apply(&self, config: &mut Config) {
+        self.active_low_opts.apply(config);
+        self.bias_opts.apply(config);
+        self.drive_opts.apply(config);
+        config.as_output(Value::from(self.safe).not());
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ValueFlags {
+    #[value(alias = "0", alias = "off")]
+    Inactive,
+    #[value(alias = "1", alias = "on")]
+    Active,
+}
+
+impl From<ValueFlags> for Value {
+    fn from(v: ValueFlags) -> Self {
+        match v {
+            ValueFlags::Inactive => Value::Inactive,
+            ValueFlags::Active => Value::Active,
+        }
+    }
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Ok(()) => true,
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            false
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<()> {
+    let line = match &opts.line {
+        Some(line) => line,
+        None => {
+            return Heartbeat::connect(&opts.socket)
+                .and_then(|hb| hb.alive())
+                .with_context(|| {
+                    format!("failed to send heartbeat to '{}'", opts.socket.display())
+                });
+        }
+    };
+    let lines = [line.clone()];
+    let mut r = common::Resolver::resolve_lines(&lines, &opts.line_opts, &opts.uapi_opts);
+    if !r.errors.is_empty() {
+        return Err(r.errors.remove(0));
+    }
+    let co = r.lines[line];
+    let ci = &r.chips[co.chip_idx];
+    let mut cfg = Config::default();
+    opts.apply(&mut cfg);
+    cfg.with_line(co.offset);
+    let mut bld = Request::from_config(cfg);
+    bld.on_chip(&ci.path).with_consumer(&opts.consumer);
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(r.abiv);
+    let req = bld.request().with_context(|| {
+        format!(
+            "failed to request line {} from {}{}",
+            co.offset,
+            ci.name,
+            common::conflict_hint(&ci.path, &[co.offset])
+        )
+    })?;
+    let sup = Supervisor::new(
+        &req,
+        co.offset,
+        opts.safe.into(),
+        opts.timeout,
+        &opts.socket,
+    )
+    .with_context(|| format!("failed to create socket '{}'", opts.socket.display()))?;
+    sup.wait_trip();
+    println!("heartbeat lost - '{}' driven to safe value", line);
+    if !opts.exit_on_trip {
+        loop {
+            std::thread::park();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common::{self, format_chip_name, EmitOpts};
+use anyhow::anyhow;
+use clap::Parser;
+use gpiocdev::chip::Chip;
+use gpiocdev::diagnostic::diagnose_line;
+use gpiocdev::line::Offset;
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// Only diagnose the specified lines
+    ///
+    /// The lines are identified by name or optionally by offset
+    /// if the --chip option is provided.
+    ///
+    /// If not specified then all lines are checked, and only
+    /// lines that are unavailable are reported.
+    #[arg(value_name = "line")]
+    lines: Vec<String>,
+
+    #[command(flatten)]
+    line_opts: common::LineOpts,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    let r = common::Resolver::resolve_lines_with_info(
+        &opts.lines,
+        &opts.line_opts,
+        &opts.uapi_opts,
+        opts.line_opts.strict,
+        true,
+    );
+    let mut res = CmdResults::default();
+    let mut chip: Option<(usize, Chip)> = None;
+    for li in &r.info {
+        let ci = &r.chips[li.chip];
+        if chip.as_ref().map(|(idx, _)| *idx) != Some(li.chip) {
+            match Chip::from_path(&ci.path) {
+                Ok(c) => chip = Some((li.chip, c)),
+                Err(e) => {
+                    res.push_error(
+                        &opts.emit,
+                        &anyhow!(e).context(format!("unable to open chip '{}'", ci.name)),
+                    );
+                    continue;
+                }
+            }
+        }
+        let c = &chip.as_ref().unwrap().1;
+        match diagnose_line(c, li.info.offset) {
+            Ok(d) => {
+                if opts.lines.is_empty() && !d.is_conflict() {
+                    continue;
+                }
+                res.lines.push(LineDiagnosis {
+                    chip: ci.name.clone(),
+                    offset: li.info.offset,
+                    name: li.info.name.to_string(),
+                    hint: d.hint(),
+                });
+            }
+            Err(e) => res.push_error(
+                &opts.emit,
+                &anyhow!(e).context(format!(
+                    "unable to diagnose line {} on '{}'",
+                    li.info.offset, ci.name
+                )),
+            ),
+        }
+    }
+    for e in &r.errors {
+        res.push_error(&opts.emit, e);
+    }
+    res.emit(opts);
+    res.errors.is_empty()
+}
+
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct CmdResults {
+    lines: Vec<LineDiagnosis>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    errors: Vec<String>,
+}
+
+impl CmdResults {
+    fn push_error(&mut self, opts: &EmitOpts, e: &anyhow::Error) {
+        self.errors.push(common::format_error(opts, e))
+    }
+
+    fn emit(&self, opts: &Opts) {
+        #[cfg(feature = "json")]
+        if opts.emit.json {
+            println!("{}", serde_json::to_string(self).unwrap());
+            return;
+        }
+        self.print(opts)
+    }
+
+    fn print(&self, opts: &Opts) {
+        for l in &self.lines {
+            let lname = if l.name.is_empty() {
+                "unnamed".to_string()
+            } else if opts.emit.quoted {
+                format!("\"{}\"", l.name)
+            } else {
+                l.name.clone()
+            };
+            println!(
+                "{} {}\t{:16}\t{}",
+                format_chip_name(&l.chip),
+                l.offset,
+                lname,
+                l.hint.as_deref().unwrap_or("available")
+            );
+        }
+        for e in &self.errors {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct LineDiagnosis {
+    chip: String,
+    offset: Offset,
+    name: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    hint: Option<String>,
+}
@@ -2,9 +2,15 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::common::{self, emit_error, format_error, format_time, ChipInfo, EmitOpts, TimeFmt};
+mod decode;
+use self::decode::{DecoderSpec, Decoders};
+
+use super::common::{
+    self, emit_error, format_error, format_time, ChipInfo, EmitOpts, EventClockFlags, Resolver,
+    TimeFmt,
+};
 use anyhow::anyhow;
-use clap::{Parser, ValueEnum};
+use clap::Parser;
 use gpiocdev::line::{EdgeEvent, EdgeKind, Offset};
 use gpiocdev::request::{Config, Request};
 use mio::unix::SourceFd;
@@ -12,7 +18,7 @@ use mio::{Events, Interest, Poll, Token};
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
 use std::os::unix::prelude::AsRawFd;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Parser)]
 #[command(aliases(["e", "mon"]))]
@@ -37,6 +43,9 @@ pub struct Opts {
     #[command(flatten)]
     bias_opts: common::BiasOpts,
 
+    #[command(flatten)]
+    line_config_opts: common::LineConfigOpts,
+
     #[command(flatten)]
     edge_opts: common::EdgeOpts,
 
@@ -60,9 +69,22 @@ pub struct Opts {
     num_events: Option<u32>,
 
     /// Specify the source clock for event timestamps
+    ///
+    /// The realtime clock allows events to be correlated with system logs,
+    /// and is selected by default if --localtime or --utc is specified.
+    ///
+    /// If the clock is not supported by the kernel, or the hte clock is not
+    /// supported by the lines, then a warning is emitted and the monotonic
+    /// clock is used instead, with timestamps displayed as seconds.
     #[cfg(feature = "uapi_v2")]
-    #[arg(short = 'E', long, value_name = "clock")]
-    event_clock: Option<EventClock>,
+    #[arg(
+        short = 'E',
+        long,
+        value_name = "clock",
+        value_enum,
+        ignore_case = true
+    )]
+    event_clock: Option<EventClockFlags>,
 
     /// Specify a custom output format
     ///
@@ -92,6 +114,27 @@ pub struct Opts {
     #[arg(long, group = "timefmt")]
     utc: bool,
 
+    /// Decode a protocol carried by the monitored lines
+    ///
+    /// The decoded frames are displayed instead of the edges.
+    /// May be specified multiple times to decode several protocols.
+    ///
+    /// Decoders:
+    ///   uart=<line>[,<baud>]  8N1 serial, default 9600 baud
+    ///   i2c=<scl>,<sda>       I2C bus transfers
+    ///   wiegand=<d0>,<d1>     Wiegand access control reader
+    ///   nec=<line>            NEC infrared remote control receiver
+    ///
+    /// The lines must also be listed as lines to monitor, and be
+    /// monitored on both edges.
+    #[arg(
+        long,
+        value_name = "protocol=lines",
+        value_parser = decode::parse_decoder,
+        verbatim_doc_comment
+    )]
+    decoder: Vec<DecoderSpec>,
+
     /// Don't generate any output
     #[arg(short = 'q', long, groups = ["emit", "timefmt"], alias = "silent")]
     quiet: bool,
@@ -114,36 +157,52 @@ pub struct Opts {
 
 impl Opts {
     // mutate the config to match the configuration
-    fn apply(&self, config: &mut Config) {
+    fn apply(&self, config: &mut Config, clock: Option<EventClockFlags>) {
         #[cfg(feature = "uapi_v2")]
-        {
-            if let Some(period) = self.debounce_period {
-                config.with_debounce_period(period);
-            }
-            if let Some(clock) = self.event_clock {
-                config.with_event_clock(Some(clock.into()));
-            } else if self.localtime || self.utc {
-                config.with_event_clock(gpiocdev::line::EventClock::Realtime);
-            }
-        }
-        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-        if self.event_clock.is_none() {
-            // uapi v1 can't select clock, so we can't force Realtime for
-            // utc or localtime - we just have to hope the kernel uses Realtime.
-            if let Some(super::common::AbiVersionFlags::V1) = self.uapi_opts.abi_version {
-                config.with_event_clock(None);
-            }
+        if let Some(period) = self.debounce_period {
+            config.with_debounce_period(period);
         }
+        config.with_event_clock(clock.map(|c| c.into()));
         self.active_low_opts.apply(config);
         self.bias_opts.apply(config);
         self.edge_opts.apply(config);
     }
 
+    // The event clock to request, and the format for the event timestamps.
+    //
+    // If the kernel does not support the requested clock then falls back to
+    // the monotonic clock, with timestamps displayed as seconds.
+    #[cfg(feature = "uapi_v2")]
+    fn event_clock(&self, abiv: gpiocdev::AbiVersion) -> (Option<EventClockFlags>, TimeFmt) {
+        let requested = match self.event_clock {
+            Some(clock) => clock,
+            None if self.localtime || self.utc => {
+                if abiv == gpiocdev::AbiVersion::V1 {
+                    // uapi v1 can't select clock, so we can't force Realtime for
+                    // utc or localtime - we just have to hope the kernel uses Realtime.
+                    return (None, self.timefmt());
+                }
+                EventClockFlags::Realtime
+            }
+            None => return (None, self.timefmt()),
+        };
+        let clock = common::select_event_clock(requested, abiv, &self.emit);
+        if clock == Some(requested) {
+            (clock, self.timefmt())
+        } else {
+            (clock, TimeFmt::Seconds)
+        }
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn event_clock(&self, _abiv: gpiocdev::AbiVersion) -> (Option<EventClockFlags>, TimeFmt) {
+        (None, self.timefmt())
+    }
+
     #[cfg(feature = "uapi_v2")]
     fn timefmt(&self) -> TimeFmt {
         if self.localtime {
             TimeFmt::Localtime
-        } else if self.utc || self.event_clock == Some(EventClock::Realtime) {
+        } else if self.utc || self.event_clock == Some(EventClockFlags::Realtime) {
             TimeFmt::Utc
         } else {
             TimeFmt::Seconds
@@ -161,20 +220,23 @@ impl Opts {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
-enum EventClock {
-    Monotonic,
-    Realtime,
-    Hte,
-}
-impl From<EventClock> for gpiocdev::line::EventClock {
-    fn from(c: EventClock) -> Self {
-        match c {
-            EventClock::Monotonic => gpiocdev::line::EventClock::Monotonic,
-            EventClock::Realtime => gpiocdev::line::EventClock::Realtime,
-            EventClock::Hte => gpiocdev::line::EventClock::Hte,
-        }
-    }
+fn request_lines(
+    opts: &Opts,
+    r: &Resolver,
+    idx: usize,
+    offsets: &[Offset],
+    clock: Option<EventClockFlags>,
+) -> gpiocdev::Result<Request> {
+    let mut cfg = Config::default();
+    opts.apply(&mut cfg, clock);
+    cfg.with_lines(offsets);
+    opts.line_config_opts.apply(r, idx, &mut cfg);
+    let mut bld = Request::from_config(cfg);
+    bld.on_chip(&r.chips[idx].path)
+        .with_consumer(&opts.consumer);
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(r.abiv);
+    bld.request()
 }
 
 pub fn cmd(opts: &Opts) -> bool {
@@ -197,6 +259,17 @@ fn do_cmd(opts: &Opts) -> CmdResults {
         }
         return res;
     }
+    if let Err(e) = opts.line_config_opts.check(&r) {
+        res.push_error(&e);
+        return res;
+    }
+    let mut decoders = match Decoders::new(&opts.decoder, &r) {
+        Ok(d) => d,
+        Err(e) => {
+            res.push_error(&e);
+            return res;
+        }
+    };
     let mut poll = match Poll::new() {
         Ok(p) => p,
         Err(e) => {
@@ -205,22 +278,22 @@ fn do_cmd(opts: &Opts) -> CmdResults {
         }
     };
 
+    let (clock, timefmt) = opts.event_clock(r.abiv);
     let mut reqs = Vec::new();
     for (idx, ci) in r.chips.iter().enumerate() {
-        let mut cfg = Config::default();
-        opts.apply(&mut cfg);
         let offsets: Vec<Offset> = r
             .lines
             .values()
             .filter(|co| co.chip_idx == idx)
             .map(|co| co.offset)
             .collect();
-        cfg.with_lines(&offsets);
-        let mut bld = Request::from_config(cfg);
-        bld.on_chip(&ci.path).with_consumer(&opts.consumer);
-        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-        bld.using_abi_version(r.abiv);
-        match bld.request() {
+        let (req, _) = common::request_with_clock_fallback(
+            clock,
+            &format!("lines {:?} from {}", offsets, ci.name),
+            &opts.emit,
+            |clock| request_lines(opts, &r, idx, &offsets, clock),
+        );
+        match req {
             Ok(req) => {
                 if let Err(e) = poll.registry().register(
                     &mut SourceFd(&req.as_raw_fd()),
@@ -237,8 +310,10 @@ fn do_cmd(opts: &Opts) -> CmdResults {
             }
             Err(e) => {
                 res.push_error(&anyhow!(e).context(format!(
-                    "failed to request lines {:?} from {}",
-                    offsets, ci.name
+                    "failed to request lines {:?} from {}{}",
+                    offsets,
+                    ci.name,
+                    common::conflict_hint(&ci.path, &offsets)
                 )));
                 return res;
             }
@@ -246,10 +321,16 @@ fn do_cmd(opts: &Opts) -> CmdResults {
     }
     let mut count = 0;
     let mut events = Events::with_capacity(r.chips.len());
-    let timefmt = opts.timefmt();
+    // decoders need to be periodically notified of idle lines
+    let poll_timeout = if decoders.is_empty() {
+        opts.idle_timeout
+    } else {
+        Some(decode::IDLE_TICK)
+    };
+    let mut last_event = Instant::now();
     emit_banner(opts);
     loop {
-        match poll.poll(&mut events, opts.idle_timeout) {
+        match poll.poll(&mut events, poll_timeout) {
             Err(e) => {
                 if e.kind() != std::io::ErrorKind::Interrupted {
                     res.push_error(&anyhow!(e));
@@ -258,14 +339,30 @@ fn do_cmd(opts: &Opts) -> CmdResults {
             }
             Ok(()) => {
                 if events.is_empty() {
-                    return res;
+                    if decoders.is_empty() {
+                        return res;
+                    }
+                    if !opts.quiet {
+                        decoders.idle(&opts.emit, &timefmt);
+                    }
+                    if let Some(timeout) = opts.idle_timeout {
+                        if last_event.elapsed() >= timeout {
+                            return res;
+                        }
+                    }
+                    continue;
                 }
+                last_event = Instant::now();
                 for event in &events {
                     let idx: usize = event.token().into();
                     while reqs[idx].has_edge_event().unwrap_or(false) {
                         match reqs[idx].read_edge_event() {
                             Ok(edge) => {
-                                emit_edge(edge, &r.chips[idx], opts, &timefmt);
+                                if decoders.is_empty() {
+                                    emit_edge(edge, &r.chips[idx], opts, &timefmt);
+                                } else if !opts.quiet {
+                                    decoders.feed(idx, &edge, &opts.emit, &timefmt);
+                                }
                                 if let Some(limit) = opts.num_events {
                                     count += 1;
                                     if count >= limit {
@@ -379,7 +476,7 @@ struct Event {
 
 impl Event {
     fn print(&self, ci: &ChipInfo, opts: &Opts) {
-        print!("{}\t{:09}\t", self.timestamp, event_kind_name(self.edge.kind));
+        print!("{}\t{}\t", self.timestamp, event_kind_name(self.edge.kind));
         if let Some(lname) = &self.name {
             if opts.line_opts.chip.is_some() {
                 print!("{} {} ", ci.name, self.edge.offset);
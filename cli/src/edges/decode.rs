@@ -0,0 +1,238 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::common::{format_time, EmitOpts, Resolver, TimeFmt};
+use anyhow::{bail, Result};
+use gpiocdev::decode::{Decoder, I2c, Nec, Uart, Wiegand};
+use gpiocdev::line::{EdgeEvent, Offset};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::time::{Duration, Instant};
+
+// How often the decoders are checked for frames completed by idle lines.
+pub const IDLE_TICK: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Protocol {
+    I2c,
+    Nec,
+    Uart,
+    Wiegand,
+}
+
+impl Protocol {
+    fn name(&self) -> &'static str {
+        match self {
+            Protocol::I2c => "i2c",
+            Protocol::Nec => "nec",
+            Protocol::Uart => "uart",
+            Protocol::Wiegand => "wiegand",
+        }
+    }
+}
+
+/// A decoder as specified on the command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecoderSpec {
+    protocol: Protocol,
+    lines: Vec<String>,
+    baud: u32,
+}
+
+pub fn parse_decoder(s: &str) -> std::result::Result<DecoderSpec, String> {
+    let (protocol, params) = s
+        .split_once('=')
+        .ok_or_else(|| "expected protocol=lines".to_string())?;
+    let params: Vec<&str> = params.split(',').collect();
+    let (protocol, num_lines) = match protocol {
+        "i2c" => (Protocol::I2c, 2),
+        "nec" => (Protocol::Nec, 1),
+        "uart" => (Protocol::Uart, 1),
+        "wiegand" => (Protocol::Wiegand, 2),
+        _ => return Err(format!("unknown protocol '{}'", protocol)),
+    };
+    let mut baud = 9600;
+    if protocol == Protocol::Uart && params.len() == 2 {
+        baud = params[1]
+            .parse::<u32>()
+            .map_err(|e| format!("invalid baud rate '{}': {}", params[1], e))?;
+    } else if params.len() != num_lines {
+        return Err(format!(
+            "{} requires {} line{}",
+            protocol.name(),
+            num_lines,
+            if num_lines > 1 { "s" } else { "" }
+        ));
+    }
+    Ok(DecoderSpec {
+        protocol,
+        lines: params[..num_lines].iter().map(|l| l.to_string()).collect(),
+        baud,
+    })
+}
+
+// A decoder with its frames rendered as strings.
+trait FrameDecoder {
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<String>;
+    fn idle(&mut self, timestamp_ns: u64) -> Option<String>;
+}
+
+impl<D> FrameDecoder for D
+where
+    D: Decoder,
+    D::Frame: std::fmt::Display,
+{
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<String> {
+        Decoder::feed(self, evt).map(|f| f.to_string())
+    }
+
+    fn idle(&mut self, timestamp_ns: u64) -> Option<String> {
+        Decoder::idle(self, timestamp_ns).map(|f| f.to_string())
+    }
+}
+
+// A decoder bound to the chip containing its lines.
+struct BoundDecoder {
+    protocol: Protocol,
+    chip_idx: usize,
+    decoder: Box<dyn FrameDecoder>,
+}
+
+/// The decoders applied to the monitored lines.
+pub struct Decoders {
+    decoders: Vec<BoundDecoder>,
+    // the timestamp of the most recent event, and when it was received,
+    // used to extrapolate timestamps on the event clock while idle.
+    last: Option<(u64, Instant)>,
+}
+
+impl Decoders {
+    pub fn new(specs: &[DecoderSpec], r: &Resolver) -> Result<Decoders> {
+        let mut decoders = Vec::new();
+        for spec in specs {
+            let mut chip_idx = None;
+            let mut offsets: Vec<Offset> = Vec::new();
+            for line in &spec.lines {
+                let co = match r.lines.get(line) {
+                    Some(co) => co,
+                    None => bail!(
+                        "{} decoder line '{}' is not monitored",
+                        spec.protocol.name(),
+                        line
+                    ),
+                };
+                if chip_idx.is_some() && chip_idx != Some(co.chip_idx) {
+                    bail!(
+                        "{} decoder lines must be on the same chip",
+                        spec.protocol.name()
+                    );
+                }
+                chip_idx = Some(co.chip_idx);
+                offsets.push(co.offset);
+            }
+            let decoder: Box<dyn FrameDecoder> = match spec.protocol {
+                Protocol::I2c => Box::new(I2c::new(offsets[0], offsets[1])),
+                Protocol::Nec => Box::new(Nec::new(offsets[0])),
+                Protocol::Uart => Box::new(Uart::new(offsets[0], spec.baud)),
+                Protocol::Wiegand => Box::new(Wiegand::new(offsets[0], offsets[1])),
+            };
+            decoders.push(BoundDecoder {
+                protocol: spec.protocol,
+                chip_idx: chip_idx.unwrap(),
+                decoder,
+            });
+        }
+        Ok(Decoders {
+            decoders,
+            last: None,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+
+    pub fn feed(&mut self, chip_idx: usize, evt: &EdgeEvent, opts: &EmitOpts, timefmt: &TimeFmt) {
+        self.last = Some((evt.timestamp_ns, Instant::now()));
+        for bd in self
+            .decoders
+            .iter_mut()
+            .filter(|bd| bd.chip_idx == chip_idx)
+        {
+            if let Some(frame) = bd.decoder.feed(evt) {
+                emit_frame(bd.protocol, evt.timestamp_ns, frame, opts, timefmt);
+            }
+        }
+    }
+
+    pub fn idle(&mut self, opts: &EmitOpts, timefmt: &TimeFmt) {
+        let timestamp_ns = match self.last {
+            Some((ts, at)) => ts + at.elapsed().as_nanos() as u64,
+            None => return,
+        };
+        for bd in self.decoders.iter_mut() {
+            if let Some(frame) = bd.decoder.idle(timestamp_ns) {
+                emit_frame(bd.protocol, timestamp_ns, frame, opts, timefmt);
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct Frame {
+    timestamp: String,
+    decoder: &'static str,
+    frame: String,
+}
+
+#[allow(unused_variables)]
+fn emit_frame(
+    protocol: Protocol,
+    timestamp_ns: u64,
+    frame: String,
+    opts: &EmitOpts,
+    timefmt: &TimeFmt,
+) {
+    let frame = Frame {
+        timestamp: format_time(timestamp_ns, timefmt),
+        decoder: protocol.name(),
+        frame,
+    };
+    #[cfg(feature = "json")]
+    if opts.json {
+        println!("{}", serde_json::to_string(&frame).unwrap());
+        return;
+    }
+    println!("{}\t{}\t{}", frame.timestamp, frame.decoder, frame.frame);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_decoder("uart=RXD"),
+            Ok(DecoderSpec {
+                protocol: Protocol::Uart,
+                lines: vec!["RXD".to_string()],
+                baud: 9600
+            })
+        );
+        assert_eq!(parse_decoder("uart=RXD,1200").unwrap().baud, 1200);
+        assert_eq!(
+            parse_decoder("i2c=SCL,SDA").unwrap().lines,
+            vec!["SCL".to_string(), "SDA".to_string()]
+        );
+        assert_eq!(parse_decoder("nec=IR").unwrap().protocol, Protocol::Nec);
+        assert_eq!(
+            parse_decoder("wiegand=D0").unwrap_err(),
+            "wiegand requires 2 lines"
+        );
+        assert!(parse_decoder("uart=RXD,fast").is_err());
+        assert!(parse_decoder("spi=CLK,MOSI").is_err());
+        assert!(parse_decoder("uart").is_err());
+    }
+}
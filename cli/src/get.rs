@@ -40,6 +40,9 @@ pub struct Opts {
     #[command(flatten)]
     bias_opts: common::BiasOpts,
 
+    #[command(flatten)]
+    line_config_opts: common::LineConfigOpts,
+
     /// Wait between requesting the lines and reading the values
     ///
     /// This provides time for any bias setting to take effect.
@@ -88,6 +91,10 @@ fn do_cmd(opts: &Opts) -> CmdResult {
     for e in &r.errors {
         res.push_error(&opts.emit, e);
     }
+    if let Err(e) = opts.line_config_opts.check(&r) {
+        res.push_error(&opts.emit, &e);
+        return res;
+    }
     let mut requests = Vec::new();
     for (idx, ci) in r.chips.iter().enumerate() {
         let mut cfg = Config::default();
@@ -99,6 +106,7 @@ fn do_cmd(opts: &Opts) -> CmdResult {
             .map(|co| co.offset)
             .collect();
         cfg.with_lines(&offsets);
+        opts.line_config_opts.apply(&r, idx, &mut cfg);
 
         let mut bld = Request::from_config(cfg);
         bld.on_chip(&ci.path).with_consumer(&opts.consumer);
@@ -113,8 +121,10 @@ fn do_cmd(opts: &Opts) -> CmdResult {
                 res.push_error(
                     &opts.emit,
                     &anyhow!(e).context(format!(
-                        "failed to request lines {:?} from {}",
-                        offsets, ci.name
+                        "failed to request lines {:?} from {}{}",
+                        offsets,
+                        ci.name,
+                        common::conflict_hint(&ci.path, &offsets)
                     )),
                 );
             }
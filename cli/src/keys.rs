@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common;
+use anyhow::{Context, Result};
+use clap::Parser;
+use gpiocdev::bridge::uinput::{KeyMap, Uinput};
+use gpiocdev::poll::wait_any;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// The time to wait for events if no long press is pending.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The key map mapping lines to key and switch codes
+    ///
+    /// The key map is reloaded on SIGHUP.
+    /// If the reloaded key map is invalid then the previous key map is retained.
+    #[arg(value_name = "config")]
+    config: PathBuf,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Ok(()) => true,
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            false
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<()> {
+    let hup = Hangup::new().context("failed to catch SIGHUP")?;
+    let mut km = load(opts)?;
+    let mut bridge = build(opts, &km)?;
+    loop {
+        let timeout = bridge.next_timeout().unwrap_or(IDLE_TIMEOUT);
+        let ready = wait_any(&[bridge.request().as_fd(), hup.0.as_fd()], timeout)?;
+        if ready.iter().any(|r| r.index == 1) {
+            hup.clear().context("failed to read SIGHUP")?;
+            (km, bridge) = reload(opts, km, bridge)?;
+        }
+        bridge.process_events().context("failed to report events")?;
+    }
+}
+
+fn load(opts: &Opts) -> Result<KeyMap> {
+    KeyMap::load(&opts.config)
+        .with_context(|| format!("failed to load key map '{}'", opts.config.display()))
+}
+
+fn build(opts: &Opts, km: &KeyMap) -> Result<Uinput> {
+    km.build()
+        .with_context(|| format!("failed to apply key map '{}'", opts.config.display()))
+}
+
+// Replace the bridge with one built from the reloaded key map.
+//
+// Errors in the reloaded key map are reported, and the previous key map restored.
+fn reload(opts: &Opts, km: KeyMap, bridge: Uinput) -> Result<(KeyMap, Uinput)> {
+    let new_km = match load(opts) {
+        Ok(new_km) => new_km,
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            return Ok((km, bridge));
+        }
+    };
+    // the lines must be released before they can be requested by the new bridge
+    drop(bridge);
+    match build(opts, &new_km) {
+        Ok(bridge) => Ok((new_km, bridge)),
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            let bridge = km.build().context("failed to restore previous key map")?;
+            Ok((km, bridge))
+        }
+    }
+}
+
+// A signalfd for SIGHUP, which is blocked from normal delivery.
+struct Hangup(OwnedFd);
+
+impl Hangup {
+    fn new() -> io::Result<Hangup> {
+        // SAFETY: the sigset is initialised by sigemptyset before use, and the
+        // fd returned by signalfd is owned by the Hangup.
+        unsafe {
+            let mut set: libc::sigset_t = std::mem::zeroed();
+            libc::sigemptyset(&mut set);
+            libc::sigaddset(&mut set, libc::SIGHUP);
+            let res = libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+            if res != 0 {
+                return Err(io::Error::from_raw_os_error(res));
+            }
+            let fd = libc::signalfd(-1, &set, libc::SFD_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Hangup(OwnedFd::from_raw_fd(fd)))
+        }
+    }
+
+    // Consume the pending signal.
+    fn clear(&self) -> io::Result<()> {
+        // SAFETY: info is plain old data, and the read is limited to its size.
+        unsafe {
+            let mut info: libc::signalfd_siginfo = std::mem::zeroed();
+            let size = std::mem::size_of::<libc::signalfd_siginfo>();
+            let n = libc::read(
+                self.0.as_raw_fd(),
+                &mut info as *mut libc::signalfd_siginfo as *mut libc::c_void,
+                size,
+            );
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
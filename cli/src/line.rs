@@ -6,6 +6,7 @@ use super::common::{
     self, emit_error, format_chip_name, stringify_attrs, EmitOpts, LineOpts, Resolver,
 };
 use clap::Parser;
+use gpiocdev::diagnostic::hog_node;
 use gpiocdev::line::Info;
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
@@ -66,6 +67,7 @@ pub fn cmd(opts: &Opts) -> bool {
         chip: opts.chip.clone(),
         strict: false, // to continue on multi-match
         by_name: opts.by_name,
+        wait_for_chip: None,
     };
     let res = Cmd {
         opts,
@@ -115,6 +117,7 @@ impl<'a> Cmd<'a> {
             res.lines.push(LineInfo {
                 chip: &self.r.chips[i.chip].name,
                 info: &i.info,
+                hog: hog_node(&i.info).map(|node| node.display().to_string()),
             });
         }
         for e in &self.r.errors {
@@ -158,6 +161,8 @@ struct LineInfo<'a> {
     chip: &'a str,
     #[cfg_attr(feature = "serde", serde(flatten))]
     info: &'a Info,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    hog: Option<String>,
 }
 
 fn print_chip_lines(r: &Resolver, idx: usize, opts: &EmitOpts) {
@@ -179,7 +184,7 @@ fn print_chip_lines(r: &Resolver, idx: usize, opts: &EmitOpts) {
             "\tline {:>3}:\t{:16}\t{}",
             li.offset,
             lname,
-            stringify_attrs(li, opts.quoted),
+            stringify_line_attrs(li, opts.quoted),
         );
     }
 }
@@ -197,6 +202,16 @@ fn print_line_info(chip_name: &str, li: &Info, quoted: bool) {
         format_chip_name(chip_name),
         li.offset,
         lname,
-        stringify_attrs(li, quoted),
+        stringify_line_attrs(li, quoted),
     );
 }
+
+// The line attributes, including any kernel hog holding the line.
+fn stringify_line_attrs(li: &Info, quoted: bool) -> String {
+    let attrs = stringify_attrs(li, quoted);
+    match hog_node(li) {
+        Some(node) if quoted => format!("{} hog=\"{}\"", attrs, node.display()),
+        Some(node) => format!("{} hog={}", attrs, node.display()),
+        None => attrs,
+    }
+}
@@ -9,24 +9,42 @@ use std::process::ExitCode;
 
 mod chip;
 mod common;
+mod deadman;
+mod doctor;
 mod edges;
 mod get;
+#[cfg(feature = "uinput")]
+mod keys;
 mod line;
 mod notify;
 mod platform;
+mod pwm;
+mod scan;
 mod set;
+mod trap;
+#[cfg(feature = "verify")]
+mod verify;
 
 fn main() -> ExitCode {
     match Opts::try_parse() {
         Ok(opt) => {
             let res = match opt.cmd {
                 Command::Chip(cfg) => chip::cmd(&cfg),
+                Command::Deadman(cfg) => deadman::cmd(&cfg),
+                Command::Doctor(cfg) => doctor::cmd(&cfg),
                 Command::Edges(cfg) => edges::cmd(&cfg),
                 Command::Get(cfg) => get::cmd(&cfg),
+                #[cfg(feature = "uinput")]
+                Command::Keys(cfg) => keys::cmd(&cfg),
                 Command::Line(cfg) => line::cmd(&cfg),
+                Command::Scan(cfg) => scan::cmd(&cfg),
                 Command::Set(cfg) => set::cmd(&cfg),
                 Command::Notify(cfg) => notify::cmd(&cfg),
                 Command::Platform(cfg) => platform::cmd(&cfg),
+                Command::Pwm(cfg) => pwm::cmd(&cfg),
+                Command::Trap(cfg) => trap::cmd(&cfg),
+                #[cfg(feature = "verify")]
+                Command::Verify(cfg) => verify::cmd(&cfg),
             };
             return if res {
                 ExitCode::SUCCESS
@@ -60,12 +78,22 @@ enum Command {
     /// Get information about GPIO chips.
     Chip(chip::Opts),
 
+    /// Drive a line to a safe value if heartbeats stop.
+    Deadman(deadman::Opts),
+
+    /// Diagnose why GPIO lines are unavailable.
+    Doctor(doctor::Opts),
+
     /// Monitor GPIO lines for edge events.
     Edges(edges::Opts),
 
     /// Read the levels of GPIO lines.
     Get(get::Opts),
 
+    /// Report GPIO lines as key and switch events from an input device.
+    #[cfg(feature = "uinput")]
+    Keys(keys::Opts),
+
     /// Get information about GPIO lines (everything but levels).
     Line(line::Opts),
 
@@ -75,6 +103,19 @@ enum Command {
     /// Get information about the platform GPIO uAPI support.
     Platform(platform::Opts),
 
+    /// Drive a line with a PWM signal, optionally sweeping its frequency or duty cycle.
+    Pwm(pwm::Opts),
+
+    /// Monitor the unused lines on a chip for activity.
+    Scan(scan::Opts),
+
     /// Set the levels of GPIO lines.
     Set(set::Opts),
+
+    /// Dump recent edges and line state on the first matching edge.
+    Trap(trap::Opts),
+
+    /// Verify that lines match a manifest.
+    #[cfg(feature = "verify")]
+    Verify(verify::Opts),
 }
@@ -2,13 +2,12 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::common::EmitOpts;
+use super::common::{kernel_release, parse_kernel_version, EmitOpts};
 use clap::Parser;
 use core::fmt;
 use gpiocdev::{AbiSupportKind, AbiVersion};
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
-use std::fs;
 #[derive(Debug, Parser)]
 #[command(aliases(["p"]))]
 pub struct Opts {
@@ -21,14 +20,7 @@ pub fn cmd(opts: &Opts) -> bool {
         opts: opts.emit,
         ..Default::default()
     };
-    if let Ok(v) = fs::read_to_string("/proc/version") {
-        let mut f = v.split_ascii_whitespace();
-        if let Some(v) = f.nth(2) {
-            p.kernel.version = v.into();
-        }
-    } else {
-        p.kernel.version = "unknown".into();
-    }
+    p.kernel.version = kernel_release().unwrap_or_else(|| "unknown".into());
     let versions = [AbiVersion::V1, AbiVersion::V2];
     for v in versions {
         match Abi::new(v, &p.kernel.version) {
@@ -89,13 +81,6 @@ impl Platform {
     }
 }
 
-fn parse_version(version: &str) -> Option<(u32, u32)> {
-    let mut f = version.split('.');
-    let major = f.next()?.parse().ok()?;
-    let minor = f.next()?.parse().ok()?;
-    Some((major, minor))
-}
-
 #[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
 enum Features {
     Bias,
@@ -135,7 +120,7 @@ impl Abi {
             version: v,
             ..Default::default()
         };
-        if let Some((major, minor)) = parse_version(kernel) {
+        if let Some((major, minor)) = parse_kernel_version(kernel) {
             match v {
                 AbiVersion::V1 => {
                     if major < 5 || (major == 5 && minor < 5) {
@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common;
+use anyhow::{Context, Result};
+use clap::Parser;
+use gpiocdev::line::Value;
+use gpiocdev::request::{Config, Request};
+use gpiocdev::waveform::{Sweep, SweepScale, Waveform};
+use std::time::Duration;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The line to drive
+    ///
+    /// The line is identified by name or optionally by offset if
+    /// the --chip option is specified.
+    #[arg(value_name = "line")]
+    line: String,
+
+    /// The frequency of the signal, in Hz
+    #[arg(short, long, value_name = "hz", default_value = "1000")]
+    frequency: f64,
+
+    /// The duty cycle of the signal, as a percentage
+    #[arg(long, value_name = "percent", default_value = "50")]
+    duty: f64,
+
+    /// The period to drive the signal
+    ///
+    /// By default the signal is driven until the process is killed.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(
+        long,
+        value_name = "period",
+        value_parser = common::parse_duration
+    )]
+    duration: Option<Duration>,
+
+    /// Ramp the frequency or duty cycle between bounds over the duration
+    ///
+    /// The sweep is specified as the parameter and its bounds,
+    /// e.g. frequency=100:5000 or duty=0:100.
+    ///
+    /// The other parameter is held at the value set by its own option.
+    #[arg(
+        long,
+        value_name = "param=from:to",
+        requires = "duration",
+        value_parser = parse_sweep,
+        verbatim_doc_comment
+    )]
+    sweep: Option<SweepRange>,
+
+    /// Ramp the swept parameter by equal ratios rather than equal amounts
+    #[arg(long, requires = "sweep")]
+    log: bool,
+
+    #[command(flatten)]
+    line_opts: common::LineOpts,
+
+    #[command(flatten)]
+    active_low_opts: common::ActiveLowOpts,
+
+    #[command(flatten)]
+    bias_opts: common::BiasOpts,
+
+    #[command(flatten)]
+    drive_opts: common::DriveOpts,
+
+    /// The consumer label applied to the requested line.
+    #[arg(short = 'C', long, value_name = "name", default_value = "gpiocdev-pwm")]
+    consumer: String,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+impl Opts {
+    // mutate the config to match the configuration
+    fn apply(&self, config: &mut Config) {
+        self.active_low_opts.apply(config);
+        self.bias_opts.apply(config);
+        self.drive_opts.apply(config);
+        config.as_output(Value::Inactive);
+    }
+
+    // the sweep for the given period
+    fn sweep(&self, duration: Duration) -> Sweep {
+        let duty = self.duty / 100.0;
+        let sweep = match self.sweep {
+            Some(SweepRange::Frequency(from, to)) => Sweep::frequency(from, to, duty, duration),
+            Some(SweepRange::Duty(from, to)) => {
+                Sweep::duty(self.frequency, from / 100.0, to / 100.0, duration)
+            }
+            None => Sweep::frequency(self.frequency, self.frequency, duty, duration),
+        };
+        if self.log {
+            sweep.with_scale(SweepScale::Log)
+        } else {
+            sweep
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SweepRange {
+    Frequency(f64, f64),
+    Duty(f64, f64),
+}
+
+fn parse_sweep(s: &str) -> std::result::Result<SweepRange, String> {
+    let (param, range) = s
+        .split_once('=')
+        .ok_or_else(|| "expected param=from:to".to_string())?;
+    let (from, to) = range
+        .split_once(':')
+        .ok_or_else(|| "expected from:to".to_string())?;
+    let from = from.parse::<f64>().map_err(|e| e.to_string())?;
+    let to = to.parse::<f64>().map_err(|e| e.to_string())?;
+    match param {
+        "frequency" | "f" => Ok(SweepRange::Frequency(from, to)),
+        "duty" => Ok(SweepRange::Duty(from, to)),
+        _ => Err(format!("unknown parameter '{}'", param)),
+    }
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Ok(()) => true,
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            false
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<()> {
+    // check the parameters before requesting the line
+    opts.sweep(Duration::ZERO).waveform()?;
+    let lines = [opts.line.clone()];
+    let mut r = common::Resolver::resolve_lines(&lines, &opts.line_opts, &opts.uapi_opts);
+    if !r.errors.is_empty() {
+        return Err(r.errors.remove(0));
+    }
+    let co = r.lines[&opts.line];
+    let ci = &r.chips[co.chip_idx];
+    let mut cfg = Config::default();
+    opts.apply(&mut cfg);
+    cfg.with_line(co.offset);
+    let mut bld = Request::from_config(cfg);
+    bld.on_chip(&ci.path).with_consumer(&opts.consumer);
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(r.abiv);
+    let req = bld.request().with_context(|| {
+        format!(
+            "failed to request line {} from {}{}",
+            co.offset,
+            ci.name,
+            common::conflict_hint(&ci.path, &[co.offset])
+        )
+    })?;
+    let play = |wf: &Waveform| {
+        req.play_waveform(co.offset, wf)
+            .with_context(|| format!("failed to drive line {}", opts.line))
+    };
+    match opts.duration {
+        Some(duration) => play(&opts.sweep(duration).waveform()?),
+        None => {
+            // a constant signal, generated a second at a time
+            let wf = opts.sweep(Duration::from_secs(1)).waveform()?;
+            loop {
+                play(&wf)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep() {
+        assert_eq!(
+            parse_sweep("frequency=100:5000"),
+            Ok(SweepRange::Frequency(100.0, 5000.0))
+        );
+        assert_eq!(parse_sweep("duty=0:100"), Ok(SweepRange::Duty(0.0, 100.0)));
+        assert!(parse_sweep("duty").is_err());
+        assert!(parse_sweep("duty=50").is_err());
+        assert!(parse_sweep("phase=0:1").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common::{self, format_chip_name, EmitOpts, LineOpts};
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use gpiocdev::line::{EdgeDetection, EdgeKind, Offset, Value};
+use gpiocdev::request::{Config, Request};
+use mio::unix::SourceFd;
+use mio::{Events, Interest, Poll, Token};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::os::unix::prelude::AsRawFd;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The chip to scan
+    ///
+    /// All unused lines on the chip are requested as inputs and monitored
+    /// for activity.  Any bias is left as is.
+    ///
+    /// Note that requesting a line as an input may change its direction.
+    ///
+    /// The chip may be identified by number, name, or path.
+    /// e.g. the following all select the same chip:
+    ///     --chip 0
+    ///     --chip gpiochip0
+    ///     --chip /dev/gpiochip0
+    #[arg(short, long, value_name = "chip", verbatim_doc_comment)]
+    chip: String,
+
+    /// The period to monitor the lines
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(
+        short = 'd',
+        long,
+        value_name = "period",
+        default_value = "5s",
+        value_parser = common::parse_duration
+    )]
+    duration: Duration,
+
+    /// The consumer label applied to requested lines.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "name",
+        default_value = "gpiocdev-scan"
+    )]
+    consumer: String,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Ok(res) => {
+            res.emit(opts);
+            res.errors.is_empty()
+        }
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            false
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<CmdResults> {
+    let line_opts = LineOpts {
+        chip: Some(opts.chip.clone()),
+        strict: false,
+        by_name: false,
+        wait_for_chip: None,
+    };
+    let mut r =
+        common::Resolver::resolve_lines_with_info(&[], &line_opts, &opts.uapi_opts, true, true);
+    if !r.errors.is_empty() {
+        return Err(r.errors.remove(0));
+    }
+    let ci = &r.chips[0];
+    let mut res = CmdResults {
+        chip: ci.name.clone(),
+        ..Default::default()
+    };
+    let mut poll = Poll::new().context("failed to create poll")?;
+
+    // Lines are requested individually as uAPI v1 only supports edge detection
+    // on single line requests.
+    let mut reqs = Vec::new();
+    for li in r.info.iter().filter(|li| !li.info.used) {
+        let offset = li.info.offset;
+        let mut cfg = Config::default();
+        cfg.with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges);
+        let mut bld = Request::from_config(cfg);
+        bld.on_chip(&ci.path).with_consumer(&opts.consumer);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        bld.using_abi_version(r.abiv);
+        let req = match bld.request() {
+            Ok(req) => req,
+            Err(e) => {
+                // lost a race with another consumer
+                res.push_error(
+                    &opts.emit,
+                    &anyhow!(e).context(format!(
+                        "failed to request line {} from {}",
+                        offset, ci.name
+                    )),
+                );
+                continue;
+            }
+        };
+        let initial = req
+            .value(offset)
+            .with_context(|| format!("failed to read line {} from {}", offset, ci.name))?;
+        poll.registry()
+            .register(
+                &mut SourceFd(&req.as_raw_fd()),
+                Token(reqs.len()),
+                Interest::READABLE,
+            )
+            .with_context(|| {
+                format!(
+                    "failed to register line {} from {} with poll",
+                    offset, ci.name
+                )
+            })?;
+        reqs.push(req);
+        res.lines
+            .push(LineActivity::new(offset, &li.info.name, initial));
+    }
+
+    let start = Instant::now();
+    let deadline = start + opts.duration;
+    let mut events = Events::with_capacity(reqs.len().max(1));
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        if let Err(e) = poll.poll(&mut events, Some(deadline - now)) {
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(anyhow!(e).context("failed to poll"));
+            }
+            continue;
+        }
+        for event in &events {
+            let idx: usize = event.token().into();
+            while reqs[idx].has_edge_event().unwrap_or(false) {
+                let edge = reqs[idx].read_edge_event().with_context(|| {
+                    format!(
+                        "failed to read event for line {} from {}",
+                        res.lines[idx].offset, ci.name
+                    )
+                })?;
+                res.lines[idx].edges += 1;
+                res.lines[idx].value = match edge.kind {
+                    EdgeKind::Rising => Value::Active,
+                    EdgeKind::Falling => Value::Inactive,
+                };
+            }
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    for la in res.lines.iter_mut() {
+        la.rate = la.edges as f64 / elapsed;
+    }
+    Ok(res)
+}
+
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct CmdResults {
+    chip: String,
+    lines: Vec<LineActivity>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    errors: Vec<String>,
+}
+
+impl CmdResults {
+    fn push_error(&mut self, opts: &EmitOpts, e: &anyhow::Error) {
+        self.errors.push(common::format_error(opts, e))
+    }
+
+    fn emit(&self, opts: &Opts) {
+        #[cfg(feature = "json")]
+        if opts.emit.json {
+            println!("{}", serde_json::to_string(self).unwrap());
+            return;
+        }
+        self.print(opts)
+    }
+
+    fn print(&self, opts: &Opts) {
+        for la in &self.lines {
+            let lname = if la.name.is_empty() {
+                "unnamed".to_string()
+            } else if opts.emit.quoted {
+                format!("\"{}\"", la.name)
+            } else {
+                la.name.clone()
+            };
+            let activity = if la.edges == 0 {
+                format!("constant {}", value_name(la.initial))
+            } else {
+                format!("toggled {} edges {:.1}Hz", la.edges, la.rate)
+            };
+            println!(
+                "{} {}\t{:16}\t{}",
+                format_chip_name(&self.chip),
+                la.offset,
+                lname,
+                activity
+            );
+        }
+        for e in &self.errors {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct LineActivity {
+    offset: Offset,
+    name: String,
+    initial: Value,
+    value: Value,
+    edges: u64,
+    rate: f64,
+}
+
+impl LineActivity {
+    fn new(offset: Offset, name: &str, initial: Value) -> LineActivity {
+        LineActivity {
+            offset,
+            name: name.to_string(),
+            initial,
+            value: initial,
+            edges: 0,
+            rate: 0.0,
+        }
+    }
+}
+
+fn value_name(v: Value) -> &'static str {
+    match v {
+        Value::Active => "active",
+        Value::Inactive => "inactive",
+    }
+}
@@ -5,7 +5,7 @@
 mod editor;
 use self::editor::{CommandWords, Editor};
 
-use super::common::{self, emit_error, EmitOpts, ParseDurationError};
+use super::common::{self, emit_error, EmitOpts};
 use anyhow::{anyhow, bail, Context, Result};
 use clap::{Arg, ArgAction, Command, Parser};
 use daemonize::Daemonize;
@@ -206,9 +206,11 @@ impl Setter {
         for (idx, ci) in self.chips.iter().enumerate() {
             let mut cfg = Config::default();
             opts.apply(&mut cfg);
+            let mut offsets = Vec::new();
             for line in self.lines.values() {
                 if line.chip_idx == idx {
                     cfg.with_line(line.offset).as_output(line.value);
+                    offsets.push(line.offset);
                 }
             }
             let mut bld = Request::from_config(cfg);
@@ -217,7 +219,13 @@ impl Setter {
             bld.using_abi_version(r.abiv);
             let req = bld
                 .request()
-                .with_context(|| format!("failed to request and set lines on {}", ci.name))?;
+                .with_context(|| {
+                    format!(
+                        "failed to request and set lines on {}{}",
+                        ci.name,
+                        common::conflict_hint(&ci.path, &offsets)
+                    )
+                })?;
             self.requests.push(req);
         }
         Ok(true)
@@ -601,7 +609,7 @@ fn parse_line_value(s: &str) -> std::result::Result<(String, LineValue), anyhow:
 #[derive(Clone, Debug)]
 struct TimeSequence(Vec<Duration>);
 
-fn parse_time_sequence(s: &str) -> std::result::Result<TimeSequence, ParseDurationError> {
+fn parse_time_sequence(s: &str) -> gpiocdev::Result<TimeSequence> {
     let mut ts = TimeSequence(Vec::new());
     for period in s.split(',') {
         ts.0.push(common::parse_duration(period)?);
@@ -732,7 +740,6 @@ mod tests {
         #[test]
         fn time_sequence() {
             use super::parse_time_sequence;
-            use crate::common::ParseDurationError;
             use std::time::Duration;
             assert!(parse_time_sequence("0")
                 .unwrap()
@@ -771,13 +778,18 @@ mod tests {
                     Duration::ZERO
                 ]
                 .iter()));
+            assert!(parse_time_sequence("5ns,1m30s")
+                .unwrap()
+                .0
+                .iter()
+                .eq([Duration::from_nanos(5), Duration::from_secs(90)].iter()));
             assert_eq!(
-                parse_time_sequence("5ns").unwrap_err(),
-                ParseDurationError::Units("5ns".to_string())
+                parse_time_sequence("5d").unwrap_err(),
+                gpiocdev::Error::InvalidArgument("invalid period '5d'.".to_string())
             );
             assert_eq!(
                 parse_time_sequence("bad").unwrap_err(),
-                ParseDurationError::NoDigits("bad".to_string())
+                gpiocdev::Error::InvalidArgument("invalid period 'bad'.".to_string())
             );
         }
     }
@@ -100,7 +100,7 @@ impl InteractiveHelper {
     }
 
     fn complete_sleep(&self, mut pos: usize, mut words: CommandWords) -> (usize, Vec<Pair>) {
-        const UNITS: [&str; 4] = ["s", "ms", "us", "ns"];
+        const UNITS: [&str; 6] = ["h", "m", "s", "ms", "us", "ns"];
         let mut candidates = Vec::new();
         let mut times = Vec::new();
         while let Some(word) = &words.next() {
@@ -108,19 +108,12 @@ impl InteractiveHelper {
         }
         if words.partial && times.len() == 1 {
             let t = &times[0];
-            match t.find(|c: char| !c.is_ascii_digit()) {
-                Some(n) => {
-                    let (_num, units) = t.split_at(n);
-                    pos -= units.len();
-                    for display in UNITS.iter().filter(|u| u.starts_with(units)) {
-                        candidates.push(base_pair(display))
-                    }
-                }
-                None => {
-                    for display in UNITS.iter() {
-                        candidates.push(base_pair(display))
-                    }
-                }
+            // complete the units of the last component of compound durations
+            let n = t.rfind(|c: char| c.is_ascii_digit()).map_or(0, |n| n + 1);
+            let units = &t[n..];
+            pos -= units.len();
+            for display in UNITS.iter().filter(|u| u.starts_with(units)) {
+                candidates.push(base_pair(display))
             }
         }
         (pos, candidates)
@@ -247,7 +240,7 @@ pub(super) struct CommandWords<'a> {
 }
 
 impl CommandWords<'_> {
-    pub fn new(line: &str) -> CommandWords {
+    pub fn new(line: &str) -> CommandWords<'_> {
         CommandWords {
             line,
             liter: line.char_indices(),
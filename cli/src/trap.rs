@@ -0,0 +1,349 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common::{
+    self, format_chip_name, format_time, stringify_attrs, ChipInfo, EdgeFlags, EmitOpts,
+    EventClockFlags, LineOpts, Resolver, TimeFmt,
+};
+use anyhow::{Context, Result};
+use clap::Parser;
+use gpiocdev::line::{EdgeDetection, EdgeEvent, EdgeKind, Info};
+use gpiocdev::request::{Config, Request};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+#[cfg(feature = "uapi_v2")]
+use std::time::Duration;
+
+// The most events preallocated for the history, so large depths only consume
+// memory as events arrive.
+const MAX_PREALLOCATED_HISTORY: usize = 1024;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The line to trap
+    ///
+    /// The line is identified by name or optionally by offset if
+    /// the --chip option is specified.
+    #[arg(value_name = "line")]
+    line: String,
+
+    /// The edge that triggers the trap
+    ///
+    /// Both edges are monitored, so the recent event history includes
+    /// edges that do not trigger the trap.
+    #[arg(
+        short,
+        long,
+        value_name = "edge",
+        value_enum,
+        default_value = "both",
+        ignore_case = true
+    )]
+    trigger: EdgeFlags,
+
+    /// The file to write the dump to
+    #[arg(short, long, value_name = "file")]
+    output: PathBuf,
+
+    /// The number of recent events to include in the dump
+    #[arg(short = 'n', long, value_name = "num", default_value = "64")]
+    depth: usize,
+
+    #[command(flatten)]
+    line_opts: common::LineOpts,
+
+    #[command(flatten)]
+    active_low_opts: common::ActiveLowOpts,
+
+    #[command(flatten)]
+    bias_opts: common::BiasOpts,
+
+    /// The debounce period for the trapped line
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[cfg(feature = "uapi_v2")]
+    #[arg(short = 'p', long, value_name = "period", value_parser = common::parse_duration)]
+    debounce_period: Option<Duration>,
+
+    /// Specify the source clock for event timestamps
+    ///
+    /// With the realtime clock the timestamps in the dump are displayed as UTC,
+    /// so they can be correlated with system logs.
+    ///
+    /// If the clock is not supported by the kernel, or the hte clock is not
+    /// supported by the line, then a warning is emitted and the monotonic
+    /// clock is used instead.
+    #[cfg(feature = "uapi_v2")]
+    #[arg(
+        short = 'E',
+        long,
+        value_name = "clock",
+        value_enum,
+        ignore_case = true
+    )]
+    event_clock: Option<EventClockFlags>,
+
+    /// The consumer label applied to the requested line.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "name",
+        default_value = "gpiocdev-trap"
+    )]
+    consumer: String,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+impl Opts {
+    // mutate the config to match the configuration
+    fn apply(&self, config: &mut Config, clock: Option<EventClockFlags>) {
+        #[cfg(feature = "uapi_v2")]
+        if let Some(period) = self.debounce_period {
+            config.with_debounce_period(period);
+        }
+        config.with_event_clock(clock.map(|c| c.into()));
+        self.active_low_opts.apply(config);
+        self.bias_opts.apply(config);
+        config.with_edge_detection(EdgeDetection::BothEdges);
+    }
+
+    // The event clock to request, falling back if the kernel does not support it.
+    #[cfg(feature = "uapi_v2")]
+    fn event_clock(&self, abiv: gpiocdev::AbiVersion) -> Option<EventClockFlags> {
+        self.event_clock
+            .and_then(|clock| common::select_event_clock(clock, abiv, &self.emit))
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn event_clock(&self, _abiv: gpiocdev::AbiVersion) -> Option<EventClockFlags> {
+        None
+    }
+
+    fn is_trigger(&self, kind: EdgeKind) -> bool {
+        match self.trigger {
+            EdgeFlags::Both => true,
+            EdgeFlags::Rising => kind == EdgeKind::Rising,
+            EdgeFlags::Falling => kind == EdgeKind::Falling,
+        }
+    }
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Ok(()) => true,
+        Err(e) => {
+            common::emit_error(&opts.emit, &e);
+            false
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<()> {
+    let lines = [opts.line.clone()];
+    let mut r = common::Resolver::resolve_lines(&lines, &opts.line_opts, &opts.uapi_opts);
+    if !r.errors.is_empty() {
+        return Err(r.errors.remove(0));
+    }
+    let co = r.lines[&opts.line];
+    let ci = &r.chips[co.chip_idx];
+    let request_line = |clock: Option<EventClockFlags>| {
+        let mut cfg = Config::default();
+        opts.apply(&mut cfg, clock);
+        cfg.with_line(co.offset);
+        let mut bld = Request::from_config(cfg);
+        bld.on_chip(&ci.path).with_consumer(&opts.consumer);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        bld.using_abi_version(r.abiv);
+        bld.request()
+    };
+    let (req, clock) = common::request_with_clock_fallback(
+        opts.event_clock(r.abiv),
+        &format!("line {} from {}", co.offset, ci.name),
+        &opts.emit,
+        request_line,
+    );
+    let timefmt = if clock == Some(EventClockFlags::Realtime) {
+        TimeFmt::Utc
+    } else {
+        TimeFmt::Seconds
+    };
+    let req = req.with_context(|| {
+        format!(
+            "failed to request line {} from {}{}",
+            co.offset,
+            ci.name,
+            common::conflict_hint(&ci.path, &[co.offset])
+        )
+    })?;
+
+    let mut history =
+        VecDeque::with_capacity(opts.depth.saturating_add(1).min(MAX_PREALLOCATED_HISTORY));
+    let trigger = loop {
+        let edge = req
+            .read_edge_event()
+            .with_context(|| format!("failed to read event from {}", ci.name))?;
+        if history.len() > opts.depth {
+            history.pop_front();
+        }
+        let triggered = opts.is_trigger(edge.kind);
+        history.push_back(edge);
+        if triggered {
+            break history.pop_back().unwrap();
+        }
+    };
+    // freeze event consumption while the state is captured
+    let snapshot = Resolver::resolve_lines_with_info(
+        &[],
+        &LineOpts {
+            chip: None,
+            strict: false,
+            by_name: false,
+            wait_for_chip: None,
+        },
+        &opts.uapi_opts,
+        true,
+        true,
+    );
+    let dump = Dump {
+        trigger: DumpEvent::new(trigger, ci, &timefmt),
+        events: history
+            .into_iter()
+            .map(|e| DumpEvent::new(e, ci, &timefmt))
+            .collect(),
+        lines: snapshot
+            .info
+            .iter()
+            .map(|li| DumpLine {
+                chip: snapshot.chips[li.chip].name.clone(),
+                info: li.info.clone(),
+            })
+            .collect(),
+        errors: snapshot
+            .errors
+            .iter()
+            .map(|e| common::format_error(&opts.emit, e))
+            .collect(),
+    };
+    let mut f = File::create(&opts.output)
+        .with_context(|| format!("failed to create '{}'", opts.output.display()))?;
+    dump.write(&mut f, &opts.emit)
+        .with_context(|| format!("failed to write '{}'", opts.output.display()))?;
+    println!(
+        "trapped {} edge on '{}' - dump written to '{}'",
+        event_kind_name(dump.trigger.edge.kind),
+        opts.line,
+        opts.output.display()
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct Dump {
+    trigger: DumpEvent,
+    events: Vec<DumpEvent>,
+    lines: Vec<DumpLine>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    errors: Vec<String>,
+}
+
+impl Dump {
+    fn write(&self, w: &mut dyn Write, opts: &EmitOpts) -> Result<()> {
+        #[cfg(feature = "json")]
+        if opts.json {
+            serde_json::to_writer_pretty(&mut *w, self)?;
+            writeln!(w)?;
+            return Ok(());
+        }
+        writeln!(w, "trigger:")?;
+        self.trigger.write(w)?;
+        writeln!(w, "events:")?;
+        for e in &self.events {
+            e.write(w)?;
+        }
+        writeln!(w, "lines:")?;
+        for l in &self.lines {
+            l.write(w, opts.quoted)?;
+        }
+        if !self.errors.is_empty() {
+            writeln!(w, "errors:")?;
+            for e in &self.errors {
+                writeln!(w, "\t{}", e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct DumpEvent {
+    chip: String,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    edge: EdgeEvent,
+    timestamp: String,
+}
+
+impl DumpEvent {
+    fn new(edge: EdgeEvent, ci: &ChipInfo, timefmt: &TimeFmt) -> DumpEvent {
+        DumpEvent {
+            chip: ci.name.clone(),
+            timestamp: format_time(edge.timestamp_ns, timefmt),
+            edge,
+        }
+    }
+
+    fn write(&self, w: &mut dyn Write) -> Result<()> {
+        writeln!(
+            w,
+            "\t{}\t{}\t{} {}",
+            self.timestamp,
+            event_kind_name(self.edge.kind),
+            format_chip_name(&self.chip),
+            self.edge.offset
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct DumpLine {
+    chip: String,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    info: Info,
+}
+
+impl DumpLine {
+    fn write(&self, w: &mut dyn Write, quoted: bool) -> Result<()> {
+        let lname = if self.info.name.is_empty() {
+            "unnamed".to_string()
+        } else if quoted {
+            format!("\"{}\"", self.info.name)
+        } else {
+            self.info.name.to_string()
+        };
+        writeln!(
+            w,
+            "\t{} {}\t{:16}\t{}",
+            format_chip_name(&self.chip),
+            self.info.offset,
+            lname,
+            stringify_attrs(&self.info, quoted),
+        )?;
+        Ok(())
+    }
+}
+
+fn event_kind_name(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Rising => "rising",
+        EdgeKind::Falling => "falling",
+    }
+}
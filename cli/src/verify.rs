@@ -0,0 +1,389 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+mod manifest;
+use self::manifest::LineSpec;
+
+use super::common::{self, format_duration, EmitOpts};
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use gpiocdev::line::{Bias, Direction, Drive, EdgeDetection, EventClock, Info, LineRef, Offset};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The manifest describing the expected state of the lines
+    ///
+    /// The manifest is a TOML file containing a table for each line, e.g.
+    ///     [[line]]
+    ///     line = "gpiochip0:17"
+    ///     name = "MOTOR_EN"
+    ///     consumer = "motord"
+    ///     config = "output,drive=open-drain"
+    ///
+    /// The line is identified by name, or by chip and offset.
+    /// The other fields are optional and only those present are checked.
+    ///
+    /// The config uses the same attributes as --line-config, and any
+    /// active-low setting is always checked.
+    #[arg(value_name = "manifest", verbatim_doc_comment)]
+    manifest: PathBuf,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    let mut res = CmdResults::default();
+    match read_manifest(opts) {
+        Ok(specs) => {
+            for spec in &specs {
+                res.lines.push(check_line(spec, &opts.emit));
+            }
+        }
+        Err(e) => res.push_error(&opts.emit, &e),
+    }
+    res.emit(opts);
+    res.errors.is_empty() && res.lines.iter().all(|l| l.is_ok())
+}
+
+fn read_manifest(opts: &Opts) -> Result<Vec<LineSpec>> {
+    let s = std::fs::read_to_string(&opts.manifest)
+        .with_context(|| format!("unable to read '{}'", opts.manifest.display()))?;
+    manifest::parse(&s).with_context(|| format!("invalid manifest '{}'", opts.manifest.display()))
+}
+
+fn check_line(spec: &LineSpec, opts: &EmitOpts) -> LineCheck {
+    let line = &spec.line;
+    let mut check = LineCheck {
+        line: line.to_string(),
+        ..Default::default()
+    };
+    let found = match line {
+        LineRef::Name(name) => gpiocdev::find_named_line_unique(name),
+        _ => line.resolve(),
+    };
+    match found {
+        Ok(found) => {
+            check.chip = found
+                .chip
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            check.offset = Some(found.info.offset);
+            check.mismatches = mismatches(spec, &found.info);
+        }
+        Err(e) => {
+            check.error = Some(common::format_error(
+                opts,
+                &anyhow!(e).context("unable to find line"),
+            ))
+        }
+    }
+    check
+}
+
+fn mismatches(spec: &LineSpec, info: &Info) -> Vec<Mismatch> {
+    let mut mm = Vec::new();
+    if let Some(name) = &spec.name {
+        if *name != info.name {
+            mm.push(Mismatch::new("name", quoted(name), quoted(&info.name)));
+        }
+    }
+    if let Some(used) = spec.used {
+        if used != info.used {
+            mm.push(Mismatch::new("used", used, info.used));
+        }
+    }
+    if let Some(consumer) = &spec.consumer {
+        if !info.used {
+            mm.push(Mismatch::new("consumer", quoted(consumer), "unused"));
+        } else if *consumer != info.consumer {
+            mm.push(Mismatch::new(
+                "consumer",
+                quoted(consumer),
+                quoted(&info.consumer),
+            ));
+        }
+    }
+    let cfg = match &spec.config {
+        Some(cfg) => cfg,
+        None => return mm,
+    };
+    if let Some(direction) = cfg.direction {
+        if direction != info.direction {
+            mm.push(Mismatch::new(
+                "direction",
+                direction_name(Some(direction)),
+                direction_name(Some(info.direction)),
+            ));
+        }
+    }
+    if cfg.active_low != info.active_low {
+        mm.push(Mismatch::new("active-low", cfg.active_low, info.active_low));
+    }
+    if cfg.bias.is_some() && cfg.bias != info.bias {
+        mm.push(Mismatch::new(
+            "bias",
+            bias_name(cfg.bias),
+            bias_name(info.bias),
+        ));
+    }
+    if cfg.drive.is_some() && cfg.drive != info.drive {
+        mm.push(Mismatch::new(
+            "drive",
+            drive_name(cfg.drive),
+            drive_name(info.drive),
+        ));
+    }
+    if cfg.edge_detection.is_some() && cfg.edge_detection != info.edge_detection {
+        mm.push(Mismatch::new(
+            "edges",
+            edges_name(cfg.edge_detection),
+            edges_name(info.edge_detection),
+        ));
+    }
+    if cfg.event_clock.is_some() && cfg.event_clock != info.event_clock {
+        mm.push(Mismatch::new(
+            "event-clock",
+            event_clock_name(cfg.event_clock),
+            event_clock_name(info.event_clock),
+        ));
+    }
+    if cfg.debounce_period.is_some() && cfg.debounce_period != info.debounce_period {
+        mm.push(Mismatch::new(
+            "debounce",
+            debounce_name(cfg.debounce_period),
+            debounce_name(info.debounce_period),
+        ));
+    }
+    mm
+}
+
+fn quoted(s: &str) -> String {
+    format!("\"{}\"", s)
+}
+
+fn direction_name(d: Option<Direction>) -> &'static str {
+    match d {
+        None => "as-is",
+        Some(Direction::Input) => "input",
+        Some(Direction::Output) => "output",
+    }
+}
+
+fn bias_name(b: Option<Bias>) -> &'static str {
+    match b {
+        None => "none",
+        Some(Bias::PullUp) => "pull-up",
+        Some(Bias::PullDown) => "pull-down",
+        Some(Bias::Disabled) => "disabled",
+    }
+}
+
+fn drive_name(d: Option<Drive>) -> &'static str {
+    match d {
+        None => "none",
+        Some(Drive::PushPull) => "push-pull",
+        Some(Drive::OpenDrain) => "open-drain",
+        Some(Drive::OpenSource) => "open-source",
+    }
+}
+
+fn edges_name(e: Option<EdgeDetection>) -> &'static str {
+    match e {
+        None => "none",
+        Some(EdgeDetection::RisingEdge) => "rising",
+        Some(EdgeDetection::FallingEdge) => "falling",
+        Some(EdgeDetection::BothEdges) => "both",
+    }
+}
+
+fn event_clock_name(c: Option<EventClock>) -> &'static str {
+    match c {
+        None => "none",
+        Some(EventClock::Monotonic) => "monotonic",
+        Some(EventClock::Realtime) => "realtime",
+        Some(EventClock::Hte) => "hte",
+    }
+}
+
+fn debounce_name(d: Option<std::time::Duration>) -> String {
+    match d {
+        None => "none".to_string(),
+        Some(d) => format_duration(d),
+    }
+}
+
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct CmdResults {
+    lines: Vec<LineCheck>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    errors: Vec<String>,
+}
+
+impl CmdResults {
+    fn push_error(&mut self, opts: &EmitOpts, e: &anyhow::Error) {
+        self.errors.push(common::format_error(opts, e))
+    }
+
+    fn emit(&self, opts: &Opts) {
+        #[cfg(feature = "json")]
+        if opts.emit.json {
+            println!("{}", serde_json::to_string(self).unwrap());
+            return;
+        }
+        self.print(opts)
+    }
+
+    fn print(&self, opts: &Opts) {
+        for l in &self.lines {
+            l.print(opts);
+        }
+        for e in &self.errors {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct LineCheck {
+    line: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "String::is_empty"))]
+    chip: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    offset: Option<Offset>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    mismatches: Vec<Mismatch>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    error: Option<String>,
+}
+
+impl LineCheck {
+    fn is_ok(&self) -> bool {
+        self.error.is_none() && self.mismatches.is_empty()
+    }
+
+    fn print(&self, opts: &Opts) {
+        let line = if opts.emit.quoted {
+            quoted(&self.line)
+        } else {
+            self.line.clone()
+        };
+        if let Some(e) = &self.error {
+            println!("{}\t{}", line, e);
+            return;
+        }
+        let status = if self.is_ok() { "ok" } else { "mismatch" };
+        println!(
+            "{}\t{} {}\t{}",
+            line,
+            self.chip,
+            self.offset.unwrap_or_default(),
+            status
+        );
+        for m in &self.mismatches {
+            println!("\t- {}={}", m.attr, m.expected);
+            println!("\t+ {}={}", m.attr, m.found);
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct Mismatch {
+    attr: &'static str,
+    expected: String,
+    found: String,
+}
+
+impl Mismatch {
+    fn new<E: ToString, F: ToString>(attr: &'static str, expected: E, found: F) -> Mismatch {
+        Mismatch {
+            attr,
+            expected: expected.to_string(),
+            found: found.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(s: &str) -> LineSpec {
+        manifest::parse(&format!("[[line]]\nline = \"GPIO17\"\n{}", s))
+            .unwrap()
+            .remove(0)
+    }
+
+    fn attrs(mm: &[Mismatch]) -> Vec<(&str, &str, &str)> {
+        mm.iter()
+            .map(|m| (m.attr, m.expected.as_str(), m.found.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn line_mismatches() {
+        let info = Info {
+            offset: 17,
+            name: "GPIO17".to_string(),
+            consumer: "gpiocdev-set".to_string(),
+            used: true,
+            direction: Direction::Output,
+            drive: Some(Drive::PushPull),
+            ..Default::default()
+        };
+        assert!(mismatches(&spec(""), &info).is_empty());
+        assert!(mismatches(
+            &spec("name = \"GPIO17\"\nused = true\nconfig = \"output\""),
+            &info
+        )
+        .is_empty());
+        assert_eq!(
+            attrs(&mismatches(
+                &spec("name = \"MOTOR_EN\"\nconsumer = \"motord\""),
+                &info
+            )),
+            [
+                ("name", "\"MOTOR_EN\"", "\"GPIO17\""),
+                ("consumer", "\"motord\"", "\"gpiocdev-set\"")
+            ]
+        );
+        assert_eq!(
+            attrs(&mismatches(
+                &spec("config = \"output,active-low,drive=open-drain,bias=pull-up\""),
+                &info
+            )),
+            [
+                ("active-low", "true", "false"),
+                ("bias", "pull-up", "none"),
+                ("drive", "open-drain", "push-pull")
+            ]
+        );
+        let info = Info {
+            offset: 17,
+            direction: Direction::Input,
+            edge_detection: Some(EdgeDetection::RisingEdge),
+            debounce_period: Some(std::time::Duration::from_millis(5)),
+            ..Default::default()
+        };
+        assert_eq!(
+            attrs(&mismatches(
+                &spec("used = true\nconsumer = \"motord\"\nconfig = \"output,edges=both,debounce=10ms\""),
+                &info
+            )),
+            [
+                ("used", "true", "false"),
+                ("consumer", "\"motord\"", "unused"),
+                ("direction", "output", "input"),
+                ("edges", "both", "rising"),
+                ("debounce", "10ms", "5ms")
+            ]
+        );
+    }
+}
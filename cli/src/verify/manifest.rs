@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// The manifest is TOML - an array of line tables, e.g.
+//
+//   [[line]]
+//   line = "gpiochip0:17"
+//   name = "MOTOR_EN"
+//   consumer = "motord"
+//   config = "output,drive=open-drain"
+
+use anyhow::{Context, Result};
+use gpiocdev::line::{Config, LineRef};
+use serde_derive::Deserialize;
+
+/// The expected state of a line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineSpec {
+    /// The line, identified by name, or by chip and offset.
+    pub line: LineRef,
+
+    /// The expected name of the line.
+    pub name: Option<String>,
+
+    /// The expected consumer of the line.
+    ///
+    /// Implies the line is used.
+    pub consumer: Option<String>,
+
+    /// The line is expected to be in use.
+    pub used: Option<bool>,
+
+    /// The expected configuration of the line.
+    pub config: Option<Config>,
+}
+
+// The layout of a manifest file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default)]
+    line: Vec<LineEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LineEntry {
+    line: String,
+    name: Option<String>,
+    consumer: Option<String>,
+    used: Option<bool>,
+    config: Option<String>,
+}
+
+impl LineEntry {
+    fn to_spec(&self) -> Result<LineSpec> {
+        Ok(LineSpec {
+            line: self.line.parse()?,
+            name: self.name.clone(),
+            consumer: self.consumer.clone(),
+            used: self.used,
+            config: self.config.as_deref().map(str::parse).transpose()?,
+        })
+    }
+}
+
+pub fn parse(s: &str) -> Result<Vec<LineSpec>> {
+    let manifest: Manifest = toml::from_str(s)?;
+    manifest
+        .line
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            entry
+                .to_spec()
+                .with_context(|| format!("entry {}", idx + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpiocdev::line::Direction;
+
+    #[test]
+    fn parse_manifest() {
+        let specs = parse(
+            r#"
+# motor controller
+[[line]]
+line = "gpiochip0:17" # the enable line
+name = "MOTOR_EN"
+consumer = "motord"
+config = "output,drive=open-drain"
+
+[[line]] # a spare
+line = 'GPIO22'
+used = false
+"#,
+        )
+        .unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].line, LineRef::Offset("gpiochip0".to_string(), 17));
+        assert_eq!(specs[0].name.as_deref(), Some("MOTOR_EN"));
+        assert_eq!(specs[0].consumer.as_deref(), Some("motord"));
+        assert_eq!(specs[0].used, None);
+        let cfg = specs[0].config.as_ref().unwrap();
+        assert_eq!(cfg.direction, Some(Direction::Output));
+        assert_eq!(specs[1].line, LineRef::Name("GPIO22".to_string()));
+        assert_eq!(specs[1].used, Some(false));
+        assert_eq!(specs[1].config, None);
+
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        // manifest syntax and structure errors are reported by toml, and identify the key
+        let toml_err = |s: &str| {
+            let e = parse(s).unwrap_err();
+            assert!(e.is::<toml::de::Error>(), "{:#}", e);
+            e.to_string()
+        };
+        assert!(toml_err("line = \"GPIO22\"").contains("`line`"));
+        assert!(toml_err("[[line]]\nused = \"yes\"\nline = \"GPIO22\"").contains("`line.used`"));
+        assert!(toml_err("[[line]]\ncolour = \"red\"").contains("`colour`"));
+        assert!(toml_err("[[line]]\nname = \"GPIO22\"").contains("`line`"));
+        toml_err("[[line]]\nline = \"GPIO22\"\nline = \"GPIO23\"");
+        toml_err("[[line]]\nline = \"GPIO22\" x");
+
+        // entry errors identify the entry
+        let e = parse("[[line]]\nline = \"GPIO22\"\nconfig = \"sideways\"").unwrap_err();
+        assert!(!e.is::<toml::de::Error>());
+        assert!(format!("{:#}", e).starts_with("entry 1: "));
+    }
+}
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use criterion::{criterion_group, criterion_main, Bencher, Criterion};
+use criterion::{criterion_group, criterion_main, Bencher, Criterion, Throughput};
 
 use gpiocdev::line::EdgeDetection;
 use gpiocdev::request::Request;
@@ -19,6 +19,7 @@ fn v1_benchmarks(c: &mut Criterion) {
     c.bench_function("uapi_v1 edge latency", |b| edge_latency(b, V1));
     c.bench_function("uapi_v1 ten edge events", |b| ten_edge_events(b, V1));
     c.bench_function("uapi_v1 edge event object", |b| edge_event_object(b, V1));
+    edge_throughput(c, "uapi_v1 edge throughput", V1);
 }
 #[cfg(not(feature = "uapi_v1"))]
 fn v1_benchmarks(_c: &mut Criterion) {}
@@ -29,6 +30,7 @@ fn v2_benchmarks(c: &mut Criterion) {
     c.bench_function("uapi_v2 edge latency", |b| edge_latency(b, V2));
     c.bench_function("uapi_v2 ten edge events", |b| ten_edge_events(b, V2));
     c.bench_function("uapi_v2 edge event object", |b| edge_event_object(b, V2));
+    edge_throughput(c, "uapi_v2 edge throughput", V2);
 }
 #[cfg(not(feature = "uapi_v2"))]
 fn v2_benchmarks(_c: &mut Criterion) {}
@@ -126,3 +128,75 @@ fn edge_event_object(b: &mut Bencher, abiv: AbiVersion) {
         let _ = req.read_edge_event().unwrap();
     });
 }
+
+// the number of events per burst in throughput benchmarks.
+// limited by the size of the v1 kernel event buffer.
+const BURST_SIZE: usize = 16;
+
+// determine the sustained rate at which events can be read, by strategy.
+fn edge_throughput(c: &mut Criterion, name: &str, abiv: AbiVersion) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(BURST_SIZE as u64));
+    group.bench_function("one by one", |b| edge_burst_one_by_one(b, abiv));
+    group.bench_function("batched", |b| edge_burst_batched(b, abiv));
+    group.finish();
+}
+
+#[allow(unused_variables)]
+fn edge_request(s: &Simpleton, offset: u32, abiv: AbiVersion) -> Request {
+    let mut builder = Request::builder();
+    builder.on_chip(s.dev_path());
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    builder.using_abi_version(abiv);
+    builder
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap()
+}
+
+// generate a burst of edges on the line.
+fn edge_burst(s: &Simpleton, offset: u32, pull: &mut Level) {
+    for _ in 0..BURST_SIZE {
+        s.set_pull(offset, *pull).unwrap();
+        *pull = match pull {
+            Level::High => Level::Low,
+            Level::Low => Level::High,
+        };
+    }
+}
+
+// determine time taken to read a burst of events individually.
+// overheads are BURST_SIZE * toggle time and 1 * latency.
+fn edge_burst_one_by_one(b: &mut Bencher, abiv: AbiVersion) {
+    let s = Simpleton::new(10);
+    let offset = 1;
+    let req = edge_request(&s, offset, abiv);
+    let mut pull = Level::High;
+
+    b.iter(|| {
+        edge_burst(&s, offset, &mut pull);
+        for _ in 0..BURST_SIZE {
+            let _ = req.read_edge_event().unwrap();
+        }
+    });
+}
+
+// determine time taken to read a burst of events in a batch.
+// overheads are BURST_SIZE * toggle time and 1 * latency.
+fn edge_burst_batched(b: &mut Bencher, abiv: AbiVersion) {
+    let s = Simpleton::new(10);
+    let offset = 1;
+    let req = edge_request(&s, offset, abiv);
+    let mut pull = Level::High;
+    let mut events = Vec::with_capacity(BURST_SIZE);
+
+    b.iter(|| {
+        edge_burst(&s, offset, &mut pull);
+        events.clear();
+        let mut n = 0;
+        while n < BURST_SIZE {
+            n += req.read_edge_events(&mut events, BURST_SIZE - n).unwrap();
+        }
+    });
+}
@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Stress test of edge event throughput.
+//
+// Drives a simulated line at increasing edge rates and reports the maximum
+// rate sustained by each read strategy without losing events.
+//
+// The rate the simulator can drive the line is limited, so the rates reported
+// are those actually achieved, which may fall short of those targeted.
+//
+// Requires the gpio-sim kernel module and permissions to configure it.
+
+use gpiocdev::line::{EdgeDetection, EdgeEvent};
+use gpiocdev::Request;
+use gpiosim::{Level, Simpleton};
+use std::time::{Duration, Instant};
+
+// The number of edges generated at each rate.
+const EDGES: usize = 2000;
+
+// The target edge rates, in edges per second.
+const RATES: [u64; 8] = [
+    1_000, 2_000, 5_000, 10_000, 20_000, 50_000, 100_000, 200_000,
+];
+
+// The time after the last edge that the reader waits for outstanding events.
+const SETTLE: Duration = Duration::from_millis(100);
+
+const OFFSET: u32 = 0;
+
+#[derive(Clone, Copy, Debug)]
+enum Strategy {
+    OneByOne,
+    Batched,
+    #[cfg(feature = "async_tokio")]
+    Async,
+}
+
+const STRATEGIES: &[Strategy] = &[
+    Strategy::OneByOne,
+    Strategy::Batched,
+    #[cfg(feature = "async_tokio")]
+    Strategy::Async,
+];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    for strategy in STRATEGIES {
+        let mut max = None;
+        for rate in RATES {
+            match run(*strategy, rate)? {
+                Some(achieved) => max = Some(achieved),
+                None => break,
+            }
+        }
+        match max {
+            Some(rate) => println!("{:?}: {} edges/s", strategy, rate),
+            None => println!("{:?}: lost events at {} edges/s", strategy, RATES[0]),
+        }
+    }
+    Ok(())
+}
+
+// Drive EDGES edges at the target rate, and read them using the strategy.
+//
+// Returns the achieved rate, or None if any events were lost.
+fn run(strategy: Strategy, rate: u64) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let s = Simpleton::new(1);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_consumer("edge-throughput")
+        .with_line(OFFSET)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()?;
+    let period = Duration::from_nanos(1_000_000_000 / rate);
+
+    let (elapsed, events) = std::thread::scope(|scope| {
+        let driver = scope.spawn(|| drive(&s, period));
+        let events = read(strategy, req);
+        (driver.join().unwrap(), events)
+    });
+    let events = events?;
+    let achieved = (EDGES as f64 / elapsed.as_secs_f64()) as u64;
+    // events lost to kernel buffer overflow are simply missing
+    let lossless = events.len() == EDGES;
+    println!(
+        "{:?}: target {} edges/s, achieved {} edges/s, read {}/{} events",
+        strategy,
+        rate,
+        achieved,
+        events.len(),
+        EDGES
+    );
+    Ok(if lossless { Some(achieved) } else { None })
+}
+
+// Toggle the line EDGES times, paced at the period.
+//
+// Returns the time taken.
+fn drive(s: &Simpleton, period: Duration) -> Duration {
+    let start = Instant::now();
+    let mut pull = Level::High;
+    for idx in 0..EDGES as u32 {
+        let next = start + period * idx;
+        while Instant::now() < next {
+            std::hint::spin_loop();
+        }
+        s.set_pull(OFFSET, pull).unwrap();
+        pull = match pull {
+            Level::High => Level::Low,
+            Level::Low => Level::High,
+        };
+    }
+    start.elapsed()
+}
+
+// Read the events until none arrive within the settle period.
+fn read(strategy: Strategy, req: Request) -> gpiocdev::Result<Vec<EdgeEvent>> {
+    let mut events = Vec::with_capacity(EDGES);
+    match strategy {
+        Strategy::OneByOne => {
+            while req.wait_edge_event(SETTLE)? {
+                events.push(req.read_edge_event()?);
+            }
+        }
+        Strategy::Batched => {
+            while req.wait_edge_event(SETTLE)? {
+                req.read_edge_events(&mut events, EDGES)?;
+            }
+        }
+        #[cfg(feature = "async_tokio")]
+        Strategy::Async => {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_io()
+                .enable_time()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                let areq = gpiocdev::tokio::AsyncRequest::new(req);
+                while let Ok(res) =
+                    tokio::time::timeout(SETTLE, areq.read_edge_events(&mut events, EDGES)).await
+                {
+                    res?;
+                }
+                Ok::<(), gpiocdev::Error>(())
+            })?;
+        }
+    }
+    Ok(events)
+}
@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Example of watching for edge events on a line and info changes on another
+// using a mio event loop.
+
+use anyhow::Context;
+use gpiocdev::line::EdgeDetection;
+use gpiocdev::{Chip, Request};
+use mio::{Events, Interest, Poll, Token};
+use std::result::Result;
+
+const REQUEST: Token = Token(0);
+const CHIP: Token = Token(1);
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut req = Request::builder()
+        .on_chip("/dev/gpiochip0")
+        .with_consumer("mio-watch-line-value")
+        .with_line(23)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .context("Failed to request line")?;
+    let mut chip = Chip::from_path("/dev/gpiochip0")?;
+    chip.watch_line_info(24)?;
+
+    let mut poll = Poll::new()?;
+    poll.registry()
+        .register(&mut req, REQUEST, Interest::READABLE)?;
+    poll.registry()
+        .register(&mut chip, CHIP, Interest::READABLE)?;
+
+    let mut events = Events::with_capacity(2);
+    loop {
+        poll.poll(&mut events, None)?;
+        for event in events.iter() {
+            // readiness is edge triggered, so drain all available events
+            match event.token() {
+                REQUEST => {
+                    while req.has_edge_event()? {
+                        println!("{:?}", req.read_edge_event()?);
+                    }
+                }
+                CHIP => {
+                    while chip.has_line_info_change_event()? {
+                        println!("{:?}", chip.read_line_info_change_event()?);
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
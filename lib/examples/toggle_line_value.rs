@@ -13,21 +13,18 @@ use std::time::Duration;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let offset = 22;
-    let mut value = Value::Active;
 
     let req = Request::builder()
         .on_chip("/dev/gpiochip0")
         .with_consumer("toggle-line-values")
         .with_line(offset)
-        .as_output(value)
+        .as_output(Value::Active)
         .request()
         .context("Failed to request line")?;
 
     loop {
-        println!("{}={:?}", offset, value);
         thread::sleep(Duration::from_millis(500));
-        value = value.not();
-        req.set_value(offset, value)
-            .context("Failed to set value")?;
+        let value = req.toggle_value(offset).context("Failed to toggle value")?;
+        println!("{}={:?}", offset, value);
     }
 }
@@ -12,7 +12,7 @@ use std::thread;
 use std::time::Duration;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut values = [(22, Value::Active), (12, Value::Inactive)]
+    let values = [(22, Value::Active), (12, Value::Inactive)]
         .into_iter()
         .collect();
     // or equivalently...
@@ -27,9 +27,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .context("Failed to request line")?;
 
     loop {
-        println!("{:?}", values);
         thread::sleep(Duration::from_millis(500));
-        values.not();
-        req.set_values(&values).context("Failed to set values")?;
+        let values = req
+            .toggle_values(&[22, 12])
+            .context("Failed to toggle values")?;
+        println!("{:?}", values);
     }
 }
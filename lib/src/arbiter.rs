@@ -0,0 +1,357 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Sharing a request between components within a process.
+//!
+//! The kernel only allows a line to be requested once, so components within an
+//! application that need to control the same line, such as a maintenance override,
+//! must share the request.
+//!
+//! An [`Arbiter`] owns the request and grants access to it to one [`Claim`] at a
+//! time, the claim with the highest priority.  When a higher priority claim is made
+//! the current holder is preempted, and when the holder releases its claim the
+//! request is granted to the highest priority remaining claim.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::{Request, Result};
+//! # use gpiocdev::arbiter::{Arbiter, Notification};
+//! # use gpiocdev::line::Value;
+//! # fn main() -> Result<()> {
+//! let req = Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_line(17)
+//!     .as_output(Value::Inactive)
+//!     .request()?;
+//! let arbiter = Arbiter::new(req);
+//!
+//! let normal = arbiter.claim_with_notify(1, |n| println!("normal: {:?}", n));
+//! normal.with_request(|req| req.set_value(17, Value::Active))?;
+//!
+//! // the maintenance override preempts normal operation...
+//! let maintenance = arbiter.claim(10);
+//! maintenance.with_request(|req| req.set_value(17, Value::Inactive))?;
+//! assert!(!normal.is_granted());
+//!
+//! // ...until it is released
+//! drop(maintenance);
+//! assert!(normal.is_granted());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Request, Result};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A change in the status of a [`Claim`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Notification {
+    /// The claim has been granted access to the request.
+    Granted,
+
+    /// The claim has lost access to the request to a higher priority claim.
+    Preempted,
+}
+
+type Notifier = Arc<dyn Fn(Notification) + Send + Sync>;
+
+struct ClaimState {
+    id: u64,
+    priority: u32,
+    notify: Option<Notifier>,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    claims: Vec<ClaimState>,
+    holder: Option<u64>,
+}
+
+impl State {
+    // Grant to the highest priority claim, returning the notifications to deliver.
+    //
+    // The current holder retains the grant against claims of equal priority,
+    // otherwise the earliest claim wins.
+    fn regrant(&mut self) -> Vec<(Notifier, Notification)> {
+        let mut best: Option<&ClaimState> = None;
+        for c in &self.claims {
+            best = match best {
+                None => Some(c),
+                Some(b) if c.priority > b.priority => Some(c),
+                Some(b) if c.priority == b.priority && Some(c.id) == self.holder => Some(c),
+                b => b,
+            };
+        }
+        let winner = best.map(|c| c.id);
+        let mut notes = Vec::new();
+        if winner == self.holder {
+            return notes;
+        }
+        for c in &self.claims {
+            if let Some(notify) = &c.notify {
+                if Some(c.id) == self.holder {
+                    notes.push((notify.clone(), Notification::Preempted));
+                } else if Some(c.id) == winner {
+                    notes.push((notify.clone(), Notification::Granted));
+                }
+            }
+        }
+        self.holder = winner;
+        notes
+    }
+}
+
+struct Shared<R> {
+    resource: R,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl<R> Shared<R> {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("failed to acquire arbiter lock")
+    }
+
+    // Update the grant and deliver any notifications, outside the lock.
+    fn regrant(&self, mut state: MutexGuard<'_, State>) {
+        let notes = state.regrant();
+        drop(state);
+        self.changed.notify_all();
+        for (notify, n) in notes {
+            notify(n);
+        }
+    }
+}
+
+/// Grants access to a shared request to the highest priority [`Claim`].
+///
+/// The arbiter may be cloned to share it between components.
+pub struct Arbiter<R = Request>(Arc<Shared<R>>);
+
+impl<R> Clone for Arbiter<R> {
+    fn clone(&self) -> Self {
+        Arbiter(self.0.clone())
+    }
+}
+
+impl<R> Arbiter<R> {
+    /// Create an arbiter controlling access to the request.
+    pub fn new(req: R) -> Arbiter<R> {
+        Arbiter(Arc::new(Shared {
+            resource: req,
+            state: Mutex::default(),
+            changed: Condvar::new(),
+        }))
+    }
+
+    /// Make a claim on the request with the given priority.
+    ///
+    /// Higher values have higher priority.
+    /// The claim is granted immediately if it has a higher priority than all
+    /// existing claims, preempting the current holder.
+    pub fn claim(&self, priority: u32) -> Claim<R> {
+        self.do_claim(priority, None)
+    }
+
+    /// Make a claim on the request that is notified when the claim is granted or preempted.
+    ///
+    /// The notifications are delivered from the thread that caused the change in grant.
+    /// The notification for the initial grant is delivered before this returns.
+    pub fn claim_with_notify<F>(&self, priority: u32, notify: F) -> Claim<R>
+    where
+        F: Fn(Notification) + Send + Sync + 'static,
+    {
+        self.do_claim(priority, Some(Arc::new(notify)))
+    }
+
+    fn do_claim(&self, priority: u32, notify: Option<Notifier>) -> Claim<R> {
+        let mut state = self.0.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.claims.push(ClaimState {
+            id,
+            priority,
+            notify,
+        });
+        self.0.regrant(state);
+        Claim {
+            shared: self.0.clone(),
+            id,
+        }
+    }
+
+    /// The number of outstanding claims.
+    pub fn claims(&self) -> usize {
+        self.0.lock().claims.len()
+    }
+}
+
+/// A claim on the request controlled by an [`Arbiter`].
+///
+/// The claim is released when dropped.
+pub struct Claim<R = Request> {
+    shared: Arc<Shared<R>>,
+    id: u64,
+}
+
+impl<R> Claim<R> {
+    /// Returns true if the claim currently has access to the request.
+    pub fn is_granted(&self) -> bool {
+        self.shared.lock().holder == Some(self.id)
+    }
+
+    /// Wait for the claim to be granted.
+    ///
+    /// Returns false if the claim was not granted within the timeout.
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely.
+    pub fn wait_granted(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = self.shared.lock();
+        while state.holder != Some(self.id) {
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    self.shared
+                        .changed
+                        .wait_timeout(state, deadline - now)
+                        .expect("failed to acquire arbiter lock")
+                        .0
+                }
+                None => self
+                    .shared
+                    .changed
+                    .wait(state)
+                    .expect("failed to acquire arbiter lock"),
+            };
+        }
+        true
+    }
+
+    /// Perform an operation on the request if the claim is granted.
+    ///
+    /// Preemption is deferred until the operation completes, so the operation
+    /// should not block.
+    ///
+    /// Returns an error if the claim is not granted.
+    pub fn with_request<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&R) -> Result<T>,
+    {
+        let state = self.shared.lock();
+        if state.holder != Some(self.id) {
+            return Err(Error::InvalidArgument("claim is not granted.".to_string()));
+        }
+        let res = f(&self.shared.resource);
+        drop(state);
+        res
+    }
+}
+
+impl<R> Drop for Claim<R> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.claims.retain(|c| c.id != self.id);
+        if state.holder == Some(self.id) {
+            state.holder = None;
+        }
+        self.shared.regrant(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorder() -> (
+        Arc<Mutex<Vec<Notification>>>,
+        impl Fn(Notification) + Send + Sync + 'static,
+    ) {
+        let log: Arc<Mutex<Vec<Notification>>> = Arc::default();
+        let l = log.clone();
+        (log, move |n| l.lock().unwrap().push(n))
+    }
+
+    #[test]
+    fn priority() {
+        let a = Arbiter::new(42);
+        let (log1, n1) = recorder();
+        let c1 = a.claim_with_notify(1, n1);
+        assert!(c1.is_granted());
+        assert_eq!(*log1.lock().unwrap(), &[Notification::Granted]);
+        assert_eq!(c1.with_request(|r| Ok(*r)).unwrap(), 42);
+
+        // lower priority waits
+        let (log0, n0) = recorder();
+        let c0 = a.claim_with_notify(0, n0);
+        assert!(!c0.is_granted());
+        assert!(log0.lock().unwrap().is_empty());
+        assert_eq!(
+            c0.with_request(|r| Ok(*r)).unwrap_err(),
+            Error::InvalidArgument("claim is not granted.".to_string())
+        );
+
+        // equal priority does not preempt
+        let c1b = a.claim(1);
+        assert!(c1.is_granted());
+        assert!(!c1b.is_granted());
+
+        // higher priority preempts
+        let c2 = a.claim(2);
+        assert!(c2.is_granted());
+        assert!(!c1.is_granted());
+        assert_eq!(
+            *log1.lock().unwrap(),
+            &[Notification::Granted, Notification::Preempted]
+        );
+        assert_eq!(a.claims(), 4);
+
+        // release regrants to the highest remaining, earliest first
+        drop(c2);
+        assert!(c1.is_granted());
+        assert_eq!(
+            *log1.lock().unwrap(),
+            &[
+                Notification::Granted,
+                Notification::Preempted,
+                Notification::Granted
+            ]
+        );
+        drop(c1);
+        assert!(c1b.is_granted());
+        drop(c1b);
+        assert!(c0.is_granted());
+        assert_eq!(*log0.lock().unwrap(), &[Notification::Granted]);
+        drop(c0);
+        assert_eq!(a.claims(), 0);
+    }
+
+    #[test]
+    fn wait_granted() {
+        let a = Arbiter::new(());
+        let c1 = a.claim(5);
+        let c0 = a.claim(0);
+        assert!(!c0.wait_granted(Duration::from_millis(10)));
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            drop(c1);
+        });
+        assert!(c0.wait_granted(Duration::from_secs(1)));
+        t.join().unwrap();
+
+        // no deadline
+        let c1 = a.claim(5);
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            drop(c0);
+        });
+        assert!(c1.wait_granted(Duration::MAX));
+        t.join().unwrap();
+    }
+}
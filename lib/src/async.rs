@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+pub mod reactor;
+
 /// Wrappers around the Tokio reactor.
 #[cfg(feature = "async_tokio")]
 pub mod tokio;
@@ -2,118 +2,90 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::chip::Chip;
-use crate::line::{EdgeEvent, InfoChangeEvent};
-use crate::request::{EdgeEventBuffer, Request};
+use crate::reactor::{self, AsyncIoBackend};
+use crate::request::Builder;
+pub use crate::request::EdgeEventStreamExt;
 use crate::Result;
 use async_io::Async;
+use futures::ready;
 use futures::task::{Context, Poll};
-use futures::{ready, Stream};
-use std::pin::Pin;
+use std::io;
+use std::os::unix::prelude::{AsFd, BorrowedFd, RawFd};
 
-/// Async wrapper around [`Chip`] for the async-io reactor.
+/// The [`AsyncIoBackend`] for the async-io reactor.
+pub struct Backend(Async<Fd>);
+
+// A file descriptor owned by the async wrapper.
+struct Fd(RawFd);
+
+impl AsFd for Fd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the wrapper keeps the fd open until after the backend is dropped.
+        unsafe { BorrowedFd::borrow_raw(self.0) }
+    }
+}
+
+impl AsyncIoBackend for Backend {
+    fn new(fd: RawFd) -> io::Result<Self> {
+        Ok(Backend(Async::new(Fd(fd))?))
+    }
+
+    fn poll_readable(
+        &self,
+        cx: &mut Context<'_>,
+        is_ready: &mut dyn FnMut() -> Result<bool>,
+    ) -> Poll<Result<()>> {
+        loop {
+            ready!(self.0.poll_readable(cx))?;
+            if is_ready()? {
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+/// Async wrapper around [`Chip`](crate::Chip) for the async-io reactor.
 ///
 /// # Example
 ///  ```no_run
 /// # use gpiocdev::Result;
-/// use gpiocdev::chip::Chip;
+/// use gpiocdev::Chip;
 /// use gpiocdev::async_io::AsyncChip;
+/// use futures::StreamExt;
 ///
 /// # async fn docfn() -> Result<()> {
 /// let chip = Chip::from_path("/dev/gpiochip0")?;
 /// let achip = AsyncChip::new(chip);
 /// let evt = achip.read_line_info_change_event().await?;
+/// let mut events = achip.watch_info_changes(&[3, 5, 8, 13])?;
+/// while let Ok(evt) = events.next().await.unwrap() {
+///     // process event...
+/// }
 /// # Ok(())
 /// # }
 /// ```
-pub struct AsyncChip(Async<Chip>);
-
-impl AsyncChip {
-    /// Create an async-io wrapper for a Chip.
-    pub fn new(chip: Chip) -> Self {
-        AsyncChip(Async::new(chip).unwrap())
-    }
-
-    /// Async form of [`Chip::read_line_info_change_event`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// # use gpiocdev::chip::Chip;
-    /// use gpiocdev::async_io::AsyncChip;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let chip = Chip::from_path("/dev/gpiochip0")?;
-    /// let achip = AsyncChip::new(chip);
-    /// let evt = achip.read_line_info_change_event().await?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub async fn read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
-        loop {
-            self.0.readable().await?;
-            let chip = self.0.get_ref();
-            if chip.has_line_info_change_event()? {
-                return chip.read_line_info_change_event();
-            }
-        }
-    }
-
-    /// Async form of [`Chip::info_change_events`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// # use gpiocdev::chip::Chip;
-    /// use gpiocdev::async_io::AsyncChip;
-    /// use futures::StreamExt;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let chip = Chip::from_path("/dev/gpiochip0")?;
-    /// let achip = AsyncChip::new(chip);
-    /// let mut events = achip.info_change_events();
-    /// while let Ok(evt) = events.next().await.unwrap() {
-    ///     // process event...
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn info_change_events(&self) -> InfoChangeStream {
-        InfoChangeStream { chip: self }
-    }
-}
-
-impl AsRef<Chip> for AsyncChip {
-    fn as_ref(&self) -> &Chip {
-        self.0.get_ref()
-    }
-}
+pub type AsyncChip = reactor::AsyncChip<Backend>;
 
-/// Async form of [`InfoChangeIterator`].
+/// Async form of [`InfoChangeIterator`] for the async-io reactor.
 ///
 /// Created by [`AsyncChip::info_change_events`].
 ///
 /// [`InfoChangeIterator`]: crate::chip::InfoChangeIterator
-pub struct InfoChangeStream<'a> {
-    chip: &'a AsyncChip,
-}
-
-impl<'a> Stream for InfoChangeStream<'a> {
-    type Item = Result<InfoChangeEvent>;
+pub type InfoChangeStream<'a> = reactor::InfoChangeStream<'a, Backend>;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        ready!(self.chip.0.poll_readable(cx))?;
-        Poll::Ready(Some(self.chip.as_ref().read_line_info_change_event()))
-    }
-}
+/// A stream of info change events that owns the [`AsyncChip`], for the async-io reactor.
+///
+/// Created by [`AsyncChip::info_change_stream`].
+pub type OwnedInfoChangeStream = reactor::OwnedInfoChangeStream<Backend>;
 
-/// Async wrapper around [`Request`] for the async-io reactor.
+/// Async wrapper around [`Request`](crate::Request) for the async-io reactor.
 ///
 /// # Example
 /// ```no_run
 /// # use gpiocdev::Result;
 /// use gpiocdev::Request;
 /// use gpiocdev::async_io::AsyncRequest;
+/// use futures::StreamExt;
 ///
 /// # async fn docfn() -> Result<()> {
 /// let req = Request::builder()
@@ -124,167 +96,52 @@ impl<'a> Stream for InfoChangeStream<'a> {
 ///    .request()?;
 /// let areq = AsyncRequest::new(req);
 /// let evt = areq.read_edge_event().await?;
+/// let mut events = areq.edge_events();
+/// while let Ok(evt) = events.next().await.unwrap() {
+///     // process event...
+/// }
 /// # Ok(())
 /// # }
 /// ```
-pub struct AsyncRequest(Async<Request>);
+pub type AsyncRequest = reactor::AsyncRequest<Backend>;
 
 impl AsyncRequest {
-    /// Create an async-io wrapper for a Request.
-    pub fn new(req: Request) -> Self {
-        AsyncRequest(Async::new(req).unwrap())
-    }
-
-    /// Async form of [`Request::read_edge_event`].
+    /// Perform the request described by the builder without blocking the reactor.
     ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// use gpiocdev::Request;
-    /// use gpiocdev::async_io::AsyncRequest;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let areq = AsyncRequest::new(req);
-    /// let evt = areq.read_edge_event().await?;
-    /// // process event...
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub async fn read_edge_event(&self) -> Result<EdgeEvent> {
-        loop {
-            self.0.readable().await?;
-            let req = self.0.get_ref();
-            if req.has_edge_event()? {
-                return req.read_edge_event();
-            }
-        }
-    }
-
-    /// Async form of [`Request::read_edge_events_into_slice`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// use gpiocdev::Request;
-    /// use gpiocdev::async_io::AsyncRequest;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let mut buf = vec![0_u64; req.edge_event_u64_size() * 3];
-    /// let areq = AsyncRequest::new(req);
-    /// let num_evts = areq.read_edge_events_into_slice(&mut buf).await?;
-    /// // process events in buf...
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub async fn read_edge_events_into_slice(&self, buf: &mut [u64]) -> Result<usize> {
-        loop {
-            self.0.readable().await?;
-            let req = self.0.get_ref();
-            if req.has_edge_event()? {
-                return req.read_edge_events_into_slice(buf);
-            }
-        }
-    }
-
-    /// Async form of [`Request::new_edge_event_buffer`].
-    ///
-    /// * `capacity` - The number of events that can be buffered in user space.
+    /// Opening the chip and requesting the lines may block on some drivers,
+    /// so these are performed on the [`blocking`] thread pool and the
+    /// resulting request wrapped for the async-io reactor.
     ///
     /// # Example
     /// ```no_run
     /// # use gpiocdev::Result;
     /// use gpiocdev::Request;
     /// use gpiocdev::async_io::AsyncRequest;
-    /// use futures::StreamExt;
     ///
     /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let areq = AsyncRequest::new(req);
-    /// let mut events = areq.new_edge_event_stream(2);
-    /// while let Ok(evt) = events.next().await.unwrap() {
-    ///     // process event...
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream {
-        EdgeEventStream {
-            req: self,
-            events: self.0.get_ref().new_edge_event_buffer(capacity),
-        }
-    }
-
-    /// Async form of [`Request::edge_events`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// use gpiocdev::Request;
-    /// use gpiocdev::async_io::AsyncRequest;
-    /// use futures::StreamExt;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let areq = AsyncRequest::new(req);
-    /// let mut events = areq.edge_events();
-    /// while let Ok(evt) = events.next().await.unwrap() {
-    ///     // process event...
-    /// }
+    /// let areq = AsyncRequest::request(
+    ///     Request::builder()
+    ///         .on_chip("/dev/gpiochip0")
+    ///         .with_line(42)
+    ///         .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges),
+    /// )
+    /// .await?;
+    /// let evt = areq.read_edge_event().await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn edge_events(&self) -> EdgeEventStream {
-        EdgeEventStream {
-            req: self,
-            events: self.0.get_ref().edge_events(),
-        }
+    pub async fn request(builder: &Builder) -> Result<Self> {
+        let mut builder = builder.clone();
+        let req = blocking::unblock(move || builder.request()).await?;
+        Ok(AsyncRequest::new(req))
     }
 }
 
-impl AsRef<Request> for AsyncRequest {
-    fn as_ref(&self) -> &Request {
-        self.0.get_ref()
-    }
-}
-
-/// Async form of [`EdgeEventBuffer`] in its role as an iterator.
+/// Async form of [`EdgeEventBuffer`] in its role as an iterator, for the async-io reactor.
 ///
 /// Created by [`AsyncRequest::new_edge_event_stream`] or [`AsyncRequest::edge_events`].
-pub struct EdgeEventStream<'a> {
-    req: &'a AsyncRequest,
-    events: EdgeEventBuffer<'a>,
-}
-
-impl<'a> Stream for EdgeEventStream<'a> {
-    type Item = Result<EdgeEvent>;
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        if self.events.has_event()? {
-            return Poll::Ready(Some(self.events.read_event()));
-        }
-        ready!(self.req.0.poll_readable(cx))?;
-        Poll::Ready(Some(self.events.read_event()))
-    }
-}
+///
+/// The stream may be filtered and transformed using the [`EdgeEventStreamExt`] adapters.
+///
+/// [`EdgeEventBuffer`]: crate::request::EdgeEventBuffer
+pub type EdgeEventStream<'a> = reactor::EdgeEventStream<'a, Backend>;
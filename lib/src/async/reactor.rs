@@ -0,0 +1,423 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Async wrappers that are generic over the async reactor.
+//!
+//! The wrappers only require the reactor to report when a file descriptor
+//! is readable, as abstracted by the [`AsyncIoBackend`] trait.
+//! Backends are provided for the tokio and async-io reactors, by the
+//! `tokio` and `async_io` modules respectively, which also provide aliases
+//! for the wrappers using those backends.
+//! Other reactors may be supported by implementing [`AsyncIoBackend`].
+//!
+//! # Examples
+//! ```no_run
+//! use gpiocdev::reactor::{AsyncIoBackend, AsyncRequest};
+//! use gpiocdev::Result;
+//! use std::os::unix::prelude::RawFd;
+//! use std::task::{Context, Poll};
+//!
+//! // a backend for a custom reactor
+//! struct MyBackend {
+//!     // ...
+//! }
+//!
+//! impl AsyncIoBackend for MyBackend {
+//!     fn new(fd: RawFd) -> std::io::Result<Self> {
+//!         // register the fd with the reactor...
+//! #       todo!()
+//!     }
+//!
+//!     fn poll_readable(
+//!         &self,
+//!         cx: &mut Context<'_>,
+//!         is_ready: &mut dyn FnMut() -> Result<bool>,
+//!     ) -> Poll<Result<()>> {
+//!         // wait for the reactor to report the fd readable,
+//!         // then confirm with is_ready()...
+//! #       todo!()
+//!     }
+//! }
+//!
+//! # async fn docfn(req: gpiocdev::Request) -> Result<()> {
+//! let areq: AsyncRequest<MyBackend> = AsyncRequest::new(req);
+//! let evt = areq.read_edge_event().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::chip::Chip;
+use crate::line::{EdgeEvent, InfoChangeEvent, Offset};
+pub use crate::request::EdgeEventStreamExt;
+use crate::request::{EdgeEventBuffer, PulseGuard, Request};
+use crate::timerfd::{monotonic_now, TimerFd};
+use crate::waveform::Waveform;
+use crate::Result;
+use futures::task::{Context, Poll};
+use futures::{ready, Stream};
+use std::io;
+use std::os::unix::prelude::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// The interface to an async reactor required by the async wrappers.
+pub trait AsyncIoBackend: Sized {
+    /// Register the file descriptor with the reactor.
+    ///
+    /// The file descriptor remains owned by the wrapper, and is only closed
+    /// after the backend is dropped.
+    fn new(fd: RawFd) -> io::Result<Self>;
+
+    /// Poll for the file descriptor to be readable.
+    ///
+    /// Once the reactor reports the file descriptor as readable, `is_ready`
+    /// is called to confirm that events are available.
+    /// If not, the readiness is stale and must be cleared, and the file
+    /// descriptor polled again.
+    ///
+    /// Any error returned by `is_ready` must be returned.
+    fn poll_readable(
+        &self,
+        cx: &mut Context<'_>,
+        is_ready: &mut dyn FnMut() -> Result<bool>,
+    ) -> Poll<Result<()>>;
+}
+
+// Wait for the file descriptor to be readable.
+async fn readable<B: AsyncIoBackend>(
+    io: &B,
+    is_ready: &mut dyn FnMut() -> Result<bool>,
+) -> Result<()> {
+    futures::future::poll_fn(|cx| io.poll_readable(cx, &mut *is_ready)).await
+}
+
+// Wait for the file descriptor to be readable, or for the timeout to expire.
+//
+// Returns true if the file descriptor is readable.
+async fn readable_timeout<B: AsyncIoBackend>(
+    io: &B,
+    is_ready: &mut dyn FnMut() -> Result<bool>,
+    timeout: Duration,
+) -> Result<bool> {
+    if timeout.is_zero() {
+        // a zero oneshot would disarm the timer
+        return is_ready();
+    }
+    let timer = TimerFd::new(true)?;
+    let tio = B::new(timer.as_raw_fd())?;
+    timer.set_oneshot(timeout)?;
+    futures::future::poll_fn(|cx| {
+        if let Poll::Ready(res) = io.poll_readable(cx, &mut *is_ready) {
+            return Poll::Ready(res.map(|_| true));
+        }
+        tio.poll_readable(cx, &mut || timer_expired(&timer))
+            .map_ok(|_| false)
+    })
+    .await
+}
+
+/// Async wrapper around [`Chip`].
+pub struct AsyncChip<B: AsyncIoBackend> {
+    // dropped before the chip so the fd is deregistered before being closed.
+    io: B,
+    chip: Chip,
+}
+
+impl<B: AsyncIoBackend> AsyncChip<B> {
+    /// Create an async wrapper for a Chip.
+    pub fn new(chip: Chip) -> Self {
+        AsyncChip {
+            io: B::new(chip.as_raw_fd()).unwrap(),
+            chip,
+        }
+    }
+
+    /// Async form of [`Chip::read_line_info_change_event`].
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    /// The event is only read from the kernel once available, and the read
+    /// completes without yielding, so no event is lost if the future is dropped.
+    pub async fn read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
+        readable(&self.io, &mut || self.chip.has_line_info_change_event()).await?;
+        self.chip.read_line_info_change_event()
+    }
+
+    /// Async form of [`Chip::info_change_events`].
+    pub fn info_change_events(&self) -> InfoChangeStream<'_, B> {
+        InfoChangeStream { chip: self }
+    }
+
+    /// Async form of [`Chip::watch_info_changes`].
+    pub fn watch_info_changes(&self, offsets: &[Offset]) -> Result<InfoChangeStream<'_, B>> {
+        self.chip.watch_line_infos(offsets)?;
+        Ok(self.info_change_events())
+    }
+
+    /// Convert the chip into a stream of info change events.
+    ///
+    /// Unlike [`info_change_events`], the stream owns the chip, so it is not
+    /// bound to the lifetime of the `AsyncChip` and may be moved into a task.
+    ///
+    /// [`info_change_events`]: AsyncChip::info_change_events
+    pub fn info_change_stream(self) -> OwnedInfoChangeStream<B> {
+        OwnedInfoChangeStream { chip: self }
+    }
+
+    fn poll_info_change_event(&self, cx: &mut Context) -> Poll<Result<InfoChangeEvent>> {
+        ready!(self
+            .io
+            .poll_readable(cx, &mut || self.chip.has_line_info_change_event()))?;
+        Poll::Ready(self.chip.read_line_info_change_event())
+    }
+}
+
+impl<B: AsyncIoBackend> AsRef<Chip> for AsyncChip<B> {
+    fn as_ref(&self) -> &Chip {
+        &self.chip
+    }
+}
+
+/// Async form of [`InfoChangeIterator`].
+///
+/// Created by [`AsyncChip::info_change_events`].
+///
+/// [`InfoChangeIterator`]: crate::chip::InfoChangeIterator
+pub struct InfoChangeStream<'a, B: AsyncIoBackend> {
+    chip: &'a AsyncChip<B>,
+}
+
+impl<'a, B: AsyncIoBackend> Stream for InfoChangeStream<'a, B> {
+    type Item = Result<InfoChangeEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.chip.poll_info_change_event(cx).map(Some)
+    }
+}
+
+/// A stream of info change events that owns the [`AsyncChip`].
+///
+/// Created by [`AsyncChip::info_change_stream`].
+pub struct OwnedInfoChangeStream<B: AsyncIoBackend> {
+    chip: AsyncChip<B>,
+}
+
+impl<B: AsyncIoBackend> OwnedInfoChangeStream<B> {
+    /// The chip providing the events.
+    ///
+    /// Which may be used to add or remove watches on lines.
+    pub fn chip(&self) -> &Chip {
+        &self.chip.chip
+    }
+
+    /// Consume the stream and return the async chip.
+    pub fn into_inner(self) -> AsyncChip<B> {
+        self.chip
+    }
+}
+
+impl<B: AsyncIoBackend> Stream for OwnedInfoChangeStream<B> {
+    type Item = Result<InfoChangeEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.chip.poll_info_change_event(cx).map(Some)
+    }
+}
+
+/// Async wrapper around [`Request`].
+pub struct AsyncRequest<B: AsyncIoBackend> {
+    // dropped before the request so the fd is deregistered before being closed.
+    io: B,
+    req: Request,
+}
+
+impl<B: AsyncIoBackend> AsyncRequest<B> {
+    /// Create an async wrapper for a Request.
+    pub fn new(req: Request) -> Self {
+        AsyncRequest {
+            io: B::new(req.as_raw_fd()).unwrap(),
+            req,
+        }
+    }
+
+    /// Async form of [`Request::read_edge_event`].
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    /// Events are only read from the kernel once available, and the read
+    /// completes without yielding, so no event is lost if the future is dropped.
+    pub async fn read_edge_event(&self) -> Result<EdgeEvent> {
+        readable(&self.io, &mut || self.req.has_edge_event()).await?;
+        self.req.read_edge_event()
+    }
+
+    /// Async form of [`Request::read_edge_event`] that gives up after a timeout.
+    ///
+    /// Returns `Ok(None)` if no event is available before the timeout expires.
+    ///
+    /// The event is only read once available, so no event is lost if the
+    /// future is dropped before completing.
+    pub async fn read_edge_event_timeout(&self, timeout: Duration) -> Result<Option<EdgeEvent>> {
+        if readable_timeout(&self.io, &mut || self.req.has_edge_event(), timeout).await? {
+            return self.req.read_edge_event().map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Async form of [`Request::read_edge_events_into_slice`].
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    /// Events are only read from the kernel once available, and the read
+    /// completes without yielding, so no event is lost if the future is dropped.
+    pub async fn read_edge_events_into_slice(&self, buf: &mut [u64]) -> Result<usize> {
+        readable(&self.io, &mut || self.req.has_edge_event()).await?;
+        self.req.read_edge_events_into_slice(buf)
+    }
+
+    /// Async form of [`Request::read_edge_events`].
+    ///
+    /// Reads up to `max` events, as are available, into `events`.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe, so may be used as a branch of a `select!`.
+    /// Events are only read from the kernel once available, and the read
+    /// completes without yielding, so if the future is dropped then no events
+    /// have been read and they remain available to subsequent reads.
+    pub async fn read_edge_events(&self, events: &mut Vec<EdgeEvent>, max: usize) -> Result<usize> {
+        readable(&self.io, &mut || self.req.has_edge_event()).await?;
+        self.req.read_edge_events(events, max)
+    }
+
+    /// Async form of [`Request::new_edge_event_buffer`].
+    ///
+    /// * `capacity` - The number of events that can be buffered in user space.
+    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream<'_, B> {
+        EdgeEventStream {
+            req: self,
+            events: self.req.new_edge_event_buffer(capacity),
+        }
+    }
+
+    /// Async form of [`Request::pulse`].
+    ///
+    /// The line is returned to inactive even if the future is dropped before
+    /// the pulse is complete.
+    pub async fn pulse(&self, offset: Offset, active_duration: Duration) -> Result<()> {
+        let timer = TimerFd::new(true)?;
+        let tio = B::new(timer.as_raw_fd())?;
+        let guard = PulseGuard::new(&self.req, offset)?;
+        if !active_duration.is_zero() {
+            timer.set_oneshot(active_duration)?;
+            expiry(&tio, &timer).await?;
+        }
+        guard.release()
+    }
+
+    /// Async form of [`Request::play_waveform`].
+    ///
+    /// The steps are timed by a timer on the reactor rather than by blocking a thread,
+    /// so no thread is dedicated to the waveform, but each step is subject to the
+    /// latency of the runtime in polling the future.
+    /// This suits low frequency signals, such as PWM dimming of LEDs, in async applications.
+    ///
+    /// Step timing is based on absolute deadlines, so latency in individual steps
+    /// does not accumulate over the waveform.
+    ///
+    /// If the future is dropped before the waveform is complete then the line is
+    /// left at the value of the current step.
+    pub async fn play_waveform(&self, offset: Offset, wf: &Waveform) -> Result<()> {
+        let timer = TimerFd::new(true)?;
+        let tio = B::new(timer.as_raw_fd())?;
+        let mut deadline = monotonic_now();
+        for step in wf.steps() {
+            self.req.set_value(offset, step.value)?;
+            deadline += step.duration;
+            timer.set_deadline(deadline)?;
+            expiry(&tio, &timer).await?;
+        }
+        Ok(())
+    }
+
+    /// Async form of [`Request::edge_events`].
+    pub fn edge_events(&self) -> EdgeEventStream<'_, B> {
+        EdgeEventStream {
+            req: self,
+            events: self.req.edge_events(),
+        }
+    }
+}
+
+impl<B: AsyncIoBackend> AsRef<Request> for AsyncRequest<B> {
+    fn as_ref(&self) -> &Request {
+        &self.req
+    }
+}
+
+// Wait for the timer to expire.
+async fn expiry<B: AsyncIoBackend>(tio: &B, timer: &TimerFd) -> Result<()> {
+    readable(tio, &mut || timer_expired(timer)).await
+}
+
+// Check if the timer has expired, clearing the expiry if so.
+fn timer_expired(timer: &TimerFd) -> Result<bool> {
+    match timer.read() {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Async form of [`EdgeEventBuffer`] in its role as an iterator.
+///
+/// Created by [`AsyncRequest::new_edge_event_stream`] or [`AsyncRequest::edge_events`].
+///
+/// The stream may be filtered and transformed using the [`EdgeEventStreamExt`] adapters.
+///
+/// # Cancel safety
+///
+/// Waiting on the next event from the stream is cancel safe.
+/// Events read from the kernel in a batch are buffered within the stream,
+/// so are returned by subsequent calls even if the future is dropped.
+pub struct EdgeEventStream<'a, B: AsyncIoBackend> {
+    req: &'a AsyncRequest<B>,
+    events: EdgeEventBuffer<'a>,
+}
+
+impl<'a, B: AsyncIoBackend> EdgeEventStream<'a, B> {
+    /// Wait for the next event, giving up after a timeout.
+    ///
+    /// Returns `Ok(None)` if no event is available before the timeout expires.
+    ///
+    /// Events are only read once available, so no event is lost if the
+    /// future is dropped before completing.
+    pub async fn next_event_timeout(&mut self, timeout: Duration) -> Result<Option<EdgeEvent>> {
+        if self.events.is_empty() {
+            let req = self.req;
+            if !readable_timeout(&req.io, &mut || req.req.has_edge_event(), timeout).await? {
+                return Ok(None);
+            }
+        }
+        self.events.read_event().map(Some)
+    }
+}
+
+impl<'a, B: AsyncIoBackend> Stream for EdgeEventStream<'a, B> {
+    type Item = Result<EdgeEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        // return any previously buffered event...
+        if !self.events.is_empty() {
+            return Poll::Ready(Some(self.events.read_event()));
+        }
+        // ... else go to the fd to check for new events
+        let req = &self.req.req;
+        ready!(self.req.io.poll_readable(cx, &mut || req.has_edge_event()))?;
+        Poll::Ready(Some(self.events.read_event()))
+    }
+}
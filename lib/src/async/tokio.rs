@@ -2,125 +2,85 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::chip::Chip;
-use crate::line::{EdgeEvent, InfoChangeEvent};
-use crate::request::{EdgeEventBuffer, Request};
-use crate::Result;
-use futures::ready;
+use crate::line::EdgeEvent;
+use crate::reactor::{self, AsyncIoBackend};
+pub use crate::request::EdgeEventStreamExt;
+use crate::request::{Builder, Request};
+use crate::uapi::Errno;
+use crate::{Error, Result};
 use futures::task::{Context, Poll};
-use std::pin::Pin;
+use futures::{ready, StreamExt};
+use std::io;
+use std::os::unix::prelude::RawFd;
 use tokio::io::unix::AsyncFd;
-use tokio_stream::Stream;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 
-/// Async wrapper around [`Chip`] for the tokio reactor.
+/// The [`AsyncIoBackend`] for the tokio reactor.
+pub struct Backend(AsyncFd<RawFd>);
+
+impl AsyncIoBackend for Backend {
+    fn new(fd: RawFd) -> io::Result<Self> {
+        Ok(Backend(AsyncFd::new(fd)?))
+    }
+
+    fn poll_readable(
+        &self,
+        cx: &mut Context<'_>,
+        is_ready: &mut dyn FnMut() -> Result<bool>,
+    ) -> Poll<Result<()>> {
+        loop {
+            let mut guard = ready!(self.0.poll_read_ready(cx))?;
+            if is_ready()? {
+                return Poll::Ready(Ok(()));
+            }
+            guard.clear_ready();
+        }
+    }
+}
+
+/// Async wrapper around [`Chip`](crate::Chip) for the tokio reactor.
 ///
 /// # Example
 ///  ```no_run
 /// # use gpiocdev::Result;
 /// use gpiocdev::Chip;
 /// use gpiocdev::tokio::AsyncChip;
+/// use tokio_stream::StreamExt;
 ///
 /// # async fn docfn() -> Result<()> {
 /// let chip = Chip::from_path("/dev/gpiochip0")?;
 /// let achip = AsyncChip::new(chip);
 /// let evt = achip.read_line_info_change_event().await?;
+/// let mut events = achip.watch_info_changes(&[3, 5, 8, 13])?;
+/// while let Ok(evt) = events.next().await.unwrap() {
+///     // process event...
+/// }
 /// # Ok(())
 /// # }
 /// ```
-pub struct AsyncChip(AsyncFd<Chip>);
-
-impl AsyncChip {
-    /// Create a Tokio wrapper for a Chip.
-    pub fn new(chip: Chip) -> Self {
-        AsyncChip(AsyncFd::new(chip).unwrap())
-    }
-
-    /// Async form of [`Chip::read_line_info_change_event`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::{Chip, Result};
-    /// use gpiocdev::tokio::AsyncChip;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let chip = Chip::from_path("/dev/gpiochip0")?;
-    /// let achip = AsyncChip::new(chip);
-    /// let evt = achip.read_line_info_change_event().await?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub async fn read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
-        loop {
-            let mut guard = self.0.readable().await?;
-            let chip = self.0.get_ref();
-            if chip.has_line_info_change_event()? {
-                let res = chip.read_line_info_change_event();
-                if !chip.has_line_info_change_event()? {
-                    guard.clear_ready();
-                }
-                return res;
-            }
-        }
-    }
-
-    /// Async form of [`Chip::info_change_events`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::{Chip, Result};
-    /// use gpiocdev::tokio::AsyncChip;
-    /// use tokio_stream::StreamExt;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let chip = Chip::from_path("/dev/gpiochip0")?;
-    /// let achip = AsyncChip::new(chip);
-    /// let mut events = achip.info_change_events();
-    /// while let Ok(evt) = events.next().await.unwrap() {
-    ///     // process event...
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn info_change_events(&self) -> InfoChangeStream {
-        InfoChangeStream { chip: self }
-    }
-}
+pub type AsyncChip = reactor::AsyncChip<Backend>;
 
-impl AsRef<Chip> for AsyncChip {
-    fn as_ref(&self) -> &Chip {
-        self.0.get_ref()
-    }
-}
-
-/// Async form of [`InfoChangeIterator`].
+/// Async form of [`InfoChangeIterator`] for the tokio reactor.
 ///
 /// Created by [`AsyncChip::info_change_events`].
 ///
 /// [`InfoChangeIterator`]: crate::chip::InfoChangeIterator
-pub struct InfoChangeStream<'a> {
-    chip: &'a AsyncChip,
-}
+pub type InfoChangeStream<'a> = reactor::InfoChangeStream<'a, Backend>;
 
-impl<'a> Stream for InfoChangeStream<'a> {
-    type Item = Result<InfoChangeEvent>;
-
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        let mut guard = ready!(self.chip.0.poll_read_ready(cx))?;
-        let res = Poll::Ready(Some(self.chip.as_ref().read_line_info_change_event()));
-        if !self.chip.as_ref().has_line_info_change_event()? {
-            guard.clear_ready();
-        }
-        res
-    }
-}
+/// A stream of info change events that owns the [`AsyncChip`], for the tokio reactor.
+///
+/// Created by [`AsyncChip::info_change_stream`].
+pub type OwnedInfoChangeStream = reactor::OwnedInfoChangeStream<Backend>;
 
-/// Async wrapper around [`Request`] for the tokio reactor.
+/// Async wrapper around [`Request`](crate::Request) for the tokio reactor.
 ///
 /// # Example
 /// ```no_run
 /// # use gpiocdev::Result;
 /// use gpiocdev::Request;
 /// use gpiocdev::tokio::AsyncRequest;
+/// use tokio_stream::StreamExt;
 ///
 /// # async fn docfn() -> Result<()> {
 /// let req = Request::builder()
@@ -131,18 +91,21 @@ impl<'a> Stream for InfoChangeStream<'a> {
 ///    .request()?;
 /// let areq = AsyncRequest::new(req);
 /// let evt = areq.read_edge_event().await?;
+/// let mut events = areq.edge_events();
+/// while let Ok(evt) = events.next().await.unwrap() {
+///     // process event...
+/// }
 /// # Ok(())
 /// # }
 /// ```
-pub struct AsyncRequest(AsyncFd<Request>);
+pub type AsyncRequest = reactor::AsyncRequest<Backend>;
 
 impl AsyncRequest {
-    /// Create a Tokio wrapper for a Request.
-    pub fn new(req: Request) -> Self {
-        AsyncRequest(AsyncFd::new(req).unwrap())
-    }
-
-    /// Async form of [`Request::read_edge_event`].
+    /// Perform the request described by the builder without blocking the reactor.
+    ///
+    /// Opening the chip and requesting the lines may block on some drivers,
+    /// so these are performed on a blocking thread, via [`tokio::task::spawn_blocking`], and the
+    /// resulting request wrapped for the tokio reactor.
     ///
     /// # Example
     /// ```no_run
@@ -151,160 +114,140 @@ impl AsyncRequest {
     /// use gpiocdev::tokio::AsyncRequest;
     ///
     /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let areq = AsyncRequest::new(req);
+    /// let areq = AsyncRequest::request(
+    ///     Request::builder()
+    ///         .on_chip("/dev/gpiochip0")
+    ///         .with_line(42)
+    ///         .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges),
+    /// )
+    /// .await?;
     /// let evt = areq.read_edge_event().await?;
-    /// // process event...
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn read_edge_event(&self) -> Result<EdgeEvent> {
-        loop {
-            let mut guard = self.0.readable().await?;
-            let req = self.0.get_ref();
-            if req.has_edge_event()? {
-                let res = req.read_edge_event();
-                if !req.has_edge_event()? {
-                    guard.clear_ready();
-                }
-                return res;
-            }
-        }
+    pub async fn request(builder: &Builder) -> Result<Self> {
+        let mut builder = builder.clone();
+        let req = match tokio::task::spawn_blocking(move || builder.request()).await {
+            Ok(res) => res?,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            // the blocking task is only cancelled if the runtime is shutting down
+            Err(_) => return Err(Error::Os(Errno(libc::ECANCELED))),
+        };
+        Ok(AsyncRequest::new(req))
     }
+}
 
-    /// Async form of [`Request::read_edge_events_into_slice`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// use gpiocdev::Request;
-    /// use gpiocdev::tokio::AsyncRequest;
-    ///
-    /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let mut buf = vec![0_u64; req.edge_event_u64_size() * 3];
-    /// let areq = AsyncRequest::new(req);
-    /// let num_evts = areq.read_edge_events_into_slice(&mut buf).await?;
-    /// // process events in buf...
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub async fn read_edge_events_into_slice(&self, buf: &mut [u64]) -> Result<usize> {
-        loop {
-            let mut guard = self.0.readable().await?;
-            let req = self.0.get_ref();
-            if req.has_edge_event()? {
-                let res = req.read_edge_events_into_slice(buf);
-                if !req.has_edge_event()? {
-                    guard.clear_ready();
-                }
-                return res;
-            }
-        }
-    }
+/// Async form of [`EdgeEventBuffer`] in its role as an iterator, for the tokio reactor.
+///
+/// Created by [`AsyncRequest::new_edge_event_stream`] or [`AsyncRequest::edge_events`].
+///
+/// The stream may be filtered and transformed using the [`EdgeEventStreamExt`] adapters.
+///
+/// [`EdgeEventBuffer`]: crate::request::EdgeEventBuffer
+pub type EdgeEventStream<'a> = reactor::EdgeEventStream<'a, Backend>;
+
+/// Broadcasts the edge events from a [`Request`](crate::Request) to multiple subscribers.
+///
+/// The edge events are read from the request by a task, and sent to a
+/// [`tokio::sync::broadcast`] channel, so multiple tasks may receive the events
+/// without coordinating ownership of the request.
+///
+/// Each subscriber buffers up to the configured capacity of events.
+/// If a subscriber falls further behind then the oldest events are dropped,
+/// and the next receive reports the number of events missed as
+/// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+///
+/// The task is aborted when the `EdgeEventBroadcast` is dropped.
+///
+/// # Example
+/// ```no_run
+/// # use gpiocdev::Result;
+/// use gpiocdev::line::EdgeDetection;
+/// use gpiocdev::tokio::EdgeEventBroadcast;
+/// use gpiocdev::Request;
+/// use tokio::sync::broadcast::error::RecvError;
+///
+/// # async fn docfn() -> Result<()> {
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_line(42)
+///     .with_edge_detection(EdgeDetection::BothEdges)
+///     .request()?;
+/// let bcast = EdgeEventBroadcast::new(req, 64);
+/// let mut events = bcast.subscribe();
+/// tokio::spawn(async move {
+///     loop {
+///         match events.recv().await {
+///             Ok(evt) => println!("{:?}", evt),
+///             Err(RecvError::Lagged(missed)) => println!("missed {} events", missed),
+///             Err(RecvError::Closed) => break,
+///         }
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+pub struct EdgeEventBroadcast {
+    tx: broadcast::Sender<EdgeEvent>,
+    task: Option<JoinHandle<Result<()>>>,
+}
 
-    /// Async form of [`Request::new_edge_event_buffer`].
+impl EdgeEventBroadcast {
+    /// Start broadcasting the edge events from the request.
     ///
-    /// * `capacity` - The number of events that can be buffered in user space.
+    /// * `capacity` - The number of events buffered for each subscriber.
     ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// use gpiocdev::Request;
-    /// use gpiocdev::tokio::AsyncRequest;
-    /// use tokio_stream::StreamExt;
+    /// # Panics
     ///
-    /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let areq = AsyncRequest::new(req);
-    /// let mut events = areq.new_edge_event_stream(2);
-    /// while let Ok(evt) = events.next().await.unwrap() {
-    ///     // process event...
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream {
-        EdgeEventStream {
-            req: self,
-            events: self.0.get_ref().new_edge_event_buffer(capacity),
+    /// Panics if `capacity` is zero, or if not called within a tokio runtime.
+    pub fn new(req: Request, capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        let task_tx = tx.clone();
+        let task = tokio::spawn(async move {
+            let areq = AsyncRequest::new(req);
+            let mut events = areq.edge_events();
+            while let Some(evt) = events.next().await {
+                // no subscribers is not an error - the event is simply dropped.
+                let _ = task_tx.send(evt?);
+            }
+            Ok(())
+        });
+        EdgeEventBroadcast {
+            tx,
+            task: Some(task),
         }
     }
-    /// Async form of [`Request::edge_events`].
-    ///
-    /// # Example
-    /// ```no_run
-    /// # use gpiocdev::Result;
-    /// use gpiocdev::Request;
-    /// use gpiocdev::tokio::AsyncRequest;
-    /// use tokio_stream::StreamExt;
+
+    /// Create a receiver for the edge events.
     ///
-    /// # async fn docfn() -> Result<()> {
-    /// let req = Request::builder()
-    ///    .on_chip("/dev/gpiochip0")
-    ///    .with_line(42)
-    ///    .as_input()
-    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
-    ///    .request()?;
-    /// let areq = AsyncRequest::new(req);
-    /// let mut events = areq.edge_events();
-    /// while let Ok(evt) = events.next().await.unwrap() {
-    ///     // process event...
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn edge_events(&self) -> EdgeEventStream {
-        EdgeEventStream {
-            req: self,
-            events: self.0.get_ref().edge_events(),
-        }
+    /// The receiver only receives events read after it is created.
+    pub fn subscribe(&self) -> broadcast::Receiver<EdgeEvent> {
+        self.tx.subscribe()
     }
-}
 
-impl AsRef<Request> for AsyncRequest {
-    fn as_ref(&self) -> &Request {
-        self.0.get_ref()
+    /// The number of active subscribers.
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count()
     }
-}
 
-/// Async form of [`EdgeEventBuffer`] in its role as an iterator.
-///
-/// Created by [`AsyncRequest::new_edge_event_stream`] or [`AsyncRequest::edge_events`].
-pub struct EdgeEventStream<'a> {
-    req: &'a AsyncRequest,
-    events: EdgeEventBuffer<'a>,
+    /// Wait for the broadcast to stop.
+    ///
+    /// The broadcast only stops if reading events from the request fails,
+    /// so this returns the error that stopped it.
+    pub async fn join(mut self) -> Result<()> {
+        let task = self.task.take().unwrap();
+        match task.await {
+            Ok(res) => res,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Ok(()),
+        }
+    }
 }
 
-impl<'a> Stream for EdgeEventStream<'a> {
-    type Item = Result<EdgeEvent>;
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        // return any previously buffered event...
-        if !self.events.is_empty() {
-            return Poll::Ready(Some(self.events.read_event()));
-        }
-        // ... else go to the fd to check for new events
-        let mut guard = ready!(self.req.0.poll_read_ready(cx))?;
-        let res = Poll::Ready(Some(self.events.read_event()));
-        if !self.req.0.get_ref().has_edge_event()? {
-            guard.clear_ready();
+impl Drop for EdgeEventBroadcast {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
         }
-        res
     }
 }
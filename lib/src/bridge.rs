@@ -0,0 +1,8 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Bridges exposing requested lines via other kernel subsystems.
+
+#[cfg(feature = "uinput")]
+pub mod uinput;
@@ -0,0 +1,527 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Exposing input lines as a Linux input device.
+//!
+//! A [`Uinput`] bridge creates an input device via `/dev/uinput` and reports
+//! changes to the lines of a [`Request`] as key or switch events, much as the
+//! kernel `gpio-keys` driver does, but without requiring a device tree overlay.
+//!
+//! The lines are requested as usual, with edge detection, and any bias, active
+//! low or debounce settings required.
+//! An active line reports the key as pressed, or the switch as on.
+//!
+//! The codes are those defined in the kernel `input-event-codes.h`,
+//! e.g. `KEY_ENTER` is 28 and `SW_LID` is 0.
+//!
+//! Keys may also report a long press, with a separate code, if held for
+//! longer than a given period.
+//! A short press of such a key is reported as a press and release once the key is
+//! released, and a long press as a press once the period expires and a release when
+//! the key is released.
+//!
+//! The mapping may also be loaded from a [`KeyMap`] file, allowing the bridge to be
+//! configured without code changes.
+//!
+//! Creating the device requires write access to `/dev/uinput`.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! use gpiocdev::bridge::uinput::Uinput;
+//! use gpiocdev::line::{Bias, EdgeDetection};
+//! use gpiocdev::Request;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<()> {
+//! let req = Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[5, 6])
+//!     .as_active_low()
+//!     .with_bias(Bias::PullUp)
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .with_debounce_period(Duration::from_millis(5))
+//!     .request()?;
+//! let mut keys = Uinput::builder()
+//!     .with_name("gpio-buttons")
+//!     .with_key(5, 28) // KEY_ENTER
+//!     .with_key(6, 1) // KEY_ESC
+//!     .with_long_press(6, 116, Duration::from_secs(2)) // KEY_POWER
+//!     .build(req)?;
+//! keys.run()
+//! # }
+//! ```
+
+use crate::line::{EdgeEvent, EdgeKind, Offset, Value};
+use crate::timerfd::monotonic_now;
+use crate::{Error, Request, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::prelude::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+mod keymap;
+pub use self::keymap::{KeyMap, KeySpec};
+
+// From linux/uinput.h
+const UINPUT_IOCTL_BASE: u8 = b'U';
+const UI_DEV_CREATE: libc::c_ulong = ioctl_sys::io!(UINPUT_IOCTL_BASE, 1) as libc::c_ulong;
+const UI_DEV_DESTROY: libc::c_ulong = ioctl_sys::io!(UINPUT_IOCTL_BASE, 2) as libc::c_ulong;
+const UI_DEV_SETUP: libc::c_ulong =
+    ioctl_sys::iow!(UINPUT_IOCTL_BASE, 3, mem::size_of::<libc::uinput_setup>()) as libc::c_ulong;
+const UI_SET_EVBIT: libc::c_ulong =
+    ioctl_sys::iow!(UINPUT_IOCTL_BASE, 100, mem::size_of::<libc::c_int>()) as libc::c_ulong;
+const UI_SET_KEYBIT: libc::c_ulong =
+    ioctl_sys::iow!(UINPUT_IOCTL_BASE, 101, mem::size_of::<libc::c_int>()) as libc::c_ulong;
+const UI_SET_SWBIT: libc::c_ulong =
+    ioctl_sys::iow!(UINPUT_IOCTL_BASE, 109, mem::size_of::<libc::c_int>()) as libc::c_ulong;
+
+// From linux/input.h and linux/input-event-codes.h
+const BUS_HOST: u16 = 0x19;
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_SW: u16 = 0x05;
+const SYN_REPORT: u16 = 0;
+const KEY_MAX: u16 = 0x2ff;
+const SW_MAX: u16 = 0x10;
+const UINPUT_MAX_NAME_SIZE: usize = 80;
+
+/// The input event reported for a line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Code {
+    /// A key or button, reported as an `EV_KEY` event with the given code.
+    Key(u16),
+
+    /// A switch, reported as an `EV_SW` event with the given code.
+    Switch(u16),
+}
+
+impl Code {
+    fn event_type(&self) -> u16 {
+        match self {
+            Code::Key(_) => EV_KEY,
+            Code::Switch(_) => EV_SW,
+        }
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            Code::Key(c) | Code::Switch(c) => *c,
+        }
+    }
+}
+
+/// The key reported when a key is held for longer than a period.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LongPress {
+    /// The key code reported for the long press.
+    pub code: u16,
+
+    /// The period the key must be held to be a long press.
+    pub period: Duration,
+}
+
+/// A builder of [`Uinput`] bridges.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Builder {
+    path: PathBuf,
+    name: String,
+    vendor: u16,
+    product: u16,
+    codes: HashMap<Offset, Code>,
+    long_presses: HashMap<Offset, LongPress>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder {
+            path: PathBuf::from("/dev/uinput"),
+            name: "gpiocdev-uinput".to_string(),
+            vendor: 0,
+            product: 0,
+            codes: HashMap::new(),
+            long_presses: HashMap::new(),
+        }
+    }
+}
+
+impl Builder {
+    /// Set the path of the uinput device.
+    ///
+    /// The default is `/dev/uinput`.
+    pub fn on_device<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.path = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Set the name of the input device.
+    ///
+    /// The default is `gpiocdev-uinput`.
+    pub fn with_name(&mut self, name: &str) -> &mut Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the vendor and product IDs of the input device.
+    ///
+    /// The default is zero for both.
+    pub fn with_id(&mut self, vendor: u16, product: u16) -> &mut Self {
+        self.vendor = vendor;
+        self.product = product;
+        self
+    }
+
+    /// Report the line as a key with the given code.
+    ///
+    /// Replaces any code previously set for the line.
+    pub fn with_key(&mut self, offset: Offset, code: u16) -> &mut Self {
+        self.codes.insert(offset, Code::Key(code));
+        self
+    }
+
+    /// Report the line as a switch with the given code.
+    ///
+    /// Replaces any code previously set for the line.
+    pub fn with_switch(&mut self, offset: Offset, code: u16) -> &mut Self {
+        self.codes.insert(offset, Code::Switch(code));
+        self
+    }
+
+    /// Report a long press of the key on the line with the given code.
+    ///
+    /// The line must also be mapped to a key using [`with_key`].
+    ///
+    /// [`with_key`]: Builder::with_key
+    pub fn with_long_press(&mut self, offset: Offset, code: u16, period: Duration) -> &mut Self {
+        self.long_presses.insert(offset, LongPress { code, period });
+        self
+    }
+
+    /// Create the input device and bridge the lines of the request to it.
+    ///
+    /// The current state of the lines is reported when the device is created.
+    pub fn build(&mut self, req: Request) -> Result<Uinput> {
+        if self.codes.is_empty() {
+            return Err(Error::InvalidArgument("No lines mapped.".to_string()));
+        }
+        if self.name.len() >= UINPUT_MAX_NAME_SIZE {
+            return Err(Error::InvalidArgument(
+                "Device name is too long.".to_string(),
+            ));
+        }
+        for (&offset, code) in self.codes.iter() {
+            match req.line_config(offset) {
+                None => {
+                    return Err(Error::InvalidArgument(format!(
+                        "Line {} is not in the request.",
+                        offset
+                    )))
+                }
+                Some(lc) if lc.edge_detection.is_none() => {
+                    return Err(Error::InvalidArgument(format!(
+                        "Line {} does not have edge detection enabled.",
+                        offset
+                    )))
+                }
+                _ => {}
+            }
+            let max = match code {
+                Code::Key(_) => KEY_MAX,
+                Code::Switch(_) => SW_MAX,
+            };
+            if code.code() > max {
+                return Err(Error::InvalidArgument(format!(
+                    "Code {} for line {} is out of range.",
+                    code.code(),
+                    offset
+                )));
+            }
+        }
+        for (&offset, lp) in self.long_presses.iter() {
+            match self.codes.get(&offset) {
+                Some(Code::Key(_)) => {}
+                _ => {
+                    return Err(Error::InvalidArgument(format!(
+                        "Long press for line {} requires a key.",
+                        offset
+                    )))
+                }
+            }
+            if lp.code > KEY_MAX {
+                return Err(Error::InvalidArgument(format!(
+                    "Code {} for line {} is out of range.",
+                    lp.code, offset
+                )));
+            }
+        }
+        let dev = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(&self.path)?;
+        self.setup(&dev)?;
+        let mut bridge = Uinput {
+            req,
+            dev,
+            codes: self.codes.clone(),
+            long_presses: self.long_presses.clone(),
+            held: HashMap::new(),
+        };
+        bridge.report_current()?;
+        Ok(bridge)
+    }
+
+    fn setup(&self, dev: &File) -> io::Result<()> {
+        let fd = dev.as_raw_fd();
+        let mut types: Vec<u16> = self.codes.values().map(|c| c.event_type()).collect();
+        types.sort_unstable();
+        types.dedup();
+        for t in types {
+            ioctl_int(fd, UI_SET_EVBIT, t)?;
+        }
+        for code in self.codes.values() {
+            let req = match code {
+                Code::Key(_) => UI_SET_KEYBIT,
+                Code::Switch(_) => UI_SET_SWBIT,
+            };
+            ioctl_int(fd, req, code.code())?;
+        }
+        for lp in self.long_presses.values() {
+            ioctl_int(fd, UI_SET_KEYBIT, lp.code)?;
+        }
+        // SAFETY: uinput_setup is a plain C struct, so zeroed is a valid value.
+        let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
+        setup.id.bustype = BUS_HOST;
+        setup.id.vendor = self.vendor;
+        setup.id.product = self.product;
+        setup.id.version = 1;
+        for (dst, src) in setup.name.iter_mut().zip(self.name.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        // SAFETY: setup is a valid uinput_setup that outlives the call.
+        if unsafe { libc::ioctl(fd, UI_DEV_SETUP as _, &setup) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: no pointers involved.
+        if unsafe { libc::ioctl(fd, UI_DEV_CREATE as _) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+fn ioctl_int(fd: libc::c_int, req: libc::c_ulong, arg: u16) -> io::Result<()> {
+    // SAFETY: the argument is passed by value so no pointers involved.
+    if unsafe { libc::ioctl(fd, req as _, arg as libc::c_int) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// A bridge reporting changes to requested lines as input events.
+///
+/// The input device is destroyed when the bridge is dropped.
+#[derive(Debug)]
+pub struct Uinput {
+    req: Request,
+    dev: File,
+    codes: HashMap<Offset, Code>,
+    long_presses: HashMap<Offset, LongPress>,
+    // The keys with a long press that are currently held.
+    held: HashMap<Offset, Held>,
+}
+
+// The state of a held key with a long press.
+#[derive(Debug)]
+struct Held {
+    // The monotonic time the key was pressed.
+    since: Duration,
+    // The long press has been reported.
+    long: bool,
+}
+
+impl Uinput {
+    /// Start building a bridge.
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// The request providing the lines.
+    ///
+    /// Which may be used to wait on edge events, e.g. in an event loop, before
+    /// calling [`process_events`].
+    ///
+    /// [`process_events`]: Uinput::process_events
+    pub fn request(&self) -> &Request {
+        &self.req
+    }
+
+    /// The code reported for a line, if any.
+    pub fn code(&self, offset: Offset) -> Option<Code> {
+        self.codes.get(&offset).copied()
+    }
+
+    /// Report the edge event as an input event.
+    ///
+    /// Events for lines without a code are ignored.
+    pub fn handle_event(&mut self, evt: &EdgeEvent) -> Result<()> {
+        self.handle_change(evt.offset, evt.kind == EdgeKind::Rising)?;
+        Ok(())
+    }
+
+    /// Report any edge events available from the request, and any long presses
+    /// that have expired, without blocking.
+    ///
+    /// Returns the number of events processed.
+    pub fn process_events(&mut self) -> Result<usize> {
+        let mut count = 0;
+        while self.req.has_edge_event()? {
+            let evt = self.req.read_edge_event()?;
+            self.handle_event(&evt)?;
+            count += 1;
+        }
+        self.process_timeouts()?;
+        Ok(count)
+    }
+
+    /// The time until the next long press expires, if any keys with a long press
+    /// are held.
+    ///
+    /// [`process_timeouts`] should be called when it expires.
+    ///
+    /// [`process_timeouts`]: Uinput::process_timeouts
+    pub fn next_timeout(&self) -> Option<Duration> {
+        let now = monotonic_now();
+        self.held
+            .iter()
+            .filter(|(_, h)| !h.long)
+            .map(|(offset, h)| (h.since + self.long_presses[offset].period).saturating_sub(now))
+            .min()
+    }
+
+    /// Report any long presses that have expired.
+    pub fn process_timeouts(&mut self) -> Result<()> {
+        let now = monotonic_now();
+        for (offset, h) in self.held.iter_mut() {
+            let lp = self.long_presses[offset];
+            if !h.long && now >= h.since + lp.period {
+                h.long = true;
+                emit(&self.dev, Code::Key(lp.code), 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Report edge events from the request, and long presses, until an error occurs.
+    ///
+    /// Blocks waiting for events, so is typically run in a dedicated thread.
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            let ready = match self.next_timeout() {
+                Some(timeout) => self.req.wait_edge_event(timeout)?,
+                None => true,
+            };
+            if ready {
+                // blocks if no long press is pending
+                let evt = self.req.read_edge_event()?;
+                self.handle_event(&evt)?;
+            }
+            self.process_timeouts()?;
+        }
+    }
+
+    fn handle_change(&mut self, offset: Offset, active: bool) -> io::Result<()> {
+        let code = match self.codes.get(&offset) {
+            Some(code) => *code,
+            None => return Ok(()),
+        };
+        let lp = match self.long_presses.get(&offset) {
+            Some(lp) => *lp,
+            None => return emit(&self.dev, code, active as i32),
+        };
+        if active {
+            self.held.entry(offset).or_insert(Held {
+                since: monotonic_now(),
+                long: false,
+            });
+            return Ok(());
+        }
+        match self.held.remove(&offset) {
+            Some(Held { long: true, .. }) => emit(&self.dev, Code::Key(lp.code), 0),
+            Some(_) => {
+                emit(&self.dev, code, 1)?;
+                emit(&self.dev, code, 0)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn report_current(&mut self) -> Result<()> {
+        let offsets: Vec<Offset> = self.codes.keys().copied().collect();
+        for offset in offsets {
+            let active = self.req.value(offset)? == Value::Active;
+            // a held key with a long press is only reported once released, or the
+            // long press expires, so an inactive key is not reported.
+            if active || !self.long_presses.contains_key(&offset) {
+                self.handle_change(offset, active)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Report the value of the code, and sync.
+fn emit(dev: &File, code: Code, value: i32) -> io::Result<()> {
+    let events = [
+        input_event(code.event_type(), code.code(), value),
+        input_event(EV_SYN, SYN_REPORT, 0),
+    ];
+    // SAFETY: input_event is a plain C struct so may be viewed as bytes.
+    let buf = unsafe {
+        std::slice::from_raw_parts(events.as_ptr() as *const u8, mem::size_of_val(&events))
+    };
+    let mut dev = dev;
+    dev.write_all(buf)
+}
+
+impl Drop for Uinput {
+    fn drop(&mut self) {
+        // SAFETY: no pointers involved.
+        // The device is also destroyed when the file is closed, so errors are ignored.
+        unsafe { libc::ioctl(self.dev.as_raw_fd(), UI_DEV_DESTROY as _) };
+    }
+}
+
+// An input event, leaving the kernel to fill in the timestamp.
+fn input_event(type_: u16, code: u16, value: i32) -> libc::input_event {
+    libc::input_event {
+        time: libc::timeval {
+            tv_sec: 0,
+            tv_usec: 0,
+        },
+        type_,
+        code,
+        value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // values as per the kernel uinput.h on x86_64
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn request_codes() {
+        assert_eq!(UI_DEV_CREATE, 0x5501);
+        assert_eq!(UI_DEV_DESTROY, 0x5502);
+        assert_eq!(UI_DEV_SETUP, 0x405c_5503);
+        assert_eq!(UI_SET_EVBIT, 0x4004_5564);
+        assert_eq!(UI_SET_KEYBIT, 0x4004_5565);
+        assert_eq!(UI_SET_SWBIT, 0x4004_556d);
+    }
+}
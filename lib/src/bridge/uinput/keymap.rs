@@ -0,0 +1,382 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{Code, LongPress, Uinput};
+use crate::line::{self, parse_period, EdgeDetection, LineRef};
+use crate::request::Config;
+use crate::{Error, Request, Result};
+use serde_derive::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The mapping of a line to an input event, as loaded from a [`KeyMap`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeySpec {
+    /// The line reported.
+    pub line: LineRef,
+
+    /// The input event reported for the line.
+    pub code: Code,
+
+    /// The configuration of the line, such as active low, bias and debounce.
+    ///
+    /// The line is always requested as an input with edge detection on both edges.
+    pub config: line::Config,
+
+    /// The long press reported for a key, if any.
+    pub long_press: Option<LongPress>,
+}
+
+/// A mapping of lines to input events, typically loaded from a file.
+///
+/// The file is TOML, with optional device settings followed by a table for
+/// each key or switch:
+///
+/// ```text
+/// name = "gpio-buttons"   # the input device name, and request consumer
+/// vendor = 1
+/// product = 2
+///
+/// [[key]]
+/// line = "BUTTON_A"       # the line name, or <chip>:<offset>
+/// code = 28               # KEY_ENTER
+/// config = "active-low,pull-up,debounce=5ms"
+/// long_press_code = 116   # KEY_POWER
+/// long_press = "2s"
+///
+/// [[switch]]
+/// line = "gpiochip0:7"
+/// code = 0                # SW_LID
+/// ```
+///
+/// The `config` is a line configuration, as parsed by [`line::Config`].
+/// Periods are taken as milliseconds unless suffixed with `s`, `ms`, `us` or `ns`.
+///
+/// The lines must all be on the same chip.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::Result;
+/// use gpiocdev::bridge::uinput::KeyMap;
+///
+/// # fn main() -> Result<()> {
+/// let mut keys = KeyMap::load("/etc/gpio-keys.toml")?.build()?;
+/// keys.run()
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeyMap {
+    /// The name of the input device, and the consumer of the requested lines.
+    pub name: Option<String>,
+
+    /// The vendor ID of the input device.
+    pub vendor: u16,
+
+    /// The product ID of the input device.
+    pub product: u16,
+
+    /// The lines mapped to input events.
+    pub keys: Vec<KeySpec>,
+}
+
+impl KeyMap {
+    /// Load a key map from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<KeyMap> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Request the lines and create the input device described by the key map.
+    pub fn build(&self) -> Result<Uinput> {
+        let mut cfg = Config::default();
+        let mut chip = None;
+        let mut builder = Uinput::builder();
+        for spec in self.keys.iter() {
+            let found = spec.line.resolve()?;
+            match &chip {
+                None => {
+                    cfg.on_chip(&found.chip);
+                    chip = Some(found.chip.clone());
+                }
+                Some(c) if *c != found.chip => {
+                    return Err(Error::InvalidArgument(format!(
+                        "Line {} is not on the same chip as the other lines.",
+                        spec.line
+                    )))
+                }
+                _ => {}
+            }
+            let offset = found.info.offset;
+            if cfg.lines().contains(&offset) {
+                return Err(Error::InvalidArgument(format!(
+                    "Line {} is mapped more than once.",
+                    spec.line
+                )));
+            }
+            cfg.with_line(offset)
+                .from_line_config(&spec.config)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges);
+            match spec.code {
+                Code::Key(code) => builder.with_key(offset, code),
+                Code::Switch(code) => builder.with_switch(offset, code),
+            };
+            if let Some(lp) = spec.long_press {
+                builder.with_long_press(offset, lp.code, lp.period);
+            }
+        }
+        if chip.is_none() {
+            return Err(Error::InvalidArgument("No lines mapped.".to_string()));
+        }
+        let mut req = Request::from_config(cfg);
+        if let Some(name) = &self.name {
+            builder.with_name(name);
+            req.with_consumer(name.as_str());
+        }
+        builder
+            .with_id(self.vendor, self.product)
+            .build(req.request()?)
+    }
+}
+
+impl FromStr for KeyMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let file: KeyMapFile =
+            toml::from_str(s).map_err(|e| Error::InvalidArgument(format!("{}.", e)))?;
+        let mut map = KeyMap {
+            name: file.name,
+            vendor: file.vendor,
+            product: file.product,
+            keys: Vec::with_capacity(file.key.len() + file.switch.len()),
+        };
+        for (idx, entry) in file.key.into_iter().enumerate() {
+            let invalid = |msg: String| invalid_entry("key", idx, msg);
+            let long_press = match (entry.long_press_code, entry.long_press) {
+                (Some(code), Some(period)) => Some(LongPress {
+                    code,
+                    period: period.to_duration().map_err(invalid)?,
+                }),
+                (None, None) => None,
+                _ => {
+                    return Err(invalid(
+                        "long_press_code and long_press must be specified together".to_string(),
+                    ))
+                }
+            };
+            map.keys.push(
+                key_spec(
+                    &entry.line,
+                    &entry.config,
+                    Code::Key(entry.code),
+                    long_press,
+                )
+                .map_err(invalid)?,
+            );
+        }
+        for (idx, entry) in file.switch.into_iter().enumerate() {
+            map.keys.push(
+                key_spec(&entry.line, &entry.config, Code::Switch(entry.code), None)
+                    .map_err(|msg| invalid_entry("switch", idx, msg))?,
+            );
+        }
+        Ok(map)
+    }
+}
+
+// The layout of a key map file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyMapFile {
+    name: Option<String>,
+    #[serde(default)]
+    vendor: u16,
+    #[serde(default)]
+    product: u16,
+    #[serde(default)]
+    key: Vec<KeyEntry>,
+    #[serde(default)]
+    switch: Vec<SwitchEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyEntry {
+    line: String,
+    code: u16,
+    config: Option<String>,
+    long_press_code: Option<u16>,
+    long_press: Option<Period>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SwitchEntry {
+    line: String,
+    code: u16,
+    config: Option<String>,
+}
+
+fn key_spec(
+    line: &str,
+    config: &Option<String>,
+    code: Code,
+    long_press: Option<LongPress>,
+) -> std::result::Result<KeySpec, String> {
+    let config = match config {
+        Some(config) => config.parse().map_err(message)?,
+        None => line::Config::default(),
+    };
+    Ok(KeySpec {
+        line: line.parse().map_err(message)?,
+        code,
+        config,
+        long_press,
+    })
+}
+
+// A period, either in milliseconds or as a string with units.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Period {
+    Millis(u64),
+    Str(String),
+}
+
+impl Period {
+    fn to_duration(&self) -> std::result::Result<Duration, String> {
+        match self {
+            Period::Millis(ms) => Ok(Duration::from_millis(*ms)),
+            Period::Str(s) => parse_period(s).map_err(message),
+        }
+    }
+}
+
+// An error for the entry at idx in the named table array.
+fn invalid_entry(table: &str, idx: usize, msg: String) -> Error {
+    Error::InvalidArgument(format!("{} {}: {}.", table, idx + 1, msg))
+}
+
+// The message from an error, without the trailing period.
+fn message(e: Error) -> String {
+    e.to_string().trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::Bias;
+
+    #[test]
+    fn parse() {
+        let km: KeyMap = r#"
+            # buttons
+            name = "gpio-buttons"
+            vendor = 1
+            product = 2
+
+            [[key]]
+            line = "BUTTON_A"
+            code = 28 # KEY_ENTER
+            config = "active-low,pull-up,debounce=5ms"
+            long_press_code = 116
+            long_press = "2s"
+
+            [[switch]]
+            line = 'gpiochip0:7'
+            code = 0
+            "#
+        .parse()
+        .unwrap();
+        assert_eq!(km.name.as_deref(), Some("gpio-buttons"));
+        assert_eq!(km.vendor, 1);
+        assert_eq!(km.product, 2);
+        assert_eq!(km.keys.len(), 2);
+        let key = &km.keys[0];
+        assert_eq!(key.line, LineRef::Name("BUTTON_A".to_string()));
+        assert_eq!(key.code, Code::Key(28));
+        assert!(key.config.active_low);
+        assert_eq!(key.config.bias, Some(Bias::PullUp));
+        assert_eq!(key.config.debounce_period, Some(Duration::from_millis(5)));
+        assert_eq!(
+            key.long_press,
+            Some(LongPress {
+                code: 116,
+                period: Duration::from_secs(2)
+            })
+        );
+        let sw = &km.keys[1];
+        assert_eq!(sw.line, LineRef::Offset("gpiochip0".to_string(), 7));
+        assert_eq!(sw.code, Code::Switch(0));
+        assert_eq!(sw.config, line::Config::default());
+        assert_eq!(sw.long_press, None);
+
+        // period in milliseconds
+        let km: KeyMap = "[[key]]\nline = \"A\"\ncode = 1\nlong_press_code = 2\nlong_press = 800"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            km.keys[0].long_press,
+            Some(LongPress {
+                code: 2,
+                period: Duration::from_millis(800)
+            })
+        );
+
+        let km: KeyMap = "".parse().unwrap();
+        assert_eq!(km, KeyMap::default());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let errs = [
+            (
+                "code = 1",
+                "unknown field `code`, expected one of `name`, `vendor`, `product`, `key`, `switch` at line 1 column 1.",
+            ),
+            (
+                "name = 1",
+                "invalid type: integer `1`, expected a string for key `name` at line 1 column 8.",
+            ),
+            (
+                "vendor = 65536",
+                "invalid value: integer `65536`, expected u16 for key `vendor` at line 1 column 10.",
+            ),
+            (
+                "[[key]]\ncode = 1",
+                "missing field `line` for key `key` at line 1 column 1.",
+            ),
+            (
+                "[[key]]\nline = \"A\"",
+                "missing field `code` for key `key` at line 1 column 1.",
+            ),
+            (
+                "[[key]]\nline = \"A\"\ncode = 1\nconfig = \"sideways\"",
+                "key 1: unknown line attribute 'sideways'.",
+            ),
+            (
+                "[[key]]\nline = \"A\"\ncode = 1\nlong_press = \"2s\"",
+                "key 1: long_press_code and long_press must be specified together.",
+            ),
+            (
+                "[[key]]\nline = \"A\"\ncode = 1\nlong_press_code = 2\nlong_press = \"2x\"",
+                "key 1: invalid period '2x'.",
+            ),
+            (
+                "[[switch]]\nline = \"A\"\ncode = 1\nlong_press_code = 2",
+                "unknown field `long_press_code`, expected one of `line`, `code`, `config` for key `switch` at line 1 column 1.",
+            ),
+            (
+                "[[key]]\nline = \"A",
+                "unterminated string at line 2 column 8.",
+            ),
+        ];
+        for (s, msg) in errs {
+            let res: Result<KeyMap> = s.parse();
+            assert_eq!(res.unwrap_err().to_string(), msg, "{}", s);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Opening many chips and requests at once.
+//!
+//! Each open chip and each request consumes a file descriptor, so applications
+//! monitoring many lines across many chips, such as banks of I/O expanders, can
+//! exceed the process file descriptor limit (`RLIMIT_NOFILE`).
+//! When that happens partway through setup the resulting `EMFILE` errors are
+//! confusing, and leave the application with some lines requested and others not.
+//!
+//! An [`Opener`] determines the number of file descriptors required to open a set of
+//! chips and requests, checks that against the limit before opening any of them,
+//! and optionally raises the soft limit to accommodate them.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::bulk::Opener;
+//! # use gpiocdev::line::EdgeDetection;
+//! # fn main() -> Result<()> {
+//! let mut opener = Opener::new();
+//! for chip in gpiocdev::chip::chips()? {
+//!     let mut builder = gpiocdev::Request::builder();
+//!     builder
+//!         .on_chip(&chip)
+//!         .with_lines(&[0, 1, 2, 3])
+//!         .with_edge_detection(EdgeDetection::BothEdges);
+//!     opener.with_request(&builder);
+//! }
+//! println!("requires {} file descriptors", opener.fds_required());
+//! let opened = opener.with_raised_limit(true).open()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::request::Builder;
+use crate::{Chip, Error, Request, Result};
+use std::path::{Path, PathBuf};
+
+/// The file descriptor limits of the process, and the number in use.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FdLimit {
+    /// The soft limit on the number of open file descriptors.
+    pub soft: u64,
+
+    /// The hard limit, the ceiling to which the soft limit may be raised
+    /// without privileges.
+    pub hard: u64,
+
+    /// The number of file descriptors currently open.
+    pub in_use: u64,
+}
+
+impl FdLimit {
+    /// Get the current limits and usage for the process.
+    // rlim_t is not 64-bit on all platforms
+    #[allow(clippy::unnecessary_cast)]
+    pub fn current() -> Result<FdLimit> {
+        let mut rlim = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: rlim is a valid rlimit.
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlim) } < 0 {
+            return Err(Error::from(std::io::Error::last_os_error()));
+        }
+        // the directory iterator holds a file descriptor of its own
+        let in_use = std::fs::read_dir("/proc/self/fd")?
+            .count()
+            .saturating_sub(1);
+        Ok(FdLimit {
+            soft: rlim.rlim_cur as u64,
+            hard: rlim.rlim_max as u64,
+            in_use: in_use as u64,
+        })
+    }
+
+    /// The number of file descriptors that may be opened before reaching the soft limit.
+    pub fn available(&self) -> u64 {
+        self.soft.saturating_sub(self.in_use)
+    }
+
+    // Determine the soft limit required to open the file descriptors.
+    //
+    // Returns None if the current soft limit is sufficient.
+    fn plan(&self, required: u64, raise: bool) -> Result<Option<u64>> {
+        if required <= self.available() {
+            return Ok(None);
+        }
+        let soft = self.in_use.saturating_add(required);
+        if raise && soft <= self.hard {
+            return Ok(Some(soft));
+        }
+        let available = if raise {
+            self.hard.saturating_sub(self.in_use)
+        } else {
+            self.available()
+        };
+        Err(Error::InsufficientFds(required, available))
+    }
+}
+
+// Set the soft limit on the number of open file descriptors.
+#[allow(clippy::unnecessary_cast)]
+fn set_soft_limit(soft: u64, hard: u64) -> Result<()> {
+    let rlim = libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    };
+    // SAFETY: rlim is a valid rlimit.
+    if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &rlim) } < 0 {
+        return Err(Error::from(std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// The chips and requests opened by an [`Opener`].
+#[derive(Debug, Default)]
+pub struct Opened {
+    /// The chips, in the order they were added.
+    pub chips: Vec<Chip>,
+
+    /// The requests, in the order they were added.
+    pub requests: Vec<Request>,
+}
+
+/// Opens a set of chips and requests, checking the file descriptor limit first.
+#[derive(Clone, Default)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Opener {
+    chips: Vec<PathBuf>,
+    requests: Vec<Builder>,
+    raise_limit: bool,
+}
+
+impl Opener {
+    /// Create an empty opener.
+    pub fn new() -> Opener {
+        Opener::default()
+    }
+
+    /// Add a chip to be opened.
+    pub fn with_chip<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.chips.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Add a set of chips to be opened.
+    pub fn with_chips<P: AsRef<Path>>(&mut self, paths: &[P]) -> &mut Self {
+        for path in paths {
+            self.with_chip(path);
+        }
+        self
+    }
+
+    /// Add a request to be made.
+    ///
+    /// The builder is cloned, so may be reused.
+    pub fn with_request(&mut self, builder: &Builder) -> &mut Self {
+        self.requests.push(builder.clone());
+        self
+    }
+
+    /// Add a set of requests to be made.
+    pub fn with_requests(&mut self, builders: &[Builder]) -> &mut Self {
+        self.requests.extend_from_slice(builders);
+        self
+    }
+
+    /// Raise the soft file descriptor limit, if necessary, to open all the
+    /// chips and requests.
+    ///
+    /// The soft limit cannot be raised beyond the hard limit.
+    pub fn with_raised_limit(&mut self, raise: bool) -> &mut Self {
+        self.raise_limit = raise;
+        self
+    }
+
+    /// The number of file descriptors required to open all the chips and requests.
+    ///
+    /// This includes the chip temporarily opened while making each request.
+    pub fn fds_required(&self) -> u64 {
+        let mut required = self.chips.len() + self.requests.len();
+        if !self.requests.is_empty() {
+            required += 1;
+        }
+        required as u64
+    }
+
+    /// Check that the chips and requests can be opened within the file descriptor limit.
+    ///
+    /// Returns the current limits and usage.
+    ///
+    /// Returns an [`Error::InsufficientFds`] if the limit is insufficient and cannot be raised.
+    pub fn check(&self) -> Result<FdLimit> {
+        let limit = FdLimit::current()?;
+        limit.plan(self.fds_required(), self.raise_limit)?;
+        Ok(limit)
+    }
+
+    /// Open all the chips and make all the requests.
+    ///
+    /// The file descriptor limit is checked, and raised if enabled, before anything
+    /// is opened.
+    ///
+    /// If any chip or request fails to open then those already opened are closed
+    /// and the error is returned.
+    pub fn open(&self) -> Result<Opened> {
+        let limit = FdLimit::current()?;
+        if let Some(soft) = limit.plan(self.fds_required(), self.raise_limit)? {
+            set_soft_limit(soft, limit.hard)?;
+        }
+        let mut opened = Opened::default();
+        for path in &self.chips {
+            opened.chips.push(Chip::from_path(path)?);
+        }
+        for builder in &self.requests {
+            opened.requests.push(builder.clone().request()?);
+        }
+        Ok(opened)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current() {
+        let limit = FdLimit::current().unwrap();
+        // at least stdin, stdout and stderr
+        assert!(limit.in_use >= 3);
+        assert!(limit.soft <= limit.hard);
+        assert_eq!(limit.available(), limit.soft - limit.in_use);
+    }
+
+    #[test]
+    fn plan() {
+        let limit = FdLimit {
+            soft: 1024,
+            hard: 4096,
+            in_use: 24,
+        };
+        assert_eq!(limit.available(), 1000);
+        assert_eq!(limit.plan(1000, false), Ok(None));
+        assert_eq!(limit.plan(1000, true), Ok(None));
+        assert_eq!(
+            limit.plan(1001, false),
+            Err(Error::InsufficientFds(1001, 1000))
+        );
+        assert_eq!(limit.plan(1001, true), Ok(Some(1025)));
+        assert_eq!(limit.plan(4072, true), Ok(Some(4096)));
+        assert_eq!(
+            limit.plan(4073, true),
+            Err(Error::InsufficientFds(4073, 4072))
+        );
+    }
+
+    #[test]
+    fn fds_required() {
+        let mut opener = Opener::new();
+        assert_eq!(opener.fds_required(), 0);
+        opener.with_chips(&["/dev/gpiochip0", "/dev/gpiochip1"]);
+        assert_eq!(opener.fds_required(), 2);
+        let builder = Request::builder();
+        opener.with_request(&builder).with_request(&builder);
+        assert_eq!(opener.fds_required(), 5);
+    }
+
+    #[test]
+    fn open_empty() {
+        let opened = Opener::new().open().unwrap();
+        assert!(opened.chips.is_empty());
+        assert!(opened.requests.is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Reading and writing groups of lines as words.
+//!
+//! A [`Bus`] maps a set of lines from a [`Request`] onto the bits of a word,
+//! so address and data buses, or banks of DIP switches, can be read and written
+//! as integers rather than line by line.
+//!
+//! The lines are listed in bus order, and are divided into bytes of 8 lines.
+//! The [`Endianness`] determines which byte of the word each group of 8 lines carries,
+//! and the [`BitOrder`] determines which bit within the byte each line carries.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::bus::{BitOrder, Bus};
+//! # use gpiocdev::line::Value;
+//! # fn main() -> Result<()> {
+//! let data_lines = [4, 5, 6, 7, 8, 9, 10, 11];
+//! let req = gpiocdev::Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&data_lines)
+//!     .as_output(Value::Inactive)
+//!     .request()?;
+//! let bus = Bus::new(&req, &data_lines)?.with_bit_order(BitOrder::MsbFirst);
+//! bus.write_u32(0xa5)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::line::{Offset, Value, Values};
+use crate::request::wait_settle;
+use crate::{Error, Request, Result};
+use std::time::Duration;
+
+/// The maximum number of lines in a bus.
+pub const MAX_BUS_WIDTH: usize = 32;
+
+/// The order of the bits within each byte of a [`Bus`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BitOrder {
+    /// The first line of each byte carries the least significant bit.
+    #[default]
+    LsbFirst,
+
+    /// The first line of each byte carries the most significant bit.
+    MsbFirst,
+}
+
+/// The order of the bytes of a [`Bus`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Endianness {
+    /// The first 8 lines carry the least significant byte.
+    #[default]
+    Little,
+
+    /// The first 8 lines carry the most significant byte.
+    ///
+    /// Requires the bus width to be a multiple of 8.
+    Big,
+}
+
+/// A group of lines from a request read and written as a word.
+///
+/// Bits of the word beyond the width of the bus are ignored when writing,
+/// and are zero when reading.
+#[derive(Clone, Debug)]
+pub struct Bus<'a> {
+    req: &'a Request,
+    offsets: Vec<Offset>,
+    bit_order: BitOrder,
+    endianness: Endianness,
+    settle: Duration,
+}
+
+impl<'a> Bus<'a> {
+    /// Create a bus from the lines of a request.
+    ///
+    /// * `req` - the request containing the lines.
+    /// * `offsets` - the lines of the bus, in bus order.
+    ///
+    /// The bus defaults to little endian with the least significant bit first,
+    /// i.e. the first line carries bit 0 of the word.
+    pub fn new(req: &'a Request, offsets: &[Offset]) -> Result<Bus<'a>> {
+        if offsets.is_empty() || offsets.len() > MAX_BUS_WIDTH {
+            return Err(Error::InvalidArgument(format!(
+                "bus width must be between 1 and {} lines.",
+                MAX_BUS_WIDTH
+            )));
+        }
+        let cfg = req.config();
+        let lines = cfg.lines();
+        for (idx, offset) in offsets.iter().enumerate() {
+            if !lines.contains(offset) {
+                return Err(Error::InvalidArgument(format!(
+                    "offset {} is not a requested line.",
+                    offset
+                )));
+            }
+            if offsets[..idx].contains(offset) {
+                return Err(Error::InvalidArgument(format!(
+                    "offset {} is repeated in the bus.",
+                    offset
+                )));
+            }
+        }
+        Ok(Bus {
+            req,
+            offsets: offsets.to_vec(),
+            bit_order: BitOrder::default(),
+            endianness: Endianness::default(),
+            settle: Duration::ZERO,
+        })
+    }
+
+    /// Set the order of the bits within each byte.
+    pub fn with_bit_order(mut self, bit_order: BitOrder) -> Bus<'a> {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Set the order of the bytes.
+    ///
+    /// Returns an error if the order is big endian and the bus width is not a
+    /// multiple of 8.
+    pub fn with_endianness(mut self, endianness: Endianness) -> Result<Bus<'a>> {
+        if endianness == Endianness::Big && self.offsets.len() % 8 != 0 {
+            return Err(Error::InvalidArgument(
+                "big endian bus width must be a multiple of 8 lines.".to_string(),
+            ));
+        }
+        self.endianness = endianness;
+        Ok(self)
+    }
+
+    /// Set the period to wait after writing, for the lines to settle.
+    ///
+    /// This allows for propagation delays in the circuit, such as level shifters
+    /// or the device latching an address, before the next operation on the bus.
+    ///
+    /// Short periods are busy waited, so are more accurate than sleeping.
+    pub fn with_settle(mut self, settle: Duration) -> Bus<'a> {
+        self.settle = settle;
+        self
+    }
+
+    /// The lines of the bus, in bus order.
+    pub fn lines(&self) -> &[Offset] {
+        &self.offsets
+    }
+
+    /// The number of lines in the bus.
+    pub fn width(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Read the lines of the bus as a word.
+    pub fn read_u32(&self) -> Result<u32> {
+        let mut values = Values::from_offsets(&self.offsets);
+        self.req.values(&mut values)?;
+        Ok(self.word_from(&values))
+    }
+
+    /// Write a word to the lines of the bus.
+    ///
+    /// The lines must be outputs.
+    /// If a settle period is set then this waits for that period before returning.
+    pub fn write_u32(&self, word: u32) -> Result<()> {
+        self.req.set_values(&self.values_from(word))?;
+        wait_settle(self.settle);
+        Ok(())
+    }
+
+    fn bit(&self, pos: usize) -> usize {
+        bit_index(self.offsets.len(), pos, self.bit_order, self.endianness)
+    }
+
+    fn word_from(&self, values: &Values) -> u32 {
+        self.offsets
+            .iter()
+            .enumerate()
+            .filter(|(_, offset)| values.get(**offset) == Some(Value::Active))
+            .fold(0, |word, (pos, _)| word | (1 << self.bit(pos)))
+    }
+
+    fn values_from(&self, word: u32) -> Values {
+        self.offsets
+            .iter()
+            .enumerate()
+            .map(|(pos, offset)| (*offset, Value::from((word >> self.bit(pos)) & 1 == 1)))
+            .collect()
+    }
+}
+
+// The bit of the word carried by the line at the given position in the bus.
+fn bit_index(width: usize, pos: usize, bit_order: BitOrder, endianness: Endianness) -> usize {
+    let byte = pos / 8;
+    let byte_width = (width - byte * 8).min(8);
+    let bit = match bit_order {
+        BitOrder::LsbFirst => pos % 8,
+        BitOrder::MsbFirst => byte_width - 1 - pos % 8,
+    };
+    let byte = match endianness {
+        Endianness::Little => byte,
+        Endianness::Big => width / 8 - 1 - byte,
+    };
+    byte * 8 + bit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(width: usize, bit_order: BitOrder, endianness: Endianness) -> Vec<usize> {
+        (0..width)
+            .map(|pos| bit_index(width, pos, bit_order, endianness))
+            .collect()
+    }
+
+    #[test]
+    fn bit_mapping() {
+        use BitOrder::*;
+        use Endianness::*;
+        assert_eq!(bits(4, LsbFirst, Little), [0, 1, 2, 3]);
+        assert_eq!(bits(4, MsbFirst, Little), [3, 2, 1, 0]);
+        assert_eq!(bits(10, LsbFirst, Little), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        // partial final byte
+        assert_eq!(bits(10, MsbFirst, Little), [7, 6, 5, 4, 3, 2, 1, 0, 9, 8]);
+        assert_eq!(
+            bits(16, LsbFirst, Big),
+            [8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(
+            bits(16, MsbFirst, Big),
+            [15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]
+        );
+        assert_eq!(bits(32, LsbFirst, Big)[31], 7);
+        assert_eq!(bits(32, MsbFirst, Little)[31], 24);
+    }
+}
@@ -0,0 +1,200 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Event sources for the [calloop](https://crates.io/crates/calloop) event loop.
+//!
+//! [`EdgeEventSource`] and [`InfoChangeEventSource`] wrap a [`Request`] and [`Chip`]
+//! respectively, and pass each edge event or info change event to the callback
+//! registered with the loop.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! use calloop::EventLoop;
+//! use gpiocdev::calloop::EdgeEventSource;
+//! use gpiocdev::line::{EdgeDetection, EdgeEvent};
+//! use gpiocdev::Request;
+//!
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! let req = Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_line(17)
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let mut event_loop: EventLoop<Vec<EdgeEvent>> = EventLoop::try_new()?;
+//! event_loop
+//!     .handle()
+//!     .insert_source(EdgeEventSource::new(req), |evt, _, events| events.push(evt))?;
+//! let mut events = Vec::new();
+//! loop {
+//!     event_loop.dispatch(None, &mut events)?;
+//!     for evt in events.drain(..) {
+//!         println!("{:?}", evt);
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::line::{EdgeEvent, InfoChangeEvent};
+use crate::{Chip, Error, Request};
+use ::calloop::generic::Generic;
+use ::calloop::{EventSource, Interest, Mode, Poll, PostAction, Readiness, Token, TokenFactory};
+
+// The maximum number of edge events read from the kernel at once.
+const EDGE_EVENT_BATCH_SIZE: usize = 16;
+
+/// A calloop event source for the edge events from a [`Request`].
+///
+/// All the events available are read and passed to the callback each time
+/// the request is readable.
+#[derive(Debug)]
+pub struct EdgeEventSource {
+    source: Generic<Request, Error>,
+    events: Vec<EdgeEvent>,
+}
+
+impl EdgeEventSource {
+    /// Create an event source for the edge events from the request.
+    pub fn new(req: Request) -> Self {
+        EdgeEventSource {
+            source: Generic::new_with_error(req, Interest::READ, Mode::Level),
+            events: Vec::with_capacity(EDGE_EVENT_BATCH_SIZE),
+        }
+    }
+
+    /// The request providing the events.
+    pub fn request(&self) -> &Request {
+        self.source.get_ref()
+    }
+
+    /// Consume the event source and return the request.
+    pub fn into_inner(self) -> Request {
+        self.source.unwrap()
+    }
+}
+
+impl EventSource for EdgeEventSource {
+    type Event = EdgeEvent;
+    type Metadata = ();
+    type Ret = ();
+    type Error = Error;
+
+    fn process_events<F>(
+        &mut self,
+        readiness: Readiness,
+        token: Token,
+        mut callback: F,
+    ) -> Result<PostAction, Error>
+    where
+        F: FnMut(EdgeEvent, &mut ()),
+    {
+        let events = &mut self.events;
+        self.source.process_events(readiness, token, |_, req| {
+            while req.has_edge_event()? {
+                events.clear();
+                req.read_edge_events(events, EDGE_EVENT_BATCH_SIZE)?;
+                for evt in events.drain(..) {
+                    callback(evt, &mut ());
+                }
+            }
+            Ok(PostAction::Continue)
+        })
+    }
+
+    fn register(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> ::calloop::Result<()> {
+        self.source.register(poll, token_factory)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> ::calloop::Result<()> {
+        self.source.reregister(poll, token_factory)
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> ::calloop::Result<()> {
+        self.source.unregister(poll)
+    }
+}
+
+/// A calloop event source for the info change events from a [`Chip`].
+///
+/// All the events available are read and passed to the callback each time
+/// the chip is readable.
+///
+/// Only lines being watched, such as via [`Chip::watch_line_info`], generate events.
+#[derive(Debug)]
+pub struct InfoChangeEventSource {
+    source: Generic<Chip, Error>,
+}
+
+impl InfoChangeEventSource {
+    /// Create an event source for the info change events from the chip.
+    pub fn new(chip: Chip) -> Self {
+        InfoChangeEventSource {
+            source: Generic::new_with_error(chip, Interest::READ, Mode::Level),
+        }
+    }
+
+    /// The chip providing the events.
+    ///
+    /// Which may be used to add or remove watches on lines.
+    pub fn chip(&self) -> &Chip {
+        self.source.get_ref()
+    }
+
+    /// Consume the event source and return the chip.
+    pub fn into_inner(self) -> Chip {
+        self.source.unwrap()
+    }
+}
+
+impl EventSource for InfoChangeEventSource {
+    type Event = InfoChangeEvent;
+    type Metadata = ();
+    type Ret = ();
+    type Error = Error;
+
+    fn process_events<F>(
+        &mut self,
+        readiness: Readiness,
+        token: Token,
+        mut callback: F,
+    ) -> Result<PostAction, Error>
+    where
+        F: FnMut(InfoChangeEvent, &mut ()),
+    {
+        self.source.process_events(readiness, token, |_, chip| {
+            while chip.has_line_info_change_event()? {
+                callback(chip.read_line_info_change_event()?, &mut ());
+            }
+            Ok(PostAction::Continue)
+        })
+    }
+
+    fn register(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> ::calloop::Result<()> {
+        self.source.register(poll, token_factory)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> ::calloop::Result<()> {
+        self.source.reregister(poll, token_factory)
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> ::calloop::Result<()> {
+        self.source.unregister(poll)
+    }
+}
@@ -16,6 +16,7 @@ use gpiocdev_uapi::{v1, v2};
 use serde_derive::{Deserialize, Serialize};
 #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
 use std::cell::Cell;
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
 use std::mem;
@@ -23,10 +24,17 @@ use std::ops::Range;
 use std::os::linux::fs::MetadataExt;
 use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd, OsStrExt};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+mod transition;
+pub use self::transition::{became_free, reconfigured, requested_by};
 
 const CHARDEV_MODE: u32 = 0x2000;
 
+// How often wait_for checks if the chip is available.
+const WAIT_FOR_POLL_PERIOD: Duration = Duration::from_millis(20);
+
 /// Check if a path corresponds to a GPIO character device.
 ///
 /// Returns the resolved path to the character device.
@@ -59,6 +67,23 @@ pub fn is_chip<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
     Err(Error::GpioChip(pb, ErrorKind::NotGpioDevice))
 }
 
+/// The path to a chip identified by name, number or path.
+///
+/// A number, such as `"3"`, identifies `/dev/gpiochip3`, a name without a `/`, such as
+/// `"gpiochip2"`, identifies the device of that name in `/dev`, and anything else
+/// is taken to be a path.
+///
+/// The path is not checked to be a GPIO character device - use [`is_chip`] for that.
+pub fn path_from_id(id: &str) -> PathBuf {
+    if id.parse::<u32>().is_ok() {
+        return PathBuf::from(format!("/dev/gpiochip{}", id));
+    }
+    if !id.contains('/') {
+        return PathBuf::from(format!("/dev/{}", id));
+    }
+    PathBuf::from(id)
+}
+
 /// Compare two chip paths.
 ///
 // Sorts paths naturally, assuming any chip numbering is at the end of the path - as it is for gpiochips.
@@ -115,6 +140,76 @@ impl<'a> Iterator for LineInfoIterator<'a> {
     }
 }
 
+/// An iterator that reads the info for each line on the [`Chip`] from the kernel.
+///
+/// Created by [`Chip::line_infos`].
+///
+/// The name and consumer are the only fields that require allocation, so they
+/// may be omitted, in which case they are returned empty.
+pub struct LineInfos<'a> {
+    chip: &'a Chip,
+    offsets: Range<Offset>,
+    strings: InfoStrings,
+}
+
+impl LineInfos<'_> {
+    /// Set whether the line names are returned.
+    ///
+    /// The default is true.
+    pub fn with_names(mut self, names: bool) -> Self {
+        self.strings.name = names;
+        self
+    }
+
+    /// Set whether the line consumers are returned.
+    ///
+    /// The default is true.
+    pub fn with_consumers(mut self, consumers: bool) -> Self {
+        self.strings.consumer = consumers;
+        self
+    }
+}
+
+impl Iterator for LineInfos<'_> {
+    type Item = Result<line::Info>;
+
+    fn next(&mut self) -> Option<Result<line::Info>> {
+        self.offsets
+            .next()
+            .map(|offset| self.chip.do_line_info(offset, self.strings))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.offsets.size_hint()
+    }
+}
+
+impl ExactSizeIterator for LineInfos<'_> {}
+
+// The string fields to populate when reading line info.
+#[derive(Clone, Copy, Debug)]
+struct InfoStrings {
+    name: bool,
+    consumer: bool,
+}
+
+impl InfoStrings {
+    const ALL: InfoStrings = InfoStrings {
+        name: true,
+        consumer: true,
+    };
+
+    // Clear the strings that are not required, so they are not converted.
+    fn strip(&self, name: &mut gpiocdev_uapi::Name, consumer: &mut gpiocdev_uapi::Name) {
+        if !self.name {
+            *name = Default::default();
+        }
+        if !self.consumer {
+            *consumer = Default::default();
+        }
+    }
+}
+
 /// A GPIO character device.
 #[derive(Debug)]
 pub struct Chip {
@@ -124,6 +219,10 @@ pub struct Chip {
     pub(crate) f: fs::File,
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     abiv: Cell<Option<AbiVersion>>,
+    /// The cached line info, if caching is enabled.
+    info_cache: Option<Mutex<HashMap<Offset, line::Info>>>,
+    /// The offsets of the named lines, built on first use.
+    names: Mutex<Option<HashMap<String, Offset>>>,
 }
 
 impl Chip {
@@ -147,6 +246,8 @@ impl Chip {
             f,
             #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
             abiv: Default::default(),
+            info_cache: None,
+            names: Mutex::default(),
         })
     }
 
@@ -170,9 +271,101 @@ impl Chip {
             f,
             #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
             abiv: Default::default(),
+            info_cache: None,
+            names: Mutex::default(),
         })
     }
 
+    /// Constructs a Chip using the given label.
+    ///
+    /// The label must exactly match the label of a GPIO chip, such as "*pinctrl-bcm2711*".
+    ///
+    /// Unlike the chip path and name, the label identifies the type of chip, so is
+    /// stable across boots. If several chips share the label then the first, in name
+    /// order, is returned.
+    ///
+    /// Use [`chips_by_label`] to find all the chips with matching labels.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # fn example() -> gpiocdev::Result<Chip>{
+    /// let chip = Chip::from_label("pinctrl-bcm2711")?;
+    /// # Ok(chip)
+    /// # }
+    ///```
+    ///
+    /// [`chips_by_label`]: crate::chips_by_label
+    pub fn from_label(label: &str) -> Result<Chip> {
+        for path in chips()? {
+            if let Ok(chip) = Chip::from_path(&path) {
+                if chip.info().map(|i| i.label == label).unwrap_or(false) {
+                    return Ok(chip);
+                }
+            }
+        }
+        Err(Error::InvalidArgument(format!(
+            "No chip with label '{}' found.",
+            label
+        )))
+    }
+
+    // A Chip using an already open GPIO character device.
+    pub(crate) fn from_parts(path: PathBuf, f: fs::File) -> Chip {
+        Chip {
+            path,
+            f,
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            abiv: Default::default(),
+            info_cache: None,
+            names: Mutex::default(),
+        }
+    }
+
+    /// Wait for a chip to become available.
+    ///
+    /// Blocks until the path resolves to a GPIO character device that can be
+    /// opened and its info read, or the timeout expires.
+    /// This covers the chip device node not yet existing, and existing but
+    /// not being usable while the driver is still probing or its permissions
+    /// are still being applied.
+    ///
+    /// Returns the error from the final attempt if the chip does not become
+    /// available within the timeout.
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # use std::time::Duration;
+    /// # fn example() -> gpiocdev::Result<Chip>{
+    /// let chip = Chip::wait_for("/dev/gpiochip0", Duration::from_secs(10))?;
+    /// # Ok(chip)
+    /// # }
+    ///```
+    pub fn wait_for<P: AsRef<Path>>(p: P, timeout: Duration) -> Result<Chip> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let err = match Chip::from_path(p.as_ref()) {
+                Ok(chip) => match chip.info() {
+                    Ok(_) => return Ok(chip),
+                    Err(e) => e,
+                },
+                Err(e) => e,
+            };
+            let mut period = WAIT_FOR_POLL_PERIOD;
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(err);
+                }
+                period = period.min(deadline - now);
+            }
+            std::thread::sleep(period);
+        }
+    }
+
     /// Get the information for the chip.
     pub fn info(&self) -> Result<Info> {
         Ok(Info::from(
@@ -218,27 +411,251 @@ impl Chip {
             .and_then(|iter| iter.filter_map(|x| x.ok()).find(|li| li.name == name))
     }
 
+    /// Find the offset of the named line.
+    ///
+    /// Returns the first matching line, or None if no line has the name.
+    ///
+    /// The names of all the lines are read on first use and retained, so
+    /// resolving many names only reads the line info once.
+    /// Line names are typically fixed for the lifetime of the chip, but if they
+    /// may change then use [`refresh_names`] to re-read them.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # fn example() -> gpiocdev::Result<()>{
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// for name in ["LED0", "LED1", "BUTTON"] {
+    ///     if let Some(offset) = chip.line_offset_from_name(name)? {
+    ///         println!("{} is line {}", name, offset);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    ///
+    /// [`refresh_names`]: Chip::refresh_names
+    pub fn line_offset_from_name(&self, name: &str) -> Result<Option<Offset>> {
+        let mut names = self
+            .names
+            .lock()
+            .expect("failed to acquire lock on line names");
+        if names.is_none() {
+            *names = Some(self.read_names()?);
+        }
+        Ok(names.as_ref().and_then(|names| names.get(name).copied()))
+    }
+
+    /// Re-read the line names used by [`line_offset_from_name`].
+    ///
+    /// The names are always read from the kernel, bypassing any [line info cache].
+    ///
+    /// [`line_offset_from_name`]: Chip::line_offset_from_name
+    /// [line info cache]: Chip::with_line_info_cache
+    pub fn refresh_names(&self) -> Result<()> {
+        let names = self.read_names()?;
+        *self
+            .names
+            .lock()
+            .expect("failed to acquire lock on line names") = Some(names);
+        Ok(())
+    }
+
+    // Read the offsets of the named lines.
+    fn read_names(&self) -> Result<HashMap<String, Offset>> {
+        let mut names = HashMap::new();
+        for info in self.line_infos()?.with_consumers(false) {
+            let info = info?;
+            if !info.name.is_empty() {
+                names.entry(info.name).or_insert(info.offset);
+            }
+        }
+        Ok(names)
+    }
+
+    /// Find the info for the lines in use with consumer labels matching a pattern.
+    ///
+    /// The pattern is matched against the whole consumer label, and may contain `*` to
+    /// match any sequence of characters, and `?` to match any single character.
+    /// e.g. "motord" or "gpiocdev-*".
+    ///
+    /// Returns the matching lines in offset order.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Chip, Result};
+    /// # fn main() -> Result<()> {
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// for info in chip.lines_with_consumer("motord")? {
+    ///     println!("{} {}", info.offset, info.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lines_with_consumer(&self, pattern: &str) -> Result<Vec<line::Info>> {
+        let mut found = Vec::new();
+        for info in self.line_info_iter()? {
+            let info = info?;
+            if info.used && crate::glob_match(pattern, &info.consumer) {
+                found.push(info);
+            }
+        }
+        Ok(found)
+    }
+
     /// Get the information for a line on the chip.
+    ///
+    /// If the [line info cache] is enabled then the info is returned from the cache,
+    /// and is only read from the kernel the first time the line is accessed.
+    ///
+    /// [line info cache]: Chip::with_line_info_cache
     pub fn line_info(&self, offset: Offset) -> Result<line::Info> {
-        self.do_line_info(offset)
+        let cache = match &self.info_cache {
+            Some(cache) => cache,
+            None => return self.do_line_info(offset, InfoStrings::ALL),
+        };
+        self.sync_line_info_cache()?;
+        if let Some(info) = cache
+            .lock()
+            .expect("failed to acquire lock on line info cache")
+            .get(&offset)
+        {
+            return Ok(info.clone());
+        }
+        let info = match self.watch_line_info(offset) {
+            Ok(info) => info,
+            // already watched, so changes are still reported
+            Err(Error::Uapi(_, gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(libc::EBUSY)))) => {
+                self.do_line_info(offset, InfoStrings::ALL)?
+            }
+            Err(e) => return Err(e),
+        };
+        cache
+            .lock()
+            .expect("failed to acquire lock on line info cache")
+            .insert(offset, info.clone());
+        Ok(info)
+    }
+
+    /// Enable caching of the info returned by [`line_info`].
+    ///
+    /// Intended for applications that repeatedly read the info for the same lines,
+    /// such as when resolving line names or reporting line status, to avoid
+    /// reading the info from the kernel on every call.
+    ///
+    /// Lines are watched when first added to the cache, and the cached info is
+    /// kept current using the resulting info change events.
+    /// Those events are read, and so consumed, by [`line_info`], so the cache
+    /// should not be used on a chip that is also being used to watch for info changes.
+    /// Any events that are read by the application are still applied to the cache.
+    ///
+    /// The watches remain in place until the chip is dropped, or the watch on
+    /// a line is removed with [`unwatch_line_info`], which also evicts the line
+    /// from the cache.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # fn example() -> gpiocdev::Result<()>{
+    /// let mut chip = Chip::from_path("/dev/gpiochip0")?;
+    /// chip.with_line_info_cache();
+    /// for _ in 0..10 {
+    ///     // only read from the kernel on the first pass
+    ///     let info = chip.line_info(3)?;
+    ///     println!("{} used: {}", info.name, info.used);
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    ///
+    /// [`line_info`]: Chip::line_info
+    /// [`unwatch_line_info`]: Chip::unwatch_line_info
+    pub fn with_line_info_cache(&mut self) -> &mut Self {
+        if self.info_cache.is_none() {
+            self.info_cache = Some(Mutex::default());
+        }
+        self
+    }
+
+    // Apply any pending info change events to the cache.
+    fn sync_line_info_cache(&self) -> Result<()> {
+        while self.has_line_info_change_event()? {
+            self.read_line_info_change_event()?;
+        }
+        Ok(())
+    }
+
+    // Update any cached info from an info change event.
+    fn update_line_info_cache(&self, evt: &InfoChangeEvent) {
+        if let Some(cache) = &self.info_cache {
+            if let Some(info) = cache
+                .lock()
+                .expect("failed to acquire lock on line info cache")
+                .get_mut(&evt.info.offset)
+            {
+                *info = evt.info.clone();
+            }
+        }
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-    fn do_line_info(&self, offset: Offset) -> Result<line::Info> {
+    fn do_line_info(&self, offset: Offset, strings: InfoStrings) -> Result<line::Info> {
         match self.actual_abi_version()? {
-            V1 => v1::get_line_info(&self.f, offset).map(|li| line::Info::from(&li)),
-            V2 => v2::get_line_info(&self.f, offset).map(|li| line::Info::from(&li)),
+            V1 => v1::get_line_info(&self.f, offset).map(|mut li| {
+                strings.strip(&mut li.name, &mut li.consumer);
+                line::Info::from(&li)
+            }),
+            V2 => v2::get_line_info(&self.f, offset).map(|mut li| {
+                strings.strip(&mut li.name, &mut li.consumer);
+                line::Info::from(&li)
+            }),
         }
         .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
     }
     #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
-    fn do_line_info(&self, offset: Offset) -> Result<line::Info> {
+    fn do_line_info(&self, offset: Offset, strings: InfoStrings) -> Result<line::Info> {
         uapi::get_line_info(&self.f, offset)
-            .map(|li| line::Info::from(&li))
+            .map(|mut li| {
+                strings.strip(&mut li.name, &mut li.consumer);
+                line::Info::from(&li)
+            })
             .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
     }
 
+    /// An iterator that reads the info for each line on the chip from the kernel.
+    ///
+    /// Unlike [`line_info_iter`], the info is always read from the kernel, bypassing
+    /// any [line info cache], and the name and consumer fields may be omitted when
+    /// not required, to avoid allocating them.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # fn example() -> gpiocdev::Result<()>{
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// for info in chip.line_infos()?.with_names(false).with_consumers(false) {
+    ///     let info = info?;
+    ///     println!("{} {:?}", info.offset, info.direction);
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    ///
+    /// [`line_info_iter`]: Chip::line_info_iter
+    /// [line info cache]: Chip::with_line_info_cache
+    pub fn line_infos(&self) -> Result<LineInfos<'_>> {
+        let cinfo = self.info()?;
+        Ok(LineInfos {
+            chip: self,
+            offsets: Range {
+                start: 0,
+                end: cinfo.num_lines,
+            },
+            strings: InfoStrings::ALL,
+        })
+    }
+
     /// An iterator that returns the info for each line on the chip.
-    pub fn line_info_iter(&self) -> Result<LineInfoIterator> {
+    pub fn line_info_iter(&self) -> Result<LineInfoIterator<'_>> {
         let cinfo = self.info()?;
         Ok(LineInfoIterator {
             chip: self,
@@ -270,14 +687,82 @@ impl Chip {
             .map_err(|e| Error::Uapi(UapiCall::WatchLineInfo, e))
     }
 
+    /// Add watches for changes to the publicly available information on a set of lines.
+    ///
+    /// Returns the current info for each line, in the order of the offsets.
+    ///
+    /// If adding any watch fails then the watches added by this call are removed,
+    /// and the error is returned.
+    /// Watches that existed prior to the call are retained, and attempting to
+    /// watch a line that is already watched fails.
+    pub fn watch_line_infos(&self, offsets: &[Offset]) -> Result<Vec<line::Info>> {
+        let mut infos = Vec::with_capacity(offsets.len());
+        for (idx, offset) in offsets.iter().enumerate() {
+            match self.watch_line_info(*offset) {
+                Ok(info) => infos.push(info),
+                Err(e) => {
+                    for offset in &offsets[..idx] {
+                        let _ = self.unwatch_line_info(*offset);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(infos)
+    }
+
+    /// Watch a set of lines and return an iterator over the info change events
+    /// for all of them.
+    ///
+    /// The events from all the lines watched on the chip are merged into the one
+    /// iterator, so there is no need for a separate watch and read loop per line.
+    /// Use [`watch_line_infos`] instead if the initial info for each line is required.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # fn example() -> gpiocdev::Result<()>{
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// for evt in chip.watch_info_changes(&[3, 5, 8, 13])? {
+    ///     let evt = evt?;
+    ///     println!("line {} {:?}", evt.info.offset, evt.kind);
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    ///
+    /// [`watch_line_infos`]: Chip::watch_line_infos
+    pub fn watch_info_changes(&self, offsets: &[Offset]) -> Result<InfoChangeIterator<'_>> {
+        self.watch_line_infos(offsets)?;
+        Ok(self.info_change_events())
+    }
+
     /// Remove a watch for changes to the publicly available information on a line.
     ///
     /// This is a null operation if there is no existing watch on the line.
+    ///
+    /// If the [line info cache] is enabled then the line is also evicted from the cache.
+    ///
+    /// [line info cache]: Chip::with_line_info_cache
     pub fn unwatch_line_info(&self, offset: Offset) -> Result<()> {
+        if let Some(cache) = &self.info_cache {
+            cache
+                .lock()
+                .expect("failed to acquire lock on line info cache")
+                .remove(&offset);
+        }
         uapi::unwatch_line_info(&self.f, offset)
             .map_err(|e| Error::Uapi(UapiCall::UnwatchLineInfo, e))
     }
 
+    /// Remove the watches for changes to the publicly available information on a set of lines.
+    pub fn unwatch_line_infos(&self, offsets: &[Offset]) -> Result<()> {
+        for offset in offsets {
+            self.unwatch_line_info(*offset)?;
+        }
+        Ok(())
+    }
+
     /// Check if the request has at least one info change event available to read.
     pub fn has_line_info_change_event(&self) -> Result<bool> {
         gpiocdev_uapi::has_event(&self.f).map_err(|e| Error::Uapi(UapiCall::HasEvent, e))
@@ -288,11 +773,104 @@ impl Chip {
         gpiocdev_uapi::wait_event(&self.f, timeout).map_err(|e| Error::Uapi(UapiCall::WaitEvent, e))
     }
 
+    /// Wait for a line to be free, i.e. not in use by any consumer.
+    ///
+    /// The wait uses a separate watch on the line, so it does not interfere with
+    /// any watches or info change events on this chip.
+    ///
+    /// Returns true if the line is free, or false if the line remained in use
+    /// for the timeout.
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely.
+    ///
+    /// Note that another consumer may request the line as soon as it is freed,
+    /// so the line is not guaranteed to remain free.
+    /// Use [`Builder::claim_when_free`] to request the line once it is free.
+    ///
+    /// [`Builder::claim_when_free`]: crate::request::Builder::claim_when_free
+    pub fn wait_for_line_free(&self, offset: Offset, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now().checked_add(timeout);
+        let watcher = Chip::from_parts(self.path.clone(), fs::File::open(&self.path)?);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        watcher.abiv.set(self.abiv.get());
+        let mut used = watcher.watch_line_info(offset)?.used;
+        while used {
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline || !watcher.wait_line_info_change_event(deadline - now)? {
+                    return Ok(false);
+                }
+            }
+            used = watcher.read_line_info_change_event()?.info.used;
+        }
+        Ok(true)
+    }
+
+    /// Wait for an info change event on a line that matches a predicate.
+    ///
+    /// Prebuilt predicates are provided by [`became_free`], [`reconfigured`]
+    /// and [`requested_by`].
+    ///
+    /// As per [`wait_for_line_free`], the wait uses a separate watch on the line, so it
+    /// does not interfere with any watches or info change events on this chip.
+    /// Only changes that occur after the call are considered, not the current state
+    /// of the line.
+    ///
+    /// Returns the matching event, or `None` if no matching event occurred within
+    /// the timeout.
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::chip::{self, Chip};
+    /// # use std::time::Duration;
+    /// # fn example() -> gpiocdev::Result<()>{
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// let timeout = Duration::from_secs(5);
+    /// if chip.wait_for_info_change(17, chip::requested_by("motor"), timeout)?.is_some() {
+    ///     println!("motor controller has started");
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    ///
+    /// [`wait_for_line_free`]: Chip::wait_for_line_free
+    pub fn wait_for_info_change<P>(
+        &self,
+        offset: Offset,
+        mut predicate: P,
+        timeout: Duration,
+    ) -> Result<Option<InfoChangeEvent>>
+    where
+        P: FnMut(&InfoChangeEvent) -> bool,
+    {
+        let deadline = Instant::now().checked_add(timeout);
+        let watcher = Chip::from_parts(self.path.clone(), fs::File::open(&self.path)?);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        watcher.abiv.set(self.abiv.get());
+        watcher.watch_line_info(offset)?;
+        loop {
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline || !watcher.wait_line_info_change_event(deadline - now)? {
+                    return Ok(None);
+                }
+            }
+            let evt = watcher.read_line_info_change_event()?;
+            if predicate(&evt) {
+                return Ok(Some(evt));
+            }
+        }
+    }
+
     /// Read a single line info change event from the chip.
     ///
     /// Will block until an edge event is available.
     pub fn read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
-        self.do_read_line_info_change_event()
+        let evt = self.do_read_line_info_change_event()?;
+        self.update_line_info_cache(&evt);
+        Ok(evt)
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
@@ -318,13 +896,47 @@ impl Chip {
     }
 
     /// An iterator for info change events from the chip.
-    pub fn info_change_events(&self) -> InfoChangeIterator {
+    pub fn info_change_events(&self) -> InfoChangeIterator<'_> {
         InfoChangeIterator {
             chip: self,
             buf: vec![0_u64; self.line_info_change_event_u64_size()],
         }
     }
 
+    /// An iterator for batches of info change events from the chip.
+    ///
+    /// Changes often occur in bursts, such as a driver reconfiguring many lines
+    /// at once, so rather than returning each event individually, events are
+    /// collected into a batch until no further event arrives within the `settle`
+    /// period.
+    ///
+    /// Each batch contains at least one event.
+    /// Note that a batch is not returned while events continue to arrive within
+    /// the settle period.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # use gpiocdev::Chip;
+    /// # use std::time::Duration;
+    /// # fn example() -> gpiocdev::Result<()>{
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// for offset in 0..32 {
+    ///     chip.watch_line_info(offset)?;
+    /// }
+    /// for batch in chip.info_change_batches(Duration::from_millis(50)) {
+    ///     let batch = batch?;
+    ///     println!("lines {:?} changed", batch.offsets());
+    /// }
+    /// # Ok(())
+    /// # }
+    ///```
+    pub fn info_change_batches(&self, settle: Duration) -> InfoChangeBatches<'_> {
+        InfoChangeBatches {
+            iter: self.info_change_events(),
+            settle,
+        }
+    }
+
     /// Detect the most recent uAPI ABI supported by the library for the chip.
     pub fn detect_abi_version(&self) -> Result<AbiVersion> {
         // check in preferred order
@@ -367,6 +979,33 @@ impl Chip {
         }
     }
 
+    /// Report the features supported by the kernel and library for the chip.
+    ///
+    /// The supported ABI versions are probed, while the other features are inferred
+    /// from the kernel version, so features that have been backported to an older
+    /// kernel are not detected.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Chip, Result};
+    /// # fn main() -> Result<()> {
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// let caps = chip.capabilities()?;
+    /// if !caps.debounce {
+    ///     println!("debounce is not supported - debounce in user space instead");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        let v1 = self.supports_abi_version(V1).is_ok();
+        let v2 = self.supports_abi_version(V2).is_ok();
+        if !v1 && !v2 {
+            return Err(Error::NoAbiSupport());
+        }
+        Ok(Capabilities::new(v1, v2, kernel_version()))
+    }
+
     /// Set the ABI version to use for subsequent operations.
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     pub fn using_abi_version(&mut self, abiv: AbiVersion) -> &mut Self {
@@ -426,6 +1065,36 @@ impl AsRawFd for Chip {
     }
 }
 
+/// Allows the chip to be registered directly with a [mio](https://crates.io/crates/mio) `Poll`.
+///
+/// The chip is readable when it has info change events available.
+/// As mio is edge triggered, all available events should be read
+/// each time the chip is reported as readable.
+#[cfg(feature = "mio")]
+impl mio::event::Source for Chip {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.f.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.f.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.f.as_raw_fd()).deregister(registry)
+    }
+}
+
 /// The publicly available information for a GPIO chip.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(
@@ -455,6 +1124,102 @@ impl From<uapi::ChipInfo> for Info {
     }
 }
 
+/// The features supported by the kernel and library for a chip.
+///
+/// Returned by [`Chip::capabilities`].
+///
+/// Features that depend on the kernel version are assumed to be supported
+/// if the kernel version cannot be determined.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Capabilities {
+    /// The major and minor version of the running kernel, if known.
+    pub kernel: Option<(u32, u32)>,
+
+    /// uAPI v1 is supported.
+    pub abi_v1: bool,
+
+    /// uAPI v2 is supported.
+    pub abi_v2: bool,
+
+    /// Line bias can be set.
+    ///
+    /// Requires uAPI v2, or uAPI v1 on Linux 5.5 or later.
+    pub bias: bool,
+
+    /// Line configuration can be changed without releasing the lines.
+    ///
+    /// Requires uAPI v2, or uAPI v1 on Linux 5.5 or later.
+    pub reconfigure: bool,
+
+    /// Debounce can be applied to input lines.
+    ///
+    /// Requires uAPI v2.
+    pub debounce: bool,
+
+    /// Edge events can be timestamped using `CLOCK_REALTIME`.
+    ///
+    /// Requires uAPI v2 on Linux 5.11 or later.
+    pub event_clock_realtime: bool,
+
+    /// Edge events can be timestamped using the hardware timestamp engine.
+    ///
+    /// Requires uAPI v2 on Linux 5.19 or later, and also that the line is
+    /// supported by a hardware timestamp provider, which cannot be detected
+    /// without requesting the line.
+    pub event_clock_hte: bool,
+
+    /// The maximum number of edge events that the kernel can buffer for a request.
+    ///
+    /// Larger buffer sizes requested are reduced to this size by the kernel.
+    pub max_event_buffer_size: usize,
+}
+
+impl Capabilities {
+    fn new(abi_v1: bool, abi_v2: bool, kernel: Option<(u32, u32)>) -> Capabilities {
+        let since = |version: (u32, u32)| kernel.map_or(true, |k| k >= version);
+        Capabilities {
+            kernel,
+            abi_v1,
+            abi_v2,
+            bias: abi_v2 || (abi_v1 && since((5, 5))),
+            reconfigure: abi_v2 || (abi_v1 && since((5, 5))),
+            debounce: abi_v2,
+            event_clock_realtime: abi_v2 && since((5, 11)),
+            event_clock_hte: abi_v2 && since((5, 19)),
+            max_event_buffer_size: if abi_v2 {
+                // the kernel caps the buffer at 16 events per line
+                gpiocdev_uapi::NUM_LINES_MAX * 16
+            } else {
+                // the v1 kernel event buffer is fixed
+                16
+            },
+        }
+    }
+}
+
+// The major and minor version of the running kernel.
+fn kernel_version() -> Option<(u32, u32)> {
+    let release = fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    parse_kernel_version(&release)
+}
+
+fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut f = release.trim().split('.');
+    let major = f.next()?.parse().ok()?;
+    let minor = f
+        .next()?
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((major, minor))
+}
+
 /// An iterator for reading info change events from a [`Chip`].
 ///
 /// Blocks until events are available.
@@ -469,7 +1234,11 @@ impl<'a> InfoChangeIterator<'a> {
     fn read_event(&mut self) -> Result<InfoChangeEvent> {
         let n = gpiocdev_uapi::read_event(&self.chip.f, &mut self.buf)
             .map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))?;
-        self.chip.line_info_change_event_from_slice(&self.buf[0..n])
+        let evt = self
+            .chip
+            .line_info_change_event_from_slice(&self.buf[0..n])?;
+        self.chip.update_line_info_cache(&evt);
+        Ok(evt)
     }
 }
 
@@ -481,6 +1250,65 @@ impl<'a> Iterator for InfoChangeIterator<'a> {
     }
 }
 
+/// An iterator that returns batches of info change events from the [`Chip`].
+///
+/// Created by [`Chip::info_change_batches`].
+pub struct InfoChangeBatches<'a> {
+    iter: InfoChangeIterator<'a>,
+    settle: Duration,
+}
+
+impl<'a> InfoChangeBatches<'a> {
+    fn read_batch(&mut self) -> Result<InfoChangeBatch> {
+        // block until the burst starts
+        let mut events = vec![self.iter.read_event()?];
+        while self.iter.chip.wait_line_info_change_event(self.settle)? {
+            events.push(self.iter.read_event()?);
+        }
+        Ok(InfoChangeBatch { events })
+    }
+}
+
+impl<'a> Iterator for InfoChangeBatches<'a> {
+    type Item = Result<InfoChangeBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.read_batch())
+    }
+}
+
+/// A burst of info change events.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InfoChangeBatch {
+    /// The events, in the order they occurred.
+    pub events: Vec<InfoChangeEvent>,
+}
+
+impl InfoChangeBatch {
+    /// The offsets of the lines that changed, in ascending order.
+    pub fn offsets(&self) -> Vec<Offset> {
+        let mut offsets: Vec<Offset> = self.events.iter().map(|e| e.info.offset).collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+
+    /// The most recent event for each line that changed, in ascending order of offset.
+    ///
+    /// This reflects the state of each line at the end of the burst.
+    pub fn latest(&self) -> Vec<&InfoChangeEvent> {
+        let mut latest: Vec<&InfoChangeEvent> = Vec::new();
+        for evt in &self.events {
+            match latest.iter_mut().find(|e| e.info.offset == evt.info.offset) {
+                Some(e) => *e = evt,
+                None => latest.push(evt),
+            }
+        }
+        latest.sort_unstable_by_key(|e| e.info.offset);
+        latest
+    }
+}
+
 /// Reasons a file cannot be opened as a GPIO character device.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ErrorKind {
@@ -512,6 +1340,27 @@ mod tests {
     // Chip, ChipIterator and InfoChangeIterator tests are all integration
     // tests as Chip construction requires GPIO chips.
 
+    #[test]
+    #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
+    fn chip_is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Chip>();
+    }
+
+    #[test]
+    fn path_from_id() {
+        assert_eq!(super::path_from_id("3"), PathBuf::from("/dev/gpiochip3"));
+        assert_eq!(
+            super::path_from_id("gpiochip2"),
+            PathBuf::from("/dev/gpiochip2")
+        );
+        assert_eq!(
+            super::path_from_id("/dev/gpiochip1"),
+            PathBuf::from("/dev/gpiochip1")
+        );
+        assert_eq!(super::path_from_id("gpio/chip"), PathBuf::from("gpio/chip"));
+    }
+
     mod info {
         use super::{uapi, Info};
 
@@ -529,6 +1378,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn capabilities() {
+        let caps = Capabilities::new(true, true, Some((6, 1)));
+        assert!(caps.bias && caps.debounce && caps.event_clock_realtime && caps.event_clock_hte);
+        assert_eq!(caps.max_event_buffer_size, 1024);
+
+        let caps = Capabilities::new(true, true, Some((5, 15)));
+        assert!(caps.event_clock_realtime);
+        assert!(!caps.event_clock_hte);
+
+        let caps = Capabilities::new(true, false, Some((5, 4)));
+        assert!(!caps.bias);
+        assert!(!caps.reconfigure);
+        assert!(!caps.debounce);
+        assert!(!caps.event_clock_realtime);
+        assert_eq!(caps.max_event_buffer_size, 16);
+
+        let caps = Capabilities::new(true, false, Some((5, 5)));
+        assert!(caps.bias);
+        assert!(!caps.debounce);
+
+        // unknown kernel versions are assumed to support everything
+        let caps = Capabilities::new(false, true, None);
+        assert!(caps.event_clock_hte);
+    }
+
+    #[test]
+    fn kernel_version() {
+        assert_eq!(parse_kernel_version("6.1.0-rpi7-rpi-v8\n"), Some((6, 1)));
+        assert_eq!(parse_kernel_version("5.15.0-72-generic"), Some((5, 15)));
+        assert_eq!(parse_kernel_version("6.8"), Some((6, 8)));
+        assert_eq!(parse_kernel_version("6.9-rc1"), Some((6, 9)));
+        assert_eq!(parse_kernel_version("garbage"), None);
+    }
+
+    #[test]
+    fn info_change_batch() {
+        use crate::line::InfoChangeKind;
+
+        fn event(offset: Offset, kind: InfoChangeKind) -> InfoChangeEvent {
+            InfoChangeEvent {
+                info: line::Info {
+                    offset,
+                    ..Default::default()
+                },
+                timestamp_ns: 0,
+                kind,
+            }
+        }
+
+        let batch = InfoChangeBatch {
+            events: vec![
+                event(5, InfoChangeKind::Requested),
+                event(2, InfoChangeKind::Requested),
+                event(5, InfoChangeKind::Reconfigured),
+                event(2, InfoChangeKind::Released),
+            ],
+        };
+        assert_eq!(batch.offsets(), vec![2, 5]);
+        let latest = batch.latest();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0], &event(2, InfoChangeKind::Released));
+        assert_eq!(latest[1], &event(5, InfoChangeKind::Reconfigured));
+    }
+
     #[test]
     fn path_compare() {
         use super::path_compare;
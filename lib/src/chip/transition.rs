@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{InfoChangeEvent, InfoChangeKind};
+
+/// A predicate matching a line becoming free, i.e. being released by its consumer.
+///
+/// For use with [`Chip::wait_for_info_change`](super::Chip::wait_for_info_change).
+pub fn became_free() -> impl Fn(&InfoChangeEvent) -> bool {
+    |evt| evt.kind == InfoChangeKind::Released
+}
+
+/// A predicate matching a line being reconfigured by its consumer.
+///
+/// For use with [`Chip::wait_for_info_change`](super::Chip::wait_for_info_change).
+pub fn reconfigured() -> impl Fn(&InfoChangeEvent) -> bool {
+    |evt| evt.kind == InfoChangeKind::Reconfigured
+}
+
+/// A predicate matching a line being requested by a particular consumer.
+///
+/// For use with [`Chip::wait_for_info_change`](super::Chip::wait_for_info_change).
+pub fn requested_by<S: Into<String>>(consumer: S) -> impl Fn(&InfoChangeEvent) -> bool {
+    let consumer = consumer.into();
+    move |evt| evt.kind == InfoChangeKind::Requested && evt.info.consumer == consumer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::Info;
+
+    fn event(kind: InfoChangeKind, consumer: &str) -> InfoChangeEvent {
+        InfoChangeEvent {
+            info: Info {
+                offset: 3,
+                consumer: consumer.into(),
+                used: kind != InfoChangeKind::Released,
+                ..Default::default()
+            },
+            timestamp_ns: 0,
+            kind,
+        }
+    }
+
+    #[test]
+    fn predicates() {
+        let requested = event(InfoChangeKind::Requested, "motor");
+        let reconf = event(InfoChangeKind::Reconfigured, "motor");
+        let released = event(InfoChangeKind::Released, "");
+
+        assert!(!became_free()(&requested));
+        assert!(!became_free()(&reconf));
+        assert!(became_free()(&released));
+
+        assert!(!reconfigured()(&requested));
+        assert!(reconfigured()(&reconf));
+        assert!(!reconfigured()(&released));
+
+        assert!(requested_by("motor")(&requested));
+        assert!(!requested_by("pump")(&requested));
+        assert!(!requested_by("motor")(&reconf));
+        assert!(!requested_by("")(&released));
+    }
+}
@@ -0,0 +1,304 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Periodic sampling of line values.
+//!
+//! A [`DataLogger`] samples the values of the lines in a request at a fixed rate,
+//! for monitoring slowly changing inputs, such as comparators and limit switches,
+//! where edge events are not required but consistent sampling is.
+//!
+//! Sampling is performed by a background thread driven by a periodic timerfd,
+//! so the sample rate is independent of how promptly the samples are consumed.
+//! Samples are delivered to a channel, and may also be written as CSV.
+//!
+//! Sample timestamps are in nanoseconds on `CLOCK_MONOTONIC`, the same clock used
+//! by default for edge event timestamps.
+
+use crate::line::{Offset, Values};
+use crate::scheduler::now_ns;
+use crate::timerfd::TimerFd;
+use crate::{Error, Request, Result};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// The number of samples buffered in the channel before samples are dropped.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// A sample of the values of the lines in a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sample {
+    /// The time the values were read, in nanoseconds on `CLOCK_MONOTONIC`.
+    pub timestamp_ns: u64,
+
+    /// The sample period that the sample was taken in, starting from 0.
+    ///
+    /// Gaps in the sequence indicate periods missed due to overruns.
+    pub seqno: u64,
+
+    /// The values of the lines.
+    pub values: Values,
+}
+
+/// Statistics of a [`DataLogger`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LoggerStats {
+    /// The number of samples taken.
+    pub samples: u64,
+
+    /// The number of sample periods missed as the logger thread was not
+    /// scheduled in time.
+    pub overruns: u64,
+
+    /// The number of samples dropped as the channel was full.
+    pub dropped: u64,
+
+    /// The number of samples that could not be read or written.
+    pub errors: u64,
+}
+
+/// Samples the values of the lines in a request at a fixed rate.
+///
+/// Samples are delivered to the [`Receiver`] returned with the logger.
+/// The channel buffers up to 1024 samples, beyond which samples are dropped
+/// and recorded in the [`LoggerStats`].
+///
+/// Sampling stops when the logger is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::datalog::DataLogger;
+/// # use std::time::Duration;
+/// # fn main() -> Result<()> {
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_lines(&[17, 22])
+///     .as_input()
+///     .request()?;
+/// let csv = std::fs::File::create("limits.csv")?;
+/// let (logger, samples) =
+///     DataLogger::with_csv(&req, Duration::from_millis(10), std::io::BufWriter::new(csv))?;
+/// for sample in samples.iter().take(100) {
+///     println!("{}: {}", sample.timestamp_ns, sample.values);
+/// }
+/// println!("{:?}", logger.stats());
+/// # Ok(())
+/// # }
+/// ```
+pub struct DataLogger {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    timer: TimerFd,
+    stats: Mutex<LoggerStats>,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, LoggerStats> {
+        self.stats
+            .lock()
+            .expect("failed to acquire lock on logger stats")
+    }
+}
+
+struct Sampler {
+    req: Request,
+    offsets: Vec<Offset>,
+    tx: SyncSender<Sample>,
+    csv: Option<Box<dyn Write + Send>>,
+}
+
+impl Sampler {
+    fn run(mut self, shared: &Shared) {
+        let mut seqno = 0;
+        if let Some(csv) = &mut self.csv {
+            if write_header(csv, &self.offsets).is_err() {
+                shared.lock().errors += 1;
+            }
+        }
+        loop {
+            // Errors, such as EINTR, only result in an early check to stop.
+            let expirations = shared.timer.read().unwrap_or(0);
+            if shared.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if expirations == 0 {
+                continue;
+            }
+            let mut values = Values::from_offsets(&self.offsets);
+            let res = self.req.values(&mut values);
+            let timestamp_ns = now_ns();
+            let mut stats = shared.lock();
+            stats.overruns += expirations - 1;
+            seqno += expirations;
+            if res.is_err() {
+                stats.errors += 1;
+                continue;
+            }
+            let sample = Sample {
+                timestamp_ns,
+                seqno: seqno - 1,
+                values,
+            };
+            stats.samples += 1;
+            if let Some(csv) = &mut self.csv {
+                if write_row(csv, &self.offsets, &sample).is_err() {
+                    stats.errors += 1;
+                }
+            }
+            match self.tx.try_send(sample) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => stats.dropped += 1,
+                // the samples may still be written to the CSV
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+        if let Some(csv) = &mut self.csv {
+            if csv.flush().is_err() {
+                shared.lock().errors += 1;
+            }
+        }
+    }
+}
+
+impl DataLogger {
+    /// Start sampling the values of the lines in a request.
+    ///
+    /// * `req` - the request containing the lines to sample.
+    /// * `period` - the period between samples.
+    ///
+    /// The first sample is taken one period after the logger is created.
+    ///
+    /// The logger shares the underlying request, so the request remains usable
+    /// by the caller.
+    pub fn new(req: &Request, period: Duration) -> Result<(DataLogger, Receiver<Sample>)> {
+        DataLogger::start(req, period, None)
+    }
+
+    /// Start sampling the values of the lines in a request, also writing the
+    /// samples as CSV.
+    ///
+    /// As per [`new`], but each sample is also written to `csv` as a row of the
+    /// timestamp, the seqno, and the value of each line, as 0 or 1, in offset order.
+    /// The first row is a header containing the line offsets.
+    ///
+    /// The writer is flushed when the logger is dropped.
+    ///
+    /// [`new`]: DataLogger::new
+    pub fn with_csv<W: Write + Send + 'static>(
+        req: &Request,
+        period: Duration,
+        csv: W,
+    ) -> Result<(DataLogger, Receiver<Sample>)> {
+        DataLogger::start(req, period, Some(Box::new(csv)))
+    }
+
+    fn start(
+        req: &Request,
+        period: Duration,
+        csv: Option<Box<dyn Write + Send>>,
+    ) -> Result<(DataLogger, Receiver<Sample>)> {
+        if period.is_zero() {
+            return Err(Error::InvalidArgument(
+                "period must be non-zero.".to_string(),
+            ));
+        }
+        let mut offsets = req.lines().to_vec();
+        offsets.sort_unstable();
+        let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let sampler = Sampler {
+            req: req.try_clone()?,
+            offsets,
+            tx,
+            csv,
+        };
+        let shared = Arc::new(Shared {
+            timer: TimerFd::new(false)?,
+            stats: Mutex::new(LoggerStats::default()),
+            stop: AtomicBool::new(false),
+        });
+        shared.timer.set_periodic(period)?;
+        let thread_shared = shared.clone();
+        let thread = std::thread::spawn(move || sampler.run(&thread_shared));
+        Ok((
+            DataLogger {
+                shared,
+                thread: Some(thread),
+            },
+            rx,
+        ))
+    }
+
+    /// The statistics of the logger.
+    pub fn stats(&self) -> LoggerStats {
+        *self.shared.lock()
+    }
+}
+
+impl Drop for DataLogger {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        // wake the thread
+        let _ = self.shared.timer.set_deadline(Duration::ZERO);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn write_header(w: &mut dyn Write, offsets: &[Offset]) -> std::io::Result<()> {
+    write!(w, "timestamp_ns,seqno")?;
+    for offset in offsets {
+        write!(w, ",{}", offset)?;
+    }
+    writeln!(w)
+}
+
+fn write_row(w: &mut dyn Write, offsets: &[Offset], sample: &Sample) -> std::io::Result<()> {
+    write!(w, "{},{}", sample.timestamp_ns, sample.seqno)?;
+    for offset in offsets {
+        match sample.values.get(*offset) {
+            Some(value) => write!(w, ",{}", u8::from(value))?,
+            None => write!(w, ",")?,
+        }
+    }
+    writeln!(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::Value;
+
+    #[test]
+    fn csv() {
+        let mut buf = Vec::new();
+        let offsets = [2, 5, 7];
+        write_header(&mut buf, &offsets).unwrap();
+        let sample = Sample {
+            timestamp_ns: 1234,
+            seqno: 3,
+            values: Values::from([(2, Value::Active), (5, Value::Inactive), (7, Value::Active)]),
+        };
+        write_row(&mut buf, &offsets, &sample).unwrap();
+        let sample = Sample {
+            timestamp_ns: 5678,
+            seqno: 5,
+            values: Values::from([(2, Value::Inactive), (7, Value::Active)]),
+        };
+        write_row(&mut buf, &offsets, &sample).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "timestamp_ns,seqno,2,5,7\n1234,3,1,0,1\n5678,5,0,,1\n"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Dead-man supervision of an output line.
+//!
+//! A [`Supervisor`] holds an output line and expects periodic heartbeats.
+//! If no heartbeat is received within the timeout then the supervisor trips and
+//! drives the line to its safe value.
+//!
+//! Heartbeats may be sent from the supervising process using [`Supervisor::alive`],
+//! or from another process, via the supervisor socket, using [`Heartbeat::alive`].
+//!
+//! The supervisor socket is a Unix datagram socket, so any datagram sent to the socket
+//! is treated as a heartbeat.  e.g. `echo | socat - UNIX-SENDTO:/run/deadman.sock`.
+
+use crate::line::{Offset, Value};
+use crate::request::Worker;
+use crate::{Error, Request, Result};
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Drives an output line to a safe value if heartbeats stop.
+///
+/// The supervisor starts timing from when it is created, so the first heartbeat
+/// must be received within the timeout.
+///
+/// Once tripped the supervisor stays tripped, and further heartbeats are ignored,
+/// until it is [`reset`].
+///
+/// The supervisor socket is removed when the supervisor is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::deadman::Supervisor;
+/// # use gpiocdev::line::Value;
+/// # use std::time::Duration;
+/// # fn main() -> Result<()> {
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_line(17)
+///     .as_output(Value::Active)
+///     .request()?;
+/// let sup = Supervisor::new(
+///     &req,
+///     17,
+///     Value::Inactive,
+///     Duration::from_secs(1),
+///     "/run/motor.sock",
+/// )?;
+/// sup.wait_trip();
+/// println!("motor stopped - heartbeat lost");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`reset`]: Supervisor::reset
+pub struct Supervisor {
+    shared: Arc<Shared>,
+    path: PathBuf,
+    _worker: Worker,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    tripped: Condvar,
+}
+
+struct State {
+    last_alive: Instant,
+    tripped: bool,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("failed to acquire lock on supervisor state")
+    }
+
+    fn alive(&self) {
+        let mut state = self.lock();
+        if !state.tripped {
+            state.last_alive = Instant::now();
+        }
+    }
+}
+
+impl Supervisor {
+    /// Start supervising a line.
+    ///
+    /// * `req` - the request containing the line.
+    /// * `offset` - the line to drive to the safe value on a trip.
+    /// * `safe` - the safe value for the line.
+    /// * `timeout` - the maximum period between heartbeats.
+    /// * `path` - the path of the socket to receive heartbeats on.
+    ///
+    /// Any stale socket at the path is replaced.
+    pub fn new<P: AsRef<Path>>(
+        req: &Request,
+        offset: Offset,
+        safe: Value,
+        timeout: Duration,
+        path: P,
+    ) -> Result<Supervisor> {
+        if !req.config().lines().contains(&offset) {
+            return Err(Error::InvalidArgument(
+                "offset is not a requested line.".to_string(),
+            ));
+        }
+        if timeout.is_zero() {
+            return Err(Error::InvalidArgument(
+                "timeout must be non-zero.".to_string(),
+            ));
+        }
+        let path = path.as_ref().to_path_buf();
+        if let Ok(md) = std::fs::symlink_metadata(&path) {
+            if md.file_type().is_socket() {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        let sock = UnixDatagram::bind(&path)?;
+        sock.set_nonblocking(true)?;
+        let req = req.try_clone()?;
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                last_alive: Instant::now(),
+                tripped: false,
+            }),
+            tripped: Condvar::new(),
+        });
+        let thread_shared = shared.clone();
+        let worker = Worker::spawn(move |stop| {
+            let mut buf = [0; 16];
+            loop {
+                let wait = {
+                    let mut state = thread_shared.lock();
+                    let remaining = timeout.saturating_sub(state.last_alive.elapsed());
+                    if !state.tripped && remaining.is_zero() {
+                        // the trip is latched even if driving the line fails.
+                        let _ = req.set_value(offset, safe);
+                        state.tripped = true;
+                        thread_shared.tripped.notify_all();
+                    }
+                    // once tripped, recheck within each timeout period, so the
+                    // timeout restarted by a reset is enforced.
+                    if state.tripped {
+                        timeout
+                    } else {
+                        remaining
+                    }
+                };
+                match stop.wait_any(&[&sock], Some(wait))? {
+                    None => return Ok(()),
+                    Some(ready) if ready.is_empty() => continue,
+                    Some(_) => {}
+                }
+                match sock.recv(&mut buf) {
+                    Ok(_) => thread_shared.alive(),
+                    Err(e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        })?;
+        Ok(Supervisor {
+            shared,
+            path,
+            _worker: worker,
+        })
+    }
+
+    /// Send a heartbeat from within the supervising process.
+    pub fn alive(&self) {
+        self.shared.alive()
+    }
+
+    /// Returns true if the supervisor has tripped.
+    pub fn is_tripped(&self) -> bool {
+        self.shared.lock().tripped
+    }
+
+    /// Block until the supervisor trips.
+    pub fn wait_trip(&self) {
+        let mut state = self.shared.lock();
+        while !state.tripped {
+            state = self
+                .shared
+                .tripped
+                .wait(state)
+                .expect("failed to acquire lock on supervisor state");
+        }
+    }
+
+    /// Clear a trip and restart the heartbeat timeout.
+    ///
+    /// The line is left at the safe value, so restoring the line to its operating
+    /// value is left to the caller.
+    pub fn reset(&self) {
+        let mut state = self.shared.lock();
+        state.tripped = false;
+        state.last_alive = Instant::now();
+    }
+
+    /// The path of the supervisor socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Sends heartbeats to a [`Supervisor`] in another process.
+#[derive(Debug)]
+pub struct Heartbeat {
+    sock: UnixDatagram,
+}
+
+impl Heartbeat {
+    /// Connect to the socket of a supervisor.
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<Heartbeat> {
+        let sock = UnixDatagram::unbound()?;
+        sock.connect(path)?;
+        Ok(Heartbeat { sock })
+    }
+
+    /// Send a heartbeat to the supervisor.
+    pub fn alive(&self) -> Result<()> {
+        self.sock.send(b"alive")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeat() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("gpiocdev-deadman-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sock = UnixDatagram::bind(&path).unwrap();
+        let hb = Heartbeat::connect(&path).unwrap();
+        hb.alive().unwrap();
+        let mut buf = [0; 16];
+        assert_eq!(sock.recv(&mut buf).unwrap(), 5);
+        drop(sock);
+        std::fs::remove_file(&path).unwrap();
+
+        // no supervisor
+        assert!(Heartbeat::connect(&path).is_err());
+    }
+}
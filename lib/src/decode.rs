@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Decoding protocols from edge events.
+//!
+//! A [`Decoder`] is fed the edge events from the lines carrying a protocol, and
+//! returns the frames decoded from them.
+//!
+//! Decoders are provided for [`Uart`], [`I2c`], [`Wiegand`] and [`Nec`] infrared
+//! remote controls, and the trait may be implemented for other protocols.
+//!
+//! Decoding is limited by the accuracy of the edge event timestamps and the
+//! rate at which the kernel can generate events, so is only suitable for slow
+//! signals, such as low baud rate serial or a 100kHz I2C bus.
+//! The lines must be requested with edge detection on both edges, and without
+//! debouncing or active-low, so the edges reflect the physical signal.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::decode::{Decoder, Uart};
+//! # use gpiocdev::line::EdgeDetection;
+//! # fn main() -> Result<()> {
+//! let req = gpiocdev::Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_line(15)
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let mut uart = Uart::new(15, 1200);
+//! for evt in req.edge_events() {
+//!     if let Some(frame) = uart.feed(&evt?) {
+//!         println!("{}", frame);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::line::EdgeEvent;
+
+mod i2c;
+pub use self::i2c::{I2c, I2cFrame};
+
+mod nec;
+pub use self::nec::{Nec, NecFrame};
+
+mod uart;
+pub use self::uart::{Uart, UartFrame};
+
+mod wiegand;
+pub use self::wiegand::{Wiegand, WiegandFrame};
+
+/// Decodes frames of a protocol from edge events.
+pub trait Decoder {
+    /// The frames decoded from the protocol.
+    type Frame;
+
+    /// Feed an edge event to the decoder, returning any frame completed by it.
+    ///
+    /// Events must be fed in the order they occurred.
+    /// Events from lines not carrying the protocol are ignored.
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<Self::Frame>;
+
+    /// Notify the decoder that no edges have occurred up to the timestamp,
+    /// returning any frame completed by the line remaining idle.
+    ///
+    /// This is required by protocols, such as UART and Wiegand, where the end
+    /// of a frame is not marked by an edge.
+    ///
+    /// The timestamp must be from the same clock as the edge events.
+    fn idle(&mut self, _timestamp_ns: u64) -> Option<Self::Frame> {
+        None
+    }
+}
+
+// Returns true if the period is within 25% of the nominal period.
+fn near(period_ns: u64, nominal_ns: u64) -> bool {
+    let tolerance = nominal_ns / 4;
+    period_ns >= nominal_ns - tolerance && period_ns <= nominal_ns + tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line::{EdgeEvent, EdgeKind, Offset};
+
+    pub(super) fn edge(offset: Offset, kind: EdgeKind, timestamp_ns: u64) -> EdgeEvent {
+        EdgeEvent {
+            timestamp_ns,
+            kind,
+            offset,
+            seqno: 0,
+            line_seqno: 0,
+        }
+    }
+
+    #[test]
+    fn near() {
+        assert!(super::near(1000, 1000));
+        assert!(super::near(750, 1000));
+        assert!(super::near(1250, 1000));
+        assert!(!super::near(749, 1000));
+        assert!(!super::near(1251, 1000));
+    }
+}
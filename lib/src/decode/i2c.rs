@@ -0,0 +1,257 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Decoder;
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+
+/// A transfer observed by an [`I2c`] decoder.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct I2cFrame {
+    /// The 7-bit address of the target device.
+    pub address: u8,
+
+    /// The transfer is a read from the target.
+    pub read: bool,
+
+    /// The bytes transferred after the address.
+    pub data: Vec<u8>,
+
+    /// The acknowledgement of each byte, starting with the address.
+    pub acks: Vec<bool>,
+}
+
+impl std::fmt::Display for I2cFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dir = if self.read { "read" } else { "write" };
+        write!(f, "{:#04x} {}", self.address, dir)?;
+        for (idx, byte) in self.data.iter().enumerate() {
+            write!(f, " {:02x}", byte)?;
+            if self.acks.get(idx + 1) == Some(&false) {
+                write!(f, "*")?;
+            }
+        }
+        if self.acks.first() == Some(&false) {
+            write!(f, " nack")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the transfers on an I2C bus.
+///
+/// Each transfer is returned once it is terminated by a stop or repeated start condition.
+/// In the [`Display`](std::fmt::Display) rendering of a transfer, bytes that were
+/// not acknowledged are marked with a `*`.
+#[derive(Clone, Debug)]
+pub struct I2c {
+    scl: Offset,
+    sda: Offset,
+    scl_high: bool,
+    sda_high: bool,
+    // a start condition has been seen
+    in_transfer: bool,
+    byte: u8,
+    num_bits: u32,
+    bytes: Vec<u8>,
+    acks: Vec<bool>,
+}
+
+impl I2c {
+    /// Create a decoder for the given clock and data lines.
+    ///
+    /// The bus is assumed to be idle, with both lines high, when the decoder is created.
+    pub fn new(scl: Offset, sda: Offset) -> I2c {
+        I2c {
+            scl,
+            sda,
+            scl_high: true,
+            sda_high: true,
+            in_transfer: false,
+            byte: 0,
+            num_bits: 0,
+            bytes: Vec::new(),
+            acks: Vec::new(),
+        }
+    }
+
+    // Complete the current transfer.
+    fn take(&mut self) -> Option<I2cFrame> {
+        self.byte = 0;
+        self.num_bits = 0;
+        let acks = std::mem::take(&mut self.acks);
+        let mut bytes = std::mem::take(&mut self.bytes);
+        if !self.in_transfer || bytes.is_empty() {
+            return None;
+        }
+        let addr = bytes.remove(0);
+        Some(I2cFrame {
+            address: addr >> 1,
+            read: addr & 1 == 1,
+            data: bytes,
+            acks,
+        })
+    }
+
+    // Sample the data line on the rising edge of the clock.
+    fn clock(&mut self) {
+        if !self.in_transfer {
+            return;
+        }
+        if self.num_bits < 8 {
+            self.byte = (self.byte << 1) | u8::from(self.sda_high);
+            self.num_bits += 1;
+        } else {
+            // ack is the target pulling the data line low
+            self.bytes.push(self.byte);
+            self.acks.push(!self.sda_high);
+            self.byte = 0;
+            self.num_bits = 0;
+        }
+    }
+}
+
+impl Decoder for I2c {
+    type Frame = I2cFrame;
+
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<I2cFrame> {
+        let rising = evt.kind == EdgeKind::Rising;
+        if evt.offset == self.scl {
+            self.scl_high = rising;
+            if rising {
+                self.clock();
+            }
+            None
+        } else if evt.offset == self.sda {
+            self.sda_high = rising;
+            if !self.scl_high {
+                return None;
+            }
+            // data changing while the clock is high is a start or stop condition
+            let frame = self.take();
+            self.in_transfer = !rising;
+            frame
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::edge;
+    use super::*;
+
+    const SCL: Offset = 1;
+    const SDA: Offset = 2;
+
+    struct Bus {
+        t: u64,
+        scl: bool,
+        sda: bool,
+        edges: Vec<EdgeEvent>,
+    }
+
+    impl Bus {
+        fn new() -> Bus {
+            Bus {
+                t: 0,
+                scl: true,
+                sda: true,
+                edges: Vec::new(),
+            }
+        }
+
+        fn drive(&mut self, offset: Offset, level: bool) {
+            let current = if offset == SCL {
+                &mut self.scl
+            } else {
+                &mut self.sda
+            };
+            if *current != level {
+                *current = level;
+                let kind = if level {
+                    EdgeKind::Rising
+                } else {
+                    EdgeKind::Falling
+                };
+                self.edges.push(edge(offset, kind, self.t));
+            }
+            self.t += 1000;
+        }
+
+        fn start(&mut self) {
+            self.drive(SDA, true);
+            self.drive(SCL, true);
+            self.drive(SDA, false);
+            self.drive(SCL, false);
+        }
+
+        fn stop(&mut self) {
+            self.drive(SDA, false);
+            self.drive(SCL, true);
+            self.drive(SDA, true);
+        }
+
+        fn bit(&mut self, bit: bool) {
+            self.drive(SDA, bit);
+            self.drive(SCL, true);
+            self.drive(SCL, false);
+        }
+
+        fn byte(&mut self, byte: u8, ack: bool) {
+            for i in (0..8).rev() {
+                self.bit(byte & (1 << i) != 0);
+            }
+            self.bit(!ack);
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let mut bus = Bus::new();
+        // write register address 0x10 to device 0x50...
+        bus.start();
+        bus.byte(0x50 << 1, true);
+        bus.byte(0x10, true);
+        // ...then read two bytes from it with a repeated start
+        bus.start();
+        bus.byte((0x50 << 1) | 1, true);
+        bus.byte(0xab, true);
+        bus.byte(0xcd, false);
+        bus.stop();
+        // then a transfer to a missing device
+        bus.start();
+        bus.byte(0x20 << 1, false);
+        bus.stop();
+
+        let mut i2c = I2c::new(SCL, SDA);
+        let frames: Vec<I2cFrame> = bus.edges.iter().filter_map(|e| i2c.feed(e)).collect();
+        assert_eq!(
+            frames,
+            [
+                I2cFrame {
+                    address: 0x50,
+                    read: false,
+                    data: vec![0x10],
+                    acks: vec![true, true],
+                },
+                I2cFrame {
+                    address: 0x50,
+                    read: true,
+                    data: vec![0xab, 0xcd],
+                    acks: vec![true, true, false],
+                },
+                I2cFrame {
+                    address: 0x20,
+                    read: false,
+                    data: vec![],
+                    acks: vec![false],
+                },
+            ]
+        );
+        assert_eq!(format!("{}", frames[0]), "0x50 write 10");
+        assert_eq!(format!("{}", frames[1]), "0x50 read ab cd*");
+        assert_eq!(format!("{}", frames[2]), "0x20 write nack");
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{near, Decoder};
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+
+const LEADER_MARK_NS: u64 = 9_000_000;
+const LEADER_SPACE_NS: u64 = 4_500_000;
+const REPEAT_SPACE_NS: u64 = 2_250_000;
+const BIT_MARK_NS: u64 = 562_500;
+const ZERO_SPACE_NS: u64 = 562_500;
+const ONE_SPACE_NS: u64 = 1_687_500;
+
+/// A command received by a [`Nec`] decoder.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NecFrame {
+    /// The address of the device.
+    ///
+    /// Extended NEC addresses are 16 bits, otherwise the address is 8 bits.
+    pub address: u16,
+
+    /// The command.
+    pub command: u8,
+
+    /// The frame is a repeat code, indicating the previous command is being held.
+    pub repeat: bool,
+}
+
+impl std::fmt::Display for NecFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "address={:#04x} command={:#04x}",
+            self.address, self.command
+        )?;
+        if self.repeat {
+            write!(f, " repeat")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum State {
+    Idle,
+    // The leader mark has been received.
+    Leader,
+    // Receiving the data bits, least significant first.
+    Bits(u32, u32),
+}
+
+/// Decodes the commands received from an NEC infrared remote control.
+///
+/// The line is the output of an infrared receiver module, which is low while
+/// the carrier is detected.
+#[derive(Clone, Debug)]
+pub struct Nec {
+    offset: Offset,
+    state: State,
+    last_fall_ns: Option<u64>,
+    last_rise_ns: Option<u64>,
+    last: Option<NecFrame>,
+}
+
+impl Nec {
+    /// Create a decoder for the line.
+    pub fn new(offset: Offset) -> Nec {
+        Nec {
+            offset,
+            state: State::Idle,
+            last_fall_ns: None,
+            last_rise_ns: None,
+            last: None,
+        }
+    }
+
+    // The end of a mark.
+    fn mark(&mut self, mark_ns: u64) {
+        if near(mark_ns, LEADER_MARK_NS) {
+            self.state = State::Leader;
+        } else if !near(mark_ns, BIT_MARK_NS) {
+            self.state = State::Idle;
+        }
+    }
+
+    // The end of a space, which is the start of the next mark.
+    fn space(&mut self, space_ns: u64) -> Option<NecFrame> {
+        match self.state {
+            State::Leader if near(space_ns, LEADER_SPACE_NS) => {
+                self.state = State::Bits(0, 0);
+                None
+            }
+            State::Leader if near(space_ns, REPEAT_SPACE_NS) => {
+                self.state = State::Idle;
+                self.last.map(|f| NecFrame { repeat: true, ..f })
+            }
+            State::Bits(bits, count) => {
+                let bit = if near(space_ns, ONE_SPACE_NS) {
+                    1
+                } else if near(space_ns, ZERO_SPACE_NS) {
+                    0
+                } else {
+                    self.state = State::Idle;
+                    return None;
+                };
+                let bits = bits | (bit << count);
+                if count < 31 {
+                    self.state = State::Bits(bits, count + 1);
+                    return None;
+                }
+                self.state = State::Idle;
+                self.last = decode(bits);
+                self.last
+            }
+            _ => {
+                self.state = State::Idle;
+                None
+            }
+        }
+    }
+}
+
+// Decode the 32 data bits, checking the command is followed by its inverse.
+fn decode(bits: u32) -> Option<NecFrame> {
+    let [addr, addr_inv, command, command_inv] = bits.to_le_bytes();
+    if command != !command_inv {
+        return None;
+    }
+    let address = if addr == !addr_inv {
+        u16::from(addr)
+    } else {
+        u16::from_le_bytes([addr, addr_inv])
+    };
+    Some(NecFrame {
+        address,
+        command,
+        repeat: false,
+    })
+}
+
+impl Decoder for Nec {
+    type Frame = NecFrame;
+
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<NecFrame> {
+        if evt.offset != self.offset {
+            return None;
+        }
+        let t = evt.timestamp_ns;
+        match evt.kind {
+            EdgeKind::Falling => {
+                let frame = match self.last_rise_ns {
+                    Some(rise) => self.space(t.saturating_sub(rise)),
+                    None => None,
+                };
+                self.last_fall_ns = Some(t);
+                frame
+            }
+            EdgeKind::Rising => {
+                if let Some(fall) = self.last_fall_ns {
+                    self.mark(t.saturating_sub(fall));
+                }
+                self.last_rise_ns = Some(t);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::edge;
+    use super::*;
+
+    // The (mark, space) periods for the frame, ending with the stop mark.
+    fn frame(bits: u32) -> Vec<(u64, u64)> {
+        let mut periods = vec![(LEADER_MARK_NS, LEADER_SPACE_NS)];
+        for i in 0..32 {
+            let space = if bits & (1 << i) != 0 {
+                ONE_SPACE_NS
+            } else {
+                ZERO_SPACE_NS
+            };
+            periods.push((BIT_MARK_NS, space));
+        }
+        periods.push((BIT_MARK_NS, 40_000_000));
+        periods
+    }
+
+    fn transmit(nec: &mut Nec, start_ns: u64, periods: &[(u64, u64)]) -> Vec<NecFrame> {
+        let mut frames = Vec::new();
+        let mut t = start_ns;
+        for (mark, space) in periods {
+            frames.extend(nec.feed(&edge(2, EdgeKind::Falling, t)));
+            t += mark;
+            frames.extend(nec.feed(&edge(2, EdgeKind::Rising, t)));
+            t += space;
+        }
+        frames
+    }
+
+    #[test]
+    fn decode() {
+        let mut nec = Nec::new(2);
+        // address 0x04, command 0x08
+        let frames = transmit(&mut nec, 0, &frame(0xf708_fb04));
+        assert_eq!(
+            frames,
+            [NecFrame {
+                address: 0x04,
+                command: 0x08,
+                repeat: false
+            }]
+        );
+        assert_eq!(format!("{}", frames[0]), "address=0x04 command=0x08");
+
+        // repeat code
+        let repeat = [(LEADER_MARK_NS, REPEAT_SPACE_NS), (BIT_MARK_NS, 0)];
+        let frames = transmit(&mut nec, 200_000_000, &repeat);
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].repeat);
+        assert_eq!(frames[0].command, 0x08);
+
+        // extended address
+        let frames = transmit(&mut nec, 400_000_000, &frame(0xe619_1234));
+        assert_eq!(frames[0].address, 0x1234);
+        assert_eq!(frames[0].command, 0x19);
+
+        // corrupt command
+        assert!(transmit(&mut nec, 600_000_000, &frame(0xe618_1234)).is_empty());
+
+        // events from other lines are ignored
+        assert_eq!(nec.feed(&edge(3, EdgeKind::Falling, 0)), None);
+    }
+}
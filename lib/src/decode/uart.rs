@@ -0,0 +1,176 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Decoder;
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+
+// The number of bits in a frame - start, 8 data, and stop.
+const FRAME_BITS: u32 = 10;
+
+/// A byte received by a [`Uart`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UartFrame {
+    /// The received byte.
+    pub data: u8,
+
+    /// The start bit was not low or the stop bit was not high.
+    pub framing_error: bool,
+}
+
+impl std::fmt::Display for UartFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#04x}", self.data)?;
+        if self.data.is_ascii_graphic() {
+            write!(f, " '{}'", self.data as char)?;
+        }
+        if self.framing_error {
+            write!(f, " framing error")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the bytes received on an asynchronous serial line.
+///
+/// The line format is 8 data bits, no parity and one stop bit, with the line idle high.
+#[derive(Clone, Debug)]
+pub struct Uart {
+    offset: Offset,
+    bit_ns: u64,
+    // the line is high
+    level: bool,
+    // the start of the frame being received
+    start_ns: Option<u64>,
+    // the bits sampled from the frame, start bit first
+    samples: u16,
+    num_samples: u32,
+}
+
+impl Uart {
+    /// Create a decoder for the line with the given baud rate.
+    pub fn new(offset: Offset, baud: u32) -> Uart {
+        Uart {
+            offset,
+            bit_ns: 1_000_000_000 / u64::from(baud.max(1)),
+            level: true,
+            start_ns: None,
+            samples: 0,
+            num_samples: 0,
+        }
+    }
+
+    // Sample the bits up to the timestamp, returning the frame once all bits are sampled.
+    fn sample_until(&mut self, timestamp_ns: u64) -> Option<UartFrame> {
+        let start_ns = self.start_ns?;
+        while self.num_samples < FRAME_BITS {
+            // sample in the middle of each bit
+            let t = start_ns + u64::from(self.num_samples) * self.bit_ns + self.bit_ns / 2;
+            if t >= timestamp_ns {
+                return None;
+            }
+            if self.level {
+                self.samples |= 1 << self.num_samples;
+            }
+            self.num_samples += 1;
+        }
+        let frame = UartFrame {
+            data: (self.samples >> 1) as u8,
+            framing_error: self.samples & 1 != 0 || self.samples & (1 << (FRAME_BITS - 1)) == 0,
+        };
+        self.start_ns = None;
+        self.samples = 0;
+        self.num_samples = 0;
+        Some(frame)
+    }
+}
+
+impl Decoder for Uart {
+    type Frame = UartFrame;
+
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<UartFrame> {
+        if evt.offset != self.offset {
+            return None;
+        }
+        let frame = self.sample_until(evt.timestamp_ns);
+        self.level = evt.kind == EdgeKind::Rising;
+        if self.start_ns.is_none() && !self.level {
+            self.start_ns = Some(evt.timestamp_ns);
+        }
+        frame
+    }
+
+    fn idle(&mut self, timestamp_ns: u64) -> Option<UartFrame> {
+        self.sample_until(timestamp_ns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::edge;
+    use super::*;
+
+    // The edges transmitting the bytes, starting at the given time.
+    fn transmit(offset: Offset, bit_ns: u64, start_ns: u64, data: &[u8]) -> Vec<EdgeEvent> {
+        let mut edges = Vec::new();
+        let mut level = true;
+        let mut t = start_ns;
+        for byte in data {
+            let bits = ((u16::from(*byte)) << 1) | (1 << 9);
+            for i in 0..FRAME_BITS {
+                let bit = bits & (1 << i) != 0;
+                if bit != level {
+                    let kind = if bit {
+                        EdgeKind::Rising
+                    } else {
+                        EdgeKind::Falling
+                    };
+                    edges.push(edge(offset, kind, t));
+                    level = bit;
+                }
+                t += bit_ns;
+            }
+        }
+        edges
+    }
+
+    #[test]
+    fn decode() {
+        let mut uart = Uart::new(3, 9600);
+        let bit_ns = 1_000_000_000 / 9600;
+        let mut frames = Vec::new();
+        for evt in transmit(3, bit_ns, 1000, b"Hi\xff\x00") {
+            frames.extend(uart.feed(&evt));
+        }
+        // the stop bit of the final byte is only sampled once the line idles
+        assert_eq!(frames.len(), 3);
+        assert_eq!(uart.idle(1000 + 40 * bit_ns - bit_ns / 2), None);
+        frames.extend(uart.idle(1000 + 40 * bit_ns));
+        let data: Vec<u8> = frames.iter().map(|f| f.data).collect();
+        assert_eq!(data, b"Hi\xff\x00");
+        assert!(frames.iter().all(|f| !f.framing_error));
+
+        // a break has no stop bit
+        uart.feed(&edge(3, EdgeKind::Falling, 100 * bit_ns));
+        let frame = uart.idle(111 * bit_ns).unwrap();
+        assert_eq!(frame.data, 0);
+        assert!(frame.framing_error);
+
+        // events from other lines are ignored
+        assert_eq!(uart.feed(&edge(4, EdgeKind::Falling, 0)), None);
+    }
+
+    #[test]
+    fn display() {
+        let f = UartFrame {
+            data: b'A',
+            framing_error: false,
+        };
+        assert_eq!(format!("{}", f), "0x41 'A'");
+        let f = UartFrame {
+            data: 0x0a,
+            framing_error: true,
+        };
+        assert_eq!(format!("{}", f), "0x0a framing error");
+    }
+}
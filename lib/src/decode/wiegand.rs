@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Decoder;
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+use std::time::Duration;
+
+/// A frame received by a [`Wiegand`] decoder.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WiegandFrame {
+    /// The received bits, in the order received.
+    pub bits: Vec<bool>,
+}
+
+impl WiegandFrame {
+    /// The number of bits in the frame.
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns true if the frame contains no bits.
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// The bits of the frame as an integer, with the first bit received as the most significant.
+    ///
+    /// Only the last 64 bits are included for longer frames.
+    pub fn value(&self) -> u64 {
+        self.bits
+            .iter()
+            .fold(0, |acc, bit| (acc << 1) | u64::from(*bit))
+    }
+}
+
+impl std::fmt::Display for WiegandFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bits {:#x}", self.len(), self.value())
+    }
+}
+
+/// Decodes the frames received from a Wiegand access control reader.
+///
+/// Each bit is a low pulse on one of the two data lines, D0 for a 0 and D1 for a 1,
+/// and frames are separated by a gap with no pulses.
+#[derive(Clone, Debug)]
+pub struct Wiegand {
+    d0: Offset,
+    d1: Offset,
+    gap_ns: u64,
+    bits: Vec<bool>,
+    last_ns: u64,
+}
+
+impl Wiegand {
+    /// Create a decoder for the given data lines.
+    ///
+    /// The default gap between frames is 20ms.
+    pub fn new(d0: Offset, d1: Offset) -> Wiegand {
+        Wiegand {
+            d0,
+            d1,
+            gap_ns: 20_000_000,
+            bits: Vec::new(),
+            last_ns: 0,
+        }
+    }
+
+    /// Set the minimum gap between frames.
+    pub fn with_gap(mut self, gap: Duration) -> Wiegand {
+        self.gap_ns = gap.as_nanos() as u64;
+        self
+    }
+
+    // Take the frame if the gap has elapsed since the last bit.
+    fn take_if_gap(&mut self, timestamp_ns: u64) -> Option<WiegandFrame> {
+        if self.bits.is_empty() || timestamp_ns.saturating_sub(self.last_ns) < self.gap_ns {
+            return None;
+        }
+        Some(WiegandFrame {
+            bits: std::mem::take(&mut self.bits),
+        })
+    }
+}
+
+impl Decoder for Wiegand {
+    type Frame = WiegandFrame;
+
+    fn feed(&mut self, evt: &EdgeEvent) -> Option<WiegandFrame> {
+        if evt.kind != EdgeKind::Falling || (evt.offset != self.d0 && evt.offset != self.d1) {
+            return None;
+        }
+        let frame = self.take_if_gap(evt.timestamp_ns);
+        self.bits.push(evt.offset == self.d1);
+        self.last_ns = evt.timestamp_ns;
+        frame
+    }
+
+    fn idle(&mut self, timestamp_ns: u64) -> Option<WiegandFrame> {
+        self.take_if_gap(timestamp_ns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::edge;
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    fn pulses(w: &mut Wiegand, start_ns: u64, bits: &[bool]) -> Vec<WiegandFrame> {
+        let mut frames = Vec::new();
+        for (i, bit) in bits.iter().enumerate() {
+            let offset = if *bit { 6 } else { 5 };
+            let t = start_ns + i as u64 * 2 * MS;
+            frames.extend(w.feed(&edge(offset, EdgeKind::Falling, t)));
+            frames.extend(w.feed(&edge(offset, EdgeKind::Rising, t + MS / 10)));
+        }
+        frames
+    }
+
+    #[test]
+    fn decode() {
+        let mut w = Wiegand::new(5, 6);
+        let bits = [true, false, true, true];
+        assert!(pulses(&mut w, 0, &bits).is_empty());
+        assert_eq!(w.idle(6 * MS + 19 * MS), None);
+        let frame = w.idle(6 * MS + 20 * MS).unwrap();
+        assert_eq!(frame.bits, bits);
+        assert_eq!(frame.value(), 0b1011);
+        assert_eq!(format!("{}", frame), "4 bits 0xb");
+        assert_eq!(w.idle(100 * MS), None);
+
+        // a new frame completes the previous
+        assert!(pulses(&mut w, 200 * MS, &[false, true]).is_empty());
+        let frames = pulses(&mut w, 300 * MS, &[true]);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].bits, [false, true]);
+
+        // events from other lines are ignored
+        assert_eq!(w.feed(&edge(7, EdgeKind::Falling, 400 * MS)), None);
+        assert_eq!(w.idle(400 * MS).unwrap().bits, [true]);
+    }
+}
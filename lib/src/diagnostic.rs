@@ -0,0 +1,430 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Diagnosis of lines that cannot be requested.
+//!
+//! A line may be unavailable because it is held by the kernel, e.g. by a hog or a
+//! driver, or because the pin is muxed to another function.
+//! The line info only provides the consumer label, so [`diagnose_line`] searches
+//! sysfs, the device tree, and debugfs for the likely owner of the line.
+//!
+//! The pin mux information is only available from debugfs, which typically requires
+//! root permissions, so pin mux conflicts are only detected where that is accessible.
+
+use crate::chip::Chip;
+use crate::line::{Info, Offset};
+use crate::Result;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A likely owner of a line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Owner {
+    /// The line is exported via the deprecated sysfs GPIO interface.
+    Sysfs,
+
+    /// The line is hogged by the kernel, as specified by a device tree node.
+    Hog(PathBuf),
+
+    /// The line is held by a kernel driver for a device.
+    Driver {
+        /// The name of the device.
+        device: String,
+
+        /// The name of the driver.
+        driver: String,
+    },
+
+    /// The pin for the line is muxed to another function.
+    Pinmux {
+        /// The name of the pin.
+        pin: String,
+
+        /// The device that claimed the pin.
+        owner: String,
+
+        /// The function the pin is muxed to.
+        function: String,
+    },
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Owner::Sysfs => write!(
+                f,
+                "line is exported via sysfs - unexport it using /sys/class/gpio/unexport"
+            ),
+            Owner::Hog(node) => write!(
+                f,
+                "line is hogged by the kernel as specified by device tree node '{}'",
+                node.display()
+            ),
+            Owner::Driver { device, driver } => write!(
+                f,
+                "line is held by kernel driver '{}' for device '{}'",
+                driver, device
+            ),
+            Owner::Pinmux {
+                pin,
+                owner,
+                function,
+            } => write!(
+                f,
+                "pin '{}' is muxed to function '{}' by '{}'",
+                pin, function, owner
+            ),
+        }
+    }
+}
+
+/// The diagnosis of a line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnosis {
+    /// The info for the line.
+    pub info: Info,
+
+    /// The likely owners of the line.
+    pub owners: Vec<Owner>,
+}
+
+impl Diagnosis {
+    /// Returns true if the line is likely to be unavailable for request.
+    pub fn is_conflict(&self) -> bool {
+        self.info.used || !self.owners.is_empty()
+    }
+
+    /// The device tree node of the hog holding the line, if the line is hogged.
+    pub fn hog(&self) -> Option<&Path> {
+        self.owners.iter().find_map(|o| match o {
+            Owner::Hog(node) => Some(node.as_path()),
+            _ => None,
+        })
+    }
+
+    /// A hint describing why the line is unavailable.
+    ///
+    /// Returns None if the line is not in conflict.
+    pub fn hint(&self) -> Option<String> {
+        if !self.owners.is_empty() {
+            let hints: Vec<String> = self.owners.iter().map(|o| o.to_string()).collect();
+            return Some(hints.join(", "));
+        }
+        if self.info.used {
+            if self.info.consumer.is_empty() {
+                return Some("line is in use by an unknown consumer".to_string());
+            }
+            return Some(format!("line is in use by '{}'", self.info.consumer));
+        }
+        None
+    }
+}
+
+/// Diagnose why a line on a chip may be unavailable.
+pub fn diagnose_line(chip: &Chip, offset: Offset) -> Result<Diagnosis> {
+    let info = chip.line_info(offset)?;
+    let owners = find_owners(&info, Path::new("/"));
+    Ok(Diagnosis { info, owners })
+}
+
+/// Find the device tree node of the hog holding a line.
+///
+/// The line info only indicates that the line is in use, so hogs are identified
+/// by the consumer label matching the name of a hog in the device tree.
+///
+/// Returns None if the line is not hogged, or the hog is not specified by the
+/// device tree.
+pub fn hog_node(info: &Info) -> Option<PathBuf> {
+    find_hog_node(info, Path::new("/"))
+}
+
+fn find_hog_node(info: &Info, root: &Path) -> Option<PathBuf> {
+    if !info.used || info.consumer.is_empty() || info.consumer == "sysfs" {
+        return None;
+    }
+    let base = root.join("sys/firmware/devicetree/base");
+    find_hog(&base, &base, &info.consumer)
+}
+
+fn find_owners(info: &Info, root: &Path) -> Vec<Owner> {
+    let mut owners = Vec::new();
+    if info.used {
+        if info.consumer == "sysfs" {
+            owners.push(Owner::Sysfs);
+        } else if !info.consumer.is_empty() {
+            if let Some(node) = find_hog_node(info, root) {
+                owners.push(Owner::Hog(node));
+            }
+            if let Some(driver) = find_driver(root, &info.consumer) {
+                owners.push(Owner::Driver {
+                    device: info.consumer.clone(),
+                    driver,
+                });
+            }
+        }
+    }
+    if !info.name.is_empty() {
+        owners.extend(find_pinmux(root, &info.name));
+    }
+    owners
+}
+
+// Find the device tree node of the hog with the given line name.
+fn find_hog(base: &Path, dir: &Path, consumer: &str) -> Option<PathBuf> {
+    if dir.join("gpio-hog").exists() {
+        let name = match fs::read(dir.join("line-name")) {
+            Ok(name) => String::from_utf8_lossy(&name)
+                .trim_end_matches('\0')
+                .to_string(),
+            Err(_) => dir
+                .file_name()?
+                .to_string_lossy()
+                .split('@')
+                .next()?
+                .to_string(),
+        };
+        if name == consumer {
+            return Some(Path::new("/").join(dir.strip_prefix(base).ok()?));
+        }
+    }
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        if let Ok(ft) = entry.file_type() {
+            if ft.is_dir() {
+                if let Some(node) = find_hog(base, &entry.path(), consumer) {
+                    return Some(node);
+                }
+            }
+        }
+    }
+    None
+}
+
+// Find the driver of a device with the given name on any bus.
+fn find_driver(root: &Path, device: &str) -> Option<String> {
+    if device.contains('/') {
+        return None;
+    }
+    for bus in fs::read_dir(root.join("sys/bus")).ok()?.flatten() {
+        let link = bus.path().join("devices").join(device).join("driver");
+        if let Ok(driver) = fs::read_link(link) {
+            return driver.file_name().map(|d| d.to_string_lossy().to_string());
+        }
+    }
+    None
+}
+
+// Find any claim on the pin with the given name in the pinctrl debugfs.
+fn find_pinmux(root: &Path, name: &str) -> Option<Owner> {
+    for pinctrl in fs::read_dir(root.join("sys/kernel/debug/pinctrl"))
+        .ok()?
+        .flatten()
+    {
+        let pins = match fs::read_to_string(pinctrl.path().join("pinmux-pins")) {
+            Ok(pins) => pins,
+            Err(_) => continue,
+        };
+        for line in pins.lines() {
+            if let Some(owner) = parse_pinmux_pin(line, name) {
+                return Some(owner);
+            }
+        }
+    }
+    None
+}
+
+// Parse a line from pinmux-pins, such as
+// `pin 2 (GPIO2): fe804000.i2c (GPIO UNCLAIMED) function i2c1 group i2c1`
+fn parse_pinmux_pin(line: &str, name: &str) -> Option<Owner> {
+    let start = line.find('(')?;
+    let end = line[start..].find("): ")? + start;
+    let pin = &line[start + 1..end];
+    if !pin.eq_ignore_ascii_case(name) {
+        return None;
+    }
+    let mut words = line[end + 3..].split_whitespace();
+    let owner = words.next()?;
+    if owner.starts_with('(') {
+        // (MUX UNCLAIMED)
+        return None;
+    }
+    let function = words
+        .skip_while(|w| *w != "function")
+        .nth(1)
+        .unwrap_or("unknown");
+    Some(Owner::Pinmux {
+        pin: pin.to_string(),
+        owner: owner.to_string(),
+        function: function.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, consumer: &str, used: bool) -> Info {
+        Info {
+            offset: 4,
+            name: name.to_string(),
+            consumer: consumer.to_string(),
+            used,
+            ..Default::default()
+        }
+    }
+
+    fn sysroot(test: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "gpiocdev-diagnostic-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn parse_pinmux() {
+        let line = "pin 2 (GPIO2): fe804000.i2c (GPIO UNCLAIMED) function i2c1 group i2c1";
+        assert_eq!(
+            parse_pinmux_pin(line, "gpio2"),
+            Some(Owner::Pinmux {
+                pin: "GPIO2".to_string(),
+                owner: "fe804000.i2c".to_string(),
+                function: "i2c1".to_string(),
+            })
+        );
+        assert_eq!(parse_pinmux_pin(line, "GPIO3"), None);
+        assert_eq!(
+            parse_pinmux_pin("pin 3 (GPIO3): (MUX UNCLAIMED) (GPIO UNCLAIMED)", "GPIO3"),
+            None
+        );
+        assert_eq!(parse_pinmux_pin("garbage", "GPIO3"), None);
+    }
+
+    #[test]
+    fn sysfs() {
+        let root = sysroot("sysfs");
+        assert_eq!(
+            find_owners(&info("GPIO4", "sysfs", true), &root),
+            vec![Owner::Sysfs]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn hog() {
+        let root = sysroot("hog");
+        let node = root.join("sys/firmware/devicetree/base/soc/gpio@7e200000/wifi-hog");
+        fs::create_dir_all(&node).unwrap();
+        fs::write(node.join("gpio-hog"), b"").unwrap();
+        fs::write(node.join("line-name"), b"wifi-enable\0").unwrap();
+        assert_eq!(
+            find_owners(&info("GPIO4", "wifi-enable", true), &root),
+            vec![Owner::Hog(PathBuf::from("/soc/gpio@7e200000/wifi-hog"))]
+        );
+        assert!(find_owners(&info("GPIO4", "other", true), &root).is_empty());
+        assert_eq!(
+            find_hog_node(&info("GPIO4", "wifi-enable", true), &root),
+            Some(PathBuf::from("/soc/gpio@7e200000/wifi-hog"))
+        );
+        assert_eq!(
+            find_hog_node(&info("GPIO4", "wifi-enable", false), &root),
+            None
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn driver() {
+        let root = sysroot("driver");
+        let dev = root.join("sys/bus/platform/devices/leds");
+        fs::create_dir_all(&dev).unwrap();
+        std::os::unix::fs::symlink(
+            "../../../bus/platform/drivers/leds-gpio",
+            dev.join("driver"),
+        )
+        .unwrap();
+        assert_eq!(
+            find_owners(&info("GPIO4", "leds", true), &root),
+            vec![Owner::Driver {
+                device: "leds".to_string(),
+                driver: "leds-gpio".to_string()
+            }]
+        );
+        // not used
+        assert!(find_owners(&info("GPIO4", "leds", false), &root).is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn pinmux() {
+        let root = sysroot("pinmux");
+        let pinctrl = root.join("sys/kernel/debug/pinctrl/fe200000.gpio-pinctrl-bcm2711");
+        fs::create_dir_all(&pinctrl).unwrap();
+        fs::write(
+            pinctrl.join("pinmux-pins"),
+            "Pinmux settings per pin\n\
+            Format: pin (name): mux_owner gpio_owner hog?\n\
+            pin 2 (GPIO2): fe804000.i2c (GPIO UNCLAIMED) function i2c1 group i2c1\n\
+            pin 4 (GPIO4): (MUX UNCLAIMED) (GPIO UNCLAIMED)\n",
+        )
+        .unwrap();
+        assert_eq!(
+            find_owners(&info("GPIO2", "", false), &root),
+            vec![Owner::Pinmux {
+                pin: "GPIO2".to_string(),
+                owner: "fe804000.i2c".to_string(),
+                function: "i2c1".to_string(),
+            }]
+        );
+        assert!(find_owners(&info("GPIO4", "", false), &root).is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn hint() {
+        let d = Diagnosis {
+            info: info("GPIO4", "", false),
+            owners: Vec::new(),
+        };
+        assert!(!d.is_conflict());
+        assert_eq!(d.hint(), None);
+
+        let d = Diagnosis {
+            info: info("GPIO4", "", true),
+            owners: Vec::new(),
+        };
+        assert!(d.is_conflict());
+        assert_eq!(
+            d.hint().unwrap(),
+            "line is in use by an unknown consumer".to_string()
+        );
+
+        let d = Diagnosis {
+            info: info("GPIO4", "gpiocdev-set", true),
+            owners: Vec::new(),
+        };
+        assert_eq!(
+            d.hint().unwrap(),
+            "line is in use by 'gpiocdev-set'".to_string()
+        );
+
+        let d = Diagnosis {
+            info: info("GPIO4", "wifi-enable", true),
+            owners: vec![Owner::Hog(PathBuf::from("/soc/wifi-hog"))],
+        };
+        assert_eq!(d.hog(), Some(Path::new("/soc/wifi-hog")));
+
+        let d = Diagnosis {
+            info: info("GPIO4", "sysfs", true),
+            owners: vec![Owner::Sysfs],
+        };
+        assert_eq!(
+            d.hint().unwrap(),
+            "line is exported via sysfs - unexport it using /sys/class/gpio/unexport".to_string()
+        );
+    }
+}
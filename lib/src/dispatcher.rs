@@ -0,0 +1,495 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Dispatching events to handlers with deterministic shutdown.
+//!
+//! Handlers are registered within a [`scope`], each running on its own thread
+//! that reads events from a [`Request`] or [`Chip`] and passes them to the handler.
+//!
+//! When the scope closure returns, all handler threads are stopped and joined
+//! before [`scope`] returns, so no handler runs beyond the scope and no
+//! `JoinHandle` bookkeeping is required.
+//! Handlers may borrow from the enclosing function, as with [`std::thread::scope`].
+//!
+//! The scope is also stopped if any handler fails to read events, and the first
+//! such error is returned by [`scope`].
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::dispatcher;
+//! # use gpiocdev::line::EdgeDetection;
+//! # fn main() -> Result<()> {
+//! let buttons = gpiocdev::Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[17, 18])
+//!     .with_edge_detection(EdgeDetection::FallingEdge)
+//!     .request()?;
+//! let mut presses = 0;
+//! dispatcher::scope(|s| {
+//!     let stopper = s.stopper();
+//!     s.on_edge(&buttons, move |evt| {
+//!         presses += 1;
+//!         println!("{:?}", evt);
+//!         if presses == 10 {
+//!             stopper.stop();
+//!         }
+//!     });
+//!     // block until a handler stops the scope
+//!     s.wait();
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::chip::Chip;
+use crate::line::{EdgeEvent, InfoChangeEvent};
+use crate::{Error, Request, Result};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often the handler threads check if they should exit.
+const STOP_POLL_PERIOD: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct State {
+    stopped: bool,
+    error: Option<Error>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("failed to acquire dispatcher lock")
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.lock().stopped
+    }
+
+    fn stop(&self) {
+        self.lock().stopped = true;
+        self.changed.notify_all();
+    }
+
+    // Stop the scope, recording the error if it is the first.
+    fn fail(&self, e: Error) {
+        let mut state = self.lock();
+        if state.error.is_none() {
+            state.error = Some(e);
+        }
+        state.stopped = true;
+        drop(state);
+        self.changed.notify_all();
+    }
+}
+
+// Stops the scope when dropped, including when unwinding from a panic,
+// so a panicking handler or scope closure cannot leave other handlers running.
+struct StopGuard(Arc<Shared>);
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+/// Stops a dispatcher [`Scope`].
+///
+/// May be moved into handlers or other threads to stop the scope from there.
+#[derive(Clone)]
+pub struct Stopper(Arc<Shared>);
+
+impl Stopper {
+    /// Stop the scope.
+    ///
+    /// The handlers exit once they finish handling the current event.
+    pub fn stop(&self) {
+        self.0.stop();
+    }
+
+    /// Returns true if the scope has been stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.0.is_stopped()
+    }
+}
+
+/// A scope in which event handlers are registered.
+///
+/// Created by [`scope`].
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: &'scope thread::Scope<'scope, 'env>,
+    shared: Arc<Shared>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Handle the edge events from a request.
+    ///
+    /// The handler is called from a dedicated thread for each event read from the request,
+    /// so the events should not be read from the request elsewhere while the scope is active.
+    pub fn on_edge<F>(&self, req: &'scope Request, mut handler: F)
+    where
+        F: FnMut(EdgeEvent) + Send + 'scope,
+    {
+        self.spawn(move || {
+            if req.wait_edge_event(STOP_POLL_PERIOD)? {
+                handler(req.read_edge_event()?);
+            }
+            Ok(())
+        });
+    }
+
+    /// Handle the info change events from a chip.
+    ///
+    /// The lines of interest must be watched using [`Chip::watch_line_info`].
+    ///
+    /// The handler is called from a dedicated thread for each event read from the chip,
+    /// so the chip is borrowed exclusively while the scope is active.
+    pub fn on_info_change<F>(&self, chip: &'scope mut Chip, mut handler: F)
+    where
+        F: FnMut(InfoChangeEvent) + Send + 'scope,
+    {
+        self.spawn(move || {
+            if chip.wait_line_info_change_event(STOP_POLL_PERIOD)? {
+                handler(chip.read_line_info_change_event()?);
+            }
+            Ok(())
+        });
+    }
+
+    /// Handle the edge events from a request, and call a tick function periodically.
+    ///
+    /// The tick function is called from the same thread as the handler, and both are
+    /// passed the `state`, so they may share it without synchronization, such as to
+    /// expire timeouts or feed a watchdog.
+    /// It is called every `interval` regardless of event activity, though a slow handler
+    /// delays the tick.
+    /// Ticks missed due to such delays are skipped rather than being called in a burst.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// # use gpiocdev::dispatcher;
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # use std::time::{Duration, Instant};
+    /// # fn main() -> Result<()> {
+    /// let sensor = gpiocdev::Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(17)
+    ///     .with_edge_detection(EdgeDetection::RisingEdge)
+    ///     .request()?;
+    /// dispatcher::scope(|s| {
+    ///     s.on_edge_with_tick(
+    ///         &sensor,
+    ///         Duration::from_secs(1),
+    ///         Instant::now(),
+    ///         |last_pulse, _| *last_pulse = Instant::now(),
+    ///         |last_pulse| {
+    ///             if last_pulse.elapsed() > Duration::from_secs(5) {
+    ///                 println!("sensor has stalled");
+    ///             }
+    ///         },
+    ///     );
+    ///     s.wait();
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_edge_with_tick<S, F, T>(
+        &self,
+        req: &'scope Request,
+        interval: Duration,
+        mut state: S,
+        mut handler: F,
+        tick: T,
+    ) where
+        S: Send + 'scope,
+        F: FnMut(&mut S, EdgeEvent) + Send + 'scope,
+        T: FnMut(&mut S) + Send + 'scope,
+    {
+        let mut ticker = Ticker::new(interval, tick);
+        self.spawn(move || {
+            if req.wait_edge_event(ticker.timeout())? {
+                handler(&mut state, req.read_edge_event()?);
+            }
+            ticker.poll(&mut state);
+            Ok(())
+        });
+    }
+
+    /// Handle the info change events from a chip, and call a tick function periodically.
+    ///
+    /// As per [`on_info_change`] and [`on_edge_with_tick`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// [`on_info_change`]: Scope::on_info_change
+    /// [`on_edge_with_tick`]: Scope::on_edge_with_tick
+    pub fn on_info_change_with_tick<S, F, T>(
+        &self,
+        chip: &'scope mut Chip,
+        interval: Duration,
+        mut state: S,
+        mut handler: F,
+        tick: T,
+    ) where
+        S: Send + 'scope,
+        F: FnMut(&mut S, InfoChangeEvent) + Send + 'scope,
+        T: FnMut(&mut S) + Send + 'scope,
+    {
+        let mut ticker = Ticker::new(interval, tick);
+        self.spawn(move || {
+            if chip.wait_line_info_change_event(ticker.timeout())? {
+                handler(&mut state, chip.read_line_info_change_event()?);
+            }
+            ticker.poll(&mut state);
+            Ok(())
+        });
+    }
+
+    // Repeatedly poll until the scope is stopped or the poll fails.
+    fn spawn<F>(&self, mut poll: F)
+    where
+        F: FnMut() -> Result<()> + Send + 'scope,
+    {
+        let shared = self.shared.clone();
+        self.inner.spawn(move || {
+            let _guard = StopGuard(shared.clone());
+            while !shared.is_stopped() {
+                if let Err(e) = poll() {
+                    shared.fail(e);
+                }
+            }
+        });
+    }
+
+    /// A handle that may be used to stop the scope from a handler or another thread.
+    pub fn stopper(&self) -> Stopper {
+        Stopper(self.shared.clone())
+    }
+
+    /// Stop the scope.
+    ///
+    /// The handlers exit once they finish handling the current event.
+    pub fn stop(&self) {
+        self.shared.stop();
+    }
+
+    /// Returns true if the scope has been stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.shared.is_stopped()
+    }
+
+    /// Wait until the scope is stopped, either by a [`Stopper`] or a handler error.
+    pub fn wait(&self) {
+        let mut state = self.shared.lock();
+        while !state.stopped {
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .expect("failed to acquire dispatcher lock");
+        }
+    }
+
+    /// Wait until the scope is stopped, or the timeout expires.
+    ///
+    /// Returns true if the scope was stopped.
+    ///
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely, as per [`wait`].
+    ///
+    /// [`wait`]: Scope::wait
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => {
+                self.wait();
+                return true;
+            }
+        };
+        let mut state = self.shared.lock();
+        while !state.stopped {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            state = self
+                .shared
+                .changed
+                .wait_timeout(state, deadline - now)
+                .expect("failed to acquire dispatcher lock")
+                .0;
+        }
+        true
+    }
+}
+
+// Calls a tick function periodically from a handler poll loop.
+struct Ticker<T> {
+    interval: Duration,
+    // None if the interval is too large for the tick to ever be due
+    next: Option<Instant>,
+    tick: T,
+}
+
+impl<T> Ticker<T> {
+    fn new(interval: Duration, tick: T) -> Self {
+        assert!(!interval.is_zero(), "tick interval must be non-zero");
+        Ticker {
+            interval,
+            next: Instant::now().checked_add(interval),
+            tick,
+        }
+    }
+
+    // The period to wait for an event, so the tick is not delayed.
+    fn timeout(&self) -> Duration {
+        match self.next {
+            Some(next) => next
+                .saturating_duration_since(Instant::now())
+                .min(STOP_POLL_PERIOD),
+            None => STOP_POLL_PERIOD,
+        }
+    }
+
+    // Call the tick function if it is due.
+    fn poll<S>(&mut self, state: &mut S)
+    where
+        T: FnMut(&mut S),
+    {
+        let now = Instant::now();
+        let next = match self.next {
+            Some(next) if now >= next => next,
+            _ => return,
+        };
+        (self.tick)(state);
+        self.next = match next.checked_add(self.interval) {
+            // skip missed ticks
+            Some(next) if next <= now => now.checked_add(self.interval),
+            next => next,
+        };
+    }
+}
+
+/// Run a closure that registers event handlers, joining all the handler threads
+/// before returning.
+///
+/// The handlers run until the closure returns, the scope is stopped, or a handler
+/// fails to read events.
+///
+/// Returns the result of the closure, or the first error encountered by a handler.
+///
+/// If a handler panics then the remaining handlers are stopped and the panic
+/// is propagated once they have been joined.
+pub fn scope<'env, F, T>(f: F) -> Result<T>
+where
+    F: for<'scope> FnOnce(&Scope<'scope, 'env>) -> T,
+{
+    let shared = Arc::new(Shared::default());
+    let scope_shared = shared.clone();
+    let res = thread::scope(move |s| {
+        let _guard = StopGuard(scope_shared.clone());
+        f(&Scope {
+            inner: s,
+            shared: scope_shared,
+        })
+    });
+    let error = shared.lock().error.take();
+    match error {
+        Some(e) => Err(e),
+        None => Ok(res),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopper() {
+        let res = scope(|s| {
+            assert!(!s.is_stopped());
+            assert!(!s.wait_timeout(Duration::from_millis(10)));
+            let stopper = s.stopper();
+            thread::spawn(move || stopper.stop());
+            s.wait();
+            assert!(s.is_stopped());
+            42
+        });
+        assert_eq!(res, Ok(42));
+    }
+
+    #[test]
+    fn error() {
+        let res = scope(|s| {
+            s.spawn(|| Err(Error::InvalidArgument("bad read.".to_string())));
+            assert!(s.wait_timeout(Duration::MAX));
+        });
+        assert_eq!(res, Err(Error::InvalidArgument("bad read.".to_string())));
+    }
+
+    #[test]
+    fn ticker() {
+        let mut ticks = 0;
+        let mut ticker = Ticker::new(Duration::from_millis(10), |t: &mut i32| *t += 1);
+        assert!(ticker.timeout() <= Duration::from_millis(10));
+        ticker.poll(&mut ticks);
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(55) {
+            thread::sleep(ticker.timeout());
+            ticker.poll(&mut ticks);
+        }
+        // missed ticks are skipped
+        thread::sleep(Duration::from_millis(35));
+        ticker.poll(&mut ticks);
+        ticker.poll(&mut ticks);
+        assert!((4..=7).contains(&ticks), "ticks: {}", ticks);
+    }
+
+    #[test]
+    fn ticker_max_interval() {
+        let mut ticks = 0;
+        let mut ticker = Ticker::new(Duration::MAX, |t: &mut i32| *t += 1);
+        assert_eq!(ticker.timeout(), STOP_POLL_PERIOD);
+        ticker.poll(&mut ticks);
+        assert_eq!(ticks, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "tick interval must be non-zero")]
+    fn ticker_zero_interval() {
+        Ticker::new(Duration::ZERO, |_: &mut ()| {});
+    }
+
+    #[test]
+    fn joins_on_return() {
+        let mut polls = 0;
+        scope(|s| {
+            s.spawn(|| {
+                polls += 1;
+                thread::sleep(Duration::from_millis(1));
+                Ok(())
+            });
+            thread::sleep(Duration::from_millis(20));
+        })
+        .unwrap();
+        // the handler has exited, releasing its borrow
+        assert!(polls > 0);
+    }
+}
@@ -0,0 +1,215 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Identifying the processes holding requested lines.
+//!
+//! The line info only provides the consumer label of a requested line, which
+//! may not identify the process holding the request.
+//! The holders of a line are found by scanning the file descriptors of each process,
+//! in `/proc/<pid>/fd`, for line requests, and matching the chip and offsets reported
+//! for each request in `/proc/<pid>/fdinfo/<fd>`.
+//!
+//! The chip and offsets are only reported in the fdinfo of uAPI v2 line requests,
+//! and only by Linux 6.7 and later, so holders are not found on earlier kernels,
+//! or for requests made using uAPI v1.
+//!
+//! The file descriptors of other processes are typically only accessible to root,
+//! so only the holders visible to the caller are found.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # fn main() -> Result<()> {
+//! if let Some(line) = gpiocdev::find_named_line("MOTOR_EN") {
+//!     for holder in line.holders()? {
+//!         println!("held by {} ({}) {:?}", holder.pid, holder.comm, holder.exe);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::line::Offset;
+use crate::{FoundLine, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A process holding a requested line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Holder {
+    /// The process ID.
+    pub pid: u32,
+
+    /// The command name of the process.
+    pub comm: String,
+
+    /// The path of the executable of the process, if accessible.
+    pub exe: Option<PathBuf>,
+
+    /// The file descriptor of the line request within the process.
+    pub fd: i32,
+}
+
+/// Find the processes holding a line.
+///
+/// The chip is identified by path, such as `/dev/gpiochip0`.
+///
+/// Returns an empty list if no holders are found, including where the kernel
+/// does not report the lines for requests.
+pub fn find_holders<P: AsRef<Path>>(chip: P, offset: Offset) -> Result<Vec<Holder>> {
+    let chip = match chip.as_ref().file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => return Ok(Vec::new()),
+    };
+    scan(Path::new("/proc"), &chip, offset)
+}
+
+impl FoundLine {
+    /// Find the processes holding the line.
+    ///
+    /// As per [`find_holders`].
+    pub fn holders(&self) -> Result<Vec<Holder>> {
+        find_holders(&self.chip, self.info.offset)
+    }
+}
+
+fn scan(proc: &Path, chip: &str, offset: Offset) -> Result<Vec<Holder>> {
+    let mut holders = Vec::new();
+    for entry in fs::read_dir(proc)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let pid = match entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        {
+            Some(pid) => pid,
+            None => continue,
+        };
+        let dir = entry.path();
+        // processes may exit, or be inaccessible, so skip any errors
+        let fds = match fs::read_dir(dir.join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        for fd in fds.flatten() {
+            let fdnum = match fd.file_name().to_str().and_then(|s| s.parse::<i32>().ok()) {
+                Some(fdnum) => fdnum,
+                None => continue,
+            };
+            match fs::read_link(fd.path()) {
+                Ok(target) if target == Path::new("anon_inode:gpio-line") => {}
+                _ => continue,
+            }
+            let fdinfo = match fs::read_to_string(dir.join("fdinfo").join(fdnum.to_string())) {
+                Ok(fdinfo) => fdinfo,
+                Err(_) => continue,
+            };
+            if holds_line(&fdinfo, chip, offset) {
+                holders.push(Holder {
+                    pid,
+                    comm: fs::read_to_string(dir.join("comm"))
+                        .map(|s| s.trim_end().to_string())
+                        .unwrap_or_default(),
+                    exe: fs::read_link(dir.join("exe")).ok(),
+                    fd: fdnum,
+                });
+            }
+        }
+    }
+    holders.sort_by_key(|h| (h.pid, h.fd));
+    Ok(holders)
+}
+
+// Returns true if the fdinfo of a line request contains the line.
+fn holds_line(fdinfo: &str, chip: &str, offset: Offset) -> bool {
+    let mut on_chip = false;
+    let mut has_line = false;
+    for l in fdinfo.lines() {
+        match l.split_once(':') {
+            Some(("gpio-chip", value)) => on_chip = value.trim() == chip,
+            Some(("gpio-line", value)) if value.trim().parse::<Offset>() == Ok(offset) => {
+                has_line = true
+            }
+            _ => {}
+        }
+    }
+    on_chip && has_line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    const FDINFO: &str = "pos:\t0\nflags:\t02000002\nmnt_id:\t15\nino:\t1057\n\
+        gpio-chip:\tgpiochip0\ngpio-line:\t17\ngpio-line:\t22\n";
+
+    fn procroot(test: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("gpiocdev-holder-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn add_fd(root: &Path, pid: u32, fd: i32, target: &str, fdinfo: &str) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(dir.join("fd")).unwrap();
+        fs::create_dir_all(dir.join("fdinfo")).unwrap();
+        fs::write(dir.join("comm"), format!("proc{}\n", pid)).unwrap();
+        symlink(target, dir.join("fd").join(fd.to_string())).unwrap();
+        fs::write(dir.join("fdinfo").join(fd.to_string()), fdinfo).unwrap();
+    }
+
+    #[test]
+    fn fdinfo() {
+        assert!(holds_line(FDINFO, "gpiochip0", 17));
+        assert!(holds_line(FDINFO, "gpiochip0", 22));
+        assert!(!holds_line(FDINFO, "gpiochip0", 1));
+        assert!(!holds_line(FDINFO, "gpiochip1", 17));
+        // pre-6.7 kernels
+        assert!(!holds_line("pos:\t0\nflags:\t02000002\n", "gpiochip0", 17));
+    }
+
+    #[test]
+    fn scan_proc() {
+        let root = procroot("scan");
+        add_fd(&root, 42, 5, "anon_inode:gpio-line", FDINFO);
+        add_fd(&root, 42, 3, "/dev/gpiochip0", "pos:\t0\n");
+        add_fd(&root, 7, 9, "anon_inode:gpio-line", FDINFO);
+        add_fd(
+            &root,
+            99,
+            4,
+            "anon_inode:gpio-line",
+            "gpio-chip:\tgpiochip1\ngpio-line:\t17\n",
+        );
+        fs::create_dir_all(root.join("self")).unwrap();
+
+        let holders = scan(&root, "gpiochip0", 17).unwrap();
+        assert_eq!(
+            holders,
+            [
+                Holder {
+                    pid: 7,
+                    comm: "proc7".to_string(),
+                    exe: None,
+                    fd: 9
+                },
+                Holder {
+                    pid: 42,
+                    comm: "proc42".to_string(),
+                    exe: None,
+                    fd: 5
+                }
+            ]
+        );
+        assert!(scan(&root, "gpiochip0", 3).unwrap().is_empty());
+        assert_eq!(scan(&root, "gpiochip1", 17).unwrap()[0].pid, 99);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
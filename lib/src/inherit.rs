@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Using chips and requests opened by a launching process.
+//!
+//! A privileged launcher, such as systemd or a custom supervisor, may open the
+//! GPIO chips and request the lines, then pass the file descriptors to a
+//! sandboxed service that has no access to the GPIO devices itself.
+//!
+//! The file descriptors are passed using the `LISTEN_FDS` protocol, as used by
+//! systemd socket activation, where the `LISTEN_PID`, `LISTEN_FDS` and
+//! `LISTEN_FDNAMES` environment variables describe the file descriptors
+//! inherited by the process.
+//!
+//! [`Inherited`] takes ownership of those file descriptors and reconstructs the
+//! [`Chip`] and [`Request`] from them, after checking that each file descriptor
+//! is of the expected kind.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::inherit::Inherited;
+//! # use gpiocdev::line::Value;
+//! # use gpiocdev::request::Config;
+//! # fn main() -> Result<()> {
+//! let mut inherited = Inherited::from_env()?;
+//! let chip = inherited.take_chip("gpiochip0")?;
+//! let mut cfg = Config::default();
+//! cfg.on_chip(chip.path()).with_line(17).as_output(Value::Inactive);
+//! let req = inherited.take_request("led", cfg)?;
+//! req.set_value(17, Value::Active)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::chip::Chip;
+use crate::request::Config;
+use crate::{Error, Request, Result};
+use std::env;
+use std::fs::{self, File};
+use std::os::unix::prelude::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// The first inherited file descriptor, following stdin, stdout and stderr.
+const LISTEN_FDS_START: RawFd = 3;
+
+// The inherited file descriptors may only be taken once.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+// The kernel names of the anonymous inodes backing line requests.
+const REQUEST_INODES: [&str; 3] = [
+    "anon_inode:gpio-line",
+    "anon_inode:gpiohandle",
+    "anon_inode:gpioevent",
+];
+
+/// The file descriptors inherited from the launching process.
+///
+/// Any file descriptors not taken are closed when this is dropped.
+#[derive(Debug)]
+pub struct Inherited {
+    fds: Vec<(String, OwnedFd)>,
+}
+
+impl Inherited {
+    /// Take ownership of the file descriptors passed to the process.
+    ///
+    /// The `LISTEN_*` environment variables are removed so they are not passed
+    /// on to child processes.
+    ///
+    /// If the variables are not set, or are intended for another process, then
+    /// no file descriptors are inherited.
+    ///
+    /// Returns an error if the file descriptors have already been taken, or if the
+    /// variables are malformed.
+    pub fn from_env() -> Result<Inherited> {
+        if TAKEN.swap(true, Ordering::SeqCst) {
+            return Err(Error::InvalidArgument(
+                "inherited file descriptors have already been taken.".to_string(),
+            ));
+        }
+        let pid = env::var("LISTEN_PID").ok();
+        let num_fds = env::var("LISTEN_FDS").ok();
+        let names = env::var("LISTEN_FDNAMES").ok();
+        for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+            env::remove_var(var);
+        }
+        let listed = match (pid, num_fds) {
+            (Some(pid), Some(num_fds)) => {
+                parse_listen_fds(&pid, &num_fds, names.as_deref(), std::process::id())?
+            }
+            _ => Vec::new(),
+        };
+        let mut fds = Vec::new();
+        for (fd, name) in listed {
+            // SAFETY: no pointers involved.
+            let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+            if flags < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            // SAFETY: no pointers involved.
+            unsafe { libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) };
+            // SAFETY: the fd is open and was passed to this process for its exclusive use.
+            fds.push((name, unsafe { OwnedFd::from_raw_fd(fd) }));
+        }
+        Ok(Inherited { fds })
+    }
+
+    /// The names of the file descriptors not yet taken.
+    ///
+    /// File descriptors passed without names are named "unknown".
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fds.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The number of file descriptors not yet taken.
+    pub fn len(&self) -> usize {
+        self.fds.len()
+    }
+
+    /// Returns true if all file descriptors have been taken.
+    pub fn is_empty(&self) -> bool {
+        self.fds.is_empty()
+    }
+
+    /// Take the named file descriptor, without checking its kind.
+    ///
+    /// If several file descriptors share the name then the first is taken.
+    pub fn take(&mut self, name: &str) -> Option<OwnedFd> {
+        let idx = self.position(name)?;
+        Some(self.fds.remove(idx).1)
+    }
+
+    /// Take the named file descriptor as a chip.
+    ///
+    /// Returns an error, and the file descriptor is not taken, if it is not
+    /// a GPIO character device.
+    pub fn take_chip(&mut self, name: &str) -> Result<Chip> {
+        let idx = self.named_position(name)?;
+        let (name, fd) = self.fds.remove(idx);
+        match fd_target(&fd) {
+            Some(path) if !REQUEST_INODES.iter().any(|i| path.as_os_str() == *i) => {
+                let chip = Chip::from_parts(path, File::from(fd));
+                if chip.info().is_ok() {
+                    return Ok(chip);
+                }
+                self.fds.insert(idx, (name.clone(), chip.f.into()));
+            }
+            _ => self.fds.insert(idx, (name.clone(), fd)),
+        }
+        Err(Error::InvalidArgument(format!(
+            "inherited fd '{}' is not a GPIO chip.",
+            name
+        )))
+    }
+
+    /// Take the named file descriptor as a line request.
+    ///
+    /// The `config` must describe the requested lines, as per [`Request::from_parts`].
+    ///
+    /// Returns an error, and the file descriptor is not taken, if it is not a
+    /// line request.
+    pub fn take_request(&mut self, name: &str, config: Config) -> Result<Request> {
+        let idx = self.named_position(name)?;
+        match fd_target(&self.fds[idx].1) {
+            Some(path) if REQUEST_INODES.iter().any(|i| path.as_os_str() == *i) => {
+                Request::from_parts(self.fds.remove(idx).1, config)
+            }
+            _ => Err(Error::InvalidArgument(format!(
+                "inherited fd '{}' is not a line request.",
+                name
+            ))),
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.fds.iter().position(|(n, _)| n == name)
+    }
+
+    fn named_position(&self, name: &str) -> Result<usize> {
+        self.position(name)
+            .ok_or_else(|| Error::InvalidArgument(format!("no inherited fd named '{}'.", name)))
+    }
+}
+
+// The file, or anonymous inode, that the fd refers to.
+fn fd_target(fd: &OwnedFd) -> Option<PathBuf> {
+    fs::read_link(format!("/proc/self/fd/{}", fd.as_raw_fd())).ok()
+}
+
+// Parse the LISTEN_* variables into the inherited fds and their names.
+fn parse_listen_fds(
+    pid: &str,
+    num_fds: &str,
+    names: Option<&str>,
+    own_pid: u32,
+) -> Result<Vec<(RawFd, String)>> {
+    let pid: u32 = pid
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("invalid LISTEN_PID '{}'.", pid)))?;
+    if pid != own_pid {
+        return Ok(Vec::new());
+    }
+    let num_fds: u32 = num_fds
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("invalid LISTEN_FDS '{}'.", num_fds)))?;
+    let mut names: Vec<&str> = match names {
+        Some(names) => names.split(':').collect(),
+        None => Vec::new(),
+    };
+    names.resize(num_fds as usize, "unknown");
+    Ok(names
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| (LISTEN_FDS_START + idx as RawFd, name.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_listen_fds("42", "2", Some("chip:led"), 42).unwrap(),
+            vec![(3, "chip".to_string()), (4, "led".to_string())]
+        );
+        assert_eq!(
+            parse_listen_fds("42", "2", None, 42).unwrap(),
+            vec![(3, "unknown".to_string()), (4, "unknown".to_string())]
+        );
+        assert_eq!(
+            parse_listen_fds("42", "1", Some("chip:led"), 42).unwrap(),
+            vec![(3, "chip".to_string())]
+        );
+        // for another process
+        assert!(parse_listen_fds("43", "2", None, 42).unwrap().is_empty());
+        assert_eq!(
+            parse_listen_fds("bad", "2", None, 42).unwrap_err(),
+            Error::InvalidArgument("invalid LISTEN_PID 'bad'.".to_string())
+        );
+        assert_eq!(
+            parse_listen_fds("42", "-1", None, 42).unwrap_err(),
+            Error::InvalidArgument("invalid LISTEN_FDS '-1'.".to_string())
+        );
+    }
+
+    #[test]
+    fn take() {
+        let null: OwnedFd = File::open("/dev/null").unwrap().into();
+        let mut inherited = Inherited {
+            fds: vec![("null".to_string(), null)],
+        };
+        assert_eq!(inherited.names().collect::<Vec<_>>(), vec!["null"]);
+        assert_eq!(
+            inherited.take_chip("null").unwrap_err(),
+            Error::InvalidArgument("inherited fd 'null' is not a GPIO chip.".to_string())
+        );
+        assert_eq!(
+            inherited
+                .take_request("null", Config::default())
+                .unwrap_err(),
+            Error::InvalidArgument("inherited fd 'null' is not a line request.".to_string())
+        );
+        assert_eq!(
+            inherited.take_chip("missing").unwrap_err(),
+            Error::InvalidArgument("no inherited fd named 'missing'.".to_string())
+        );
+        assert_eq!(inherited.len(), 1);
+        assert!(inherited.take("null").is_some());
+        assert!(inherited.is_empty());
+    }
+}
@@ -10,7 +10,8 @@
 //! The lines available on specific chips can be discovered using the [`chip`] module.
 //!
 //! The lines available on the system can be discovered by name using the [`find_named_line`]
-//! and [`find_named_lines`] functions, or using the iterator returned by [`lines`].
+//! and [`find_named_lines`] functions, by name pattern using [`find_lines_matching`],
+//! or using the iterator returned by [`lines`].
 //!
 //! # Example Usage
 //!
@@ -71,6 +72,16 @@ use std::ops::Range;
 use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
 
+// Enter a debug level tracing span for the remainder of the enclosing block.
+//
+// A null operation unless the tracing feature is enabled.
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($args)*).entered();
+    };
+}
+
 /// Types and functions specific to chips.
 pub mod chip;
 pub use chip::Chip;
@@ -79,14 +90,32 @@ pub use chip::Chip;
 pub mod line;
 
 /// Wrappers for various async reactors.
-#[cfg(any(feature = "async_tokio", feature = "async_io"))]
+#[cfg(feature = "async")]
 mod r#async;
 
+#[cfg(feature = "async")]
+pub use r#async::reactor;
+
 #[cfg(feature = "async_io")]
 pub use r#async::async_io;
 #[cfg(feature = "async_tokio")]
 pub use r#async::tokio;
 
+#[cfg(feature = "uinput")]
+pub mod bridge;
+
+#[cfg(feature = "calloop")]
+pub mod calloop;
+
+#[cfg(feature = "holder")]
+pub mod holder;
+
+#[cfg(feature = "mirror")]
+pub mod mirror;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
 /// An iterator over all the GPIO lines visible to the caller.
 pub fn lines() -> Result<LineIterator> {
     LineIterator::new()
@@ -98,8 +127,8 @@ pub fn lines() -> Result<LineIterator> {
 ///
 /// Returns the path of the chip containing the line, and the offset of the line on that chip.
 ///
-/// If checking that the line name is unique is required then use [`find_named_lines`]
-/// with the strict option.
+/// If checking that the line name is unique is required then use [`find_named_line_unique`],
+/// or [`find_named_lines`] with the strict option.
 ///
 /// If multiple lines are required then [`find_named_lines`] is more performant.
 ///
@@ -139,6 +168,84 @@ pub fn find_named_line(name: &str) -> Option<FoundLine> {
     None
 }
 
+/// The policy for resolving a line name that is found on several lines.
+///
+/// Line names are not required to be unique, and systems with several similar chips,
+/// such as multiple GPIO expanders, often have the same name on several lines.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum NameResolution {
+    /// The first line found, in chip name order, then offset order.
+    ///
+    /// This is the behaviour of [`find_named_line`].
+    #[default]
+    FirstMatch,
+
+    /// The name must identify a single line.
+    ///
+    /// If several lines have the name then an [`Error::AmbiguousLineName`] is returned,
+    /// listing the candidates.
+    RequireUnique,
+
+    /// All the lines with the name.
+    All,
+}
+
+/// Find the lines with a name, resolving duplicate names as per the policy.
+///
+/// Returns the found lines in chip name order, then offset order.
+/// If no line has the name then the returned list is empty.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::{NameResolution, Result};
+/// # fn main() -> Result<()> {
+/// for line in gpiocdev::find_named_line_with("RESET", NameResolution::All)? {
+///     println!("{}:{}", line.chip.display(), line.info.offset);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_named_line_with(name: &str, policy: NameResolution) -> Result<Vec<FoundLine>> {
+    let mut found = Vec::new();
+    for l in LineIterator::new()? {
+        if l.info.name != name {
+            continue;
+        }
+        found.push(l);
+        if policy == NameResolution::FirstMatch {
+            break;
+        }
+    }
+    if policy == NameResolution::RequireUnique && found.len() > 1 {
+        return Err(Error::AmbiguousLineName(name.to_string(), found));
+    }
+    Ok(found)
+}
+
+/// Find the line with a name, requiring that the name identifies a single line.
+///
+/// Returns an [`Error::AmbiguousLineName`], listing the candidates, if several lines
+/// have the name, or an [`Error::InvalidArgument`] if no line has the name.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::line::Value;
+/// # fn main() -> Result<()> {
+/// let led0 = gpiocdev::find_named_line_unique("LED0")?;
+/// let req = Request::builder()
+///     .with_found_line(&led0)
+///     .as_output(Value::Active)
+///     .request()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_named_line_unique(name: &str) -> Result<FoundLine> {
+    find_named_line_with(name, NameResolution::RequireUnique)?
+        .pop()
+        .ok_or_else(|| Error::InvalidArgument(format!("No line named '{}' found.", name)))
+}
+
 /// Find a collection of named lines.
 ///
 ///  - `strict`: if true then the names are checked to be unique within the available lines
@@ -210,6 +317,135 @@ pub fn find_named_lines<'a>(
     Ok(found)
 }
 
+/// Find all the lines with names matching a pattern.
+///
+/// The pattern is matched against the whole name, and may contain `*` to match any
+/// sequence of characters, and `?` to match any single character.
+/// e.g. "PMIC_*" or "LED?".
+///
+/// Returns the matching lines in chip name order, then offset order.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::Result;
+/// # fn main() -> Result<()> {
+/// let pmic = gpiocdev::find_lines_matching("PMIC_*")?;
+/// for line in &pmic {
+///     println!("{} {}:{}", line.info.name, line.chip.display(), line.info.offset);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_lines_matching(pattern: &str) -> Result<Vec<FoundLine>> {
+    Ok(LineIterator::new()?
+        .filter(|l| glob_match(pattern, &l.info.name))
+        .collect())
+}
+
+/// Find all the lines in use with consumer labels matching a pattern.
+///
+/// The pattern is matched against the whole consumer label, as per
+/// [`Chip::lines_with_consumer`].
+/// This identifies the lines held by a particular process, such as a daemon,
+/// provided the process sets a distinctive consumer label when requesting them.
+///
+/// Returns the matching lines in chip name order, then offset order.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::Result;
+/// # fn main() -> Result<()> {
+/// for line in gpiocdev::find_lines_by_consumer("motord")? {
+///     println!("{} {}:{}", line.info.name, line.chip.display(), line.info.offset);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_lines_by_consumer(pattern: &str) -> Result<Vec<FoundLine>> {
+    Ok(LineIterator::new()?
+        .filter(|l| l.info.used && glob_match(pattern, &l.info.consumer))
+        .collect())
+}
+
+/// Find all the lines with names matching a regular expression.
+///
+/// As per [`regex::Regex::is_match`], the expression may match any part of the name
+/// unless it is anchored, e.g. "^PMIC_[0-9]+$".
+///
+/// Returns the matching lines in chip name order, then offset order.
+///
+/// Returns an error if the expression is invalid.
+#[cfg(feature = "regex")]
+pub fn find_lines_matching_regex(pattern: &str) -> Result<Vec<FoundLine>> {
+    let re = regex::Regex::new(pattern).map_err(|e| {
+        Error::InvalidArgument(format!("invalid regular expression '{}': {}", pattern, e))
+    })?;
+    Ok(LineIterator::new()?
+        .filter(|l| re.is_match(&l.info.name))
+        .collect())
+}
+
+/// Find the chips with labels matching a pattern.
+///
+/// The pattern is matched against the whole label, and may contain `*` to match any
+/// sequence of characters, and `?` to match any single character.
+/// e.g. "*bcm2711" or "gpio-sim*".
+///
+/// Chips are frequently identified by path, such as */dev/gpiochip0*, but the
+/// numbering of chips is not stable across boots on many platforms, whereas
+/// the label identifies the type of chip.
+///
+/// Returns the paths of the matching chips, sorted in name order.
+/// Chips that cannot be opened are skipped.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::Result;
+/// # fn main() -> Result<()> {
+/// for path in gpiocdev::chips_by_label("pinctrl-*")? {
+///     println!("{}", path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn chips_by_label(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for path in chip::chips()? {
+        if let Ok(info) = Chip::from_path(&path).and_then(|c| c.info()) {
+            if glob_match(pattern, &info.label) {
+                found.push(path);
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Match a string against a pattern containing `*` and `?` wildcards.
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    let (mut pi, mut si) = (0, 0);
+    // the position of the most recent `*`, and the string position it matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while si < s.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == s[si]) {
+            pi += 1;
+            si += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, si));
+            pi += 1;
+        } else if let Some((spi, ssi)) = star {
+            // extend the match of the `*` by one character
+            pi = spi + 1;
+            si = ssi + 1;
+            star = Some((spi, ssi + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
 /// The info for a line discovered in the system.
 ///
 /// Identifies the chip hosting the line, and the line info.
@@ -372,6 +608,42 @@ impl Iterator for LineIterator {
 pub mod request;
 pub use request::Request;
 
+pub mod waveform;
+
+pub mod scheduler;
+
+pub mod deadman;
+
+pub mod diagnostic;
+
+pub mod privilege;
+
+pub mod arbiter;
+
+pub mod inherit;
+
+pub mod dispatcher;
+
+pub mod decode;
+
+pub mod bulk;
+
+pub mod bus;
+
+pub mod supervisor;
+
+pub mod trace;
+
+pub mod selftest;
+
+pub mod datalog;
+
+pub mod poll;
+
+pub mod realtime;
+
+mod timerfd;
+
 /// The uAPI ABI versions available to interact with the kernel.
 ///
 /// Two versions of the Linux GPIO uAPI ABI currently exist, with v1 being released in
@@ -383,7 +655,7 @@ pub use request::Request;
 ///
 /// * `V2` is the current ABI and is used by default.
 /// * `V1` is more restrictive than V2, so some information and features are
-/// unavailable.
+///   unavailable.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(
     feature = "serde",
@@ -428,13 +700,29 @@ impl From<Timestamp> for SystemTime {
 
 /// Errors returned by [`gpiocdev`] functions.
 ///
+/// Further variants may be added in the future.
+///
 /// [`gpiocdev`]: crate
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
     /// An operation cannot be performed due to a limitation in the ABI version being used.
     #[error("{0} {1}.")]
     AbiLimitation(AbiVersion, String),
 
+    /// Returned by [`find_named_line_unique`] when multiple lines have the name.
+    ///
+    /// Contains the name and the candidate lines.
+    #[error("Line name '{0}' is not unique - found at {}", format_candidates(.1))]
+    AmbiguousLineName(String, Vec<FoundLine>),
+
+    /// Returned by [`bulk::Opener`] when the file descriptor limit is too low
+    /// to open all the chips and requests.
+    ///
+    /// Contains the number of file descriptors required and the number available.
+    #[error("Opening requires {0} file descriptors but only {1} are available")]
+    InsufficientFds(u64, u64),
+
     /// Problem accessing GPIO chip character devices
     #[error("\"{0}\" {1}.")]
     GpioChip(PathBuf, chip::ErrorKind),
@@ -472,12 +760,129 @@ pub enum Error {
     NoAbiSupport(),
 }
 
+fn format_candidates(lines: &[FoundLine]) -> String {
+    lines
+        .iter()
+        .map(|l| format!("{}:{}", l.chip.display(), l.info.offset))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Error::Os(uapi::Errno::from(&e))
     }
 }
 
+impl Error {
+    /// The underlying OS error number, if the error resulted from a failed system call.
+    pub fn errno(&self) -> Option<uapi::Errno> {
+        match self {
+            Error::Os(e) => Some(e.clone()),
+            Error::Uapi(_, uapi::Error::Os(e)) => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    /// The category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::AbiLimitation(..) | Error::UnsupportedAbi(..) | Error::NoAbiSupport() => {
+                ErrorKind::AbiUnsupported
+            }
+            Error::AmbiguousLineName(..) | Error::NonuniqueLineName(_) => ErrorKind::Ambiguous,
+            Error::InsufficientFds(..) => ErrorKind::ResourceExhausted,
+            Error::GpioChip(..) => ErrorKind::NotGpioDevice,
+            Error::InvalidArgument(_) => ErrorKind::InvalidArgument,
+            Error::NoGpioChips() => ErrorKind::NotFound,
+            Error::Os(e) | Error::Uapi(_, uapi::Error::Os(e)) => ErrorKind::from_errno(e.0),
+            Error::Uapi(..) | Error::UnexpectedResponse(_) => ErrorKind::UnexpectedResponse,
+        }
+    }
+
+    /// Returns true if the operation may succeed if retried.
+    ///
+    /// That is the operation was interrupted, would have blocked, or the line
+    /// or resource was busy.
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Busy | ErrorKind::Interrupted | ErrorKind::WouldBlock
+        )
+    }
+
+    /// Returns true if the error is due to insufficient permissions.
+    pub fn is_permission(&self) -> bool {
+        self.kind() == ErrorKind::PermissionDenied
+    }
+}
+
+/// The category of an [`Error`].
+///
+/// Allows callers to decide how to handle an error without matching on the
+/// details of each error variant, or on error messages.
+///
+/// Further kinds may be added in the future.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The ABI version does not support the operation, or is not supported itself.
+    AbiUnsupported,
+
+    /// A line name matches multiple lines.
+    Ambiguous,
+
+    /// The line, or other resource, is in use.
+    Busy,
+
+    /// The operation was interrupted by a signal.
+    Interrupted,
+
+    /// An argument, or the requested configuration, is invalid.
+    InvalidArgument,
+
+    /// A chip, line or other file was not found.
+    NotFound,
+
+    /// A file is not a GPIO character device.
+    NotGpioDevice,
+
+    /// A value or buffer exceeded the supported range.
+    Overflow,
+
+    /// The caller lacks the permissions for the operation.
+    PermissionDenied,
+
+    /// A resource limit, such as the number of open file descriptors, was reached.
+    ResourceExhausted,
+
+    /// The kernel returned content that could not be interpreted.
+    UnexpectedResponse,
+
+    /// The operation would have blocked.
+    WouldBlock,
+
+    /// Any other error.
+    Other,
+}
+
+impl ErrorKind {
+    fn from_errno(errno: i32) -> ErrorKind {
+        match errno {
+            libc::EACCES | libc::EPERM => ErrorKind::PermissionDenied,
+            libc::EAGAIN => ErrorKind::WouldBlock,
+            libc::EBUSY => ErrorKind::Busy,
+            libc::EINTR => ErrorKind::Interrupted,
+            libc::EINVAL => ErrorKind::InvalidArgument,
+            libc::EMFILE | libc::ENFILE | libc::ENOMEM => ErrorKind::ResourceExhausted,
+            libc::ENODEV | libc::ENOENT | libc::ENXIO => ErrorKind::NotFound,
+            libc::ENOTTY | libc::EOPNOTSUPP => ErrorKind::AbiUnsupported,
+            libc::E2BIG | libc::EOVERFLOW => ErrorKind::Overflow,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Identifiers for the underlying uAPI calls.
 #[doc(hidden)]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -573,20 +978,120 @@ pub fn supports_abi_version(abiv: AbiVersion) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn glob_match() {
+        use super::glob_match;
+        assert!(glob_match("pinctrl-bcm2711", "pinctrl-bcm2711"));
+        assert!(!glob_match("pinctrl-bcm2711", "pinctrl-bcm2835"));
+        assert!(glob_match("pinctrl-*", "pinctrl-bcm2711"));
+        assert!(glob_match("*2711", "pinctrl-bcm2711"));
+        assert!(glob_match("*bcm*", "pinctrl-bcm2711"));
+        assert!(glob_match("pinctrl-bcm27??", "pinctrl-bcm2711"));
+        assert!(!glob_match("pinctrl-bcm27?", "pinctrl-bcm2711"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(!glob_match("", "a"));
+    }
+
+    #[test]
+    fn ambiguous_line_name() {
+        let mut a = FoundLine::from(3);
+        a.chip = PathBuf::from("/dev/gpiochip0");
+        let mut b = FoundLine::from(7);
+        b.chip = PathBuf::from("/dev/gpiochip2");
+        let e = Error::AmbiguousLineName("RESET".to_string(), vec![a, b]);
+        assert_eq!(
+            e.to_string(),
+            "Line name 'RESET' is not unique - found at /dev/gpiochip0:3, /dev/gpiochip2:7"
+        );
+    }
+
+    mod error {
+        use crate::{AbiSupportKind, AbiVersion, Error, ErrorKind, UapiCall};
+        use gpiocdev_uapi::{Errno, Error as UapiError};
+
+        #[test]
+        fn errno() {
+            assert_eq!(
+                Error::Os(Errno(libc::EBUSY)).errno(),
+                Some(Errno(libc::EBUSY))
+            );
+            assert_eq!(
+                Error::Uapi(UapiCall::GetLine, UapiError::Os(Errno(libc::EPERM))).errno(),
+                Some(Errno(libc::EPERM))
+            );
+            assert_eq!(Error::InvalidArgument("bad".into()).errno(), None);
+            assert_eq!(Error::NoAbiSupport().errno(), None);
+        }
+
+        #[test]
+        fn kind() {
+            let uapi = |errno| Error::Uapi(UapiCall::GetLine, UapiError::Os(Errno(errno)));
+            assert_eq!(uapi(libc::EBUSY).kind(), ErrorKind::Busy);
+            assert_eq!(uapi(libc::EACCES).kind(), ErrorKind::PermissionDenied);
+            assert_eq!(uapi(libc::EPERM).kind(), ErrorKind::PermissionDenied);
+            assert_eq!(uapi(libc::EINVAL).kind(), ErrorKind::InvalidArgument);
+            assert_eq!(uapi(libc::ENOTTY).kind(), ErrorKind::AbiUnsupported);
+            assert_eq!(uapi(libc::E2BIG).kind(), ErrorKind::Overflow);
+            assert_eq!(uapi(libc::EIO).kind(), ErrorKind::Other);
+            assert_eq!(Error::Os(Errno(libc::ENOENT)).kind(), ErrorKind::NotFound);
+            assert_eq!(
+                Error::Os(Errno(libc::EMFILE)).kind(),
+                ErrorKind::ResourceExhausted
+            );
+            assert_eq!(
+                Error::UnsupportedAbi(AbiVersion::V1, AbiSupportKind::Kernel).kind(),
+                ErrorKind::AbiUnsupported
+            );
+            assert_eq!(
+                Error::NonuniqueLineName("RESET".into()).kind(),
+                ErrorKind::Ambiguous
+            );
+            assert_eq!(
+                Error::InsufficientFds(10, 5).kind(),
+                ErrorKind::ResourceExhausted
+            );
+            assert_eq!(Error::NoGpioChips().kind(), ErrorKind::NotFound);
+            assert_eq!(
+                Error::UnexpectedResponse("junk".into()).kind(),
+                ErrorKind::UnexpectedResponse
+            );
+        }
+
+        #[test]
+        fn is_retriable() {
+            assert!(Error::Os(Errno(libc::EBUSY)).is_retriable());
+            assert!(Error::Os(Errno(libc::EINTR)).is_retriable());
+            assert!(Error::Os(Errno(libc::EAGAIN)).is_retriable());
+            assert!(!Error::Os(Errno(libc::EPERM)).is_retriable());
+            assert!(!Error::InvalidArgument("bad".into()).is_retriable());
+        }
+
+        #[test]
+        fn is_permission() {
+            assert!(Error::Os(Errno(libc::EACCES)).is_permission());
+            assert!(Error::Os(Errno(libc::EPERM)).is_permission());
+            assert!(!Error::Os(Errno(libc::EBUSY)).is_permission());
+            assert!(!Error::NoGpioChips().is_permission());
+        }
+    }
+
     mod timestamp {
         use crate::Timestamp;
         use chrono::{DateTime, Utc};
+        use std::time::SystemTime;
 
         #[test]
         fn from_nanos() {
             let t = Timestamp::from_nanos(123);
-            assert_eq!(t.0.timestamp_nanos_opt(), Some(123));
+            assert_eq!(DateTime::<Utc>::from(t.0).timestamp_nanos_opt(), Some(123));
         }
 
         #[test]
         fn into_datetime() {
             let t = Timestamp::from_nanos(678);
-            let dt: DateTime<Utc> = t.into();
+            let dt: DateTime<Utc> = SystemTime::from(t).into();
             assert_eq!(dt.timestamp_nanos_opt(), Some(678));
         }
     }
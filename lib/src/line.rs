@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 mod config;
-pub use self::config::Config;
+pub use self::config::{format_period, parse_period, Config};
 
 mod event;
 pub use self::event::{EdgeEvent, EdgeKind, InfoChangeEvent, InfoChangeKind};
@@ -11,6 +11,9 @@ pub use self::event::{EdgeEvent, EdgeKind, InfoChangeEvent, InfoChangeKind};
 mod info;
 pub use self::info::Info;
 
+mod line_ref;
+pub use self::line_ref::LineRef;
+
 mod value;
 pub use self::value::{Value, Values};
 
@@ -384,6 +387,10 @@ mod tests {
                 EventClock::from(v2::LineFlags::EVENT_CLOCK_REALTIME),
                 EventClock::Realtime
             );
+            assert_eq!(
+                EventClock::from(v2::LineFlags::EVENT_CLOCK_HTE),
+                EventClock::Hte
+            );
         }
     }
 }
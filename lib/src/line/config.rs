@@ -2,7 +2,8 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::{Bias, Direction, Drive, EdgeDetection, EventClock, Value};
+use super::{Bias, Direction, Drive, EdgeDetection, EventClock, Info, Value};
+use crate::Error;
 
 #[cfg(feature = "uapi_v1")]
 use gpiocdev_uapi::v1;
@@ -10,6 +11,7 @@ use gpiocdev_uapi::v1;
 use gpiocdev_uapi::v2;
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::time::Duration;
 
 /// The configuration settings for a single line.
@@ -71,6 +73,69 @@ impl Config {
         false
     }
 
+    /// The value last set for an output line, if known.
+    pub(crate) fn last_set_value(&self) -> Option<Value> {
+        if self.direction == Some(Direction::Output) {
+            self.value
+        } else {
+            None
+        }
+    }
+
+    /// Create a config matching the current configuration of a line, as reported
+    /// by its info.
+    ///
+    /// The output value is not available from the info, so is left unset.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// # use gpiocdev::chip::Chip;
+    /// # use gpiocdev::line::Config;
+    /// # fn main() -> Result<()> {
+    /// let chip = Chip::from_path("/dev/gpiochip0")?;
+    /// let lcfg = Config::from_info(&chip.line_info(17)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_info(info: &Info) -> Config {
+        Config {
+            direction: Some(info.direction),
+            active_low: info.active_low,
+            bias: info.bias,
+            drive: info.drive,
+            edge_detection: info.edge_detection,
+            event_clock: info.event_clock,
+            debounce_period: info.debounce_period.filter(|p| !p.is_zero()),
+            value: None,
+        }
+    }
+
+    /// Return the configuration as applied by the kernel.
+    ///
+    /// Settings that are not relevant to the direction are not passed to the kernel,
+    /// so are dropped, and the debounce period is rounded up to the microsecond
+    /// resolution of the uAPI.
+    pub(crate) fn effective(&self) -> Config {
+        let mut cfg = self.clone();
+        if cfg.direction != Some(Direction::Output) {
+            cfg.drive = None;
+            cfg.value = None;
+        }
+        if cfg.direction != Some(Direction::Input) {
+            cfg.edge_detection = None;
+            cfg.debounce_period = None;
+        }
+        if cfg.edge_detection.is_none() {
+            cfg.event_clock = None;
+        }
+        if let Some(dp) = cfg.debounce_period {
+            let dp_us = (dp + Duration::from_nanos(999)).as_micros() as u64;
+            cfg.debounce_period = Some(Duration::from_micros(dp_us));
+        }
+        cfg
+    }
+
     /// return the effective value specified by the config
     pub(crate) fn value(&self) -> Value {
         match self.value {
@@ -80,6 +145,244 @@ impl Config {
     }
 }
 
+/// Parse a line configuration from a compact comma separated list of attributes.
+///
+/// The supported attributes are:
+///
+/// | Attribute | Setting |
+/// |-----------|---------|
+/// | `input`, `output`, `as-is` | direction |
+/// | `active-low`, `active-high` | active low |
+/// | `pull-up`, `pull-down`, `bias-disabled`, `bias=<bias>` | bias |
+/// | `push-pull`, `open-drain`, `open-source`, `drive=<drive>` | drive |
+/// | `edges=rising\|falling\|both\|none` | edge detection |
+/// | `event-clock=monotonic\|realtime\|hte` | event clock |
+/// | `debounce=<period>` | debounce period |
+/// | `value=active\|inactive\|1\|0`, `output=<value>` | output value |
+///
+/// Periods are parsed by [`parse_period`].
+///
+/// Attributes are case insensitive and applied in order, so later attributes
+/// override earlier ones.
+///
+/// # Examples
+/// ```
+/// # use gpiocdev::line::{Bias, Config, Direction, EdgeDetection};
+/// # use std::time::Duration;
+/// let cfg: Config = "input,pull-up,active-low,edges=both,debounce=5ms".parse()?;
+/// assert_eq!(cfg.direction, Some(Direction::Input));
+/// assert_eq!(cfg.bias, Some(Bias::PullUp));
+/// assert!(cfg.active_low);
+/// assert_eq!(cfg.edge_detection, Some(EdgeDetection::BothEdges));
+/// assert_eq!(cfg.debounce_period, Some(Duration::from_millis(5)));
+/// # Ok::<(), gpiocdev::Error>(())
+/// ```
+impl FromStr for Config {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cfg = Config::default();
+        cfg.apply_str(s)?;
+        Ok(cfg)
+    }
+}
+
+impl Config {
+    /// Apply the attributes in a configuration string on top of the existing
+    /// configuration.
+    ///
+    /// The string has the same form as that parsed by [`FromStr`].
+    /// Each attribute explicitly sets its setting, so `active-high`, `as-is`,
+    /// `bias=as-is` and `edges=none` clear settings from the existing
+    /// configuration, while settings not named in the string are unchanged.
+    ///
+    /// On error the configuration may be partially updated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gpiocdev::line::{Bias, Config};
+    /// let mut cfg: Config = "input,pull-up,active-low".parse()?;
+    /// cfg.apply_str("active-high,bias=as-is")?;
+    /// assert_eq!(cfg.bias, None);
+    /// assert!(!cfg.active_low);
+    /// # Ok::<(), gpiocdev::Error>(())
+    /// ```
+    pub fn apply_str(&mut self, s: &str) -> Result<&mut Self, Error> {
+        for attr in s.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            let (key, value) = match attr.split_once('=') {
+                Some((k, v)) => (k.trim().to_ascii_lowercase(), Some(v.trim())),
+                None => (attr.to_ascii_lowercase(), None),
+            };
+            match (key.as_str(), value) {
+                ("input", None) => self.direction = Some(Direction::Input),
+                ("output", None) => self.direction = Some(Direction::Output),
+                ("output", Some(v)) => {
+                    self.direction = Some(Direction::Output);
+                    self.value = Some(parse_value(v)?);
+                }
+                ("as-is", None) => self.direction = None,
+                ("active-low", None) => self.active_low = true,
+                ("active-high", None) => self.active_low = false,
+                ("pull-up", None) => self.bias = Some(Bias::PullUp),
+                ("pull-down", None) => self.bias = Some(Bias::PullDown),
+                ("bias-disabled", None) => self.bias = Some(Bias::Disabled),
+                ("bias", Some(v)) => self.bias = parse_bias(v)?,
+                ("push-pull", None) => self.drive = Some(Drive::PushPull),
+                ("open-drain", None) => self.drive = Some(Drive::OpenDrain),
+                ("open-source", None) => self.drive = Some(Drive::OpenSource),
+                ("drive", Some(v)) => self.drive = Some(parse_drive(v)?),
+                ("edges", Some(v)) => self.edge_detection = parse_edges(v)?,
+                ("event-clock", Some(v)) => self.event_clock = Some(parse_event_clock(v)?),
+                ("debounce", Some(v)) => self.debounce_period = Some(parse_period(v)?),
+                ("value", Some(v)) => self.value = Some(parse_value(v)?),
+                _ => {
+                    return Err(Error::InvalidArgument(format!(
+                        "unknown line attribute '{}'.",
+                        attr
+                    )))
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn invalid_setting(attr: &str, value: &str) -> Error {
+    Error::InvalidArgument(format!("invalid {} '{}'.", attr, value))
+}
+
+fn parse_bias(s: &str) -> Result<Option<Bias>, Error> {
+    match s.to_ascii_lowercase().as_str() {
+        "pull-up" => Ok(Some(Bias::PullUp)),
+        "pull-down" => Ok(Some(Bias::PullDown)),
+        "disabled" => Ok(Some(Bias::Disabled)),
+        "as-is" => Ok(None),
+        _ => Err(invalid_setting("bias", s)),
+    }
+}
+
+fn parse_drive(s: &str) -> Result<Drive, Error> {
+    match s.to_ascii_lowercase().as_str() {
+        "push-pull" => Ok(Drive::PushPull),
+        "open-drain" => Ok(Drive::OpenDrain),
+        "open-source" => Ok(Drive::OpenSource),
+        _ => Err(invalid_setting("drive", s)),
+    }
+}
+
+fn parse_edges(s: &str) -> Result<Option<EdgeDetection>, Error> {
+    match s.to_ascii_lowercase().as_str() {
+        "rising" => Ok(Some(EdgeDetection::RisingEdge)),
+        "falling" => Ok(Some(EdgeDetection::FallingEdge)),
+        "both" => Ok(Some(EdgeDetection::BothEdges)),
+        "none" => Ok(None),
+        _ => Err(invalid_setting("edges", s)),
+    }
+}
+
+fn parse_event_clock(s: &str) -> Result<EventClock, Error> {
+    match s.to_ascii_lowercase().as_str() {
+        "monotonic" => Ok(EventClock::Monotonic),
+        "realtime" => Ok(EventClock::Realtime),
+        "hte" => Ok(EventClock::Hte),
+        _ => Err(invalid_setting("event-clock", s)),
+    }
+}
+
+fn parse_value(s: &str) -> Result<Value, Error> {
+    match s.to_ascii_lowercase().as_str() {
+        "active" | "1" => Ok(Value::Active),
+        "inactive" | "0" => Ok(Value::Inactive),
+        _ => Err(invalid_setting("value", s)),
+    }
+}
+
+// The units accepted in periods, and their length in nanoseconds, largest first.
+const PERIOD_UNITS: [(&str, u64); 6] = [
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Parse a period, such as `20ms` or `1m30s`.
+///
+/// A bare number is taken as milliseconds.
+/// Otherwise the period is a sequence of numbers, each suffixed with units of
+/// `h`, `m`, `s`, `ms`, `us` or `ns`.
+///
+/// # Examples
+/// ```
+/// # use gpiocdev::line::parse_period;
+/// # use std::time::Duration;
+/// assert_eq!(parse_period("20")?, Duration::from_millis(20));
+/// assert_eq!(parse_period("1m30s")?, Duration::from_secs(90));
+/// # Ok::<(), gpiocdev::Error>(())
+/// ```
+pub fn parse_period(s: &str) -> Result<Duration, Error> {
+    let invalid = || invalid_setting("period", s);
+    if s.is_empty() {
+        return Err(invalid());
+    }
+    if s.chars().all(|c| c.is_ascii_digit()) {
+        return s.parse().map(Duration::from_millis).map_err(|_| invalid());
+    }
+    let mut ns: u64 = 0;
+    let mut rem = s;
+    while !rem.is_empty() {
+        let n = rem.find(|c: char| !c.is_ascii_digit()).unwrap_or(rem.len());
+        if n == 0 {
+            return Err(invalid());
+        }
+        let (num, tail) = rem.split_at(n);
+        let u = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (units, tail) = tail.split_at(u);
+        let scale = match PERIOD_UNITS.iter().find(|(name, _)| *name == units) {
+            Some((_, scale)) => *scale,
+            None => return Err(invalid()),
+        };
+        ns = num
+            .parse::<u64>()
+            .ok()
+            .and_then(|t| t.checked_mul(scale))
+            .and_then(|t| t.checked_add(ns))
+            .ok_or_else(invalid)?;
+        rem = tail;
+    }
+    Ok(Duration::from_nanos(ns))
+}
+
+/// Format a period using the largest units that represent it exactly.
+///
+/// The result can be parsed by [`parse_period`] to return the original period.
+///
+/// # Examples
+/// ```
+/// # use gpiocdev::line::format_period;
+/// # use std::time::Duration;
+/// assert_eq!(format_period(Duration::from_millis(20)), "20ms");
+/// assert_eq!(format_period(Duration::from_micros(1500)), "1ms500us");
+/// ```
+pub fn format_period(d: Duration) -> String {
+    if d.is_zero() {
+        return "0".to_string();
+    }
+    let mut ns = d.as_nanos();
+    let mut s = String::new();
+    for (units, scale) in PERIOD_UNITS {
+        let scale = u128::from(scale);
+        if ns >= scale {
+            s += &format!("{}{}", ns / scale, units);
+            ns %= scale;
+        }
+    }
+    s
+}
+
 #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
 impl From<&Config> for v2::LineFlags {
     fn from(cfg: &Config) -> v2::LineFlags {
@@ -193,6 +496,257 @@ mod tests {
         assert!(cfg.value.is_none());
     }
 
+    #[test]
+    fn from_info() {
+        let info = Info {
+            offset: 3,
+            name: "GPIO3".to_string(),
+            used: true,
+            direction: Direction::Input,
+            active_low: true,
+            bias: Some(Bias::PullDown),
+            edge_detection: Some(EdgeDetection::RisingEdge),
+            event_clock: Some(EventClock::Realtime),
+            debounce_period: Some(Duration::from_millis(3)),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_info(&info),
+            Config {
+                direction: Some(Direction::Input),
+                active_low: true,
+                bias: Some(Bias::PullDown),
+                edge_detection: Some(EdgeDetection::RisingEdge),
+                event_clock: Some(EventClock::Realtime),
+                debounce_period: Some(Duration::from_millis(3)),
+                ..Default::default()
+            }
+        );
+
+        let info = Info {
+            direction: Direction::Output,
+            drive: Some(Drive::OpenSource),
+            debounce_period: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_info(&info),
+            Config {
+                direction: Some(Direction::Output),
+                drive: Some(Drive::OpenSource),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn effective() {
+        let cfg = Config {
+            direction: Some(Direction::Input),
+            bias: Some(Bias::PullUp),
+            drive: Some(Drive::OpenDrain),
+            event_clock: Some(EventClock::Realtime),
+            debounce_period: Some(Duration::from_nanos(1500)),
+            value: Some(Value::Active),
+            ..Default::default()
+        };
+        assert_eq!(
+            cfg.effective(),
+            Config {
+                direction: Some(Direction::Input),
+                bias: Some(Bias::PullUp),
+                debounce_period: Some(Duration::from_micros(2)),
+                ..Default::default()
+            }
+        );
+
+        let cfg = Config {
+            direction: Some(Direction::Output),
+            drive: Some(Drive::OpenDrain),
+            edge_detection: Some(EdgeDetection::BothEdges),
+            event_clock: Some(EventClock::Realtime),
+            debounce_period: Some(Duration::from_millis(5)),
+            value: Some(Value::Active),
+            ..Default::default()
+        };
+        assert_eq!(
+            cfg.effective(),
+            Config {
+                direction: Some(Direction::Output),
+                drive: Some(Drive::OpenDrain),
+                value: Some(Value::Active),
+                ..Default::default()
+            }
+        );
+
+        let cfg = Config {
+            active_low: true,
+            drive: Some(Drive::OpenDrain),
+            value: Some(Value::Active),
+            ..Default::default()
+        };
+        assert_eq!(
+            cfg.effective(),
+            Config {
+                active_low: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let cfg: Config = "input,pull-up,active-low,edges=both,debounce=5ms"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            cfg,
+            Config {
+                direction: Some(Direction::Input),
+                active_low: true,
+                bias: Some(Bias::PullUp),
+                edge_detection: Some(EdgeDetection::BothEdges),
+                debounce_period: Some(Duration::from_millis(5)),
+                ..Default::default()
+            }
+        );
+
+        let cfg: Config = " Output=1, open-drain , bias=disabled,event-clock=realtime"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            cfg,
+            Config {
+                direction: Some(Direction::Output),
+                bias: Some(Bias::Disabled),
+                drive: Some(Drive::OpenDrain),
+                event_clock: Some(EventClock::Realtime),
+                value: Some(Value::Active),
+                ..Default::default()
+            }
+        );
+
+        // later attributes override
+        let cfg: Config =
+            "output,value=active,as-is,active-low,active-high,bias=pull-down,bias=as-is"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            cfg,
+            Config {
+                value: Some(Value::Active),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!("".parse::<Config>().unwrap(), Config::default());
+        assert_eq!(
+            "debounce=2s".parse::<Config>().unwrap().debounce_period,
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            "debounce=20".parse::<Config>().unwrap().debounce_period,
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            "debounce=10us".parse::<Config>().unwrap().debounce_period,
+            Some(Duration::from_micros(10))
+        );
+    }
+
+    #[test]
+    fn from_str_errors() {
+        assert_eq!(
+            "input,pull-sideways".parse::<Config>().unwrap_err(),
+            Error::InvalidArgument("unknown line attribute 'pull-sideways'.".to_string())
+        );
+        assert_eq!(
+            "input=1".parse::<Config>().unwrap_err(),
+            Error::InvalidArgument("unknown line attribute 'input=1'.".to_string())
+        );
+        assert_eq!(
+            "edges=up".parse::<Config>().unwrap_err(),
+            Error::InvalidArgument("invalid edges 'up'.".to_string())
+        );
+        assert_eq!(
+            "debounce=5min".parse::<Config>().unwrap_err(),
+            Error::InvalidArgument("invalid period '5min'.".to_string())
+        );
+        assert_eq!(
+            "debounce=ms".parse::<Config>().unwrap_err(),
+            Error::InvalidArgument("invalid period 'ms'.".to_string())
+        );
+        assert_eq!(
+            "value=on".parse::<Config>().unwrap_err(),
+            Error::InvalidArgument("invalid value 'on'.".to_string())
+        );
+    }
+
+    #[test]
+    fn apply_str() {
+        let mut cfg: Config = "output,active-low,pull-up,open-drain,value=1"
+            .parse()
+            .unwrap();
+        cfg.apply_str("active-high,bias=as-is").unwrap();
+        assert_eq!(
+            cfg,
+            Config {
+                direction: Some(Direction::Output),
+                drive: Some(Drive::OpenDrain),
+                value: Some(Value::Active),
+                ..Default::default()
+            }
+        );
+        let mut cfg: Config = "input,edges=both".parse().unwrap();
+        cfg.apply_str("edges=none").unwrap();
+        assert_eq!(cfg.edge_detection, None);
+        assert_eq!(cfg.direction, Some(Direction::Input));
+        cfg.apply_str("as-is").unwrap();
+        assert_eq!(cfg, Config::default());
+    }
+
+    #[test]
+    fn period() {
+        assert_eq!(parse_period("0").unwrap(), Duration::ZERO);
+        assert_eq!(parse_period("1").unwrap(), Duration::from_millis(1));
+        assert_eq!(parse_period("2ms").unwrap(), Duration::from_millis(2));
+        assert_eq!(parse_period("3us").unwrap(), Duration::from_micros(3));
+        assert_eq!(parse_period("4s").unwrap(), Duration::new(4, 0));
+        assert_eq!(parse_period("5ns").unwrap(), Duration::from_nanos(5));
+        assert_eq!(parse_period("6m").unwrap(), Duration::from_secs(360));
+        assert_eq!(parse_period("7h").unwrap(), Duration::from_secs(25200));
+        assert_eq!(parse_period("1m30s").unwrap(), Duration::from_secs(90));
+        assert_eq!(
+            parse_period("1s500ms20us").unwrap(),
+            Duration::from_micros(1_500_020)
+        );
+        for s in ["", "5d", "1m30", "1mm", "99999999999h", "bad"] {
+            assert_eq!(
+                parse_period(s).unwrap_err(),
+                Error::InvalidArgument(format!("invalid period '{}'.", s))
+            );
+        }
+    }
+
+    #[test]
+    fn format_periods() {
+        assert_eq!(format_period(Duration::ZERO), "0");
+        assert_eq!(format_period(Duration::from_millis(20)), "20ms");
+        assert_eq!(format_period(Duration::from_secs(90)), "1m30s");
+        assert_eq!(format_period(Duration::from_micros(1500)), "1ms500us");
+        assert_eq!(format_period(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_period(Duration::new(3661, 1)), "1h1m1s1ns");
+        // round trips
+        for d in [
+            Duration::from_nanos(1),
+            Duration::from_nanos(999_999_999),
+            Duration::from_micros(12_345_678),
+            Duration::new(86_400, 500),
+        ] {
+            assert_eq!(parse_period(&format_period(d)).unwrap(), d);
+        }
+    }
+
     #[test]
     #[cfg(feature = "uapi_v1")]
     fn equivalent() {
@@ -301,6 +855,23 @@ mod tests {
         assert!(!flags.contains(v2::LineFlags::OPEN_SOURCE));
         assert!(!flags.contains(v2::LineFlags::EVENT_CLOCK_HTE));
 
+        let cfg = Config {
+            direction: Some(Direction::Input),
+            active_low: false,
+            bias: None,
+            drive: None,
+            edge_detection: Some(EdgeDetection::RisingEdge),
+            event_clock: Some(EventClock::Hte),
+            debounce_period: None,
+            value: None,
+        };
+        let flags = v2::LineFlags::from(&cfg);
+        assert!(flags.contains(v2::LineFlags::INPUT));
+        assert!(flags.contains(v2::LineFlags::EDGE_RISING));
+        assert!(!flags.contains(v2::LineFlags::EDGE_FALLING));
+        assert!(flags.contains(v2::LineFlags::EVENT_CLOCK_HTE));
+        assert!(!flags.contains(v2::LineFlags::EVENT_CLOCK_REALTIME));
+
         let cfg = Config {
             direction: Some(Direction::Output),
             active_low: false,
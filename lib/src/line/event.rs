@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::{Info, Offset};
+use super::{EventClock, Info, Offset};
 #[cfg(all(feature = "uapi_v1", not(feature = "uapi_v2")))]
 use gpiocdev_uapi::v1 as uapi;
 #[cfg(feature = "uapi_v1")]
@@ -11,6 +11,7 @@ use gpiocdev_uapi::v1;
 use gpiocdev_uapi::{v2, v2 as uapi};
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
 
 /// The details of an edge detected on an input line.
 ///
@@ -23,10 +24,12 @@ pub struct EdgeEvent {
     /// The interpretation of this field depends on line [`EventClock`](super::EventClock)
     /// configuration and uAPI ABI version, and so is left raw here.
     ///
-    /// **CLOCK_REALTIME** can be converted to [`Timestamp`](crate::Timestamp).
+    /// **CLOCK_REALTIME** can be converted to [`Timestamp`](crate::Timestamp),
+    /// or using [`system_time`](EdgeEvent::system_time).
     ///
     /// **CLOCK_MONOTONIC** is intended for comparing times between events and
-    /// should be converted to [`Duration`](std::time::Duration).
+    /// should be converted to [`Duration`](std::time::Duration), such as using
+    /// [`duration_since`](EdgeEvent::duration_since).
     pub timestamp_ns: u64,
 
     /// The event trigger identifier.
@@ -48,6 +51,45 @@ pub struct EdgeEvent {
     )]
     pub line_seqno: u32,
 }
+
+impl EdgeEvent {
+    /// The time of the event as a [`SystemTime`].
+    ///
+    /// The `clock` is the [`EventClock`] the line was configured with.
+    ///
+    /// Returns `None` unless the clock is [`EventClock::Realtime`], as the other clocks
+    /// are not related to wall clock time.
+    ///
+    /// Note that uAPI v1 does not support selecting the event clock, and uses
+    /// **CLOCK_MONOTONIC** on Linux 5.7 and later, and **CLOCK_REALTIME** prior to that.
+    pub fn system_time(&self, clock: EventClock) -> Option<SystemTime> {
+        match clock {
+            EventClock::Realtime => {
+                Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(self.timestamp_ns))
+            }
+            _ => None,
+        }
+    }
+
+    /// The time of the event as a UTC [`DateTime`](chrono::DateTime).
+    ///
+    /// As per [`system_time`](EdgeEvent::system_time).
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self, clock: EventClock) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.system_time(clock).map(chrono::DateTime::from)
+    }
+
+    /// The time elapsed between an earlier event, the anchor, and this event.
+    ///
+    /// This is valid for any clock, as long as both events were timestamped using the
+    /// same clock, such as events from the same request.
+    ///
+    /// Returns a zero duration if the anchor occurred after this event.
+    pub fn duration_since(&self, anchor: &EdgeEvent) -> Duration {
+        Duration::from_nanos(self.timestamp_ns.saturating_sub(anchor.timestamp_ns))
+    }
+}
+
 #[cfg(feature = "uapi_v1")]
 impl From<&v1::LineEdgeEvent> for EdgeEvent {
     fn from(le: &v1::LineEdgeEvent) -> Self {
@@ -199,6 +241,46 @@ mod tests {
             assert_eq!(ee.seqno, 2);
             assert_eq!(ee.line_seqno, 1);
         }
+
+        fn event(timestamp_ns: u64) -> EdgeEvent {
+            EdgeEvent {
+                timestamp_ns,
+                kind: EdgeKind::Rising,
+                offset: 3,
+                seqno: 0,
+                line_seqno: 0,
+            }
+        }
+
+        #[test]
+        fn system_time() {
+            let ee = event(1_700_000_000_123_456_789);
+            assert_eq!(
+                ee.system_time(EventClock::Realtime),
+                Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789))
+            );
+            assert_eq!(ee.system_time(EventClock::Monotonic), None);
+            assert_eq!(ee.system_time(EventClock::Hte), None);
+        }
+
+        #[test]
+        #[cfg(feature = "chrono")]
+        fn date_time() {
+            let ee = event(1_700_000_000_123_456_789);
+            let dt = ee.date_time(EventClock::Realtime).unwrap();
+            assert_eq!(dt.timestamp(), 1_700_000_000);
+            assert_eq!(dt.timestamp_subsec_nanos(), 123_456_789);
+            assert_eq!(ee.date_time(EventClock::Monotonic), None);
+        }
+
+        #[test]
+        fn duration_since() {
+            let anchor = event(1_000_000);
+            let ee = event(3_500_000);
+            assert_eq!(ee.duration_since(&anchor), Duration::from_micros(2500));
+            assert_eq!(anchor.duration_since(&ee), Duration::ZERO);
+            assert_eq!(ee.duration_since(&ee), Duration::ZERO);
+        }
     }
 
     mod info_change_event {
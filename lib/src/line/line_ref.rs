@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Offset;
+use crate::{Chip, Error, FoundLine, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// A reference to a line, either by chip and offset, or by name.
+///
+/// A line reference is parsed from one of:
+///  - `<chip>:<offset>`, such as `"gpiochip0:5"`, `"0:5"`, or `"/dev/gpiochip0:5"`,
+///    where the chip is identified by name, number or path.
+///  - `<name>`, such as `"MOTOR_EN"`, the name of the line as reported by the kernel.
+///
+/// A string ending in a colon and a number is always taken to be a chip and offset.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::line::{LineRef, Value};
+/// # fn main() -> Result<()> {
+/// let motor: LineRef = "MOTOR_EN".parse()?;
+/// let led: LineRef = "gpiochip0:5".parse()?;
+/// assert_eq!(led, LineRef::Offset("gpiochip0".to_string(), 5));
+/// let req = Request::builder()
+///     .with_line_ref(&motor)
+///     .as_output(Value::Active)
+///     .request()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum LineRef {
+    /// The line with the offset on the chip identified by name, number or path.
+    Offset(String, Offset),
+
+    /// The line with the name.
+    Name(String),
+}
+
+impl LineRef {
+    /// Find the line referred to.
+    ///
+    /// A line referred to by name is the first line with that name found in the system,
+    /// as per [`find_named_line`].
+    ///
+    /// [`find_named_line`]: crate::find_named_line
+    pub fn resolve(&self) -> Result<FoundLine> {
+        match self {
+            LineRef::Offset(chip, offset) => {
+                let chip = Chip::from_path(crate::chip::path_from_id(chip))?;
+                let info = chip.line_info(*offset)?;
+                Ok(FoundLine {
+                    chip: chip.path().to_path_buf(),
+                    info,
+                })
+            }
+            LineRef::Name(name) => crate::find_named_line(name)
+                .ok_or_else(|| Error::InvalidArgument(format!("No line named '{}' found.", name))),
+        }
+    }
+}
+
+impl FromStr for LineRef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Err(Error::InvalidArgument("empty line reference.".to_string()));
+        }
+        if let Some((chip, offset)) = s.rsplit_once(':') {
+            if let Ok(offset) = offset.parse::<Offset>() {
+                if chip.is_empty() {
+                    return Err(Error::InvalidArgument(format!(
+                        "no chip in line reference '{}'.",
+                        s
+                    )));
+                }
+                return Ok(LineRef::Offset(chip.to_string(), offset));
+            }
+        }
+        Ok(LineRef::Name(s.to_string()))
+    }
+}
+
+impl fmt::Display for LineRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineRef::Offset(chip, offset) => write!(f, "{}:{}", chip, offset),
+            LineRef::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl From<&FoundLine> for LineRef {
+    fn from(line: &FoundLine) -> Self {
+        LineRef::Offset(line.chip.to_string_lossy().into_owned(), line.info.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "gpiochip0:5".parse::<LineRef>(),
+            Ok(LineRef::Offset("gpiochip0".to_string(), 5))
+        );
+        assert_eq!(
+            "0:5".parse::<LineRef>(),
+            Ok(LineRef::Offset("0".to_string(), 5))
+        );
+        assert_eq!(
+            "/dev/gpiochip1:12".parse::<LineRef>(),
+            Ok(LineRef::Offset("/dev/gpiochip1".to_string(), 12))
+        );
+        assert_eq!(
+            "MOTOR_EN".parse::<LineRef>(),
+            Ok(LineRef::Name("MOTOR_EN".to_string()))
+        );
+        // names may contain colons
+        assert_eq!(
+            "P9:header".parse::<LineRef>(),
+            Ok(LineRef::Name("P9:header".to_string()))
+        );
+        assert_eq!("5".parse::<LineRef>(), Ok(LineRef::Name("5".to_string())));
+        assert!("".parse::<LineRef>().is_err());
+        assert!(":5".parse::<LineRef>().is_err());
+    }
+
+    #[test]
+    fn display() {
+        for s in ["gpiochip0:5", "0:5", "/dev/gpiochip1:12", "MOTOR_EN"] {
+            assert_eq!(s.parse::<LineRef>().unwrap().to_string(), s);
+        }
+    }
+}
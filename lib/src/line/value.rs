@@ -3,10 +3,6 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use super::Offset;
-#[cfg(feature = "uapi_v1")]
-use gpiocdev_uapi::v1;
-#[cfg(feature = "uapi_v2")]
-use gpiocdev_uapi::v2;
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -105,165 +101,16 @@ pub struct LineValue {
 /// A  collection of line values.
 ///
 /// Lines are identified by their offset.
-#[derive(Debug, Default, Eq, PartialEq)]
+///
+/// Once populated, a collection can be reused across calls to [`Request::values`]
+/// and [`Request::set_values`] without further allocation.
+///
+/// [`Request::values`]: crate::Request::values
+/// [`Request::set_values`]: crate::Request::set_values
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Values(Vec<LineValue>);
 impl Values {
-    // updates the values in dst with values from src.
-    //
-    // Both sets of offsets must be sorted.
-    #[cfg(feature = "uapi_v1")]
-    pub(crate) fn update_from_v1(&mut self, offsets: &[Offset], src: &v1::LineValues) {
-        // requested full set
-        if self.0.is_empty() {
-            self.0.reserve_exact(offsets.len());
-            for (idx, offset) in offsets.iter().enumerate() {
-                self.0.push(LineValue {
-                    offset: *offset,
-                    value: Value::from(src.get(idx)),
-                });
-            }
-            return;
-        }
-        // requested explicit set
-        let mut sidx = 0;
-        for lv in self.0.iter_mut() {
-            // values is a superset of src, and both are sorted...
-            while sidx < offsets.len() {
-                match lv.offset.cmp(&offsets[sidx]) {
-                    Ordering::Less => {
-                        // offset is self, but not src, so skip it
-                        break;
-                    }
-                    Ordering::Equal => {
-                        lv.value = Value::from(src.get(sidx));
-                        sidx += 1;
-                        break;
-                    }
-                    Ordering::Greater => {
-                        // offset is in src, but not self, so ignore it
-                        sidx += 1;
-                        continue;
-                    }
-                }
-            }
-        }
-    }
-
-    // updates the values in dst with values from src.
-    #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
-    pub(crate) fn update_from_v2(&mut self, offsets: &[Offset], src: &v2::LineValues) {
-        // requested full set
-        if self.0.is_empty() {
-            self.0.reserve_exact(offsets.len());
-            for (idx, offset) in offsets.iter().enumerate() {
-                self.0.push(LineValue {
-                    offset: *offset,
-                    value: Value::from(src.get(idx).unwrap()),
-                });
-            }
-            return;
-        }
-        // requested explicit set
-        let mut didx = 0;
-        for (idx, offset) in offsets.iter().enumerate() {
-            if let Some(v) = src.get(idx) {
-                // self is a superset of src, and both are sorted, so scan to find offset
-                loop {
-                    if let Some(lv) = self.0.get_mut(didx) {
-                        match lv.offset.cmp(offset) {
-                            Ordering::Less => {
-                                // offset is in src, but not dst, so skip it
-                                didx += 1;
-                                continue;
-                            }
-                            Ordering::Equal => {
-                                lv.value = Value::from(v);
-                                didx += 1;
-                                break;
-                            }
-                            Ordering::Greater => {
-                                // offset is in values, but not offsets, so ignore it
-                                break;
-                            }
-                        }
-                    }
-                    return;
-                }
-            }
-        }
-    }
-
-    // v1 values are a contiguous list.  If a list shorter than offsets
-    // is presented to the kernel then the missing lines default to zero.
-    // Build the complete values list here with any missing values being zero filled.
-    ///
-    /// Both sets of offsets must be sorted.
-    #[cfg(feature = "uapi_v1")]
-    pub(crate) fn to_v1(&self, offsets: &[Offset]) -> v1::LineValues {
-        let mut dst: v1::LineValues = Default::default();
-        let mut didx = 0;
-        for lv in self.0.iter() {
-            while didx < offsets.len() {
-                match lv.offset.cmp(&offsets[didx]) {
-                    Ordering::Less => {
-                        // offset is in values, but not offsets, so ignore it
-                        break;
-                    }
-                    Ordering::Equal => {
-                        dst.set(didx, lv.value.into());
-                        didx += 1;
-                        break;
-                    }
-                    Ordering::Greater => {
-                        // offset is in offsets, but not values, so it defaults
-                        didx += 1;
-                        continue;
-                    }
-                }
-            }
-        }
-        dst
-    }
-
-    // v2 values are a bitmap in offsets ordering
-    ///
-    /// Both sets of offsets must be sorted.
-    #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
-    pub(crate) fn to_v2(&self, offsets: &[Offset]) -> v2::LineValues {
-        let mut dst: v2::LineValues = Default::default();
-        if self.0.is_empty() {
-            if offsets.len() < 64 {
-                dst.mask = (0x01_u64 << offsets.len()) - 1;
-            } else {
-                dst.mask = !0_u64;
-            }
-            return dst;
-        }
-        let mut didx = 0;
-        for lv in self.0.iter() {
-            while didx < offsets.len() {
-                match lv.offset.cmp(&offsets[didx]) {
-                    Ordering::Less => {
-                        // offset is in values, but not offsets, so ignore it
-                        break;
-                    }
-                    Ordering::Equal => {
-                        dst.set(didx, lv.value.into());
-                        didx += 1;
-                        break;
-                    }
-                    Ordering::Greater => {
-                        // offset is in offsets, but not values, so it defaults
-                        didx += 1;
-                        continue;
-                    }
-                }
-            }
-        }
-        dst
-    }
-
     /// Get the value of a line.
     #[inline]
     pub fn get(&self, offset: Offset) -> Option<Value> {
@@ -351,6 +198,21 @@ impl Values {
         offsets.iter().collect()
     }
 
+    /// Construct a `Values` collection from a set of line offsets and a bitmap of their values.
+    ///
+    /// Bit n of `bits` sets the value of the nth offset, with set bits being active.
+    /// Any offsets beyond the 64th are set inactive.
+    pub fn from_offsets_and_bits(offsets: &[Offset], bits: u64) -> Values {
+        offsets
+            .iter()
+            .enumerate()
+            .map(|(idx, offset)| {
+                let active = idx < 64 && (bits >> idx) & 1 == 1;
+                (*offset, Value::from(active))
+            })
+            .collect()
+    }
+
     /// An iterator to visit all values.
     pub fn iter(&self) -> std::slice::Iter<'_, LineValue> {
         self.0.iter()
@@ -360,27 +222,6 @@ impl Values {
     pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, LineValue> {
         self.0.iter_mut()
     }
-
-    /// Returns true if values are defined for all offsets.
-    ///
-    /// Offsets must be sorted.
-    #[cfg(feature = "uapi_v1")]
-    pub(crate) fn contains_keys(&self, offsets: &[Offset]) -> bool {
-        let mut start_idx = 0;
-        for offset in offsets.iter() {
-            match self.0[start_idx..self.0.len()]
-                .iter()
-                .position(|lv| &lv.offset == offset)
-            {
-                Some(idx) => {
-                    start_idx += idx + 1;
-                    continue;
-                }
-                None => return false,
-            }
-        }
-        true
-    }
 }
 impl<'a> FromIterator<&'a Offset> for Values {
     fn from_iter<I: IntoIterator<Item = &'a Offset>>(iter: I) -> Self {
@@ -395,13 +236,163 @@ impl<'a> FromIterator<&'a Offset> for Values {
 impl FromIterator<(Offset, Value)> for Values {
     fn from_iter<I: IntoIterator<Item = (Offset, Value)>>(iter: I) -> Self {
         let mut values = Values::default();
+        values.extend(iter);
+        values
+    }
+}
+impl FromIterator<LineValue> for Values {
+    fn from_iter<I: IntoIterator<Item = LineValue>>(iter: I) -> Self {
+        let mut values = Values::default();
+        values.extend(iter);
+        values
+    }
+}
+impl<const N: usize> From<[(Offset, Value); N]> for Values {
+    fn from(values: [(Offset, Value); N]) -> Self {
+        values.into_iter().collect()
+    }
+}
+impl Extend<(Offset, Value)> for Values {
+    fn extend<I: IntoIterator<Item = (Offset, Value)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.0.reserve(iter.size_hint().0);
         // not quite as fast as append and sort, but also performs de-duping.
         for (offset, value) in iter {
-            values.set(offset, value);
+            self.set(offset, value);
         }
+    }
+}
+impl Extend<LineValue> for Values {
+    fn extend<I: IntoIterator<Item = LineValue>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|lv| (lv.offset, lv.value)));
+    }
+}
+impl Values {
+    // Combine two sets of values, line by line.
+    //
+    // The result contains the union of the lines, with lines missing from one
+    // set treated as inactive in that set.
+    fn combine(&self, other: &Values, op: fn(bool, bool) -> bool) -> Values {
+        let mut dst = Vec::with_capacity(self.0.len().max(other.0.len()));
+        let (mut sidx, mut oidx) = (0, 0);
+        loop {
+            let (offset, s, o) = match (self.0.get(sidx), other.0.get(oidx)) {
+                (Some(s), Some(o)) => match s.offset.cmp(&o.offset) {
+                    Ordering::Less => {
+                        sidx += 1;
+                        (s.offset, s.value.active(), false)
+                    }
+                    Ordering::Equal => {
+                        sidx += 1;
+                        oidx += 1;
+                        (s.offset, s.value.active(), o.value.active())
+                    }
+                    Ordering::Greater => {
+                        oidx += 1;
+                        (o.offset, false, o.value.active())
+                    }
+                },
+                (Some(s), None) => {
+                    sidx += 1;
+                    (s.offset, s.value.active(), false)
+                }
+                (None, Some(o)) => {
+                    oidx += 1;
+                    (o.offset, false, o.value.active())
+                }
+                (None, None) => break,
+            };
+            dst.push(LineValue {
+                offset,
+                value: op(s, o).into(),
+            });
+        }
+        Values(dst)
+    }
+
+    // The digits of the values, in the given radix, most significant first.
+    //
+    // The lowest offset is the least significant bit.
+    fn digits(&self, bits_per_digit: usize) -> String {
+        let len = self.0.len();
+        let num_digits = (len + bits_per_digit - 1) / bits_per_digit;
+        (0..num_digits)
+            .rev()
+            .map(|d| {
+                let mut digit = 0;
+                for bit in 0..bits_per_digit {
+                    if let Some(lv) = self.0.get(d * bits_per_digit + bit) {
+                        digit |= u32::from(u8::from(lv.value)) << bit;
+                    }
+                }
+                std::char::from_digit(digit, 1 << bits_per_digit).unwrap()
+            })
+            .collect()
+    }
+}
+impl std::ops::Not for &Values {
+    type Output = Values;
+
+    fn not(self) -> Values {
+        let mut values = self.clone();
+        Values::not(&mut values);
         values
     }
 }
+impl std::ops::Not for Values {
+    type Output = Values;
+
+    fn not(mut self) -> Values {
+        Values::not(&mut self);
+        self
+    }
+}
+
+macro_rules! impl_values_bitop {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl std::ops::$trait for &Values {
+            type Output = Values;
+
+            fn $method(self, rhs: &Values) -> Values {
+                self.combine(rhs, |a, b| a $op b)
+            }
+        }
+        impl std::ops::$trait for Values {
+            type Output = Values;
+
+            fn $method(self, rhs: Values) -> Values {
+                self.combine(&rhs, |a, b| a $op b)
+            }
+        }
+    };
+}
+impl_values_bitop!(BitAnd, bitand, &);
+impl_values_bitop!(BitOr, bitor, |);
+impl_values_bitop!(BitXor, bitxor, ^);
+
+/// Renders the values as a binary word.
+///
+/// The values are rendered in offset order, with the lowest offset as the
+/// least significant, rightmost, bit.
+/// Offsets not contained in the values are skipped, not rendered as zero.
+///
+/// The alternate form adds a `0b` prefix.
+impl std::fmt::Display for Values {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(true, "0b", &self.digits(1))
+    }
+}
+
+/// Renders the values as a hexadecimal word.
+///
+/// As per the `Display` rendering, but with four lines per digit.
+///
+/// The alternate form adds a `0x` prefix.
+impl std::fmt::LowerHex for Values {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(true, "0x", &self.digits(4))
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -451,126 +442,6 @@ mod tests {
     mod values {
         use super::*;
 
-        #[test]
-        #[cfg(feature = "uapi_v1")]
-        fn from_v1() {
-            // offset must be sorted
-            let offsets = Vec::from([1, 3, 5, 8]);
-            let src = v1::LineValues::from_slice(&[1, 0, 1, 1]);
-            // full set
-            let mut dst = Values::default();
-            dst.update_from_v1(&offsets, &src);
-            assert_eq!(dst.get(1), Some(Value::Active));
-            assert_eq!(dst.get(2), None);
-            assert_eq!(dst.get(3), Some(Value::Inactive));
-            assert_eq!(dst.get(4), None);
-            assert_eq!(dst.get(5), Some(Value::Active));
-            assert_eq!(dst.get(6), None);
-            assert_eq!(dst.get(7), None);
-            assert_eq!(dst.get(8), Some(Value::Active));
-
-            // explicit set
-            let mut dst = Values::default();
-            dst.set(1, Value::Inactive);
-            dst.set(3, Value::Active);
-            dst.set(4, Value::Active);
-            dst.set(7, Value::Inactive);
-            dst.update_from_v1(&offsets, &src);
-            assert_eq!(dst.get(1), Some(Value::Active));
-            assert_eq!(dst.get(2), None);
-            assert_eq!(dst.get(3), Some(Value::Inactive));
-            assert_eq!(dst.get(4), Some(Value::Active));
-            assert_eq!(dst.get(5), None);
-            assert_eq!(dst.get(6), None);
-            assert_eq!(dst.get(7), Some(Value::Inactive));
-            assert_eq!(dst.get(8), None);
-        }
-
-        #[test]
-        #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
-        fn from_v2() {
-            // offsets must be sorted
-            let offsets = Vec::from([1, 3, 5, 8]);
-            let mut src = v2::LineValues::default();
-            src.set(0, true);
-            src.set(1, false);
-            src.set(2, true);
-            src.set(3, true);
-            let mut dst = Values::default();
-            dst.set(3, Value::Active);
-            dst.set(4, Value::Active);
-            dst.set(5, Value::Inactive);
-            dst.set(7, Value::Inactive);
-            dst.update_from_v2(&offsets, &src);
-            assert_eq!(dst.get(1), None);
-            assert_eq!(dst.get(2), None);
-            assert_eq!(dst.get(3), Some(Value::Inactive));
-            assert_eq!(dst.get(4), Some(Value::Active));
-            assert_eq!(dst.get(5), Some(Value::Active));
-            assert_eq!(dst.get(6), None);
-            assert_eq!(dst.get(7), Some(Value::Inactive));
-            assert_eq!(dst.get(8), None);
-        }
-
-        #[test]
-        #[cfg(feature = "uapi_v1")]
-        fn to_v1() {
-            // offsets must be sorted.
-            let offsets = Vec::from([1, 3, 5, 8]);
-            // both empty
-            let mut src = Values::default();
-            let dst = src.to_v1(&[]);
-            assert_eq!(dst.get(0), 0); // 1
-            assert_eq!(dst.get(1), 0); // 5
-            assert_eq!(dst.get(2), 0); // 3
-            assert_eq!(dst.get(3), 0); // 8
-
-            let dst = src.to_v1(&offsets);
-            assert_eq!(dst.get(0), 0); // 1
-            assert_eq!(dst.get(1), 0); // 5
-            assert_eq!(dst.get(2), 0); // 3
-            assert_eq!(dst.get(3), 0); // 8
-
-            // values intersects offsets
-            src.set(1, Value::Active);
-            src.set(3, Value::Inactive);
-            src.set(7, Value::Active); // should be ignored
-            src.set(8, Value::Active);
-            let dst = src.to_v1(&offsets);
-            assert_eq!(dst.get(0), 1); // 1
-            assert_eq!(dst.get(1), 0); // 5
-            assert_eq!(dst.get(2), 0); // 3
-            assert_eq!(dst.get(3), 1); // 8
-        }
-
-        #[test]
-        #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
-        fn to_v2() {
-            // offsets must be sorted.
-            let offsets = Vec::from([1, 3, 5, 8]);
-            // both empty
-            let mut src = Values::default();
-            let dst = src.to_v2(&[]);
-            assert_eq!(dst.bits, 0);
-            assert_eq!(dst.mask, 0);
-            // empty values
-            let dst = src.to_v2(&offsets);
-            assert_eq!(dst.bits, 0);
-            assert_eq!(dst.mask, 0b01111);
-
-            // values intersects offsets
-            src.set(1, Value::Active);
-            src.set(3, Value::Inactive);
-            src.set(7, Value::Active); // should be ignored
-            src.set(8, Value::Active);
-            let dst = src.to_v2(&offsets);
-            assert!(dst.get(0).unwrap()); // 1
-            assert!(!dst.get(1).unwrap()); // 3
-            assert!(dst.get(2).is_none()); // 5
-            assert!(dst.get(3).unwrap()); // 8
-            assert_eq!(dst.mask, 0b1011); // only 3 entries set
-        }
-
         #[test]
         fn from_offsets() {
             let values = Values::from_offsets(&[1, 6, 3, 9]);
@@ -689,6 +560,107 @@ mod tests {
             assert_eq!(vv.get(3), Some(Value::Active));
         }
 
+        #[test]
+        fn from_line_value_struct_iterator() {
+            let vv: Values = [
+                LineValue {
+                    offset: 3,
+                    value: Value::Active,
+                },
+                LineValue {
+                    offset: 1,
+                    value: Value::Inactive,
+                },
+            ]
+            .into_iter()
+            .collect();
+            assert_eq!(vv.get(1), Some(Value::Inactive));
+            assert_eq!(vv.get(3), Some(Value::Active));
+            assert_eq!(vv.len(), 2);
+        }
+
+        #[test]
+        fn from_array() {
+            let vv = Values::from([
+                (3, Value::Active),
+                (1, Value::Inactive),
+                (3, Value::Inactive),
+            ]);
+            assert_eq!(vv.get(1), Some(Value::Inactive));
+            assert_eq!(vv.get(3), Some(Value::Inactive));
+            assert_eq!(vv.len(), 2);
+        }
+
+        #[test]
+        fn from_offsets_and_bits() {
+            let vv = Values::from_offsets_and_bits(&[4, 2, 7], 0b101);
+            assert_eq!(vv.get(2), Some(Value::Inactive));
+            assert_eq!(vv.get(4), Some(Value::Active));
+            assert_eq!(vv.get(7), Some(Value::Active));
+            assert_eq!(vv.len(), 3);
+
+            let offsets: Vec<Offset> = (0..70).collect();
+            let vv = Values::from_offsets_and_bits(&offsets, !0);
+            assert_eq!(vv.get(63), Some(Value::Active));
+            assert_eq!(vv.get(64), Some(Value::Inactive));
+            assert_eq!(vv.len(), 70);
+        }
+
+        #[test]
+        fn extend() {
+            let mut vv = Values::from_offsets(&[1, 2]);
+            vv.extend([(2, Value::Active), (5, Value::Active)]);
+            vv.extend([LineValue {
+                offset: 0,
+                value: Value::Active,
+            }]);
+            assert_eq!(vv.get(0), Some(Value::Active));
+            assert_eq!(vv.get(1), Some(Value::Inactive));
+            assert_eq!(vv.get(2), Some(Value::Active));
+            assert_eq!(vv.get(5), Some(Value::Active));
+            assert_eq!(vv.len(), 4);
+        }
+
+        #[test]
+        fn bitops() {
+            let a = Values::from_offsets_and_bits(&[1, 2, 3], 0b011);
+            let b = Values::from_offsets_and_bits(&[2, 3, 4], 0b011);
+            // a: 1 => 1, 2 => 1, 3 => 0
+            // b: 2 => 1, 3 => 1, 4 => 0
+            assert_eq!(
+                &a & &b,
+                Values::from_offsets_and_bits(&[1, 2, 3, 4], 0b0010)
+            );
+            assert_eq!(
+                &a | &b,
+                Values::from_offsets_and_bits(&[1, 2, 3, 4], 0b0111)
+            );
+            assert_eq!(
+                &a ^ &b,
+                Values::from_offsets_and_bits(&[1, 2, 3, 4], 0b0101)
+            );
+            assert_eq!(!&a, Values::from_offsets_and_bits(&[1, 2, 3], 0b100));
+            assert_eq!(!a.clone(), Values::from_offsets_and_bits(&[1, 2, 3], 0b100));
+            assert_eq!(a.clone() & a.clone(), a);
+            assert_eq!(a.clone() | Values::default(), a);
+            assert_eq!(a.clone() ^ a, Values::from_offsets(&[1, 2, 3]));
+        }
+
+        #[test]
+        fn display() {
+            let vv = Values::from_offsets_and_bits(&[1, 2, 5, 7, 9], 0b10110);
+            assert_eq!(format!("{}", vv), "10110");
+            assert_eq!(format!("{:#}", vv), "0b10110");
+            assert_eq!(format!("{:08}", vv), "00010110");
+            assert_eq!(format!("{:x}", vv), "16");
+            assert_eq!(format!("{:#x}", vv), "0x16");
+            assert_eq!(format!("{:#06x}", vv), "0x0016");
+            assert_eq!(format!("{}", Values::default()), "");
+            let offsets: Vec<Offset> = (0..68).collect();
+            let vv = Values::from_offsets_and_bits(&offsets, 0xdead_beef_0000_0001);
+            assert_eq!(format!("{:x}", vv), "0deadbeef00000001");
+        }
+
         #[test]
         fn len() {
             assert_eq!(Values::default().len(), 0);
@@ -770,19 +742,5 @@ mod tests {
                 [Value::Active, Value::Inactive, Value::Active]
             )
         }
-
-        #[test]
-        #[cfg(feature = "uapi_v1")]
-        fn contains_keys() {
-            let vv = Values::from_offsets(&[1, 2, 3]);
-            assert!(!vv.contains_keys(&[0]));
-            assert!(vv.contains_keys(&[1]));
-            assert!(vv.contains_keys(&[2]));
-            assert!(vv.contains_keys(&[3]));
-            assert!(!vv.contains_keys(&[4]));
-            assert!(vv.contains_keys(&[1, 2, 3]));
-            assert!(vv.contains_keys(&[1, 3]));
-            assert!(vv.contains_keys(&[2, 3]));
-        }
     }
 }
@@ -0,0 +1,620 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! A read-only mirror of request state in shared memory.
+//!
+//! A [`Publisher`] mirrors the current values of a set of lines, and a ring
+//! of the most recent edge events, into a memory mapped file.
+//! Any number of [`Observer`]s, in the same or other processes, may map the
+//! same file to observe the line state without making any syscalls and
+//! without owning the request.
+//!
+//! The file is typically placed in `/dev/shm`.
+//!
+//! The publisher is responsible for keeping the mirror current by calling
+//! [`Publisher::publish_values`] and [`Publisher::publish_event`] as it reads
+//! values and events from the request.
+//!
+//! # Layout
+//!
+//! All fields are native endian and naturally aligned.
+//!
+//! The segment starts with a 64 byte header:
+//!
+//! | Offset | Size | Field |
+//! |-------:|-----:|-------|
+//! | 0 | 4 | magic - `0x4d444347` ("GCDM") |
+//! | 4 | 4 | layout version - currently 1 |
+//! | 8 | 4 | sequence lock |
+//! | 12 | 4 | number of lines |
+//! | 16 | 4 | event ring capacity |
+//! | 20 | 4 | reserved |
+//! | 24 | 8 | total number of events published |
+//! | 32 | 32 | reserved |
+//!
+//! The header is followed by one 8 byte entry per line, sorted by offset:
+//!
+//! | Offset | Size | Field |
+//! |-------:|-----:|-------|
+//! | 0 | 4 | line offset |
+//! | 4 | 4 | value - 0 inactive, 1 active, `0xffffffff` unknown |
+//!
+//! The line entries are followed by the event ring, with one 32 byte entry per event:
+//!
+//! | Offset | Size | Field |
+//! |-------:|-----:|-------|
+//! | 0 | 8 | timestamp_ns |
+//! | 8 | 4 | line offset |
+//! | 12 | 4 | kind - 1 rising, 2 falling |
+//! | 16 | 4 | seqno |
+//! | 20 | 4 | line_seqno |
+//! | 24 | 8 | reserved |
+//!
+//! Event number `n` is stored in ring entry `n % capacity`.
+//!
+//! The sequence lock is odd while the publisher is updating the lines or events.
+//! Readers should read the sequence lock, then the fields, then the sequence lock
+//! again, and retry if the lock was odd or changed.
+//!
+//! The magic is written last when the segment is created, so readers should
+//! treat a segment with a zero magic as not yet initialised.
+
+use crate::line::{EdgeEvent, EdgeKind, Offset, Value, Values};
+use crate::{Error, Request, Result};
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// The magic number identifying a mirror segment.
+pub const MAGIC: u32 = 0x4d44_4347;
+
+/// The version of the segment layout.
+pub const LAYOUT_VERSION: u32 = 1;
+
+/// The value of a line that has not yet been published.
+pub const VALUE_UNKNOWN: u32 = 0xffff_ffff;
+
+const HEADER_SIZE: usize = 64;
+const LINE_SIZE: usize = 8;
+const EVENT_SIZE: usize = 32;
+
+const MAGIC_POS: usize = 0;
+const VERSION_POS: usize = 4;
+const SEQ_POS: usize = 8;
+const NUM_LINES_POS: usize = 12;
+const EVENT_CAPACITY_POS: usize = 16;
+const EVENT_COUNT_POS: usize = 24;
+
+const KIND_RISING: u32 = 1;
+const KIND_FALLING: u32 = 2;
+
+fn segment_size(num_lines: usize, event_capacity: usize) -> usize {
+    HEADER_SIZE + num_lines * LINE_SIZE + event_capacity * EVENT_SIZE
+}
+
+/// Publishes line values and edge events into a shared memory mirror.
+///
+/// There should be only one publisher for a given segment.
+/// The publisher may be shared between threads, as concurrent updates are serialized.
+pub struct Publisher {
+    map: Mapping,
+    offsets: Vec<Offset>,
+    event_capacity: usize,
+    // Serializes writers, as the sequence lock only supports a single writer.
+    writer: Mutex<()>,
+}
+
+impl Publisher {
+    /// Create a mirror segment at the given path for the given lines.
+    ///
+    /// Any existing file at the path is replaced.
+    /// The line values are initially unknown.
+    ///
+    /// * `offsets` - the lines to be mirrored.
+    /// * `event_capacity` - the number of recent events retained in the event ring.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        offsets: &[Offset],
+        event_capacity: usize,
+    ) -> Result<Publisher> {
+        let mut offsets = offsets.to_vec();
+        offsets.sort_unstable();
+        offsets.dedup();
+        if offsets.len() > u32::MAX as usize || event_capacity > u32::MAX as usize {
+            return Err(Error::InvalidArgument("mirror is too large.".to_string()));
+        }
+        let f = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let size = segment_size(offsets.len(), event_capacity);
+        f.set_len(size as u64)?;
+        let map = Mapping::new(&f, size, true)?;
+        map.u32_at(VERSION_POS)
+            .store(LAYOUT_VERSION, Ordering::Relaxed);
+        map.u32_at(NUM_LINES_POS)
+            .store(offsets.len() as u32, Ordering::Relaxed);
+        map.u32_at(EVENT_CAPACITY_POS)
+            .store(event_capacity as u32, Ordering::Relaxed);
+        for (idx, offset) in offsets.iter().enumerate() {
+            let pos = HEADER_SIZE + idx * LINE_SIZE;
+            map.u32_at(pos).store(*offset, Ordering::Relaxed);
+            map.u32_at(pos + 4).store(VALUE_UNKNOWN, Ordering::Relaxed);
+        }
+        map.u32_at(MAGIC_POS).store(MAGIC, Ordering::Release);
+        Ok(Publisher {
+            map,
+            offsets,
+            event_capacity,
+            writer: Mutex::default(),
+        })
+    }
+
+    /// Create a mirror segment at the given path for the lines in the request,
+    /// and publish their current values.
+    ///
+    /// * `event_capacity` - the number of recent events retained in the event ring.
+    pub fn for_request<P: AsRef<Path>>(
+        path: P,
+        req: &Request,
+        event_capacity: usize,
+    ) -> Result<Publisher> {
+        let p = Publisher::create(path, req.config().lines(), event_capacity)?;
+        let mut values = Values::default();
+        req.values(&mut values)?;
+        p.publish_values(&values);
+        Ok(p)
+    }
+
+    /// The offsets of the lines being mirrored.
+    pub fn offsets(&self) -> &[Offset] {
+        &self.offsets
+    }
+
+    /// Publish the values of lines.
+    ///
+    /// Values for lines not in the mirror are ignored.
+    pub fn publish_values(&self, values: &Values) {
+        let _writer = self
+            .writer
+            .lock()
+            .expect("failed to acquire lock on mirror");
+        let seq = self.begin_write();
+        for lv in values.iter() {
+            self.store_value(lv.offset, lv.value);
+        }
+        self.end_write(seq);
+    }
+
+    /// Publish an edge event.
+    ///
+    /// The event is added to the event ring, and the value of the line is
+    /// updated to reflect the edge.
+    pub fn publish_event(&self, evt: &EdgeEvent) {
+        let _writer = self
+            .writer
+            .lock()
+            .expect("failed to acquire lock on mirror");
+        let seq = self.begin_write();
+        let value = match evt.kind {
+            EdgeKind::Rising => Value::Active,
+            EdgeKind::Falling => Value::Inactive,
+        };
+        self.store_value(evt.offset, value);
+        let count = self.map.u64_at(EVENT_COUNT_POS).load(Ordering::Relaxed);
+        if self.event_capacity > 0 {
+            let pos = self.event_pos(count);
+            let kind = match evt.kind {
+                EdgeKind::Rising => KIND_RISING,
+                EdgeKind::Falling => KIND_FALLING,
+            };
+            self.map
+                .u64_at(pos)
+                .store(evt.timestamp_ns, Ordering::Relaxed);
+            self.map
+                .u32_at(pos + 8)
+                .store(evt.offset, Ordering::Relaxed);
+            self.map.u32_at(pos + 12).store(kind, Ordering::Relaxed);
+            self.map
+                .u32_at(pos + 16)
+                .store(evt.seqno, Ordering::Relaxed);
+            self.map
+                .u32_at(pos + 20)
+                .store(evt.line_seqno, Ordering::Relaxed);
+        }
+        self.map
+            .u64_at(EVENT_COUNT_POS)
+            .store(count + 1, Ordering::Relaxed);
+        self.end_write(seq);
+    }
+
+    fn store_value(&self, offset: Offset, value: Value) {
+        if let Ok(idx) = self.offsets.binary_search(&offset) {
+            let v = match value {
+                Value::Inactive => 0,
+                Value::Active => 1,
+            };
+            self.map
+                .u32_at(HEADER_SIZE + idx * LINE_SIZE + 4)
+                .store(v, Ordering::Relaxed);
+        }
+    }
+
+    fn event_pos(&self, n: u64) -> usize {
+        HEADER_SIZE
+            + self.offsets.len() * LINE_SIZE
+            + (n % self.event_capacity as u64) as usize * EVENT_SIZE
+    }
+
+    // Must be called with the writer lock held.
+    fn begin_write(&self) -> u32 {
+        let seq = self.map.u32_at(SEQ_POS);
+        let s = seq.load(Ordering::Relaxed);
+        seq.store(s.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        s
+    }
+
+    fn end_write(&self, s: u32) {
+        self.map
+            .u32_at(SEQ_POS)
+            .store(s.wrapping_add(2), Ordering::Release);
+    }
+}
+
+/// Observes the line values and edge events published to a shared memory mirror.
+pub struct Observer {
+    map: Mapping,
+    offsets: Vec<Offset>,
+    event_capacity: usize,
+}
+
+impl Observer {
+    /// Open the mirror segment at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Observer> {
+        let f = File::open(path)?;
+        let len = f.metadata()?.len() as usize;
+        if len < HEADER_SIZE {
+            return Err(Error::InvalidArgument(
+                "mirror segment is truncated.".to_string(),
+            ));
+        }
+        let map = Mapping::new(&f, len, false)?;
+        if map.u32_at(MAGIC_POS).load(Ordering::Acquire) != MAGIC {
+            return Err(Error::InvalidArgument(
+                "file is not a mirror segment.".to_string(),
+            ));
+        }
+        let version = map.u32_at(VERSION_POS).load(Ordering::Relaxed);
+        if version != LAYOUT_VERSION {
+            return Err(Error::InvalidArgument(format!(
+                "unsupported mirror layout version {}.",
+                version
+            )));
+        }
+        let num_lines = map.u32_at(NUM_LINES_POS).load(Ordering::Relaxed) as usize;
+        let event_capacity = map.u32_at(EVENT_CAPACITY_POS).load(Ordering::Relaxed) as usize;
+        if len < segment_size(num_lines, event_capacity) {
+            return Err(Error::InvalidArgument(
+                "mirror segment is truncated.".to_string(),
+            ));
+        }
+        let offsets = (0..num_lines)
+            .map(|idx| {
+                map.u32_at(HEADER_SIZE + idx * LINE_SIZE)
+                    .load(Ordering::Relaxed)
+            })
+            .collect();
+        Ok(Observer {
+            map,
+            offsets,
+            event_capacity,
+        })
+    }
+
+    /// The offsets of the lines being mirrored.
+    pub fn offsets(&self) -> &[Offset] {
+        &self.offsets
+    }
+
+    /// The number of events that the event ring can retain.
+    pub fn event_capacity(&self) -> usize {
+        self.event_capacity
+    }
+
+    /// A consistent snapshot of the line values.
+    ///
+    /// Lines that have not yet been published are not included.
+    pub fn values(&self) -> Values {
+        loop {
+            let s = self.begin_read();
+            let values: Values = self
+                .offsets
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, offset)| {
+                    match self
+                        .map
+                        .u32_at(HEADER_SIZE + idx * LINE_SIZE + 4)
+                        .load(Ordering::Relaxed)
+                    {
+                        0 => Some((*offset, Value::Inactive)),
+                        1 => Some((*offset, Value::Active)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            if self.end_read(s) {
+                return values;
+            }
+        }
+    }
+
+    /// The value of a single line.
+    ///
+    /// Returns None if the line is not mirrored or its value has not yet been published.
+    pub fn value(&self, offset: Offset) -> Option<Value> {
+        let idx = self.offsets.binary_search(&offset).ok()?;
+        match self
+            .map
+            .u32_at(HEADER_SIZE + idx * LINE_SIZE + 4)
+            .load(Ordering::Acquire)
+        {
+            0 => Some(Value::Inactive),
+            1 => Some(Value::Active),
+            _ => None,
+        }
+    }
+
+    /// The total number of events published to the mirror.
+    pub fn event_count(&self) -> u64 {
+        self.map.u64_at(EVENT_COUNT_POS).load(Ordering::Acquire)
+    }
+
+    /// The events published since the given event count.
+    ///
+    /// Returns the events still retained in the ring, oldest first, and the
+    /// event count to pass to the next call.
+    /// Events that have been overwritten in the ring are skipped.
+    pub fn events_since(&self, count: u64) -> (Vec<EdgeEvent>, u64) {
+        loop {
+            let s = self.begin_read();
+            let end = self.map.u64_at(EVENT_COUNT_POS).load(Ordering::Relaxed);
+            let start = count.max(end.saturating_sub(self.event_capacity as u64));
+            let mut events = Vec::new();
+            for n in start..end {
+                events.push(self.event_at(n));
+            }
+            if self.end_read(s) {
+                return (events, end);
+            }
+        }
+    }
+
+    fn event_at(&self, n: u64) -> EdgeEvent {
+        let pos = HEADER_SIZE
+            + self.offsets.len() * LINE_SIZE
+            + (n % self.event_capacity as u64) as usize * EVENT_SIZE;
+        let kind = if self.map.u32_at(pos + 12).load(Ordering::Relaxed) == KIND_RISING {
+            EdgeKind::Rising
+        } else {
+            EdgeKind::Falling
+        };
+        EdgeEvent {
+            timestamp_ns: self.map.u64_at(pos).load(Ordering::Relaxed),
+            kind,
+            offset: self.map.u32_at(pos + 8).load(Ordering::Relaxed),
+            seqno: self.map.u32_at(pos + 16).load(Ordering::Relaxed),
+            line_seqno: self.map.u32_at(pos + 20).load(Ordering::Relaxed),
+        }
+    }
+
+    fn begin_read(&self) -> u32 {
+        let seq = self.map.u32_at(SEQ_POS);
+        loop {
+            let s = seq.load(Ordering::Acquire);
+            if s & 1 == 0 {
+                return s;
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    fn end_read(&self, s: u32) -> bool {
+        fence(Ordering::Acquire);
+        self.map.u32_at(SEQ_POS).load(Ordering::Relaxed) == s
+    }
+}
+
+// A shared memory mapping of a mirror segment.
+struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// The mapping is only accessed via atomics.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn new(f: &File, len: usize, writable: bool) -> Result<Mapping> {
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        // SAFETY: mapping a file we have open, with the length checked against the file.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                prot,
+                libc::MAP_SHARED,
+                f.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Mapping {
+            ptr: ptr as *mut u8,
+            len,
+        })
+    }
+
+    fn u32_at(&self, pos: usize) -> &AtomicU32 {
+        assert!(pos % 4 == 0 && pos + 4 <= self.len);
+        // SAFETY: in bounds and aligned, as the mapping is page aligned.
+        unsafe { &*(self.ptr.add(pos) as *const AtomicU32) }
+    }
+
+    fn u64_at(&self, pos: usize) -> &AtomicU64 {
+        assert!(pos % 8 == 0 && pos + 8 <= self.len);
+        // SAFETY: in bounds and aligned, as the mapping is page aligned.
+        unsafe { &*(self.ptr.add(pos) as *const AtomicU64) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: unmapping the region mapped in new.
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn segment_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gpiocdev-mirror-{}-{}", name, std::process::id()))
+    }
+
+    fn event(offset: Offset, kind: EdgeKind, seqno: u32) -> EdgeEvent {
+        EdgeEvent {
+            timestamp_ns: 1000 * seqno as u64,
+            kind,
+            offset,
+            seqno,
+            line_seqno: seqno,
+        }
+    }
+
+    #[test]
+    fn publish_values() {
+        let path = segment_path("values");
+        let p = Publisher::create(&path, &[5, 2, 7], 4).unwrap();
+        assert_eq!(p.offsets(), &[2, 5, 7]);
+        let o = Observer::open(&path).unwrap();
+        assert_eq!(o.offsets(), &[2, 5, 7]);
+        assert_eq!(o.event_capacity(), 4);
+        assert!(o.values().is_empty());
+        assert_eq!(o.value(2), None);
+
+        let values: Values = [(2, Value::Active), (7, Value::Inactive), (9, Value::Active)]
+            .into_iter()
+            .collect();
+        p.publish_values(&values);
+        let expected: Values = [(2, Value::Active), (7, Value::Inactive)]
+            .into_iter()
+            .collect();
+        assert_eq!(o.values(), expected);
+        assert_eq!(o.value(2), Some(Value::Active));
+        assert_eq!(o.value(5), None);
+        assert_eq!(o.value(9), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn publish_event() {
+        let path = segment_path("event");
+        let p = Publisher::create(&path, &[1, 3], 2).unwrap();
+        let o = Observer::open(&path).unwrap();
+        assert_eq!(o.event_count(), 0);
+        assert_eq!(o.events_since(0), (vec![], 0));
+
+        let e1 = event(1, EdgeKind::Rising, 1);
+        p.publish_event(&e1);
+        assert_eq!(o.value(1), Some(Value::Active));
+        assert_eq!(o.event_count(), 1);
+        assert_eq!(o.events_since(0), (vec![e1.clone()], 1));
+        assert_eq!(o.events_since(1), (vec![], 1));
+
+        let e2 = event(3, EdgeKind::Falling, 2);
+        let e3 = event(1, EdgeKind::Falling, 3);
+        p.publish_event(&e2);
+        p.publish_event(&e3);
+        assert_eq!(o.value(1), Some(Value::Inactive));
+        assert_eq!(o.value(3), Some(Value::Inactive));
+        // e1 has been overwritten
+        assert_eq!(o.events_since(0), (vec![e2.clone(), e3.clone()], 3));
+        assert_eq!(o.events_since(2), (vec![e3], 3));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn publish_event_without_ring() {
+        let path = segment_path("noring");
+        let p = Publisher::create(&path, &[1], 0).unwrap();
+        let o = Observer::open(&path).unwrap();
+        p.publish_event(&event(1, EdgeKind::Rising, 1));
+        assert_eq!(o.value(1), Some(Value::Active));
+        assert_eq!(o.event_count(), 1);
+        assert_eq!(o.events_since(0), (vec![], 1));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn publish_concurrently() {
+        let path = segment_path("concurrent");
+        let p = Publisher::create(&path, &[1, 2, 3, 4], 64).unwrap();
+        let o = Observer::open(&path).unwrap();
+        std::thread::scope(|s| {
+            for offset in 1..=4 {
+                let p = &p;
+                s.spawn(move || {
+                    for seqno in 0..16 {
+                        p.publish_event(&event(offset, EdgeKind::Rising, seqno));
+                    }
+                });
+            }
+        });
+        assert_eq!(o.event_count(), 64);
+        let (events, count) = o.events_since(0);
+        assert_eq!(count, 64);
+        for offset in 1..=4 {
+            let seqnos: Vec<u32> = events
+                .iter()
+                .filter(|e| e.offset == offset)
+                .map(|e| e.seqno)
+                .collect();
+            assert_eq!(seqnos, (0..16).collect::<Vec<u32>>());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_invalid() {
+        let path = segment_path("invalid");
+        std::fs::write(&path, [0_u8; 128]).unwrap();
+        assert_eq!(
+            Observer::open(&path).err().unwrap(),
+            Error::InvalidArgument("file is not a mirror segment.".to_string())
+        );
+        std::fs::write(&path, [0_u8; 16]).unwrap();
+        assert_eq!(
+            Observer::open(&path).err().unwrap(),
+            Error::InvalidArgument("mirror segment is truncated.".to_string())
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
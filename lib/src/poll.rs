@@ -0,0 +1,228 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Waiting on multiple requests and chips at once.
+//!
+//! [`wait_any`] waits until any of a set of requests has edge events, or any of a
+//! set of chips has info change events, available to read, so synchronous
+//! applications servicing several requests or chips can do so from a single thread
+//! without resorting to `poll(2)` directly.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use gpiocdev::{Request, Result};
+//! # use gpiocdev::line::EdgeDetection;
+//! # use gpiocdev::poll::wait_any;
+//! # use std::time::Duration;
+//! # fn main() -> Result<()> {
+//! let buttons = Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[17, 22])
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let encoder = Request::builder()
+//!     .on_chip("/dev/gpiochip1")
+//!     .with_lines(&[3, 4])
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let reqs = [&buttons, &encoder];
+//! loop {
+//!     for ready in wait_any(&reqs, Duration::from_secs(1))? {
+//!         let evt = reqs[ready.index].read_edge_event()?;
+//!         println!("{}: {:?}", ready.index, evt);
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! Requests and chips may be waited on together as `&dyn AsFd`:
+//!
+//! ```no_run
+//! # use gpiocdev::{Chip, Request, Result};
+//! # use gpiocdev::line::EdgeDetection;
+//! # use gpiocdev::poll::wait_any;
+//! # use std::os::unix::prelude::AsFd;
+//! # use std::time::Duration;
+//! # fn main() -> Result<()> {
+//! let req = Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_line(17)
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let chip = Chip::from_path("/dev/gpiochip0")?;
+//! chip.watch_line_info(22)?;
+//! let sources: [&dyn AsFd; 2] = [&req, &chip];
+//! for ready in wait_any(&sources, Duration::from_secs(1))? {
+//!     match ready.index {
+//!         0 => println!("edge: {:?}", req.read_edge_event()?),
+//!         _ => println!("info: {:?}", chip.read_line_info_change_event()?),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Result, UapiCall};
+use std::os::unix::prelude::{AsFd, AsRawFd};
+use std::time::Duration;
+
+/// A source reported by [`wait_any`] as requiring attention.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Ready {
+    /// The index of the source in the slice passed to [`wait_any`].
+    pub index: usize,
+
+    /// The source has events available to read.
+    pub readable: bool,
+
+    /// The source has been closed, or is in an error state, and will not provide
+    /// further events.
+    pub hangup: bool,
+}
+
+/// Wait for any of the sources to have events available to read.
+///
+/// The sources are typically [`Request`]s, which are readable when they have edge
+/// events available, or [`Chip`]s, which are readable when they have line info
+/// change events available.
+///
+/// Returns the sources requiring attention, in the order they were provided,
+/// or an empty list if none became ready within the timeout.
+///
+/// A timeout too large to be represented by `ppoll(2)`, such as `Duration::MAX`,
+/// waits indefinitely.
+///
+/// [`Request`]: crate::Request
+/// [`Chip`]: crate::Chip
+pub fn wait_any<F: AsFd>(sources: &[F], timeout: Duration) -> Result<Vec<Ready>> {
+    let mut pfds = pollfds(sources);
+    poll(&mut pfds, Some(timeout))?;
+    Ok(ready(&pfds))
+}
+
+// The pollfds to wait for the sources to be readable.
+pub(crate) fn pollfds<F: AsFd>(sources: &[F]) -> Vec<libc::pollfd> {
+    sources
+        .iter()
+        .map(|s| libc::pollfd {
+            fd: s.as_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect()
+}
+
+// Wait for any of the pollfds to have events pending, or for the timeout.
+//
+// A timeout of None, or one too large for a timespec, waits indefinitely.
+//
+// Returns the number of pollfds with events pending.
+pub(crate) fn poll(pfds: &mut [libc::pollfd], timeout: Option<Duration>) -> Result<usize> {
+    let timeout = timeout.and_then(|t| {
+        Some(libc::timespec {
+            tv_sec: libc::time_t::try_from(t.as_secs()).ok()?,
+            tv_nsec: t.subsec_nanos() as libc::c_long,
+        })
+    });
+    let timeout_ptr = match &timeout {
+        Some(ts) => ts as *const libc::timespec,
+        None => std::ptr::null(),
+    };
+    // SAFETY: pfds is a valid array of pollfds, of the length provided,
+    // and timeout_ptr is either null or points to a timespec that outlives the call.
+    let n = unsafe {
+        libc::ppoll(
+            pfds.as_mut_ptr(),
+            pfds.len() as libc::nfds_t,
+            timeout_ptr,
+            std::ptr::null(),
+        )
+    };
+    if n < 0 {
+        return Err(Error::Uapi(
+            UapiCall::WaitEvent,
+            gpiocdev_uapi::Error::from_errno(),
+        ));
+    }
+    Ok(n as usize)
+}
+
+// The sources with events pending.
+pub(crate) fn ready(pfds: &[libc::pollfd]) -> Vec<Ready> {
+    pfds.iter()
+        .enumerate()
+        .filter(|(_, pfd)| pfd.revents != 0)
+        .map(|(index, pfd)| Ready {
+            index,
+            readable: pfd.revents & libc::POLLIN != 0,
+            hangup: pfd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn ready() {
+        let pfd = |revents| libc::pollfd {
+            fd: 0,
+            events: libc::POLLIN,
+            revents,
+        };
+        let pfds = [
+            pfd(0),
+            pfd(libc::POLLIN),
+            pfd(libc::POLLHUP),
+            pfd(libc::POLLIN | libc::POLLHUP),
+        ];
+        assert_eq!(
+            super::ready(&pfds),
+            [
+                Ready {
+                    index: 1,
+                    readable: true,
+                    hangup: false
+                },
+                Ready {
+                    index: 2,
+                    readable: false,
+                    hangup: true
+                },
+                Ready {
+                    index: 3,
+                    readable: true,
+                    hangup: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn wait_any() {
+        let (mut tx1, rx1) = UnixStream::pair().unwrap();
+        let (_tx2, rx2) = UnixStream::pair().unwrap();
+        let sources = [&rx1, &rx2];
+        assert!(super::wait_any(&sources, Duration::ZERO)
+            .unwrap()
+            .is_empty());
+
+        tx1.write_all(b"x").unwrap();
+        let expected = [Ready {
+            index: 0,
+            readable: true,
+            hangup: false,
+        }];
+        assert_eq!(
+            super::wait_any(&sources, Duration::from_millis(10)).unwrap(),
+            expected
+        );
+        // no timeout
+        assert_eq!(super::wait_any(&sources, Duration::MAX).unwrap(), expected);
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Dropping privileges after requesting lines.
+//!
+//! Accessing GPIO chips commonly requires elevated privileges, but a service
+//! should not retain those privileges once its lines are requested.
+//!
+//! A [`Dropper`] performs setup, such as opening chips and requesting lines,
+//! while privileged and then switches to a target user and group.
+//! Requests made before the drop remain usable, as the file descriptors
+//! are unaffected by the change in credentials.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::{Request, Result};
+//! # use gpiocdev::line::Value;
+//! # use gpiocdev::privilege::Dropper;
+//! # fn main() -> Result<()> {
+//! let req = Dropper::new(1000, 1000).run(|| {
+//!     Request::builder()
+//!         .on_chip("/dev/gpiochip0")
+//!         .with_line(17)
+//!         .as_output(Value::Inactive)
+//!         .request()
+//! })?;
+//! // no longer root, but can still drive the line
+//! req.set_value(17, Value::Active)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Result};
+use std::fs::File;
+use std::os::unix::prelude::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// Switches the process to a target user and group.
+///
+/// The change applies to all threads in the process.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dropper {
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    groups: Vec<libc::gid_t>,
+    namespaces: Vec<PathBuf>,
+}
+
+impl Dropper {
+    /// Create a Dropper that switches to the given user and group.
+    ///
+    /// By default all supplementary groups are dropped.
+    pub fn new(uid: u32, gid: u32) -> Dropper {
+        Dropper {
+            uid,
+            gid,
+            groups: Vec::new(),
+            namespaces: Vec::new(),
+        }
+    }
+
+    /// Set the supplementary groups to retain after the drop.
+    pub fn with_groups(&mut self, groups: &[u32]) -> &mut Self {
+        self.groups = groups.to_vec();
+        self
+    }
+
+    /// Enter a namespace before dropping privileges.
+    ///
+    /// The path identifies the namespace, such as `/proc/<pid>/ns/net` or a bind
+    /// mount of one.  Entering a namespace typically requires `CAP_SYS_ADMIN`, so
+    /// is performed before the user and group are changed.
+    ///
+    /// May be called multiple times to enter several namespaces, which are entered
+    /// in the order added.
+    pub fn with_namespace<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.namespaces.push(path.into());
+        self
+    }
+
+    /// Perform setup while privileged, then drop privileges.
+    ///
+    /// If the setup fails then privileges are not dropped and the error is returned.
+    /// The caller should then exit rather than continue while privileged.
+    pub fn run<T, F>(&self, setup: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let res = setup()?;
+        self.drop_privileges()?;
+        Ok(res)
+    }
+
+    /// Drop privileges now.
+    ///
+    /// Namespaces are entered first, then the supplementary groups, group and
+    /// user are changed, in that order.
+    ///
+    /// Returns an error if any step fails, or if root privileges can be regained
+    /// after the drop.
+    pub fn drop_privileges(&self) -> Result<()> {
+        for ns in &self.namespaces {
+            enter_namespace(ns)?;
+        }
+        // SAFETY: groups is a valid slice for the length passed.
+        check(unsafe { libc::setgroups(self.groups.len(), self.groups.as_ptr()) })?;
+        // SAFETY: no pointers involved.
+        check(unsafe { libc::setgid(self.gid) })?;
+        // SAFETY: no pointers involved.
+        check(unsafe { libc::setuid(self.uid) })?;
+        if self.uid != 0 {
+            // SAFETY: no pointers involved.
+            if unsafe { libc::setuid(0) } == 0 {
+                return Err(Error::InvalidArgument(
+                    "root privileges were regained after drop.".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn enter_namespace(path: &Path) -> Result<()> {
+    let f = File::open(path)?;
+    // SAFETY: the fd is valid for the duration of the call.
+    check(unsafe { libc::setns(f.as_raw_fd(), 0) })
+}
+
+fn check(rc: libc::c_int) -> Result<()> {
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run the test in a forked child so the test process retains its privileges.
+    //
+    // Only applicable when running as root.
+    fn in_child(d: &Dropper, check: fn() -> bool) {
+        // SAFETY: no pointers involved.
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+        // SAFETY: the child only performs the drop and checks before exiting.
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            let ok = d.drop_privileges().is_ok() && check();
+            // SAFETY: exit the child without running the parent's cleanup.
+            unsafe { libc::_exit(if ok { 0 } else { 1 }) };
+        }
+        let mut status = 0;
+        // SAFETY: status is a valid pointer.
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+    }
+
+    #[test]
+    fn drop_privileges() {
+        let mut d = Dropper::new(65534, 65533);
+        d.with_groups(&[65532]);
+        in_child(&d, || {
+            let mut groups = [0; 4];
+            // SAFETY: groups is a valid buffer for the length passed.
+            let n = unsafe { libc::getgroups(groups.len() as i32, groups.as_mut_ptr()) };
+            // SAFETY: no pointers involved.
+            unsafe {
+                libc::getuid() == 65534
+                    && libc::geteuid() == 65534
+                    && libc::getgid() == 65533
+                    && libc::getegid() == 65533
+                    && n == 1
+                    && groups[0] == 65532
+                    && libc::setuid(0) != 0
+            }
+        });
+    }
+
+    #[test]
+    fn run() {
+        let d = Dropper::new(65534, 65534);
+        let res: Result<u32> = d.run(|| Err(Error::InvalidArgument("failed".to_string())));
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidArgument("failed".to_string())
+        );
+    }
+
+    #[test]
+    fn bad_namespace() {
+        let mut d = Dropper::new(65534, 65534);
+        d.with_namespace("/nonexistent/ns/net");
+        assert!(matches!(d.drop_privileges(), Err(Error::Os(_))));
+    }
+}
@@ -0,0 +1,344 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Reading edge events with bounded latency.
+//!
+//! A [`RealtimeReader`] spawns a thread that reads the edge events from a [`Request`]
+//! and passes them to a handler, with the thread configured to minimise the latency
+//! from edge to handler:
+//!  - scheduled with the `SCHED_FIFO` real-time policy at a given priority,
+//!  - optionally pinned to a set of CPUs,
+//!  - with its event buffer and stack pre-faulted, and optionally the process memory
+//!    locked, so reading events does not incur page faults.
+//!
+//! Each of these typically requires privileges, such as `CAP_SYS_NICE` and
+//! `CAP_IPC_LOCK`, or suitable `RLIMIT_RTPRIO` and `RLIMIT_MEMLOCK` limits.
+//! Without them the reader falls back to a normal thread, and reports which settings
+//! were [`Applied`], so the application can decide whether the resulting latency
+//! is acceptable.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::{Request, Result};
+//! # use gpiocdev::line::EdgeDetection;
+//! # use gpiocdev::realtime::RealtimeReader;
+//! # fn main() -> Result<()> {
+//! let req = Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[17, 18])
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let reader = RealtimeReader::new()
+//!     .with_priority(80)
+//!     .with_cpu(3)
+//!     .spawn(&req, |evt| {
+//!         // update the motion controller
+//!         println!("{:?}", evt);
+//!     })?;
+//! if !reader.applied().priority {
+//!     eprintln!("warning: not running with real-time priority");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::line::EdgeEvent;
+use crate::request::{EdgeEventBuffer, Stop, Worker};
+use crate::{Error, Request, Result};
+use std::sync::mpsc;
+
+// The size of the stack pre-faulted by the reader thread.
+const STACK_PREFAULT_SIZE: usize = 64 * 1024;
+
+/// The real-time settings successfully applied to a reader thread.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Applied {
+    /// The thread is scheduled with the `SCHED_FIFO` policy at the requested priority.
+    pub priority: bool,
+
+    /// The thread is restricted to the requested CPUs.
+    ///
+    /// True if no CPUs were requested.
+    pub affinity: bool,
+
+    /// The process memory is locked.
+    ///
+    /// True if memory locking was not requested.
+    pub memory_locked: bool,
+}
+
+/// A builder of real-time edge event reader threads.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RealtimeReader {
+    priority: i32,
+    cpus: Vec<usize>,
+    lock_memory: bool,
+    buffer_size: usize,
+}
+
+impl Default for RealtimeReader {
+    fn default() -> Self {
+        RealtimeReader::new()
+    }
+}
+
+impl RealtimeReader {
+    /// Create a builder for a reader thread.
+    ///
+    /// The defaults are a priority of 50, no CPU affinity, memory not locked, and a buffer
+    /// of 16 events.
+    pub fn new() -> RealtimeReader {
+        RealtimeReader {
+            priority: 50,
+            cpus: Vec::new(),
+            lock_memory: false,
+            buffer_size: 16,
+        }
+    }
+
+    /// Set the `SCHED_FIFO` priority of the reader thread.
+    ///
+    /// The priority must be in the range supported by the system, typically 1 to 99.
+    pub fn with_priority(&mut self, priority: i32) -> &mut Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Restrict the reader thread to a CPU.
+    ///
+    /// May be called multiple times to allow the thread to run on several CPUs.
+    pub fn with_cpu(&mut self, cpu: usize) -> &mut Self {
+        if !self.cpus.contains(&cpu) {
+            self.cpus.push(cpu);
+        }
+        self
+    }
+
+    /// Set whether the process memory is locked.
+    ///
+    /// Locking uses `mlockall`, so applies to the whole process, not only the reader,
+    /// as is typical for real-time applications.
+    /// Pages are locked as they are faulted in, where supported by the kernel, so large
+    /// mappings elsewhere in the process are not committed to memory up front.
+    pub fn with_memory_locked(&mut self, lock: bool) -> &mut Self {
+        self.lock_memory = lock;
+        self
+    }
+
+    /// Set the number of events the reader buffers from the kernel in a single read.
+    pub fn with_buffer_size(&mut self, events: usize) -> &mut Self {
+        self.buffer_size = events;
+        self
+    }
+
+    /// Spawn the reader thread, passing each edge event read from the request
+    /// to the handler.
+    ///
+    /// Returns once the thread has applied its real-time settings, so the settings
+    /// applied are available from the returned [`RealtimeHandle`].
+    ///
+    /// Settings that cannot be applied, typically due to insufficient privileges,
+    /// are skipped rather than returned as errors.
+    pub fn spawn<F>(&self, req: &Request, handler: F) -> Result<RealtimeHandle>
+    where
+        F: FnMut(EdgeEvent) + Send + 'static,
+    {
+        self.validate()?;
+        let req = req.try_clone()?;
+        let rr = self.clone();
+        let (tx, rx) = mpsc::channel();
+        let worker = Worker::spawn(move |stop| {
+            let mut buf = req.new_edge_event_buffer(rr.buffer_size);
+            buf.prefault();
+            prefault_stack();
+            let applied = Applied {
+                affinity: rr.cpus.is_empty() || set_affinity(&rr.cpus),
+                priority: set_priority(rr.priority),
+                memory_locked: !rr.lock_memory || lock_memory(),
+            };
+            trace_applied(&applied);
+            let _ = tx.send(applied);
+            drop(tx);
+            read_events(&req, &mut buf, handler, stop)
+        })?;
+        let applied = rx.recv().unwrap_or_default();
+        Ok(RealtimeHandle { worker, applied })
+    }
+
+    fn validate(&self) -> Result<()> {
+        // SAFETY: no pointers involved.
+        let (min, max) = unsafe {
+            (
+                libc::sched_get_priority_min(libc::SCHED_FIFO),
+                libc::sched_get_priority_max(libc::SCHED_FIFO),
+            )
+        };
+        if self.priority < min || self.priority > max {
+            return Err(Error::InvalidArgument(format!(
+                "priority {} is outside the supported range {}..={}.",
+                self.priority, min, max
+            )));
+        }
+        let cpu_setsize = libc::CPU_SETSIZE as usize;
+        if let Some(cpu) = self.cpus.iter().find(|cpu| **cpu >= cpu_setsize) {
+            return Err(Error::InvalidArgument(format!(
+                "cpu {} exceeds the maximum cpu {}.",
+                cpu,
+                cpu_setsize - 1
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A handle to a real-time reader thread.
+///
+/// Created by [`RealtimeReader::spawn`].
+///
+/// The events are consumed by the reader, so should not be read from the request
+/// while the reader is active.
+///
+/// The thread is stopped when the handle is dropped or [`stop`]ped, or if reading
+/// from the request fails.
+///
+/// [`stop`]: RealtimeHandle::stop
+pub struct RealtimeHandle {
+    worker: Worker,
+    applied: Applied,
+}
+
+impl RealtimeHandle {
+    /// The real-time settings applied to the reader thread.
+    pub fn applied(&self) -> Applied {
+        self.applied
+    }
+
+    /// Returns true if the reader thread has stopped as reading from the request failed.
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Stop the reader thread.
+    ///
+    /// Returns the error that stopped the thread, if reading from the request failed.
+    pub fn stop(mut self) -> Result<()> {
+        self.worker.stop()
+    }
+}
+
+fn read_events<F: FnMut(EdgeEvent)>(
+    req: &Request,
+    buf: &mut EdgeEventBuffer,
+    mut handler: F,
+    stop: &Stop,
+) -> Result<()> {
+    while !stop.is_stopped() {
+        if buf.is_empty() && !stop.wait_readable(req)? {
+            break;
+        }
+        handler(buf.read_event()?);
+    }
+    Ok(())
+}
+
+// Touch the stack so later growth, up to the prefault size, does not page fault.
+fn prefault_stack() {
+    let mut stack = [0_u8; STACK_PREFAULT_SIZE];
+    for b in stack.iter_mut().step_by(4096) {
+        // SAFETY: writing to a valid, local, location.
+        unsafe { std::ptr::write_volatile(b, 1) };
+    }
+}
+
+// Restrict the calling thread to the cpus.
+fn set_affinity(cpus: &[usize]) -> bool {
+    // SAFETY: cpu_set_t is a plain bitmask, and cpus are validated as less than CPU_SETSIZE.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        for cpu in cpus {
+            libc::CPU_SET(*cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+// Schedule the calling thread with SCHED_FIFO at the priority.
+fn set_priority(priority: i32) -> bool {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: param is a valid sched_param.
+    unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) == 0 }
+}
+
+// Lock the current and future pages of the process into memory as they are faulted in.
+//
+// Falls back to locking pages when mapped for kernels prior to 4.4, which do not
+// support MCL_ONFAULT.
+fn lock_memory() -> bool {
+    let flags = libc::MCL_CURRENT | libc::MCL_FUTURE;
+    // SAFETY: no pointers involved.
+    if unsafe { libc::mlockall(flags | libc::MCL_ONFAULT) } == 0 {
+        return true;
+    }
+    if std::io::Error::last_os_error().raw_os_error() != Some(libc::EINVAL) {
+        return false;
+    }
+    // SAFETY: no pointers involved.
+    unsafe { libc::mlockall(flags) == 0 }
+}
+
+#[allow(unused_variables)]
+fn trace_applied(applied: &Applied) {
+    #[cfg(feature = "tracing")]
+    if !(applied.priority && applied.affinity && applied.memory_locked) {
+        tracing::warn!(?applied, "real-time reader settings not fully applied");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let mut rr = RealtimeReader::new();
+        assert_eq!(rr, RealtimeReader::default());
+        assert_eq!(rr.priority, 50);
+        assert!(rr.cpus.is_empty());
+        assert!(!rr.lock_memory);
+        assert_eq!(rr.buffer_size, 16);
+
+        rr.with_priority(80)
+            .with_cpu(3)
+            .with_cpu(1)
+            .with_cpu(3)
+            .with_memory_locked(true)
+            .with_buffer_size(64);
+        assert_eq!(rr.priority, 80);
+        assert_eq!(rr.cpus, [3, 1]);
+        assert!(rr.lock_memory);
+        assert_eq!(rr.buffer_size, 64);
+    }
+
+    #[test]
+    fn validate() {
+        let mut rr = RealtimeReader::new();
+        assert!(rr.validate().is_ok());
+
+        rr.with_priority(0);
+        assert!(matches!(rr.validate(), Err(Error::InvalidArgument(_))));
+
+        rr.with_priority(99).with_cpu(libc::CPU_SETSIZE as usize);
+        assert_eq!(
+            rr.validate().unwrap_err().to_string(),
+            format!(
+                "cpu {} exceeds the maximum cpu {}.",
+                libc::CPU_SETSIZE,
+                libc::CPU_SETSIZE - 1
+            )
+        );
+    }
+}
@@ -8,24 +8,98 @@ pub use self::builder::Builder;
 mod config;
 pub use self::config::Config;
 
+mod config_delta;
+pub use self::config_delta::{Change, ConfigDelta, LineDelta};
+
+mod drop_policy;
+pub use self::drop_policy::DropPolicy;
+
+mod dump;
+pub use self::dump::UapiRequestDump;
+
+mod edge_event_adapters;
+#[cfg(feature = "async")]
+pub use self::edge_event_adapters::EdgeEventStreamExt;
+pub use self::edge_event_adapters::{
+    Chunks, Coalesce, EdgeEventIteratorExt, FilterKind, FilterLine, MapTimestamps,
+};
+
 mod edge_event_buffer;
 pub use self::edge_event_buffer::EdgeEventBuffer;
 
-use crate::line::{self, EdgeEvent, Offset, Value, Values};
+mod edge_fanout;
+pub use self::edge_fanout::{EdgeFanout, EdgeFilter, Subscription};
+
+mod edge_forward;
+pub use self::edge_forward::{EdgeEventSink, EdgeForwarder};
+
+mod edge_signal;
+pub use self::edge_signal::EdgeSignaller;
+
+mod event_slice;
+pub use self::event_slice::{EdgeEventRef, EventSlice};
+
+mod event_loss;
+pub use self::event_loss::{DetectLoss, EdgeOrLoss, EventLoss};
+
+#[cfg(feature = "uapi_v1")]
+mod fallback;
 #[cfg(feature = "uapi_v1")]
-use crate::AbiVersion;
-use crate::{Error, Result, UapiCall};
+pub use self::fallback::{Degradation, FallbackReport};
+
+mod config_guard;
+pub use self::config_guard::ConfigGuard;
+
+mod line_map;
+use self::line_map::LineMap;
+
+mod pulse;
+pub(crate) use self::pulse::PulseGuard;
+
+mod rate_limited;
+pub use self::rate_limited::{OverflowPolicy, RateLimited};
+
+mod retry_policy;
+pub use self::retry_policy::RetryPolicy;
+
+mod snapshot;
+pub use self::snapshot::Snapshot;
+
+mod suspend;
+pub use self::suspend::{DetectSuspend, EdgeOrResume, EdgeOrResumeEvents, SuspendDetector};
+
+mod value_source;
+pub use self::value_source::ValueSource;
+
+mod validate;
+pub use self::validate::{Diagnostic, Severity};
+
+mod verify;
+pub use self::verify::ValueMismatch;
+
+mod wide;
+pub use self::wide::WideRequest;
+
+mod worker;
+pub(crate) use self::worker::{Stop, Worker};
+
+use crate::line::{self, EdgeEvent, EdgeKind, Offset, Value, Values};
+use crate::timerfd::TimerFd;
+use crate::waveform::Waveform;
+use crate::{AbiVersion, Error, Result, UapiCall};
 #[cfg(not(feature = "uapi_v2"))]
 use gpiocdev_uapi::v1 as uapi;
 #[cfg(feature = "uapi_v1")]
 use gpiocdev_uapi::v1;
+use gpiocdev_uapi::NUM_LINES_MAX;
 #[cfg(feature = "uapi_v2")]
 use gpiocdev_uapi::{v2, v2 as uapi};
+use std::cmp::min;
 use std::fs::File;
-use std::mem;
-use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd};
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::mem::{self, ManuallyDrop};
+use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 /// An active request of a set of lines.
 ///
@@ -62,11 +136,21 @@ use std::time::Duration;
 /// from the kernel.  The [`edge_event_size`] method provides the size required to store a
 /// single event to allow sizing of custom slices.
 ///
+/// # ABI Selection
+///
+/// When built with only one of the **uapi_v1** and **uapi_v2** features, the uAPI
+/// version is selected at compile time, and the request dispatches directly to
+/// the ioctls of that version.
+/// Only when built with both is the version selected at runtime, by the request
+/// recording the version used to create it and branching on that in each call.
+///
 /// # Reading Output Values
 ///
 /// Note that reading back output values using [`value`] or [`values`] is dependent on driver
 /// and hardware support and so cannot be guaranteed to work, though frequently it does.
 /// Test with your particular hardware to be sure.
+/// For hardware that cannot read back outputs, the request can be built to return the
+/// values last set instead, using [`with_value_source`].
 ///
 /// [`edge_events`]: #method.edge_events
 /// [`edge_event_size`]: #method.edge_event_size
@@ -74,22 +158,48 @@ use std::time::Duration;
 /// [`read_edge_events_into_slice`]: #method.read_edge_events_into_slice
 /// [`with_kernel_event_buffer_size`]: struct.Builder.html#method.with_kernel_event_buffer_size
 /// [`with_user_event_buffer_size`]: struct.Builder.html#method.with_user_event_buffer_size
+/// [`with_value_source`]: struct.Builder.html#method.with_value_source
 /// [`value`]: #method.value
 /// [`values`]: #method.values
 #[derive(Debug)]
 pub struct Request {
     /// The request file.
-    f: File,
+    ///
+    /// Closed when the request is dropped, unless taken by conversion to an [`OwnedFd`].
+    f: ManuallyDrop<File>,
 
-    /// The offsets of the requested lines.
-    offsets: Vec<Offset>,
+    /// The request file has been taken, so the request no longer owns the lines.
+    released: bool,
+
+    /// The offsets of the requested lines, and their mapping to the uAPI line values.
+    lines: LineMap,
 
     /// A snapshot of the active configuration for the request.
     cfg: Arc<RwLock<Config>>,
 
+    /// The values set on the lines since the configuration was last updated, indexed by line.
+    ///
+    /// Kept separate from the configuration so setting values does not block readers of
+    /// the configuration.
+    /// Where both are required this lock is acquired first.
+    last_set: Arc<Mutex<Vec<Option<Value>>>>,
+
     /// The size of the user buffer created for the `edge_events` iterator.
     user_event_buffer_size: usize,
 
+    /// The kernel event buffer size requested, or 0 for the kernel default.
+    kernel_event_buffer_size: u32,
+
+    /// The action performed on the lines when the request is dropped.
+    drop_policy: DropPolicy,
+
+    /// The source of the values returned for output lines.
+    value_source: ValueSource,
+
+    /// The settings dropped to make the request using uAPI v1.
+    #[cfg(feature = "uapi_v1")]
+    fallback: FallbackReport,
+
     /// The ABI version used to create the request, and so determines how to decode events.
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     abiv: AbiVersion,
@@ -138,12 +248,77 @@ impl Request {
         Builder::from_config(config)
     }
 
+    /// Reattach a request to an already open line request file descriptor.
+    ///
+    /// This allows a request to be passed between processes, or recovered from
+    /// an event loop that owns the file descriptor, such as one obtained from
+    /// the conversion of a `Request` into an [`OwnedFd`].
+    ///
+    /// The `config` must describe the lines in the request, as returned by
+    /// [`config`], and the lines must have been requested in ascending order of
+    /// offset, as is the case for requests made by this library.
+    ///
+    /// The request has the default [`DropPolicy`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # use std::os::unix::prelude::OwnedFd;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(5)
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// let cfg = req.config();
+    /// let fd = OwnedFd::from(req);
+    /// // ... pass the fd and config elsewhere ...
+    /// let req = Request::from_parts(fd, cfg)?;
+    /// req.set_value(5, Value::Inactive)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`config`]: #method.config
+    pub fn from_parts(fd: OwnedFd, config: Config) -> Result<Request> {
+        if config.num_lines() == 0 {
+            return Err(Error::InvalidArgument("No lines specified.".to_string()));
+        }
+        let mut cfg = config.effective();
+        cfg.offsets.sort_unstable();
+        let f = File::from(fd);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        let abiv = detect_abi_version(&f);
+        Ok(Request {
+            f: ManuallyDrop::new(f),
+            released: false,
+            lines: LineMap::new(cfg.offsets.clone()),
+            last_set: Arc::new(Mutex::new(vec![None; cfg.offsets.len()])),
+            cfg: Arc::new(RwLock::new(cfg)),
+            user_event_buffer_size: 1,
+            kernel_event_buffer_size: 0,
+            drop_policy: DropPolicy::default(),
+            value_source: ValueSource::default(),
+            #[cfg(feature = "uapi_v1")]
+            fallback: FallbackReport::default(),
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            abiv,
+        })
+    }
+
     /// Get the values for a subset of the requested lines.
     ///
     /// The keys indicate the lines to get.
     /// Keys that are not requested offsets are ignored.
     /// If no keys are set then all requested lines are returned.
     ///
+    /// # Allocation
+    ///
+    /// The values are updated in place, so getting values does not allocate once
+    /// the keys are set, either explicitly or by a previous call.
+    /// This does not hold if the `tracing` feature is enabled.
+    ///
     /// # Examples
     /// ```no_run
     /// # use gpiocdev::{Request, Result};
@@ -163,7 +338,105 @@ impl Request {
     /// # }
     /// ```
     pub fn values(&self, values: &mut Values) -> Result<()> {
-        self.do_values(values)
+        self.values_with_source(values).map(|_| ())
+    }
+
+    /// Get the values for a subset of the requested lines, and the source of those values.
+    ///
+    /// As per [`values`], but also returns the source of the values.
+    ///
+    /// If the request was built with [`ValueSource::LastSet`] and any of the returned
+    /// values are the last values set for output lines, rather than read from the kernel,
+    /// then returns [`ValueSource::LastSet`], else returns [`ValueSource::Physical`].
+    ///
+    /// [`values`]: #method.values
+    pub fn values_with_source(&self, values: &mut Values) -> Result<ValueSource> {
+        trace_span!(
+            "values",
+            chip = %self.chip_path().display(),
+            offsets = ?self.lines.offsets,
+            abiv = %self.abi_version(),
+        );
+        if self.value_source == ValueSource::Physical {
+            return self.do_values(values).map(|_| ValueSource::Physical);
+        }
+        if values.is_empty() {
+            values.extend(self.lines.offsets.iter().map(|o| (*o, Value::Inactive)));
+        }
+        let set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        let cfg = self
+            .cfg
+            .read()
+            .expect("failed to acquire read lock on config");
+        let last_set = |offset| self.last_set_value(&cfg, &set, offset);
+        self.do_physical_values(values, last_set)?;
+        let mut source = ValueSource::Physical;
+        for lv in values.iter_mut() {
+            if let Some(value) = last_set(lv.offset) {
+                lv.value = value;
+                source = ValueSource::LastSet;
+            }
+        }
+        Ok(source)
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn do_physical_values<F>(&self, values: &mut Values, last_set: F) -> Result<()>
+    where
+        F: Fn(Offset) -> Option<Value>,
+    {
+        match self.abiv {
+            AbiVersion::V1 => self.do_physical_values_v1(values, last_set),
+            AbiVersion::V2 => self.do_physical_values_v2(values, last_set),
+        }
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn do_physical_values<F>(&self, values: &mut Values, last_set: F) -> Result<()>
+    where
+        F: Fn(Offset) -> Option<Value>,
+    {
+        self.do_physical_values_v1(values, last_set)
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn do_physical_values<F>(&self, values: &mut Values, last_set: F) -> Result<()>
+    where
+        F: Fn(Offset) -> Option<Value>,
+    {
+        self.do_physical_values_v2(values, last_set)
+    }
+    // v1 can only read all lines, so reads them if any lack a last set value.
+    #[cfg(feature = "uapi_v1")]
+    fn do_physical_values_v1<F>(&self, values: &mut Values, last_set: F) -> Result<()>
+    where
+        F: Fn(Offset) -> Option<Value>,
+    {
+        if values
+            .iter()
+            .any(|lv| self.lines.index(lv.offset).is_some() && last_set(lv.offset).is_none())
+        {
+            self.do_values_v1(values)?;
+        }
+        Ok(())
+    }
+    #[cfg(feature = "uapi_v2")]
+    fn do_physical_values_v2<F>(&self, values: &mut Values, last_set: F) -> Result<()>
+    where
+        F: Fn(Offset) -> Option<Value>,
+    {
+        let mut vals = self.lines.to_v2(values);
+        for lv in values.iter() {
+            if let (Some(idx), Some(_)) = (self.lines.index(lv.offset), last_set(lv.offset)) {
+                vals.unset_mask(idx);
+            }
+        }
+        if vals.mask == 0 {
+            return Ok(());
+        }
+        v2::get_line_values(&self.f, &mut vals)
+            .map(|_| self.lines.update_from_v2(values, &vals))
+            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_values(&self, values: &mut Values) -> Result<()> {
@@ -184,14 +457,14 @@ impl Request {
     fn do_values_v1(&self, values: &mut Values) -> Result<()> {
         let mut vals = v1::LineValues::default();
         v1::get_line_values(&self.f, &mut vals)
-            .map(|_| values.update_from_v1(&self.offsets, &vals))
+            .map(|_| self.lines.update_from_v1(values, &vals))
             .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))
     }
     #[cfg(feature = "uapi_v2")]
     fn do_values_v2(&self, values: &mut Values) -> Result<()> {
-        let mut vals = values.to_v2(&self.offsets);
+        let mut vals = self.lines.to_v2(values);
         v2::get_line_values(&self.f, &mut vals)
-            .map(|_| values.update_from_v2(&self.offsets, &vals))
+            .map(|_| self.lines.update_from_v2(values, &vals))
             .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))
     }
 
@@ -211,13 +484,41 @@ impl Request {
     /// # }
     /// ```
     pub fn value(&self, offset: Offset) -> Result<Value> {
+        trace_span!(
+            "value",
+            chip = %self.chip_path().display(),
+            offset,
+            abiv = %self.abi_version(),
+        );
         let idx = self
-            .offsets
-            .iter()
-            .position(|v| v == &offset)
+            .lines
+            .index(offset)
             .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".to_string()))?;
+        if self.value_source == ValueSource::LastSet {
+            let set = self
+                .last_set
+                .lock()
+                .expect("failed to acquire lock on values");
+            let cfg = self
+                .cfg
+                .read()
+                .expect("failed to acquire read lock on config");
+            if let Some(value) = self.last_set_value(&cfg, &set, offset) {
+                return Ok(value);
+            }
+        }
         self.do_value(idx)
     }
+
+    // The value last set on an output line, either since the config was last
+    // updated or as set by the config.
+    fn last_set_value(&self, cfg: &Config, set: &[Option<Value>], offset: Offset) -> Option<Value> {
+        let lc = cfg.lcfg.get(&offset)?;
+        let idx = self.lines.index(offset)?;
+        set[idx]
+            .filter(|_| lc.direction == Some(line::Direction::Output))
+            .or_else(|| lc.last_set_value())
+    }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_value(&self, idx: usize) -> Result<Value> {
         match self.abiv {
@@ -267,9 +568,78 @@ impl Request {
     /// req.set_values(&values)?;
     /// # Ok(())
     /// # }
+    /// ```
+    ///
+    /// # Allocation
+    ///
+    /// Setting values does not allocate, unless the `tracing` feature is enabled.
     pub fn set_values(&self, values: &Values) -> Result<()> {
-        self.do_set_values(values)
+        trace_span!(
+            "set_values",
+            chip = %self.chip_path().display(),
+            ?values,
+            abiv = %self.abi_version(),
+        );
+        let mut set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        self.do_set_values(values)?;
+        self.update_last_set(&mut set, values);
+        Ok(())
+    }
+
+    // Record the values set on the lines.
+    fn update_last_set(&self, set: &mut [Option<Value>], values: &Values) {
+        for lv in values.iter() {
+            if let Some(idx) = self.lines.index(lv.offset) {
+                set[idx] = Some(lv.value);
+            }
+        }
+    }
+
+    /// Set the values for a subset of the requested lines, then read the lines back
+    /// to confirm the values were applied.
+    ///
+    /// Returns the lines that read back a different value to that set, so an empty
+    /// result confirms all the values.
+    ///
+    /// The values are always read back from the kernel, irrespective of the
+    /// [`ValueSource`] of the request, so this requires a driver that can read the
+    /// physical level of output lines.
+    /// A mismatch indicates the line is being driven by something else, such as
+    /// another device on an open-drain bus, or faulty wiring.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::{Drive, Value, Values};
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[5, 6])
+    ///     .as_output(Value::Inactive)
+    ///     .with_drive(Drive::OpenDrain)
+    ///     .request()?;
+    /// let values = Values::from([(5, Value::Active), (6, Value::Active)]);
+    /// for mismatch in req.set_values_verified(&values)? {
+    ///     eprintln!("{}", mismatch);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_values_verified(&self, values: &Values) -> Result<Vec<ValueMismatch>> {
+        self.set_values(values)?;
+        let offsets: Vec<Offset> = values
+            .iter()
+            .map(|lv| lv.offset)
+            .filter(|offset| self.lines.index(*offset).is_some())
+            .collect();
+        let mut readback = Values::from_offsets(&offsets);
+        self.do_values(&mut readback)?;
+        Ok(verify::mismatches(values, &readback))
     }
+
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_set_values(&self, values: &Values) -> Result<()> {
         match self.abiv {
@@ -287,18 +657,18 @@ impl Request {
     }
     #[cfg(feature = "uapi_v1")]
     fn do_set_values_v1(&self, values: &Values) -> Result<()> {
-        if !values.contains_keys(&self.offsets) {
+        if !self.lines.contains_all(values) {
             return Err(Error::AbiLimitation(
                 AbiVersion::V1,
                 "requires all requested lines".to_string(),
             ));
         }
-        v1::set_line_values(&self.f, &values.to_v1(&self.offsets))
+        v1::set_line_values(&self.f, &self.lines.to_v1(values))
             .map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
     }
     #[cfg(feature = "uapi_v2")]
     fn do_set_values_v2(&self, values: &Values) -> Result<()> {
-        let lv = &values.to_v2(&self.offsets);
+        let lv = &self.lines.to_v2(values);
         if lv.mask == 0 {
             return Err(Error::InvalidArgument(
                 "no requested lines in set values.".to_string(),
@@ -307,6 +677,123 @@ impl Request {
         v2::set_line_values(&self.f, lv).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
     }
 
+    /// Set the values for a subset of the requested lines, then read back all the
+    /// requested lines.
+    ///
+    /// All the requested lines, including those set in `values`, are read after the
+    /// optional `settle` delay, and their values returned.
+    ///
+    /// Both ioctl calls are prepared before the values are set, so the only delay
+    /// between them is the settle period, making this suitable for handshake-style
+    /// interfaces where an input responds to an output.
+    /// Settle periods below 100 microseconds are busy-waited, rather than slept,
+    /// for accuracy.
+    ///
+    /// With uAPI v1 all requested lines must be set.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::{Value, Values};
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let mut cfg = gpiocdev::request::Config::default();
+    /// cfg.on_chip("/dev/gpiochip0")
+    ///     .with_line(3)
+    ///     .as_output(Value::Inactive)
+    ///     .with_line(5)
+    ///     .as_input();
+    /// let req = Request::from_config(cfg).request()?;
+    /// let strobe = Values::from([(3, Value::Active)]);
+    /// let ack = req.set_then_read(&strobe, Duration::from_micros(10))?;
+    /// println!("ack: {:?}", ack.get(5));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_then_read(&self, values: &Values, settle: Duration) -> Result<Values> {
+        let mut inputs = Values::from_offsets(&self.lines.offsets);
+        let mut set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        self.do_set_then_read(values, settle, &mut inputs)?;
+        self.update_last_set(&mut set, values);
+        Ok(inputs)
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn do_set_then_read(
+        &self,
+        values: &Values,
+        settle: Duration,
+        inputs: &mut Values,
+    ) -> Result<()> {
+        match self.abiv {
+            AbiVersion::V1 => self.do_set_then_read_v1(values, settle, inputs),
+            AbiVersion::V2 => self.do_set_then_read_v2(values, settle, inputs),
+        }
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn do_set_then_read(
+        &self,
+        values: &Values,
+        settle: Duration,
+        inputs: &mut Values,
+    ) -> Result<()> {
+        self.do_set_then_read_v1(values, settle, inputs)
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn do_set_then_read(
+        &self,
+        values: &Values,
+        settle: Duration,
+        inputs: &mut Values,
+    ) -> Result<()> {
+        self.do_set_then_read_v2(values, settle, inputs)
+    }
+    #[cfg(feature = "uapi_v1")]
+    fn do_set_then_read_v1(
+        &self,
+        values: &Values,
+        settle: Duration,
+        inputs: &mut Values,
+    ) -> Result<()> {
+        if !self.lines.contains_all(values) {
+            return Err(Error::AbiLimitation(
+                AbiVersion::V1,
+                "requires all requested lines".to_string(),
+            ));
+        }
+        let set = self.lines.to_v1(values);
+        let mut get = v1::LineValues::default();
+        v1::set_line_values(&self.f, &set).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))?;
+        wait_settle(settle);
+        v1::get_line_values(&self.f, &mut get)
+            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))?;
+        self.lines.update_from_v1(inputs, &get);
+        Ok(())
+    }
+    #[cfg(feature = "uapi_v2")]
+    fn do_set_then_read_v2(
+        &self,
+        values: &Values,
+        settle: Duration,
+        inputs: &mut Values,
+    ) -> Result<()> {
+        let set = self.lines.to_v2(values);
+        if set.mask == 0 {
+            return Err(Error::InvalidArgument(
+                "no requested lines in set values.".to_string(),
+            ));
+        }
+        let mut get = self.lines.to_v2(inputs);
+        v2::set_line_values(&self.f, &set).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))?;
+        wait_settle(settle);
+        v2::get_line_values(&self.f, &mut get)
+            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))?;
+        self.lines.update_from_v2(inputs, &get);
+        Ok(())
+    }
+
     /// Set the value for one line in the request.
     ///
     /// # Examples
@@ -324,12 +811,24 @@ impl Request {
     /// # Ok(())
     /// # }
     pub fn set_value(&self, offset: Offset, value: Value) -> Result<()> {
+        trace_span!(
+            "set_value",
+            chip = %self.chip_path().display(),
+            offset,
+            ?value,
+            abiv = %self.abi_version(),
+        );
         let idx = self
-            .offsets
-            .iter()
-            .position(|v| v == &offset)
+            .lines
+            .index(offset)
             .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".to_string()))?;
-        self.do_set_value(idx, value)
+        let mut set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        self.do_set_value(idx, value)?;
+        set[idx] = Some(value);
+        Ok(())
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_set_value(&self, idx: usize, value: Value) -> Result<()> {
@@ -348,7 +847,7 @@ impl Request {
     }
     #[cfg(feature = "uapi_v1")]
     fn do_set_value_v1(&self, idx: usize, value: Value) -> Result<()> {
-        if self.offsets.len() > 1 {
+        if self.lines.offsets.len() > 1 {
             return Err(Error::AbiLimitation(
                 AbiVersion::V1,
                 "requires all requested lines".to_string(),
@@ -369,80 +868,379 @@ impl Request {
         v2::set_line_values(&self.f, &vals).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
     }
 
-    /// Return the path of the chip for this request.
-    pub fn chip_path(&self) -> std::path::PathBuf {
-        self.cfg
-            .read()
-            .expect("failed to acquire read lock on config")
-            .chip
-            .clone()
-    }
-
-    /// Get a snapshot of the requested configuration.
+    /// Toggle the value of one line in the request.
     ///
-    /// This is the configuration currently applied to the hardware.
-    pub fn config(&self) -> Config {
-        self.cfg
-            .read()
-            .expect("failed to acquire read lock on config")
-            .clone()
+    /// The current value is taken from the value most recently set by the request,
+    /// rather than being read from the line, so this is only applicable to output lines.
+    ///
+    /// Returns the new value of the line.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(22)
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// let value = req.toggle_value(22)?;
+    /// assert_eq!(value, Value::Inactive);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn toggle_value(&self, offset: Offset) -> Result<Value> {
+        let idx = self
+            .lines
+            .index(offset)
+            .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".to_string()))?;
+        let mut set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        let value = self.toggled_value(&set, offset);
+        self.do_set_value(idx, value)?;
+        set[idx] = Some(value);
+        Ok(value)
     }
 
-    /// Get a snapshot of the requested configuration for a particular line.
+    /// Toggle the values of a subset of the requested lines.
     ///
-    /// This is the configuration currently applied to the line.
-    pub fn line_config(&self, offset: Offset) -> Option<line::Config> {
-        self.cfg
-            .read()
-            .expect("failed to acquire read lock on config")
-            .line_config(offset)
-            .cloned()
+    /// The current values are taken from the values most recently set by the request,
+    /// rather than being read from the lines, so this is only applicable to output lines.
+    ///
+    /// Returns the new values of the lines.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[12, 22])
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// let values = req.toggle_values(&[12, 22])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn toggle_values(&self, offsets: &[Offset]) -> Result<Values> {
+        if let Some(offset) = offsets.iter().find(|o| !self.lines.offsets.contains(o)) {
+            return Err(Error::InvalidArgument(format!(
+                "offset {} is not a requested line.",
+                offset
+            )));
+        }
+        let mut set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        let values: Values = offsets
+            .iter()
+            .map(|offset| (*offset, self.toggled_value(&set, *offset)))
+            .collect();
+        self.do_set_values(&values)?;
+        self.update_last_set(&mut set, &values);
+        Ok(values)
     }
 
-    /// Reconfigure the request with the an updated configuration.
-    ///
-    /// Note that lines cannot be added or removed from the request.
-    /// Any additional lines in `new_cfg` will be ignored, and any missing
-    /// lines will retain their existing configuration.
-    pub fn reconfigure(&self, new_cfg: &Config) -> Result<()> {
+    // The inverse of the value most recently set on a line.
+    fn toggled_value(&self, set: &[Option<Value>], offset: Offset) -> Value {
         let cfg = self
             .cfg
             .read()
-            .expect("failed to acquire read lock on config")
-            .overlay(new_cfg);
-        self.do_reconfigure(&cfg)?;
-        // only update request config if reconfigure succeeds.
-        self.cfg
-            .write()
-            .expect("failed to acquire write lock on config")
-            .update(cfg);
-        Ok(())
+            .expect("failed to acquire read lock on config");
+        match (cfg.lcfg.get(&offset), self.lines.index(offset)) {
+            (Some(lc), Some(idx)) => set[idx].unwrap_or_else(|| lc.value()).not(),
+            _ => Value::Active,
+        }
     }
-    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-    fn do_reconfigure(&self, cfg: &Config) -> Result<()> {
-        match self.abiv {
-            AbiVersion::V1 => {
-                if self
-                    .cfg
-                    .read()
-                    .expect("failed to acquire read lock on config")
-                    .unique()?
-                    .edge_detection
-                    .is_some()
-                {
-                    return Err(Error::AbiLimitation(
-                        AbiVersion::V1,
-                        "cannot reconfigure lines with edge detection".to_string(),
-                    ));
-                }
-                if cfg.unique()?.edge_detection.is_some() {
-                    return Err(Error::AbiLimitation(
-                        AbiVersion::V1,
-                        "cannot reconfigure edge detection".to_string(),
-                    ));
-                }
-                v1::set_line_config(&self.f, cfg.to_v1()?)
-                    .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e))
+
+    /// Play a waveform on one line in the request.
+    ///
+    /// Blocks until the final step of the waveform has elapsed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # use gpiocdev::waveform::Waveform;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(22)
+    ///     .as_output(Value::Inactive)
+    ///     .request()?;
+    /// let mut wf = Waveform::new();
+    /// wf.push(Value::Active, Duration::from_millis(20))
+    ///     .push(Value::Inactive, Duration::from_millis(80))
+    ///     .repeat(10);
+    /// req.play_waveform(22, &wf)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn play_waveform(&self, offset: Offset, wf: &Waveform) -> Result<()> {
+        crate::waveform::play(self, offset, wf)
+    }
+
+    /// Play a sequence of frames on the requested lines at a fixed frame rate.
+    ///
+    /// Each frame is set using a single [`set_values`], so all the lines in a frame
+    /// change together, and is held for `frame_period` before the next frame is set.
+    /// Frame timing is based on absolute deadlines, so scheduling latency in individual
+    /// frames does not accumulate over the sequence.
+    ///
+    /// All frames are checked before any are set, so an invalid frame does not leave the
+    /// sequence partially played.
+    ///
+    /// Blocks until the final frame period has elapsed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # use gpiocdev::waveform::chaser;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let offsets = [17, 18, 22, 23, 24, 25];
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&offsets)
+    ///     .as_output(Value::Inactive)
+    ///     .request()?;
+    /// let frames = chaser(&offsets);
+    /// for _ in 0..10 {
+    ///     // 20 frames per second
+    ///     req.play_frames(&frames, Duration::from_millis(50))?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_values`]: Request::set_values
+    pub fn play_frames(&self, frames: &[Values], frame_period: Duration) -> Result<()> {
+        crate::waveform::play_frames(self, &self.lines.offsets, frames, frame_period)
+    }
+
+    /// Pulse one line in the request active for a period, then return it to inactive.
+    ///
+    /// The period is timed using a timerfd, and blocks until the pulse is complete.
+    ///
+    /// The line is returned to inactive even if the wait for the timer fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(22)
+    ///     .as_output(Value::Inactive)
+    ///     .request()?;
+    /// // trigger the relay
+    /// req.pulse(22, Duration::from_millis(250))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pulse(&self, offset: Offset, active_duration: Duration) -> Result<()> {
+        let timer = TimerFd::new(false)?;
+        let guard = PulseGuard::new(self, offset)?;
+        if !active_duration.is_zero() {
+            timer.set_oneshot(active_duration)?;
+            timer.read()?;
+        }
+        guard.release()
+    }
+
+    // The ABI version used to create the request.
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn abi_version(&self) -> AbiVersion {
+        self.abiv
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn abi_version(&self) -> AbiVersion {
+        AbiVersion::V1
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn abi_version(&self) -> AbiVersion {
+        AbiVersion::V2
+    }
+
+    /// Return the path of the chip for this request.
+    pub fn chip_path(&self) -> std::path::PathBuf {
+        self.cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .chip
+            .clone()
+    }
+
+    /// Return the offsets of the requested lines.
+    ///
+    /// The offsets are sorted in ascending order.
+    pub fn lines(&self) -> &[Offset] {
+        &self.lines.offsets
+    }
+
+    /// Get a snapshot of the requested configuration.
+    ///
+    /// This is the configuration currently applied to the hardware, including
+    /// any reconfiguration.
+    /// Settings that are not applicable to the line, and so are not passed to the
+    /// kernel, such as the drive setting of an input, are not included.
+    pub fn config(&self) -> Config {
+        let set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        let mut cfg = self
+            .cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .clone();
+        self.apply_last_set(&set, &mut cfg);
+        cfg
+    }
+
+    // Apply the values set since the config was last updated to a copy of the config.
+    fn apply_last_set(&self, set: &[Option<Value>], cfg: &mut Config) {
+        for (offset, value) in self.lines.offsets.iter().zip(set.iter()) {
+            if let (Some(lc), Some(value)) = (cfg.lcfg.get_mut(offset), value) {
+                lc.value = Some(*value);
+            }
+        }
+    }
+
+    /// Get a snapshot of the requested configuration for a particular line.
+    ///
+    /// This is the configuration currently applied to the line, as per [`config`].
+    ///
+    /// Returns None if the line is not part of the request.
+    ///
+    /// [`config`]: #method.config
+    pub fn line_config(&self, offset: Offset) -> Option<line::Config> {
+        let set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        let mut lc = self
+            .cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .line_config(offset)
+            .cloned()?;
+        if let Some(value) = self.lines.index(offset).and_then(|idx| set[idx]) {
+            lc.value = Some(value);
+        }
+        Some(lc)
+    }
+
+    /// The settings dropped to make the request using uAPI v1.
+    ///
+    /// Empty unless the request was built [`with_v1_fallback`] and made using uAPI v1.
+    ///
+    /// [`with_v1_fallback`]: Builder::with_v1_fallback
+    #[cfg(feature = "uapi_v1")]
+    pub fn fallback_report(&self) -> &FallbackReport {
+        &self.fallback
+    }
+
+    /// The size of the buffer used by the kernel to store edge events until they are read,
+    /// in events.
+    ///
+    /// This is the size allocated by the kernel, which may differ from the size requested
+    /// using [`with_kernel_event_buffer_size`], as the kernel limits the size to
+    /// 1024 events and rounds it up to a power of two.
+    /// The buffer is only allocated if edge detection is enabled on at least one line.
+    ///
+    /// uAPI v1 uses a fixed size buffer of 16 events per line.
+    ///
+    /// For a request reattached using [`from_parts`] the default size is assumed.
+    ///
+    /// [`with_kernel_event_buffer_size`]: Builder::with_kernel_event_buffer_size
+    /// [`from_parts`]: Request::from_parts
+    pub fn kernel_event_buffer_size(&self) -> u32 {
+        kernel_event_buffer_size(
+            self.abi_version(),
+            self.kernel_event_buffer_size,
+            self.lines.offsets.len(),
+        )
+    }
+
+    /// Reconfigure the request with the an updated configuration.
+    ///
+    /// Note that lines cannot be added or removed from the request.
+    /// Any additional lines in `new_cfg` will be ignored, and any missing
+    /// lines will retain their existing configuration.
+    pub fn reconfigure(&self, new_cfg: &Config) -> Result<()> {
+        trace_span!(
+            "reconfigure",
+            chip = %self.chip_path().display(),
+            offsets = ?self.lines.offsets,
+            abiv = %self.abi_version(),
+        );
+        // held throughout so values set concurrently are not lost by the update.
+        let mut set = self
+            .last_set
+            .lock()
+            .expect("failed to acquire lock on values");
+        let mut cfg = self
+            .cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .clone();
+        self.apply_last_set(&set, &mut cfg);
+        let cfg = cfg.overlay(new_cfg);
+        #[cfg(feature = "uapi_v1")]
+        let cfg = self.degrade_for_v1(cfg);
+        self.do_reconfigure(&cfg)?;
+        // only update request config if reconfigure succeeds.
+        self.cfg
+            .write()
+            .expect("failed to acquire write lock on config")
+            .update(cfg);
+        set.iter_mut().for_each(|v| *v = None);
+        Ok(())
+    }
+    // uAPI v1 cannot reconfigure the debounce period or event clock, so drop them.
+    #[cfg(feature = "uapi_v1")]
+    fn degrade_for_v1(&self, cfg: Config) -> Config {
+        let mut cfg = cfg.effective();
+        if self.abi_version() == AbiVersion::V1 {
+            fallback::degrade(&mut cfg).trace();
+        }
+        cfg
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn do_reconfigure(&self, cfg: &Config) -> Result<()> {
+        match self.abiv {
+            AbiVersion::V1 => {
+                if self
+                    .cfg
+                    .read()
+                    .expect("failed to acquire read lock on config")
+                    .unique()?
+                    .edge_detection
+                    .is_some()
+                {
+                    return Err(Error::AbiLimitation(
+                        AbiVersion::V1,
+                        "cannot reconfigure lines with edge detection".to_string(),
+                    ));
+                }
+                if cfg.unique()?.edge_detection.is_some() {
+                    return Err(Error::AbiLimitation(
+                        AbiVersion::V1,
+                        "cannot reconfigure edge detection".to_string(),
+                    ));
+                }
+                v1::set_line_config(&self.f, cfg.to_v1()?)
+                    .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e))
             }
             AbiVersion::V2 => v2::set_line_config(&self.f, cfg.to_v2()?)
                 .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e)),
@@ -478,6 +1276,79 @@ impl Request {
             .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e))
     }
 
+    /// Temporarily reconfigure the request.
+    ///
+    /// The configuration is applied as per [`reconfigure`], and the previous
+    /// configuration is restored when the returned guard is dropped.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(22)
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// let mut cfg = req.config();
+    /// cfg.as_input();
+    /// let value = {
+    ///     // briefly flip the line to input to read it back
+    ///     let _guard = req.with_config_scope(&cfg)?;
+    ///     req.value(22)?
+    /// };
+    /// // line is an output again
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`reconfigure`]: #method.reconfigure
+    pub fn with_config_scope(&self, new_cfg: &Config) -> Result<ConfigGuard<'_>> {
+        ConfigGuard::new(self, new_cfg)
+    }
+
+    /// Take a snapshot of the configuration of the requested lines, including the
+    /// current values of output lines.
+    ///
+    /// The snapshot can be reapplied later using [`restore`], e.g. to resume outputs
+    /// after they have been paused, or after the application restarts.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[17, 22])
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// let snapshot = req.snapshot()?;
+    /// // pause the outputs
+    /// req.set_values(&[(17, Value::Inactive), (22, Value::Inactive)].into())?;
+    /// // ... later, resume
+    /// req.restore(&snapshot)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`restore`]: #method.restore
+    pub fn snapshot(&self) -> Result<Snapshot> {
+        Snapshot::new(self)
+    }
+
+    /// Reapply a snapshot of the configuration and output values to the requested lines.
+    ///
+    /// As per [`reconfigure`], any lines in the snapshot that are not in the request
+    /// are ignored, and any requested lines missing from the snapshot retain their
+    /// existing configuration.
+    ///
+    /// [`reconfigure`]: #method.reconfigure
+    pub fn restore(&self, snapshot: &Snapshot) -> Result<()> {
+        self.reconfigure(&snapshot.to_config())
+    }
+
     /// An iterator for events from the request.
     ///
     /// By default the events are read from the kernel individually.
@@ -505,10 +1376,47 @@ impl Request {
     /// ```
     ///
     /// [`Builder.with_user_event_buffer_size`]: struct.Builder.html#method.with_user_event_buffer_size
-    pub fn edge_events(&self) -> EdgeEventBuffer {
+    pub fn edge_events(&self) -> EdgeEventBuffer<'_> {
         self.new_edge_event_buffer(self.user_event_buffer_size)
     }
 
+    /// An iterator for events from the request that also reports any system suspend
+    /// as soon as the system resumes.
+    ///
+    /// Events are returned as [`EdgeOrResume::Edge`], and a suspend detected by the
+    /// detector is returned as an [`EdgeOrResume::Resume`], without waiting for the
+    /// next event as the [`detect_suspend`] adapter does.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # use gpiocdev::request::{EdgeOrResume, SuspendDetector};
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// for item in req.edge_events_or_resume(SuspendDetector::new())? {
+    ///     match item? {
+    ///         EdgeOrResume::Edge(evt) => println!("{:?}", evt),
+    ///         EdgeOrResume::Resume(_) => println!("resumed - resync line state"),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`detect_suspend`]: EdgeEventIteratorExt::detect_suspend
+    pub fn edge_events_or_resume(
+        &self,
+        detector: SuspendDetector,
+    ) -> Result<EdgeOrResumeEvents<'_>> {
+        EdgeOrResumeEvents::new(self, detector)
+    }
+
     /// Returns true when the request has edge events available to read using [`read_edge_event`].
     ///
     /// [`read_edge_event`]: #method.read_edge_event
@@ -538,6 +1446,61 @@ impl Request {
     pub fn read_edge_event(&self) -> Result<EdgeEvent> {
         self.do_read_edge_event()
     }
+
+    /// Wait for a particular edge on a particular line.
+    ///
+    /// Returns the matching event, or None if the timeout expires before a matching
+    /// event is received.
+    ///
+    /// Events that do not match are read and discarded.
+    ///
+    /// * `offset` - The line to wait for.
+    /// * `kind` - The edge to wait for.
+    /// * `timeout` - The maximum time to wait for a matching event.
+    ///   A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    ///   waits indefinitely.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::{EdgeDetection, EdgeKind};
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// match req.wait_for_edge(23, EdgeKind::Falling, Duration::from_secs(5))? {
+    ///     Some(evt) => println!("button pressed: {:?}", evt),
+    ///     None => println!("timed out"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_for_edge(
+        &self,
+        offset: Offset,
+        kind: EdgeKind,
+        timeout: Duration,
+    ) -> Result<Option<EdgeEvent>> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if !self.wait_edge_event(remaining)? {
+                    return Ok(None);
+                }
+            }
+            let evt = self.read_edge_event()?;
+            if evt.offset == offset && evt.kind == kind {
+                return Ok(Some(evt));
+            }
+        }
+    }
+
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_read_edge_event(&self) -> Result<EdgeEvent> {
         // bbuf is statically sized to the greater of the v1/v2 size so it can be placed on the stack.
@@ -554,13 +1517,219 @@ impl Request {
         self.do_edge_event_from_slice(&buf[0..n])
     }
 
+    /// Read a batch of edge events from the request.
+    ///
+    /// Reads as many events as are available, up to `max`, with a single read where
+    /// possible, and appends them to `events`.
+    /// This is more efficient than reading events individually for bursty sources,
+    /// such as rotary encoders.
+    ///
+    /// Will block until at least one edge event is available, but does not block
+    /// waiting for further events.
+    ///
+    /// Returns the number of events appended.
+    ///
+    /// If reading fails then `events` is truncated back to its original length before
+    /// the error is returned, so either all or none of the events read are appended.
+    /// Events read before the failure are discarded.
+    ///
+    /// * `events` - The vector to append the events to.
+    /// * `max` - The maximum number of events to read.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[17, 18])
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    /// let mut events = Vec::new();
+    /// loop {
+    ///     events.clear();
+    ///     req.read_edge_events(&mut events, 32)?;
+    ///     for evt in &events {
+    ///         println!("{:?}", evt);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn read_edge_events(&self, events: &mut Vec<EdgeEvent>, max: usize) -> Result<usize> {
+        let len = events.len();
+        let res = self.do_read_edge_events(events, max);
+        if res.is_err() {
+            events.truncate(len);
+        }
+        res
+    }
+    fn do_read_edge_events(&self, events: &mut Vec<EdgeEvent>, max: usize) -> Result<usize> {
+        // bbuf is statically sized for a batch of the larger of the v1/v2 events,
+        // so it can be placed on the stack.
+        let mut bbuf = [0_u64; EDGE_EVENT_BATCH_SIZE * mem::size_of::<uapi::LineEdgeEvent>() / 8];
+        let event_u64_size = self.edge_event_u64_size();
+        let mut count = 0;
+        while count < max {
+            let batch = min(max - count, EDGE_EVENT_BATCH_SIZE);
+            let n = self.read_edge_events_with(&mut bbuf[..batch * event_u64_size], |evt| {
+                events.push(evt)
+            })?;
+            count += n;
+            if n < batch || !self.has_edge_event()? {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
     /// Create an edge event buffer.
     ///
     /// * `capacity` - The number of events that can be buffered.
-    pub fn new_edge_event_buffer(&self, capacity: usize) -> EdgeEventBuffer {
+    pub fn new_edge_event_buffer(&self, capacity: usize) -> EdgeEventBuffer<'_> {
         EdgeEventBuffer::new(self, self.edge_event_size(), capacity)
     }
 
+    /// Deliver a POSIX realtime signal to the process for each edge event.
+    ///
+    /// This is an alternative to polling the request for edge events, intended
+    /// for signal driven applications.
+    ///
+    /// The offset of the line triggering the event is provided in the `si_value`
+    /// of the signal.  The events themselves are consumed by the returned [`EdgeSignaller`],
+    /// and signals are delivered until it is dropped.
+    ///
+    /// * `signo` - The realtime signal to deliver, in the range `SIGRTMIN..=SIGRTMAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// // signal handler for SIGRTMIN+1 is installed elsewhere...
+    /// let signaller = req.signal_edges(libc::SIGRTMIN() + 1)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn signal_edges(&self, signo: i32) -> Result<EdgeSignaller<'_>> {
+        EdgeSignaller::new(self, signo)
+    }
+
+    /// Distribute the edge events to multiple subscribers.
+    ///
+    /// Each subscriber selects the events it receives using an [`EdgeFilter`],
+    /// such as only rising edges on a particular line, so consumers with differing
+    /// interests can share the one request with edge detection enabled for all of them.
+    ///
+    /// The events are consumed by the returned [`EdgeFanout`], and delivered until
+    /// it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::{EdgeDetection, EdgeKind};
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// let fanout = req.fanout_edges()?;
+    /// let presses = fanout.subscribe_kind(EdgeKind::Falling);
+    /// let releases = fanout.subscribe_kind(EdgeKind::Rising);
+    /// std::thread::spawn(move || {
+    ///     for evt in releases {
+    ///         println!("released: {:?}", evt);
+    ///     }
+    /// });
+    /// for evt in presses {
+    ///     println!("pressed: {:?}", evt);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fanout_edges(&self) -> Result<EdgeFanout<'_>> {
+        EdgeFanout::new(self)
+    }
+
+    /// Forward the edge events into a channel.
+    ///
+    /// The events are read by a background thread, managed by the returned
+    /// [`EdgeForwarder`], and sent to the channel until the forwarder is dropped,
+    /// or the receiving half of the channel is dropped.
+    ///
+    /// The sender may be a `std::sync::mpsc` [`Sender`](std::sync::mpsc::Sender) or
+    /// [`SyncSender`](std::sync::mpsc::SyncSender), or any other channel implementing
+    /// [`EdgeEventSink`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// let (tx, rx) = std::sync::mpsc::sync_channel(64);
+    /// let forwarder = req.events_to_channel(tx)?;
+    /// for evt in rx.iter().take(10) {
+    ///     println!("{:?}", evt);
+    /// }
+    /// forwarder.stop()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn events_to_channel<S: EdgeEventSink>(&self, sender: S) -> Result<EdgeForwarder<'_>> {
+        EdgeForwarder::new(self, sender)
+    }
+
+    /// Queue the edge events for a consumer that may not keep up with them.
+    ///
+    /// The events are read from the kernel by a background thread, managed by the
+    /// returned [`RateLimited`], into a queue holding up to `capacity` events.
+    /// If the queue fills, the `policy` determines which events are discarded,
+    /// and the discarded events are counted, so a slow consumer can detect and
+    /// decide how to handle event loss rather than have the kernel buffer
+    /// overflow silently.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # use gpiocdev::request::OverflowPolicy;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// let events = req.rate_limited(16, OverflowPolicy::DropNewest)?;
+    /// while let Some(evt) = events.recv() {
+    ///     println!("{:?} ({} dropped)", evt, events.dropped());
+    ///     std::thread::sleep(std::time::Duration::from_millis(100));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rate_limited(&self, capacity: usize, policy: OverflowPolicy) -> Result<RateLimited<'_>> {
+        RateLimited::new(self, capacity, policy)
+    }
+
     // External buffer/slice methods.
 
     /// Read edge events from the kernel into a user space `[u64]` slice.
@@ -582,9 +1751,135 @@ impl Request {
     ///
     /// [`edge_event_size`]: #method.edge_event_size
     pub fn read_edge_events_into_slice(&self, buf: &mut [u64]) -> Result<usize> {
+        trace_span!(
+            "read_edge_events",
+            chip = %self.chip_path().display(),
+            offsets = ?self.lines.offsets,
+            abiv = %self.abi_version(),
+        );
         gpiocdev_uapi::read_event(&self.f, buf).map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))
     }
 
+    // Read the available edge events into the buffer, with a single read, and pass
+    // each event to the handler.
+    //
+    // Returns the number of events read.
+    pub(crate) fn read_edge_events_with<F: FnMut(EdgeEvent)>(
+        &self,
+        buf: &mut [u64],
+        mut handler: F,
+    ) -> Result<usize> {
+        trace_span!(
+            "read_edge_events",
+            chip = %self.chip_path().display(),
+            offsets = ?self.lines.offsets,
+            abiv = %self.abi_version(),
+        );
+        // SAFETY: the memory of a u64 slice is a valid u8 slice of 8 times the length.
+        let buf =
+            unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, buf.len() * 8) };
+        self.do_read_edge_events_with(buf, &mut handler)
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn do_read_edge_events_with<F: FnMut(EdgeEvent)>(
+        &self,
+        buf: &mut [u8],
+        handler: &mut F,
+    ) -> Result<usize> {
+        match self.abiv {
+            AbiVersion::V1 => self.read_v1_edge_events_with(buf, handler),
+            AbiVersion::V2 => self.read_v2_edge_events_with(buf, handler),
+        }
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn do_read_edge_events_with<F: FnMut(EdgeEvent)>(
+        &self,
+        buf: &mut [u8],
+        handler: &mut F,
+    ) -> Result<usize> {
+        self.read_v1_edge_events_with(buf, handler)
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn do_read_edge_events_with<F: FnMut(EdgeEvent)>(
+        &self,
+        buf: &mut [u8],
+        handler: &mut F,
+    ) -> Result<usize> {
+        self.read_v2_edge_events_with(buf, handler)
+    }
+    #[cfg(feature = "uapi_v1")]
+    fn read_v1_edge_events_with<F: FnMut(EdgeEvent)>(
+        &self,
+        buf: &mut [u8],
+        handler: &mut F,
+    ) -> Result<usize> {
+        let mut count = 0;
+        for le in v1::read_events(&self.f, buf) {
+            let mut ee = EdgeEvent::from(le.map_err(read_events_error)?);
+            // populate offset for v1
+            ee.offset = self.lines.offsets[0]; // there can be only one
+            handler(ee);
+            count += 1;
+        }
+        Ok(count)
+    }
+    #[cfg(feature = "uapi_v2")]
+    fn read_v2_edge_events_with<F: FnMut(EdgeEvent)>(
+        &self,
+        buf: &mut [u8],
+        handler: &mut F,
+    ) -> Result<usize> {
+        let mut count = 0;
+        for le in v2::read_events(&self.f, buf) {
+            handler(EdgeEvent::from(le.map_err(read_events_error)?));
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Read edge events from the kernel into a user space `[u64]` slice, and return
+    /// views of the events read.
+    ///
+    /// As per [`read_edge_events_into_slice`], but the returned [`EventSlice`] decodes
+    /// the events in place, so reading a burst of events requires a single read and
+    /// no copying of the events, unless converted into [`EdgeEvent`]s.
+    ///
+    /// This function will block if no events are available to read.
+    ///
+    /// * `buf` - The slice to contain the raw events.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[17, 18])
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    /// let mut buf = vec![0_u64; req.edge_event_u64_size() * 64];
+    /// loop {
+    ///     for evt in req.read_edge_events_into(&mut buf)? {
+    ///         let evt = evt?;
+    ///         println!("{} {:?}", evt.offset(), evt.kind());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`read_edge_events_into_slice`]: #method.read_edge_events_into_slice
+    pub fn read_edge_events_into<'b>(&self, buf: &'b mut [u64]) -> Result<EventSlice<'b>> {
+        let n = self.read_edge_events_into_slice(buf)?;
+        Ok(EventSlice::new(
+            &buf[..n],
+            self.edge_event_u64_size(),
+            self.lines.offsets[0],
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            self.abiv,
+        ))
+    }
+
     /// Read an edge event from a `[u64]` slice.
     ///
     /// This is a helper function for the special case where the user prefers to
@@ -609,7 +1904,7 @@ impl Request {
                         .map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
                 );
                 // populate offset for v1
-                ee.offset = self.offsets[0];
+                ee.offset = self.lines.offsets[0];
                 ee
             }
             AbiVersion::V2 => EdgeEvent::from(
@@ -624,7 +1919,7 @@ impl Request {
             v1::LineEdgeEvent::from_slice(buf).map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
         );
         // populate offset for v1
-        ee.offset = self.offsets[0]; // there can be only one
+        ee.offset = self.lines.offsets[0]; // there can be only one
         Ok(ee)
     }
     #[cfg(not(feature = "uapi_v1"))]
@@ -662,6 +1957,37 @@ impl Request {
     fn do_edge_event_size(&self) -> usize {
         mem::size_of::<uapi::LineEdgeEvent>()
     }
+
+    // A request sharing the underlying request file.
+    pub(crate) fn try_clone(&self) -> Result<Request> {
+        Ok(Request {
+            f: ManuallyDrop::new(self.f.try_clone()?),
+            released: false,
+            lines: self.lines.clone(),
+            cfg: self.cfg.clone(),
+            last_set: self.last_set.clone(),
+            user_event_buffer_size: self.user_event_buffer_size,
+            kernel_event_buffer_size: self.kernel_event_buffer_size,
+            // only the original request applies the policy
+            drop_policy: DropPolicy::LeaveAsIs,
+            value_source: self.value_source,
+            #[cfg(feature = "uapi_v1")]
+            fallback: self.fallback.clone(),
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            abiv: self.abiv,
+        })
+    }
+}
+impl Drop for Request {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        // best effort, as there is no way to report the error
+        let _ = self.drop_policy.apply(self);
+        // SAFETY: the file is not used after being dropped.
+        unsafe { ManuallyDrop::drop(&mut self.f) };
+    }
 }
 impl AsFd for Request {
     #[inline]
@@ -676,6 +2002,115 @@ impl AsRawFd for Request {
     }
 }
 
+/// Allows the request to be registered directly with a [mio](https://crates.io/crates/mio) `Poll`.
+///
+/// The request is readable when it has edge events available.
+/// As mio is edge triggered, all available events should be read
+/// each time the request is reported as readable.
+#[cfg(feature = "mio")]
+impl mio::event::Source for Request {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.f.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.f.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> std::io::Result<()> {
+        mio::unix::SourceFd(&self.f.as_raw_fd()).deregister(registry)
+    }
+}
+
+impl From<Request> for OwnedFd {
+    /// Take ownership of the request file descriptor.
+    ///
+    /// The lines remain requested, so the drop policy is not applied.
+    fn from(mut req: Request) -> OwnedFd {
+        req.released = true;
+        // SAFETY: the file is taken exactly once, and as the request is released
+        // it is not dropped with the remaining fields.
+        OwnedFd::from(unsafe { ManuallyDrop::take(&mut req.f) })
+    }
+}
+
+// The maximum number of events read by a single read in read_edge_events.
+const EDGE_EVENT_BATCH_SIZE: usize = 64;
+
+// Map an error from the uAPI read_events iterator to the failing call.
+//
+// OS errors are from reading the request, while the remainder are from decoding
+// the events read.
+fn read_events_error(e: gpiocdev_uapi::Error) -> Error {
+    match e {
+        gpiocdev_uapi::Error::Os(_) => Error::Uapi(UapiCall::ReadEvent, e),
+        _ => Error::Uapi(UapiCall::LEEFromBuf, e),
+    }
+}
+
+// Settle periods shorter than this are busy-waited, as sleeping overshoots them.
+const SPIN_SETTLE_LIMIT: Duration = Duration::from_micros(100);
+
+// Wait for outputs to settle before reading inputs.
+pub(crate) fn wait_settle(settle: Duration) {
+    if settle.is_zero() {
+        return;
+    }
+    if settle >= SPIN_SETTLE_LIMIT {
+        std::thread::sleep(settle);
+        return;
+    }
+    let deadline = Instant::now() + settle;
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+// Determine the ABI version used to create a line request.
+//
+// The ioctls for each version are rejected by the file descriptors of the other.
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+fn detect_abi_version(f: &File) -> AbiVersion {
+    // read the first line, which is present in every request
+    let mut vals = v2::LineValues {
+        mask: 1,
+        ..Default::default()
+    };
+    if v2::get_line_values(f, &mut vals).is_ok() {
+        AbiVersion::V2
+    } else {
+        AbiVersion::V1
+    }
+}
+
+// The maximum size of the kernel event buffer, in events.
+const KERNEL_EVENT_BUFFER_SIZE_MAX: u32 = (NUM_LINES_MAX * 16) as u32;
+
+// The size of the kernel event buffer allocated for a request.
+fn kernel_event_buffer_size(abiv: AbiVersion, requested: u32, num_lines: usize) -> u32 {
+    match abiv {
+        AbiVersion::V1 => 16,
+        AbiVersion::V2 => {
+            let size = if requested == 0 {
+                num_lines as u32 * 16
+            } else {
+                requested
+            };
+            min(size, KERNEL_EVENT_BUFFER_SIZE_MAX).next_power_of_two()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Request;
@@ -711,4 +2146,22 @@ mod tests {
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
         assert_eq!(b.abiv, None);
     }
+
+    #[test]
+    fn kernel_event_buffer_size() {
+        use super::{kernel_event_buffer_size, AbiVersion};
+
+        // default
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V2, 0, 1), 16);
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V2, 0, 3), 64);
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V2, 0, 64), 1024);
+        // rounded up
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V2, 42, 1), 64);
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V2, 2, 1), 2);
+        // limited
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V2, 5000, 1), 1024);
+        // fixed
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V1, 0, 1), 16);
+        assert_eq!(kernel_event_buffer_size(AbiVersion::V1, 42, 3), 16);
+    }
 }
@@ -3,10 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 use crate::chip::Chip;
 use crate::line::{self, Bias, Direction, Drive, EdgeDetection, EventClock, Offset, Value, Values};
-use crate::request::{Config, Request};
+use crate::request::UapiRequestDump;
+use crate::request::KERNEL_EVENT_BUFFER_SIZE_MAX;
 #[cfg(feature = "uapi_v1")]
-use crate::AbiVersion;
-use crate::{Error, Result, UapiCall};
+use crate::request::{fallback, Degradation, FallbackReport};
+use crate::request::{Config, DropPolicy, LineMap, Request, RetryPolicy, ValueSource, WideRequest};
+use crate::{AbiVersion, Error, Result, UapiCall};
 #[cfg(feature = "uapi_v1")]
 use gpiocdev_uapi::v1;
 #[cfg(feature = "uapi_v2")]
@@ -15,9 +17,10 @@ use gpiocdev_uapi::NUM_LINES_MAX;
 use std::cmp::max;
 use std::collections::HashMap;
 use std::fs::File;
+use std::mem::ManuallyDrop;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 /// A builder of line requests.
 ///
@@ -83,7 +86,13 @@ pub struct Builder {
     pub(super) consumer: String,
     pub(super) kernel_event_buffer_size: u32,
     pub(super) user_event_buffer_size: usize,
+    pub(super) drop_policy: DropPolicy,
+    pub(super) retry_policy: RetryPolicy,
+    pub(super) value_source: ValueSource,
     err: Option<Error>,
+    /// Drop settings not supported by uAPI v1, rather than failing the request.
+    #[cfg(feature = "uapi_v1")]
+    pub(super) v1_fallback: bool,
     /// The ABI version used to create the request, and so determines how to decode events.
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     pub(super) abiv: Option<AbiVersion>,
@@ -110,9 +119,19 @@ impl Builder {
         if self.cfg.chip.as_os_str().is_empty() {
             return Err(Error::InvalidArgument("No chip specified.".to_string()));
         }
+        trace_span!("request", chip = %self.cfg.chip.display(), offsets = ?self.cfg.offsets);
         let chip = Chip::from_path(&self.cfg.chip)?;
         self.cfg.offsets.sort_unstable();
-        self.do_request(&chip).map(|f| self.to_request(f))
+        let mut attempt = 1;
+        loop {
+            match self.do_request(&chip) {
+                Err(e) if is_busy(&e) && attempt < self.retry_policy.max_attempts => {
+                    std::thread::sleep(self.retry_policy.delay(attempt));
+                    attempt += 1;
+                }
+                res => return res.map(|f| self.to_request(f)),
+            }
+        }
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_request(&mut self, chip: &Chip) -> Result<File> {
@@ -150,16 +169,141 @@ impl Builder {
     }
 
     fn to_request(&self, f: File) -> Request {
+        #[cfg(feature = "uapi_v1")]
+        let (cfg, fallback) = self.fallback_config();
+        #[cfg(not(feature = "uapi_v1"))]
+        let cfg = &self.cfg;
         Request {
-            f,
-            offsets: self.cfg.offsets.clone(),
-            cfg: Arc::new(RwLock::new(self.cfg.clone())),
+            f: ManuallyDrop::new(f),
+            released: false,
+            lines: LineMap::new(self.cfg.offsets.clone()),
+            cfg: Arc::new(RwLock::new(cfg.effective())),
+            last_set: Arc::new(Mutex::new(vec![None; self.cfg.offsets.len()])),
             user_event_buffer_size: max(self.user_event_buffer_size, 1),
+            kernel_event_buffer_size: self.kernel_event_buffer_size,
+            drop_policy: self.drop_policy.clone(),
+            value_source: self.value_source,
+            #[cfg(feature = "uapi_v1")]
+            fallback,
             #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
             abiv: self.abiv.unwrap(),
         }
     }
 
+    // The config as requested, and the settings dropped from it to fit uAPI v1.
+    #[cfg(feature = "uapi_v1")]
+    fn fallback_config(&self) -> (Config, FallbackReport) {
+        #[cfg(feature = "uapi_v2")]
+        let v1 = self.abiv == Some(AbiVersion::V1);
+        #[cfg(not(feature = "uapi_v2"))]
+        let v1 = true;
+        if !self.v1_fallback || !v1 {
+            return (self.cfg.clone(), FallbackReport::default());
+        }
+        let mut b = self.clone();
+        let report = b.degrade_to_v1();
+        report.trace();
+        (b.cfg, report)
+    }
+
+    // Drop the settings not supported by uAPI v1.
+    #[cfg(feature = "uapi_v1")]
+    fn degrade_to_v1(&mut self) -> FallbackReport {
+        let mut report = fallback::degrade(&mut self.cfg);
+        if self.kernel_event_buffer_size != 0 {
+            report.degradations.insert(
+                0,
+                Degradation::KernelEventBufferSize(self.kernel_event_buffer_size),
+            );
+            self.kernel_event_buffer_size = 0;
+        }
+        report
+    }
+
+    /// Perform the request once the lines are free.
+    ///
+    /// If any of the lines are in use then waits for them to be released before
+    /// requesting them.
+    /// As another consumer may request a line as soon as it is released, the
+    /// request is retried until it succeeds or the timeout expires.
+    ///
+    /// This allows lines to be handed over between cooperating processes.
+    ///
+    /// Returns the busy error if the lines are not free within the timeout.
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(17)
+    ///     .as_output(Value::Active)
+    ///     .claim_when_free(Duration::from_secs(5))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn claim_when_free(&mut self, timeout: Duration) -> Result<Request> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let err = match self.request() {
+                Err(e) if is_busy(&e) => e,
+                res => return res,
+            };
+            let chip = Chip::from_path(&self.cfg.chip)?;
+            for offset in &self.cfg.offsets {
+                let remaining = match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Err(err);
+                        }
+                        deadline - now
+                    }
+                    None => timeout,
+                };
+                if !chip.wait_for_line_free(*offset, remaining)? {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Perform the request, splitting the lines across several kernel requests
+    /// if there are more than the kernel supports in a single request.
+    ///
+    /// The lines are split in offset order, with each kernel request containing
+    /// up to 64 lines.
+    ///
+    /// If any of the kernel requests fail then any lines already requested are released.
+    pub fn request_wide(&mut self) -> Result<WideRequest> {
+        if let Some(e) = &self.err {
+            return Err(e.clone());
+        }
+        if self.cfg.num_lines() == 0 {
+            return Err(Error::InvalidArgument("No lines specified.".to_string()));
+        }
+        self.cfg.offsets.sort_unstable();
+        let mut reqs = Vec::new();
+        for chunk in self.cfg.offsets.chunks(NUM_LINES_MAX) {
+            let mut builder = self.clone();
+            let others: Vec<Offset> = self
+                .cfg
+                .offsets
+                .iter()
+                .filter(|o| !chunk.contains(o))
+                .copied()
+                .collect();
+            builder.cfg.without_lines(&others);
+            reqs.push(builder.request()?);
+        }
+        Ok(WideRequest::new(reqs))
+    }
+
     /// Replace the request configuration with the new one provided.
     pub fn with_config(&mut self, cfg: Config) -> &mut Self {
         self.cfg = cfg;
@@ -223,6 +367,44 @@ impl Builder {
         self
     }
 
+    /// Size the kernel event buffer to hold the events expected while the application
+    /// is not servicing the request.
+    ///
+    /// * `events_per_sec` - the expected peak event rate, across all lines.
+    /// * `max_latency` - the maximum period the application may take to read events.
+    ///
+    /// The buffer is sized to hold the events arriving at the given rate for the given
+    /// latency, rounded up.  The kernel limits the buffer to 1024 events, so rates
+    /// and latencies beyond that should be addressed by servicing the request more
+    /// promptly.  The size allocated is reported by [`Request::kernel_event_buffer_size`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::EdgeDetection;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// // a 20kHz encoder serviced at least every 10ms requires 200 events
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[17, 18])
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .with_kernel_event_buffer_for_rate(20_000, Duration::from_millis(10))
+    ///     .request()?;
+    /// assert_eq!(req.kernel_event_buffer_size(), 256);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_kernel_event_buffer_for_rate(
+        &mut self,
+        events_per_sec: u32,
+        max_latency: Duration,
+    ) -> &mut Self {
+        self.kernel_event_buffer_size = events_for_rate(events_per_sec, max_latency);
+        self
+    }
+
     /// Set the event buffer size for edge events buffered in user space.
     ///
     /// This method is only required in unusual circumstances.
@@ -240,6 +422,92 @@ impl Builder {
         self
     }
 
+    /// Set the action performed on the requested lines when the request is dropped.
+    ///
+    /// This provides a safe state for outputs, such as those driving heaters or motors,
+    /// when the program exits or panics.
+    ///
+    /// The default is [`DropPolicy::LeaveAsIs`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// # use gpiocdev::line::Value;
+    /// # use gpiocdev::request::{DropPolicy, Request};
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(17)
+    ///     .as_output(Value::Active)
+    ///     .on_drop(DropPolicy::RevertToInput)
+    ///     .request()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_drop(&mut self, policy: DropPolicy) -> &mut Self {
+        self.drop_policy = policy;
+        self
+    }
+
+    /// Set the source of the values returned for output lines by [`Request::values`]
+    /// and [`Request::value`].
+    ///
+    /// For drivers that cannot read back the physical level of output lines,
+    /// [`ValueSource::LastSet`] returns the values last set by the request instead.
+    ///
+    /// The default is [`ValueSource::Physical`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// # use gpiocdev::line::Value;
+    /// # use gpiocdev::request::{Request, ValueSource};
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(17)
+    ///     .as_output(Value::Active)
+    ///     .with_value_source(ValueSource::LastSet)
+    ///     .request()?;
+    /// assert_eq!(req.value(17)?, Value::Active);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_value_source(&mut self, source: ValueSource) -> &mut Self {
+        self.value_source = source;
+        self
+    }
+
+    /// Set how the request is retried if the lines are busy.
+    ///
+    /// Only failures due to the lines being in use are retried.
+    ///
+    /// The default is to not retry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// # use gpiocdev::line::Value;
+    /// # use gpiocdev::request::{Request, RetryPolicy};
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(17)
+    ///     .as_output(Value::Active)
+    ///     .retry_on_busy(RetryPolicy::exponential(5, Duration::from_millis(10)))
+    ///     .request()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn retry_on_busy(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = policy;
+        self
+    }
+
     /// Select the ABI version to use when requesting the lines and for subsequent operations.
     ///
     /// This is not normally required - the library will determine the available ABI versions
@@ -258,6 +526,40 @@ impl Builder {
         self
     }
 
+    /// Drop settings that are not supported by uAPI v1, rather than failing the request,
+    /// if the request is made using uAPI v1.
+    ///
+    /// The debounce period, event clock and kernel event buffer size are dropped.
+    /// Lines that still have differing configurations once those are dropped are not
+    /// approximated, and the request fails as usual.
+    ///
+    /// The dropped settings are available from [`Request::fallback_report`], and are
+    /// logged if the `tracing` feature is enabled.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// # use gpiocdev::request::Request;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(5)
+    ///     .with_debounce_period(Duration::from_millis(5))
+    ///     .with_v1_fallback()
+    ///     .request()?;
+    /// for d in &req.fallback_report().degradations {
+    ///     eprintln!("{}", d);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "uapi_v1")]
+    pub fn with_v1_fallback(&mut self) -> &mut Self {
+        self.v1_fallback = true;
+        self
+    }
+
     /// Set the chip from which to request lines.
     ///
     /// This applies to all lines in the request. It is not possible to request lines
@@ -406,6 +708,38 @@ impl Builder {
         self
     }
 
+    /// Add a line, identified by a [`LineRef`], to the request.
+    ///
+    /// The line is resolved immediately. If it cannot be found, or is not on the same
+    /// chip as any existing lines in the request, then the line is ignored and an error
+    /// returned when [`request`](#method.request) is called.
+    ///
+    /// Note that all configuration mutators applied subsequently only apply to this line.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::Value;
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .with_line_ref(&"gpiochip0:5".parse()?)
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`LineRef`]: crate::line::LineRef
+    pub fn with_line_ref(&mut self, line: &crate::line::LineRef) -> &mut Self {
+        match line.resolve() {
+            Ok(found) => self.with_found_line(&found),
+            Err(e) => {
+                self.err = Some(e);
+                self
+            }
+        }
+    }
+
     /// Add a set of found lines to the request.
     ///
     /// The lines must be on the same chip as any existing lines in the request, else the line is
@@ -457,6 +791,46 @@ impl Builder {
         self
     }
 
+    /// Add a line to the request and configure it using a closure.
+    ///
+    /// The closure is passed the request config with only the line selected.
+    /// Once the closure returns the previous selection is restored, so subsequent
+    /// mutators are unaffected.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::line::{EdgeDetection, Value};
+    /// # fn main() -> Result<()> {
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line_config(17, |c| c.as_output(Value::Inactive))
+    ///     .with_line_config(22, |c| c.as_input().with_edge_detection(EdgeDetection::BothEdges))
+    ///     .request()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_line_config<F>(&mut self, offset: Offset, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Config) -> &mut Config,
+    {
+        self.cfg.with_line_config(offset, f);
+        self
+    }
+
+    /// Add a set of lines to the request and configure them using a closure.
+    ///
+    /// The closure is passed the request config with only the lines selected.
+    /// Once the closure returns the previous selection is restored, so subsequent
+    /// mutators are unaffected.
+    pub fn with_lines_config<F>(&mut self, offsets: &[Offset], f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Config) -> &mut Config,
+    {
+        self.cfg.with_lines_config(offsets, f);
+        self
+    }
+
     /// Add a set of output lines, with values, to the selected lines.
     ///
     /// This is a short form of [`with_line(offset)`](#method.with_line) and
@@ -514,8 +888,33 @@ impl Builder {
         self.to_v2()
     }
 
+    // The uAPI request for a particular ABI version, without requesting the lines.
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    pub(super) fn to_uapi_dump(&self, abiv: AbiVersion) -> Result<UapiRequestDump> {
+        let mut b = self.clone();
+        b.abiv = Some(abiv);
+        b.to_uapi().map(UapiRequestDump)
+    }
+    #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
+    pub(super) fn to_uapi_dump(&self, abiv: AbiVersion) -> Result<UapiRequestDump> {
+        #[cfg(not(feature = "uapi_v2"))]
+        let built = AbiVersion::V1;
+        #[cfg(not(feature = "uapi_v1"))]
+        let built = AbiVersion::V2;
+        if abiv != built {
+            return Err(Error::UnsupportedAbi(abiv, crate::AbiSupportKind::Build));
+        }
+        self.to_uapi().map(UapiRequestDump)
+    }
+
     #[cfg(feature = "uapi_v1")]
     fn to_v1(&self) -> Result<UapiRequest> {
+        if self.v1_fallback {
+            let mut b = self.clone();
+            b.v1_fallback = false;
+            b.degrade_to_v1();
+            return b.to_v1();
+        }
         if self.kernel_event_buffer_size != 0 {
             return Err(Error::AbiLimitation(
                 AbiVersion::V1,
@@ -584,9 +983,9 @@ impl Builder {
     }
 }
 
-#[cfg_attr(test, derive(Debug))]
+#[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
-enum UapiRequest {
+pub(super) enum UapiRequest {
     #[cfg(feature = "uapi_v1")]
     Handle(v1::HandleRequest),
 
@@ -597,6 +996,20 @@ enum UapiRequest {
     Line(v2::LineRequest),
 }
 
+// The number of events arriving at a rate over a period, rounded up.
+//
+// The kernel requires a buffer of at least two events, and limits it to
+// KERNEL_EVENT_BUFFER_SIZE_MAX events.
+fn events_for_rate(events_per_sec: u32, period: Duration) -> u32 {
+    let events = (events_per_sec as u128 * period.as_nanos() + 999_999_999) / 1_000_000_000;
+    events.clamp(2, KERNEL_EVENT_BUFFER_SIZE_MAX as u128) as u32
+}
+
+// Returns true if the request failed as lines are already in use.
+fn is_busy(e: &Error) -> bool {
+    e.kind() == crate::ErrorKind::Busy
+}
+
 fn default_consumer() -> String {
     format!("gpiocdev-p{}", std::process::id())
 }
@@ -687,6 +1100,30 @@ mod tests {
         b.with_kernel_event_buffer_size(42);
         assert_eq!(b.kernel_event_buffer_size, 42);
     }
+
+    #[test]
+    fn with_kernel_event_buffer_for_rate() {
+        let mut b = Builder::default();
+        b.with_kernel_event_buffer_for_rate(20_000, Duration::from_millis(10));
+        assert_eq!(b.kernel_event_buffer_size, 200);
+
+        // rounded up
+        b.with_kernel_event_buffer_for_rate(1000, Duration::from_micros(1500));
+        assert_eq!(b.kernel_event_buffer_size, 2);
+        b.with_kernel_event_buffer_for_rate(3000, Duration::from_micros(1001));
+        assert_eq!(b.kernel_event_buffer_size, 4);
+
+        // minimum
+        b.with_kernel_event_buffer_for_rate(0, Duration::from_millis(10));
+        assert_eq!(b.kernel_event_buffer_size, 2);
+
+        // maximum
+        b.with_kernel_event_buffer_for_rate(200_000, Duration::from_millis(10));
+        assert_eq!(b.kernel_event_buffer_size, 1024);
+        b.with_kernel_event_buffer_for_rate(u32::MAX, Duration::from_secs(10));
+        assert_eq!(b.kernel_event_buffer_size, 1024);
+    }
+
     #[test]
     fn with_user_event_buffer_size() {
         let mut b = Builder::default();
@@ -696,6 +1133,30 @@ mod tests {
         assert_eq!(b.user_event_buffer_size, 67);
     }
 
+    #[test]
+    fn on_drop() {
+        let mut b = Builder::default();
+        assert_eq!(b.drop_policy, DropPolicy::LeaveAsIs);
+
+        b.on_drop(DropPolicy::RevertToInput);
+        assert_eq!(b.drop_policy, DropPolicy::RevertToInput);
+
+        let mut values = Values::default();
+        values.set(3, Value::Inactive);
+        b.on_drop(DropPolicy::SetValues(values.clone()));
+        assert_eq!(b.drop_policy, DropPolicy::SetValues(values));
+    }
+
+    #[test]
+    fn retry_on_busy() {
+        let mut b = Builder::default();
+        assert_eq!(b.retry_policy, RetryPolicy::default());
+
+        let policy = RetryPolicy::fixed(3, Duration::from_millis(5));
+        b.retry_on_busy(policy.clone());
+        assert_eq!(b.retry_policy, policy);
+    }
+
     #[test]
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn using_abi_version() {
@@ -924,6 +1385,19 @@ mod tests {
         assert_eq!(b.cfg.offsets, &[1, 0, 7]);
     }
 
+    #[test]
+    fn with_lines_config() {
+        let mut b = Builder::default();
+        b.with_lines_config(&[3, 1], |c| c.as_output(Active))
+            .with_line_config(5, |c| c.as_input().with_bias(PullUp));
+        assert_eq!(b.cfg.offsets, &[3, 1, 5]);
+        assert!(b.cfg.selected.is_empty());
+        assert_eq!(b.cfg.lcfg.get(&1).unwrap().direction, Some(Output));
+        assert_eq!(b.cfg.lcfg.get(&3).unwrap().value, Some(Active));
+        assert_eq!(b.cfg.lcfg.get(&5).unwrap().direction, Some(Input));
+        assert_eq!(b.cfg.lcfg.get(&5).unwrap().bias, Some(PullUp));
+    }
+
     fn sorted(s: &[u32]) -> Vec<u32> {
         let mut x = s.to_vec();
         x.sort();
@@ -944,7 +1418,7 @@ mod tests {
         assert_eq!(sorted(&b.cfg.offsets), &[3, 5]);
         assert!(b.cfg.lcfg.contains_key(&3));
         assert_eq!(b.cfg.lcfg.get(&3).unwrap().direction, Some(Output));
-        assert_eq!(b.cfg.lcfg.get(&3).unwrap().value, Some(Value::Active));
+        assert_eq!(b.cfg.lcfg.get(&3).unwrap().value, Some(Active));
         assert!(b.cfg.lcfg.contains_key(&5));
         assert_eq!(b.cfg.lcfg.get(&5).unwrap().direction, Some(Output));
         assert_eq!(b.cfg.lcfg.get(&5).unwrap().value, Some(Value::Inactive));
@@ -1129,6 +1603,52 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn with_v1_fallback() {
+        let mut b = Builder::default();
+        b.with_lines(&[3, 1])
+            .with_edge_detection(RisingEdge)
+            .with_debounce_period(Duration::from_millis(4))
+            .with_event_clock(Realtime)
+            .with_line(1)
+            .with_debounce_period(Duration::from_millis(2))
+            .with_kernel_event_buffer_size(42);
+        assert!(b.to_v1().is_err());
+
+        b.with_v1_fallback();
+        assert!(b.v1_fallback);
+        // lines 1 and 3 share a config once the debounce periods are dropped,
+        // but v1 edge detection is limited to a single line
+        assert_eq!(
+            b.to_v1().unwrap_err().to_string(),
+            "uAPI ABI v1 only supports edge detection on single line requests."
+        );
+        b.without_lines(&[1]);
+        if let UapiRequest::Event(er) = b.to_v1().unwrap() {
+            assert_eq!(er.offset, 3);
+            assert!(er.eventflags.contains(v1::EventRequestFlags::RISING_EDGE));
+        } else {
+            panic!("not an event request");
+        }
+
+        let report = b.clone().degrade_to_v1();
+        assert_eq!(
+            report.degradations,
+            [
+                Degradation::KernelEventBufferSize(42),
+                Degradation::Debounce {
+                    offset: 3,
+                    period: Duration::from_millis(4)
+                },
+                Degradation::EventClock {
+                    offset: 3,
+                    clock: Realtime
+                },
+            ]
+        );
+    }
+
     #[test]
     #[allow(irrefutable_let_patterns)]
     #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+use super::{validate, ConfigDelta, Diagnostic, Request, UapiRequestDump};
+use crate::chip::{Capabilities, Chip};
 use crate::line::{
     self, Bias, Direction, Drive, EdgeDetection, EventClock, Offset, Offsets, Value, Values,
 };
@@ -72,7 +74,18 @@ impl Config {
     /// Other fields are not updated as they are effectively immutable for the
     /// lifetime of the associated request.
     pub(super) fn update(&mut self, cfg: Config) {
-        self.lcfg = cfg.lcfg;
+        self.lcfg = cfg.effective().lcfg;
+    }
+
+    /// Return the configuration as applied by the kernel.
+    ///
+    /// Line settings that are not passed to the kernel are dropped.
+    pub(super) fn effective(&self) -> Config {
+        let mut cfg = self.clone();
+        for lc in cfg.lcfg.values_mut() {
+            *lc = lc.effective();
+        }
+        cfg
     }
 
     /// Set the chip from which to request lines.
@@ -360,6 +373,48 @@ impl Config {
         self
     }
 
+    /// Add a line to the config and configure it using a closure.
+    ///
+    /// The closure is passed the config with only the line selected.
+    /// Once the closure returns the previous selection is restored, so subsequent
+    /// mutators are unaffected.
+    ///
+    /// # Examples
+    /// ```
+    ///    use gpiocdev::line::{EdgeDetection, Value};
+    ///    use gpiocdev::request::Config;
+    ///
+    ///    let mut cfg = Config::default();
+    ///    cfg.with_line_config(3, |c| c.as_output(Value::Active))
+    ///        .with_line_config(5, |c| c.as_input().with_edge_detection(EdgeDetection::BothEdges));
+    /// ```
+    pub fn with_line_config<F>(&mut self, offset: Offset, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Config) -> &mut Config,
+    {
+        self.with_lines_config(&[offset], f)
+    }
+
+    /// Add a set of lines to the config and configure them using a closure.
+    ///
+    /// The closure is passed the config with only the lines selected.
+    /// Once the closure returns the previous selection is restored, so subsequent
+    /// mutators are unaffected.
+    pub fn with_lines_config<F>(&mut self, offsets: &[Offset], f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Config) -> &mut Config,
+    {
+        let selected = std::mem::take(&mut self.selected);
+        self.with_lines(offsets);
+        f(self);
+        // lines may have been removed by the closure
+        self.selected = selected
+            .into_iter()
+            .filter(|offset| self.lcfg.contains_key(offset))
+            .collect();
+        self
+    }
+
     /// Add a set of output lines, with values, to the config.
     ///
     /// Note that all configuration mutators applied subsequently only
@@ -398,7 +453,60 @@ impl Config {
         self
     }
 
-    fn selected_iter(&mut self) -> SelectedIterator {
+    /// Create a config that reproduces the current configuration of the lines
+    /// in a request.
+    ///
+    /// The configuration is read from the line info reported by the kernel,
+    /// and the values of output lines are read from the lines, so the config can
+    /// be used to re-request the lines identically, e.g. after a restart.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gpiocdev::{Request, Result};
+    /// # use gpiocdev::request::Config;
+    /// # fn main() -> Result<()> {
+    /// # let req = Request::builder()
+    /// #    .on_chip("/dev/gpiochip0")
+    /// #    .with_line(17)
+    /// #    .request()?;
+    /// let cfg = Config::reproduce_request(&req)?;
+    /// drop(req);
+    /// // ... later
+    /// let req = Request::from_config(cfg).request()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reproduce_request(req: &Request) -> Result<Config> {
+        let mut cfg = Config::default();
+        cfg.on_chip(req.chip_path());
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        let chip = {
+            let mut chip = Chip::from_path(&cfg.chip)?;
+            chip.using_abi_version(req.abiv);
+            chip
+        };
+        #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
+        let chip = Chip::from_path(&cfg.chip)?;
+        let mut outputs = Vec::new();
+        for offset in &req.lines.offsets {
+            let lc = line::Config::from_info(&chip.line_info(*offset)?);
+            if lc.direction == Some(Direction::Output) {
+                outputs.push(*offset);
+            }
+            cfg.with_line(*offset).from_line_config(&lc);
+        }
+        if !outputs.is_empty() {
+            let mut values = Values::from_offsets(&outputs);
+            req.values(&mut values)?;
+            for lv in values.iter() {
+                cfg.with_line(lv.offset).with_value(lv.value);
+            }
+        }
+        cfg.with_lines(&[]);
+        Ok(cfg)
+    }
+
+    fn selected_iter(&mut self) -> SelectedIterator<'_> {
         SelectedIterator {
             cfg: self,
             index: 0,
@@ -422,6 +530,24 @@ impl Config {
         &self.offsets
     }
 
+    /// Returns the differences from this configuration to another.
+    ///
+    /// # Examples
+    /// ```
+    ///    use gpiocdev::line::{Bias::*, Value::*};
+    ///    use gpiocdev::request::Config;
+    ///
+    ///    let mut old = Config::default();
+    ///    old.with_lines(&[3, 5]).as_input();
+    ///    let mut new = old.clone();
+    ///    new.with_line(5).with_bias(PullUp);
+    ///    let delta = old.diff(&new);
+    ///    assert_eq!(delta.to_string(), "line 5: bias unset -> pullup");
+    /// ```
+    pub fn diff(&self, other: &Config) -> ConfigDelta {
+        ConfigDelta::new(self, other)
+    }
+
     /// Returns the number of lines currently described by the Config.
     pub fn num_lines(&self) -> usize {
         self.lcfg.len()
@@ -491,6 +617,68 @@ impl Config {
         cfg
     }
 
+    /// The uAPI request that this configuration would submit to the kernel.
+    ///
+    /// This is a dry run - no chip is opened and no lines are requested.
+    /// The request uses the default consumer label and kernel event buffer size.
+    ///
+    /// Useful to determine exactly which offsets, flags and attributes are passed to
+    /// the kernel, such as when a request is rejected with `EINVAL`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gpiocdev::line::Bias;
+    /// # use gpiocdev::request::Config;
+    /// # use gpiocdev::AbiVersion;
+    /// let mut cfg = Config::default();
+    /// cfg.with_lines(&[3, 5]).as_input().with_bias(Bias::PullUp);
+    /// # #[cfg(feature = "uapi_v2")]
+    /// println!("{}", cfg.to_uapi(AbiVersion::V2).unwrap());
+    /// ```
+    pub fn to_uapi(&self, abiv: AbiVersion) -> Result<UapiRequestDump> {
+        Request::from_config(self.clone()).to_uapi_dump(abiv)
+    }
+
+    /// Check the configuration for problems before requesting the lines.
+    ///
+    /// Reports both configurations the kernel would reject, such as drive on an input
+    /// or more lines or attributes than the uAPI supports, and those it would accept but
+    /// that are unlikely to behave as intended, such as debounce without edge detection.
+    ///
+    /// Problems with individual lines are reported in offset order, followed by any
+    /// problems with the request as a whole.
+    /// An empty result means no problems were found, though the request may still be
+    /// rejected, e.g. if the lines are already requested.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// # use gpiocdev::chip::Chip;
+    /// # use gpiocdev::line::Bias;
+    /// # use gpiocdev::request::Config;
+    /// # use gpiocdev::AbiVersion;
+    /// let chip = Chip::from_name("gpiochip0")?;
+    /// let mut cfg = Config::default();
+    /// cfg.with_lines(&[3, 5]).as_input().with_bias(Bias::PullUp);
+    /// for diag in cfg.validate_for(AbiVersion::V2, &chip.capabilities()?) {
+    ///     println!("{}", diag);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_for(&self, abiv: AbiVersion, caps: &Capabilities) -> Vec<Diagnostic> {
+        let mut diags = Vec::new();
+        let mut offsets = self.offsets.clone();
+        offsets.sort_unstable();
+        for offset in offsets {
+            // unwrap is safe here as offsets match lcfg keys
+            let lcfg = self.lcfg.get(&offset).unwrap();
+            validate::check_line(offset, lcfg, caps, &mut diags);
+        }
+        validate::check_request(self, abiv, caps, &mut diags);
+        diags
+    }
+
     // conversions to uapi
 
     // v1
@@ -663,6 +851,7 @@ impl<'a> Iterator for SelectedIterator<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::request::{Change, Severity};
     use Bias::*;
     use Direction::*;
     use Drive::*;
@@ -848,6 +1037,8 @@ mod tests {
         assert_eq!(cfg.base.event_clock, Some(Realtime));
         cfg.with_event_clock(Monotonic);
         assert_eq!(cfg.base.event_clock, Some(Monotonic));
+        cfg.with_event_clock(Hte);
+        assert_eq!(cfg.base.event_clock, Some(Hte));
         cfg.with_event_clock(None);
         assert_eq!(cfg.base.event_clock, None);
     }
@@ -977,6 +1168,56 @@ mod tests {
         assert!(cfg.lcfg.contains_key(&9));
     }
 
+    #[test]
+    fn with_line_config() {
+        let mut cfg = Config::default();
+        cfg.as_input().with_lines(&[1, 2]);
+        cfg.with_line_config(3, |c| c.as_output(Active))
+            .with_line_config(2, |c| c.with_edge_detection(BothEdges))
+            .with_bias(PullUp);
+        assert_eq!(cfg.offsets, &[1, 2, 3]);
+        // selection restored
+        assert_eq!(cfg.selected, &[1, 2]);
+        let lc = cfg.lcfg.get(&1).unwrap();
+        assert_eq!(lc.direction, Some(Input));
+        assert_eq!(lc.edge_detection, None);
+        assert_eq!(lc.bias, Some(PullUp));
+        let lc = cfg.lcfg.get(&2).unwrap();
+        assert_eq!(lc.direction, Some(Input));
+        assert_eq!(lc.edge_detection, Some(BothEdges));
+        assert_eq!(lc.bias, Some(PullUp));
+        let lc = cfg.lcfg.get(&3).unwrap();
+        assert_eq!(lc.direction, Some(Output));
+        assert_eq!(lc.value, Some(Active));
+        assert_eq!(lc.bias, None);
+    }
+
+    #[test]
+    fn with_lines_config() {
+        let mut cfg = Config::default();
+        cfg.with_lines_config(&[3, 4], |c| c.as_output(Inactive).with_drive(OpenDrain))
+            .with_lines_config(&[5, 6], |c| c.as_input().with_bias(PullDown));
+        assert_eq!(cfg.offsets, &[3, 4, 5, 6]);
+        // base selection restored
+        assert!(cfg.selected.is_empty());
+        for offset in [3, 4] {
+            let lc = cfg.lcfg.get(&offset).unwrap();
+            assert_eq!(lc.direction, Some(Output));
+            assert_eq!(lc.drive, Some(OpenDrain));
+            assert_eq!(lc.value, Some(Inactive));
+        }
+        for offset in [5, 6] {
+            let lc = cfg.lcfg.get(&offset).unwrap();
+            assert_eq!(lc.direction, Some(Input));
+            assert_eq!(lc.bias, Some(PullDown));
+        }
+
+        // removed lines are dropped from the restored selection
+        cfg.with_lines(&[3, 5]);
+        cfg.with_lines_config(&[4], |c| c.without_line(3));
+        assert_eq!(cfg.selected, &[5]);
+    }
+
     #[test]
     fn without_lines() {
         let mut cfg = Config::default();
@@ -1179,6 +1420,38 @@ mod tests {
         assert_eq!(cfg.lines(), &[4, 6, 9]);
     }
 
+    #[test]
+    fn diff() {
+        let mut old = Config::default();
+        old.with_lines(&[1, 2, 3]).as_input();
+        assert!(old.diff(&old).is_empty());
+
+        let mut new = old.clone();
+        new.with_line(2)
+            .with_bias(Bias::PullUp)
+            .with_line(3)
+            .as_output(Value::Active)
+            .without_line(1)
+            .with_line(7);
+        let delta = old.diff(&new);
+        assert!(!delta.is_empty());
+        assert_eq!(delta.added, vec![7]);
+        assert_eq!(delta.removed, vec![1]);
+        assert_eq!(delta.lines.len(), 2);
+        assert_eq!(
+            delta.line(2).unwrap().changes,
+            vec![Change::Bias(None, Some(Bias::PullUp))]
+        );
+        assert_eq!(
+            delta.line(3).unwrap().changes,
+            vec![
+                Change::Direction(Some(Direction::Input), Some(Direction::Output)),
+                Change::Value(None, Some(Value::Active)),
+            ]
+        );
+        assert!(delta.line(1).is_none());
+    }
+
     #[test]
     fn num_lines() {
         let mut cfg = Config::default();
@@ -1338,4 +1611,211 @@ mod tests {
             "uAPI ABI v2 supports 10 attrs, configuration requires 13."
         );
     }
+
+    #[test]
+    fn to_uapi() {
+        #[cfg(feature = "uapi_v2")]
+        let abiv = AbiVersion::V2;
+        #[cfg(not(feature = "uapi_v2"))]
+        let abiv = AbiVersion::V1;
+        let cfg = Config::default();
+        assert_eq!(
+            cfg.to_uapi(abiv).unwrap_err().to_string(),
+            "No lines specified."
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn to_uapi_v1() {
+        let mut cfg = Config::default();
+        cfg.with_lines(&[3, 5])
+            .as_output(Active)
+            .with_line(5)
+            .with_value(Inactive);
+        let dump = cfg.to_uapi(AbiVersion::V1).unwrap();
+        assert_eq!(dump.ioctl(), "GPIO_GET_LINEHANDLE_IOCTL");
+        let s = dump.to_string();
+        assert!(s.starts_with("GPIO_GET_LINEHANDLE_IOCTL\noffsets: [3, 5]\nconsumer: \"gpiocdev-p"));
+        assert!(s.ends_with("flags: HandleRequestFlags(OUTPUT)\nvalues: [1, 0]"));
+
+        let mut cfg = Config::default();
+        cfg.with_line(8).with_edge_detection(RisingEdge);
+        let dump = cfg.to_uapi(AbiVersion::V1).unwrap();
+        assert_eq!(dump.ioctl(), "GPIO_GET_LINEEVENT_IOCTL");
+        let s = dump.to_string();
+        assert!(s.starts_with("GPIO_GET_LINEEVENT_IOCTL\noffset: 8\n"));
+        assert!(s.ends_with(
+            "handleflags: HandleRequestFlags(INPUT)\neventflags: EventRequestFlags(RISING_EDGE)"
+        ));
+
+        cfg.with_debounce_period(Duration::from_millis(5));
+        assert_eq!(
+            cfg.to_uapi(AbiVersion::V1).unwrap_err().to_string(),
+            "uAPI ABI v1 does not support debounce."
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v2")]
+    fn to_uapi_v2() {
+        let mut cfg = Config::default();
+        cfg.with_lines(&[3, 5, 7])
+            .as_input()
+            .with_bias(PullUp)
+            .with_line(5)
+            .as_output(Active)
+            .with_line(7)
+            .with_debounce_period(Duration::from_micros(10));
+        let dump = cfg.to_uapi(AbiVersion::V2).unwrap();
+        assert_eq!(dump.ioctl(), "GPIO_V2_GET_LINE_IOCTL");
+        let s = dump.to_string();
+        assert!(s.starts_with("GPIO_V2_GET_LINE_IOCTL\noffsets: [3, 5, 7]\nconsumer: \"gpiocdev-p"));
+        assert!(s.contains("\nevent_buffer_size: 0\nflags: LineFlags(INPUT | BIAS_PULL_UP)\n"));
+        assert!(s.contains("\nattrs[0]: flags: LineFlags(OUTPUT | BIAS_PULL_UP), mask: 0x2"));
+        assert!(s.contains("\nattrs[1]: values: 00000002, mask: 0x2"));
+        assert!(s.ends_with("\nattrs[2]: debounce_period_us: 10, mask: 0x4"));
+    }
+
+    #[test]
+    #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
+    fn to_uapi_unsupported() {
+        #[cfg(not(feature = "uapi_v1"))]
+        let abiv = AbiVersion::V1;
+        #[cfg(not(feature = "uapi_v2"))]
+        let abiv = AbiVersion::V2;
+        let mut cfg = Config::default();
+        cfg.with_line(3);
+        assert_eq!(
+            cfg.to_uapi(abiv).unwrap_err(),
+            Error::UnsupportedAbi(abiv, crate::AbiSupportKind::Build)
+        );
+    }
+
+    fn all_caps() -> Capabilities {
+        Capabilities {
+            kernel: None,
+            abi_v1: true,
+            abi_v2: true,
+            bias: true,
+            reconfigure: true,
+            debounce: true,
+            event_clock_realtime: true,
+            event_clock_hte: true,
+            max_event_buffer_size: 16,
+        }
+    }
+
+    fn default_abiv() -> AbiVersion {
+        #[cfg(feature = "uapi_v2")]
+        let abiv = AbiVersion::V2;
+        #[cfg(not(feature = "uapi_v2"))]
+        let abiv = AbiVersion::V1;
+        abiv
+    }
+
+    #[test]
+    fn validate_for() {
+        let caps = all_caps();
+        let abiv = default_abiv();
+        let mut cfg = Config::default();
+        cfg.with_lines(&[3, 5]).as_input().with_bias(PullUp);
+        assert!(cfg.validate_for(abiv, &caps).is_empty());
+
+        cfg.with_line(7)
+            .as_output(Active)
+            .with_bias(PullUp)
+            .with_line(2)
+            .as_is()
+            .with_bias(PullUp)
+            .with_line(9)
+            .with_debounce_period(Duration::from_millis(10))
+            .with_event_clock(Realtime);
+        // drive on an input is only possible by modifying the line config directly
+        cfg.lcfg.get_mut(&5).unwrap().drive = Some(OpenDrain);
+        // uAPI v1 also reports the mixed line configs for the request as a whole
+        let diags: Vec<Diagnostic> = cfg
+            .validate_for(abiv, &caps)
+            .into_iter()
+            .filter(|d| d.offset.is_some())
+            .collect();
+        let summary: Vec<(Option<Offset>, Severity)> =
+            diags.iter().map(|d| (d.offset, d.severity)).collect();
+        assert_eq!(
+            summary,
+            [
+                (Some(2), Severity::Error),
+                (Some(5), Severity::Error),
+                (Some(7), Severity::Warning),
+                (Some(9), Severity::Warning),
+                (Some(9), Severity::Warning),
+            ]
+        );
+        assert_eq!(
+            diags[0].to_string(),
+            "error: line 2: bias PullUp requires the direction to be set"
+        );
+        assert_eq!(
+            diags[1].message,
+            "drive OpenDrain requires the line to be an output"
+        );
+        assert_eq!(
+            diags[2].message,
+            "bias PullUp has no effect on a push-pull output"
+        );
+    }
+
+    #[test]
+    fn validate_for_capabilities() {
+        let mut caps = all_caps();
+        caps.bias = false;
+        caps.debounce = false;
+        caps.event_clock_hte = false;
+        let abiv = default_abiv();
+        let mut cfg = Config::default();
+        cfg.with_line(3)
+            .as_input()
+            .with_bias(PullDown)
+            .with_line(4)
+            .with_edge_detection(RisingEdge)
+            .with_debounce_period(Duration::from_millis(5))
+            .with_event_clock(Hte);
+        let diags = cfg.validate_for(abiv, &caps);
+        let messages: Vec<&str> = diags
+            .iter()
+            .filter(|d| d.offset.is_some())
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "setting bias is not supported by the kernel",
+                "debounce is not supported by the kernel",
+                "event clock Hte is not supported by the kernel",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_for_request() {
+        let mut caps = all_caps();
+        let abiv = default_abiv();
+        match abiv {
+            AbiVersion::V1 => caps.abi_v1 = false,
+            AbiVersion::V2 => caps.abi_v2 = false,
+        }
+        let mut cfg = Config::default();
+        let offsets: Vec<Offset> = (0..70).collect();
+        cfg.with_lines(&offsets).as_input();
+        let diags = cfg.validate_for(abiv, &caps);
+        assert_eq!(diags.len(), 2);
+        assert!(diags.iter().all(|d| d.offset.is_none() && d.is_error()));
+        assert_eq!(
+            diags[0].message,
+            format!("{} is not supported by the chip", abiv)
+        );
+        assert!(diags[1]
+            .message
+            .contains("Requested 70 lines is greater than the maximum of 64."));
+    }
 }
@@ -0,0 +1,308 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Config;
+use crate::line::{self, Bias, Direction, Drive, EdgeDetection, EventClock, Offset, Value, Values};
+use std::fmt;
+use std::time::Duration;
+
+/// A change to an attribute of a line, from an old setting to a new setting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// The direction setting changed.
+    Direction(Option<Direction>, Option<Direction>),
+
+    /// The active low setting changed.
+    ActiveLow(bool, bool),
+
+    /// The bias setting changed.
+    Bias(Option<Bias>, Option<Bias>),
+
+    /// The drive setting changed.
+    Drive(Option<Drive>, Option<Drive>),
+
+    /// The edge detection setting changed.
+    EdgeDetection(Option<EdgeDetection>, Option<EdgeDetection>),
+
+    /// The event clock setting changed.
+    EventClock(Option<EventClock>, Option<EventClock>),
+
+    /// The debounce period changed.
+    DebouncePeriod(Option<Duration>, Option<Duration>),
+
+    /// The output value changed.
+    Value(Option<Value>, Option<Value>),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Direction(from, to) => fmt_change(f, "direction", from, to),
+            Change::ActiveLow(from, to) => write!(f, "active-low {} -> {}", from, to),
+            Change::Bias(from, to) => fmt_change(f, "bias", from, to),
+            Change::Drive(from, to) => fmt_change(f, "drive", from, to),
+            Change::EdgeDetection(from, to) => fmt_change(f, "edge-detection", from, to),
+            Change::EventClock(from, to) => fmt_change(f, "event-clock", from, to),
+            Change::DebouncePeriod(from, to) => fmt_change(f, "debounce-period", from, to),
+            Change::Value(from, to) => fmt_change(f, "value", from, to),
+        }
+    }
+}
+
+fn fmt_change<T: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    attr: &str,
+    from: &Option<T>,
+    to: &Option<T>,
+) -> fmt::Result {
+    write!(f, "{} {} -> {}", attr, fmt_setting(from), fmt_setting(to))
+}
+
+fn fmt_setting<T: fmt::Debug>(setting: &Option<T>) -> String {
+    match setting {
+        Some(x) => format!("{:?}", x).to_lowercase(),
+        None => "unset".to_string(),
+    }
+}
+
+/// The changes to the configuration of a single line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineDelta {
+    /// The offset of the line.
+    pub offset: Offset,
+
+    /// The changed attributes.
+    pub changes: Vec<Change>,
+}
+
+/// The differences between two request configurations.
+///
+/// Created by [`Config::diff`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigDelta {
+    /// Lines present only in the new configuration.
+    pub added: Vec<Offset>,
+
+    /// Lines present only in the old configuration.
+    pub removed: Vec<Offset>,
+
+    /// Lines present in both configurations with changed attributes, in offset order.
+    pub lines: Vec<LineDelta>,
+}
+
+impl ConfigDelta {
+    pub(super) fn new(old: &Config, new: &Config) -> ConfigDelta {
+        let mut delta = ConfigDelta::default();
+        for offset in old.lines() {
+            match new.line_config(*offset) {
+                Some(nlc) => {
+                    let changes = line_changes(old.line_config(*offset).unwrap(), nlc);
+                    if !changes.is_empty() {
+                        delta.lines.push(LineDelta {
+                            offset: *offset,
+                            changes,
+                        });
+                    }
+                }
+                None => delta.removed.push(*offset),
+            }
+        }
+        for offset in new.lines() {
+            if old.line_config(*offset).is_none() {
+                delta.added.push(*offset);
+            }
+        }
+        delta.added.sort_unstable();
+        delta.removed.sort_unstable();
+        delta.lines.sort_unstable_by_key(|ld| ld.offset);
+        delta
+    }
+
+    /// Returns true if the configurations are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.lines.is_empty()
+    }
+
+    /// The changes for a particular line.
+    pub fn line(&self, offset: Offset) -> Option<&LineDelta> {
+        self.lines.iter().find(|ld| ld.offset == offset)
+    }
+
+    /// Returns true if the only changes are to output values.
+    ///
+    /// Such changes can be applied using [`Request::set_values`] with the [`values`]
+    /// rather than requiring a [`Request::reconfigure`].
+    ///
+    /// [`Request::set_values`]: super::Request::set_values
+    /// [`Request::reconfigure`]: super::Request::reconfigure
+    /// [`values`]: ConfigDelta::values
+    pub fn is_value_only(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.lines.iter().all(|ld| {
+                ld.changes
+                    .iter()
+                    .all(|c| matches!(c, Change::Value(_, Some(_))))
+            })
+    }
+
+    /// The new values of the lines with changed output values.
+    pub fn values(&self) -> Values {
+        let mut values = Values::default();
+        for ld in &self.lines {
+            for c in &ld.changes {
+                if let Change::Value(_, Some(v)) = c {
+                    values.set(ld.offset, *v);
+                }
+            }
+        }
+        values
+    }
+}
+
+impl fmt::Display for ConfigDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        for ld in &self.lines {
+            write!(f, "{}line {}:", sep, ld.offset)?;
+            let mut csep = " ";
+            for c in &ld.changes {
+                write!(f, "{}{}", csep, c)?;
+                csep = ", ";
+            }
+            sep = "; ";
+        }
+        if !self.added.is_empty() {
+            write!(f, "{}added {:?}", sep, self.added)?;
+            sep = "; ";
+        }
+        if !self.removed.is_empty() {
+            write!(f, "{}removed {:?}", sep, self.removed)?;
+        }
+        Ok(())
+    }
+}
+
+fn line_changes(old: &line::Config, new: &line::Config) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.direction != new.direction {
+        changes.push(Change::Direction(old.direction, new.direction));
+    }
+    if old.active_low != new.active_low {
+        changes.push(Change::ActiveLow(old.active_low, new.active_low));
+    }
+    if old.bias != new.bias {
+        changes.push(Change::Bias(old.bias, new.bias));
+    }
+    if old.drive != new.drive {
+        changes.push(Change::Drive(old.drive, new.drive));
+    }
+    if old.edge_detection != new.edge_detection {
+        changes.push(Change::EdgeDetection(
+            old.edge_detection,
+            new.edge_detection,
+        ));
+    }
+    if old.event_clock != new.event_clock {
+        changes.push(Change::EventClock(old.event_clock, new.event_clock));
+    }
+    if old.debounce_period != new.debounce_period {
+        changes.push(Change::DebouncePeriod(
+            old.debounce_period,
+            new.debounce_period,
+        ));
+    }
+    if old.value != new.value {
+        changes.push(Change::Value(old.value, new.value));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_changes() {
+        let old = line::Config::default();
+        assert!(super::line_changes(&old, &old).is_empty());
+
+        let new = line::Config {
+            direction: Some(Direction::Output),
+            active_low: true,
+            bias: Some(Bias::PullUp),
+            drive: Some(Drive::OpenDrain),
+            edge_detection: Some(EdgeDetection::BothEdges),
+            event_clock: Some(EventClock::Realtime),
+            debounce_period: Some(Duration::from_millis(5)),
+            value: Some(Value::Active),
+        };
+        assert_eq!(
+            super::line_changes(&old, &new),
+            vec![
+                Change::Direction(None, Some(Direction::Output)),
+                Change::ActiveLow(false, true),
+                Change::Bias(None, Some(Bias::PullUp)),
+                Change::Drive(None, Some(Drive::OpenDrain)),
+                Change::EdgeDetection(None, Some(EdgeDetection::BothEdges)),
+                Change::EventClock(None, Some(EventClock::Realtime)),
+                Change::DebouncePeriod(None, Some(Duration::from_millis(5))),
+                Change::Value(None, Some(Value::Active)),
+            ]
+        );
+    }
+
+    #[test]
+    fn change_display() {
+        assert_eq!(
+            Change::Direction(Some(Direction::Input), Some(Direction::Output)).to_string(),
+            "direction input -> output"
+        );
+        assert_eq!(
+            Change::ActiveLow(false, true).to_string(),
+            "active-low false -> true"
+        );
+        assert_eq!(
+            Change::Bias(Some(Bias::PullUp), None).to_string(),
+            "bias pullup -> unset"
+        );
+        assert_eq!(
+            Change::DebouncePeriod(None, Some(Duration::from_millis(5))).to_string(),
+            "debounce-period unset -> 5ms"
+        );
+    }
+
+    #[test]
+    fn values() {
+        let mut old = Config::default();
+        old.with_lines(&[1, 2, 3]).as_output(Value::Inactive);
+        let mut new = old.clone();
+        new.with_lines(&[1, 3]).with_value(Value::Active);
+        let delta = old.diff(&new);
+        assert!(delta.is_value_only());
+        let values = delta.values();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get(1), Some(Value::Active));
+        assert_eq!(values.get(3), Some(Value::Active));
+
+        new.with_line(2).with_bias(Bias::PullDown);
+        assert!(!old.diff(&new).is_value_only());
+    }
+
+    #[test]
+    fn display() {
+        let mut old = Config::default();
+        old.with_lines(&[1, 2, 3]);
+        let mut new = old.clone();
+        new.with_line(2)
+            .as_output(Value::Active)
+            .without_line(3)
+            .with_line(4);
+        assert_eq!(
+            old.diff(&new).to_string(),
+            "line 2: direction unset -> output, value unset -> active; added [4]; removed [3]"
+        );
+        assert_eq!(old.diff(&old).to_string(), "");
+    }
+}
@@ -0,0 +1,62 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Config;
+use crate::{Request, Result};
+
+/// A temporary configuration applied to a [`Request`].
+///
+/// Created by [`Request::with_config_scope`].
+///
+/// The previous configuration is restored when the guard is dropped, unless the
+/// temporary configuration is made permanent with [`commit`].
+///
+/// Errors restoring the configuration on drop are ignored, so use [`restore`]
+/// where such errors must be handled.
+///
+/// [`commit`]: ConfigGuard::commit
+/// [`restore`]: ConfigGuard::restore
+#[must_use = "the previous configuration is restored when the guard is dropped"]
+pub struct ConfigGuard<'a> {
+    req: &'a Request,
+    prev: Option<Config>,
+}
+
+impl<'a> ConfigGuard<'a> {
+    pub(super) fn new(req: &'a Request, new_cfg: &Config) -> Result<ConfigGuard<'a>> {
+        let prev = req.config();
+        req.reconfigure(new_cfg)?;
+        Ok(ConfigGuard {
+            req,
+            prev: Some(prev),
+        })
+    }
+
+    /// Keep the temporary configuration, discarding the previous configuration.
+    pub fn commit(mut self) {
+        self.prev = None;
+    }
+
+    /// Restore the previous configuration.
+    pub fn restore(mut self) -> Result<()> {
+        match self.prev.take() {
+            Some(prev) => self.req.reconfigure(&prev),
+            None => Ok(()),
+        }
+    }
+
+    /// The configuration that will be restored.
+    pub fn previous(&self) -> Option<&Config> {
+        self.prev.as_ref()
+    }
+}
+
+impl Drop for ConfigGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            // best effort, as there is no way to report the error
+            let _ = self.req.reconfigure(&prev);
+        }
+    }
+}
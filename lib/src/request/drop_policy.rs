@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{Direction, Offset, Values};
+use crate::{Request, Result};
+
+/// The action taken on the requested lines when a [`Request`] is dropped.
+///
+/// The action is performed before the request is released, including when the
+/// request is dropped while unwinding from a panic.
+/// It is not performed if the process is killed or aborts, including a panic with
+/// `panic = "abort"`.
+///
+/// Errors performing the action are ignored, as there is no way to report them.
+///
+/// Set using [`Builder::on_drop`].
+///
+/// [`Builder::on_drop`]: super::Builder::on_drop
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum DropPolicy {
+    /// Release the lines without changing them.
+    ///
+    /// The output values become indeterminate once the request is released.
+    #[default]
+    LeaveAsIs,
+
+    /// Set the values of output lines before releasing them.
+    SetValues(Values),
+
+    /// Reconfigure output lines as inputs before releasing them.
+    RevertToInput,
+}
+
+impl DropPolicy {
+    pub(super) fn apply(&self, req: &Request) -> Result<()> {
+        match self {
+            DropPolicy::LeaveAsIs => Ok(()),
+            DropPolicy::SetValues(values) => req.set_values(values),
+            DropPolicy::RevertToInput => {
+                let mut cfg = req.config();
+                let outputs: Vec<Offset> = cfg
+                    .lines()
+                    .iter()
+                    .filter(|offset| {
+                        cfg.line_config(**offset)
+                            .map(|lc| lc.direction == Some(Direction::Output))
+                            .unwrap_or(false)
+                    })
+                    .copied()
+                    .collect();
+                if outputs.is_empty() {
+                    return Ok(());
+                }
+                cfg.with_lines(&outputs).as_input();
+                req.reconfigure(&cfg)
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::builder::UapiRequest;
+use std::fmt;
+
+/// The uAPI request that would be submitted to the kernel to request a set of lines.
+///
+/// Created by [`Config::to_uapi`](super::Config::to_uapi).
+///
+/// The [`Display`](fmt::Display) rendering lists the ioctl and only the populated
+/// fields of the request - the offsets, flags, attributes and output values that the
+/// kernel would validate.
+/// The [`Debug`] rendering is the raw uAPI structure.
+#[derive(Clone, Debug)]
+pub struct UapiRequestDump(pub(super) UapiRequest);
+
+impl UapiRequestDump {
+    /// The name of the ioctl used to submit the request, as per the kernel headers.
+    pub fn ioctl(&self) -> &'static str {
+        match &self.0 {
+            #[cfg(feature = "uapi_v1")]
+            UapiRequest::Handle(_) => "GPIO_GET_LINEHANDLE_IOCTL",
+            #[cfg(feature = "uapi_v1")]
+            UapiRequest::Event(_) => "GPIO_GET_LINEEVENT_IOCTL",
+            #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
+            UapiRequest::Line(_) => "GPIO_V2_GET_LINE_IOCTL",
+        }
+    }
+}
+
+impl fmt::Display for UapiRequestDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.ioctl())?;
+        match &self.0 {
+            #[cfg(feature = "uapi_v1")]
+            UapiRequest::Handle(hr) => {
+                let num_lines = hr.num_lines as usize;
+                let offsets: Vec<u32> = (0..num_lines).map(|i| hr.offsets.get(i)).collect();
+                let values: Vec<u8> = (0..num_lines).map(|i| hr.values.get(i)).collect();
+                writeln!(f, "offsets: {:?}", offsets)?;
+                writeln!(f, "consumer: {:?}", String::from(&hr.consumer))?;
+                writeln!(f, "flags: {:?}", hr.flags)?;
+                write!(f, "values: {:?}", values)
+            }
+            #[cfg(feature = "uapi_v1")]
+            UapiRequest::Event(er) => {
+                writeln!(f, "offset: {}", er.offset)?;
+                writeln!(f, "consumer: {:?}", String::from(&er.consumer))?;
+                writeln!(f, "handleflags: {:?}", er.handleflags)?;
+                write!(f, "eventflags: {:?}", er.eventflags)
+            }
+            #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
+            UapiRequest::Line(lr) => {
+                let offsets: Vec<u32> = (0..lr.num_lines as usize)
+                    .map(|i| lr.offsets.get(i))
+                    .collect();
+                writeln!(f, "offsets: {:?}", offsets)?;
+                writeln!(f, "consumer: {:?}", String::from(&lr.consumer))?;
+                writeln!(f, "event_buffer_size: {}", lr.event_buffer_size)?;
+                write!(f, "flags: {:?}", lr.config.flags)?;
+                for idx in 0..lr.config.num_attrs as usize {
+                    let attr = lr.config.attr(idx);
+                    write!(
+                        f,
+                        "\nattrs[{}]: {:?}, mask: {:#x}",
+                        idx, attr.attr, attr.mask
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
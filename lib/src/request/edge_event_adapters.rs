@@ -0,0 +1,685 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{DetectLoss, DetectSuspend, SuspendDetector};
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+use crate::Result;
+#[cfg(feature = "async")]
+use futures::task::{Context, Poll};
+#[cfg(feature = "async")]
+use futures::{ready, Stream};
+use std::collections::HashMap;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Adapters for iterators over edge events, such as [`EdgeEventBuffer`].
+///
+/// Errors returned by the underlying iterator are always passed through
+/// to the caller.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::Result;
+/// use gpiocdev::line::{EdgeDetection, EdgeKind};
+/// use gpiocdev::request::{EdgeEventIteratorExt, Request};
+///
+/// # fn main() -> Result<()> {
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_lines(&[3, 5])
+///     .with_edge_detection(EdgeDetection::BothEdges)
+///     .request()?;
+/// for edge in req.edge_events().filter_line(5).filter_kind(EdgeKind::Rising) {
+///     println!("{:?}", edge?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`EdgeEventBuffer`]: super::EdgeEventBuffer
+pub trait EdgeEventIteratorExt: Iterator<Item = Result<EdgeEvent>> + Sized {
+    /// Only pass events for the line with the given offset.
+    fn filter_line(self, offset: Offset) -> FilterLine<Self> {
+        FilterLine {
+            inner: self,
+            offset,
+        }
+    }
+
+    /// Only pass events of the given kind.
+    fn filter_kind(self, kind: EdgeKind) -> FilterKind<Self> {
+        FilterKind { inner: self, kind }
+    }
+
+    /// Apply a function to the timestamp of each event.
+    ///
+    /// Useful for converting timestamps to a different epoch, e.g. relative
+    /// to the start of a capture.
+    fn map_timestamps<F: FnMut(u64) -> u64>(self, f: F) -> MapTimestamps<Self, F> {
+        MapTimestamps { inner: self, f }
+    }
+
+    /// Group events into chunks of `size` events.
+    ///
+    /// Each chunk is returned once it is full, so the iterator blocks until
+    /// `size` events have been read.
+    ///
+    /// If an error is encountered part way through a chunk then the partial
+    /// chunk is returned, followed by the error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    fn chunks(self, size: usize) -> Chunks<Self> {
+        Chunks::new(self, size)
+    }
+
+    /// Report any system suspend before the next event.
+    ///
+    /// Events are returned as [`EdgeOrResume::Edge`], and a suspend detected by the
+    /// detector is returned as an [`EdgeOrResume::Resume`] before the first event
+    /// read after the resume.
+    ///
+    /// [`EdgeOrResume::Edge`]: super::EdgeOrResume::Edge
+    /// [`EdgeOrResume::Resume`]: super::EdgeOrResume::Resume
+    fn detect_suspend(self, detector: SuspendDetector) -> DetectSuspend<Self> {
+        DetectSuspend::new(self, detector)
+    }
+
+    /// Suppress events closer together than `min_interval` on the same line.
+    ///
+    /// The first event on a line is passed, and any subsequent events on that line
+    /// within `min_interval` of it are merged into it, so a line chattering
+    /// continuously produces at most one event per interval.
+    ///
+    /// This is intended for noisy contacts that are not adequately settled by
+    /// debouncing.  As merged events are discarded, the kind of the last event
+    /// passed may not reflect the final state of the line.
+    ///
+    /// The number of events merged is available from [`Coalesce::merged`].
+    fn coalesce(self, min_interval: Duration) -> Coalesce<Self> {
+        Coalesce::new(self, min_interval)
+    }
+
+    /// Report any events lost to kernel event buffer overflows.
+    ///
+    /// Events are returned as [`EdgeOrLoss::Edge`], and a gap in the event sequence
+    /// numbers is returned as an [`EdgeOrLoss::Loss`] before the event following the gap.
+    ///
+    /// Must be applied before any adapter that filters events.
+    ///
+    /// [`EdgeOrLoss::Edge`]: super::EdgeOrLoss::Edge
+    /// [`EdgeOrLoss::Loss`]: super::EdgeOrLoss::Loss
+    fn detect_loss(self) -> DetectLoss<Self> {
+        DetectLoss::new(self)
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> EdgeEventIteratorExt for I {}
+
+/// Adapters for async streams of edge events, such as [`reactor::EdgeEventStream`].
+///
+/// These are the async equivalents of the [`EdgeEventIteratorExt`] adapters.
+///
+/// [`reactor::EdgeEventStream`]: crate::reactor::EdgeEventStream
+#[cfg(feature = "async")]
+pub trait EdgeEventStreamExt: Stream<Item = Result<EdgeEvent>> + Unpin + Sized {
+    /// Only pass events for the line with the given offset.
+    fn filter_line(self, offset: Offset) -> FilterLine<Self> {
+        FilterLine {
+            inner: self,
+            offset,
+        }
+    }
+
+    /// Only pass events of the given kind.
+    fn filter_kind(self, kind: EdgeKind) -> FilterKind<Self> {
+        FilterKind { inner: self, kind }
+    }
+
+    /// Apply a function to the timestamp of each event.
+    fn map_timestamps<F: FnMut(u64) -> u64 + Unpin>(self, f: F) -> MapTimestamps<Self, F> {
+        MapTimestamps { inner: self, f }
+    }
+
+    /// Group events into chunks of `size` events.
+    ///
+    /// Each chunk is returned once it is full.
+    ///
+    /// If an error is encountered part way through a chunk then the partial
+    /// chunk is returned, followed by the error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    fn chunks(self, size: usize) -> Chunks<Self> {
+        Chunks::new(self, size)
+    }
+
+    /// Report any system suspend before the next event.
+    fn detect_suspend(self, detector: SuspendDetector) -> DetectSuspend<Self> {
+        DetectSuspend::new(self, detector)
+    }
+
+    /// Suppress events closer together than `min_interval` on the same line.
+    fn coalesce(self, min_interval: Duration) -> Coalesce<Self> {
+        Coalesce::new(self, min_interval)
+    }
+
+    /// Report any events lost to kernel event buffer overflows.
+    fn detect_loss(self) -> DetectLoss<Self> {
+        DetectLoss::new(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> EdgeEventStreamExt for S {}
+
+/// An adapter that only passes events from a particular line.
+///
+/// Created by [`EdgeEventIteratorExt::filter_line`] or `EdgeEventStreamExt::filter_line`.
+pub struct FilterLine<I> {
+    inner: I,
+    offset: Offset,
+}
+
+impl<I> FilterLine<I> {
+    fn accept(&self, res: &Result<EdgeEvent>) -> bool {
+        match res {
+            Ok(evt) => evt.offset == self.offset,
+            Err(_) => true,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> Iterator for FilterLine<I> {
+    type Item = Result<EdgeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let res = self.inner.next()?;
+            if self.accept(&res) {
+                return Some(res);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> Stream for FilterLine<S> {
+    type Item = Result<EdgeEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+                Some(res) if !self.accept(&res) => continue,
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+/// An adapter that only passes events of a particular kind.
+///
+/// Created by [`EdgeEventIteratorExt::filter_kind`] or `EdgeEventStreamExt::filter_kind`.
+pub struct FilterKind<I> {
+    inner: I,
+    kind: EdgeKind,
+}
+
+impl<I> FilterKind<I> {
+    fn accept(&self, res: &Result<EdgeEvent>) -> bool {
+        match res {
+            Ok(evt) => evt.kind == self.kind,
+            Err(_) => true,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> Iterator for FilterKind<I> {
+    type Item = Result<EdgeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let res = self.inner.next()?;
+            if self.accept(&res) {
+                return Some(res);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> Stream for FilterKind<S> {
+    type Item = Result<EdgeEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+                Some(res) if !self.accept(&res) => continue,
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+/// An adapter that applies a function to event timestamps.
+///
+/// Created by [`EdgeEventIteratorExt::map_timestamps`] or `EdgeEventStreamExt::map_timestamps`.
+pub struct MapTimestamps<I, F> {
+    inner: I,
+    f: F,
+}
+
+impl<I, F: FnMut(u64) -> u64> MapTimestamps<I, F> {
+    fn map(&mut self, res: Result<EdgeEvent>) -> Result<EdgeEvent> {
+        res.map(|mut evt| {
+            evt.timestamp_ns = (self.f)(evt.timestamp_ns);
+            evt
+        })
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>, F: FnMut(u64) -> u64> Iterator for MapTimestamps<I, F> {
+    type Item = Result<EdgeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.inner.next()?;
+        Some(self.map(res))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin, F: FnMut(u64) -> u64 + Unpin> Stream
+    for MapTimestamps<S, F>
+{
+    type Item = Result<EdgeEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let res = ready!(Pin::new(&mut self.inner).poll_next(cx));
+        Poll::Ready(res.map(|res| self.map(res)))
+    }
+}
+
+/// An adapter that groups events into chunks.
+///
+/// Created by [`EdgeEventIteratorExt::chunks`] or `EdgeEventStreamExt::chunks`.
+pub struct Chunks<I> {
+    inner: I,
+    size: usize,
+    chunk: Vec<EdgeEvent>,
+    // an error that terminated a partial chunk, to be returned next.
+    err: Option<crate::Error>,
+}
+
+impl<I> Chunks<I> {
+    fn new(inner: I, size: usize) -> Self {
+        assert!(size > 0, "chunk size must be non-zero");
+        Chunks {
+            inner,
+            size,
+            chunk: Vec::with_capacity(size),
+            err: None,
+        }
+    }
+
+    // Add an item to the chunk, returning the chunk if it is complete.
+    fn push(&mut self, res: Option<Result<EdgeEvent>>) -> Option<Option<Result<Vec<EdgeEvent>>>> {
+        match res {
+            Some(Ok(evt)) => {
+                self.chunk.push(evt);
+                if self.chunk.len() < self.size {
+                    return None;
+                }
+                Some(Some(Ok(self.take())))
+            }
+            Some(Err(e)) => {
+                if self.chunk.is_empty() {
+                    return Some(Some(Err(e)));
+                }
+                self.err = Some(e);
+                Some(Some(Ok(self.take())))
+            }
+            None => {
+                if self.chunk.is_empty() {
+                    return Some(None);
+                }
+                Some(Some(Ok(self.take())))
+            }
+        }
+    }
+
+    fn take(&mut self) -> Vec<EdgeEvent> {
+        std::mem::replace(&mut self.chunk, Vec::with_capacity(self.size))
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> Iterator for Chunks<I> {
+    type Item = Result<Vec<EdgeEvent>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.err.take() {
+            return Some(Err(e));
+        }
+        loop {
+            let res = self.inner.next();
+            if let Some(chunk) = self.push(res) {
+                return chunk;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> Stream for Chunks<S> {
+    type Item = Result<Vec<EdgeEvent>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if let Some(e) = self.err.take() {
+            return Poll::Ready(Some(Err(e)));
+        }
+        loop {
+            let res = ready!(Pin::new(&mut self.inner).poll_next(cx));
+            if let Some(chunk) = self.push(res) {
+                return Poll::Ready(chunk);
+            }
+        }
+    }
+}
+
+/// An adapter that suppresses events closer together than a minimum interval on each line.
+///
+/// Created by [`EdgeEventIteratorExt::coalesce`] or `EdgeEventStreamExt::coalesce`.
+///
+/// The merged counts can be read while iterating by using the adapter via
+/// [`Iterator::by_ref`].
+pub struct Coalesce<I> {
+    inner: I,
+    min_interval_ns: u64,
+    // the timestamp of the last event passed on each line, and the number of events merged
+    lines: HashMap<Offset, (u64, u64)>,
+}
+
+impl<I> Coalesce<I> {
+    fn new(inner: I, min_interval: Duration) -> Self {
+        Coalesce {
+            inner,
+            min_interval_ns: min_interval.as_nanos() as u64,
+            lines: HashMap::new(),
+        }
+    }
+
+    /// The total number of events merged, across all lines.
+    pub fn merged(&self) -> u64 {
+        self.lines.values().map(|(_, merged)| merged).sum()
+    }
+
+    /// The number of events merged on a particular line.
+    pub fn merged_on(&self, offset: Offset) -> u64 {
+        self.lines.get(&offset).map_or(0, |(_, merged)| *merged)
+    }
+
+    fn accept(&mut self, res: &Result<EdgeEvent>) -> bool {
+        let evt = match res {
+            Ok(evt) => evt,
+            Err(_) => return true,
+        };
+        match self.lines.get_mut(&evt.offset) {
+            Some((last, merged)) => {
+                if evt.timestamp_ns.saturating_sub(*last) < self.min_interval_ns {
+                    *merged += 1;
+                    return false;
+                }
+                *last = evt.timestamp_ns;
+            }
+            None => {
+                self.lines.insert(evt.offset, (evt.timestamp_ns, 0));
+            }
+        }
+        true
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> Iterator for Coalesce<I> {
+    type Item = Result<EdgeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let res = self.inner.next()?;
+            if self.accept(&res) {
+                return Some(res);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> Stream for Coalesce<S> {
+    type Item = Result<EdgeEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+                Some(res) if !self.accept(&res) => continue,
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    fn event(offset: Offset, kind: EdgeKind, timestamp_ns: u64) -> Result<EdgeEvent> {
+        Ok(EdgeEvent {
+            timestamp_ns,
+            kind,
+            offset,
+            seqno: 0,
+            line_seqno: 0,
+        })
+    }
+
+    fn events() -> Vec<Result<EdgeEvent>> {
+        vec![
+            event(1, EdgeKind::Rising, 10),
+            event(2, EdgeKind::Rising, 20),
+            Err(Error::InvalidArgument("oops".to_string())),
+            event(1, EdgeKind::Falling, 30),
+            event(2, EdgeKind::Falling, 40),
+        ]
+    }
+
+    #[test]
+    fn filter_line() {
+        let evts: Vec<Result<EdgeEvent>> = events().into_iter().filter_line(1).collect();
+        assert_eq!(
+            evts,
+            vec![
+                event(1, EdgeKind::Rising, 10),
+                Err(Error::InvalidArgument("oops".to_string())),
+                event(1, EdgeKind::Falling, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_kind() {
+        let evts: Vec<Result<EdgeEvent>> = events()
+            .into_iter()
+            .filter_kind(EdgeKind::Falling)
+            .collect();
+        assert_eq!(
+            evts,
+            vec![
+                Err(Error::InvalidArgument("oops".to_string())),
+                event(1, EdgeKind::Falling, 30),
+                event(2, EdgeKind::Falling, 40),
+            ]
+        );
+    }
+
+    #[test]
+    fn map_timestamps() {
+        let evts: Vec<Result<EdgeEvent>> = events()
+            .into_iter()
+            .filter_line(2)
+            .map_timestamps(|t| t - 20)
+            .collect();
+        assert_eq!(
+            evts,
+            vec![
+                event(2, EdgeKind::Rising, 0),
+                Err(Error::InvalidArgument("oops".to_string())),
+                event(2, EdgeKind::Falling, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn chunks() {
+        let chunks: Vec<Result<Vec<EdgeEvent>>> = events().into_iter().chunks(2).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                Ok(vec![
+                    event(1, EdgeKind::Rising, 10).unwrap(),
+                    event(2, EdgeKind::Rising, 20).unwrap()
+                ]),
+                Err(Error::InvalidArgument("oops".to_string())),
+                Ok(vec![
+                    event(1, EdgeKind::Falling, 30).unwrap(),
+                    event(2, EdgeKind::Falling, 40).unwrap()
+                ]),
+            ]
+        );
+
+        // partial chunks
+        let chunks: Vec<Result<Vec<EdgeEvent>>> = events().into_iter().chunks(3).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                Ok(vec![
+                    event(1, EdgeKind::Rising, 10).unwrap(),
+                    event(2, EdgeKind::Rising, 20).unwrap()
+                ]),
+                Err(Error::InvalidArgument("oops".to_string())),
+                Ok(vec![
+                    event(1, EdgeKind::Falling, 30).unwrap(),
+                    event(2, EdgeKind::Falling, 40).unwrap()
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunks_zero() {
+        let _ = events().into_iter().chunks(0);
+    }
+
+    #[test]
+    fn coalesce() {
+        let chatter = vec![
+            event(1, EdgeKind::Rising, 100),
+            event(1, EdgeKind::Falling, 105),
+            event(2, EdgeKind::Rising, 106),
+            event(1, EdgeKind::Rising, 109),
+            Err(Error::InvalidArgument("oops".to_string())),
+            event(1, EdgeKind::Falling, 110),
+            event(2, EdgeKind::Falling, 112),
+            event(1, EdgeKind::Rising, 115),
+        ];
+        let mut c = chatter.into_iter().coalesce(Duration::from_nanos(10));
+        let evts: Vec<Result<EdgeEvent>> = c.by_ref().collect();
+        assert_eq!(
+            evts,
+            vec![
+                event(1, EdgeKind::Rising, 100),
+                event(2, EdgeKind::Rising, 106),
+                Err(Error::InvalidArgument("oops".to_string())),
+                event(1, EdgeKind::Falling, 110),
+            ]
+        );
+        assert_eq!(c.merged(), 4);
+        assert_eq!(c.merged_on(1), 3);
+        assert_eq!(c.merged_on(2), 1);
+        assert_eq!(c.merged_on(3), 0);
+
+        // zero interval passes everything
+        let mut c = events().into_iter().coalesce(Duration::ZERO);
+        assert_eq!(c.by_ref().count(), 5);
+        assert_eq!(c.merged(), 0);
+    }
+
+    #[cfg(feature = "async")]
+    mod stream {
+        use super::*;
+        use futures::executor::block_on;
+        use futures::stream::{self, StreamExt};
+
+        #[test]
+        fn filter_line() {
+            let s = EdgeEventStreamExt::filter_line(stream::iter(events()), 2);
+            let evts: Vec<Result<EdgeEvent>> = block_on(s.collect());
+            assert_eq!(
+                evts,
+                vec![
+                    event(2, EdgeKind::Rising, 20),
+                    Err(Error::InvalidArgument("oops".to_string())),
+                    event(2, EdgeKind::Falling, 40),
+                ]
+            );
+        }
+
+        #[test]
+        fn filter_kind() {
+            let s = EdgeEventStreamExt::filter_kind(stream::iter(events()), EdgeKind::Rising);
+            let evts: Vec<Result<EdgeEvent>> = block_on(s.collect());
+            assert_eq!(
+                evts,
+                vec![
+                    event(1, EdgeKind::Rising, 10),
+                    event(2, EdgeKind::Rising, 20),
+                    Err(Error::InvalidArgument("oops".to_string())),
+                ]
+            );
+        }
+
+        #[test]
+        fn map_timestamps() {
+            let s = EdgeEventStreamExt::map_timestamps(stream::iter(events()), |t| t * 2);
+            let evts: Vec<Result<EdgeEvent>> = block_on(s.collect());
+            assert_eq!(evts[0], event(1, EdgeKind::Rising, 20));
+            assert_eq!(evts[4], event(2, EdgeKind::Falling, 80));
+        }
+
+        #[test]
+        fn coalesce() {
+            let s = EdgeEventStreamExt::coalesce(stream::iter(events()), Duration::from_nanos(25));
+            let evts: Vec<Result<EdgeEvent>> = block_on(s.collect());
+            assert_eq!(
+                evts,
+                vec![
+                    event(1, EdgeKind::Rising, 10),
+                    event(2, EdgeKind::Rising, 20),
+                    Err(Error::InvalidArgument("oops".to_string())),
+                ]
+            );
+        }
+
+        #[test]
+        fn chunks() {
+            let s = EdgeEventStreamExt::chunks(stream::iter(events()), 3);
+            let chunks: Vec<Result<Vec<EdgeEvent>>> = block_on(s.collect());
+            assert_eq!(chunks.len(), 3);
+            assert_eq!(chunks[0].as_ref().unwrap().len(), 2);
+            assert!(chunks[1].is_err());
+            assert_eq!(chunks[2].as_ref().unwrap().len(), 2);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::line::EdgeEvent;
+use crate::line::{EdgeEvent, EdgeKind};
 use crate::{Request, Result};
 use std::cmp::max;
 use std::time::Duration;
@@ -17,10 +17,10 @@ pub struct EdgeEventBuffer<'a> {
     /// The size of an individual edge event stored in the buffer.
     event_u64_size: usize,
 
-    /// The number of u64s currently written into the buffer
-    filled: usize,
+    /// The events decoded from the most recent read.
+    events: Vec<EdgeEvent>,
 
-    /// The number of u64s currently read from the buffer.
+    /// The number of events currently read from the buffer.
     read: usize,
 
     /// The buffer for uAPI edge events, sized by event size and capacity
@@ -28,28 +28,29 @@ pub struct EdgeEventBuffer<'a> {
 }
 
 impl<'a> EdgeEventBuffer<'a> {
-    pub(super) fn new(req: &Request, event_size: usize, capacity: usize) -> EdgeEventBuffer {
+    pub(super) fn new(req: &Request, event_size: usize, capacity: usize) -> EdgeEventBuffer<'_> {
         debug_assert!(event_size % 8 == 0);
         let event_u64_size = event_size / 8;
+        let capacity = max(capacity, 1);
         EdgeEventBuffer {
             req,
             event_u64_size,
-            filled: 0,
+            events: Vec::with_capacity(capacity),
             read: 0,
-            buf: vec![0_u64; max(capacity, 1) * event_u64_size],
+            buf: vec![0_u64; capacity * event_u64_size],
         }
     }
 
     /// The number of events that can be stored in the buffer.
     pub fn capacity(&self) -> usize {
-        self.buf.capacity() / self.event_u64_size
+        self.buf.len() / self.event_u64_size
     }
 
     /// The number of unread events currently stored in this buffer.
     ///
     /// This does not include events which may be buffered in the kernel.
     pub fn len(&self) -> usize {
-        (self.filled - self.read) / self.event_u64_size
+        self.events.len() - self.read
     }
 
     /// Returns true if there are no unread events in the buffer.
@@ -57,12 +58,12 @@ impl<'a> EdgeEventBuffer<'a> {
     /// This does not check whether events are available in the kernel buffer,
     /// only if there are events currently stored in this buffer.
     pub fn is_empty(&self) -> bool {
-        self.read >= self.filled
+        self.read >= self.events.len()
     }
 
     /// Returns true when either the buffer, or the request, has edge events available to read.
     pub fn has_event(&mut self) -> Result<bool> {
-        if self.read < self.filled {
+        if !self.is_empty() {
             return Ok(true);
         }
         self.req.has_edge_event()
@@ -77,23 +78,47 @@ impl<'a> EdgeEventBuffer<'a> {
     /// [`has_event`]: #method.has_event
     /// [`wait_event`]: #method.wait_event
     pub fn read_event(&mut self) -> Result<EdgeEvent> {
-        if self.read < self.filled {
-            let evt_end = self.read + self.event_u64_size;
-            let evt = &self.buf[self.read..evt_end];
-            self.read = evt_end;
-            return self.req.edge_event_from_slice(evt);
+        if self.read < self.events.len() {
+            let evt = self.events[self.read].clone();
+            self.read += 1;
+            return Ok(evt);
         }
         self.read = 0;
-        self.filled = 0;
-        let n = self.req.read_edge_events_into_slice(&mut self.buf)?;
-        // Could turn these into run-time errors, but they should never happen
-        // so make them asserts to keep it simple.
+        self.events.clear();
+        let events = &mut self.events;
+        let n = self
+            .req
+            .read_edge_events_with(&mut self.buf, |evt| events.push(evt))?;
+        // Could turn this into a run-time error, but it should never happen
+        // so make it an assert to keep it simple.
         assert!(n > 0);
-        assert_eq!(n % (self.event_u64_size), 0);
-        self.filled = n;
-        self.read = self.event_u64_size;
-        self.req
-            .edge_event_from_slice(&self.buf[0..self.event_u64_size])
+        self.read = 1;
+        Ok(self.events[0].clone())
+    }
+
+    // Touch every page of the buffers, so later reads into them do not page fault.
+    //
+    // Only valid while the buffer is empty.
+    pub(crate) fn prefault(&mut self) {
+        let page_u64s = 4096 / std::mem::size_of::<u64>();
+        for w in self.buf.iter_mut().step_by(page_u64s) {
+            // SAFETY: writing to a valid location within the buffer.
+            unsafe { std::ptr::write_volatile(w, 0) };
+        }
+        if let Some(w) = self.buf.last_mut() {
+            // SAFETY: writing to a valid location within the buffer.
+            unsafe { std::ptr::write_volatile(w, 0) };
+        }
+        let evt = EdgeEvent {
+            timestamp_ns: 0,
+            kind: EdgeKind::Rising,
+            offset: 0,
+            seqno: 0,
+            line_seqno: 0,
+        };
+        self.events.resize(self.events.capacity(), evt);
+        self.events.clear();
+        self.read = 0;
     }
 
     /// Wait for an edge event from the request.
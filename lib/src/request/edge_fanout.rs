@@ -0,0 +1,331 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{Stop, Worker};
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+use crate::{Request, Result};
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Selects the edge events delivered to a [`Subscription`].
+///
+/// The default filter passes all events.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EdgeFilter {
+    offsets: Vec<Offset>,
+    kind: Option<EdgeKind>,
+}
+
+impl EdgeFilter {
+    /// Only pass events of a particular kind.
+    pub fn with_kind(&mut self, kind: EdgeKind) -> &mut Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only pass events from a particular line.
+    ///
+    /// May be called multiple times to pass events from several lines.
+    pub fn with_line(&mut self, offset: Offset) -> &mut Self {
+        if !self.offsets.contains(&offset) {
+            self.offsets.push(offset);
+        }
+        self
+    }
+
+    /// Returns true if the event passes the filter.
+    pub fn matches(&self, evt: &EdgeEvent) -> bool {
+        if let Some(kind) = self.kind {
+            if evt.kind != kind {
+                return false;
+            }
+        }
+        self.offsets.is_empty() || self.offsets.contains(&evt.offset)
+    }
+}
+
+// The events delivered to, and dropped from, a subscription.
+#[derive(Default)]
+struct Counters {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+enum Tx {
+    Unbounded(Sender<EdgeEvent>),
+    Bounded(SyncSender<EdgeEvent>),
+}
+
+struct Subscriber {
+    filter: EdgeFilter,
+    tx: Tx,
+    counters: Arc<Counters>,
+}
+
+impl Subscriber {
+    // Deliver the event to the subscription.
+    //
+    // Returns false if the subscription has been dropped.
+    fn deliver(&self, evt: &EdgeEvent) -> bool {
+        if !self.filter.matches(evt) {
+            return true;
+        }
+        let res = match &self.tx {
+            Tx::Unbounded(tx) => tx
+                .send(evt.clone())
+                .map_err(|e| TrySendError::Disconnected(e.0)),
+            Tx::Bounded(tx) => tx.try_send(evt.clone()),
+        };
+        match res {
+            Ok(()) => {
+                self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// Distributes the edge events from a [`Request`] to multiple subscribers.
+///
+/// Created by [`Request::fanout_edges`].
+///
+/// The edge events are read from the request by a background thread, and each event
+/// is delivered to every [`Subscription`] with a matching [`EdgeFilter`].
+/// This allows consumers interested in different lines or edges to share a request
+/// with edge detection enabled for all of them, without reconfiguring the request
+/// as consumers come and go.
+///
+/// Subscriptions are unbounded by default. A [bounded](EdgeFanout::subscribe_bounded)
+/// subscription drops events rather than fall behind without limit, and each
+/// subscription reports its [lag](Subscription::lag) and [drops](Subscription::dropped).
+///
+/// The events are consumed by the fanout, so should not be read from the request
+/// while the fanout is active.
+///
+/// Events are no longer delivered once the fanout is dropped, or if reading from
+/// the request fails.
+pub struct EdgeFanout<'a> {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    _worker: Worker,
+    _req: PhantomData<&'a Request>,
+}
+
+impl<'a> EdgeFanout<'a> {
+    pub(super) fn new(req: &'a Request) -> Result<EdgeFanout<'a>> {
+        let req = req.try_clone()?;
+        let subscribers: Arc<Mutex<Vec<Subscriber>>> = Arc::default();
+        let thread_subscribers = subscribers.clone();
+        let worker = Worker::spawn(move |stop| {
+            let res = fanout(&req, &thread_subscribers, stop);
+            // disconnect any remaining subscriptions
+            thread_subscribers.lock().unwrap().clear();
+            res
+        })?;
+        Ok(EdgeFanout {
+            subscribers,
+            _worker: worker,
+            _req: PhantomData,
+        })
+    }
+
+    /// Subscribe to the events that pass the filter.
+    ///
+    /// Only events read after the subscription is created are delivered.
+    pub fn subscribe(&self, filter: &EdgeFilter) -> Subscription {
+        let (tx, rx) = mpsc::channel();
+        self.add_subscriber(filter, Tx::Unbounded(tx), rx)
+    }
+
+    /// Subscribe to the events that pass the filter, buffering at most `capacity` events.
+    ///
+    /// Events that arrive while the subscription is full are dropped, and counted by
+    /// [`Subscription::dropped`], so a slow subscriber does not delay or grow without
+    /// bound at the expense of the other subscribers.
+    ///
+    /// A zero capacity is treated as a capacity of 1.
+    pub fn subscribe_bounded(&self, filter: &EdgeFilter, capacity: usize) -> Subscription {
+        let (tx, rx) = mpsc::sync_channel(capacity.max(1));
+        self.add_subscriber(filter, Tx::Bounded(tx), rx)
+    }
+
+    fn add_subscriber(&self, filter: &EdgeFilter, tx: Tx, rx: Receiver<EdgeEvent>) -> Subscription {
+        let counters: Arc<Counters> = Arc::default();
+        self.subscribers.lock().unwrap().push(Subscriber {
+            filter: filter.clone(),
+            tx,
+            counters: counters.clone(),
+        });
+        Subscription {
+            rx,
+            counters,
+            received: Cell::new(0),
+        }
+    }
+
+    /// Subscribe to events of a particular kind from all lines.
+    pub fn subscribe_kind(&self, kind: EdgeKind) -> Subscription {
+        self.subscribe(EdgeFilter::default().with_kind(kind))
+    }
+
+    /// The number of active subscriptions.
+    ///
+    /// Dropped subscriptions are only detected when the next matching event is delivered,
+    /// so may be included in the count until then.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+fn fanout(req: &Request, subscribers: &Mutex<Vec<Subscriber>>, stop: &Stop) -> Result<()> {
+    while stop.wait_readable(req)? {
+        let evt = req.read_edge_event()?;
+        // drop subscribers whose subscription has been dropped
+        subscribers.lock().unwrap().retain(|s| s.deliver(&evt));
+    }
+    Ok(())
+}
+
+/// A stream of edge events from an [`EdgeFanout`].
+///
+/// Iterating over the subscription blocks until the next event is available,
+/// and ends once the fanout is dropped.
+pub struct Subscription {
+    rx: Receiver<EdgeEvent>,
+    counters: Arc<Counters>,
+    received: Cell<u64>,
+}
+
+impl Subscription {
+    /// Wait for the next event.
+    ///
+    /// Returns None if the fanout has been dropped.
+    pub fn recv(&self) -> Option<EdgeEvent> {
+        self.received(self.rx.recv().ok())
+    }
+
+    /// Wait up to a timeout for the next event.
+    ///
+    /// Returns None if no event arrived within the timeout, or if the fanout has been dropped.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<EdgeEvent> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(evt) => self.received(Some(evt)),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Return the next event if one is immediately available.
+    pub fn try_recv(&self) -> Option<EdgeEvent> {
+        self.received(self.rx.try_recv().ok())
+    }
+
+    /// The number of events delivered to the subscription but not yet received.
+    pub fn lag(&self) -> u64 {
+        // delivered is updated after the send, so may briefly trail received
+        self.counters
+            .delivered
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.received.get())
+    }
+
+    /// The number of events dropped as the subscription was full.
+    ///
+    /// Only bounded subscriptions drop events.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    fn received(&self, evt: Option<EdgeEvent>) -> Option<EdgeEvent> {
+        if evt.is_some() {
+            self.received.set(self.received.get() + 1);
+        }
+        evt
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = EdgeEvent;
+
+    fn next(&mut self) -> Option<EdgeEvent> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(offset: Offset, kind: EdgeKind) -> EdgeEvent {
+        EdgeEvent {
+            timestamp_ns: 0,
+            kind,
+            offset,
+            seqno: 0,
+            line_seqno: 0,
+        }
+    }
+
+    #[test]
+    fn filter() {
+        let f = EdgeFilter::default();
+        assert!(f.matches(&event(1, EdgeKind::Rising)));
+        assert!(f.matches(&event(2, EdgeKind::Falling)));
+
+        let mut f = EdgeFilter::default();
+        f.with_kind(EdgeKind::Rising);
+        assert!(f.matches(&event(1, EdgeKind::Rising)));
+        assert!(!f.matches(&event(1, EdgeKind::Falling)));
+
+        f.with_line(3).with_line(5).with_line(3);
+        assert_eq!(f.offsets, &[3, 5]);
+        assert!(!f.matches(&event(1, EdgeKind::Rising)));
+        assert!(f.matches(&event(3, EdgeKind::Rising)));
+        assert!(f.matches(&event(5, EdgeKind::Rising)));
+        assert!(!f.matches(&event(5, EdgeKind::Falling)));
+    }
+
+    #[test]
+    fn bounded_subscriber() {
+        let (tx, rx) = mpsc::sync_channel(2);
+        let counters: Arc<Counters> = Arc::default();
+        let mut filter = EdgeFilter::default();
+        filter.with_kind(EdgeKind::Rising);
+        let sub = Subscriber {
+            filter,
+            tx: Tx::Bounded(tx),
+            counters: counters.clone(),
+        };
+        let subscription = Subscription {
+            rx,
+            counters,
+            received: Cell::new(0),
+        };
+        for _ in 0..4 {
+            assert!(sub.deliver(&event(1, EdgeKind::Rising)));
+        }
+        // filtered events are neither delivered nor dropped
+        assert!(sub.deliver(&event(1, EdgeKind::Falling)));
+        assert_eq!(subscription.lag(), 2);
+        assert_eq!(subscription.dropped(), 2);
+
+        assert!(subscription.try_recv().is_some());
+        assert_eq!(subscription.lag(), 1);
+        assert!(sub.deliver(&event(1, EdgeKind::Rising)));
+        assert_eq!(subscription.lag(), 2);
+        assert_eq!(subscription.dropped(), 2);
+
+        drop(subscription);
+        assert!(!sub.deliver(&event(1, EdgeKind::Rising)));
+        // filtered events do not detect the dropped subscription
+        assert!(sub.deliver(&event(1, EdgeKind::Falling)));
+    }
+}
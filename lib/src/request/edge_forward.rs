@@ -0,0 +1,158 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{Stop, Worker};
+use crate::line::EdgeEvent;
+use crate::{Request, Result};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+
+/// The sending half of a channel that edge events can be forwarded into.
+///
+/// Implemented for the `std::sync::mpsc` senders, and may be implemented for other
+/// channels, such as those provided by crossbeam, by mapping their send errors
+/// to the equivalent [`TrySendError`].
+pub trait EdgeEventSink: Send + 'static {
+    /// Attempt to send an event without blocking.
+    fn try_send(&self, evt: EdgeEvent) -> std::result::Result<(), TrySendError<EdgeEvent>>;
+}
+
+impl EdgeEventSink for Sender<EdgeEvent> {
+    fn try_send(&self, evt: EdgeEvent) -> std::result::Result<(), TrySendError<EdgeEvent>> {
+        self.send(evt).map_err(|e| TrySendError::Disconnected(e.0))
+    }
+}
+
+impl EdgeEventSink for SyncSender<EdgeEvent> {
+    fn try_send(&self, evt: EdgeEvent) -> std::result::Result<(), TrySendError<EdgeEvent>> {
+        SyncSender::try_send(self, evt)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Forwards the edge events from a [`Request`] into a channel.
+///
+/// Created by [`Request::events_to_channel`].
+///
+/// The edge events are read from the request by a background thread, and sent
+/// to the channel.  Events that arrive while a bounded channel is full are dropped,
+/// rather than stall reading the request, and are counted by [`dropped`].
+///
+/// The events are consumed by the forwarder, so should not be read from the request
+/// while the forwarder is active.
+///
+/// Forwarding ends when the forwarder is dropped or [`stop`]ped, when the receiving
+/// half of the channel is dropped, or if reading from the request fails.
+///
+/// [`dropped`]: EdgeForwarder::dropped
+/// [`stop`]: EdgeForwarder::stop
+pub struct EdgeForwarder<'a> {
+    counters: Arc<Counters>,
+    worker: Worker,
+    _req: PhantomData<&'a Request>,
+}
+
+impl<'a> EdgeForwarder<'a> {
+    pub(super) fn new<S: EdgeEventSink>(req: &'a Request, sink: S) -> Result<EdgeForwarder<'a>> {
+        let req = req.try_clone()?;
+        let counters: Arc<Counters> = Arc::default();
+        let thread_counters = counters.clone();
+        let worker = Worker::spawn(move |stop| forward(&req, &sink, &thread_counters, stop))?;
+        Ok(EdgeForwarder {
+            counters,
+            worker,
+            _req: PhantomData,
+        })
+    }
+
+    /// The number of events sent to the channel.
+    pub fn forwarded(&self) -> u64 {
+        self.counters.forwarded.load(Ordering::Relaxed)
+    }
+
+    /// The number of events dropped as the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if forwarding has ended.
+    ///
+    /// This occurs when the receiving half of the channel is dropped, or if reading
+    /// from the request fails.
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Stop forwarding events.
+    ///
+    /// Returns the error that ended forwarding, if reading from the request failed.
+    pub fn stop(mut self) -> Result<()> {
+        self.worker.stop()
+    }
+}
+
+fn forward<S: EdgeEventSink>(
+    req: &Request,
+    sink: &S,
+    counters: &Counters,
+    stop: &Stop,
+) -> Result<()> {
+    while stop.wait_readable(req)? {
+        let evt = req.read_edge_event()?;
+        match sink.try_send(evt) {
+            Ok(()) => counters.forwarded.fetch_add(1, Ordering::Relaxed),
+            Err(TrySendError::Full(_)) => counters.dropped.fetch_add(1, Ordering::Relaxed),
+            Err(TrySendError::Disconnected(_)) => break,
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::EdgeKind;
+    use std::sync::mpsc;
+
+    fn event() -> EdgeEvent {
+        EdgeEvent {
+            timestamp_ns: 0,
+            kind: EdgeKind::Rising,
+            offset: 1,
+            seqno: 0,
+            line_seqno: 0,
+        }
+    }
+
+    #[test]
+    fn sinks() {
+        let (tx, rx) = mpsc::channel();
+        assert!(EdgeEventSink::try_send(&tx, event()).is_ok());
+        assert_eq!(rx.try_recv().unwrap(), event());
+        drop(rx);
+        assert!(matches!(
+            EdgeEventSink::try_send(&tx, event()),
+            Err(TrySendError::Disconnected(_))
+        ));
+
+        let (tx, rx) = mpsc::sync_channel(1);
+        assert!(EdgeEventSink::try_send(&tx, event()).is_ok());
+        assert!(matches!(
+            EdgeEventSink::try_send(&tx, event()),
+            Err(TrySendError::Full(_))
+        ));
+        drop(rx);
+        assert!(matches!(
+            EdgeEventSink::try_send(&tx, event()),
+            Err(TrySendError::Disconnected(_))
+        ));
+    }
+}
@@ -0,0 +1,75 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{Stop, Worker};
+use crate::{Error, Request, Result};
+use std::marker::PhantomData;
+
+/// Delivers a POSIX realtime signal to the process for each edge event on a [`Request`].
+///
+/// Created by [`Request::signal_edges`].
+///
+/// The edge events are read from the request by a background thread, which queues
+/// the signal to the process for each event.  The offset of the line is provided in
+/// the `si_value` of the signal, as the `sival_ptr` cast to an integer.
+///
+/// The events are consumed by the signaller, so should not be read from the request
+/// while the signaller is active.
+///
+/// Signals are no longer delivered once the signaller is dropped or [`stop`]ped,
+/// or if reading from the request or queueing the signal fails.
+///
+/// [`stop`]: EdgeSignaller::stop
+pub struct EdgeSignaller<'a> {
+    worker: Worker,
+    _req: PhantomData<&'a Request>,
+}
+
+impl<'a> EdgeSignaller<'a> {
+    pub(super) fn new(req: &'a Request, signo: i32) -> Result<EdgeSignaller<'a>> {
+        if signo < libc::SIGRTMIN() || signo > libc::SIGRTMAX() {
+            return Err(Error::InvalidArgument(format!(
+                "signal {} is not a realtime signal.",
+                signo
+            )));
+        }
+        let req = req.try_clone()?;
+        let worker = Worker::spawn(move |stop| signal(&req, signo, stop))?;
+        Ok(EdgeSignaller {
+            worker,
+            _req: PhantomData,
+        })
+    }
+
+    /// Returns true if signalling has ended.
+    ///
+    /// This occurs if reading from the request or queueing the signal fails.
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Stop delivering signals.
+    ///
+    /// Returns the error that ended signalling, if reading from the request or
+    /// queueing the signal failed.
+    pub fn stop(mut self) -> Result<()> {
+        self.worker.stop()
+    }
+}
+
+fn signal(req: &Request, signo: i32, stop: &Stop) -> Result<()> {
+    // SAFETY: getpid is always successful.
+    let pid = unsafe { libc::getpid() };
+    while stop.wait_readable(req)? {
+        let evt = req.read_edge_event()?;
+        let value = libc::sigval {
+            sival_ptr: evt.offset as usize as *mut libc::c_void,
+        };
+        // SAFETY: queueing a signal to ourselves with a plain integer value.
+        if unsafe { libc::sigqueue(pid, signo, value) } == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::EdgeEvent;
+use crate::Result;
+#[cfg(feature = "async")]
+use futures::task::{Context, Poll};
+#[cfg(feature = "async")]
+use futures::{ready, Stream};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+
+/// Edge events discarded by the kernel as its event buffer overflowed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EventLoss {
+    /// The number of events missed.
+    pub missed: u32,
+}
+
+/// An item returned by a [`DetectLoss`] adapter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EdgeOrLoss {
+    /// An edge event from the underlying iterator or stream.
+    Edge(EdgeEvent),
+
+    /// Events were lost before the next event.
+    ///
+    /// Returned before the first event read after the loss.
+    Loss(EventLoss),
+}
+
+/// An adapter that reports edge events lost to kernel event buffer overflows.
+///
+/// Created by [`EdgeEventIteratorExt::detect_loss`] or `EdgeEventStreamExt::detect_loss`.
+///
+/// Losses are detected from gaps in the event sequence numbers, which are only
+/// provided by uAPI v2, so no losses are reported for uAPI v1.
+///
+/// The sequence numbers span all the lines in the request, so the adapter must be
+/// applied before any adapter that filters events, else the filtered events are
+/// reported as lost.
+///
+/// The sequence numbers start from 1 when the lines are requested, so events lost
+/// before the first event read are also reported.  The adapter should be applied
+/// before any events are read from the request, else those events are reported
+/// as lost.
+///
+/// [`EdgeEventIteratorExt::detect_loss`]: super::EdgeEventIteratorExt::detect_loss
+pub struct DetectLoss<I> {
+    inner: I,
+    // the seqno of the previous event, or 0 before the first event
+    seqno: u32,
+    lost: u64,
+    // an event read when a loss was detected, to be returned next.
+    pending: Option<EdgeEvent>,
+}
+
+impl<I> DetectLoss<I> {
+    pub(crate) fn new(inner: I) -> Self {
+        DetectLoss {
+            inner,
+            seqno: 0,
+            lost: 0,
+            pending: None,
+        }
+    }
+
+    /// The total number of events lost since the adapter was created.
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    fn map(&mut self, res: Result<EdgeEvent>) -> Result<EdgeOrLoss> {
+        let evt = res?;
+        let prev = std::mem::replace(&mut self.seqno, evt.seqno);
+        // seqnos not provided by the ABI are always zero, so appear to go backwards,
+        // as do seqnos that are out of order, and neither is reported as a loss.
+        let missed = evt.seqno.wrapping_sub(prev).wrapping_sub(1);
+        if missed != 0 && missed < u32::MAX / 2 {
+            self.lost += missed as u64;
+            self.pending = Some(evt);
+            return Ok(EdgeOrLoss::Loss(EventLoss { missed }));
+        }
+        Ok(EdgeOrLoss::Edge(evt))
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> Iterator for DetectLoss<I> {
+    type Item = Result<EdgeOrLoss>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(evt) = self.pending.take() {
+            return Some(Ok(EdgeOrLoss::Edge(evt)));
+        }
+        let res = self.inner.next()?;
+        Some(self.map(res))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> Stream for DetectLoss<S> {
+    type Item = Result<EdgeOrLoss>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if let Some(evt) = self.pending.take() {
+            return Poll::Ready(Some(Ok(EdgeOrLoss::Edge(evt))));
+        }
+        let res = ready!(Pin::new(&mut self.inner).poll_next(cx));
+        Poll::Ready(res.map(|res| self.map(res)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::EdgeKind;
+    use crate::Error;
+
+    fn event(seqno: u32) -> Result<EdgeEvent> {
+        Ok(EdgeEvent {
+            timestamp_ns: 0,
+            kind: EdgeKind::Rising,
+            offset: 1,
+            seqno,
+            line_seqno: seqno,
+        })
+    }
+
+    fn seqnos(items: &[Result<EdgeOrLoss>]) -> Vec<i64> {
+        // losses are reported as negative counts
+        items
+            .iter()
+            .map(|item| match item {
+                Ok(EdgeOrLoss::Edge(evt)) => evt.seqno as i64,
+                Ok(EdgeOrLoss::Loss(loss)) => -(loss.missed as i64),
+                Err(_) => 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detect_loss() {
+        let events = vec![
+            event(4),
+            event(5),
+            event(8),
+            Err(Error::InvalidArgument("oops".to_string())),
+            event(9),
+            event(11),
+        ];
+        let mut dl = DetectLoss::new(events.into_iter());
+        let items: Vec<Result<EdgeOrLoss>> = dl.by_ref().collect();
+        assert_eq!(seqnos(&items), [-3, 4, 5, -2, 8, 0, 9, -1, 11]);
+        assert_eq!(dl.lost(), 6);
+    }
+
+    #[test]
+    fn no_loss_at_start() {
+        let events = vec![event(1), event(2), event(3)];
+        let mut dl = DetectLoss::new(events.into_iter());
+        let items: Vec<Result<EdgeOrLoss>> = dl.by_ref().collect();
+        assert_eq!(seqnos(&items), [1, 2, 3]);
+        assert_eq!(dl.lost(), 0);
+    }
+
+    #[test]
+    fn wrap() {
+        let events = vec![event(u32::MAX - 1), event(u32::MAX), event(0), event(2)];
+        let mut dl = DetectLoss::new(events.into_iter());
+        let items: Vec<Result<EdgeOrLoss>> = dl.by_ref().collect();
+        assert_eq!(
+            seqnos(&items),
+            [u32::MAX as i64 - 1, u32::MAX as i64, 0, -1, 2]
+        );
+        assert_eq!(dl.lost(), 1);
+    }
+
+    #[test]
+    fn no_seqno() {
+        // uAPI v1
+        let events = vec![event(0), event(0)];
+        let mut dl = DetectLoss::new(events.into_iter());
+        let items: Vec<Result<EdgeOrLoss>> = dl.by_ref().collect();
+        assert_eq!(seqnos(&items), [0, 0]);
+        assert_eq!(dl.lost(), 0);
+    }
+}
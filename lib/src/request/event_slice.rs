@@ -0,0 +1,310 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{EdgeEvent, EdgeKind, Offset};
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+use crate::AbiVersion;
+use crate::{Error, Result, UapiCall};
+#[cfg(feature = "uapi_v1")]
+use gpiocdev_uapi::v1;
+#[cfg(feature = "uapi_v2")]
+use gpiocdev_uapi::v2;
+
+/// A view of an edge event in a buffer of raw events.
+///
+/// Returned by the [`EventSlice`] iterator, and borrows the buffer the events
+/// were read into, so the event is not copied unless converted to an [`EdgeEvent`].
+///
+/// ABI v1 does not provide the seqno nor line_seqno, so they are returned as 0.
+#[derive(Clone, Copy, Debug)]
+pub struct EdgeEventRef<'a>(Raw<'a>);
+
+#[derive(Clone, Copy, Debug)]
+enum Raw<'a> {
+    // v1 events do not contain the offset, so it is provided by the request.
+    #[cfg(feature = "uapi_v1")]
+    V1(&'a v1::LineEdgeEvent, Offset),
+    #[cfg(feature = "uapi_v2")]
+    V2(&'a v2::LineEdgeEvent),
+}
+
+impl EdgeEventRef<'_> {
+    /// The best estimate of time of event occurrence, in nanoseconds.
+    ///
+    /// As per [`EdgeEvent::timestamp_ns`].
+    #[inline]
+    pub fn timestamp_ns(&self) -> u64 {
+        match self.0 {
+            #[cfg(feature = "uapi_v1")]
+            Raw::V1(le, _) => le.timestamp_ns,
+            #[cfg(feature = "uapi_v2")]
+            Raw::V2(le) => le.timestamp_ns,
+        }
+    }
+
+    /// The event trigger identifier.
+    #[inline]
+    pub fn kind(&self) -> EdgeKind {
+        match self.0 {
+            #[cfg(feature = "uapi_v1")]
+            Raw::V1(le, _) => EdgeKind::from(le.kind),
+            #[cfg(feature = "uapi_v2")]
+            Raw::V2(le) => EdgeKind::from(le.kind),
+        }
+    }
+
+    /// The offset of the line that triggered the event.
+    #[inline]
+    pub fn offset(&self) -> Offset {
+        match self.0 {
+            #[cfg(feature = "uapi_v1")]
+            Raw::V1(_, offset) => offset,
+            #[cfg(feature = "uapi_v2")]
+            Raw::V2(le) => le.offset,
+        }
+    }
+
+    /// The sequence number for this event in the sequence of events for all
+    /// the lines in the request.
+    #[inline]
+    pub fn seqno(&self) -> u32 {
+        match self.0 {
+            #[cfg(feature = "uapi_v1")]
+            Raw::V1(..) => 0,
+            #[cfg(feature = "uapi_v2")]
+            Raw::V2(le) => le.seqno,
+        }
+    }
+
+    /// The sequence number for this event in the sequence of events on this
+    /// particular line.
+    #[inline]
+    pub fn line_seqno(&self) -> u32 {
+        match self.0 {
+            #[cfg(feature = "uapi_v1")]
+            Raw::V1(..) => 0,
+            #[cfg(feature = "uapi_v2")]
+            Raw::V2(le) => le.line_seqno,
+        }
+    }
+}
+
+impl From<EdgeEventRef<'_>> for EdgeEvent {
+    fn from(er: EdgeEventRef<'_>) -> Self {
+        EdgeEvent {
+            timestamp_ns: er.timestamp_ns(),
+            kind: er.kind(),
+            offset: er.offset(),
+            seqno: er.seqno(),
+            line_seqno: er.line_seqno(),
+        }
+    }
+}
+
+/// The edge events read into a buffer by [`Request::read_edge_events_into`].
+///
+/// Iterates over views of the events in the buffer, decoding each in place.
+///
+/// [`Request::read_edge_events_into`]: crate::Request::read_edge_events_into
+#[derive(Clone, Debug)]
+pub struct EventSlice<'a> {
+    /// The raw events, trimmed to those read.
+    buf: &'a [u64],
+
+    /// The size of an individual raw event.
+    event_u64_size: usize,
+
+    /// The offset of the line, as v1 events do not contain it.
+    #[cfg(feature = "uapi_v1")]
+    offset: Offset,
+
+    /// The ABI version of the raw events.
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    abiv: AbiVersion,
+}
+
+impl<'a> EventSlice<'a> {
+    #[allow(unused_variables)]
+    pub(super) fn new(
+        buf: &'a [u64],
+        event_u64_size: usize,
+        offset: Offset,
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))] abiv: AbiVersion,
+    ) -> EventSlice<'a> {
+        EventSlice {
+            buf,
+            event_u64_size,
+            #[cfg(feature = "uapi_v1")]
+            offset,
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            abiv,
+        }
+    }
+
+    /// The number of events in the slice.
+    pub fn len(&self) -> usize {
+        self.buf.len() / self.event_u64_size
+    }
+
+    /// Returns true if there are no events in the slice.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The event at the index, if the index is in range.
+    pub fn get(&self, idx: usize) -> Option<Result<EdgeEventRef<'a>>> {
+        let start = idx.checked_mul(self.event_u64_size)?;
+        let end = start.checked_add(self.event_u64_size)?;
+        self.buf.get(start..end).map(|evt| self.decode(evt))
+    }
+
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn decode(&self, evt: &'a [u64]) -> Result<EdgeEventRef<'a>> {
+        match self.abiv {
+            AbiVersion::V1 => self.decode_v1(evt),
+            AbiVersion::V2 => self.decode_v2(evt),
+        }
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn decode(&self, evt: &'a [u64]) -> Result<EdgeEventRef<'a>> {
+        self.decode_v1(evt)
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn decode(&self, evt: &'a [u64]) -> Result<EdgeEventRef<'a>> {
+        self.decode_v2(evt)
+    }
+    #[cfg(feature = "uapi_v1")]
+    fn decode_v1(&self, evt: &'a [u64]) -> Result<EdgeEventRef<'a>> {
+        v1::LineEdgeEvent::from_slice(evt)
+            .map(|le| EdgeEventRef(Raw::V1(le, self.offset)))
+            .map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))
+    }
+    #[cfg(feature = "uapi_v2")]
+    fn decode_v2(&self, evt: &'a [u64]) -> Result<EdgeEventRef<'a>> {
+        v2::LineEdgeEvent::from_slice(evt)
+            .map(|le| EdgeEventRef(Raw::V2(le)))
+            .map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))
+    }
+}
+
+impl<'a> Iterator for EventSlice<'a> {
+    type Item = Result<EdgeEventRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let evt = self.get(0)?;
+        self.buf = &self.buf[self.event_u64_size..];
+        Some(evt)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl ExactSizeIterator for EventSlice<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    // Pack raw event fields, as native endian u32s, into u64 words.
+    fn words(fields: &[u32]) -> Vec<u64> {
+        fields
+            .chunks(2)
+            .map(|c| {
+                let mut b = [0_u8; 8];
+                b[..4].copy_from_slice(&c[0].to_ne_bytes());
+                b[4..].copy_from_slice(&c.get(1).copied().unwrap_or(0).to_ne_bytes());
+                u64::from_ne_bytes(b)
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "uapi_v2")]
+    fn v2_event(ts: u64, kind: u32, offset: u32, seqno: u32) -> Vec<u64> {
+        let mut evt = vec![ts];
+        evt.extend(words(&[kind, offset, seqno, seqno + 10]));
+        evt.resize(mem::size_of::<v2::LineEdgeEvent>() / 8, 0);
+        evt
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v2")]
+    fn decode_v2() {
+        let size = mem::size_of::<v2::LineEdgeEvent>() / 8;
+        let mut buf = v2_event(1234, 1, 3, 1);
+        buf.extend(v2_event(2345, 2, 5, 2));
+        let es = EventSlice::new(
+            &buf,
+            size,
+            0,
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            AbiVersion::V2,
+        );
+        assert_eq!(es.len(), 2);
+        assert!(!es.is_empty());
+        assert!(es.get(2).is_none());
+
+        let evts: Vec<EdgeEvent> = es.map(|er| EdgeEvent::from(er.unwrap())).collect();
+        assert_eq!(
+            evts,
+            [
+                EdgeEvent {
+                    timestamp_ns: 1234,
+                    kind: EdgeKind::Rising,
+                    offset: 3,
+                    seqno: 1,
+                    line_seqno: 11,
+                },
+                EdgeEvent {
+                    timestamp_ns: 2345,
+                    kind: EdgeKind::Falling,
+                    offset: 5,
+                    seqno: 2,
+                    line_seqno: 12,
+                }
+            ]
+        );
+
+        // invalid kind
+        let buf = v2_event(1234, 3, 3, 1);
+        let mut es = EventSlice::new(
+            &buf,
+            size,
+            0,
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            AbiVersion::V2,
+        );
+        assert!(matches!(
+            es.next(),
+            Some(Err(Error::Uapi(UapiCall::LEEFromBuf, _)))
+        ));
+        assert!(es.next().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn decode_v1() {
+        let size = mem::size_of::<v1::LineEdgeEvent>() / 8;
+        let mut buf = vec![1234];
+        buf.extend(words(&[1]));
+        buf.resize(size, 0);
+        let mut es = EventSlice::new(
+            &buf,
+            size,
+            7,
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            AbiVersion::V1,
+        );
+        assert_eq!(es.len(), 1);
+        let er = es.next().unwrap().unwrap();
+        assert_eq!(er.timestamp_ns(), 1234);
+        assert_eq!(er.kind(), EdgeKind::Rising);
+        assert_eq!(er.offset(), 7);
+        assert_eq!(er.seqno(), 0);
+        assert_eq!(er.line_seqno(), 0);
+        assert!(es.next().is_none());
+    }
+}
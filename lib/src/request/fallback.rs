@@ -0,0 +1,180 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Config;
+use crate::line::{EventClock, Offset};
+use std::fmt;
+use std::time::Duration;
+
+/// A setting dropped when degrading a configuration to uAPI v1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Degradation {
+    /// The debounce period of a line was dropped, so the line is not debounced.
+    Debounce {
+        /// The line that was to be debounced.
+        offset: Offset,
+
+        /// The debounce period that was dropped.
+        period: Duration,
+    },
+
+    /// The event clock of a line was dropped, so edge events are timestamped
+    /// using the kernel default clock.
+    EventClock {
+        /// The line with the event clock.
+        offset: Offset,
+
+        /// The event clock that was dropped.
+        clock: EventClock,
+    },
+
+    /// The requested kernel event buffer size was dropped, so the kernel default
+    /// buffer size is used.
+    KernelEventBufferSize(u32),
+}
+
+impl fmt::Display for Degradation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Degradation::Debounce { offset, period } => {
+                write!(f, "line {}: dropped debounce period {:?}", offset, period)
+            }
+            Degradation::EventClock { offset, clock } => {
+                write!(f, "line {}: dropped event clock {:?}", offset, clock)
+            }
+            Degradation::KernelEventBufferSize(size) => {
+                write!(f, "dropped kernel event buffer size {}", size)
+            }
+        }
+    }
+}
+
+/// The settings dropped when a configuration was degraded to uAPI v1.
+///
+/// Returned by [`Request::fallback_report`](super::Request::fallback_report).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FallbackReport {
+    /// The settings that were dropped, in offset order.
+    pub degradations: Vec<Degradation>,
+}
+
+impl FallbackReport {
+    /// Returns true if no settings were dropped.
+    pub fn is_empty(&self) -> bool {
+        self.degradations.is_empty()
+    }
+
+    // Log the dropped settings.
+    pub(super) fn trace(&self) {
+        #[cfg(feature = "tracing")]
+        for d in &self.degradations {
+            tracing::warn!("uAPI v1 fallback {}", d);
+        }
+    }
+}
+
+impl fmt::Display for FallbackReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, d) in self.degradations.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+// Drop the line settings not supported by uAPI v1.
+pub(super) fn degrade(cfg: &mut Config) -> FallbackReport {
+    let mut report = FallbackReport::default();
+    let mut offsets = cfg.offsets.clone();
+    offsets.sort_unstable();
+    for offset in offsets {
+        // unwrap is safe here as offsets match lcfg keys
+        let lcfg = cfg.lcfg.get_mut(&offset).unwrap();
+        if let Some(period) = lcfg.debounce_period.take() {
+            report
+                .degradations
+                .push(Degradation::Debounce { offset, period });
+        }
+        if let Some(clock) = lcfg.event_clock.take() {
+            report
+                .degradations
+                .push(Degradation::EventClock { offset, clock });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::EdgeDetection;
+
+    #[test]
+    fn degrade() {
+        let mut cfg = Config::default();
+        cfg.with_line(5)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .with_debounce_period(Duration::from_millis(2))
+            .with_event_clock(EventClock::Realtime)
+            .with_line(3)
+            .with_debounce_period(Duration::from_millis(1))
+            .with_line(4)
+            .as_input();
+        let report = super::degrade(&mut cfg);
+        assert_eq!(
+            report.degradations,
+            [
+                Degradation::Debounce {
+                    offset: 3,
+                    period: Duration::from_millis(1)
+                },
+                Degradation::Debounce {
+                    offset: 5,
+                    period: Duration::from_millis(2)
+                },
+                Degradation::EventClock {
+                    offset: 5,
+                    clock: EventClock::Realtime
+                },
+            ]
+        );
+        // remaining settings are retained
+        assert_eq!(
+            cfg.line_config(5).unwrap().edge_detection,
+            Some(EdgeDetection::BothEdges)
+        );
+        assert!(cfg.lcfg.values().all(|lc| lc.debounce_period.is_none()));
+        assert!(cfg.lcfg.values().all(|lc| lc.event_clock.is_none()));
+
+        // nothing left to drop
+        assert!(super::degrade(&mut cfg).is_empty());
+    }
+
+    #[test]
+    fn display() {
+        let report = FallbackReport {
+            degradations: vec![
+                Degradation::KernelEventBufferSize(42),
+                Degradation::Debounce {
+                    offset: 3,
+                    period: Duration::from_millis(1),
+                },
+                Degradation::EventClock {
+                    offset: 3,
+                    clock: EventClock::Hte,
+                },
+            ],
+        };
+        assert_eq!(
+            report.to_string(),
+            "dropped kernel event buffer size 42\n\
+            line 3: dropped debounce period 1ms\n\
+            line 3: dropped event clock Hte"
+        );
+        assert_eq!(FallbackReport::default().to_string(), "");
+    }
+}
@@ -0,0 +1,309 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{Offset, Value, Values};
+#[cfg(feature = "uapi_v1")]
+use gpiocdev_uapi::v1;
+#[cfg(feature = "uapi_v2")]
+use gpiocdev_uapi::v2;
+
+// The mapping from the requested offsets to their index in the uAPI line values.
+//
+// Built once when the request is made, so getting and setting values only has
+// to look up the offsets being accessed, rather than merge them with the full
+// set of requested offsets on every call.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct LineMap {
+    // The requested offsets, sorted.
+    pub(crate) offsets: Vec<Offset>,
+
+    // The first offset, if the offsets form a contiguous range, so the index
+    // is a simple subtraction.
+    base: Option<Offset>,
+
+    // The line values that access all the requested lines.
+    #[cfg(feature = "uapi_v2")]
+    all: v2::LineValues,
+}
+
+impl LineMap {
+    // The offsets must be sorted.
+    pub(crate) fn new(offsets: Vec<Offset>) -> LineMap {
+        let base = match (offsets.first(), offsets.last()) {
+            (Some(first), Some(last)) if (last - first) as usize + 1 == offsets.len() => {
+                Some(*first)
+            }
+            _ => None,
+        };
+        #[cfg(feature = "uapi_v2")]
+        let all = v2::LineValues {
+            mask: match offsets.len() {
+                n if n < 64 => (0x01_u64 << n) - 1,
+                _ => !0_u64,
+            },
+            ..Default::default()
+        };
+        LineMap {
+            offsets,
+            base,
+            #[cfg(feature = "uapi_v2")]
+            all,
+        }
+    }
+
+    // The index of the offset in the requested lines.
+    #[inline]
+    pub(crate) fn index(&self, offset: Offset) -> Option<usize> {
+        match self.base {
+            Some(base) => offset
+                .checked_sub(base)
+                .map(|idx| idx as usize)
+                .filter(|idx| *idx < self.offsets.len()),
+            None => self.offsets.binary_search(&offset).ok(),
+        }
+    }
+
+    // Returns true if values are defined for all the requested lines.
+    #[cfg(feature = "uapi_v1")]
+    pub(crate) fn contains_all(&self, values: &Values) -> bool {
+        values
+            .iter()
+            .filter(|lv| self.index(lv.offset).is_some())
+            .count()
+            == self.offsets.len()
+    }
+
+    // v1 values are a contiguous list.  If a list shorter than offsets
+    // is presented to the kernel then the missing lines default to zero.
+    // Values for lines not in the request are ignored.
+    #[cfg(feature = "uapi_v1")]
+    pub(crate) fn to_v1(&self, values: &Values) -> v1::LineValues {
+        let mut dst = v1::LineValues::default();
+        for lv in values.iter() {
+            if let Some(idx) = self.index(lv.offset) {
+                dst.set(idx, lv.value.into());
+            }
+        }
+        dst
+    }
+
+    // v2 values are a bitmap in offsets ordering.
+    // Empty values select all the requested lines.
+    // Values for lines not in the request are ignored.
+    #[cfg(feature = "uapi_v2")]
+    pub(crate) fn to_v2(&self, values: &Values) -> v2::LineValues {
+        if values.is_empty() {
+            return self.all;
+        }
+        let mut dst = v2::LineValues::default();
+        for lv in values.iter() {
+            if let Some(idx) = self.index(lv.offset) {
+                dst.set(idx, lv.value.into());
+            }
+        }
+        dst
+    }
+
+    // Update the values from the values read from the kernel.
+    // Empty values are populated with all the requested lines.
+    #[cfg(feature = "uapi_v1")]
+    pub(crate) fn update_from_v1(&self, values: &mut Values, src: &v1::LineValues) {
+        if values.is_empty() {
+            values.extend(
+                self.offsets
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, offset)| (*offset, Value::from(src.get(idx)))),
+            );
+            return;
+        }
+        for lv in values.iter_mut() {
+            if let Some(idx) = self.index(lv.offset) {
+                lv.value = Value::from(src.get(idx));
+            }
+        }
+    }
+
+    // Update the values from the values read from the kernel.
+    // Only the lines set in the src mask are updated.
+    // Empty values are populated with all the requested lines.
+    #[cfg(feature = "uapi_v2")]
+    pub(crate) fn update_from_v2(&self, values: &mut Values, src: &v2::LineValues) {
+        if values.is_empty() {
+            values.extend(
+                self.offsets
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, offset)| (*offset, Value::from(src.get(idx).unwrap_or_default()))),
+            );
+            return;
+        }
+        for lv in values.iter_mut() {
+            if let Some(v) = self.index(lv.offset).and_then(|idx| src.get(idx)) {
+                lv.value = Value::from(v);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        let lm = LineMap::new(vec![1, 3, 5, 8]);
+        assert_eq!(lm.base, None);
+        assert_eq!(lm.index(0), None);
+        assert_eq!(lm.index(1), Some(0));
+        assert_eq!(lm.index(3), Some(1));
+        assert_eq!(lm.index(4), None);
+        assert_eq!(lm.index(5), Some(2));
+        assert_eq!(lm.index(8), Some(3));
+        assert_eq!(lm.index(9), None);
+
+        let lm = LineMap::new(vec![4, 5, 6]);
+        assert_eq!(lm.base, Some(4));
+        assert_eq!(lm.index(3), None);
+        assert_eq!(lm.index(4), Some(0));
+        assert_eq!(lm.index(6), Some(2));
+        assert_eq!(lm.index(7), None);
+
+        let lm = LineMap::new(Vec::new());
+        assert_eq!(lm.index(0), None);
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn contains_all() {
+        let lm = LineMap::new(vec![1, 2, 3]);
+        assert!(!lm.contains_all(&Values::from_offsets(&[1, 3])));
+        assert!(!lm.contains_all(&Values::from_offsets(&[0, 1, 2])));
+        assert!(lm.contains_all(&Values::from_offsets(&[1, 2, 3])));
+        assert!(lm.contains_all(&Values::from_offsets(&[0, 1, 2, 3, 4])));
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn from_v1() {
+        let lm = LineMap::new(vec![1, 3, 5, 8]);
+        let src = v1::LineValues::from_slice(&[1, 0, 1, 1]);
+        // full set
+        let mut dst = Values::default();
+        lm.update_from_v1(&mut dst, &src);
+        assert_eq!(dst.get(1), Some(Value::Active));
+        assert_eq!(dst.get(2), None);
+        assert_eq!(dst.get(3), Some(Value::Inactive));
+        assert_eq!(dst.get(4), None);
+        assert_eq!(dst.get(5), Some(Value::Active));
+        assert_eq!(dst.get(6), None);
+        assert_eq!(dst.get(7), None);
+        assert_eq!(dst.get(8), Some(Value::Active));
+
+        // explicit set
+        let mut dst = Values::default();
+        dst.set(1, Value::Inactive);
+        dst.set(3, Value::Active);
+        dst.set(4, Value::Active);
+        dst.set(7, Value::Inactive);
+        lm.update_from_v1(&mut dst, &src);
+        assert_eq!(dst.get(1), Some(Value::Active));
+        assert_eq!(dst.get(2), None);
+        assert_eq!(dst.get(3), Some(Value::Inactive));
+        assert_eq!(dst.get(4), Some(Value::Active));
+        assert_eq!(dst.get(5), None);
+        assert_eq!(dst.get(6), None);
+        assert_eq!(dst.get(7), Some(Value::Inactive));
+        assert_eq!(dst.get(8), None);
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v2")]
+    fn from_v2() {
+        let lm = LineMap::new(vec![1, 3, 5, 8]);
+        let mut src = v2::LineValues::default();
+        src.set(0, true);
+        src.set(1, false);
+        src.set(2, true);
+        src.set(3, true);
+        // full set
+        let mut dst = Values::default();
+        lm.update_from_v2(&mut dst, &src);
+        assert_eq!(dst, Values::from_offsets_and_bits(&[1, 3, 5, 8], 0b1101));
+
+        // explicit set
+        let mut dst = Values::default();
+        dst.set(3, Value::Active);
+        dst.set(4, Value::Active);
+        dst.set(5, Value::Inactive);
+        dst.set(7, Value::Inactive);
+        lm.update_from_v2(&mut dst, &src);
+        assert_eq!(dst.get(1), None);
+        assert_eq!(dst.get(2), None);
+        assert_eq!(dst.get(3), Some(Value::Inactive));
+        assert_eq!(dst.get(4), Some(Value::Active));
+        assert_eq!(dst.get(5), Some(Value::Active));
+        assert_eq!(dst.get(6), None);
+        assert_eq!(dst.get(7), Some(Value::Inactive));
+        assert_eq!(dst.get(8), None);
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn to_v1() {
+        let mut src = Values::default();
+        // both empty
+        let dst = LineMap::default().to_v1(&src);
+        assert_eq!(dst, v1::LineValues::default());
+
+        // empty values
+        let lm = LineMap::new(vec![1, 3, 5, 8]);
+        let dst = lm.to_v1(&src);
+        assert_eq!(dst, v1::LineValues::default());
+
+        // values intersects offsets
+        src.set(1, Value::Active);
+        src.set(3, Value::Inactive);
+        src.set(7, Value::Active); // should be ignored
+        src.set(8, Value::Active);
+        let dst = lm.to_v1(&src);
+        assert_eq!(dst.get(0), 1); // 1
+        assert_eq!(dst.get(1), 0); // 3
+        assert_eq!(dst.get(2), 0); // 5
+        assert_eq!(dst.get(3), 1); // 8
+        assert_eq!(dst.get(4), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "uapi_v2")]
+    fn to_v2() {
+        let mut src = Values::default();
+        // both empty
+        let dst = LineMap::new(Vec::new()).to_v2(&src);
+        assert_eq!(dst.bits, 0);
+        assert_eq!(dst.mask, 0);
+
+        // empty values
+        let lm = LineMap::new(vec![1, 3, 5, 8]);
+        let dst = lm.to_v2(&src);
+        assert_eq!(dst.bits, 0);
+        assert_eq!(dst.mask, 0b01111);
+
+        // values intersects offsets
+        src.set(1, Value::Active);
+        src.set(3, Value::Inactive);
+        src.set(7, Value::Active); // should be ignored
+        src.set(8, Value::Active);
+        let dst = lm.to_v2(&src);
+        assert!(dst.get(0).unwrap()); // 1
+        assert!(!dst.get(1).unwrap()); // 3
+        assert!(dst.get(2).is_none()); // 5
+        assert!(dst.get(3).unwrap()); // 8
+        assert_eq!(dst.mask, 0b1011); // only 3 entries set
+
+        // all lines
+        let lm = LineMap::new((0..64).collect());
+        assert_eq!(lm.to_v2(&Values::default()).mask, !0);
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{Offset, Value};
+use crate::{Request, Result};
+
+// Holds a line active for the duration of a pulse, and restores it to
+// inactive when released or dropped.
+pub(crate) struct PulseGuard<'a> {
+    req: &'a Request,
+    offset: Offset,
+    armed: bool,
+}
+
+impl<'a> PulseGuard<'a> {
+    // Start the pulse by setting the line active.
+    pub(crate) fn new(req: &'a Request, offset: Offset) -> Result<PulseGuard<'a>> {
+        req.set_value(offset, Value::Active)?;
+        Ok(PulseGuard {
+            req,
+            offset,
+            armed: true,
+        })
+    }
+
+    // End the pulse by setting the line inactive.
+    pub(crate) fn release(mut self) -> Result<()> {
+        self.armed = false;
+        self.req.set_value(self.offset, Value::Inactive)
+    }
+}
+
+impl Drop for PulseGuard<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            // best effort, as there is no way to report the error
+            let _ = self.req.set_value(self.offset, Value::Inactive);
+        }
+    }
+}
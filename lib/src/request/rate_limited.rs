@@ -0,0 +1,327 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{Stop, Worker};
+use crate::line::EdgeEvent;
+use crate::{Error, Request, Result};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How a [`RateLimited`] handles events that arrive while its queue is full.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued event to make room for the new event.
+    ///
+    /// This matches the behaviour of the kernel event buffer, but with the
+    /// discarded events counted.
+    #[default]
+    DropOldest,
+
+    /// Discard the new event.
+    DropNewest,
+
+    /// Stop reading events from the kernel until there is room in the queue.
+    ///
+    /// No events are dropped by the queue, but events continue to be buffered
+    /// by the kernel, which silently discards the oldest events if its buffer fills.
+    Block,
+}
+
+// The events read from the request and not yet received.
+struct Queue {
+    events: VecDeque<EdgeEvent>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: u64,
+    finished: bool,
+}
+
+impl Queue {
+    fn new(capacity: usize, policy: OverflowPolicy) -> Queue {
+        Queue {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            policy,
+            dropped: 0,
+            finished: false,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.events.len() >= self.capacity
+    }
+
+    // Add an event to the queue, applying the overflow policy if the queue is full.
+    //
+    // Returns the event if the queue is full and the policy is to block.
+    fn push(&mut self, evt: EdgeEvent) -> Option<EdgeEvent> {
+        if self.is_full() {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    self.events.pop_front();
+                    self.dropped += 1;
+                }
+                OverflowPolicy::DropNewest => {
+                    self.dropped += 1;
+                    return None;
+                }
+                OverflowPolicy::Block => return Some(evt),
+            }
+        }
+        self.events.push_back(evt);
+        None
+    }
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    // signalled when an event is queued, or the reader finishes
+    not_empty: Condvar,
+    // signalled when an event is received, or the reader is stopped
+    not_full: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue
+            .lock()
+            .expect("failed to acquire lock on event queue")
+    }
+}
+
+/// Queues the edge events from a [`Request`] for a consumer that may not keep up.
+///
+/// Created by [`Request::rate_limited`].
+///
+/// The edge events are read from the request by a background thread as soon as
+/// they arrive, and queued until received.  If the consumer falls behind and the
+/// queue fills, the [`OverflowPolicy`] determines which events are discarded,
+/// and the discarded events are counted by [`dropped`], so losses are explicit
+/// rather than occurring silently in the kernel.
+///
+/// The events are consumed by the queue, so should not be read from the request
+/// while it is active.
+///
+/// Iterating over the queue blocks until the next event is available, and ends
+/// if reading from the request fails.  The error that ended reading is returned
+/// by [`stop`].
+///
+/// [`dropped`]: RateLimited::dropped
+/// [`stop`]: RateLimited::stop
+pub struct RateLimited<'a> {
+    shared: Arc<Shared>,
+    worker: Worker,
+    _req: PhantomData<&'a Request>,
+}
+
+impl<'a> RateLimited<'a> {
+    pub(super) fn new(
+        req: &'a Request,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Result<RateLimited<'a>> {
+        if capacity == 0 {
+            return Err(Error::InvalidArgument(
+                "capacity must be non-zero.".to_string(),
+            ));
+        }
+        let req = req.try_clone()?;
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::new(capacity, policy)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        });
+        let thread_shared = shared.clone();
+        let worker = Worker::spawn(move |stop| {
+            let res = read_events(&req, &thread_shared, stop);
+            thread_shared.lock().finished = true;
+            thread_shared.not_empty.notify_all();
+            res
+        })?;
+        Ok(RateLimited {
+            shared,
+            worker,
+            _req: PhantomData,
+        })
+    }
+
+    /// Wait for the next event.
+    ///
+    /// Returns None if reading from the request has failed and the queue is empty.
+    pub fn recv(&self) -> Option<EdgeEvent> {
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(evt) = self.pop(&mut queue) {
+                return Some(evt);
+            }
+            if queue.finished {
+                return None;
+            }
+            queue = self.shared.not_empty.wait(queue).unwrap();
+        }
+    }
+
+    /// Wait up to a timeout for the next event.
+    ///
+    /// Returns None if no event arrived within the timeout, or if reading from the
+    /// request has failed and the queue is empty.
+    ///
+    /// A timeout too large to be represented as a deadline, such as `Duration::MAX`,
+    /// waits indefinitely, as per [`recv`].
+    ///
+    /// [`recv`]: RateLimited::recv
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<EdgeEvent> {
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return self.recv(),
+        };
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(evt) = self.pop(&mut queue) {
+                return Some(evt);
+            }
+            let now = Instant::now();
+            if queue.finished || now >= deadline {
+                return None;
+            }
+            queue = self
+                .shared
+                .not_empty
+                .wait_timeout(queue, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Return the next event if one is immediately available.
+    pub fn try_recv(&self) -> Option<EdgeEvent> {
+        self.pop(&mut self.shared.lock())
+    }
+
+    /// The number of events queued and not yet received.
+    pub fn len(&self) -> usize {
+        self.shared.lock().events.len()
+    }
+
+    /// Returns true if there are no events queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of events discarded by the [`OverflowPolicy`] as the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    /// Stop reading events from the request.
+    ///
+    /// Returns the error that ended reading, if reading from the request failed.
+    pub fn stop(mut self) -> Result<()> {
+        self.shutdown();
+        self.worker.stop()
+    }
+
+    // Wake the reader, whether it is waiting on the request or blocked on a full queue.
+    fn shutdown(&mut self) {
+        self.worker.signal_stop();
+        // notify while holding the lock, so the reader either sees the stop before
+        // waiting on a full queue, or is waiting and receives the notification.
+        let _queue = self.shared.lock();
+        self.shared.not_full.notify_all();
+    }
+
+    fn pop(&self, queue: &mut Queue) -> Option<EdgeEvent> {
+        let evt = queue.events.pop_front();
+        if evt.is_some() {
+            self.shared.not_full.notify_one();
+        }
+        evt
+    }
+}
+
+impl Drop for RateLimited<'_> {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Iterator for RateLimited<'_> {
+    type Item = EdgeEvent;
+
+    fn next(&mut self) -> Option<EdgeEvent> {
+        self.recv()
+    }
+}
+
+fn read_events(req: &Request, shared: &Shared, stop: &Stop) -> Result<()> {
+    while stop.wait_readable(req)? {
+        let mut evt = req.read_edge_event()?;
+        let mut queue = shared.lock();
+        while let Some(blocked) = queue.push(evt) {
+            if stop.is_stopped() {
+                return Ok(());
+            }
+            evt = blocked;
+            queue = shared.not_full.wait(queue).unwrap();
+        }
+        drop(queue);
+        shared.not_empty.notify_one();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::EdgeKind;
+
+    fn event(seqno: u32) -> EdgeEvent {
+        EdgeEvent {
+            timestamp_ns: 0,
+            kind: EdgeKind::Rising,
+            offset: 1,
+            seqno,
+            line_seqno: seqno,
+        }
+    }
+
+    fn seqnos(q: &Queue) -> Vec<u32> {
+        q.events.iter().map(|evt| evt.seqno).collect()
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut q = Queue::new(2, OverflowPolicy::DropOldest);
+        for seqno in 1..=4 {
+            assert!(q.push(event(seqno)).is_none());
+        }
+        assert_eq!(seqnos(&q), [3, 4]);
+        assert_eq!(q.dropped, 2);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let mut q = Queue::new(2, OverflowPolicy::DropNewest);
+        for seqno in 1..=4 {
+            assert!(q.push(event(seqno)).is_none());
+        }
+        assert_eq!(seqnos(&q), [1, 2]);
+        assert_eq!(q.dropped, 2);
+    }
+
+    #[test]
+    fn block() {
+        let mut q = Queue::new(2, OverflowPolicy::Block);
+        assert!(q.push(event(1)).is_none());
+        assert!(q.push(event(2)).is_none());
+        assert_eq!(q.push(event(3)), Some(event(3)));
+        assert_eq!(seqnos(&q), [1, 2]);
+        assert_eq!(q.dropped, 0);
+        q.events.pop_front();
+        assert!(q.push(event(3)).is_none());
+        assert_eq!(seqnos(&q), [2, 3]);
+    }
+}
@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::time::Duration;
+
+/// How a request is retried when the lines are busy.
+///
+/// Lines are commonly busy briefly, such as immediately after being released
+/// by another process, or while services compete for lines during boot.
+///
+/// The delay before each retry starts at `initial_delay` and is multiplied by
+/// `backoff` after each retry, up to `max_delay`.
+///
+/// The default policy does not retry.
+///
+/// Set using [`Builder::retry_on_busy`].
+///
+/// [`Builder::retry_on_busy`]: super::Builder::retry_on_busy
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the initial attempt.
+    pub max_attempts: u32,
+
+    /// The delay before the first retry.
+    pub initial_delay: Duration,
+
+    /// The factor applied to the delay after each retry.
+    pub backoff: u32,
+
+    /// The maximum delay between retries.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_delay: Duration::ZERO,
+            backoff: 1,
+            max_delay: Duration::ZERO,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that retries with exponential backoff.
+    ///
+    /// The delay doubles after each retry, up to a maximum of one second.
+    pub fn exponential(max_attempts: u32, initial_delay: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_delay,
+            backoff: 2,
+            max_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// A policy that retries with a fixed delay between attempts.
+    pub fn fixed(max_attempts: u32, delay: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_delay: delay,
+            backoff: 1,
+            max_delay: delay,
+        }
+    }
+
+    // The delay before the given retry, numbered from 1.
+    pub(super) fn delay(&self, retry: u32) -> Duration {
+        let mut delay = self.initial_delay;
+        for _ in 1..retry {
+            if delay >= self.max_delay {
+                break;
+            }
+            delay = delay.saturating_mul(self.backoff);
+        }
+        delay.min(self.max_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let p = RetryPolicy::default();
+        assert_eq!(p.max_attempts, 1);
+    }
+
+    #[test]
+    fn exponential() {
+        let p = RetryPolicy::exponential(20, Duration::from_millis(10));
+        assert_eq!(p.max_attempts, 20);
+        assert_eq!(p.delay(1), Duration::from_millis(10));
+        assert_eq!(p.delay(2), Duration::from_millis(20));
+        assert_eq!(p.delay(3), Duration::from_millis(40));
+        assert_eq!(p.delay(7), Duration::from_millis(640));
+        assert_eq!(p.delay(8), Duration::from_secs(1));
+        assert_eq!(p.delay(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn fixed() {
+        let p = RetryPolicy::fixed(3, Duration::from_millis(5));
+        assert_eq!(p.max_attempts, 3);
+        assert_eq!(p.delay(1), Duration::from_millis(5));
+        assert_eq!(p.delay(3), Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Config;
+use crate::line::{self, Direction, Offset, Value, Values};
+use crate::{Request, Result};
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+
+/// A snapshot of the configuration and output values of the lines in a [`Request`].
+///
+/// Created by [`Request::snapshot`], and reapplied by [`Request::restore`].
+///
+/// With the `serde` feature the snapshot can be persisted, e.g. so a controller
+/// can restore its outputs after a restart.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snapshot {
+    // The configuration of each line, in offset order.
+    lines: Vec<(Offset, line::Config)>,
+}
+
+impl Snapshot {
+    pub(super) fn new(req: &Request) -> Result<Snapshot> {
+        let cfg = req.config();
+        let mut lines: Vec<(Offset, line::Config)> = cfg
+            .lines()
+            .iter()
+            // unwrap is safe here as offsets match lcfg keys
+            .map(|offset| (*offset, cfg.line_config(*offset).unwrap().clone()))
+            .collect();
+        lines.sort_unstable_by_key(|(offset, _)| *offset);
+        let outputs: Vec<Offset> = lines
+            .iter()
+            .filter(|(_, lc)| lc.direction == Some(Direction::Output))
+            .map(|(offset, _)| *offset)
+            .collect();
+        if !outputs.is_empty() {
+            let mut values = Values::from_offsets(&outputs);
+            req.values(&mut values)?;
+            for (offset, lc) in lines.iter_mut() {
+                if let Some(value) = values.get(*offset) {
+                    lc.value = Some(value);
+                }
+            }
+        }
+        Ok(Snapshot { lines })
+    }
+
+    /// The offsets of the lines in the snapshot, in offset order.
+    pub fn lines(&self) -> Vec<Offset> {
+        self.lines.iter().map(|(offset, _)| *offset).collect()
+    }
+
+    /// The configuration of a line in the snapshot.
+    ///
+    /// Returns None if the line is not in the snapshot.
+    pub fn line_config(&self, offset: Offset) -> Option<&line::Config> {
+        self.lines
+            .iter()
+            .find(|(o, _)| *o == offset)
+            .map(|(_, lc)| lc)
+    }
+
+    /// The value of an output line in the snapshot.
+    ///
+    /// Returns None if the line is not in the snapshot or is not an output.
+    pub fn value(&self, offset: Offset) -> Option<Value> {
+        self.line_config(offset)
+            .filter(|lc| lc.direction == Some(Direction::Output))
+            .and_then(|lc| lc.value)
+    }
+
+    // The config to reapply the snapshot to a request.
+    pub(super) fn to_config(&self) -> Config {
+        let mut cfg = Config::default();
+        for (offset, lc) in &self.lines {
+            cfg.with_line(*offset).from_line_config(lc);
+        }
+        cfg.with_lines(&[]);
+        cfg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            lines: vec![
+                (
+                    2,
+                    line::Config {
+                        direction: Some(Direction::Input),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    5,
+                    line::Config {
+                        direction: Some(Direction::Output),
+                        value: Some(Value::Active),
+                        ..Default::default()
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn accessors() {
+        let s = snapshot();
+        assert_eq!(s.lines(), [2, 5]);
+        assert_eq!(s.line_config(2).unwrap().direction, Some(Direction::Input));
+        assert!(s.line_config(3).is_none());
+        assert_eq!(s.value(2), None);
+        assert_eq!(s.value(3), None);
+        assert_eq!(s.value(5), Some(Value::Active));
+    }
+
+    #[test]
+    fn to_config() {
+        let s = snapshot();
+        let cfg = s.to_config();
+        assert_eq!(cfg.lines(), &[2, 5]);
+        assert_eq!(cfg.line_config(2), s.line_config(2));
+        assert_eq!(cfg.line_config(5), s.line_config(5));
+        // no lines left selected
+        assert!(cfg.selected.is_empty());
+    }
+}
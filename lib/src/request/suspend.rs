@@ -0,0 +1,300 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::EdgeEventBuffer;
+use crate::line::EdgeEvent;
+use crate::poll::wait_any;
+use crate::timerfd::{boottime_now, monotonic_now, TimerFd};
+use crate::{Request, Result};
+#[cfg(feature = "async")]
+use futures::task::{Context, Poll};
+#[cfg(feature = "async")]
+use futures::{ready, Stream};
+use std::os::unix::prelude::AsFd;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::time::Duration;
+
+// How often the resume timer of an EdgeOrResumeEvents expires.
+//
+// The timer continues to run while the system is suspended, so expires immediately
+// on resume.
+const RESUME_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Detects that the system has been suspended and resumed.
+///
+/// `CLOCK_MONOTONIC`, which is used by default for edge event timestamps and by the
+/// kernel for debouncing, does not advance while the system is suspended, whereas
+/// `CLOCK_BOOTTIME` does.
+/// A suspend is detected by the difference between the two clocks increasing.
+///
+/// After a resume, the state of the lines may have changed without any edge events,
+/// and any interval measured across the suspend, such as between timestamps or a
+/// debounce period, is short by the time spent suspended, so the application should
+/// resync its state.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::request::SuspendDetector;
+/// # use std::time::Duration;
+/// let mut sd = SuspendDetector::new();
+/// loop {
+///     std::thread::sleep(Duration::from_secs(1));
+///     if let Some(suspended) = sd.check() {
+///         println!("resumed after {:?} suspended", suspended);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SuspendDetector {
+    // CLOCK_BOOTTIME - CLOCK_MONOTONIC at the last check
+    offset: Duration,
+    threshold: Duration,
+}
+
+impl Default for SuspendDetector {
+    fn default() -> Self {
+        SuspendDetector::new()
+    }
+}
+
+impl SuspendDetector {
+    /// Create a detector that reports suspends since it was created.
+    ///
+    /// The default threshold is 100ms.
+    pub fn new() -> SuspendDetector {
+        SuspendDetector {
+            offset: clock_offset(),
+            threshold: Duration::from_millis(100),
+        }
+    }
+
+    /// Set the minimum period of suspension that is reported.
+    ///
+    /// The clocks are read separately, so small differences between checks
+    /// are to be expected and should not be reported.
+    pub fn with_threshold(mut self, threshold: Duration) -> SuspendDetector {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Check if the system has been suspended since the previous check.
+    ///
+    /// Returns the total time spent suspended since the previous report.
+    pub fn check(&mut self) -> Option<Duration> {
+        self.observe(clock_offset())
+    }
+
+    fn observe(&mut self, offset: Duration) -> Option<Duration> {
+        let suspended = offset.saturating_sub(self.offset);
+        if suspended < self.threshold {
+            return None;
+        }
+        self.offset = offset;
+        Some(suspended)
+    }
+}
+
+// The time the system has spent suspended since boot.
+fn clock_offset() -> Duration {
+    let mono = monotonic_now();
+    boottime_now().saturating_sub(mono)
+}
+
+/// An item returned by a [`DetectSuspend`] adapter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EdgeOrResume {
+    /// An edge event from the underlying iterator or stream.
+    Edge(EdgeEvent),
+
+    /// The system has resumed after being suspended for the period.
+    ///
+    /// Returned before the first event read after the resume.
+    Resume(Duration),
+}
+
+/// An adapter that reports system suspends among the edge events.
+///
+/// Created by [`EdgeEventIteratorExt::detect_suspend`] or `EdgeEventStreamExt::detect_suspend`.
+///
+/// The check for a suspend is made as each event is read, so a suspend is only reported
+/// when the next event arrives, and before that event is returned.
+/// To report a resume without waiting for an event use [`Request::edge_events_or_resume`].
+///
+/// [`EdgeEventIteratorExt::detect_suspend`]: super::EdgeEventIteratorExt::detect_suspend
+pub struct DetectSuspend<I> {
+    inner: I,
+    detector: SuspendDetector,
+    // an event read when a resume was detected, to be returned next.
+    pending: Option<EdgeEvent>,
+}
+
+impl<I> DetectSuspend<I> {
+    pub(crate) fn new(inner: I, detector: SuspendDetector) -> Self {
+        DetectSuspend {
+            inner,
+            detector,
+            pending: None,
+        }
+    }
+
+    fn map(&mut self, res: Result<EdgeEvent>) -> Result<EdgeOrResume> {
+        let evt = res?;
+        if let Some(suspended) = self.detector.check() {
+            self.pending = Some(evt);
+            return Ok(EdgeOrResume::Resume(suspended));
+        }
+        Ok(EdgeOrResume::Edge(evt))
+    }
+}
+
+impl<I: Iterator<Item = Result<EdgeEvent>>> Iterator for DetectSuspend<I> {
+    type Item = Result<EdgeOrResume>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(evt) = self.pending.take() {
+            return Some(Ok(EdgeOrResume::Edge(evt)));
+        }
+        let res = self.inner.next()?;
+        Some(self.map(res))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Stream<Item = Result<EdgeEvent>> + Unpin> Stream for DetectSuspend<S> {
+    type Item = Result<EdgeOrResume>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if let Some(evt) = self.pending.take() {
+            return Poll::Ready(Some(Ok(EdgeOrResume::Edge(evt))));
+        }
+        let res = ready!(Pin::new(&mut self.inner).poll_next(cx));
+        Poll::Ready(res.map(|res| self.map(res)))
+    }
+}
+
+/// An iterator over the edge events from a [`Request`] that reports a system resume
+/// as soon as it occurs.
+///
+/// Created by [`Request::edge_events_or_resume`].
+///
+/// Unlike the [`DetectSuspend`] adapter, which only checks for a suspend as each event
+/// is read, this also waits on a periodic `CLOCK_BOOTTIME` timer.  That timer continues
+/// to run while the system is suspended, so it expires on resume and the resume is
+/// reported even if no edge events arrive.
+pub struct EdgeOrResumeEvents<'a> {
+    req: &'a Request,
+    buf: EdgeEventBuffer<'a>,
+    detector: SuspendDetector,
+    timer: TimerFd,
+    // an event read when a resume was detected, to be returned next.
+    pending: Option<EdgeEvent>,
+}
+
+impl<'a> EdgeOrResumeEvents<'a> {
+    pub(crate) fn new(req: &'a Request, detector: SuspendDetector) -> Result<Self> {
+        let timer = TimerFd::with_clock(libc::CLOCK_BOOTTIME, false)?;
+        timer.set_periodic(RESUME_CHECK_PERIOD)?;
+        Ok(EdgeOrResumeEvents {
+            req,
+            buf: req.edge_events(),
+            detector,
+            timer,
+            pending: None,
+        })
+    }
+
+    fn read(&mut self) -> Result<EdgeOrResume> {
+        loop {
+            if self.buf.has_event()? {
+                let evt = self.buf.read_event()?;
+                if let Some(suspended) = self.detector.check() {
+                    self.pending = Some(evt);
+                    return Ok(EdgeOrResume::Resume(suspended));
+                }
+                return Ok(EdgeOrResume::Edge(evt));
+            }
+            let sources: [&dyn AsFd; 2] = [self.req, &self.timer];
+            let ready = wait_any(&sources, RESUME_CHECK_PERIOD)?;
+            if ready.iter().any(|r| r.index == 1) {
+                self.timer.read()?;
+                if let Some(suspended) = self.detector.check() {
+                    return Ok(EdgeOrResume::Resume(suspended));
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for EdgeOrResumeEvents<'_> {
+    type Item = Result<EdgeOrResume>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(evt) = self.pending.take() {
+            return Some(Ok(EdgeOrResume::Edge(evt)));
+        }
+        Some(self.read())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn observe() {
+        let mut sd = SuspendDetector {
+            offset: 500 * MS,
+            threshold: 100 * MS,
+        };
+        assert_eq!(sd.observe(500 * MS), None);
+        assert_eq!(sd.observe(599 * MS), None);
+        assert_eq!(sd.observe(2500 * MS), Some(2000 * MS));
+        assert_eq!(sd.observe(2500 * MS), None);
+        // small drifts accumulate until reported
+        assert_eq!(sd.observe(2550 * MS), None);
+        assert_eq!(sd.observe(2600 * MS), Some(100 * MS));
+    }
+
+    #[test]
+    fn check() {
+        let mut sd = SuspendDetector::new();
+        assert_eq!(sd.check(), None);
+    }
+
+    #[test]
+    fn adapter() {
+        let evt = |offset| {
+            Ok(EdgeEvent {
+                timestamp_ns: 0,
+                kind: crate::line::EdgeKind::Rising,
+                offset,
+                seqno: 0,
+                line_seqno: 0,
+            })
+        };
+        let events = vec![evt(1), evt(2)];
+        // a zero threshold reports on every check, forcing a resume on the first event.
+        let detector = SuspendDetector {
+            offset: Duration::ZERO,
+            threshold: Duration::ZERO,
+        };
+        let mut ds = DetectSuspend::new(events.into_iter(), detector);
+        assert!(matches!(ds.next(), Some(Ok(EdgeOrResume::Resume(_)))));
+        match ds.next() {
+            Some(Ok(EdgeOrResume::Edge(evt))) => assert_eq!(evt.offset, 1),
+            x => panic!("unexpected {:?}", x),
+        }
+        // skip any further resumes
+        let offsets: Vec<u32> = ds
+            .filter_map(|r| match r {
+                Ok(EdgeOrResume::Edge(evt)) => Some(evt.offset),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(offsets, [2]);
+    }
+}
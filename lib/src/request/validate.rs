@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Config;
+use crate::chip::Capabilities;
+use crate::line::{self, Direction, EventClock, Offset};
+use crate::AbiVersion;
+use std::fmt;
+
+/// The severity of a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    /// The kernel will reject the request.
+    Error,
+
+    /// The kernel will accept the request, but the configuration is unlikely
+    /// to behave as intended.
+    Warning,
+}
+
+/// A problem with a [`Config`] found by [`Config::validate_for`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The line with the problem, or `None` if the problem applies to the request
+    /// as a whole.
+    pub offset: Option<Offset>,
+
+    /// The severity of the problem.
+    pub severity: Severity,
+
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error<M: Into<String>>(offset: Option<Offset>, message: M) -> Diagnostic {
+        Diagnostic {
+            offset,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning<M: Into<String>>(offset: Option<Offset>, message: M) -> Diagnostic {
+        Diagnostic {
+            offset,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    /// Returns true if the kernel will reject the request.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.offset {
+            Some(offset) => write!(f, "{}: line {}: {}", severity, offset, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+// Check the request as a whole.
+pub(super) fn check_request(
+    cfg: &Config,
+    abiv: AbiVersion,
+    caps: &Capabilities,
+    diags: &mut Vec<Diagnostic>,
+) {
+    let supported = match abiv {
+        AbiVersion::V1 => caps.abi_v1,
+        AbiVersion::V2 => caps.abi_v2,
+    };
+    if !supported {
+        diags.push(Diagnostic::error(
+            None,
+            format!("{} is not supported by the chip", abiv),
+        ));
+    }
+    // The uAPI conversion reports the remaining request level limitations,
+    // such as the number of lines or attributes.
+    if let Err(e) = cfg.to_uapi(abiv) {
+        diags.push(Diagnostic::error(None, e.to_string()));
+    }
+}
+
+// Check the configuration of a single line.
+pub(super) fn check_line(
+    offset: Offset,
+    lcfg: &line::Config,
+    caps: &Capabilities,
+    diags: &mut Vec<Diagnostic>,
+) {
+    let offset = Some(offset);
+    let input = lcfg.direction == Some(Direction::Input);
+    let output = lcfg.direction == Some(Direction::Output);
+    if let Some(bias) = lcfg.bias {
+        if lcfg.direction.is_none() {
+            diags.push(Diagnostic::error(
+                offset,
+                format!("bias {:?} requires the direction to be set", bias),
+            ));
+        }
+        if output && lcfg.drive.map_or(true, |d| d == line::Drive::PushPull) {
+            diags.push(Diagnostic::warning(
+                offset,
+                format!("bias {:?} has no effect on a push-pull output", bias),
+            ));
+        }
+        if !caps.bias {
+            diags.push(Diagnostic::error(
+                offset,
+                "setting bias is not supported by the kernel",
+            ));
+        }
+    }
+    if let Some(drive) = lcfg.drive {
+        if !output && drive != line::Drive::PushPull {
+            diags.push(Diagnostic::error(
+                offset,
+                format!("drive {:?} requires the line to be an output", drive),
+            ));
+        }
+    }
+    if let Some(edge) = lcfg.edge_detection {
+        if !input {
+            diags.push(Diagnostic::error(
+                offset,
+                format!("edge detection {:?} requires the line to be an input", edge),
+            ));
+        }
+    }
+    if let Some(period) = lcfg.debounce_period {
+        if !input {
+            diags.push(Diagnostic::error(
+                offset,
+                format!("debounce {:?} requires the line to be an input", period),
+            ));
+        } else if !caps.debounce {
+            diags.push(Diagnostic::error(
+                offset,
+                "debounce is not supported by the kernel",
+            ));
+        } else if lcfg.edge_detection.is_none() {
+            diags.push(Diagnostic::warning(
+                offset,
+                format!(
+                    "debounce {:?} without edge detection only filters the values read",
+                    period
+                ),
+            ));
+        }
+    }
+    if let Some(clock) = lcfg.event_clock {
+        if lcfg.edge_detection.is_none() {
+            diags.push(Diagnostic::warning(
+                offset,
+                format!(
+                    "event clock {:?} has no effect without edge detection",
+                    clock
+                ),
+            ));
+        }
+        let supported = match clock {
+            EventClock::Monotonic => true,
+            EventClock::Realtime => caps.event_clock_realtime,
+            EventClock::Hte => caps.event_clock_hte,
+        };
+        if !supported {
+            diags.push(Diagnostic::error(
+                offset,
+                format!("event clock {:?} is not supported by the kernel", clock),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let d = Diagnostic::error(Some(3), "bias PullUp requires the direction to be set");
+        assert_eq!(
+            d.to_string(),
+            "error: line 3: bias PullUp requires the direction to be set"
+        );
+        assert!(d.is_error());
+        let d = Diagnostic::warning(None, "something odd");
+        assert_eq!(d.to_string(), "warning: something odd");
+        assert!(!d.is_error());
+    }
+}
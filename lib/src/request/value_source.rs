@@ -0,0 +1,26 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+/// The source of the values returned for output lines by a [`Request`].
+///
+/// Some drivers cannot read back the physical level of output lines, and either
+/// return an error or a meaningless value when the values of outputs are read.
+/// For those drivers the request can instead return the values it last set.
+///
+/// Set using [`Builder::with_value_source`].
+///
+/// [`Request`]: super::Request
+/// [`Builder::with_value_source`]: super::Builder::with_value_source
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ValueSource {
+    /// Read the values of all lines from the kernel.
+    #[default]
+    Physical,
+
+    /// Return the last values set by the request for output lines.
+    ///
+    /// The values of input lines, and of output lines requested as-is and not
+    /// subsequently set, are still read from the kernel.
+    LastSet,
+}
@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{Offset, Value, Values};
+use std::fmt;
+
+/// A line that did not read back the value it was set to.
+///
+/// Returned by [`Request::set_values_verified`](super::Request::set_values_verified).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ValueMismatch {
+    /// The offset of the line.
+    pub offset: Offset,
+
+    /// The value the line was set to.
+    pub expected: Value,
+
+    /// The value read back from the line.
+    pub actual: Value,
+}
+
+impl fmt::Display for ValueMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: set {}, read back {}",
+            self.offset, self.expected, self.actual
+        )
+    }
+}
+
+// The lines in actual that differ from expected, in offset order.
+pub(super) fn mismatches(expected: &Values, actual: &Values) -> Vec<ValueMismatch> {
+    let mut mm: Vec<ValueMismatch> = expected
+        .iter()
+        .filter_map(|lv| {
+            actual
+                .get(lv.offset)
+                .filter(|value| *value != lv.value)
+                .map(|value| ValueMismatch {
+                    offset: lv.offset,
+                    expected: lv.value,
+                    actual: value,
+                })
+        })
+        .collect();
+    mm.sort_unstable_by_key(|m| m.offset);
+    mm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Value::{Active, Inactive};
+
+    #[test]
+    fn mismatches() {
+        let expected = Values::from([(5, Active), (2, Active), (3, Inactive), (7, Active)]);
+        // line 7 could not be read back
+        let actual = Values::from([(2, Inactive), (3, Inactive), (5, Inactive)]);
+        assert_eq!(
+            super::mismatches(&expected, &actual),
+            [
+                ValueMismatch {
+                    offset: 2,
+                    expected: Active,
+                    actual: Inactive,
+                },
+                ValueMismatch {
+                    offset: 5,
+                    expected: Active,
+                    actual: Inactive,
+                },
+            ]
+        );
+        assert!(super::mismatches(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn display() {
+        let m = ValueMismatch {
+            offset: 4,
+            expected: Active,
+            actual: Inactive,
+        };
+        assert_eq!(m.to_string(), "line 4: set active, read back inactive");
+    }
+}
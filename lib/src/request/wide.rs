@@ -0,0 +1,197 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{EdgeEvent, Offset, Value, Values};
+use crate::poll;
+use crate::request::Request;
+use crate::{Error, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// A set of lines on a chip, spread across as many kernel requests as required.
+///
+/// The kernel limits a single request to 64 lines, so a wide request splits its lines,
+/// in offset order, into several [`Request`]s and presents them as one.
+///
+/// Created by [`Builder::request_wide`].
+///
+/// Operations spanning several of the underlying requests are not atomic.
+/// e.g. [`set_values`] sets the lines of each request in turn, so lines in
+/// different requests change at slightly different times.
+///
+/// Edge events are read from the underlying requests in turn, so events from
+/// different requests are not strictly ordered by timestamp, and the sequence
+/// numbers are per underlying request.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::line::{Value, Values};
+/// # fn main() -> Result<()> {
+/// let offsets: Vec<u32> = (0..96).collect();
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_lines(&offsets)
+///     .as_output(Value::Inactive)
+///     .request_wide()?;
+/// let mut values = Values::default();
+/// values.set(3, Value::Active).set(95, Value::Active);
+/// req.set_values(&values)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Builder::request_wide`]: crate::request::Builder::request_wide
+/// [`set_values`]: WideRequest::set_values
+#[derive(Debug)]
+pub struct WideRequest {
+    reqs: Vec<Request>,
+    // the request to check first when reading edge events
+    next: AtomicUsize,
+}
+
+impl WideRequest {
+    pub(crate) fn new(reqs: Vec<Request>) -> WideRequest {
+        WideRequest {
+            reqs,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The underlying kernel requests.
+    pub fn requests(&self) -> &[Request] {
+        &self.reqs
+    }
+
+    /// The offsets of the requested lines, in ascending order.
+    pub fn lines(&self) -> Vec<Offset> {
+        self.reqs
+            .iter()
+            .flat_map(|r| r.lines().iter().copied())
+            .collect()
+    }
+
+    // The request containing the line.
+    //
+    // The lines are split across the requests in offset order, so the request is
+    // the first with a last line not less than the offset.
+    fn request_for(&self, offset: Offset) -> Result<&Request> {
+        let idx = self
+            .reqs
+            .partition_point(|r| r.lines().last().map_or(true, |last| *last < offset));
+        self.reqs
+            .get(idx)
+            .filter(|r| r.lines().binary_search(&offset).is_ok())
+            .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".to_string()))
+    }
+
+    // The values for the lines of a request that are present in values.
+    fn subset(req: &Request, values: &Values) -> Values {
+        let lines = req.lines();
+        values
+            .iter()
+            .filter(|lv| lines.binary_search(&lv.offset).is_ok())
+            .map(|lv| (lv.offset, lv.value))
+            .collect()
+    }
+
+    /// Get the values for a subset of the requested lines.
+    ///
+    /// The keys indicate the lines to get.
+    /// Keys that are not requested offsets are ignored.
+    /// If no keys are set then all requested lines are returned.
+    pub fn values(&self, values: &mut Values) -> Result<()> {
+        let all = values.is_empty();
+        for req in &self.reqs {
+            let mut sub = if all {
+                Values::default()
+            } else {
+                let sub = Self::subset(req, values);
+                if sub.is_empty() {
+                    continue;
+                }
+                sub
+            };
+            req.values(&mut sub)?;
+            values.extend(sub.iter().map(|lv| (lv.offset, lv.value)));
+        }
+        Ok(())
+    }
+
+    /// Get the value for one line in the request.
+    pub fn value(&self, offset: Offset) -> Result<Value> {
+        self.request_for(offset)?.value(offset)
+    }
+
+    /// Set the values for a subset of the requested lines.
+    ///
+    /// Returns an error if none of the keys are requested lines.
+    pub fn set_values(&self, values: &Values) -> Result<()> {
+        let mut found = false;
+        for req in &self.reqs {
+            let sub = Self::subset(req, values);
+            if !sub.is_empty() {
+                req.set_values(&sub)?;
+                found = true;
+            }
+        }
+        if !found {
+            return Err(Error::InvalidArgument(
+                "no requested lines in set values.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the value for one line in the request.
+    pub fn set_value(&self, offset: Offset, value: Value) -> Result<()> {
+        self.request_for(offset)?.set_value(offset, value)
+    }
+
+    /// Returns true when any of the underlying requests has edge events available to read.
+    pub fn has_edge_event(&self) -> Result<bool> {
+        for req in &self.reqs {
+            if req.has_edge_event()? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Wait for an edge event to be available from any of the underlying requests.
+    ///
+    /// Returns true if an edge event is available.
+    ///
+    /// A timeout too large to be represented by the kernel, such as `Duration::MAX`,
+    /// waits indefinitely.
+    pub fn wait_edge_event(&self, timeout: Duration) -> Result<bool> {
+        let mut pfds = poll::pollfds(&self.reqs);
+        Ok(poll::poll(&mut pfds, Some(timeout))? > 0)
+    }
+
+    /// Read a single edge event from the underlying requests.
+    ///
+    /// Blocks until an edge event is available from any of the requests.
+    pub fn read_edge_event(&self) -> Result<EdgeEvent> {
+        if self.reqs.len() == 1 {
+            return self.reqs[0].read_edge_event();
+        }
+        let mut pfds = poll::pollfds(&self.reqs);
+        loop {
+            poll::poll(&mut pfds, None)?;
+            // read from the first ready request following the last read, so a busy
+            // request cannot starve the others.
+            let n = self.reqs.len();
+            let start = self.next.load(Ordering::Relaxed) % n;
+            let idx = poll::ready(&pfds)
+                .iter()
+                .map(|r| r.index)
+                .min_by_key(|idx| (idx + n - start) % n);
+            if let Some(idx) = idx {
+                self.next.store(idx + 1, Ordering::Relaxed);
+                return self.reqs[idx].read_edge_event();
+            }
+        }
+    }
+}
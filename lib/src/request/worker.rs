@@ -0,0 +1,254 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::poll::{self, Ready};
+use crate::{Error, ErrorKind, Result};
+use std::os::unix::prelude::{AsFd, AsRawFd, FromRawFd, OwnedFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// Signals a worker thread to stop.
+//
+// The stop is signalled via an eventfd, which the worker includes in the set of
+// fds it waits on, so the worker wakes as soon as it is stopped, rather than
+// periodically checking if it should exit.
+pub(crate) struct Stop {
+    stopped: AtomicBool,
+    fd: OwnedFd,
+}
+
+impl Stop {
+    fn new() -> Result<Stop> {
+        // SAFETY: no pointers involved.
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Stop {
+            stopped: AtomicBool::new(false),
+            // SAFETY: fd is a newly created eventfd that is not owned elsewhere.
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    // Signal the worker to stop.
+    //
+    // The eventfd is never read, so it remains readable once signalled.
+    fn signal(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let one: u64 = 1;
+        // SAFETY: writing a u64 from a valid location.
+        // Failure is only possible if the counter would overflow, in which case
+        // the eventfd is already readable.
+        unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                &one as *const u64 as *const libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    // Returns true if the worker has been signalled to stop.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    // Wait for any of the sources to be readable, the timeout to expire, or the
+    // worker to be stopped.
+    //
+    // A timeout of None waits indefinitely.
+    //
+    // Returns None if the worker has been stopped, else the sources requiring
+    // attention, which is empty if the wait timed out or was interrupted.
+    pub(crate) fn wait_any<F: AsFd>(
+        &self,
+        sources: &[F],
+        timeout: Option<Duration>,
+    ) -> Result<Option<Vec<Ready>>> {
+        let mut pfds = poll::pollfds(sources);
+        pfds.push(libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        });
+        match poll::poll(&mut pfds, timeout) {
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            res => {
+                res?;
+            }
+        }
+        if self.is_stopped() {
+            return Ok(None);
+        }
+        pfds.pop();
+        Ok(Some(poll::ready(&pfds)))
+    }
+
+    // Wait for the source to be readable, or the worker to be stopped.
+    //
+    // Returns false if the worker has been stopped.
+    pub(crate) fn wait_readable<F: AsFd>(&self, source: &F) -> Result<bool> {
+        loop {
+            match self.wait_any(std::slice::from_ref(source), None)? {
+                None => return Ok(false),
+                Some(ready) if !ready.is_empty() => return Ok(true),
+                Some(_) => {}
+            }
+        }
+    }
+
+    // Sleep for the period, or until the worker is stopped.
+    //
+    // Returns false if the worker has been stopped.
+    pub(crate) fn sleep(&self, period: Duration) -> Result<bool> {
+        let sources: [&OwnedFd; 0] = [];
+        Ok(self.wait_any(&sources, Some(period))?.is_some())
+    }
+}
+
+struct Shared {
+    stop: Stop,
+    finished: AtomicBool,
+    error: Mutex<Option<Error>>,
+}
+
+// A background thread that is stopped when dropped, and retains the error that
+// ended it.
+//
+// The body of the thread should wait using the provided Stop, and return once
+// the worker is stopped.
+pub(crate) struct Worker {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    // Spawn a thread running the body.
+    pub(crate) fn spawn<F>(body: F) -> Result<Worker>
+    where
+        F: FnOnce(&Stop) -> Result<()> + Send + 'static,
+    {
+        Worker::spawn_with(std::thread::Builder::new(), body)
+    }
+
+    // Spawn a thread, configured by the builder, running the body.
+    pub(crate) fn spawn_with<F>(builder: std::thread::Builder, body: F) -> Result<Worker>
+    where
+        F: FnOnce(&Stop) -> Result<()> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            stop: Stop::new()?,
+            finished: AtomicBool::new(false),
+            error: Mutex::new(None),
+        });
+        let thread_shared = shared.clone();
+        let thread = builder.spawn(move || {
+            if let Err(e) = body(&thread_shared.stop) {
+                *thread_shared.error.lock().unwrap() = Some(e);
+            }
+            thread_shared.finished.store(true, Ordering::Relaxed);
+        })?;
+        Ok(Worker {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    // Returns true if the body has returned.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.shared.finished.load(Ordering::Relaxed)
+    }
+
+    // Signal the body to stop, without waiting for the thread to exit.
+    pub(crate) fn signal_stop(&self) {
+        self.shared.stop.signal();
+    }
+
+    // Stop the thread and wait for it to exit.
+    //
+    // Returns the error that ended the body, if any.
+    pub(crate) fn stop(&mut self) -> Result<()> {
+        self.shutdown();
+        match self.shared.error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.stop.signal();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+    use std::time::Instant;
+
+    #[test]
+    fn stop() {
+        let (_tx, rx) = UnixStream::pair().unwrap();
+        let mut w = Worker::spawn(move |stop| {
+            assert!(!stop.wait_readable(&rx)?);
+            Ok(())
+        })
+        .unwrap();
+        assert!(!w.is_finished());
+        let start = Instant::now();
+        assert_eq!(w.stop(), Ok(()));
+        assert!(start.elapsed() < Duration::from_millis(50));
+        assert!(w.is_finished());
+    }
+
+    #[test]
+    fn error() {
+        let mut w = Worker::spawn(|_| Err(Error::InvalidArgument("oops.".to_string()))).unwrap();
+        while !w.is_finished() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(w.stop(), Err(Error::InvalidArgument("oops.".to_string())));
+    }
+
+    #[test]
+    fn wait_any() {
+        let stop = Stop::new().unwrap();
+        let (mut tx, rx) = UnixStream::pair().unwrap();
+        let sources = [&rx];
+        assert_eq!(
+            stop.wait_any(&sources, Some(Duration::ZERO)),
+            Ok(Some(vec![]))
+        );
+        tx.write_all(b"x").unwrap();
+        assert!(stop.wait_readable(&rx).unwrap());
+        assert_eq!(
+            stop.wait_any(&sources, None),
+            Ok(Some(vec![Ready {
+                index: 0,
+                readable: true,
+                hangup: false
+            }]))
+        );
+        assert!(stop.sleep(Duration::ZERO).unwrap());
+
+        stop.signal();
+        assert!(stop.is_stopped());
+        assert_eq!(stop.wait_any(&sources, None), Ok(None));
+        assert!(!stop.wait_readable(&rx).unwrap());
+        assert!(!stop.sleep(Duration::MAX).unwrap());
+    }
+}
@@ -0,0 +1,405 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Deadline scheduling of output values.
+//!
+//! A [`Scheduler`] sets output values on one or more requests at absolute deadlines.
+//! Jobs are executed by a single background thread driven by a timerfd, so job timing
+//! is independent of the thread scheduling the jobs.
+//!
+//! Deadlines are specified in nanoseconds on `CLOCK_MONOTONIC`, the same clock used by
+//! default for edge event timestamps, so outputs may be scheduled relative to input edges.
+//! The current time on that clock is provided by [`now_ns`].
+//!
+//! The latency between each job deadline and its values being set is recorded in the
+//! scheduler [`JitterStats`].
+//!
+//! The scheduler is thread based - there is no async variant.
+//! Pulses and waveforms may be scheduled as jobs using [`Scheduler::pulse_at`] and
+//! [`Scheduler::play_waveform_at`], but [`Request::pulse`] and [`Request::play_waveform`],
+//! and their async variants, do not use a scheduler, so they remain usable without
+//! the scheduler thread.
+
+use crate::line::{Offset, Value, Values};
+use crate::timerfd::{monotonic_now, TimerFd};
+use crate::waveform::Waveform;
+use crate::{Error, Request, Result};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The current time on `CLOCK_MONOTONIC`, in nanoseconds.
+pub fn now_ns() -> u64 {
+    monotonic_now().as_nanos() as u64
+}
+
+/// Identifies a request attached to a [`Scheduler`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Target(usize);
+
+/// Identifies a job scheduled on a [`Scheduler`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct JobId(u64);
+
+/// Statistics of the latency between job deadlines and their execution.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct JitterStats {
+    /// The number of jobs executed.
+    pub jobs: u64,
+
+    /// The number of jobs that failed to set their values.
+    pub errors: u64,
+
+    /// The minimum latency.
+    pub min: Duration,
+
+    /// The maximum latency.
+    pub max: Duration,
+
+    /// The sum of the latencies.
+    pub total: Duration,
+}
+
+impl JitterStats {
+    /// The mean latency.
+    pub fn mean(&self) -> Duration {
+        if self.jobs == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.total.as_nanos() / self.jobs as u128) as u64)
+    }
+
+    fn record(&mut self, latency: Duration) {
+        if self.jobs == 0 || latency < self.min {
+            self.min = latency;
+        }
+        if latency > self.max {
+            self.max = latency;
+        }
+        self.total += latency;
+        self.jobs += 1;
+    }
+}
+
+/// Sets output values on requests at absolute deadlines.
+///
+/// Requests are attached to the scheduler with [`attach`], which shares the underlying
+/// request with the scheduler.
+/// Values set by the scheduler are tracked in the request config, as for values set
+/// directly using the request.
+///
+/// Jobs with the same deadline are executed in the order they were scheduled.
+/// Jobs with deadlines already past are executed as soon as possible.
+///
+/// Pending jobs are discarded when the scheduler is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::line::Value;
+/// # use gpiocdev::scheduler::{now_ns, Scheduler};
+/// # fn main() -> Result<()> {
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_lines(&[17, 22])
+///     .as_output(Value::Inactive)
+///     .request()?;
+/// let sched = Scheduler::new()?;
+/// let tgt = sched.attach(&req)?;
+/// let start = now_ns() + 1_000_000;
+/// sched.set_value_at(tgt, start, 17, Value::Active)?;
+/// sched.set_value_at(tgt, start + 500_000, 22, Value::Active)?;
+/// sched.set_value_at(tgt, start + 2_000_000, 17, Value::Inactive)?;
+/// sched.wait_idle();
+/// println!("mean latency {:?}", sched.stats().mean());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`attach`]: Scheduler::attach
+pub struct Scheduler {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    timer: TimerFd,
+    state: Mutex<State>,
+    idle: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    targets: Vec<Request>,
+    jobs: BTreeMap<(u64, JobId), Job>,
+    next_id: u64,
+    stats: JitterStats,
+    stop: bool,
+}
+
+struct Job {
+    target: usize,
+    values: Values,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("failed to acquire lock on scheduler state")
+    }
+
+    // Arm the timer for the earliest pending job.
+    fn rearm(&self, state: &State) -> Result<()> {
+        match state.jobs.keys().next() {
+            Some((deadline, _)) => self.timer.set_deadline(Duration::from_nanos(*deadline)),
+            None => self.timer.disarm(),
+        }?;
+        Ok(())
+    }
+
+    fn run(&self) {
+        loop {
+            // Errors, such as EINTR, only result in an early check of the pending jobs.
+            let _ = self.timer.read();
+            let mut state = self.lock();
+            loop {
+                if state.stop {
+                    return;
+                }
+                let key = match state.jobs.keys().next() {
+                    Some(key) if key.0 <= now_ns() => *key,
+                    _ => break,
+                };
+                let job = state.jobs.remove(&key).unwrap();
+                let res = state.targets[job.target].set_values(&job.values);
+                let latency = Duration::from_nanos(now_ns().saturating_sub(key.0));
+                state.stats.record(latency);
+                if res.is_err() {
+                    state.stats.errors += 1;
+                }
+            }
+            if state.jobs.is_empty() {
+                self.idle.notify_all();
+            }
+            let _ = self.rearm(&state);
+        }
+    }
+}
+
+impl Scheduler {
+    /// Create a scheduler and start its thread.
+    pub fn new() -> Result<Scheduler> {
+        let shared = Arc::new(Shared {
+            timer: TimerFd::new(false)?,
+            state: Mutex::new(State::default()),
+            idle: Condvar::new(),
+        });
+        let thread_shared = shared.clone();
+        let thread = std::thread::spawn(move || thread_shared.run());
+        Ok(Scheduler {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Attach a request to the scheduler, so jobs may be scheduled on it.
+    ///
+    /// The request remains usable by the caller, but the scheduler holds the
+    /// underlying request open until the scheduler is dropped.
+    pub fn attach(&self, req: &Request) -> Result<Target> {
+        let req = req.try_clone()?;
+        let mut state = self.shared.lock();
+        state.targets.push(req);
+        Ok(Target(state.targets.len() - 1))
+    }
+
+    /// Schedule values to be set on a request at the deadline.
+    ///
+    /// * `target` - the request to set the values on.
+    /// * `deadline_ns` - the time to set the values, on `CLOCK_MONOTONIC`, in nanoseconds.
+    /// * `values` - the values to set.
+    pub fn set_values_at(&self, target: Target, deadline_ns: u64, values: Values) -> Result<JobId> {
+        let mut state = self.shared.lock();
+        if target.0 >= state.targets.len() {
+            return Err(Error::InvalidArgument(
+                "target is not attached to the scheduler.".to_string(),
+            ));
+        }
+        let id = JobId(state.next_id);
+        state.next_id += 1;
+        let earliest = match state.jobs.keys().next() {
+            Some(key) => deadline_ns < key.0,
+            None => true,
+        };
+        state.jobs.insert(
+            (deadline_ns, id),
+            Job {
+                target: target.0,
+                values,
+            },
+        );
+        if earliest {
+            self.shared.rearm(&state)?;
+        }
+        Ok(id)
+    }
+
+    /// Schedule the value of a single line to be set on a request at the deadline.
+    pub fn set_value_at(
+        &self,
+        target: Target,
+        deadline_ns: u64,
+        offset: Offset,
+        value: Value,
+    ) -> Result<JobId> {
+        let mut values = Values::default();
+        values.set(offset, value);
+        self.set_values_at(target, deadline_ns, values)
+    }
+
+    /// Schedule a pulse on a line, with the line set active at the deadline and
+    /// inactive after the active duration has elapsed.
+    ///
+    /// Returns the jobs setting the line active and inactive.
+    pub fn pulse_at(
+        &self,
+        target: Target,
+        deadline_ns: u64,
+        offset: Offset,
+        active_duration: Duration,
+    ) -> Result<(JobId, JobId)> {
+        let start = self.set_value_at(target, deadline_ns, offset, Value::Active)?;
+        let end = self.set_value_at(
+            target,
+            deadline_ns + active_duration.as_nanos() as u64,
+            offset,
+            Value::Inactive,
+        )?;
+        Ok((start, end))
+    }
+
+    /// Schedule a waveform to be played on a line, starting at the deadline.
+    ///
+    /// Returns the time the waveform ends, on `CLOCK_MONOTONIC`, in nanoseconds.
+    pub fn play_waveform_at(
+        &self,
+        target: Target,
+        deadline_ns: u64,
+        offset: Offset,
+        wf: &Waveform,
+    ) -> Result<u64> {
+        let mut deadline = deadline_ns;
+        for step in wf.steps() {
+            self.set_value_at(target, deadline, offset, step.value)?;
+            deadline += step.duration.as_nanos() as u64;
+        }
+        Ok(deadline)
+    }
+
+    /// Cancel a pending job.
+    ///
+    /// Returns false if the job has already been executed or cancelled.
+    pub fn cancel(&self, id: JobId) -> bool {
+        let mut state = self.shared.lock();
+        let key = match state.jobs.keys().find(|k| k.1 == id) {
+            Some(key) => *key,
+            None => return false,
+        };
+        state.jobs.remove(&key);
+        if state.jobs.is_empty() {
+            self.shared.idle.notify_all();
+        }
+        true
+    }
+
+    /// Cancel all pending jobs.
+    pub fn clear(&self) {
+        let mut state = self.shared.lock();
+        state.jobs.clear();
+        self.shared.idle.notify_all();
+    }
+
+    /// The number of pending jobs.
+    pub fn pending(&self) -> usize {
+        self.shared.lock().jobs.len()
+    }
+
+    /// Block until there are no pending jobs.
+    pub fn wait_idle(&self) {
+        let mut state = self.shared.lock();
+        while !state.jobs.is_empty() {
+            state = self
+                .shared
+                .idle
+                .wait(state)
+                .expect("failed to acquire lock on scheduler state");
+        }
+    }
+
+    /// The latency statistics of the jobs executed so far.
+    pub fn stats(&self) -> JitterStats {
+        self.shared.lock().stats
+    }
+
+    /// Reset the latency statistics.
+    pub fn reset_stats(&self) {
+        self.shared.lock().stats = JitterStats::default();
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.lock();
+            state.stop = true;
+            // wake the thread
+            let _ = self.shared.timer.set_deadline(Duration::ZERO);
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_stats() {
+        let mut s = JitterStats::default();
+        assert_eq!(s.mean(), Duration::ZERO);
+        s.record(Duration::from_micros(30));
+        s.record(Duration::from_micros(10));
+        s.record(Duration::from_micros(20));
+        assert_eq!(s.jobs, 3);
+        assert_eq!(s.min, Duration::from_micros(10));
+        assert_eq!(s.max, Duration::from_micros(30));
+        assert_eq!(s.total, Duration::from_micros(60));
+        assert_eq!(s.mean(), Duration::from_micros(20));
+    }
+
+    #[test]
+    fn invalid_target() {
+        let sched = Scheduler::new().unwrap();
+        assert_eq!(
+            sched
+                .set_value_at(Target(0), now_ns(), 1, Value::Active)
+                .unwrap_err(),
+            Error::InvalidArgument("target is not attached to the scheduler.".to_string())
+        );
+        assert_eq!(sched.pending(), 0);
+        sched.wait_idle();
+    }
+
+    #[test]
+    fn now() {
+        let start = now_ns();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(now_ns() >= start + 2_000_000);
+    }
+}
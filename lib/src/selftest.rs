@@ -0,0 +1,414 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Self-tests for hardware bring-up.
+//!
+//! [`loopback`] verifies output lines that are wired back to input lines, such as
+//! on a test jig, by driving each output in turn and checking that only the
+//! paired input follows it.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::line::{EdgeDetection, Value};
+//! # use gpiocdev::selftest;
+//! # fn main() -> Result<()> {
+//! let outputs = gpiocdev::Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[4, 5])
+//!     .as_output(Value::Inactive)
+//!     .request()?;
+//! let inputs = gpiocdev::Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[17, 18])
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .request()?;
+//! let report = selftest::loopback(&outputs, &inputs, &[(4, 17), (5, 18)])?;
+//! if !report.passed() {
+//!     eprintln!("{}", report);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::line::{EdgeDetection, EdgeKind, Offset, Value};
+use crate::{Error, Request, Result};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// The maximum time for an input to follow its output.
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+// The period between polls of an input value.
+const POLL_PERIOD: Duration = Duration::from_millis(1);
+
+/// A failure detected by a loopback test.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Failure {
+    /// The input did not follow the output.
+    Level {
+        /// The value driven on the output.
+        driven: Value,
+
+        /// The value read from the input.
+        read: Value,
+    },
+
+    /// Another input followed the output, indicating a short or miswiring.
+    Crosstalk {
+        /// The value driven on the output.
+        driven: Value,
+
+        /// The input that followed the output.
+        input: Offset,
+    },
+
+    /// The expected edge event was not received on the input.
+    MissingEdge(EdgeKind),
+
+    /// An edge event that was not expected was received on the input.
+    UnexpectedEdge(EdgeKind),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Level { driven, read } => {
+                write!(f, "drove {}, read {}", driven, read)
+            }
+            Failure::Crosstalk { driven, input } => {
+                write!(f, "drove {}, also seen on input {}", driven, input)
+            }
+            Failure::MissingEdge(kind) => write!(f, "missing {:?} edge", kind),
+            Failure::UnexpectedEdge(kind) => write!(f, "unexpected {:?} edge", kind),
+        }
+    }
+}
+
+/// The result of testing one pair of lines.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairResult {
+    /// The output line.
+    pub output: Offset,
+
+    /// The input line wired to the output.
+    pub input: Offset,
+
+    /// The failures detected for the pair, if any.
+    pub failures: Vec<Failure>,
+}
+
+impl PairResult {
+    /// Returns true if no failures were detected for the pair.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for PairResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}: ", self.output, self.input)?;
+        if self.passed() {
+            return write!(f, "pass");
+        }
+        write!(f, "FAIL")?;
+        for failure in &self.failures {
+            write!(f, "\n  {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of a [`loopback`] test.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LoopbackReport {
+    /// The results for each pair, in the order tested.
+    pub pairs: Vec<PairResult>,
+}
+
+impl LoopbackReport {
+    /// Returns true if all pairs passed.
+    pub fn passed(&self) -> bool {
+        self.pairs.iter().all(|p| p.passed())
+    }
+
+    /// The pairs with failures.
+    pub fn failed(&self) -> impl Iterator<Item = &PairResult> {
+        self.pairs.iter().filter(|p| !p.passed())
+    }
+}
+
+impl fmt::Display for LoopbackReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, pair) in self.pairs.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", pair)?;
+        }
+        Ok(())
+    }
+}
+
+/// Test output lines wired back to input lines.
+///
+/// * `output_req` - the request containing the output lines.
+/// * `input_req` - the request containing the input lines.
+/// * `pairs` - the (output, input) pairs of lines that are wired together.
+///
+/// All the outputs are first driven inactive.
+/// Each output is then driven active and back to inactive in turn, and after each
+/// change the paired input is checked to follow the output, and the other inputs
+/// are checked to remain inactive.
+///
+/// If edge detection is enabled on an input then the edge events are also checked.
+///
+/// The active levels of the outputs and inputs are assumed to match.
+///
+/// Returns an error if a line is not in the corresponding request, or if the
+/// lines cannot be set or read.
+/// Failures of the wiring are returned in the report.
+pub fn loopback(
+    output_req: &Request,
+    input_req: &Request,
+    pairs: &[(Offset, Offset)],
+) -> Result<LoopbackReport> {
+    let in_cfg = input_req.config();
+    for (output, input) in pairs {
+        check_line(output_req, *output)?;
+        check_line(input_req, *input)?;
+    }
+    let edge_detection = |input: Offset| in_cfg.line_config(input).and_then(|lc| lc.edge_detection);
+
+    for (output, _) in pairs {
+        output_req.set_value(*output, Value::Inactive)?;
+    }
+    for (_, input) in pairs {
+        wait_for_value(input_req, *input, Value::Inactive)?;
+    }
+    if pairs
+        .iter()
+        .any(|(_, input)| edge_detection(*input).is_some())
+    {
+        drain_edge_events(input_req)?;
+    }
+
+    let mut report = LoopbackReport::default();
+    for (output, input) in pairs {
+        let mut failures = Vec::new();
+        for driven in [Value::Active, Value::Inactive] {
+            output_req.set_value(*output, driven)?;
+            let read = wait_for_value(input_req, *input, driven)?;
+            if read != driven {
+                failures.push(Failure::Level { driven, read });
+            }
+            for (_, other) in pairs.iter().filter(|(_, i)| i != input) {
+                if input_req.value(*other)? != Value::Inactive {
+                    let failure = Failure::Crosstalk {
+                        driven,
+                        input: *other,
+                    };
+                    if !failures.contains(&failure) {
+                        failures.push(failure);
+                    }
+                }
+            }
+            if let Some(ed) = edge_detection(*input) {
+                let expected = expected_edge(ed, driven);
+                failures.extend(check_edges(input_req, *input, expected)?);
+            }
+        }
+        report.pairs.push(PairResult {
+            output: *output,
+            input: *input,
+            failures,
+        });
+    }
+    Ok(report)
+}
+
+fn check_line(req: &Request, offset: Offset) -> Result<()> {
+    if req.lines().contains(&offset) {
+        return Ok(());
+    }
+    Err(Error::InvalidArgument(format!(
+        "offset {} is not a requested line.",
+        offset
+    )))
+}
+
+// Poll the input until it has the value or the timeout expires.
+//
+// Returns the last value read.
+fn wait_for_value(req: &Request, offset: Offset, value: Value) -> Result<Value> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        let read = req.value(offset)?;
+        let now = Instant::now();
+        if read == value || now >= deadline {
+            return Ok(read);
+        }
+        std::thread::sleep(POLL_PERIOD.min(deadline - now));
+    }
+}
+
+fn drain_edge_events(req: &Request) -> Result<()> {
+    while req.has_edge_event()? {
+        req.read_edge_event()?;
+    }
+    Ok(())
+}
+
+// The edge expected on an input with the edge detection when the value is driven.
+fn expected_edge(ed: EdgeDetection, driven: Value) -> Option<EdgeKind> {
+    match (ed, driven) {
+        (EdgeDetection::RisingEdge | EdgeDetection::BothEdges, Value::Active) => {
+            Some(EdgeKind::Rising)
+        }
+        (EdgeDetection::FallingEdge | EdgeDetection::BothEdges, Value::Inactive) => {
+            Some(EdgeKind::Falling)
+        }
+        _ => None,
+    }
+}
+
+// Read the edge events for the input, and check them against the expected edge.
+//
+// Events on other inputs are discarded, as any crosstalk is detected from the values.
+fn check_edges(req: &Request, offset: Offset, expected: Option<EdgeKind>) -> Result<Vec<Failure>> {
+    let mut kinds = Vec::new();
+    if expected.is_some() {
+        // the value has already changed, so the event should be available promptly
+        let deadline = Instant::now() + TIMEOUT;
+        while kinds.is_empty() {
+            let now = Instant::now();
+            if now >= deadline || !req.wait_edge_event(deadline - now)? {
+                break;
+            }
+            let evt = req.read_edge_event()?;
+            if evt.offset == offset {
+                kinds.push(evt.kind);
+            }
+        }
+    }
+    while req.has_edge_event()? {
+        let evt = req.read_edge_event()?;
+        if evt.offset == offset {
+            kinds.push(evt.kind);
+        }
+    }
+    Ok(edge_failures(expected, &kinds))
+}
+
+// Compare the edges received with the expected edge.
+fn edge_failures(expected: Option<EdgeKind>, kinds: &[EdgeKind]) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut matched = false;
+    for kind in kinds {
+        if !matched && Some(*kind) == expected {
+            matched = true;
+        } else {
+            failures.push(Failure::UnexpectedEdge(*kind));
+        }
+    }
+    if let (Some(kind), false) = (expected, matched) {
+        failures.insert(0, Failure::MissingEdge(kind));
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EdgeKind::*;
+
+    #[test]
+    fn expected_edge() {
+        use EdgeDetection::*;
+        assert_eq!(
+            super::expected_edge(RisingEdge, Value::Active),
+            Some(Rising)
+        );
+        assert_eq!(super::expected_edge(RisingEdge, Value::Inactive), None);
+        assert_eq!(super::expected_edge(FallingEdge, Value::Active), None);
+        assert_eq!(
+            super::expected_edge(FallingEdge, Value::Inactive),
+            Some(Falling)
+        );
+        assert_eq!(super::expected_edge(BothEdges, Value::Active), Some(Rising));
+        assert_eq!(
+            super::expected_edge(BothEdges, Value::Inactive),
+            Some(Falling)
+        );
+    }
+
+    #[test]
+    fn edge_failures() {
+        assert!(super::edge_failures(Some(Rising), &[Rising]).is_empty());
+        assert!(super::edge_failures(None, &[]).is_empty());
+        assert_eq!(
+            super::edge_failures(Some(Rising), &[]),
+            [Failure::MissingEdge(Rising)]
+        );
+        assert_eq!(
+            super::edge_failures(None, &[Falling]),
+            [Failure::UnexpectedEdge(Falling)]
+        );
+        // bounce
+        assert_eq!(
+            super::edge_failures(Some(Rising), &[Rising, Falling, Rising]),
+            [
+                Failure::UnexpectedEdge(Falling),
+                Failure::UnexpectedEdge(Rising)
+            ]
+        );
+        assert_eq!(
+            super::edge_failures(Some(Rising), &[Falling]),
+            [
+                Failure::MissingEdge(Rising),
+                Failure::UnexpectedEdge(Falling)
+            ]
+        );
+    }
+
+    #[test]
+    fn report() {
+        let mut report = LoopbackReport {
+            pairs: vec![PairResult {
+                output: 4,
+                input: 17,
+                failures: Vec::new(),
+            }],
+        };
+        assert!(report.passed());
+        assert_eq!(report.failed().count(), 0);
+        assert_eq!(report.to_string(), "4 -> 17: pass");
+
+        report.pairs.push(PairResult {
+            output: 5,
+            input: 18,
+            failures: vec![
+                Failure::Level {
+                    driven: Value::Active,
+                    read: Value::Inactive,
+                },
+                Failure::Crosstalk {
+                    driven: Value::Active,
+                    input: 17,
+                },
+                Failure::MissingEdge(Rising),
+            ],
+        });
+        assert!(!report.passed());
+        assert_eq!(report.failed().count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "4 -> 17: pass\n\
+            5 -> 18: FAIL\n  \
+            drove active, read inactive\n  \
+            drove active, also seen on input 17\n  \
+            missing Rising edge"
+        );
+    }
+}
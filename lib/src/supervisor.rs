@@ -0,0 +1,386 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Supervised edge event handling for long-running applications.
+//!
+//! A [`RequestSupervisor`] owns the requests for an application and runs a reader thread
+//! for each chip, passing the edge events from the requests on that chip to their
+//! handlers.
+//!
+//! If a request fails, such as the chip being removed (`ENODEV`), then the request
+//! is dropped and requested again.
+//! Failed requests are retried after the restart delay until they succeed,
+//! so the supervisor recovers when a hotplugged chip returns.
+//!
+//! Kernel event buffer overflows are counted in the health of the chip, but the
+//! request is retained.  The remaining events are drained from the request, and the
+//! sequence number tracking resumes from the most recent event.
+//!
+//! The state of each chip is available from [`RequestSupervisor::health`], which can be
+//! exported to a watchdog or monitoring system.
+//!
+//! # Examples
+//! ```no_run
+//! # use gpiocdev::Result;
+//! # use gpiocdev::line::EdgeDetection;
+//! # use gpiocdev::supervisor::RequestSupervisor;
+//! # use std::time::Duration;
+//! # fn main() -> Result<()> {
+//! let mut buttons = gpiocdev::Request::builder();
+//! buttons
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_lines(&[17, 18])
+//!     .with_edge_detection(EdgeDetection::FallingEdge);
+//! let mut sup = RequestSupervisor::new();
+//! sup.add(&buttons, |evt| println!("{:?}", evt))?;
+//! sup.start()?;
+//! loop {
+//!     std::thread::sleep(Duration::from_secs(10));
+//!     for chip in sup.health().chips {
+//!         println!("{:?}: {} events, {} errors", chip.chip, chip.events, chip.errors);
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::line::EdgeEvent;
+use crate::poll::Ready;
+use crate::request::{Builder, Stop, Worker};
+use crate::uapi::Errno;
+use crate::{Error, Request, Result};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A snapshot of the health of the requests managed by a [`RequestSupervisor`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Health {
+    /// The health of each chip, in the order the chips were added.
+    pub chips: Vec<ChipHealth>,
+}
+
+impl Health {
+    /// Returns true if all requests on all chips are active.
+    pub fn is_healthy(&self) -> bool {
+        self.chips.iter().all(|c| c.running)
+    }
+}
+
+/// The health of the requests on one chip.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChipHealth {
+    /// The path of the chip.
+    pub chip: PathBuf,
+
+    /// All the requests on the chip are active.
+    ///
+    /// False while any request is waiting to be restarted.
+    pub running: bool,
+
+    /// When the most recent edge event on the chip was read.
+    pub last_event: Option<Instant>,
+
+    /// The number of edge events read from the chip.
+    pub events: u64,
+
+    /// The number of errors encountered by requests on the chip,
+    /// including failed restarts.
+    pub errors: u64,
+
+    /// The number of kernel event buffer overflows detected.
+    ///
+    /// Overflows are detected from gaps in the event sequence numbers,
+    /// so are not detected with uAPI v1.
+    pub overflows: u64,
+
+    /// The number of times requests on the chip have been restarted.
+    pub restarts: u64,
+
+    /// The most recent error.
+    pub last_error: Option<Error>,
+}
+
+impl ChipHealth {
+    fn new(chip: PathBuf) -> ChipHealth {
+        ChipHealth {
+            chip,
+            running: true,
+            last_event: None,
+            events: 0,
+            errors: 0,
+            overflows: 0,
+            restarts: 0,
+            last_error: None,
+        }
+    }
+}
+
+struct Shared {
+    health: Mutex<Vec<ChipHealth>>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Vec<ChipHealth>> {
+        self.health
+            .lock()
+            .expect("failed to acquire lock on supervisor health")
+    }
+
+    fn error(&self, chip_idx: usize, e: Error) {
+        let mut health = self.lock();
+        health[chip_idx].errors += 1;
+        health[chip_idx].last_error = Some(e);
+    }
+}
+
+type Handler = Box<dyn FnMut(EdgeEvent) + Send>;
+
+// A request and the means to restart it.
+struct Slot {
+    builder: Builder,
+    req: Option<Request>,
+    retry_at: Instant,
+    last_seqno: u32,
+    handler: Handler,
+}
+
+impl Slot {
+    // Read and handle the available events, counting any overflows.
+    fn service(&mut self, shared: &Shared, chip_idx: usize, ready: &Ready) -> Result<()> {
+        let req = match &self.req {
+            Some(req) => req,
+            None => return Ok(()),
+        };
+        if ready.hangup {
+            return Err(Error::Os(Errno(libc::ENODEV)));
+        }
+        let mut overflow = false;
+        let mut events = 0;
+        while req.has_edge_event()? {
+            let evt = req.read_edge_event()?;
+            if evt.seqno != 0
+                && self.last_seqno != 0
+                && evt.seqno != self.last_seqno.wrapping_add(1)
+            {
+                overflow = true;
+            }
+            self.last_seqno = evt.seqno;
+            (self.handler)(evt);
+            events += 1;
+        }
+        let mut health = shared.lock();
+        let health = &mut health[chip_idx];
+        health.events += events;
+        if events > 0 {
+            health.last_event = Some(Instant::now());
+        }
+        if overflow {
+            health.overflows += 1;
+        }
+        Ok(())
+    }
+
+    // Restart the request if it is due.
+    fn restart(&mut self, shared: &Shared, chip_idx: usize, restart_delay: Duration) {
+        if self.req.is_some() || Instant::now() < self.retry_at {
+            return;
+        }
+        match self.builder.request() {
+            Ok(req) => {
+                self.req = Some(req);
+                self.last_seqno = 0;
+                shared.lock()[chip_idx].restarts += 1;
+            }
+            Err(e) => {
+                shared.error(chip_idx, e);
+                self.retry_at = Instant::now() + restart_delay;
+            }
+        }
+    }
+}
+
+// The reader thread for a chip.
+fn run(
+    shared: &Shared,
+    chip_idx: usize,
+    mut slots: Vec<Slot>,
+    restart_delay: Duration,
+    stop: &Stop,
+) -> Result<()> {
+    let mut active = Vec::with_capacity(slots.len());
+    loop {
+        for slot in slots.iter_mut() {
+            slot.restart(shared, chip_idx, restart_delay);
+        }
+        shared.lock()[chip_idx].running = slots.iter().all(|s| s.req.is_some());
+        active.clear();
+        active.extend(
+            slots
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| slot.req.is_some())
+                .map(|(idx, _)| idx),
+        );
+        // wake when the next failed request is due to be restarted
+        let timeout = slots
+            .iter()
+            .filter(|slot| slot.req.is_none())
+            .map(|slot| slot.retry_at.saturating_duration_since(Instant::now()))
+            .min();
+        let reqs: Vec<&Request> = slots.iter().filter_map(|slot| slot.req.as_ref()).collect();
+        let ready = match stop.wait_any(&reqs, timeout) {
+            Ok(Some(ready)) => ready,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                shared.error(chip_idx, e);
+                if !stop.sleep(restart_delay)? {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        for r in ready.iter() {
+            let slot = &mut slots[active[r.index]];
+            if let Err(e) = slot.service(shared, chip_idx, r) {
+                shared.error(chip_idx, e);
+                slot.req = None;
+                slot.retry_at = Instant::now() + restart_delay;
+            }
+        }
+    }
+}
+
+/// Owns the requests for an application, restarting them if they fail.
+///
+/// Requests are added with a handler for their edge events, then the supervisor
+/// is started, which spawns a reader thread for each chip.
+/// The handlers are called from the reader thread of their chip, so a slow
+/// handler delays the handling of events from other requests on the same chip.
+///
+/// The reader threads are stopped and joined, and the requests released,
+/// when the supervisor is dropped.
+pub struct RequestSupervisor {
+    shared: Arc<Shared>,
+    chips: Vec<Vec<Slot>>,
+    restart_delay: Duration,
+    workers: Vec<Worker>,
+}
+
+impl Default for RequestSupervisor {
+    fn default() -> Self {
+        RequestSupervisor::new()
+    }
+}
+
+impl RequestSupervisor {
+    /// Create an empty supervisor.
+    ///
+    /// The default restart delay is 1 second.
+    pub fn new() -> RequestSupervisor {
+        RequestSupervisor {
+            shared: Arc::new(Shared {
+                health: Mutex::new(Vec::new()),
+            }),
+            chips: Vec::new(),
+            restart_delay: Duration::from_secs(1),
+            workers: Vec::new(),
+        }
+    }
+
+    /// Set the delay between attempts to restart a failed request.
+    pub fn with_restart_delay(mut self, delay: Duration) -> RequestSupervisor {
+        self.restart_delay = delay;
+        self
+    }
+
+    /// Request the lines described by the builder, and handle their edge events.
+    ///
+    /// The lines are requested immediately, so configuration errors are returned here.
+    /// The builder is retained to restart the request if it fails.
+    ///
+    /// Requests may only be added before the supervisor is started.
+    pub fn add<F>(&mut self, builder: &Builder, handler: F) -> Result<()>
+    where
+        F: FnMut(EdgeEvent) + Send + 'static,
+    {
+        if !self.workers.is_empty() {
+            return Err(Error::InvalidArgument(
+                "supervisor has already been started.".to_string(),
+            ));
+        }
+        let mut builder = builder.clone();
+        let req = builder.request()?;
+        let chip = req.chip_path();
+        let slot = Slot {
+            builder,
+            req: Some(req),
+            retry_at: Instant::now(),
+            last_seqno: 0,
+            handler: Box::new(handler),
+        };
+        let mut health = self.shared.lock();
+        match health.iter().position(|c| c.chip == chip) {
+            Some(idx) => self.chips[idx].push(slot),
+            None => {
+                health.push(ChipHealth::new(chip));
+                self.chips.push(vec![slot]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Start the reader threads.
+    pub fn start(&mut self) -> Result<()> {
+        if !self.workers.is_empty() {
+            return Err(Error::InvalidArgument(
+                "supervisor has already been started.".to_string(),
+            ));
+        }
+        for (chip_idx, slots) in self.chips.drain(..).enumerate() {
+            let shared = self.shared.clone();
+            let restart_delay = self.restart_delay;
+            let builder =
+                std::thread::Builder::new().name(format!("gpiocdev-supervisor-{}", chip_idx));
+            let worker = Worker::spawn_with(builder, move |stop| {
+                run(&shared, chip_idx, slots, restart_delay, stop)
+            })?;
+            self.workers.push(worker);
+        }
+        Ok(())
+    }
+
+    /// A snapshot of the health of the requests on each chip.
+    pub fn health(&self) -> Health {
+        Health {
+            chips: self.shared.lock().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health() {
+        let mut health = Health::default();
+        assert!(health.is_healthy());
+        health
+            .chips
+            .push(ChipHealth::new(PathBuf::from("/dev/gpiochip0")));
+        health
+            .chips
+            .push(ChipHealth::new(PathBuf::from("/dev/gpiochip1")));
+        assert!(health.is_healthy());
+        health.chips[1].running = false;
+        assert!(!health.is_healthy());
+    }
+
+    #[test]
+    fn empty() {
+        let mut sup = RequestSupervisor::new().with_restart_delay(Duration::from_millis(10));
+        sup.start().unwrap();
+        assert_eq!(sup.health(), Health::default());
+    }
+}
@@ -0,0 +1,314 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Declarative assertions for tests using the GPIO simulator.
+//!
+//! Tests driving requests with [`gpiosim`] typically pull a line, wait for the change
+//! to propagate, read the result and compare it with the expected value.
+//! The expectations provided here wrap that pattern, polling or waiting for the
+//! expected state until a timeout, and panicking with a descriptive message if it
+//! is not reached.
+//!
+//! Each expectation is configured and then checked by calling `within`, which
+//! returns once the expectation is met and panics if the timeout expires first.
+//!
+//! # Examples
+//! ```no_run
+//! use gpiocdev::line::{EdgeDetection, EdgeKind, Value};
+//! use gpiocdev::testing::{ExpectLevel, ExpectRequest};
+//! use gpiosim::Level;
+//! use std::time::Duration;
+//!
+//! let s = gpiosim::Simpleton::new(8);
+//! let req = gpiocdev::Request::builder()
+//!     .on_chip(s.dev_path())
+//!     .with_line(3)
+//!     .with_edge_detection(EdgeDetection::BothEdges)
+//!     .with_line(5)
+//!     .as_output(Value::Inactive)
+//!     .request()
+//!     .unwrap();
+//!
+//! s.pullup(3).unwrap();
+//! req.expect_edge(EdgeKind::Rising)
+//!     .on(3)
+//!     .within(Duration::from_millis(10));
+//! req.expect_value(3, Value::Active)
+//!     .within(Duration::from_millis(10));
+//!
+//! req.set_value(5, Value::Active).unwrap();
+//! s.chip()
+//!     .expect_level(5, Level::High)
+//!     .within(Duration::from_millis(10));
+//! ```
+
+use crate::line::{EdgeEvent, EdgeKind, Offset, Value};
+use crate::Request;
+use gpiosim::Level;
+use std::time::{Duration, Instant};
+
+// The period between polls of a line value or level.
+const POLL_PERIOD: Duration = Duration::from_millis(1);
+
+/// Expectations on the lines of a [`Request`].
+pub trait ExpectRequest {
+    /// Expect the next edge event to be of the given kind.
+    fn expect_edge(&self, kind: EdgeKind) -> EdgeExpectation<'_>;
+
+    /// Expect the value of a line to become the given value.
+    fn expect_value(&self, offset: Offset, value: Value) -> ValueExpectation<'_>;
+}
+
+impl ExpectRequest for Request {
+    fn expect_edge(&self, kind: EdgeKind) -> EdgeExpectation<'_> {
+        EdgeExpectation {
+            req: self,
+            kind,
+            offset: None,
+        }
+    }
+
+    fn expect_value(&self, offset: Offset, value: Value) -> ValueExpectation<'_> {
+        ValueExpectation {
+            req: self,
+            offset,
+            value,
+        }
+    }
+}
+
+/// Expectations on the level of the lines of a simulator.
+pub trait ExpectLevel {
+    /// Expect the level of a line to become the given level.
+    ///
+    /// The level is that driven by the request holding the line, if it is an output,
+    /// else the simulated pull.
+    fn expect_level(&self, offset: Offset, level: Level) -> LevelExpectation<'_>;
+}
+
+impl ExpectLevel for gpiosim::Chip {
+    fn expect_level(&self, offset: Offset, level: Level) -> LevelExpectation<'_> {
+        LevelExpectation {
+            chip: self,
+            offset,
+            level,
+        }
+    }
+}
+
+impl ExpectLevel for gpiosim::Simpleton {
+    fn expect_level(&self, offset: Offset, level: Level) -> LevelExpectation<'_> {
+        self.chip().expect_level(offset, level)
+    }
+}
+
+/// An expected edge event.
+///
+/// Created by [`ExpectRequest::expect_edge`].
+#[must_use = "expectations are only checked by calling within"]
+pub struct EdgeExpectation<'a> {
+    req: &'a Request,
+    kind: EdgeKind,
+    offset: Option<Offset>,
+}
+
+impl EdgeExpectation<'_> {
+    /// Expect the event to be on the given line.
+    ///
+    /// By default the event may be on any line in the request.
+    pub fn on(mut self, offset: Offset) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Wait for the next edge event and check it matches the expectation.
+    ///
+    /// Returns the event.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no event is received within the timeout, or if the event does not
+    /// match the expectation.
+    #[track_caller]
+    pub fn within(self, timeout: Duration) -> EdgeEvent {
+        match self.req.wait_edge_event(timeout) {
+            Ok(true) => {}
+            Ok(false) => panic!(
+                "expected {:?} edge{} within {:?}, got none",
+                self.kind,
+                self.describe_offset(),
+                timeout
+            ),
+            Err(e) => panic!("failed waiting for edge event: {}", e),
+        }
+        let evt = match self.req.read_edge_event() {
+            Ok(evt) => evt,
+            Err(e) => panic!("failed reading edge event: {}", e),
+        };
+        if evt.kind != self.kind || self.offset.map_or(false, |offset| offset != evt.offset) {
+            panic!(
+                "expected {:?} edge{}, got {:?} edge on line {}",
+                self.kind,
+                self.describe_offset(),
+                evt.kind,
+                evt.offset
+            );
+        }
+        evt
+    }
+
+    fn describe_offset(&self) -> String {
+        match self.offset {
+            Some(offset) => format!(" on line {}", offset),
+            None => String::new(),
+        }
+    }
+}
+
+/// An expected line value.
+///
+/// Created by [`ExpectRequest::expect_value`].
+#[must_use = "expectations are only checked by calling within"]
+pub struct ValueExpectation<'a> {
+    req: &'a Request,
+    offset: Offset,
+    value: Value,
+}
+
+impl ValueExpectation<'_> {
+    /// Poll the value of the line until it matches the expectation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line does not have the expected value within the timeout,
+    /// or if the value cannot be read.
+    #[track_caller]
+    pub fn within(self, timeout: Duration) {
+        let mut last = None;
+        let met = poll_until(timeout, || match self.req.value(self.offset) {
+            Ok(value) => {
+                last = Some(value);
+                value == self.value
+            }
+            Err(e) => panic!("failed reading value of line {}: {}", self.offset, e),
+        });
+        if !met {
+            panic!(
+                "expected line {} to be {:?} within {:?}, got {:?}",
+                self.offset,
+                self.value,
+                timeout,
+                last.unwrap_or(Value::Inactive)
+            );
+        }
+    }
+}
+
+/// An expected simulated line level.
+///
+/// Created by [`ExpectLevel::expect_level`].
+#[must_use = "expectations are only checked by calling within"]
+pub struct LevelExpectation<'a> {
+    chip: &'a gpiosim::Chip,
+    offset: Offset,
+    level: Level,
+}
+
+impl LevelExpectation<'_> {
+    /// Poll the level of the line until it matches the expectation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line does not have the expected level within the timeout,
+    /// or if the level cannot be read.
+    #[track_caller]
+    pub fn within(self, timeout: Duration) {
+        let mut last = None;
+        let met = poll_until(timeout, || match self.chip.get_level(self.offset) {
+            Ok(level) => {
+                last = Some(level);
+                level == self.level
+            }
+            Err(e) => panic!("failed reading level of line {}: {}", self.offset, e),
+        });
+        if !met {
+            panic!(
+                "expected line {} to be {:?} within {:?}, got {:?}",
+                self.offset,
+                self.level,
+                timeout,
+                last.unwrap_or(Level::Low)
+            );
+        }
+    }
+}
+
+// Poll the condition until it holds or the timeout expires.
+//
+// The condition is always checked at least once, and once more after the timeout.
+// A timeout too large to be represented as a deadline polls indefinitely.
+fn poll_until<F: FnMut() -> bool>(timeout: Duration, mut cond: F) -> bool {
+    let deadline = Instant::now().checked_add(timeout);
+    loop {
+        if cond() {
+            return true;
+        }
+        let mut period = POLL_PERIOD;
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            period = period.min(deadline - now);
+        }
+        std::thread::sleep(period);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_until_met() {
+        let mut count = 0;
+        assert!(poll_until(Duration::from_millis(50), || {
+            count += 1;
+            count == 3
+        }));
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn poll_until_timeout() {
+        let mut count = 0;
+        let start = Instant::now();
+        assert!(!poll_until(Duration::from_millis(5), || {
+            count += 1;
+            false
+        }));
+        assert!(start.elapsed() >= Duration::from_millis(5));
+        assert!(count >= 2);
+    }
+
+    #[test]
+    fn poll_until_zero_timeout() {
+        let mut count = 0;
+        assert!(!poll_until(Duration::ZERO, || {
+            count += 1;
+            false
+        }));
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn poll_until_max_timeout() {
+        let mut count = 0;
+        assert!(poll_until(Duration::MAX, || {
+            count += 1;
+            count == 3
+        }));
+        assert_eq!(count, 3);
+    }
+}
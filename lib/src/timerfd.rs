@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::io;
+use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::time::Duration;
+
+// A thin wrapper around a timerfd, using CLOCK_MONOTONIC unless created with_clock.
+#[derive(Debug)]
+pub(crate) struct TimerFd(OwnedFd);
+
+impl TimerFd {
+    // Create a disarmed timer.
+    //
+    // A non-blocking timer is required for use with async reactors.
+    pub(crate) fn new(nonblocking: bool) -> io::Result<TimerFd> {
+        TimerFd::with_clock(libc::CLOCK_MONOTONIC, nonblocking)
+    }
+
+    // Create a disarmed timer using the given clock.
+    pub(crate) fn with_clock(clock: libc::clockid_t, nonblocking: bool) -> io::Result<TimerFd> {
+        let mut flags = libc::TFD_CLOEXEC;
+        if nonblocking {
+            flags |= libc::TFD_NONBLOCK;
+        }
+        // SAFETY: no pointers involved, and the returned fd is checked before use.
+        let fd = unsafe { libc::timerfd_create(clock, flags) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: fd is a newly created and otherwise unowned fd.
+        Ok(TimerFd(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    // Arm the timer to expire once, after the delay.
+    //
+    // A zero delay disarms the timer.
+    pub(crate) fn set_oneshot(&self, delay: Duration) -> io::Result<()> {
+        self.settime(0, delay, Duration::ZERO)
+    }
+
+    // Arm the timer to expire once, at an absolute CLOCK_MONOTONIC time.
+    //
+    // A deadline in the past expires immediately.
+    pub(crate) fn set_deadline(&self, deadline: Duration) -> io::Result<()> {
+        // a zero value would disarm the timer
+        let deadline = deadline.max(Duration::from_nanos(1));
+        self.settime(libc::TFD_TIMER_ABSTIME, deadline, Duration::ZERO)
+    }
+
+    // Arm the timer to expire repeatedly, every period, starting one period from now.
+    //
+    // A zero period disarms the timer.
+    pub(crate) fn set_periodic(&self, period: Duration) -> io::Result<()> {
+        self.settime(0, period, period)
+    }
+
+    // Disarm the timer.
+    pub(crate) fn disarm(&self) -> io::Result<()> {
+        self.settime(0, Duration::ZERO, Duration::ZERO)
+    }
+
+    fn settime(&self, flags: libc::c_int, value: Duration, interval: Duration) -> io::Result<()> {
+        let spec = libc::itimerspec {
+            it_interval: timespec(interval),
+            it_value: timespec(value),
+        };
+        // SAFETY: spec is a valid itimerspec and the old value is not requested.
+        let rc = unsafe {
+            libc::timerfd_settime(self.0.as_raw_fd(), flags, &spec, std::ptr::null_mut())
+        };
+        if rc < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Read the number of expirations since the last read.
+    //
+    // Blocks until the timer expires, unless the timer is non-blocking.
+    pub(crate) fn read(&self) -> io::Result<u64> {
+        let mut expirations: u64 = 0;
+        // SAFETY: reading 8 bytes into a u64.
+        let n = unsafe {
+            libc::read(
+                self.0.as_raw_fd(),
+                &mut expirations as *mut u64 as *mut libc::c_void,
+                8,
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(expirations)
+    }
+}
+
+impl AsFd for TimerFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for TimerFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+// The current CLOCK_MONOTONIC time.
+pub(crate) fn monotonic_now() -> Duration {
+    clock_now(libc::CLOCK_MONOTONIC)
+}
+
+// The current CLOCK_BOOTTIME time.
+//
+// Unlike CLOCK_MONOTONIC, this includes any time the system is suspended.
+pub(crate) fn boottime_now() -> Duration {
+    clock_now(libc::CLOCK_BOOTTIME)
+}
+
+fn clock_now(clock: libc::clockid_t) -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: ts is a valid timespec and the clocks used are always supported.
+    unsafe {
+        libc::clock_gettime(clock, &mut ts);
+    }
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+fn timespec(d: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: d.as_secs() as libc::time_t,
+        tv_nsec: d.subsec_nanos() as libc::c_long,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn oneshot() {
+        let t = TimerFd::new(false).unwrap();
+        let start = Instant::now();
+        t.set_oneshot(Duration::from_millis(10)).unwrap();
+        assert_eq!(t.read().unwrap(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn deadline() {
+        let t = TimerFd::new(false).unwrap();
+        let deadline = monotonic_now() + Duration::from_millis(10);
+        t.set_deadline(deadline).unwrap();
+        assert_eq!(t.read().unwrap(), 1);
+        assert!(monotonic_now() >= deadline);
+
+        // past deadlines expire immediately
+        t.set_deadline(Duration::ZERO).unwrap();
+        assert_eq!(t.read().unwrap(), 1);
+    }
+
+    #[test]
+    fn periodic() {
+        let t = TimerFd::new(false).unwrap();
+        let start = Instant::now();
+        t.set_periodic(Duration::from_millis(5)).unwrap();
+        assert_eq!(t.read().unwrap(), 1);
+        assert_eq!(t.read().unwrap(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        // missed expirations are accumulated
+        std::thread::sleep(Duration::from_millis(12));
+        assert!(t.read().unwrap() >= 2);
+    }
+
+    #[test]
+    fn disarm() {
+        let t = TimerFd::new(true).unwrap();
+        t.set_oneshot(Duration::from_millis(1)).unwrap();
+        t.disarm().unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(t.read().unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn nonblocking() {
+        let t = TimerFd::new(true).unwrap();
+        assert_eq!(t.read().unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+        t.set_oneshot(Duration::from_millis(1)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(t.read().unwrap(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Capture and export of the edges on a single line.
+//!
+//! An [`EdgeTrace`] collects the edge events for one line, and can resample them into
+//! a fixed-rate stream of samples for analysis in tools that expect uniform sampling,
+//! such as audio editors or DSP pipelines.
+//!
+//! The samples may be exported as CSV, or as an 8-bit mono PCM WAV file.
+
+use crate::line::{EdgeEvent, EdgeKind, Offset, Value};
+use std::io::{self, Write};
+
+/// The edges captured from a single line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EdgeTrace {
+    offset: Offset,
+    initial: Value,
+    start_ns: Option<u64>,
+    edges: Vec<(u64, Value)>,
+}
+
+impl EdgeTrace {
+    /// Create an empty trace for a line.
+    ///
+    /// * `offset` - the line being traced.
+    /// * `initial` - the value of the line at the start of the trace.
+    pub fn new(offset: Offset, initial: Value) -> EdgeTrace {
+        EdgeTrace {
+            offset,
+            initial,
+            start_ns: None,
+            edges: Vec::new(),
+        }
+    }
+
+    /// Set the timestamp of the start of the trace.
+    ///
+    /// If not set then the trace starts at the first edge.
+    pub fn with_start(mut self, start_ns: u64) -> EdgeTrace {
+        self.start_ns = Some(start_ns);
+        self
+    }
+
+    /// Add an edge to the trace.
+    ///
+    /// Events for other lines are ignored, as are events preceding the start of the trace.
+    /// Events are expected to be added in timestamp order.
+    pub fn push(&mut self, evt: &EdgeEvent) {
+        if evt.offset != self.offset {
+            return;
+        }
+        let start = *self.start_ns.get_or_insert(evt.timestamp_ns);
+        if evt.timestamp_ns < start {
+            return;
+        }
+        let value = match evt.kind {
+            EdgeKind::Rising => Value::Active,
+            EdgeKind::Falling => Value::Inactive,
+        };
+        self.edges.push((evt.timestamp_ns, value));
+    }
+
+    /// The number of edges in the trace.
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns true if the trace contains no edges.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Resample the trace into a fixed-rate sequence of values.
+    ///
+    /// Samples are taken from the start of the trace up to, but excluding, `end_ns`.
+    ///
+    /// * `rate` - the sample rate, in Hz.
+    /// * `end_ns` - the timestamp of the end of the trace.
+    pub fn resample(&self, rate: u32, end_ns: u64) -> Vec<Value> {
+        let start = match self.start_ns {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+        if rate == 0 || end_ns <= start {
+            return Vec::new();
+        }
+        let num_samples = ((end_ns - start) as u128 * rate as u128 / 1_000_000_000) as usize;
+        let mut samples = Vec::with_capacity(num_samples);
+        let mut value = self.initial;
+        let mut edges = self.edges.iter().peekable();
+        for idx in 0..num_samples {
+            let t = start + (idx as u128 * 1_000_000_000 / rate as u128) as u64;
+            while let Some((ts, v)) = edges.peek() {
+                if *ts > t {
+                    break;
+                }
+                value = *v;
+                edges.next();
+            }
+            samples.push(value);
+        }
+        samples
+    }
+
+    /// Write the resampled trace as CSV, with a header row and one row per sample
+    /// containing the time since the start of the trace, in seconds, and the value.
+    ///
+    /// * `rate` - the sample rate, in Hz.
+    /// * `end_ns` - the timestamp of the end of the trace.
+    pub fn write_csv<W: Write>(&self, w: &mut W, rate: u32, end_ns: u64) -> io::Result<()> {
+        writeln!(w, "time,value")?;
+        for (idx, value) in self.resample(rate, end_ns).iter().enumerate() {
+            let t = idx as u128 * 1_000_000_000 / rate as u128;
+            writeln!(
+                w,
+                "{}.{:09},{}",
+                t / 1_000_000_000,
+                t % 1_000_000_000,
+                u8::from(*value == Value::Active)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the resampled trace as an 8-bit mono PCM WAV file.
+    ///
+    /// Inactive samples are written as 0x00 and active samples as 0xff.
+    ///
+    /// * `rate` - the sample rate, in Hz.
+    /// * `end_ns` - the timestamp of the end of the trace.
+    pub fn write_wav<W: Write>(&self, w: &mut W, rate: u32, end_ns: u64) -> io::Result<()> {
+        let samples = self.resample(rate, end_ns);
+        let data_len = u32::try_from(samples.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "trace too long for WAV"))?;
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + data_len).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+        w.write_all(b"fmt ")?;
+        w.write_all(&16_u32.to_le_bytes())?;
+        w.write_all(&1_u16.to_le_bytes())?; // PCM
+        w.write_all(&1_u16.to_le_bytes())?; // mono
+        w.write_all(&rate.to_le_bytes())?;
+        w.write_all(&rate.to_le_bytes())?; // byte rate
+        w.write_all(&1_u16.to_le_bytes())?; // block align
+        w.write_all(&8_u16.to_le_bytes())?; // bits per sample
+        w.write_all(b"data")?;
+        w.write_all(&data_len.to_le_bytes())?;
+        let data: Vec<u8> = samples
+            .iter()
+            .map(|v| match v {
+                Value::Active => 0xff,
+                Value::Inactive => 0x00,
+            })
+            .collect();
+        w.write_all(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(offset: Offset, kind: EdgeKind, timestamp_ns: u64) -> EdgeEvent {
+        EdgeEvent {
+            timestamp_ns,
+            kind,
+            offset,
+            seqno: 0,
+            line_seqno: 0,
+        }
+    }
+
+    fn trace() -> EdgeTrace {
+        let mut t = EdgeTrace::new(3, Value::Inactive).with_start(1_000);
+        t.push(&edge(3, EdgeKind::Rising, 1_000 + 2_500));
+        t.push(&edge(4, EdgeKind::Rising, 1_000 + 3_000));
+        t.push(&edge(3, EdgeKind::Falling, 1_000 + 6_000));
+        t
+    }
+
+    #[test]
+    fn push() {
+        let mut t = EdgeTrace::new(3, Value::Inactive);
+        assert!(t.is_empty());
+        t.push(&edge(3, EdgeKind::Rising, 500));
+        // before start
+        t.push(&edge(3, EdgeKind::Falling, 400));
+        // other line
+        t.push(&edge(2, EdgeKind::Falling, 600));
+        assert_eq!(t.len(), 1);
+        assert_eq!(t.start_ns, Some(500));
+    }
+
+    #[test]
+    fn resample() {
+        use Value::{Active as A, Inactive as I};
+        // 1MHz => 1us per sample
+        let t = trace();
+        assert_eq!(
+            t.resample(1_000_000, 1_000 + 8_000),
+            vec![I, I, I, A, A, A, I, I]
+        );
+        assert_eq!(t.resample(0, 1_000 + 8_000), vec![]);
+        assert_eq!(t.resample(1_000_000, 500), vec![]);
+        assert!(EdgeTrace::new(1, I).resample(1_000_000, 1_000).is_empty());
+    }
+
+    #[test]
+    fn write_csv() {
+        let mut buf = Vec::new();
+        trace().write_csv(&mut buf, 500_000, 1_000 + 8_000).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "time,value\n\
+            0.000000000,0\n\
+            0.000002000,0\n\
+            0.000004000,1\n\
+            0.000006000,0\n"
+        );
+    }
+
+    #[test]
+    fn write_wav() {
+        let mut buf = Vec::new();
+        trace()
+            .write_wav(&mut buf, 1_000_000, 1_000 + 8_000)
+            .unwrap();
+        assert_eq!(buf.len(), 44 + 8);
+        assert_eq!(&buf[0..4], b"RIFF");
+        assert_eq!(&buf[4..8], &44_u32.to_le_bytes());
+        assert_eq!(&buf[8..16], b"WAVEfmt ");
+        assert_eq!(&buf[24..28], &1_000_000_u32.to_le_bytes());
+        assert_eq!(&buf[36..40], b"data");
+        assert_eq!(&buf[40..44], &8_u32.to_le_bytes());
+        assert_eq!(&buf[44..], &[0, 0, 0, 0xff, 0xff, 0xff, 0, 0]);
+    }
+}
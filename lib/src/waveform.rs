@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Timed sequences of output values.
+//!
+//! A [`Waveform`] is a sequence of [`Step`]s, each holding an output line at
+//! a value for a period of time.
+//! Waveforms are played on a line in a [`Request`] using [`Request::play_waveform`].
+//! In async applications they may also be played using the `play_waveform` method of the
+//! `AsyncRequest` wrappers, which time the steps using the reactor rather than a blocked thread.
+//!
+//! Step timing is based on absolute deadlines, so timing errors in individual steps,
+//! such as those due to scheduling latency, do not accumulate over the waveform.
+//!
+//! Sequences of [`Values`] frames, such as those generated by [`marquee`] and [`chaser`],
+//! are played across multiple lines using [`Request::play_frames`].
+//!
+//! A [`Sweep`] generates a PWM waveform with its frequency or duty cycle ramped
+//! between bounds over time.
+//!
+//! [`Values`]: crate::line::Values
+
+use crate::line::{Offset, Value};
+use crate::{Request, Result};
+use std::time::{Duration, Instant};
+
+mod frames;
+pub(crate) use self::frames::play as play_frames;
+pub use self::frames::{chaser, marquee};
+
+mod ook;
+pub use self::ook::{OokEncoding, OokTransmitter};
+
+mod sweep;
+pub use self::sweep::{Sweep, SweepScale, MAX_SWEEP_FREQUENCY};
+
+/// A single step in a [`Waveform`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Step {
+    /// The value of the line for the step.
+    pub value: Value,
+
+    /// The period the line is held at the value.
+    pub duration: Duration,
+}
+
+/// A timed sequence of values for an output line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Waveform {
+    steps: Vec<Step>,
+}
+
+impl Waveform {
+    /// Create an empty waveform.
+    pub fn new() -> Waveform {
+        Waveform::default()
+    }
+
+    /// Add a step to the end of the waveform.
+    pub fn push(&mut self, value: Value, duration: Duration) -> &mut Self {
+        self.steps.push(Step { value, duration });
+        self
+    }
+
+    /// Add the steps of another waveform to the end of the waveform.
+    pub fn append(&mut self, wf: &Waveform) -> &mut Self {
+        self.steps.extend_from_slice(&wf.steps);
+        self
+    }
+
+    /// Repeat the current contents of the waveform so it occurs `count` times in total.
+    ///
+    /// A count of zero clears the waveform.
+    pub fn repeat(&mut self, count: usize) -> &mut Self {
+        let len = self.steps.len();
+        if count == 0 {
+            self.steps.clear();
+        } else {
+            self.steps.reserve(len * (count - 1));
+            for _ in 1..count {
+                self.steps.extend_from_within(0..len);
+            }
+        }
+        self
+    }
+
+    /// The steps in the waveform.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// The total duration of the waveform.
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|s| s.duration).sum()
+    }
+
+    /// Returns true if the waveform contains no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The number of steps in the waveform.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+impl FromIterator<Step> for Waveform {
+    fn from_iter<I: IntoIterator<Item = Step>>(iter: I) -> Self {
+        Waveform {
+            steps: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromIterator<(Value, Duration)> for Waveform {
+    fn from_iter<I: IntoIterator<Item = (Value, Duration)>>(iter: I) -> Self {
+        Waveform {
+            steps: iter
+                .into_iter()
+                .map(|(value, duration)| Step { value, duration })
+                .collect(),
+        }
+    }
+}
+
+// Play the waveform on the line, returning once the final step has elapsed.
+pub(crate) fn play(req: &Request, offset: Offset, wf: &Waveform) -> Result<()> {
+    let mut deadline = Instant::now();
+    for step in wf.steps() {
+        req.set_value(offset, step.value)?;
+        deadline += step.duration;
+        sleep_until(deadline);
+    }
+    Ok(())
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        std::thread::sleep(deadline - now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn push() {
+        let mut wf = Waveform::new();
+        assert!(wf.is_empty());
+        wf.push(Value::Active, MS).push(Value::Inactive, 2 * MS);
+        assert_eq!(wf.len(), 2);
+        assert_eq!(
+            wf.steps(),
+            &[
+                Step {
+                    value: Value::Active,
+                    duration: MS
+                },
+                Step {
+                    value: Value::Inactive,
+                    duration: 2 * MS
+                }
+            ]
+        );
+        assert_eq!(wf.duration(), 3 * MS);
+    }
+
+    #[test]
+    fn append() {
+        let mut wf = Waveform::new();
+        wf.push(Value::Active, MS);
+        let mut tail = Waveform::new();
+        tail.push(Value::Inactive, 2 * MS);
+        wf.append(&tail);
+        let expected: Waveform = [(Value::Active, MS), (Value::Inactive, 2 * MS)]
+            .into_iter()
+            .collect();
+        assert_eq!(wf, expected);
+    }
+
+    #[test]
+    fn repeat() {
+        let mut wf: Waveform = [(Value::Active, MS), (Value::Inactive, 2 * MS)]
+            .into_iter()
+            .collect();
+        wf.repeat(3);
+        assert_eq!(wf.len(), 6);
+        assert_eq!(wf.duration(), 9 * MS);
+        assert_eq!(wf.steps()[4].value, Value::Active);
+        assert_eq!(wf.steps()[5].value, Value::Inactive);
+
+        wf.repeat(1);
+        assert_eq!(wf.len(), 6);
+
+        wf.repeat(0);
+        assert!(wf.is_empty());
+    }
+}
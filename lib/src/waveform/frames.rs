@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::sleep_until;
+use crate::line::{Offset, Value, Values};
+use crate::{Error, Request, Result};
+use std::time::{Duration, Instant};
+
+// Play the frames on the request, returning once the final frame period has elapsed.
+pub(crate) fn play(
+    req: &Request,
+    offsets: &[Offset],
+    frames: &[Values],
+    frame_period: Duration,
+) -> Result<()> {
+    // check all frames before setting any, so a bad frame can't leave the
+    // sequence partially played.
+    for (idx, frame) in frames.iter().enumerate() {
+        if frame.is_empty() {
+            return Err(Error::InvalidArgument(format!("frame {} is empty.", idx)));
+        }
+        if let Some(lv) = frame.iter().find(|lv| !offsets.contains(&lv.offset)) {
+            return Err(Error::InvalidArgument(format!(
+                "offset {} in frame {} is not a requested line.",
+                lv.offset, idx
+            )));
+        }
+    }
+    let mut deadline = Instant::now();
+    for frame in frames {
+        req.set_values(frame)?;
+        deadline += frame_period;
+        sleep_until(deadline);
+    }
+    Ok(())
+}
+
+/// Generate the frames to scroll a pattern of values across a set of lines.
+///
+/// The pattern starts on the first line and moves one line towards the end of
+/// `offsets` in each subsequent frame, wrapping back to the start.
+/// Lines not covered by the pattern are set inactive.
+///
+/// One frame is generated for each line, so repeatedly playing the frames
+/// scrolls the pattern continuously.
+///
+/// # Examples
+/// ```
+/// # use gpiocdev::line::Value::{Active, Inactive};
+/// # use gpiocdev::waveform::marquee;
+/// let frames = marquee(&[1, 2, 3, 4], &[Active, Active]);
+/// assert_eq!(frames.len(), 4);
+/// assert_eq!(frames[1].get(1), Some(Inactive));
+/// assert_eq!(frames[1].get(2), Some(Active));
+/// assert_eq!(frames[1].get(3), Some(Active));
+/// assert_eq!(frames[3].get(1), Some(Active));
+/// ```
+pub fn marquee(offsets: &[Offset], pattern: &[Value]) -> Vec<Values> {
+    let num_lines = offsets.len();
+    (0..num_lines)
+        .map(|shift| {
+            offsets
+                .iter()
+                .enumerate()
+                .map(|(idx, offset)| {
+                    let pidx = (idx + num_lines - shift) % num_lines;
+                    let value = pattern.get(pidx).copied().unwrap_or(Value::Inactive);
+                    (*offset, value)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Generate the frames for a single active line chasing across a set of lines.
+///
+/// Equivalent to a [`marquee`] with a pattern of a single active value.
+pub fn chaser(offsets: &[Offset]) -> Vec<Values> {
+    marquee(offsets, &[Value::Active])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Value::{Active, Inactive};
+
+    #[test]
+    fn chaser() {
+        let frames = super::chaser(&[3, 5, 7]);
+        let expected: Vec<Values> = vec![
+            [(3, Active), (5, Inactive), (7, Inactive)]
+                .into_iter()
+                .collect(),
+            [(3, Inactive), (5, Active), (7, Inactive)]
+                .into_iter()
+                .collect(),
+            [(3, Inactive), (5, Inactive), (7, Active)]
+                .into_iter()
+                .collect(),
+        ];
+        assert_eq!(frames, expected);
+
+        assert!(super::chaser(&[]).is_empty());
+    }
+
+    #[test]
+    fn marquee() {
+        let frames = super::marquee(&[1, 2, 3], &[Active, Inactive, Active]);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].get(1), Some(Active));
+        assert_eq!(frames[0].get(2), Some(Inactive));
+        assert_eq!(frames[0].get(3), Some(Active));
+        assert_eq!(frames[1].get(1), Some(Active));
+        assert_eq!(frames[1].get(2), Some(Active));
+        assert_eq!(frames[1].get(3), Some(Inactive));
+
+        // pattern longer than lines is truncated
+        let frames = super::marquee(&[1, 2], &[Active, Inactive, Active, Active]);
+        assert_eq!(frames[0].get(1), Some(Active));
+        assert_eq!(frames[0].get(2), Some(Inactive));
+        assert_eq!(frames[1].get(1), Some(Inactive));
+        assert_eq!(frames[1].get(2), Some(Active));
+    }
+}
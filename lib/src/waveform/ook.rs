@@ -0,0 +1,218 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Waveform;
+use crate::line::{Offset, Value};
+use crate::{Request, Result};
+use std::time::Duration;
+
+/// The timing of the symbols used to encode bits for on-off keying.
+///
+/// Each symbol is a period with the line active followed by a period with the line inactive.
+/// Zero length periods are omitted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OokEncoding {
+    /// The active and inactive periods encoding a 1 bit.
+    pub one: (Duration, Duration),
+
+    /// The active and inactive periods encoding a 0 bit.
+    pub zero: (Duration, Duration),
+
+    /// The active and inactive periods of an optional sync symbol sent at the start of each frame.
+    pub sync: Option<(Duration, Duration)>,
+}
+
+impl OokEncoding {
+    /// Plain on-off keying, with each bit sent as a single symbol period
+    /// that is active for a 1 and inactive for a 0.
+    pub fn nrz(period: Duration) -> OokEncoding {
+        OokEncoding {
+            one: (period, Duration::ZERO),
+            zero: (Duration::ZERO, period),
+            sync: None,
+        }
+    }
+
+    /// Pulse width encoding, with each bit sent as an active pulse followed by
+    /// an inactive gap, both specified as a multiple of a base period.
+    ///
+    /// e.g. the common 433MHz remote-control encoding uses
+    /// `OokEncoding::pulse_width(period, (3, 1), (1, 3)).with_sync(period, 31 * period)`.
+    pub fn pulse_width(period: Duration, one: (u32, u32), zero: (u32, u32)) -> OokEncoding {
+        OokEncoding {
+            one: (period * one.0, period * one.1),
+            zero: (period * zero.0, period * zero.1),
+            sync: None,
+        }
+    }
+
+    /// Add a sync symbol to the start of each frame.
+    pub fn with_sync(mut self, active: Duration, inactive: Duration) -> OokEncoding {
+        self.sync = Some((active, inactive));
+        self
+    }
+}
+
+/// Transmits bit patterns on a single output line using on-off keying.
+///
+/// This is intended for driving simple transmitters, such as 433MHz OOK remote-control
+/// modules, where the line directly keys the carrier.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use gpiocdev::{Request, Result};
+/// # use gpiocdev::line::Value;
+/// # use gpiocdev::waveform::{OokEncoding, OokTransmitter};
+/// # use std::time::Duration;
+/// # fn main() -> Result<()> {
+/// let req = Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_line(17)
+///     .as_output(Value::Inactive)
+///     .request()?;
+/// let period = Duration::from_micros(350);
+/// let tx = OokTransmitter::new(
+///     OokEncoding::pulse_width(period, (3, 1), (1, 3)).with_sync(period, 31 * period),
+/// )
+/// .with_repeats(4)
+/// .with_gap(Duration::from_millis(10));
+/// tx.transmit(&req, 17, &[true, false, true, true, false])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OokTransmitter {
+    encoding: OokEncoding,
+    repeats: usize,
+    gap: Duration,
+}
+
+impl OokTransmitter {
+    /// Create a transmitter that sends each frame once.
+    pub fn new(encoding: OokEncoding) -> OokTransmitter {
+        OokTransmitter {
+            encoding,
+            repeats: 1,
+            gap: Duration::ZERO,
+        }
+    }
+
+    /// Set the number of times each frame is sent.
+    pub fn with_repeats(mut self, repeats: usize) -> OokTransmitter {
+        self.repeats = repeats;
+        self
+    }
+
+    /// Set the inactive period between repeated frames.
+    pub fn with_gap(mut self, gap: Duration) -> OokTransmitter {
+        self.gap = gap;
+        self
+    }
+
+    /// The waveform that transmits the bits.
+    ///
+    /// The line is left inactive at the end of the waveform.
+    pub fn waveform(&self, bits: &[bool]) -> Waveform {
+        let mut frame = Waveform::new();
+        if let Some(sync) = self.encoding.sync {
+            push_symbol(&mut frame, sync);
+        }
+        for bit in bits {
+            if *bit {
+                push_symbol(&mut frame, self.encoding.one);
+            } else {
+                push_symbol(&mut frame, self.encoding.zero);
+            }
+        }
+        let mut wf = Waveform::new();
+        for idx in 0..self.repeats {
+            if idx > 0 && !self.gap.is_zero() {
+                wf.push(Value::Inactive, self.gap);
+            }
+            wf.append(&frame);
+        }
+        if let Some(last) = wf.steps().last() {
+            if last.value == Value::Active {
+                wf.push(Value::Inactive, Duration::ZERO);
+            }
+        }
+        wf
+    }
+
+    /// Transmit the bits on the line.
+    ///
+    /// Blocks until the transmission is complete.
+    pub fn transmit(&self, req: &Request, offset: Offset, bits: &[bool]) -> Result<()> {
+        req.play_waveform(offset, &self.waveform(bits))
+    }
+}
+
+fn push_symbol(wf: &mut Waveform, symbol: (Duration, Duration)) {
+    if !symbol.0.is_zero() {
+        wf.push(Value::Active, symbol.0);
+    }
+    if !symbol.1.is_zero() {
+        wf.push(Value::Inactive, symbol.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const US: Duration = Duration::from_micros(1);
+
+    #[test]
+    fn nrz() {
+        let tx = OokTransmitter::new(OokEncoding::nrz(10 * US));
+        let wf = tx.waveform(&[true, false, false, true]);
+        let expected: Waveform = [
+            (Value::Active, 10 * US),
+            (Value::Inactive, 10 * US),
+            (Value::Inactive, 10 * US),
+            (Value::Active, 10 * US),
+            (Value::Inactive, Duration::ZERO),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(wf, expected);
+    }
+
+    #[test]
+    fn pulse_width() {
+        let enc = OokEncoding::pulse_width(US, (3, 1), (1, 3)).with_sync(US, 31 * US);
+        assert_eq!(enc.one, (3 * US, US));
+        assert_eq!(enc.zero, (US, 3 * US));
+        assert_eq!(enc.sync, Some((US, 31 * US)));
+        let tx = OokTransmitter::new(enc);
+        let wf = tx.waveform(&[true, false]);
+        let expected: Waveform = [
+            (Value::Active, US),
+            (Value::Inactive, 31 * US),
+            (Value::Active, 3 * US),
+            (Value::Inactive, US),
+            (Value::Active, US),
+            (Value::Inactive, 3 * US),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(wf, expected);
+    }
+
+    #[test]
+    fn repeats() {
+        let tx = OokTransmitter::new(OokEncoding::nrz(US))
+            .with_repeats(3)
+            .with_gap(100 * US);
+        let wf = tx.waveform(&[true, false]);
+        assert_eq!(wf.len(), 8);
+        assert_eq!(wf.duration(), 206 * US);
+        assert_eq!(wf.steps()[2].value, Value::Inactive);
+        assert_eq!(wf.steps()[2].duration, 100 * US);
+
+        let tx = OokTransmitter::new(OokEncoding::nrz(US)).with_repeats(0);
+        assert!(tx.waveform(&[true]).is_empty());
+    }
+}
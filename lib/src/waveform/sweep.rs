@@ -0,0 +1,272 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Waveform;
+use crate::line::Value;
+use crate::{Error, Result};
+use std::time::Duration;
+
+/// The highest frequency, in Hz, that a sweep may reach.
+///
+/// Shorter periods cannot be reliably generated from userspace, and would
+/// result in excessively large waveforms.
+pub const MAX_SWEEP_FREQUENCY: f64 = 1_000_000.0;
+
+/// How a swept parameter changes between its bounds.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SweepScale {
+    /// The parameter changes by equal amounts in equal times.
+    #[default]
+    Linear,
+
+    /// The parameter changes by equal ratios in equal times.
+    ///
+    /// The bounds of any parameter that changes must be positive.
+    Log,
+}
+
+/// A PWM signal with its frequency and duty cycle ramped between bounds over time.
+///
+/// Sweeps are used to characterize the response of filters, buzzers, motors and
+/// the like over a range of frequencies or duty cycles.
+///
+/// # Examples
+/// ```no_run
+/// # use gpiocdev::Result;
+/// # use gpiocdev::line::Value;
+/// # use gpiocdev::waveform::{Sweep, SweepScale};
+/// # use std::time::Duration;
+/// # fn main() -> Result<()> {
+/// let req = gpiocdev::Request::builder()
+///     .on_chip("/dev/gpiochip0")
+///     .with_line(22)
+///     .as_output(Value::Inactive)
+///     .request()?;
+/// // sweep a buzzer from 100Hz to 5kHz over 10 seconds
+/// let wf = Sweep::frequency(100.0, 5000.0, 0.5, Duration::from_secs(10))
+///     .with_scale(SweepScale::Log)
+///     .waveform()?;
+/// req.play_waveform(22, &wf)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sweep {
+    /// The frequency, in Hz, at the start and end of the sweep.
+    pub frequency: (f64, f64),
+
+    /// The duty cycle, as a fraction from 0 to 1, at the start and end of the sweep.
+    pub duty: (f64, f64),
+
+    /// The duration of the sweep.
+    pub duration: Duration,
+
+    /// How the frequency and duty cycle change between their bounds.
+    pub scale: SweepScale,
+}
+
+impl Sweep {
+    /// A sweep of the frequency with a fixed duty cycle.
+    pub fn frequency(from: f64, to: f64, duty: f64, duration: Duration) -> Sweep {
+        Sweep {
+            frequency: (from, to),
+            duty: (duty, duty),
+            duration,
+            scale: SweepScale::Linear,
+        }
+    }
+
+    /// A sweep of the duty cycle with a fixed frequency.
+    pub fn duty(frequency: f64, from: f64, to: f64, duration: Duration) -> Sweep {
+        Sweep {
+            frequency: (frequency, frequency),
+            duty: (from, to),
+            duration,
+            scale: SweepScale::Linear,
+        }
+    }
+
+    /// Set how the parameters change between their bounds.
+    pub fn with_scale(mut self, scale: SweepScale) -> Sweep {
+        self.scale = scale;
+        self
+    }
+
+    /// Generate the waveform for the sweep.
+    ///
+    /// Each cycle is generated with the parameters at the time the cycle starts,
+    /// and the final cycle is completed, so the waveform may slightly exceed the
+    /// duration of the sweep.
+    ///
+    /// Returns an error if the frequency is not positive or exceeds
+    /// [`MAX_SWEEP_FREQUENCY`], the duty cycle is
+    /// outside the range 0 to 1, or a log scale is applied to a changing
+    /// parameter with a bound of zero.
+    pub fn waveform(&self) -> Result<Waveform> {
+        self.check()?;
+        let mut wf = Waveform::new();
+        let total = self.duration.as_secs_f64();
+        let mut t = Duration::ZERO;
+        while t < self.duration {
+            let (frequency, duty) = self.at(t.as_secs_f64() / total);
+            let period = Duration::from_secs_f64(1.0 / frequency);
+            let active = Duration::from_secs_f64(period.as_secs_f64() * duty);
+            push_merged(&mut wf, Value::Active, active);
+            push_merged(&mut wf, Value::Inactive, period.saturating_sub(active));
+            t += period;
+        }
+        Ok(wf)
+    }
+
+    fn check(&self) -> Result<()> {
+        let (f0, f1) = self.frequency;
+        if !(f0 > 0.0 && f1 > 0.0 && f0.is_finite() && f1.is_finite()) {
+            return Err(Error::InvalidArgument(
+                "sweep frequency must be positive.".to_string(),
+            ));
+        }
+        if f0 > MAX_SWEEP_FREQUENCY || f1 > MAX_SWEEP_FREQUENCY {
+            return Err(Error::InvalidArgument(format!(
+                "sweep frequency must not exceed {}Hz.",
+                MAX_SWEEP_FREQUENCY
+            )));
+        }
+        let (d0, d1) = self.duty;
+        if !((0.0..=1.0).contains(&d0) && (0.0..=1.0).contains(&d1)) {
+            return Err(Error::InvalidArgument(
+                "sweep duty cycle must be between 0 and 1.".to_string(),
+            ));
+        }
+        if self.scale == SweepScale::Log && d0 != d1 && (d0 == 0.0 || d1 == 0.0) {
+            return Err(Error::InvalidArgument(
+                "log sweep duty cycle must be positive.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // The frequency and duty cycle at the given progress through the sweep, from 0 to 1.
+    fn at(&self, progress: f64) -> (f64, f64) {
+        (
+            self.interpolate(self.frequency, progress),
+            self.interpolate(self.duty, progress),
+        )
+    }
+
+    fn interpolate(&self, (from, to): (f64, f64), progress: f64) -> f64 {
+        if from == to {
+            return from;
+        }
+        match self.scale {
+            SweepScale::Linear => from + (to - from) * progress,
+            SweepScale::Log => from * (to / from).powf(progress),
+        }
+    }
+}
+
+// Add a step to the waveform, extending the final step if it has the same value.
+fn push_merged(wf: &mut Waveform, value: Value, duration: Duration) {
+    if duration.is_zero() {
+        return;
+    }
+    match wf.steps.last_mut() {
+        Some(last) if last.value == value => last.duration += duration,
+        _ => {
+            wf.push(value, duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn assert_near(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9 * b.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn frequency() {
+        let s = Sweep::frequency(1000.0, 2000.0, 0.5, 10 * MS);
+        let (f, d) = s.at(0.5);
+        assert_near(f, 1500.0);
+        assert_near(d, 0.5);
+        let wf = s.waveform().unwrap();
+        let steps = wf.steps();
+        assert_eq!(steps[0].value, Value::Active);
+        assert_eq!(steps[0].duration, Duration::from_micros(500));
+        assert_eq!(steps[1].value, Value::Inactive);
+        assert_eq!(steps[1].duration, Duration::from_micros(500));
+        // cycles shorten as the frequency rises
+        assert!(steps[steps.len() - 1].duration < steps[1].duration);
+        assert!(wf.duration() >= 10 * MS);
+        assert!(wf.duration() < 11 * MS);
+    }
+
+    #[test]
+    fn log() {
+        let s = Sweep::frequency(100.0, 10000.0, 0.5, 10 * MS).with_scale(SweepScale::Log);
+        assert_near(s.at(0.0).0, 100.0);
+        assert_near(s.at(0.5).0, 1000.0);
+        assert_near(s.at(1.0).0, 10000.0);
+    }
+
+    #[test]
+    fn duty() {
+        let s = Sweep::duty(1000.0, 0.0, 1.0, 10 * MS);
+        assert_near(s.at(0.25).1, 0.25);
+        let wf = s.waveform().unwrap();
+        let steps = wf.steps();
+        // the first cycle is entirely inactive
+        assert_eq!(steps[0].value, Value::Inactive);
+        assert_eq!(steps[0].duration, MS);
+        assert_eq!(steps[1].value, Value::Active);
+        assert_eq!(steps[1].duration, Duration::from_micros(100));
+        for pair in steps.windows(2) {
+            assert_ne!(pair[0].value, pair[1].value);
+        }
+        assert_eq!(wf.duration(), 10 * MS);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            Sweep::frequency(0.0, 100.0, 0.5, MS)
+                .waveform()
+                .unwrap_err(),
+            Error::InvalidArgument("sweep frequency must be positive.".to_string())
+        );
+        assert_eq!(
+            Sweep::frequency(100.0, 3e9, 0.5, MS)
+                .waveform()
+                .unwrap_err(),
+            Error::InvalidArgument("sweep frequency must not exceed 1000000Hz.".to_string())
+        );
+        assert!(Sweep::frequency(100.0, MAX_SWEEP_FREQUENCY, 0.5, MS)
+            .waveform()
+            .is_ok());
+        assert_eq!(
+            Sweep::duty(100.0, 0.0, 1.5, MS).waveform().unwrap_err(),
+            Error::InvalidArgument("sweep duty cycle must be between 0 and 1.".to_string())
+        );
+        assert_eq!(
+            Sweep::duty(100.0, 0.0, 1.0, MS)
+                .with_scale(SweepScale::Log)
+                .waveform()
+                .unwrap_err(),
+            Error::InvalidArgument("log sweep duty cycle must be positive.".to_string())
+        );
+        // log with a constant zero duty is fine
+        assert!(Sweep::frequency(100.0, 1000.0, 0.0, MS)
+            .with_scale(SweepScale::Log)
+            .waveform()
+            .is_ok());
+        assert!(Sweep::frequency(100.0, 200.0, 0.5, Duration::ZERO)
+            .waveform()
+            .unwrap()
+            .is_empty());
+    }
+}
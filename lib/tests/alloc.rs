@@ -0,0 +1,136 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Getting and setting values must not allocate, other than for tracing.
+#![cfg(not(feature = "tracing"))]
+
+use gpiocdev::line::{Value, Values};
+use gpiocdev::request::{Request, ValueSource};
+use gpiocdev::AbiVersion;
+use gpiosim::Simpleton;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// An allocator that counts the allocations made by each thread, so tests
+// running in parallel do not skew each other's counts.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_alloc() {
+    let _ = ALLOCS.try_with(|a| a.set(a.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_alloc();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_alloc();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// The number of allocations made by f.
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    let start = ALLOCS.with(|a| a.get());
+    f();
+    ALLOCS.with(|a| a.get()) - start
+}
+
+macro_rules! common_tests {
+    ($abiv:expr, $($name:ident),*) => {
+        $(
+            #[test]
+            fn $name() {
+                super::$name($abiv)
+            }
+        )*
+        }
+}
+
+#[cfg(feature = "uapi_v1")]
+mod uapi_v1 {
+    common_tests! {
+        gpiocdev::AbiVersion::V1,
+        values,
+        values_last_set
+    }
+}
+
+#[cfg(feature = "uapi_v2")]
+mod uapi_v2 {
+    common_tests! {
+        gpiocdev::AbiVersion::V2,
+        values,
+        values_last_set
+    }
+}
+
+#[allow(unused_variables)]
+fn request(abiv: AbiVersion, s: &Simpleton, source: ValueSource) -> Request {
+    let mut builder = Request::builder();
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    builder.using_abi_version(abiv);
+    builder
+        .on_chip(s.dev_path())
+        .with_lines(&[0, 1, 2, 3])
+        .as_output(Value::Inactive)
+        .with_value_source(source)
+        .request()
+        .unwrap()
+}
+
+fn values(abiv: AbiVersion) {
+    let s = Simpleton::new(4);
+    let req = request(abiv, &s, ValueSource::Physical);
+
+    let mut vals = Values::from_offsets(&[0, 1, 2, 3]);
+    let mut subset = Values::from_offsets(&[1, 3]);
+    let n = allocations(|| {
+        for _ in 0..100 {
+            vals.not();
+            req.set_values(&vals).unwrap();
+            req.values(&mut vals).unwrap();
+            req.values(&mut subset).unwrap();
+        }
+    });
+    assert_eq!(n, 0);
+    assert_eq!(subset.get(1), vals.get(1));
+
+    // populating empty values allocates only on the first call
+    let mut vals = Values::default();
+    req.values(&mut vals).unwrap();
+    assert_eq!(vals.len(), 4);
+    assert_eq!(allocations(|| req.values(&mut vals).unwrap()), 0);
+}
+
+fn values_last_set(abiv: AbiVersion) {
+    let s = Simpleton::new(4);
+    let req = request(abiv, &s, ValueSource::LastSet);
+
+    let mut vals = Values::from_offsets(&[0, 1, 2, 3]);
+    let n = allocations(|| {
+        for _ in 0..100 {
+            vals.not();
+            req.set_values(&vals).unwrap();
+            assert_eq!(
+                req.values_with_source(&mut vals).unwrap(),
+                ValueSource::LastSet
+            );
+        }
+    });
+    assert_eq!(n, 0);
+}
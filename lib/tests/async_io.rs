@@ -27,7 +27,9 @@ mod chip {
         common_tests! {
             gpiocdev::AbiVersion::V1,
             read_line_info_change_event,
-            info_change_events
+            info_change_events,
+            info_change_stream,
+            watch_info_changes
         }
     }
 
@@ -36,7 +38,9 @@ mod chip {
         common_tests! {
             gpiocdev::AbiVersion::V2,
             read_line_info_change_event,
-            info_change_events
+            info_change_events,
+            info_change_stream,
+            watch_info_changes
         }
     }
 
@@ -84,6 +88,82 @@ mod chip {
         })
     }
 
+    fn info_change_stream(abiv: gpiocdev::AbiVersion) {
+        use futures::stream::StreamExt;
+        use gpiocdev::async_io::AsyncChip;
+        use gpiocdev::line::InfoChangeKind;
+
+        let s = gpiosim::Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        let offset = 3;
+
+        assert!(c.watch_line_info(offset).is_ok());
+
+        let mut events = AsyncChip::new(c).info_change_stream();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .request()
+            .unwrap();
+
+        async_io::block_on(async {
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Requested);
+            assert_eq!(evt.info.offset, offset);
+
+            // watches can be added via the stream
+            assert!(events.chip().watch_line_info(1).is_ok());
+            drop(req);
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(1)
+                .as_input()
+                .request()
+                .unwrap();
+
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Released);
+            assert_eq!(evt.info.offset, offset);
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Requested);
+            assert_eq!(evt.info.offset, 1);
+            drop(req);
+        })
+    }
+
+    fn watch_info_changes(abiv: gpiocdev::AbiVersion) {
+        use futures::stream::StreamExt;
+        use gpiocdev::async_io::AsyncChip;
+        use gpiocdev::line::InfoChangeKind;
+
+        let s = gpiosim::Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+
+        let ac = AsyncChip::new(c);
+        let mut events = ac.watch_info_changes(&[1, 3]).unwrap();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_lines(&[0, 1, 3])
+            .as_input()
+            .request()
+            .unwrap();
+
+        async_io::block_on(async {
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Requested);
+            assert_eq!(evt.info.offset, 1);
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Requested);
+            assert_eq!(evt.info.offset, 3);
+
+            drop(req);
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Released);
+            assert_eq!(evt.info.offset, 1);
+        })
+    }
+
     fn read_line_info_change_event(abiv: gpiocdev::AbiVersion) {
         use gpiocdev::async_io::AsyncChip;
         use std::time::Duration;
@@ -177,23 +257,151 @@ mod request {
     mod uapi_v1 {
         common_tests! {
             gpiocdev::AbiVersion::V1,
+            request,
             read_edge_event,
+            read_edge_event_timeout,
             read_edge_events_into_slice,
+            read_edge_events,
+            read_edge_events_cancel_safe,
             new_edge_event_stream,
-            edge_events
+            edge_events,
+            next_event_timeout,
+            pulse,
+            play_waveform
         }
     }
 
     #[cfg(feature = "uapi_v2")]
     mod uapi_v2 {
         common_tests! {
-            gpiocdev::AbiVersion::V2,            read_edge_event,
+            gpiocdev::AbiVersion::V2,            request,
+            read_edge_event,
+            read_edge_event_timeout,
             read_edge_events_into_slice,
+            read_edge_events,
+            read_edge_events_cancel_safe,
             new_edge_event_stream,
-            edge_events
+            edge_events,
+            next_event_timeout,
+            pulse,
+            play_waveform
         }
     }
 
+    #[allow(unused)]
+    fn pulse(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::Value;
+        use gpiosim::Level;
+
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        async_io::block_on(async {
+            let start = std::time::Instant::now();
+            req.pulse(offset, Duration::from_millis(20)).await.unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(20));
+            assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+
+            // line is restored when the pulse is cancelled
+            let res = future::timeout(
+                Duration::from_millis(10),
+                req.pulse(offset, Duration::from_millis(100)),
+            )
+            .await;
+            assert!(res.is_err());
+            assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+        })
+    }
+
+    #[allow(unused)]
+    fn play_waveform(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::Value;
+        use gpiocdev::waveform::Waveform;
+        use gpiosim::Level;
+
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        async_io::block_on(async {
+            let wf: Waveform = [
+                (Value::Active, Duration::from_millis(10)),
+                (Value::Inactive, Duration::from_millis(10)),
+                (Value::Active, Duration::from_millis(10)),
+            ]
+            .into_iter()
+            .collect();
+            let start = std::time::Instant::now();
+            req.play_waveform(offset, &wf).await.unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(30));
+            assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+            // line is left at the current step when the waveform is cancelled
+            let wf: Waveform = [
+                (Value::Inactive, Duration::from_millis(100)),
+                (Value::Active, Duration::from_millis(10)),
+            ]
+            .into_iter()
+            .collect();
+            let res =
+                future::timeout(Duration::from_millis(10), req.play_waveform(offset, &wf)).await;
+            assert!(res.is_err());
+            assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+        })
+    }
+
+    #[allow(unused)]
+    fn request(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        async_io::block_on(async {
+            let req = AsyncRequest::request(&builder).await.unwrap();
+            assert_eq!(req.as_ref().lines(), [offset]);
+
+            s.pullup(offset).unwrap();
+            let evt = req.read_edge_event().await.unwrap();
+            assert_eq!(evt.offset, offset);
+            assert_eq!(evt.kind, EdgeKind::Rising);
+
+            // errors are returned
+            let mut builder = Request::builder();
+            builder.on_chip(s.dev_path()).with_line(4);
+            assert!(AsyncRequest::request(&builder).await.is_err());
+        })
+    }
+
     fn read_edge_event(abiv: gpiocdev::AbiVersion) {
         let s = gpiosim::Simpleton::new(4);
         let offset = 2;
@@ -242,6 +450,188 @@ mod request {
         })
     }
 
+    #[allow(unused)]
+    fn read_edge_event_timeout(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        async_io::block_on(async {
+            let timeout = Duration::from_millis(10);
+            let res = req.read_edge_event_timeout(timeout).await.unwrap();
+            assert!(res.is_none());
+            let res = req.read_edge_event_timeout(Duration::ZERO).await.unwrap();
+            assert!(res.is_none());
+
+            s.pullup(offset).unwrap();
+            let evt = req.read_edge_event_timeout(timeout).await.unwrap().unwrap();
+            assert_eq!(evt.offset, offset);
+            assert_eq!(evt.kind, EdgeKind::Rising);
+
+            // event is not lost if the future is dropped
+            s.pulldown(offset).unwrap();
+            drop(req.read_edge_event_timeout(timeout));
+            let evt = req
+                .read_edge_event_timeout(Duration::ZERO)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(evt.kind, EdgeKind::Falling);
+
+            let res = req.read_edge_event_timeout(timeout).await.unwrap();
+            assert!(res.is_none());
+        })
+    }
+
+    #[allow(unused)]
+    fn next_event_timeout(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+        let mut events = req.new_edge_event_stream(2);
+
+        async_io::block_on(async {
+            let timeout = Duration::from_millis(10);
+            let res = events.next_event_timeout(timeout).await.unwrap();
+            assert!(res.is_none());
+
+            s.pullup(offset).unwrap();
+            s.pulldown(offset).unwrap();
+            s.pullup(offset).unwrap();
+            let evt = events.next_event_timeout(timeout).await.unwrap().unwrap();
+            assert_eq!(evt.kind, EdgeKind::Rising);
+            // from the user space buffer
+            let evt = events
+                .next_event_timeout(Duration::ZERO)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(evt.kind, EdgeKind::Falling);
+            // from the kernel
+            let evt = events.next_event_timeout(timeout).await.unwrap().unwrap();
+            assert_eq!(evt.kind, EdgeKind::Rising);
+
+            let res = events.next_event_timeout(timeout).await.unwrap();
+            assert!(res.is_none());
+        })
+    }
+
+    #[allow(unused)]
+    fn read_edge_events_cancel_safe(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+        let mut events = Vec::new();
+
+        async_io::block_on(async {
+            // dropped while waiting
+            let res = future::timeout(
+                Duration::from_millis(10),
+                req.read_edge_events(&mut events, 2),
+            )
+            .await;
+            assert!(res.is_err());
+            assert!(events.is_empty());
+
+            for _ in 0..4 {
+                s.pullup(offset).unwrap();
+                s.pulldown(offset).unwrap();
+            }
+
+            // read with a short timeout, where the read may be dropped
+            while events.len() < 8 {
+                let _ = future::timeout(
+                    Duration::from_micros(1),
+                    req.read_edge_events(&mut events, 2),
+                )
+                .await;
+            }
+            assert_eq!(events.len(), 8);
+            for (idx, evt) in events.iter().enumerate() {
+                let kind = if idx % 2 == 0 {
+                    EdgeKind::Rising
+                } else {
+                    EdgeKind::Falling
+                };
+                assert_eq!(evt.kind, kind);
+            }
+            let res = req
+                .read_edge_event_timeout(Duration::from_millis(10))
+                .await
+                .unwrap();
+            assert!(res.is_none());
+        })
+    }
+
+    fn read_edge_events(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        // create four events
+        for _ in 0..4 {
+            s.toggle(offset).unwrap();
+            wait_propagation_delay();
+        }
+
+        async_io::block_on(async {
+            let mut events = Vec::new();
+            assert_eq!(req.read_edge_events(&mut events, 3).await.unwrap(), 3);
+            assert_eq!(req.read_edge_events(&mut events, 3).await.unwrap(), 1);
+            assert_eq!(events.len(), 4);
+            for (idx, evt) in events.iter().enumerate() {
+                assert_eq!(evt.offset, offset);
+                if abiv == gpiocdev::AbiVersion::V2 {
+                    assert_eq!(evt.seqno, idx as u32 + 1);
+                }
+            }
+            assert_eq!(events[3].kind, EdgeKind::Falling);
+        })
+    }
+
     fn read_edge_events_into_slice(abiv: gpiocdev::AbiVersion) {
         let s = gpiosim::Simpleton::new(3);
         let offset = 1;
@@ -24,7 +24,9 @@ mod chip {
         common_tests! {
             gpiocdev::AbiVersion::V1,
             read_line_info_change_event,
-            info_change_events
+            info_change_events,
+            info_change_stream,
+            watch_info_changes
         }
     }
 
@@ -33,7 +35,9 @@ mod chip {
         common_tests! {
             gpiocdev::AbiVersion::V2,
             read_line_info_change_event,
-            info_change_events
+            info_change_events,
+            info_change_stream,
+            watch_info_changes
         }
     }
 
@@ -79,6 +83,84 @@ mod chip {
         assert_eq!(evt.info.offset, offset);
     }
 
+    async fn info_change_stream(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::InfoChangeKind;
+        use gpiocdev::tokio::AsyncChip;
+        use tokio_stream::StreamExt;
+
+        let s = gpiosim::Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        let offset = 3;
+
+        assert!(c.watch_line_info(offset).is_ok());
+
+        let mut events = AsyncChip::new(c).info_change_stream();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .request()
+            .unwrap();
+
+        // stream owns the chip so can be moved into a task
+        let mut events = tokio::spawn(async move {
+            let evt = events.next().await.unwrap().unwrap();
+            assert_eq!(evt.kind, InfoChangeKind::Requested);
+            assert_eq!(evt.info.offset, offset);
+            events
+        })
+        .await
+        .unwrap();
+
+        // watches can be added via the stream
+        assert!(events.chip().watch_line_info(1).is_ok());
+        drop(req);
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_input()
+            .request()
+            .unwrap();
+
+        let evt = events.next().await.unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Released);
+        assert_eq!(evt.info.offset, offset);
+        let evt = events.next().await.unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Requested);
+        assert_eq!(evt.info.offset, 1);
+        drop(req);
+    }
+
+    async fn watch_info_changes(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::InfoChangeKind;
+        use gpiocdev::tokio::AsyncChip;
+        use tokio_stream::StreamExt;
+
+        let s = gpiosim::Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+
+        let ac = AsyncChip::new(c);
+        let mut events = ac.watch_info_changes(&[1, 3]).unwrap();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_lines(&[0, 1, 3])
+            .as_input()
+            .request()
+            .unwrap();
+
+        let evt = events.next().await.unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Requested);
+        assert_eq!(evt.info.offset, 1);
+        let evt = events.next().await.unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Requested);
+        assert_eq!(evt.info.offset, 3);
+
+        drop(req);
+        let evt = events.next().await.unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Released);
+        assert_eq!(evt.info.offset, 1);
+    }
+
     async fn read_line_info_change_event(abiv: gpiocdev::AbiVersion) {
         use gpiocdev::tokio::AsyncChip;
         use std::time::Duration;
@@ -167,10 +249,18 @@ mod request {
     mod uapi_v1 {
         common_tests! {
             gpiocdev::AbiVersion::V1,
+            request,
             read_edge_event,
+            read_edge_event_timeout,
             read_edge_events_into_slice,
+            read_edge_events,
+            read_edge_events_cancel_safe,
             new_edge_event_stream,
-            edge_events
+            edge_events,
+            edge_event_broadcast,
+            next_event_timeout,
+            pulse,
+            play_waveform
         }
     }
 
@@ -178,13 +268,128 @@ mod request {
     mod uapi_v2 {
         common_tests! {
             gpiocdev::AbiVersion::V2,
+            request,
             read_edge_event,
+            read_edge_event_timeout,
             read_edge_events_into_slice,
+            read_edge_events,
+            read_edge_events_cancel_safe,
             new_edge_event_stream,
-            edge_events
+            edge_events,
+            edge_event_broadcast,
+            next_event_timeout,
+            pulse,
+            play_waveform
         }
     }
 
+    #[allow(unused)]
+    async fn pulse(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::Value;
+        use gpiosim::Level;
+
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        let start = time::Instant::now();
+        req.pulse(offset, Duration::from_millis(20)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+
+        // line is restored when the pulse is cancelled
+        let res = time::timeout(
+            Duration::from_millis(10),
+            req.pulse(offset, Duration::from_millis(100)),
+        )
+        .await;
+        assert!(res.is_err());
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+    }
+
+    #[allow(unused)]
+    async fn play_waveform(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::Value;
+        use gpiocdev::waveform::Waveform;
+        use gpiosim::Level;
+
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        let wf: Waveform = [
+            (Value::Active, Duration::from_millis(10)),
+            (Value::Inactive, Duration::from_millis(10)),
+            (Value::Active, Duration::from_millis(10)),
+        ]
+        .into_iter()
+        .collect();
+        let start = time::Instant::now();
+        req.play_waveform(offset, &wf).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+        // line is left at the current step when the waveform is cancelled
+        let wf: Waveform = [
+            (Value::Inactive, Duration::from_millis(100)),
+            (Value::Active, Duration::from_millis(10)),
+        ]
+        .into_iter()
+        .collect();
+        let res = time::timeout(Duration::from_millis(10), req.play_waveform(offset, &wf)).await;
+        assert!(res.is_err());
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+    }
+
+    #[allow(unused)]
+    async fn request(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::request(&builder).await.unwrap();
+        assert_eq!(req.as_ref().lines(), [offset]);
+
+        s.pullup(offset).unwrap();
+        let evt = req.read_edge_event().await.unwrap();
+        assert_eq!(evt.offset, offset);
+        assert_eq!(evt.kind, EdgeKind::Rising);
+
+        // errors are returned
+        let mut builder = Request::builder();
+        builder.on_chip(s.dev_path()).with_line(4);
+        assert!(AsyncRequest::request(&builder).await.is_err());
+    }
+
     #[allow(unused)]
     async fn read_edge_event(abiv: gpiocdev::AbiVersion) {
         let s = gpiosim::Simpleton::new(4);
@@ -232,6 +437,227 @@ mod request {
         assert!(res.is_err());
     }
 
+    #[allow(unused)]
+    async fn read_edge_event_timeout(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        let timeout = Duration::from_millis(10);
+        let res = req.read_edge_event_timeout(timeout).await.unwrap();
+        assert!(res.is_none());
+        let res = req.read_edge_event_timeout(Duration::ZERO).await.unwrap();
+        assert!(res.is_none());
+
+        s.pullup(offset).unwrap();
+        let evt = req.read_edge_event_timeout(timeout).await.unwrap().unwrap();
+        assert_eq!(evt.offset, offset);
+        assert_eq!(evt.kind, EdgeKind::Rising);
+
+        // event is not lost if the future is dropped
+        s.pulldown(offset).unwrap();
+        drop(req.read_edge_event_timeout(timeout));
+        let evt = req
+            .read_edge_event_timeout(Duration::ZERO)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(evt.kind, EdgeKind::Falling);
+
+        let res = req.read_edge_event_timeout(timeout).await.unwrap();
+        assert!(res.is_none());
+    }
+
+    #[allow(unused)]
+    async fn edge_event_broadcast(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::tokio::EdgeEventBroadcast;
+        use tokio::sync::broadcast::error::RecvError;
+
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let bcast = EdgeEventBroadcast::new(builder.request().unwrap(), 2);
+        let mut rx1 = bcast.subscribe();
+        let mut rx2 = bcast.subscribe();
+        assert_eq!(bcast.receiver_count(), 2);
+
+        s.pullup(offset).unwrap();
+        let evt = rx1.recv().await.unwrap();
+        assert_eq!(evt.offset, offset);
+        assert_eq!(evt.kind, EdgeKind::Rising);
+        let evt2 = rx2.recv().await.unwrap();
+        assert_eq!(evt, evt2);
+
+        // rx2 falls behind
+        s.pulldown(offset).unwrap();
+        assert_eq!(rx1.recv().await.unwrap().kind, EdgeKind::Falling);
+        s.pullup(offset).unwrap();
+        assert_eq!(rx1.recv().await.unwrap().kind, EdgeKind::Rising);
+        s.pulldown(offset).unwrap();
+        assert_eq!(rx1.recv().await.unwrap().kind, EdgeKind::Falling);
+        assert_eq!(rx2.recv().await, Err(RecvError::Lagged(1)));
+        assert_eq!(rx2.recv().await.unwrap().kind, EdgeKind::Rising);
+        assert_eq!(rx2.recv().await.unwrap().kind, EdgeKind::Falling);
+
+        drop(rx1);
+        assert_eq!(bcast.receiver_count(), 1);
+    }
+
+    #[allow(unused)]
+    async fn next_event_timeout(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+        let mut events = req.new_edge_event_stream(2);
+
+        let timeout = Duration::from_millis(10);
+        let res = events.next_event_timeout(timeout).await.unwrap();
+        assert!(res.is_none());
+
+        s.pullup(offset).unwrap();
+        s.pulldown(offset).unwrap();
+        s.pullup(offset).unwrap();
+        let evt = events.next_event_timeout(timeout).await.unwrap().unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+        // from the user space buffer
+        let evt = events
+            .next_event_timeout(Duration::ZERO)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(evt.kind, EdgeKind::Falling);
+        // from the kernel
+        let evt = events.next_event_timeout(timeout).await.unwrap().unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+
+        let res = events.next_event_timeout(timeout).await.unwrap();
+        assert!(res.is_none());
+    }
+
+    #[allow(unused)]
+    async fn read_edge_events_cancel_safe(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = AsyncRequest::new(builder.request().unwrap());
+        let mut events = Vec::new();
+
+        // dropped while waiting
+        let res = time::timeout(
+            Duration::from_millis(10),
+            req.read_edge_events(&mut events, 2),
+        )
+        .await;
+        assert!(res.is_err());
+        assert!(events.is_empty());
+
+        for _ in 0..4 {
+            s.pullup(offset).unwrap();
+            s.pulldown(offset).unwrap();
+        }
+
+        // read in a select loop, where the read may be dropped
+        let mut ticker = time::interval(Duration::from_micros(1));
+        while events.len() < 8 {
+            tokio::select! {
+                res = req.read_edge_events(&mut events, 2) => {
+                    res.unwrap();
+                }
+                _ = ticker.tick() => {}
+            }
+        }
+        assert_eq!(events.len(), 8);
+        for (idx, evt) in events.iter().enumerate() {
+            let kind = if idx % 2 == 0 {
+                EdgeKind::Rising
+            } else {
+                EdgeKind::Falling
+            };
+            assert_eq!(evt.kind, kind);
+        }
+        let res = req
+            .read_edge_event_timeout(Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(res.is_none());
+    }
+
+    async fn read_edge_events(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges);
+
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = AsyncRequest::new(builder.request().unwrap());
+
+        // create four events
+        for _ in 0..4 {
+            s.toggle(offset).unwrap();
+            propagation_delay().await;
+        }
+
+        let mut events = Vec::new();
+        assert_eq!(req.read_edge_events(&mut events, 3).await.unwrap(), 3);
+        assert_eq!(req.read_edge_events(&mut events, 3).await.unwrap(), 1);
+        assert_eq!(events.len(), 4);
+        for (idx, evt) in events.iter().enumerate() {
+            assert_eq!(evt.offset, offset);
+            if abiv == gpiocdev::AbiVersion::V2 {
+                assert_eq!(evt.seqno, idx as u32 + 1);
+            }
+        }
+        assert_eq!(events[3].kind, gpiocdev::line::EdgeKind::Falling);
+    }
+
     async fn read_edge_events_into_slice(abiv: gpiocdev::AbiVersion) {
         let s = gpiosim::Simpleton::new(3);
         let offset = 1;
@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use calloop::EventLoop;
+use gpiocdev::calloop::{EdgeEventSource, InfoChangeEventSource};
+use gpiocdev::line::{EdgeDetection, EdgeEvent, EdgeKind, InfoChangeEvent, InfoChangeKind};
+use gpiocdev::{Chip, Request};
+use gpiosim::Simpleton;
+use std::time::Duration;
+
+// max time to wait for an event
+const TIMEOUT: Duration = Duration::from_millis(25);
+
+#[test]
+fn edge_event_source() {
+    let s = Simpleton::new(4);
+    let offset = 2;
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let mut event_loop: EventLoop<Vec<EdgeEvent>> = EventLoop::try_new().unwrap();
+    let token = event_loop
+        .handle()
+        .insert_source(EdgeEventSource::new(req), |evt, _, events| events.push(evt))
+        .unwrap();
+    let mut events = Vec::new();
+    event_loop.dispatch(TIMEOUT, &mut events).unwrap();
+    assert!(events.is_empty());
+
+    // fill the kernel event buffer
+    for _ in 0..8 {
+        s.pullup(offset).unwrap();
+        s.pulldown(offset).unwrap();
+    }
+    event_loop.dispatch(TIMEOUT, &mut events).unwrap();
+    assert_eq!(events.len(), 16);
+    for (idx, evt) in events.iter().enumerate() {
+        assert_eq!(evt.offset, offset);
+        let kind = if idx % 2 == 0 {
+            EdgeKind::Rising
+        } else {
+            EdgeKind::Falling
+        };
+        assert_eq!(evt.kind, kind);
+    }
+
+    event_loop.handle().remove(token);
+    events.clear();
+    s.pullup(offset).unwrap();
+    event_loop.dispatch(TIMEOUT, &mut events).unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn info_change_event_source() {
+    let s = Simpleton::new(4);
+    let offset = 1;
+    let chip = Chip::from_path(s.dev_path()).unwrap();
+    chip.watch_line_info(offset).unwrap();
+
+    let mut event_loop: EventLoop<Vec<InfoChangeEvent>> = EventLoop::try_new().unwrap();
+    event_loop
+        .handle()
+        .insert_source(InfoChangeEventSource::new(chip), |evt, _, events| {
+            events.push(evt)
+        })
+        .unwrap();
+    let mut events = Vec::new();
+    event_loop.dispatch(TIMEOUT, &mut events).unwrap();
+    assert!(events.is_empty());
+
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(offset)
+        .as_input()
+        .request()
+        .unwrap();
+    drop(req);
+    event_loop.dispatch(TIMEOUT, &mut events).unwrap();
+    let kinds: Vec<InfoChangeKind> = events.iter().map(|evt| evt.kind).collect();
+    assert_eq!(kinds, [InfoChangeKind::Requested, InfoChangeKind::Released]);
+}
@@ -104,6 +104,19 @@ fn chips() {
     }
 }
 
+#[test]
+fn chips_by_label() {
+    let cc = bag_of_chips();
+    let found = gpiocdev::chips_by_label("veintid*").unwrap();
+    assert!(found.contains(cc.chips()[0].dev_path()));
+    assert!(!found.contains(cc.chips()[1].dev_path()));
+    let found = gpiocdev::chips_by_label("w?ggle").unwrap();
+    assert!(found.contains(cc.chips()[2].dev_path()));
+    assert!(gpiocdev::chips_by_label("nonexistent label")
+        .unwrap()
+        .is_empty());
+}
+
 macro_rules! common_tests {
     ($abiv:expr, $($name:ident),*) => {
         $(
@@ -155,6 +168,19 @@ mod chip {
         }
     }
 
+    #[test]
+    fn from_label() {
+        let _cc = bag_of_chips();
+        let chip = Chip::from_label("babel").unwrap();
+        assert_eq!(chip.info().unwrap().label, "babel");
+        assert_eq!(
+            Chip::from_label("nonexistent label").unwrap_err(),
+            gpiocdev::Error::InvalidArgument(
+                "No chip with label 'nonexistent label' found.".to_string()
+            )
+        );
+    }
+
     #[test]
     fn from_path() {
         let s = bag_of_chips();
@@ -204,6 +230,32 @@ mod chip {
         );
     }
 
+    #[test]
+    fn wait_for() {
+        let s = bag_of_chips();
+        for sc in s.chips() {
+            let c = Chip::wait_for(sc.dev_path(), Duration::ZERO).unwrap();
+            assert_eq!(c.path(), sc.dev_path());
+            // no deadline
+            let c = Chip::wait_for(sc.dev_path(), Duration::MAX).unwrap();
+            assert_eq!(c.path(), sc.dev_path());
+        }
+
+        let path = PathBuf::from("/dev/gpiochip_nonexistent");
+        let start = std::time::Instant::now();
+        assert_eq!(
+            Chip::wait_for(&path, Duration::from_millis(50)).unwrap_err(),
+            gpiocdev::Error::Os(gpiocdev_uapi::Errno(2))
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let path = PathBuf::from("/dev/null");
+        assert_eq!(
+            Chip::wait_for(&path, Duration::ZERO).unwrap_err(),
+            ChipError(path, ErrorKind::NotGpioDevice)
+        );
+    }
+
     #[test]
     fn info() {
         let s = bag_of_chips();
@@ -250,6 +302,44 @@ mod chip {
         }
     }
 
+    #[test]
+    fn line_offset_from_name() {
+        let s = detailed_sim();
+        for sc in s.chips() {
+            let c = Chip::from_path(sc.dev_path()).unwrap();
+            for (offset, name) in &sc.config().names {
+                assert_eq!(c.line_offset_from_name(name).unwrap(), Some(*offset));
+            }
+
+            // non-existent
+            assert_eq!(c.line_offset_from_name("not such line").unwrap(), None);
+            // unnamed lines are not matched
+            assert_eq!(c.line_offset_from_name("").unwrap(), None);
+
+            c.refresh_names().unwrap();
+            for (offset, name) in &sc.config().names {
+                assert_eq!(c.line_offset_from_name(name).unwrap(), Some(*offset));
+            }
+        }
+    }
+
+    #[test]
+    fn lines_with_consumer() {
+        let s = detailed_sim();
+        let c = Chip::from_path(s.chips()[0].dev_path()).unwrap();
+        let offsets = |pattern: &str| -> Vec<u32> {
+            c.lines_with_consumer(pattern)
+                .unwrap()
+                .iter()
+                .map(|li| li.offset)
+                .collect()
+        };
+        assert_eq!(offsets("hogster"), [2]);
+        assert_eq!(offsets("*"), [2, 5]);
+        assert_eq!(offsets("b?eath"), [5]);
+        assert!(offsets("nobody").is_empty());
+    }
+
     fn line_info(abiv: gpiocdev::AbiVersion) {
         let s = detailed_sim();
         for sc in s.chips() {
@@ -435,16 +525,118 @@ mod chip {
         }
     }
 
+    fn line_infos(abiv: gpiocdev::AbiVersion) {
+        let s = detailed_sim();
+        for sc in s.chips() {
+            let c = new_chip(sc.dev_path(), abiv);
+            let infos = c.line_infos().unwrap();
+            assert_eq!(infos.len(), sc.config().num_lines as usize);
+            let infos: Vec<gpiocdev::line::Info> = infos.map(|li| li.unwrap()).collect();
+            let expected: Vec<gpiocdev::line::Info> =
+                c.line_info_iter().unwrap().map(|li| li.unwrap()).collect();
+            assert_eq!(infos, expected);
+
+            // without strings
+            for li in c
+                .line_infos()
+                .unwrap()
+                .with_names(false)
+                .with_consumers(false)
+            {
+                let li = li.unwrap();
+                let expected = &expected[li.offset as usize];
+                assert!(li.name.is_empty());
+                assert!(li.consumer.is_empty());
+                assert_eq!(li.used, expected.used);
+                assert_eq!(li.direction, expected.direction);
+            }
+
+            // names only
+            for li in c.line_infos().unwrap().with_consumers(false) {
+                let li = li.unwrap();
+                assert_eq!(li.name, expected[li.offset as usize].name);
+                assert!(li.consumer.is_empty());
+            }
+        }
+    }
+
+    fn line_info_cache(abiv: gpiocdev::AbiVersion) {
+        let s = Simpleton::new(4);
+        let mut c = new_chip(s.dev_path(), abiv);
+        c.with_line_info_cache();
+        let offset = 2;
+
+        let info = c.line_info(offset).unwrap();
+        assert!(!info.used);
+        // cached lines are watched
+        assert_eq!(
+            c.watch_line_info(offset),
+            Err(gpiocdev::Error::Uapi(
+                gpiocdev::UapiCall::WatchLineInfo,
+                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(16))
+            ))
+        );
+        assert_eq!(c.line_info(offset).unwrap(), info);
+
+        // changes are applied to the cache
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_consumer("cached")
+            .with_line(offset)
+            .as_output(gpiocdev::line::Value::Active)
+            .request()
+            .unwrap();
+        let info = c.line_info(offset).unwrap();
+        assert!(info.used);
+        assert_eq!(info.consumer.as_str(), "cached");
+        assert_eq!(info.direction, gpiocdev::line::Direction::Output);
+        assert!(!c.has_line_info_change_event().unwrap());
+
+        drop(req);
+        assert!(!c.line_info(offset).unwrap().used);
+
+        // lines already watched are cached too
+        c.watch_line_info(1).unwrap();
+        assert!(!c.line_info(1).unwrap().used);
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_input()
+            .request()
+            .unwrap();
+        assert!(c.line_info(1).unwrap().used);
+        drop(req);
+
+        // unwatching evicts
+        c.unwatch_line_info(offset).unwrap();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .request()
+            .unwrap();
+        assert!(c.line_info(offset).unwrap().used);
+        drop(req);
+        assert!(!c.line_info(offset).unwrap().used);
+    }
+
     #[cfg(feature = "uapi_v1")]
     mod uapi_v1 {
         common_tests! {
             gpiocdev::AbiVersion::V1,
             line_info,
+            line_info_cache,
+            line_infos,
             watch_line_info,
+            watch_line_infos,
+            watch_info_changes,
             has_line_info_change_event,
             read_line_info_change_event,
             info_change_events,
-            wait_info_change_event
+            info_change_batches,
+            wait_info_change_event,
+            wait_for_line_free,
+            wait_for_info_change
         }
     }
 
@@ -453,11 +645,18 @@ mod chip {
         common_tests! {
             gpiocdev::AbiVersion::V2,
             line_info,
+            line_info_cache,
+            line_infos,
             watch_line_info,
+            watch_line_infos,
+            watch_info_changes,
             has_line_info_change_event,
             read_line_info_change_event,
             info_change_events,
-            wait_info_change_event
+            info_change_batches,
+            wait_info_change_event,
+            wait_for_line_free,
+            wait_for_info_change
         }
     }
 
@@ -498,6 +697,88 @@ mod chip {
         assert_eq!(c.has_line_info_change_event(), Ok(true));
     }
 
+    fn watch_line_infos(abiv: gpiocdev::AbiVersion) {
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_consumer("watch infos")
+            .with_line(2)
+            .as_input()
+            .request()
+            .unwrap();
+
+        let infos = c.watch_line_infos(&[1, 2]).unwrap();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].offset, 1);
+        assert!(!infos[0].used);
+        assert_eq!(infos[1].offset, 2);
+        assert!(infos[1].used);
+        assert_eq!(infos[1].consumer, "watch infos");
+
+        // failure removes the watches
+        assert_eq!(
+            c.watch_line_infos(&[0, 5]),
+            Err(gpiocdev::Error::Uapi(
+                gpiocdev::UapiCall::WatchLineInfo,
+                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
+            ))
+        );
+        let req0 = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(0)
+            .request()
+            .unwrap();
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+        drop(req0);
+
+        // existing watches are retained
+        assert_eq!(
+            c.watch_line_infos(&[1, 2]),
+            Err(gpiocdev::Error::Uapi(
+                gpiocdev::UapiCall::WatchLineInfo,
+                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(16))
+            ))
+        );
+        assert!(c.unwatch_line_infos(&[1, 2]).is_ok());
+        assert!(c.watch_line_infos(&[1, 2]).is_ok());
+        assert!(c.unwatch_line_infos(&[1, 2]).is_ok());
+        drop(req);
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+    }
+
+    fn watch_info_changes(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::InfoChangeKind;
+
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+
+        let mut events = c.watch_info_changes(&[1, 3]).unwrap();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_lines(&[0, 1, 3])
+            .as_input()
+            .request()
+            .unwrap();
+
+        let evt = events.next().unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Requested);
+        assert_eq!(evt.info.offset, 1);
+        let evt = events.next().unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Requested);
+        assert_eq!(evt.info.offset, 3);
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+
+        drop(req);
+        let evt = events.next().unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Released);
+        assert_eq!(evt.info.offset, 1);
+        let evt = events.next().unwrap().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Released);
+        assert_eq!(evt.info.offset, 3);
+    }
+
     #[test]
     fn unwatch_line_info() {
         let s = Simpleton::new(4);
@@ -688,6 +969,51 @@ mod chip {
         assert!(res.is_ok());
     }
 
+    fn info_change_batches(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::InfoChangeKind;
+        use std::thread;
+
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        let offsets = [0, 1, 2, 3];
+        for offset in offsets {
+            assert!(c.watch_line_info(offset).is_ok());
+        }
+        let t = thread::spawn(move || {
+            // a burst of requests
+            let reqs: Vec<Request> = offsets
+                .iter()
+                .map(|offset| {
+                    Request::builder()
+                        .on_chip(s.dev_path())
+                        .with_line(*offset)
+                        .as_input()
+                        .request()
+                        .unwrap()
+                })
+                .collect();
+            thread::sleep(Duration::from_millis(200));
+            // and a burst of releases
+            drop(reqs);
+        });
+        let mut batches = c.info_change_batches(Duration::from_millis(50));
+        let batch = batches.next().unwrap().unwrap();
+        assert_eq!(batch.events.len(), 4);
+        assert_eq!(batch.offsets(), offsets);
+        assert!(batch
+            .events
+            .iter()
+            .all(|e| e.kind == InfoChangeKind::Requested));
+
+        let batch = batches.next().unwrap().unwrap();
+        assert_eq!(batch.offsets(), offsets);
+        assert!(batch
+            .latest()
+            .iter()
+            .all(|e| e.kind == InfoChangeKind::Released));
+        assert!(t.join().is_ok());
+    }
+
     fn wait_info_change_event(abiv: gpiocdev::AbiVersion) {
         let s = Simpleton::new(4);
         let c = new_chip(s.dev_path(), abiv);
@@ -723,6 +1049,96 @@ mod chip {
         );
     }
 
+    fn wait_for_line_free(abiv: gpiocdev::AbiVersion) {
+        use std::thread;
+
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        let offset = 1;
+
+        // free
+        assert_eq!(c.wait_for_line_free(offset, Duration::ZERO), Ok(true));
+
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .request()
+            .unwrap();
+        assert_eq!(
+            c.wait_for_line_free(offset, Duration::from_millis(10)),
+            Ok(false)
+        );
+
+        // does not interfere with existing watches
+        assert!(c.watch_line_info(2).is_ok());
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(req);
+        });
+        assert_eq!(
+            c.wait_for_line_free(offset, Duration::from_secs(1)),
+            Ok(true)
+        );
+        assert!(t.join().is_ok());
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+
+        // no deadline
+        assert_eq!(c.wait_for_line_free(offset, Duration::MAX), Ok(true));
+    }
+
+    fn wait_for_info_change(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::chip::{became_free, reconfigured, requested_by};
+        use gpiocdev::line::{Bias, InfoChangeKind};
+        use std::thread;
+
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        let offset = 1;
+
+        // no change
+        assert_eq!(
+            c.wait_for_info_change(offset, became_free(), Duration::from_millis(10)),
+            Ok(None)
+        );
+
+        let path = s.dev_path().clone();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            let req = Request::builder()
+                .on_chip(&path)
+                .with_consumer("transition")
+                .with_line(offset)
+                .as_input()
+                .request()
+                .unwrap();
+            thread::sleep(Duration::from_millis(20));
+            let mut cfg = req.config();
+            cfg.with_bias(Bias::PullUp);
+            req.reconfigure(&cfg).unwrap();
+            thread::sleep(Duration::from_millis(20));
+            drop(req);
+        });
+        let evt = c
+            .wait_for_info_change(offset, requested_by("transition"), Duration::from_secs(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Requested);
+        assert_eq!(evt.info.consumer, "transition");
+        // no deadline
+        let evt = c
+            .wait_for_info_change(offset, reconfigured(), Duration::MAX)
+            .unwrap()
+            .unwrap();
+        assert_eq!(evt.info.bias, Some(Bias::PullUp));
+        let evt = c
+            .wait_for_info_change(offset, became_free(), Duration::from_secs(1))
+            .unwrap()
+            .unwrap();
+        assert!(!evt.info.used);
+        assert!(t.join().is_ok());
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+    }
+
     #[test]
     fn detect_abi_version() {
         // assumes a kernel with both v1 and v2 supported.
@@ -734,6 +1150,19 @@ mod chip {
         assert_eq!(c.detect_abi_version(), Ok(gpiocdev::AbiVersion::V1));
     }
 
+    #[test]
+    fn capabilities() {
+        // assumes a kernel with both v1 and v2 supported.
+        let s = Simpleton::new(4);
+        let c = Chip::from_path(s.dev_path()).unwrap();
+        let caps = c.capabilities().unwrap();
+        assert_eq!(caps.abi_v1, cfg!(feature = "uapi_v1"));
+        assert_eq!(caps.abi_v2, cfg!(feature = "uapi_v2"));
+        assert!(caps.bias);
+        assert_eq!(caps.debounce, cfg!(feature = "uapi_v2"));
+        assert!(caps.max_event_buffer_size >= 16);
+    }
+
     #[test]
     fn supports_abi_version() {
         // assumes a kernel with both v1 and v2 supported.
@@ -8,7 +8,7 @@
 //  - kernel supports uAPI versions corresponding to selected build features
 
 use gpiocdev::chip::path_compare;
-use gpiosim::Bank;
+use gpiosim::{Bank, Direction};
 use std::cmp::Ordering;
 
 #[test]
@@ -50,6 +50,7 @@ fn find_named_line() {
 }
 
 #[test]
+#[allow(clippy::unnecessary_get_then_check)]
 fn find_named_lines() {
     let sim = gpiosim::builder()
         .with_bank(
@@ -117,6 +118,154 @@ fn find_named_lines() {
     assert!(found.get(&"fls nada").is_none());
 }
 
+#[test]
+fn find_named_line_with() {
+    use gpiocdev::NameResolution;
+
+    let sim = gpiosim::builder()
+        .with_bank(
+            Bank::new(8, "find_line_with 1")
+                .name(3, "flw banana")
+                .name(6, "flw apple"),
+        )
+        .with_bank(Bank::new(42, "find_line_with 2").name(5, "flw apple"))
+        .live()
+        .unwrap();
+
+    let found =
+        gpiocdev::find_named_line_with("flw banana", NameResolution::RequireUnique).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(&found[0].chip, sim.chips()[0].dev_path());
+    assert_eq!(found[0].info.offset, 3);
+    let l = gpiocdev::find_named_line_unique("flw banana").unwrap();
+    assert_eq!(l, found[0]);
+
+    let found = gpiocdev::find_named_line_with("flw apple", NameResolution::FirstMatch).unwrap();
+    assert_eq!(found.len(), 1);
+
+    let found = gpiocdev::find_named_line_with("flw apple", NameResolution::All).unwrap();
+    assert_eq!(found.len(), 2);
+    let mut lines: Vec<(&std::path::Path, u32)> = found
+        .iter()
+        .map(|l| (l.chip.as_path(), l.info.offset))
+        .collect();
+    lines.sort_unstable();
+    let mut expected = vec![
+        (sim.chips()[0].dev_path().as_path(), 6),
+        (sim.chips()[1].dev_path().as_path(), 5),
+    ];
+    expected.sort_unstable();
+    assert_eq!(lines, expected);
+
+    let e = gpiocdev::find_named_line_with("flw apple", NameResolution::RequireUnique);
+    assert_eq!(
+        e,
+        Err(gpiocdev::Error::AmbiguousLineName(
+            "flw apple".to_string(),
+            found
+        ))
+    );
+    assert!(matches!(
+        gpiocdev::find_named_line_unique("flw apple"),
+        Err(gpiocdev::Error::AmbiguousLineName(..))
+    ));
+
+    let found = gpiocdev::find_named_line_with("flw nada", NameResolution::All).unwrap();
+    assert!(found.is_empty());
+    assert_eq!(
+        gpiocdev::find_named_line_unique("flw nada"),
+        Err(gpiocdev::Error::InvalidArgument(
+            "No line named 'flw nada' found.".to_string()
+        ))
+    );
+}
+
+#[test]
+fn find_lines_matching() {
+    let sim = gpiosim::builder()
+        .with_bank(
+            Bank::new(8, "find_lines_matching 1")
+                .name(3, "flm_PMIC_EN")
+                .name(6, "flm_PMIC_IRQ")
+                .name(7, "flm_LED0"),
+        )
+        .with_bank(
+            Bank::new(12, "find_lines_matching 2")
+                .name(2, "flm_PMIC_RST")
+                .name(4, "flm_LED1")
+                .name(11, "flm_LED10"),
+        )
+        .live()
+        .unwrap();
+    let c0 = sim.chips()[0].dev_path();
+    let c1 = sim.chips()[1].dev_path();
+
+    let found = gpiocdev::find_lines_matching("flm_PMIC_*").unwrap();
+    let mut lines: Vec<(&std::path::Path, u32)> = found
+        .iter()
+        .map(|l| (l.chip.as_path(), l.info.offset))
+        .collect();
+    lines.sort_unstable();
+    let mut expected = vec![(c0.as_path(), 3), (c0.as_path(), 6), (c1.as_path(), 2)];
+    expected.sort_unstable();
+    assert_eq!(lines, expected);
+
+    let found = gpiocdev::find_lines_matching("flm_LED?").unwrap();
+    assert_eq!(found.len(), 2);
+    assert!(found.iter().all(|l| l.info.name.len() == 8));
+
+    let found = gpiocdev::find_lines_matching("flm_nada*").unwrap();
+    assert!(found.is_empty());
+
+    #[cfg(feature = "regex")]
+    {
+        let found = gpiocdev::find_lines_matching_regex("^flm_LED[0-9]+$").unwrap();
+        assert_eq!(found.len(), 3);
+        let found = gpiocdev::find_lines_matching_regex("flm_PMIC_(EN|RST)").unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(matches!(
+            gpiocdev::find_lines_matching_regex("flm_("),
+            Err(gpiocdev::Error::InvalidArgument(_))
+        ));
+    }
+}
+
+#[test]
+fn find_lines_by_consumer() {
+    let sim = gpiosim::builder()
+        .with_bank(
+            Bank::new(8, "find_lines_by_consumer 1")
+                .hog(2, "flbc-motord", Direction::OutputLow)
+                .hog(5, "flbc-other", Direction::Input),
+        )
+        .with_bank(Bank::new(12, "find_lines_by_consumer 2").hog(
+            7,
+            "flbc-motord",
+            Direction::OutputHigh,
+        ))
+        .live()
+        .unwrap();
+    let c0 = sim.chips()[0].dev_path();
+    let c1 = sim.chips()[1].dev_path();
+
+    let found = gpiocdev::find_lines_by_consumer("flbc-motord").unwrap();
+    let mut lines: Vec<(&std::path::Path, u32)> = found
+        .iter()
+        .map(|l| (l.chip.as_path(), l.info.offset))
+        .collect();
+    lines.sort_unstable();
+    let mut expected = vec![(c0.as_path(), 2), (c1.as_path(), 7)];
+    expected.sort_unstable();
+    assert_eq!(lines, expected);
+
+    let found = gpiocdev::find_lines_by_consumer("flbc-*").unwrap();
+    assert_eq!(found.len(), 3);
+    assert!(found.iter().all(|l| l.info.used));
+
+    let found = gpiocdev::find_lines_by_consumer("flbc-nada").unwrap();
+    assert!(found.is_empty());
+}
+
 #[test]
 fn detect_abi_version() {
     // assumes a kernel with both v1 and v2 supported.
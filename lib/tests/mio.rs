@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::line::{EdgeDetection, EdgeKind, InfoChangeKind};
+use gpiocdev::{Chip, Request};
+use gpiosim::Simpleton;
+use mio::{Events, Interest, Poll, Token};
+use std::time::Duration;
+
+// max time to wait for an event
+const TIMEOUT: Duration = Duration::from_millis(25);
+
+// The tokens reported as readable within the timeout.
+fn poll_tokens(poll: &mut Poll) -> Vec<Token> {
+    let mut events = Events::with_capacity(4);
+    poll.poll(&mut events, Some(TIMEOUT)).unwrap();
+    events.iter().map(|e| e.token()).collect()
+}
+
+#[test]
+fn request_source() {
+    let s = Simpleton::new(4);
+    let offset = 2;
+    let mut req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let mut poll = Poll::new().unwrap();
+    poll.registry()
+        .register(&mut req, Token(3), Interest::READABLE)
+        .unwrap();
+    assert!(poll_tokens(&mut poll).is_empty());
+
+    s.pullup(offset).unwrap();
+    s.pulldown(offset).unwrap();
+    assert_eq!(poll_tokens(&mut poll), [Token(3)]);
+    let mut kinds = Vec::new();
+    while req.has_edge_event().unwrap() {
+        kinds.push(req.read_edge_event().unwrap().kind);
+    }
+    assert_eq!(kinds, [EdgeKind::Rising, EdgeKind::Falling]);
+
+    poll.registry()
+        .reregister(&mut req, Token(4), Interest::READABLE)
+        .unwrap();
+    s.pullup(offset).unwrap();
+    assert_eq!(poll_tokens(&mut poll), [Token(4)]);
+    req.read_edge_event().unwrap();
+
+    poll.registry().deregister(&mut req).unwrap();
+    s.pulldown(offset).unwrap();
+    assert!(poll_tokens(&mut poll).is_empty());
+}
+
+#[test]
+fn chip_source() {
+    let s = Simpleton::new(4);
+    let offset = 1;
+    let mut chip = Chip::from_path(s.dev_path()).unwrap();
+    chip.watch_line_info(offset).unwrap();
+
+    let mut poll = Poll::new().unwrap();
+    poll.registry()
+        .register(&mut chip, Token(1), Interest::READABLE)
+        .unwrap();
+    assert!(poll_tokens(&mut poll).is_empty());
+
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(offset)
+        .as_input()
+        .request()
+        .unwrap();
+    assert_eq!(poll_tokens(&mut poll), [Token(1)]);
+    let evt = chip.read_line_info_change_event().unwrap();
+    assert_eq!(evt.kind, InfoChangeKind::Requested);
+
+    poll.registry().deregister(&mut chip).unwrap();
+    drop(req);
+    assert!(poll_tokens(&mut poll).is_empty());
+}
@@ -0,0 +1,79 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::line::EdgeDetection;
+use gpiocdev::poll::{wait_any, Ready};
+use gpiocdev::{Chip, Request};
+use gpiosim::Simpleton;
+use std::os::unix::prelude::AsFd;
+use std::time::Duration;
+
+#[test]
+fn wait_any_requests() {
+    let s = Simpleton::new(4);
+    let req1 = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(1)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+    let req2 = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(2)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+    let reqs = [&req1, &req2];
+    assert!(wait_any(&reqs, Duration::from_millis(10))
+        .unwrap()
+        .is_empty());
+
+    s.pullup(2).unwrap();
+    assert_eq!(
+        wait_any(&reqs, Duration::from_millis(50)).unwrap(),
+        [Ready {
+            index: 1,
+            readable: true,
+            hangup: false
+        }]
+    );
+    req2.read_edge_event().unwrap();
+
+    s.pullup(1).unwrap();
+    assert_eq!(
+        wait_any(&reqs, Duration::from_millis(50)).unwrap(),
+        [Ready {
+            index: 0,
+            readable: true,
+            hangup: false
+        }]
+    );
+}
+
+#[test]
+fn wait_any_chip_and_request() {
+    let s = Simpleton::new(4);
+    let chip = Chip::from_path(s.dev_path()).unwrap();
+    chip.watch_line_info(3).unwrap();
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(1)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+    let sources: [&dyn AsFd; 2] = [&req, &chip];
+    assert!(wait_any(&sources, Duration::from_millis(10))
+        .unwrap()
+        .is_empty());
+
+    let _watched = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(3)
+        .request()
+        .unwrap();
+    let ready = wait_any(&sources, Duration::from_millis(50)).unwrap();
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].index, 1);
+    assert!(ready[0].readable);
+}
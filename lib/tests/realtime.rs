@@ -0,0 +1,61 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::line::{EdgeDetection, EdgeKind};
+use gpiocdev::realtime::RealtimeReader;
+use gpiocdev::Request;
+use gpiosim::Simpleton;
+use std::time::Duration;
+
+// Events are delivered whether or not the real-time settings could be applied.
+#[test]
+fn spawn() {
+    let s = Simpleton::new(4);
+    let offset = 2;
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let reader = RealtimeReader::new()
+        .with_priority(10)
+        .with_cpu(0)
+        .with_memory_locked(false)
+        .spawn(&req, move |evt| {
+            let _ = tx.send(evt);
+        })
+        .unwrap();
+    assert!(reader.applied().memory_locked);
+    // the reader does not borrow the request
+    drop(req);
+
+    s.pullup(offset).unwrap();
+    let evt = rx.recv_timeout(Duration::from_millis(50)).unwrap();
+    assert_eq!(evt.offset, offset);
+    assert_eq!(evt.kind, EdgeKind::Rising);
+    s.pulldown(offset).unwrap();
+    let evt = rx.recv_timeout(Duration::from_millis(50)).unwrap();
+    assert_eq!(evt.kind, EdgeKind::Falling);
+
+    assert!(!reader.is_finished());
+    assert!(reader.stop().is_ok());
+    // the handler is dropped with the reader
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn invalid_priority() {
+    let s = Simpleton::new(4);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(1)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+    let res = RealtimeReader::new().with_priority(0).spawn(&req, |_| {});
+    assert!(matches!(res, Err(gpiocdev::Error::InvalidArgument(_))));
+}
@@ -48,6 +48,7 @@ mod builder {
             request_as_is,
             request_found_line,
             request_found_lines,
+            request_line_ref,
             request_output_lines,
             request_mixed_config,
             request_invalid_offset
@@ -122,6 +123,44 @@ mod builder {
             );
         }
 
+        #[test]
+        fn request_v1_fallback() {
+            use gpiocdev::line::EventClock;
+            use gpiocdev::request::Degradation;
+
+            let s = Simpleton::new(10);
+
+            let mut builder = Request::builder();
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            builder.using_abi_version(V1);
+
+            let req = builder
+                .on_chip(s.dev_path())
+                .with_line(1)
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .with_debounce_period(Duration::from_millis(4))
+                .with_event_clock(EventClock::Realtime)
+                .with_v1_fallback()
+                .request()
+                .unwrap();
+            assert_eq!(
+                req.fallback_report().degradations,
+                [
+                    Degradation::Debounce {
+                        offset: 1,
+                        period: Duration::from_millis(4)
+                    },
+                    Degradation::EventClock {
+                        offset: 1,
+                        clock: EventClock::Realtime
+                    },
+                ]
+            );
+            let cfg = req.line_config(1).unwrap();
+            assert_eq!(cfg.debounce_period, None);
+            assert_eq!(cfg.event_clock, None);
+        }
+
         #[test]
         fn request_line_config() {
             use gpiocdev::line::{Config, Direction};
@@ -179,6 +218,7 @@ mod builder {
             request_as_is,
             request_found_line,
             request_found_lines,
+            request_line_ref,
             request_output_lines,
             request_mixed_config,
             request_invalid_offset
@@ -684,6 +724,55 @@ mod builder {
         );
     }
 
+    #[allow(unused_variables)]
+    fn request_line_ref(abiv: AbiVersion) {
+        use gpiocdev::line::LineRef;
+
+        let sim = gpiosim::builder()
+            .with_bank(gpiosim::Bank::new(8, "left").name(4, "durian"))
+            .live()
+            .unwrap();
+        let s = &sim.chips()[0];
+        let c = Chip::from_path(s.dev_path()).unwrap();
+
+        // by chip and offset
+        let line: LineRef = format!("{}:3", s.dev_path().display()).parse().unwrap();
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder.with_line_ref(&line).as_input().request().unwrap();
+        assert_eq!(&req.chip_path(), s.dev_path());
+        assert_eq!(req.lines(), &[3]);
+        assert!(c.line_info(3).unwrap().used);
+        drop(req);
+
+        // by name
+        let line: LineRef = "durian".parse().unwrap();
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder.with_line_ref(&line).as_input().request().unwrap();
+        assert!(c.line_info(4).unwrap().used);
+        drop(req);
+
+        // unknown name
+        let line: LineRef = "no_such_durian".parse().unwrap();
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        assert_eq!(
+            builder.with_line_ref(&line).request().unwrap_err(),
+            gpiocdev::Error::InvalidArgument("No line named 'no_such_durian' found.".to_string())
+        );
+
+        // offset out of range
+        let line: LineRef = format!("{}:8", s.dev_path().display()).parse().unwrap();
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        assert!(builder.with_line_ref(&line).request().is_err());
+    }
+
     #[allow(unused_variables)]
     fn request_output_lines(abiv: AbiVersion) {
         use gpiosim::Level;
@@ -860,6 +949,7 @@ mod builder {
 
 mod request {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     #[cfg(feature = "uapi_v1")]
     mod uapi_v1 {
         use crate::common::wait_propagation_delay;
@@ -874,12 +964,44 @@ mod request {
             values,
             set_value,
             set_values,
+            set_values_verified,
             reconfigure,
             has_edge_event,
             wait_edge_event,
             read_edge_event,
             new_edge_event_buffer,
-            read_edge_events_into_slice
+            read_edge_events_into_slice,
+            read_edge_events_into,
+            read_edge_events,
+            signal_edges,
+            edge_events_or_resume,
+            wait_for_edge,
+            toggle_value,
+            toggle_values,
+            play_waveform,
+            pulse,
+            scheduler,
+            deadman,
+            with_config_scope,
+            drop_policy,
+            play_frames,
+            reproduce_request,
+            snapshot_restore,
+            fanout_edges,
+            effective_config,
+            from_parts,
+            claim_when_free,
+            retry_on_busy,
+            dispatcher_scope,
+            set_then_read,
+            bus,
+            supervisor,
+            request_wide,
+            value_source,
+            data_logger,
+            events_to_channel,
+            rate_limited,
+            kernel_event_buffer_size
         }
 
         #[test]
@@ -920,6 +1042,31 @@ mod request {
             );
         }
 
+        #[test]
+        fn reconfigure_debounce_dropped() {
+            use std::time::Duration;
+
+            let s = Simpleton::new(4);
+
+            let mut builder = Request::builder();
+            #[cfg(feature = "uapi_v2")]
+            builder.using_abi_version(V1);
+            let req = builder
+                .on_chip(s.dev_path())
+                .with_lines(&[1, 2])
+                .as_input()
+                .request()
+                .unwrap();
+            assert!(req.fallback_report().is_empty());
+
+            let mut cfg = req.config();
+            cfg.with_debounce_period(Duration::from_millis(4));
+            req.reconfigure(&cfg).unwrap();
+            // the debounce is not applied, so is not reported in the config
+            assert_eq!(req.line_config(1).unwrap().debounce_period, None);
+            assert_eq!(req.line_config(2).unwrap().debounce_period, None);
+        }
+
         #[test]
         fn edge_events() {
             let s = Simpleton::new(3);
@@ -1068,12 +1215,44 @@ mod request {
             values,
             set_value,
             set_values,
+            set_values_verified,
             reconfigure,
             has_edge_event,
             wait_edge_event,
             read_edge_event,
             new_edge_event_buffer,
-            read_edge_events_into_slice
+            read_edge_events_into_slice,
+            read_edge_events_into,
+            read_edge_events,
+            signal_edges,
+            edge_events_or_resume,
+            wait_for_edge,
+            toggle_value,
+            toggle_values,
+            play_waveform,
+            pulse,
+            scheduler,
+            deadman,
+            with_config_scope,
+            drop_policy,
+            play_frames,
+            reproduce_request,
+            snapshot_restore,
+            fanout_edges,
+            effective_config,
+            from_parts,
+            claim_when_free,
+            retry_on_busy,
+            dispatcher_scope,
+            set_then_read,
+            bus,
+            supervisor,
+            request_wide,
+            value_source,
+            data_logger,
+            events_to_channel,
+            rate_limited,
+            kernel_event_buffer_size
         }
 
         #[test]
@@ -1169,6 +1348,49 @@ mod request {
             assert_eq!(evt.seqno, 4);
         }
 
+        #[test]
+        fn edge_events_detect_loss() {
+            use gpiocdev::request::{EdgeEventIteratorExt, EdgeOrLoss, EventLoss};
+
+            let s = Simpleton::new(3);
+            let offset = 1;
+
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .with_kernel_event_buffer_size(2)
+                .request()
+                .unwrap();
+
+            s.toggle(offset).unwrap();
+            wait_propagation_delay();
+            let mut iter = req.edge_events().detect_loss();
+            match iter.next().unwrap().unwrap() {
+                EdgeOrLoss::Edge(evt) => assert_eq!(evt.seqno, 1),
+                x => panic!("unexpected {:?}", x),
+            }
+
+            // overflow the kernel buffer, which retains the two most recent events
+            for _ in 0..4 {
+                s.toggle(offset).unwrap();
+                wait_propagation_delay();
+            }
+            assert_eq!(
+                iter.next().unwrap().unwrap(),
+                EdgeOrLoss::Loss(EventLoss { missed: 2 })
+            );
+            match iter.next().unwrap().unwrap() {
+                EdgeOrLoss::Edge(evt) => assert_eq!(evt.seqno, 4),
+                x => panic!("unexpected {:?}", x),
+            }
+            match iter.next().unwrap().unwrap() {
+                EdgeOrLoss::Edge(evt) => assert_eq!(evt.seqno, 5),
+                x => panic!("unexpected {:?}", x),
+            }
+            assert_eq!(iter.lost(), 2);
+        }
+
         #[test]
         fn edge_event_from_slice() {
             let s = Simpleton::new(3);
@@ -1429,11 +1651,10 @@ mod request {
         );
     }
 
-    fn set_values(abiv: AbiVersion) {
-        use gpiosim::Level;
-
-        let s = Simpleton::new(5);
-        let offsets = &[0, 1, 3];
+    #[allow(unused_variables)]
+    fn set_values_verified(abiv: AbiVersion) {
+        let s = Simpleton::new(4);
+        let offsets = &[0, 2, 3];
 
         let mut builder = Request::builder();
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
@@ -1442,108 +1663,115 @@ mod request {
         let req = builder
             .on_chip(s.dev_path())
             .with_lines(offsets)
-            .as_output(Value::Active)
+            .as_output(Value::Inactive)
             .request()
             .unwrap();
 
-        let mut vals = Values::from_offsets(offsets);
+        let values = Values::from([(0, Value::Active), (2, Value::Inactive), (3, Value::Active)]);
+        let mismatches = req.set_values_verified(&values).unwrap();
+        assert!(mismatches.is_empty());
+        assert_eq!(s.get_level(0).unwrap(), gpiosim::Level::High);
+        assert_eq!(s.get_level(2).unwrap(), gpiosim::Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), gpiosim::Level::High);
+    }
 
-        // full set
-        assert!(req.set_values(&vals).is_ok());
-        assert_eq!(s.get_level(0).unwrap(), Level::Low);
-        assert_eq!(s.get_level(1).unwrap(), Level::Low);
-        assert_eq!(s.get_level(3).unwrap(), Level::Low);
+    #[allow(unused_variables)]
+    fn toggle_value(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 1;
 
-        vals.set(1, Value::Active);
-        vals.set(3, Value::Active);
-        assert!(req.set_values(&vals).is_ok());
-        assert_eq!(s.get_level(0).unwrap(), Level::Low);
-        assert_eq!(s.get_level(1).unwrap(), Level::High);
-        assert_eq!(s.get_level(3).unwrap(), Level::High);
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
 
-        vals.set(0, Value::Active);
-        vals.set(3, Value::Inactive);
-        assert!(req.set_values(&vals).is_ok());
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::Low);
+
+        assert_eq!(req.toggle_value(offset), Ok(Value::Active));
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::High);
+
+        assert_eq!(req.toggle_value(offset), Ok(Value::Inactive));
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::Low);
+
+        // tracks values set explicitly
+        assert!(req.set_value(offset, Value::Active).is_ok());
+        assert_eq!(req.toggle_value(offset), Ok(Value::Inactive));
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::Low);
+        assert_eq!(
+            req.line_config(offset).unwrap().value,
+            Some(Value::Inactive)
+        );
+
+        // invalid offset
+        assert_eq!(
+            req.toggle_value(3).unwrap_err(),
+            gpiocdev::Error::InvalidArgument("offset is not a requested line.".to_string())
+        );
+    }
+
+    fn toggle_values(abiv: AbiVersion) {
+        use gpiosim::Level;
+
+        let s = Simpleton::new(3);
+        let offsets = &[0, 1, 2];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Inactive)
+            .with_line(1)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+
+        let values = req.toggle_values(offsets).unwrap();
+        let expected: Values = [(0, Value::Active), (1, Value::Inactive), (2, Value::Active)]
+            .into_iter()
+            .collect();
+        assert_eq!(values, expected);
         assert_eq!(s.get_level(0).unwrap(), Level::High);
-        assert_eq!(s.get_level(1).unwrap(), Level::High);
-        assert_eq!(s.get_level(3).unwrap(), Level::Low);
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
 
         if abiv == AbiVersion::V2 {
             // subset
-            let mut vals = Values::default();
-            vals.set(1, Value::Inactive);
-            vals.set(3, Value::Active);
-            assert!(req.set_values(&vals).is_ok());
-            assert_eq!(s.get_level(0).unwrap(), Level::High);
-            assert_eq!(s.get_level(1).unwrap(), Level::Low);
-            assert_eq!(s.get_level(3).unwrap(), Level::High);
-
-            // singular
-            let mut vals = Values::default();
-            vals.set(3, Value::Inactive);
-            assert!(req.set_values(&vals).is_ok());
+            let values = req.toggle_values(&[2]).unwrap();
+            let expected: Values = [(2, Value::Inactive)].into_iter().collect();
+            assert_eq!(values, expected);
             assert_eq!(s.get_level(0).unwrap(), Level::High);
-            assert_eq!(s.get_level(1).unwrap(), Level::Low);
-            assert_eq!(s.get_level(3).unwrap(), Level::Low);
-
-            // empty set
-            let vals = Values::default();
-            assert!(req.set_values(&vals).is_ok());
-            assert_eq!(s.get_level(0).unwrap(), Level::Low);
-            assert_eq!(s.get_level(1).unwrap(), Level::Low);
-            assert_eq!(s.get_level(3).unwrap(), Level::Low);
+            assert_eq!(s.get_level(2).unwrap(), Level::Low);
         } else {
-            // subset
-            let mut vals = Values::default();
-            vals.set(1, Value::Inactive);
-            vals.set(3, Value::Active);
-            assert_eq!(
-                req.set_values(&vals).unwrap_err(),
-                gpiocdev::Error::AbiLimitation(
-                    AbiVersion::V1,
-                    "requires all requested lines".to_string(),
-                )
-            );
-
-            // singular
-            let mut vals = Values::default();
-            vals.set(3, Value::Inactive);
             assert_eq!(
-                req.set_values(&vals).unwrap_err(),
+                req.toggle_values(&[2]).unwrap_err(),
                 gpiocdev::Error::AbiLimitation(
                     AbiVersion::V1,
                     "requires all requested lines".to_string(),
                 )
             );
-
-            // empty set
-            let vals = Values::default();
-            assert_eq!(
-                req.set_values(&vals),
-                Err(gpiocdev::Error::AbiLimitation(
-                    AbiVersion::V1,
-                    "requires all requested lines".to_string()
-                ))
-            );
         }
 
-        // invalid offset - ignored
-        let mut vals = Values::from_offsets(&[0, 1, 2, 3]);
-        vals.set(0, Value::Active);
-        vals.set(1, Value::Active);
-        assert!(req.set_values(&vals).is_ok());
-        assert_eq!(s.get_level(0).unwrap(), Level::High);
-        assert_eq!(s.get_level(1).unwrap(), Level::High);
-        assert_eq!(s.get_level(3).unwrap(), Level::Low);
+        // invalid offset
+        assert_eq!(
+            req.toggle_values(&[1, 3]).unwrap_err(),
+            gpiocdev::Error::InvalidArgument("offset 3 is not a requested line.".to_string())
+        );
     }
 
     #[allow(unused_variables)]
-    fn reconfigure(abiv: AbiVersion) {
-        use gpiocdev::line::{Bias, Direction, Drive};
-        use gpiosim::Level;
+    fn play_waveform(abiv: AbiVersion) {
+        use gpiocdev::waveform::Waveform;
 
-        let s = Simpleton::new(5);
-        let c = gpiocdev::chip::Chip::from_path(s.dev_path()).unwrap();
+        let s = Simpleton::new(3);
         let offset = 1;
 
         let mut builder = Request::builder();
@@ -1553,28 +1781,1182 @@ mod request {
         let req = builder
             .on_chip(s.dev_path())
             .with_line(offset)
-            .as_active_low()
-            .with_bias(gpiocdev::line::Bias::PullDown)
-            .with_drive(gpiocdev::line::Drive::OpenDrain)
+            .as_output(Value::Inactive)
             .request()
             .unwrap();
 
-        let info = c.line_info(offset).unwrap();
-        assert!(info.active_low);
-        assert_eq!(info.direction, Direction::Output);
-        assert_eq!(info.bias, Some(Bias::PullDown));
-        assert_eq!(info.drive, Some(Drive::OpenDrain));
-        assert_eq!(info.edge_detection, None);
-        assert_eq!(info.debounce_period, None);
-        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+        let mut wf = Waveform::new();
+        wf.push(Value::Active, Duration::from_millis(5))
+            .push(Value::Inactive, Duration::from_millis(5))
+            .repeat(2)
+            .push(Value::Active, Duration::from_millis(5));
+        let start = std::time::Instant::now();
+        assert!(req.play_waveform(offset, &wf).is_ok());
+        assert!(start.elapsed() >= wf.duration());
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::High);
 
-        let mut cfg = req.config();
-        cfg.with_bias(gpiocdev::line::Bias::PullUp)
-            .with_drive(gpiocdev::line::Drive::OpenSource)
-            .as_active_high();
-        assert!(req.reconfigure(&cfg).is_ok());
+        // invalid offset
+        assert_eq!(
+            req.play_waveform(3, &wf).unwrap_err(),
+            gpiocdev::Error::InvalidArgument("offset is not a requested line.".to_string())
+        );
+    }
 
-        let info = c.line_info(offset).unwrap();
+    #[allow(unused_variables)]
+    fn play_frames(abiv: AbiVersion) {
+        use gpiocdev::waveform::chaser;
+
+        let s = Simpleton::new(4);
+        let offsets = &[0, 1, 3];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        let frames = chaser(offsets);
+        let period = Duration::from_millis(5);
+        let start = std::time::Instant::now();
+        assert!(req.play_frames(&frames, period).is_ok());
+        assert!(start.elapsed() >= period * 3);
+        assert_eq!(s.get_level(0).unwrap(), gpiosim::Level::Low);
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), gpiosim::Level::High);
+
+        // invalid offset in a later frame leaves lines unchanged
+        let mut bad = chaser(offsets);
+        bad[2].set(2, Value::Active);
+        assert_eq!(
+            req.play_frames(&bad, period).unwrap_err(),
+            gpiocdev::Error::InvalidArgument(
+                "offset 2 in frame 2 is not a requested line.".to_string()
+            )
+        );
+        assert_eq!(s.get_level(0).unwrap(), gpiosim::Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), gpiosim::Level::High);
+    }
+
+    #[allow(unused_variables)]
+    fn reproduce_request(abiv: AbiVersion) {
+        use gpiocdev::line::{Bias, Direction};
+        use gpiocdev::request::Config;
+
+        let s = Simpleton::new(4);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[1, 2])
+            .as_output(Value::Active)
+            .as_active_low()
+            .with_bias(Bias::PullUp)
+            .request()
+            .unwrap();
+        req.set_value(2, Value::Inactive).unwrap();
+
+        let cfg = Config::reproduce_request(&req).unwrap();
+        assert_eq!(cfg.chip(), s.dev_path());
+        assert_eq!(cfg.lines(), &[1, 2]);
+        let lc = cfg.line_config(1).unwrap();
+        assert_eq!(lc.direction, Some(Direction::Output));
+        assert!(lc.active_low);
+        assert_eq!(lc.bias, Some(Bias::PullUp));
+        assert_eq!(lc.value, Some(Value::Active));
+        assert_eq!(cfg.line_config(2).unwrap().value, Some(Value::Inactive));
+        drop(req);
+
+        let mut builder = Request::from_config(cfg);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder.request().unwrap();
+        assert_eq!(req.value(1).unwrap(), Value::Active);
+        assert_eq!(req.value(2).unwrap(), Value::Inactive);
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::Low);
+        assert_eq!(s.get_level(2).unwrap(), gpiosim::Level::High);
+    }
+
+    #[allow(unused_variables)]
+    fn snapshot_restore(abiv: AbiVersion) {
+        use gpiocdev::line::Direction;
+
+        let s = Simpleton::new(4);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[1, 2])
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+        req.set_value(2, Value::Inactive).unwrap();
+
+        let snapshot = req.snapshot().unwrap();
+        assert_eq!(snapshot.lines(), [1, 2]);
+        assert_eq!(snapshot.value(1), Some(Value::Active));
+        assert_eq!(snapshot.value(2), Some(Value::Inactive));
+        assert_eq!(
+            snapshot.line_config(1).unwrap().direction,
+            Some(Direction::Output)
+        );
+
+        // pause the outputs
+        let mut cfg = req.config();
+        cfg.with_lines(&[1, 2]).as_output(Value::Inactive);
+        req.reconfigure(&cfg).unwrap();
+        wait_propagation_delay();
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::Low);
+
+        req.restore(&snapshot).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(1).unwrap(), Value::Active);
+        assert_eq!(req.value(2).unwrap(), Value::Inactive);
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::High);
+        assert_eq!(s.get_level(2).unwrap(), gpiosim::Level::Low);
+        assert_eq!(req.snapshot().unwrap(), snapshot);
+    }
+
+    #[allow(unused_variables)]
+    fn fanout_edges(abiv: AbiVersion) {
+        use gpiocdev::request::EdgeFilter;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        let fanout = req.fanout_edges().unwrap();
+        let rising = fanout.subscribe_kind(EdgeKind::Rising);
+        let falling = fanout.subscribe_kind(EdgeKind::Falling);
+        let mut filter = EdgeFilter::default();
+        filter.with_line(offset);
+        let all = fanout.subscribe(&filter);
+        let bounded = fanout.subscribe_bounded(&filter, 1);
+        assert_eq!(fanout.subscribers(), 4);
+
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+
+        // the bounded subscription only buffers the first event
+        assert_eq!(all.lag(), 2);
+        assert_eq!(all.dropped(), 0);
+        assert_eq!(bounded.lag(), 1);
+        assert_eq!(bounded.dropped(), 1);
+        assert_eq!(bounded.try_recv().unwrap().kind, EdgeKind::Rising);
+        assert_eq!(bounded.lag(), 0);
+        assert!(bounded.try_recv().is_none());
+        drop(bounded);
+
+        let timeout = Duration::from_millis(50);
+        let evt = rising.recv_timeout(timeout).unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+        assert_eq!(evt.offset, offset);
+        assert!(rising.recv_timeout(timeout).is_none());
+
+        let evt = falling.recv_timeout(timeout).unwrap();
+        assert_eq!(evt.kind, EdgeKind::Falling);
+        assert!(falling.recv_timeout(timeout).is_none());
+
+        assert_eq!(all.recv_timeout(timeout).unwrap().kind, EdgeKind::Rising);
+        assert_eq!(all.recv_timeout(timeout).unwrap().kind, EdgeKind::Falling);
+        assert!(all.try_recv().is_none());
+
+        // dropped subscriptions are pruned on the next matching event
+        drop(rising);
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert!(all.recv_timeout(timeout).is_some());
+        assert_eq!(fanout.subscribers(), 2);
+
+        // subscriptions end when the fanout is dropped
+        drop(fanout);
+        assert!(all.recv().is_none());
+    }
+
+    #[allow(unused_variables)]
+    fn effective_config(abiv: AbiVersion) {
+        use gpiocdev::line::{Config, Direction, EventClock};
+
+        let s = Simpleton::new(4);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        builder.on_chip(s.dev_path()).with_lines(&[3, 1]).as_input();
+        if abiv == AbiVersion::V2 {
+            // event clock without edge detection, and debounce below the uAPI resolution
+            builder
+                .with_event_clock(EventClock::Realtime)
+                .with_debounce_period(Duration::from_nanos(1500));
+        }
+        let req = builder.request().unwrap();
+        assert_eq!(req.lines(), &[1, 3]);
+        let expected = if abiv == AbiVersion::V2 {
+            Config {
+                direction: Some(Direction::Input),
+                debounce_period: Some(Duration::from_micros(2)),
+                ..Default::default()
+            }
+        } else {
+            Config {
+                direction: Some(Direction::Input),
+                ..Default::default()
+            }
+        };
+        assert_eq!(req.line_config(1), Some(expected.clone()));
+        assert_eq!(req.line_config(3), Some(expected));
+        assert_eq!(req.line_config(2), None);
+    }
+
+    #[allow(unused_variables)]
+    fn from_parts(abiv: AbiVersion) {
+        use gpiocdev::request::DropPolicy;
+        use std::os::unix::prelude::OwnedFd;
+
+        let s = Simpleton::new(4);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[2, 1])
+            .as_output(Value::Active)
+            .on_drop(DropPolicy::RevertToInput)
+            .request()
+            .unwrap();
+        let cfg = req.config();
+        let fd = OwnedFd::from(req);
+        // drop policy is not applied
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::High);
+        assert_eq!(s.get_level(2).unwrap(), gpiosim::Level::High);
+
+        let req = Request::from_parts(fd, cfg).unwrap();
+        assert_eq!(req.lines(), &[1, 2]);
+        req.set_value(2, Value::Inactive).unwrap();
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::High);
+        assert_eq!(s.get_level(2).unwrap(), gpiosim::Level::Low);
+        assert_eq!(req.value(1).unwrap(), Value::Active);
+    }
+
+    #[allow(unused_variables)]
+    fn claim_when_free(abiv: AbiVersion) {
+        use std::thread;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+        let holder = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive);
+
+        let err = builder
+            .claim_when_free(Duration::from_millis(10))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            gpiocdev::Error::Uapi(_, gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(16)))
+        ));
+
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(holder);
+        });
+        let req = builder.claim_when_free(Duration::from_secs(1)).unwrap();
+        assert!(t.join().is_ok());
+        assert_eq!(req.lines(), &[offset]);
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::Low);
+        drop(req);
+
+        // no deadline
+        let req = builder.claim_when_free(Duration::MAX).unwrap();
+        assert_eq!(req.lines(), &[offset]);
+    }
+
+    #[allow(unused_variables)]
+    fn retry_on_busy(abiv: AbiVersion) {
+        use gpiocdev::request::RetryPolicy;
+        use std::thread;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+        let holder = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .request()
+            .unwrap();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder.on_chip(s.dev_path()).with_line(offset);
+
+        // retries exhausted
+        builder.retry_on_busy(RetryPolicy::fixed(3, Duration::from_millis(5)));
+        assert!(matches!(
+            builder.request().unwrap_err(),
+            gpiocdev::Error::Uapi(_, gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(16)))
+        ));
+
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(holder);
+        });
+        builder.retry_on_busy(RetryPolicy::exponential(10, Duration::from_millis(5)));
+        assert!(builder.request().is_ok());
+        assert!(t.join().is_ok());
+    }
+
+    #[allow(unused_variables)]
+    fn dispatcher_scope(abiv: AbiVersion) {
+        use gpiocdev::dispatcher;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        let mut kinds = Vec::new();
+        dispatcher::scope(|ds| {
+            let stopper = ds.stopper();
+            let k = &mut kinds;
+            ds.on_edge(&req, move |evt| {
+                k.push(evt.kind);
+                if k.len() == 2 {
+                    stopper.stop();
+                }
+            });
+            s.pullup(offset).unwrap();
+            wait_propagation_delay();
+            s.pulldown(offset).unwrap();
+            assert!(ds.wait_timeout(Duration::from_secs(1)));
+        })
+        .unwrap();
+        // the handler has been joined, so its borrow is released
+        assert_eq!(kinds, &[EdgeKind::Rising, EdgeKind::Falling]);
+
+        // returning from the closure stops the handlers
+        dispatcher::scope(|ds| ds.on_edge(&req, |_| {})).unwrap();
+    }
+
+    #[allow(unused_variables)]
+    fn set_then_read(abiv: AbiVersion) {
+        use gpiosim::Level;
+
+        let s = Simpleton::new(4);
+        let offsets = &[1, 2];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        // full set is read back
+        let vals = Values::from([(1, Value::Active), (2, Value::Inactive)]);
+        let read = req.set_then_read(&vals, Duration::from_micros(10)).unwrap();
+        assert_eq!(read, vals);
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::Low);
+
+        if abiv == AbiVersion::V2 {
+            // subset still reads back all lines
+            let vals = Values::from([(2, Value::Active)]);
+            let read = req.set_then_read(&vals, Duration::ZERO).unwrap();
+            assert_eq!(read, Values::from([(1, Value::Active), (2, Value::Active)]));
+            assert_eq!(s.get_level(2).unwrap(), Level::High);
+        } else {
+            assert_eq!(
+                req.set_then_read(&Values::from([(2, Value::Active)]), Duration::ZERO),
+                Err(gpiocdev::Error::AbiLimitation(
+                    AbiVersion::V1,
+                    "requires all requested lines".to_string()
+                ))
+            );
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn bus(abiv: AbiVersion) {
+        use gpiocdev::bus::{BitOrder, Bus, Endianness};
+        use gpiosim::Level;
+
+        let s = Simpleton::new(10);
+        let offsets = &[0, 1, 2, 3, 4, 5, 6, 7];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        let bus = Bus::new(&req, offsets)
+            .unwrap()
+            .with_bit_order(BitOrder::MsbFirst)
+            .with_settle(Duration::from_micros(10));
+        bus.write_u32(0xa1).unwrap();
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
+        assert_eq!(s.get_level(7).unwrap(), Level::High);
+        for offset in [1, 3, 4, 5, 6] {
+            assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+        }
+        assert_eq!(bus.read_u32().unwrap(), 0xa1);
+
+        // a subset, least significant bit first
+        let bus = Bus::new(&req, &[7, 6, 5, 4]).unwrap();
+        assert_eq!(bus.read_u32().unwrap(), 0x01);
+        bus.write_u32(0xfa).unwrap();
+        assert_eq!(s.get_level(6).unwrap(), Level::High);
+        assert_eq!(s.get_level(4).unwrap(), Level::High);
+        assert_eq!(s.get_level(7).unwrap(), Level::Low);
+        assert_eq!(s.get_level(5).unwrap(), Level::Low);
+
+        // invalid buses
+        assert!(Bus::new(&req, &[1, 8]).is_err());
+        assert!(Bus::new(&req, &[1, 1]).is_err());
+        assert!(Bus::new(&req, &[]).is_err());
+        assert!(bus.with_endianness(Endianness::Big).is_err());
+    }
+
+    #[allow(unused_variables)]
+    fn supervisor(abiv: AbiVersion) {
+        use gpiocdev::line::EdgeEvent;
+        use gpiocdev::supervisor::RequestSupervisor;
+        use std::sync::mpsc;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .with_edge_detection(EdgeDetection::BothEdges);
+
+        let (tx, rx) = mpsc::channel();
+        let handler = move |evt: EdgeEvent| tx.send(evt.kind).unwrap();
+        let mut sup = RequestSupervisor::new();
+        sup.add(&builder, handler).unwrap();
+        // line already requested
+        assert!(sup.add(&builder, |_| {}).is_err());
+        sup.start().unwrap();
+        assert!(sup.add(&builder, |_| {}).is_err());
+
+        s.pullup(offset).unwrap();
+        let kind = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(kind, EdgeKind::Rising);
+
+        let health = sup.health();
+        assert!(health.is_healthy());
+        assert_eq!(health.chips.len(), 1);
+        let chip = &health.chips[0];
+        assert_eq!(&chip.chip, s.dev_path());
+        assert_eq!(chip.events, 1);
+        assert!(chip.last_event.is_some());
+        assert_eq!(chip.errors, 0);
+        assert_eq!(chip.restarts, 0);
+
+        // dropping the supervisor releases the line
+        drop(sup);
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .request()
+            .unwrap();
+    }
+
+    #[allow(unused_variables)]
+    fn request_wide(abiv: AbiVersion) {
+        use gpiosim::Level;
+
+        let s = Simpleton::new(100);
+        let offsets: Vec<u32> = (0..100).collect();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        builder
+            .on_chip(s.dev_path())
+            .with_lines(&offsets)
+            .as_output(Value::Inactive);
+        if abiv == AbiVersion::V2 {
+            // v1 requires all lines in a request to share the same config
+            builder
+                .with_line(98)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges);
+        }
+        let req = builder.request_wide().unwrap();
+        assert_eq!(req.requests().len(), 2);
+        assert_eq!(req.lines(), offsets);
+
+        let vals = Values::from([(3, Value::Active), (95, Value::Active)]);
+        req.set_values(&vals).unwrap();
+        assert_eq!(s.get_level(3).unwrap(), Level::High);
+        assert_eq!(s.get_level(95).unwrap(), Level::High);
+        assert_eq!(s.get_level(4).unwrap(), Level::Low);
+        req.set_value(70, Value::Active).unwrap();
+        assert_eq!(s.get_level(70).unwrap(), Level::High);
+
+        let mut vals = Values::from_offsets(&[3, 4, 95]);
+        req.values(&mut vals).unwrap();
+        assert_eq!(
+            vals,
+            Values::from([
+                (3, Value::Active),
+                (4, Value::Inactive),
+                (95, Value::Active)
+            ])
+        );
+        let mut vals = Values::default();
+        req.values(&mut vals).unwrap();
+        assert_eq!(vals.len(), 100);
+        assert_eq!(vals.get(70), Some(Value::Active));
+        assert_eq!(req.value(95).unwrap(), Value::Active);
+
+        if abiv == AbiVersion::V2 {
+            assert!(!req.has_edge_event().unwrap());
+            s.pullup(98).unwrap();
+            assert!(req.wait_edge_event(Duration::from_millis(100)).unwrap());
+            let evt = req.read_edge_event().unwrap();
+            assert_eq!(evt.offset, 98);
+            assert_eq!(evt.kind, EdgeKind::Rising);
+        }
+
+        assert!(req.value(100).is_err());
+        assert!(req
+            .set_values(&Values::from([(120, Value::Active)]))
+            .is_err());
+    }
+
+    #[allow(unused_variables)]
+    fn value_source(abiv: AbiVersion) {
+        use gpiocdev::request::ValueSource;
+
+        let s = Simpleton::new(4);
+        s.pullup(3).unwrap();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder
+            .on_chip(s.dev_path())
+            .with_lines(&[1, 2])
+            .as_output(Value::Inactive)
+            .with_line(1)
+            .as_output(Value::Active);
+        if abiv == AbiVersion::V2 {
+            builder.with_line(3).as_input();
+        }
+        let req = builder
+            .with_value_source(ValueSource::LastSet)
+            .request()
+            .unwrap();
+
+        let mut vals = Values::default();
+        assert_eq!(
+            req.values_with_source(&mut vals).unwrap(),
+            ValueSource::LastSet
+        );
+        assert_eq!(vals.get(1), Some(Value::Active));
+        assert_eq!(vals.get(2), Some(Value::Inactive));
+        req.set_value(2, Value::Active).unwrap();
+        assert_eq!(req.value(2).unwrap(), Value::Active);
+
+        if abiv == AbiVersion::V2 {
+            // inputs are read from the kernel
+            assert_eq!(vals.get(3), Some(Value::Active));
+            let mut vals = Values::from_offsets(&[3]);
+            assert_eq!(
+                req.values_with_source(&mut vals).unwrap(),
+                ValueSource::Physical
+            );
+            assert_eq!(vals.get(3), Some(Value::Active));
+        }
+        drop(req);
+
+        // default is physical
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+        let mut vals = Values::default();
+        assert_eq!(
+            req.values_with_source(&mut vals).unwrap(),
+            ValueSource::Physical
+        );
+        assert_eq!(vals.get(1), Some(Value::Active));
+    }
+
+    #[allow(unused_variables)]
+    fn data_logger(abiv: AbiVersion) {
+        use gpiocdev::datalog::DataLogger;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for SharedBuf {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let s = Simpleton::new(4);
+        s.pullup(2).unwrap();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[2, 1])
+            .as_input()
+            .request()
+            .unwrap();
+
+        // zero period
+        assert_eq!(
+            DataLogger::new(&req, Duration::ZERO).err().unwrap(),
+            gpiocdev::Error::InvalidArgument("period must be non-zero.".to_string())
+        );
+
+        let csv = SharedBuf::default();
+        let (logger, samples) =
+            DataLogger::with_csv(&req, Duration::from_millis(5), csv.clone()).unwrap();
+        let first = samples.recv_timeout(Duration::from_secs(1)).unwrap();
+        let second = samples.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(second.seqno > first.seqno);
+        assert!(second.timestamp_ns > first.timestamp_ns);
+        assert_eq!(first.values.get(1), Some(Value::Inactive));
+        assert_eq!(first.values.get(2), Some(Value::Active));
+
+        // the request remains usable
+        assert_eq!(req.value(2).unwrap(), Value::Active);
+
+        let stats = logger.stats();
+        drop(logger);
+        assert!(stats.samples >= 2);
+        assert_eq!(stats.errors, 0);
+        let csv = String::from_utf8(csv.0.lock().unwrap().clone()).unwrap();
+        let mut rows = csv.lines();
+        assert_eq!(rows.next(), Some("timestamp_ns,seqno,1,2"));
+        assert_eq!(
+            rows.next(),
+            Some(format!("{},{},0,1", first.timestamp_ns, first.seqno).as_str())
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn events_to_channel(abiv: AbiVersion) {
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let fwd = req.events_to_channel(tx).unwrap();
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+
+        let timeout = Duration::from_millis(50);
+        let evt = rx.recv_timeout(timeout).unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+        assert_eq!(evt.offset, offset);
+        assert_eq!(rx.recv_timeout(timeout).unwrap().kind, EdgeKind::Falling);
+        assert_eq!(fwd.forwarded(), 2);
+        assert_eq!(fwd.dropped(), 0);
+        assert!(!fwd.is_finished());
+        assert!(fwd.stop().is_ok());
+        // the sender is dropped with the forwarder
+        assert!(rx.recv().is_err());
+
+        // full bounded channel
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let fwd = req.events_to_channel(tx).unwrap();
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(fwd.forwarded(), 1);
+        assert_eq!(fwd.dropped(), 1);
+        assert_eq!(rx.recv().unwrap().kind, EdgeKind::Rising);
+
+        // forwarding ends when the receiver is dropped
+        drop(rx);
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert!(fwd.is_finished());
+        assert!(fwd.stop().is_ok());
+    }
+
+    #[allow(unused_variables)]
+    fn rate_limited(abiv: AbiVersion) {
+        use gpiocdev::request::OverflowPolicy;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        // zero capacity
+        assert_eq!(
+            req.rate_limited(0, OverflowPolicy::Block).err().unwrap(),
+            gpiocdev::Error::InvalidArgument("capacity must be non-zero.".to_string())
+        );
+
+        let timeout = Duration::from_millis(50);
+        for (policy, kinds) in [
+            (
+                OverflowPolicy::DropOldest,
+                [EdgeKind::Falling, EdgeKind::Rising],
+            ),
+            (
+                OverflowPolicy::DropNewest,
+                [EdgeKind::Rising, EdgeKind::Falling],
+            ),
+        ] {
+            let events = req.rate_limited(2, policy).unwrap();
+            s.pullup(offset).unwrap();
+            wait_propagation_delay();
+            s.pulldown(offset).unwrap();
+            wait_propagation_delay();
+            s.pullup(offset).unwrap();
+            wait_propagation_delay();
+            assert_eq!(events.len(), 2);
+            assert_eq!(events.dropped(), 1);
+            assert_eq!(events.recv_timeout(timeout).unwrap().kind, kinds[0]);
+            assert_eq!(events.recv_timeout(timeout).unwrap().kind, kinds[1]);
+            assert!(events.try_recv().is_none());
+            assert!(events.is_empty());
+            assert!(events.stop().is_ok());
+            s.pulldown(offset).unwrap();
+            wait_propagation_delay();
+            // discard the event not read by the queue
+            req.read_edge_event().unwrap();
+        }
+
+        // blocked events remain in the kernel
+        let events = req.rate_limited(1, OverflowPolicy::Block).unwrap();
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.recv_timeout(timeout).unwrap().kind, EdgeKind::Rising);
+        // no deadline
+        assert_eq!(
+            events.recv_timeout(Duration::MAX).unwrap().kind,
+            EdgeKind::Falling
+        );
+        assert_eq!(events.dropped(), 0);
+        assert!(events.stop().is_ok());
+    }
+
+    #[allow(unused_variables)]
+    fn kernel_event_buffer_size(abiv: AbiVersion) {
+        let s = Simpleton::new(4);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder
+            .on_chip(s.dev_path())
+            .with_line(2)
+            .with_edge_detection(EdgeDetection::BothEdges);
+        if abiv == AbiVersion::V2 {
+            builder.with_kernel_event_buffer_for_rate(20_000, Duration::from_millis(2));
+        }
+        let req = builder.request().unwrap();
+        let expected = match abiv {
+            AbiVersion::V1 => 16,
+            AbiVersion::V2 => 64,
+        };
+        assert_eq!(req.kernel_event_buffer_size(), expected);
+        drop(req);
+
+        // default
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(2)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+        assert_eq!(req.kernel_event_buffer_size(), 16);
+    }
+
+    #[allow(unused_variables)]
+    fn pulse(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        assert!(req.pulse(offset, Duration::from_millis(20)).is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(s.get_level(offset).unwrap(), gpiosim::Level::Low);
+
+        // invalid offset
+        assert_eq!(
+            req.pulse(3, Duration::from_millis(20)).unwrap_err(),
+            gpiocdev::Error::InvalidArgument("offset is not a requested line.".to_string())
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn scheduler(abiv: AbiVersion) {
+        use gpiocdev::scheduler::{now_ns, Scheduler};
+        use gpiosim::Level;
+
+        let s = Simpleton::new(4);
+        let offsets = &[1, 2];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        let sched = Scheduler::new().unwrap();
+        let tgt = sched.attach(&req).unwrap();
+        let start = now_ns() + 10_000_000;
+        let mut values = Values::default();
+        values.set(1, Value::Active).set(2, Value::Active);
+        sched.set_values_at(tgt, start, values).unwrap();
+        sched
+            .set_value_at(tgt, start + 10_000_000, 2, Value::Inactive)
+            .unwrap();
+        let cancelled = sched
+            .set_value_at(tgt, start + 20_000_000, 1, Value::Inactive)
+            .unwrap();
+        assert_eq!(sched.pending(), 3);
+        assert!(sched.cancel(cancelled));
+        assert!(!sched.cancel(cancelled));
+        sched.wait_idle();
+        assert!(now_ns() >= start + 10_000_000);
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::Low);
+        let stats = sched.stats();
+        assert_eq!(stats.jobs, 2);
+        assert_eq!(stats.errors, 0);
+        assert!(stats.min <= stats.max);
+
+        // values set by the scheduler are tracked by the request
+        assert_eq!(req.toggle_value(1).unwrap(), Value::Inactive);
+    }
+
+    #[allow(unused_variables)]
+    fn deadman(abiv: AbiVersion) {
+        use gpiocdev::deadman::{Heartbeat, Supervisor};
+        use gpiosim::Level;
+
+        let s = Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "gpiocdev-deadman-{:?}-{}.sock",
+            abiv,
+            std::process::id()
+        ));
+        let timeout = Duration::from_millis(50);
+        let sup = Supervisor::new(&req, offset, Value::Inactive, timeout, &path).unwrap();
+        let hb = Heartbeat::connect(&path).unwrap();
+        for _ in 0..5 {
+            std::thread::sleep(Duration::from_millis(20));
+            hb.alive().unwrap();
+        }
+        assert!(!sup.is_tripped());
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+        // heartbeat lost
+        sup.wait_trip();
+        assert!(sup.is_tripped());
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+
+        // latched
+        hb.alive().unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(sup.is_tripped());
+
+        sup.reset();
+        assert!(!sup.is_tripped());
+        drop(sup);
+        assert!(!path.exists());
+
+        // invalid offset
+        assert_eq!(
+            Supervisor::new(&req, 3, Value::Inactive, timeout, &path)
+                .err()
+                .unwrap(),
+            gpiocdev::Error::InvalidArgument("offset is not a requested line.".to_string())
+        );
+    }
+
+    fn set_values(abiv: AbiVersion) {
+        use gpiosim::Level;
+
+        let s = Simpleton::new(5);
+        let offsets = &[0, 1, 3];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+
+        let mut vals = Values::from_offsets(offsets);
+
+        // full set
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::Low);
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), Level::Low);
+
+        vals.set(1, Value::Active);
+        vals.set(3, Value::Active);
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::Low);
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(3).unwrap(), Level::High);
+
+        vals.set(0, Value::Active);
+        vals.set(3, Value::Inactive);
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(3).unwrap(), Level::Low);
+
+        if abiv == AbiVersion::V2 {
+            // subset
+            let mut vals = Values::default();
+            vals.set(1, Value::Inactive);
+            vals.set(3, Value::Active);
+            assert!(req.set_values(&vals).is_ok());
+            assert_eq!(s.get_level(0).unwrap(), Level::High);
+            assert_eq!(s.get_level(1).unwrap(), Level::Low);
+            assert_eq!(s.get_level(3).unwrap(), Level::High);
+
+            // singular
+            let mut vals = Values::default();
+            vals.set(3, Value::Inactive);
+            assert!(req.set_values(&vals).is_ok());
+            assert_eq!(s.get_level(0).unwrap(), Level::High);
+            assert_eq!(s.get_level(1).unwrap(), Level::Low);
+            assert_eq!(s.get_level(3).unwrap(), Level::Low);
+
+            // empty set
+            let vals = Values::default();
+            assert!(req.set_values(&vals).is_ok());
+            assert_eq!(s.get_level(0).unwrap(), Level::Low);
+            assert_eq!(s.get_level(1).unwrap(), Level::Low);
+            assert_eq!(s.get_level(3).unwrap(), Level::Low);
+        } else {
+            // subset
+            let mut vals = Values::default();
+            vals.set(1, Value::Inactive);
+            vals.set(3, Value::Active);
+            assert_eq!(
+                req.set_values(&vals).unwrap_err(),
+                gpiocdev::Error::AbiLimitation(
+                    AbiVersion::V1,
+                    "requires all requested lines".to_string(),
+                )
+            );
+
+            // singular
+            let mut vals = Values::default();
+            vals.set(3, Value::Inactive);
+            assert_eq!(
+                req.set_values(&vals).unwrap_err(),
+                gpiocdev::Error::AbiLimitation(
+                    AbiVersion::V1,
+                    "requires all requested lines".to_string(),
+                )
+            );
+
+            // empty set
+            let vals = Values::default();
+            assert_eq!(
+                req.set_values(&vals),
+                Err(gpiocdev::Error::AbiLimitation(
+                    AbiVersion::V1,
+                    "requires all requested lines".to_string()
+                ))
+            );
+        }
+
+        // invalid offset - ignored
+        let mut vals = Values::from_offsets(&[0, 1, 2, 3]);
+        vals.set(0, Value::Active);
+        vals.set(1, Value::Active);
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(3).unwrap(), Level::Low);
+    }
+
+    #[allow(unused_variables)]
+    fn reconfigure(abiv: AbiVersion) {
+        use gpiocdev::line::{Bias, Direction, Drive};
+        use gpiosim::Level;
+
+        let s = Simpleton::new(5);
+        let c = gpiocdev::chip::Chip::from_path(s.dev_path()).unwrap();
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_active_low()
+            .with_bias(gpiocdev::line::Bias::PullDown)
+            .with_drive(gpiocdev::line::Drive::OpenDrain)
+            .request()
+            .unwrap();
+
+        let info = c.line_info(offset).unwrap();
+        assert!(info.active_low);
+        assert_eq!(info.direction, Direction::Output);
+        assert_eq!(info.bias, Some(Bias::PullDown));
+        assert_eq!(info.drive, Some(Drive::OpenDrain));
+        assert_eq!(info.edge_detection, None);
+        assert_eq!(info.debounce_period, None);
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+
+        let mut cfg = req.config();
+        cfg.with_bias(gpiocdev::line::Bias::PullUp)
+            .with_drive(gpiocdev::line::Drive::OpenSource)
+            .as_active_high();
+        assert!(req.reconfigure(&cfg).is_ok());
+
+        let info = c.line_info(offset).unwrap();
         assert!(!info.active_low);
         assert_eq!(info.direction, Direction::Output);
         assert_eq!(info.bias, Some(Bias::PullUp));
@@ -1584,6 +2966,119 @@ mod request {
         assert_eq!(s.get_level(offset).unwrap(), Level::High);
     }
 
+    #[allow(unused_variables)]
+    fn with_config_scope(abiv: AbiVersion) {
+        use gpiocdev::line::Direction;
+        use gpiosim::Level;
+
+        let s = Simpleton::new(3);
+        let c = gpiocdev::chip::Chip::from_path(s.dev_path()).unwrap();
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+        let mut cfg = req.config();
+        cfg.as_input();
+
+        // restored on drop
+        {
+            let guard = req.with_config_scope(&cfg).unwrap();
+            assert_eq!(c.line_info(offset).unwrap().direction, Direction::Input);
+            assert_eq!(
+                guard
+                    .previous()
+                    .unwrap()
+                    .line_config(offset)
+                    .unwrap()
+                    .direction,
+                Some(Direction::Output)
+            );
+            s.pullup(offset).unwrap();
+            wait_propagation_delay();
+            assert_eq!(req.value(offset).unwrap(), Value::Active);
+            s.pulldown(offset).unwrap();
+            wait_propagation_delay();
+            assert_eq!(req.value(offset).unwrap(), Value::Inactive);
+        }
+        assert_eq!(c.line_info(offset).unwrap().direction, Direction::Output);
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+        // explicit restore
+        let guard = req.with_config_scope(&cfg).unwrap();
+        assert_eq!(c.line_info(offset).unwrap().direction, Direction::Input);
+        assert!(guard.restore().is_ok());
+        assert_eq!(c.line_info(offset).unwrap().direction, Direction::Output);
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+        // commit
+        let guard = req.with_config_scope(&cfg).unwrap();
+        guard.commit();
+        assert_eq!(c.line_info(offset).unwrap().direction, Direction::Input);
+        assert_eq!(
+            req.line_config(offset).unwrap().direction,
+            Some(Direction::Input)
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn drop_policy(abiv: AbiVersion) {
+        use gpiocdev::line::Direction;
+        use gpiocdev::request::DropPolicy;
+
+        let s = Simpleton::new(4);
+        let c = gpiocdev::chip::Chip::from_path(s.dev_path()).unwrap();
+        let offsets = &[1, 2];
+
+        let request = |policy: DropPolicy| {
+            let mut builder = Request::builder();
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            builder.using_abi_version(abiv);
+            builder
+                .on_chip(s.dev_path())
+                .with_lines(offsets)
+                .as_output(Value::Active)
+                .on_drop(policy)
+                .request()
+                .unwrap()
+        };
+
+        // direction is retained by the kernel after release
+        drop(request(DropPolicy::LeaveAsIs));
+        for offset in offsets {
+            let info = c.line_info(*offset).unwrap();
+            assert!(!info.used);
+            assert_eq!(info.direction, Direction::Output);
+        }
+
+        drop(request(DropPolicy::RevertToInput));
+        for offset in offsets {
+            let info = c.line_info(*offset).unwrap();
+            assert!(!info.used);
+            assert_eq!(info.direction, Direction::Input);
+        }
+
+        let mut values = Values::default();
+        values.set(1, Value::Inactive).set(2, Value::Inactive);
+        let req = request(DropPolicy::SetValues(values));
+        // clones used by helpers do not apply the policy
+        let sched = gpiocdev::scheduler::Scheduler::new().unwrap();
+        sched.attach(&req).unwrap();
+        drop(sched);
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::High);
+        drop(req);
+        assert!(!c.line_info(1).unwrap().used);
+    }
+
     #[allow(unused_variables)]
     fn has_edge_event(abiv: AbiVersion) {
         let s = Simpleton::new(3);
@@ -1711,6 +3206,116 @@ mod request {
         assert_eq!(wlen, req.edge_event_u64_size());
     }
 
+    #[allow(unused_variables)]
+    fn read_edge_events_into(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        let mut buf = vec![0_u64; req.edge_event_u64_size() * 3];
+
+        // create four events
+        for _ in 0..4 {
+            s.toggle(offset).unwrap();
+            wait_propagation_delay();
+        }
+
+        // read a buffer full
+        let evts = req.read_edge_events_into(&mut buf).unwrap();
+        assert_eq!(evts.len(), 3);
+        let kinds: Vec<EdgeKind> = evts.map(|evt| evt.unwrap().kind()).collect();
+        assert_eq!(
+            kinds,
+            [EdgeKind::Rising, EdgeKind::Falling, EdgeKind::Rising]
+        );
+
+        // read remaining event
+        let mut evts = req.read_edge_events_into(&mut buf).unwrap();
+        assert_eq!(evts.len(), 1);
+        let evt = evts.next().unwrap().unwrap();
+        assert_eq!(evt.offset(), offset);
+        assert_eq!(evt.kind(), EdgeKind::Falling);
+        if abiv == AbiVersion::V2 {
+            assert_eq!(evt.seqno(), 4);
+            assert_eq!(evt.line_seqno(), 4);
+        }
+        let evt = gpiocdev::line::EdgeEvent::from(evt);
+        assert_eq!(evt.offset, offset);
+        assert!(evts.next().is_none());
+    }
+
+    #[allow(unused_variables)]
+    fn read_edge_events(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        if abiv == AbiVersion::V2 {
+            builder.with_kernel_event_buffer_size(128);
+        }
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        let mut events = Vec::new();
+        assert_eq!(req.read_edge_events(&mut events, 0).unwrap(), 0);
+
+        // create four events
+        for _ in 0..4 {
+            s.toggle(offset).unwrap();
+            wait_propagation_delay();
+        }
+
+        // read up to max
+        assert_eq!(req.read_edge_events(&mut events, 3).unwrap(), 3);
+        // read remaining event, without blocking for more
+        assert_eq!(req.read_edge_events(&mut events, 3).unwrap(), 1);
+        assert_eq!(events.len(), 4);
+        let kinds: Vec<EdgeKind> = events.iter().map(|evt| evt.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EdgeKind::Rising,
+                EdgeKind::Falling,
+                EdgeKind::Rising,
+                EdgeKind::Falling
+            ]
+        );
+        if abiv == AbiVersion::V2 {
+            let seqnos: Vec<u32> = events.iter().map(|evt| evt.seqno).collect();
+            assert_eq!(seqnos, [1, 2, 3, 4]);
+        }
+
+        // batches larger than a single read
+        for _ in 0..100 {
+            s.toggle(offset).unwrap();
+        }
+        wait_propagation_delay();
+        events.clear();
+        let n = req.read_edge_events(&mut events, 200).unwrap();
+        assert_eq!(n, events.len());
+        if abiv == AbiVersion::V2 {
+            assert_eq!(n, 100);
+        }
+        assert!(!req.has_edge_event().unwrap());
+    }
+
     #[allow(unused_variables)]
     fn new_edge_event_buffer(abiv: AbiVersion) {
         let s = Simpleton::new(3);
@@ -1731,6 +3336,158 @@ mod request {
         assert_eq!(buf.has_event(), Ok(false));
         assert_eq!(buf.capacity(), 4);
     }
+
+    fn signal_edges(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        assert_eq!(
+            req.signal_edges(0).err().unwrap(),
+            gpiocdev::Error::InvalidArgument("signal 0 is not a realtime signal.".to_string())
+        );
+
+        // separate signals so the ABI variants can run concurrently
+        let (signo, slot) = match abiv {
+            AbiVersion::V1 => (libc::SIGRTMAX() - 1, 0),
+            AbiVersion::V2 => (libc::SIGRTMAX(), 1),
+        };
+        // SAFETY: installs a handler that only touches atomics.
+        unsafe {
+            let mut sa: libc::sigaction = std::mem::zeroed();
+            sa.sa_sigaction = record_edge_signal as *const () as usize;
+            sa.sa_flags = libc::SA_SIGINFO;
+            libc::sigemptyset(&mut sa.sa_mask);
+            assert_eq!(libc::sigaction(signo, &sa, std::ptr::null_mut()), 0);
+        }
+        EDGE_SIGNALS[slot].store(0, Ordering::SeqCst);
+
+        let signaller = req.signal_edges(signo).unwrap();
+        s.pullup(offset).unwrap();
+        let signalled = || EDGE_SIGNALS[slot].load(Ordering::SeqCst);
+        let deadline = std::time::Instant::now() + EVENT_WAIT_TIMEOUT;
+        while signalled() == 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(signalled(), offset as usize);
+        assert!(!signaller.is_finished());
+        assert_eq!(signaller.stop(), Ok(()));
+
+        // the request remains usable once the signaller is stopped
+        s.pulldown(offset).unwrap();
+        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+        let evt = req.read_edge_event().unwrap();
+        assert_eq!(evt.kind, EdgeKind::Falling);
+    }
+
+    // The si_value of the last edge signal received, by ABI version,
+    // or usize::MAX if the siginfo was not that queued by the signaller.
+    static EDGE_SIGNALS: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(0)];
+
+    extern "C" fn record_edge_signal(
+        signo: libc::c_int,
+        info: *mut libc::siginfo_t,
+        _ctx: *mut libc::c_void,
+    ) {
+        let slot = if signo == libc::SIGRTMAX() { 1 } else { 0 };
+        // SAFETY: the kernel passes a valid siginfo to SA_SIGINFO handlers.
+        let info = unsafe { &*info };
+        let value = if info.si_signo == signo && info.si_code == libc::SI_QUEUE {
+            // SAFETY: si_value is populated for signals queued by sigqueue.
+            unsafe { info.si_value().sival_ptr as usize }
+        } else {
+            usize::MAX
+        };
+        EDGE_SIGNALS[slot].store(value, Ordering::SeqCst);
+    }
+
+    #[allow(unused_variables)]
+    fn edge_events_or_resume(abiv: AbiVersion) {
+        use gpiocdev::request::{EdgeOrResume, SuspendDetector};
+
+        let s = Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        let mut iter = req.edge_events_or_resume(SuspendDetector::new()).unwrap();
+        s.pullup(offset).unwrap();
+        match iter.next() {
+            Some(Ok(EdgeOrResume::Edge(evt))) => {
+                assert_eq!(evt.kind, EdgeKind::Rising);
+                assert_eq!(evt.offset, offset);
+            }
+            x => panic!("unexpected {:?}", x),
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn wait_for_edge(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        assert_eq!(
+            req.wait_for_edge(offset, EdgeKind::Rising, EVENT_WAIT_TIMEOUT),
+            Ok(None)
+        );
+
+        // non-matching edge is discarded
+        s.pullup(offset).unwrap();
+        assert_eq!(
+            req.wait_for_edge(offset, EdgeKind::Falling, EVENT_WAIT_TIMEOUT),
+            Ok(None)
+        );
+        assert_eq!(req.has_edge_event(), Ok(false));
+
+        s.pulldown(offset).unwrap();
+        let evt = req
+            .wait_for_edge(offset, EdgeKind::Falling, EVENT_WAIT_TIMEOUT)
+            .unwrap()
+            .unwrap();
+        assert_eq!(evt.kind, EdgeKind::Falling);
+        assert_eq!(evt.offset, offset);
+
+        // no deadline
+        s.pullup(offset).unwrap();
+        let evt = req
+            .wait_for_edge(offset, EdgeKind::Rising, Duration::MAX)
+            .unwrap()
+            .unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+    }
 }
 
 mod edge_event_buffer {
@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::line::{EdgeDetection, EdgeKind, Value};
+use gpiocdev::selftest::{self, Failure};
+use gpiocdev::Request;
+use gpiosim::Simpleton;
+
+// The simulator does not wire lines together, so the outputs are not seen on the inputs.
+
+#[test]
+fn loopback_unwired() {
+    let s = Simpleton::new(8);
+    let outputs = Request::builder()
+        .on_chip(s.dev_path())
+        .with_lines(&[0, 1])
+        .as_output(Value::Active)
+        .request()
+        .unwrap();
+    let inputs = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(4)
+        .as_input()
+        .with_line(5)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let report = selftest::loopback(&outputs, &inputs, &[(0, 4), (1, 5)]).unwrap();
+    assert!(!report.passed());
+    assert_eq!(report.failed().count(), 2);
+    // outputs are left inactive
+    assert_eq!(s.get_level(0).unwrap(), gpiosim::Level::Low);
+    assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::Low);
+
+    let level = Failure::Level {
+        driven: Value::Active,
+        read: Value::Inactive,
+    };
+    assert_eq!(report.pairs[0].failures, std::slice::from_ref(&level));
+    assert_eq!(
+        report.pairs[1].failures,
+        [
+            level,
+            Failure::MissingEdge(EdgeKind::Rising),
+            Failure::MissingEdge(EdgeKind::Falling)
+        ]
+    );
+}
+
+#[test]
+fn loopback_crosstalk() {
+    let s = Simpleton::new(8);
+    let outputs = Request::builder()
+        .on_chip(s.dev_path())
+        .with_lines(&[0, 1])
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+    let inputs = Request::builder()
+        .on_chip(s.dev_path())
+        .with_lines(&[4, 5])
+        .as_input()
+        .request()
+        .unwrap();
+
+    // input 5 is stuck active
+    s.pullup(5).unwrap();
+    let report = selftest::loopback(&outputs, &inputs, &[(0, 4), (1, 5)]).unwrap();
+    assert!(report.pairs[0].failures.contains(&Failure::Crosstalk {
+        driven: Value::Inactive,
+        input: 5
+    }));
+    assert!(report.pairs[1].failures.contains(&Failure::Level {
+        driven: Value::Inactive,
+        read: Value::Active
+    }));
+}
+
+#[test]
+fn loopback_unrequested_line() {
+    let s = Simpleton::new(8);
+    let outputs = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(0)
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+    let inputs = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(4)
+        .as_input()
+        .request()
+        .unwrap();
+
+    let err = selftest::loopback(&outputs, &inputs, &[(0, 5)]).unwrap_err();
+    assert_eq!(err.to_string(), "offset 5 is not a requested line.");
+}
@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::line::{EdgeDetection, EdgeKind, Value};
+use gpiocdev::testing::{ExpectLevel, ExpectRequest};
+use gpiocdev::Request;
+use gpiosim::{Level, Simpleton};
+use std::time::Duration;
+
+// max time to wait for an expectation to be met
+const TIMEOUT: Duration = Duration::from_millis(25);
+
+#[test]
+fn expect_edge() {
+    let s = Simpleton::new(8);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_lines(&[3, 5])
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    s.pullup(3).unwrap();
+    let evt = req.expect_edge(EdgeKind::Rising).on(3).within(TIMEOUT);
+    assert_eq!(evt.offset, 3);
+    s.pullup(5).unwrap();
+    req.expect_edge(EdgeKind::Rising).within(TIMEOUT);
+    s.pulldown(5).unwrap();
+    req.expect_edge(EdgeKind::Falling).on(5).within(TIMEOUT);
+}
+
+#[test]
+#[should_panic(expected = "expected Rising edge on line 3 within")]
+fn expect_edge_none() {
+    let s = Simpleton::new(8);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(3)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    req.expect_edge(EdgeKind::Rising).on(3).within(TIMEOUT);
+}
+
+#[test]
+#[should_panic(expected = "expected Rising edge on line 5, got Rising edge on line 3")]
+fn expect_edge_wrong_line() {
+    let s = Simpleton::new(8);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_lines(&[3, 5])
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    s.pullup(3).unwrap();
+    req.expect_edge(EdgeKind::Rising).on(5).within(TIMEOUT);
+}
+
+#[test]
+fn expect_value() {
+    let s = Simpleton::new(8);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(3)
+        .as_input()
+        .request()
+        .unwrap();
+
+    req.expect_value(3, Value::Inactive).within(TIMEOUT);
+    s.pullup(3).unwrap();
+    req.expect_value(3, Value::Active).within(TIMEOUT);
+}
+
+#[test]
+#[should_panic(expected = "expected line 3 to be Active within")]
+fn expect_value_timeout() {
+    let s = Simpleton::new(8);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(3)
+        .as_input()
+        .request()
+        .unwrap();
+
+    req.expect_value(3, Value::Active).within(TIMEOUT);
+}
+
+#[test]
+fn expect_level() {
+    let s = Simpleton::new(8);
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(5)
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+
+    s.expect_level(5, Level::Low).within(TIMEOUT);
+    req.set_value(5, Value::Active).unwrap();
+    s.chip().expect_level(5, Level::High).within(TIMEOUT);
+}
@@ -0,0 +1,194 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::bridge::uinput::{Code, KeyMap, Uinput};
+use gpiocdev::line::EdgeDetection;
+use gpiocdev::{Chip, ErrorKind, Request};
+use gpiosim::Simpleton;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const EV_SYN: u16 = 0;
+const EV_KEY: u16 = 1;
+const EV_SW: u16 = 5;
+
+// Find the event device for the input device with the given name.
+fn event_device(name: &str) -> PathBuf {
+    for entry in fs::read_dir("/sys/class/input").unwrap() {
+        let path = entry.unwrap().path();
+        let dev_name = fs::read_to_string(path.join("device/name")).unwrap_or_default();
+        if dev_name.trim_end() == name
+            && path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("event")
+        {
+            return PathBuf::from("/dev/input").join(path.file_name().unwrap());
+        }
+    }
+    panic!("no event device for {}", name);
+}
+
+// Read the next non-sync event, returning the type, code and value.
+fn read_event(f: &mut File) -> (u16, u16, i32) {
+    loop {
+        let mut buf = [0; std::mem::size_of::<libc::input_event>()];
+        f.read_exact(&mut buf).unwrap();
+        let len = buf.len();
+        let type_ = u16::from_ne_bytes([buf[len - 8], buf[len - 7]]);
+        let code = u16::from_ne_bytes([buf[len - 6], buf[len - 5]]);
+        let value = i32::from_ne_bytes([buf[len - 4], buf[len - 3], buf[len - 2], buf[len - 1]]);
+        if type_ != EV_SYN {
+            return (type_, code, value);
+        }
+    }
+}
+
+fn request(s: &Simpleton, offsets: &[u32]) -> Request {
+    Request::builder()
+        .on_chip(s.dev_path())
+        .with_lines(offsets)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap()
+}
+
+#[test]
+fn events() {
+    let s = Simpleton::new(4);
+    let name = "gpiocdev-uinput-events";
+    let mut bridge = Uinput::builder()
+        .with_name(name)
+        .with_key(1, 28)
+        .with_switch(2, 0)
+        .build(request(&s, &[1, 2, 3]))
+        .unwrap();
+    assert_eq!(bridge.code(1), Some(Code::Key(28)));
+    assert_eq!(bridge.code(2), Some(Code::Switch(0)));
+    assert_eq!(bridge.code(3), None);
+
+    let mut f = File::open(event_device(name)).unwrap();
+    s.pullup(1).unwrap();
+    s.pullup(3).unwrap();
+    s.pullup(2).unwrap();
+    s.pulldown(1).unwrap();
+    assert_eq!(bridge.process_events().unwrap(), 4);
+    assert_eq!(read_event(&mut f), (EV_KEY, 28, 1));
+    assert_eq!(read_event(&mut f), (EV_SW, 0, 1));
+    assert_eq!(read_event(&mut f), (EV_KEY, 28, 0));
+    assert_eq!(bridge.process_events().unwrap(), 0);
+}
+
+#[test]
+fn long_press() {
+    let s = Simpleton::new(4);
+    let name = "gpiocdev-uinput-long-press";
+    let period = Duration::from_millis(50);
+    let mut bridge = Uinput::builder()
+        .with_name(name)
+        .with_key(1, 28)
+        .with_long_press(1, 116, period)
+        .build(request(&s, &[1]))
+        .unwrap();
+    let mut f = File::open(event_device(name)).unwrap();
+    assert_eq!(bridge.next_timeout(), None);
+
+    // short press
+    s.pullup(1).unwrap();
+    assert_eq!(bridge.process_events().unwrap(), 1);
+    assert!(bridge.next_timeout().unwrap() <= period);
+    s.pulldown(1).unwrap();
+    assert_eq!(bridge.process_events().unwrap(), 1);
+    assert_eq!(read_event(&mut f), (EV_KEY, 28, 1));
+    assert_eq!(read_event(&mut f), (EV_KEY, 28, 0));
+    assert_eq!(bridge.next_timeout(), None);
+
+    // long press
+    s.pullup(1).unwrap();
+    assert_eq!(bridge.process_events().unwrap(), 1);
+    std::thread::sleep(period);
+    bridge.process_timeouts().unwrap();
+    assert_eq!(read_event(&mut f), (EV_KEY, 116, 1));
+    assert_eq!(bridge.next_timeout(), None);
+    s.pulldown(1).unwrap();
+    assert_eq!(bridge.process_events().unwrap(), 1);
+    assert_eq!(read_event(&mut f), (EV_KEY, 116, 0));
+}
+
+#[test]
+fn keymap() {
+    let s = Simpleton::new(4);
+    let name = "gpiocdev-uinput-keymap";
+    let km: KeyMap = format!(
+        "name = \"{}\"\n[[key]]\nline = \"{}:1\"\ncode = 28\nconfig = \"active-low\"\n[[switch]]\nline = \"{}:2\"\ncode = 0\n",
+        name,
+        s.dev_path().display(),
+        s.dev_path().display()
+    )
+    .parse()
+    .unwrap();
+    let mut bridge = km.build().unwrap();
+    assert_eq!(bridge.code(1), Some(Code::Key(28)));
+    assert_eq!(bridge.code(2), Some(Code::Switch(0)));
+    let info = Chip::from_path(s.dev_path()).unwrap().line_info(1).unwrap();
+    assert_eq!(info.consumer, name);
+    assert!(info.active_low);
+
+    // active low key is released when pulled up
+    let mut f = File::open(event_device(name)).unwrap();
+    s.pullup(1).unwrap();
+    assert_eq!(bridge.process_events().unwrap(), 1);
+    assert_eq!(read_event(&mut f), (EV_KEY, 28, 0));
+
+    // lines must be on the same chip
+    let s2 = Simpleton::new(4);
+    let km: KeyMap = format!(
+        "[[key]]\nline = \"{}:1\"\ncode = 28\n[[key]]\nline = \"{}:1\"\ncode = 1\n",
+        s.dev_path().display(),
+        s2.dev_path().display()
+    )
+    .parse()
+    .unwrap();
+    assert_eq!(km.build().unwrap_err().kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn build_errors() {
+    let s = Simpleton::new(4);
+
+    let res = Uinput::builder().build(request(&s, &[1]));
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = Uinput::builder().with_key(2, 28).build(request(&s, &[1]));
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = Uinput::builder()
+        .with_name(&"x".repeat(80))
+        .with_key(1, 28)
+        .build(request(&s, &[1]));
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = Uinput::builder()
+        .with_switch(1, 0x11)
+        .build(request(&s, &[1]));
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = Uinput::builder()
+        .with_switch(1, 0)
+        .with_long_press(1, 116, Duration::from_secs(1))
+        .build(request(&s, &[1]));
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let req = Request::builder()
+        .on_chip(s.dev_path())
+        .with_line(1)
+        .as_input()
+        .request()
+        .unwrap();
+    let res = Uinput::builder().with_key(1, 28).build(req);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+}
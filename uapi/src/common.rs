@@ -4,12 +4,14 @@
 
 use libc::{self, c_long, pollfd, time_t, timespec, POLLIN};
 use std::ffi::OsStr;
-use std::mem::{self, MaybeUninit};
+use std::fs::File;
+use std::mem::MaybeUninit;
 use std::os::unix::prelude::{AsRawFd, OsStrExt};
 use std::ptr;
 use std::slice;
 use std::time::Duration;
-use std::fs::File;
+
+use crate::raw::{GPIO_GET_CHIPINFO_IOCTL, GPIO_GET_LINEINFO_UNWATCH_IOCTL};
 
 /// Check if the file has an event available to read.
 ///
@@ -59,6 +61,42 @@ pub fn read_event(f: &File, buf: &mut [u64]) -> Result<usize> {
     }
 }
 
+// Read the events available from a chip or request file into a byte buffer.
+//
+// The events are read into the u64 aligned portion of the buffer, and each
+// record of u64_size words is validated by from_slice as it is iterated.
+// A read error is returned as the only item.
+pub(crate) fn read_records<'a, T: 'a>(
+    f: &File,
+    buf: &'a mut [u8],
+    u64_size: usize,
+    from_slice: fn(&'a [u64]) -> Result<&'a T>,
+) -> impl Iterator<Item = Result<&'a T>> + 'a {
+    // SAFETY: any bit pattern is a valid u64.
+    let (_, words, _) = unsafe { buf.align_to_mut::<u64>() };
+    let res = read_event(f, words);
+    let words: &'a [u64] = words;
+    let (err, records) = match res {
+        Ok(n) => (None, &words[..n]),
+        Err(e) => (Some(Err(e)), &words[..0]),
+    };
+    err.into_iter()
+        .chain(records.chunks(u64_size).map(from_slice))
+}
+
+// Copy the leading bytes of a buffer into a u64 buffer, so an object can be
+// cast from it regardless of the alignment of the original buffer.
+pub(crate) fn copy_bytes(obj: &'static str, b: &[u8], words: &mut [u64]) -> Result<()> {
+    let size = words.len() * 8;
+    if b.len() < size {
+        return Err(Error::from(UnderReadError::new(obj, size, b.len())));
+    }
+    for (w, c) in words.iter_mut().zip(b.chunks_exact(8)) {
+        *w = u64::from_ne_bytes(c.try_into().unwrap());
+    }
+    Ok(())
+}
+
 /// Wait for the file to have an event available to read.
 pub fn wait_event(f: &File, d: Duration) -> Result<bool> {
     let mut pfd = pollfd {
@@ -84,14 +122,6 @@ pub fn wait_event(f: &File, d: Duration) -> Result<bool> {
     }
 }
 
-pub(crate) const IOCTL_MAGIC: u8 = 0xb4;
-
-#[repr(u8)]
-enum Ioctl {
-    GetChipInfo = 1,
-    UnwatchLineInfo = 0xC,
-}
-
 /// Information about a particular GPIO chip.
 #[repr(C)]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -114,11 +144,7 @@ pub struct ChipInfo {
 pub fn get_chip_info(cf: &File) -> Result<ChipInfo> {
     let mut chip = MaybeUninit::<ChipInfo>::uninit();
     unsafe {
-        match libc::ioctl(
-            cf.as_raw_fd(),
-            ior!(Ioctl::GetChipInfo, ChipInfo),
-            chip.as_mut_ptr(),
-        ) {
+        match libc::ioctl(cf.as_raw_fd(), GPIO_GET_CHIPINFO_IOCTL, chip.as_mut_ptr()) {
             0 => Ok(chip.assume_init()),
             _ => Err(Error::from_errno()),
         }
@@ -132,13 +158,7 @@ pub fn get_chip_info(cf: &File) -> Result<ChipInfo> {
 ///
 /// [`LineInfo`]: struct.LineInfo.html
 pub fn unwatch_line_info(cf: &File, offset: Offset) -> Result<()> {
-    match unsafe {
-        libc::ioctl(
-            cf.as_raw_fd(),
-            iorw!(Ioctl::UnwatchLineInfo, u32),
-            &offset,
-        )
-    } {
+    match unsafe { libc::ioctl(cf.as_raw_fd(), GPIO_GET_LINEINFO_UNWATCH_IOCTL, &offset) } {
         0 => Ok(()),
         _ => Err(Error::from_errno()),
     }
@@ -449,6 +469,7 @@ impl LineEdgeEventKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::mem;
 
     #[test]
     fn size_of_chip_info() {
@@ -6,6 +6,8 @@
 
 pub(crate) mod common;
 
+pub mod raw;
+
 // move ops into v1/v2??
 pub use common::{
     has_event, read_event, wait_event, Errno, Error, Name, Result, ValidationError, NAME_LEN_MAX,
@@ -0,0 +1,127 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! The raw ioctl request codes and structures of the GPIO uAPI.
+//!
+//! These are intended for tools that need to reference the uAPI directly,
+//! such as strace decoders, seccomp policy generators and FFI consumers,
+//! rather than duplicating the definitions.
+//!
+//! The request codes are named as per the kernel `gpio.h` header.
+//! The structures are the `repr(C)` equivalents of the kernel structs, and
+//! are the same types used by the rest of the crate.
+
+use crate::common::{ior, iorw};
+use std::mem;
+use std::os::raw::c_ulong;
+
+pub use crate::common::ChipInfo;
+
+/// The ioctl type shared by all GPIO uAPI request codes.
+pub const IOCTL_MAGIC: u8 = 0xb4;
+
+/// Get the [`ChipInfo`] for a chip.
+pub const GPIO_GET_CHIPINFO_IOCTL: c_ulong = ior!(0x01, ChipInfo);
+
+/// Remove a watch on the info for a line, with the offset passed as a `u32`.
+pub const GPIO_GET_LINEINFO_UNWATCH_IOCTL: c_ulong = iorw!(0x0C, u32);
+
+/// The request codes and structures of GPIO uAPI v1.
+#[cfg(feature = "uapi_v1")]
+pub mod v1 {
+    use super::IOCTL_MAGIC;
+    use crate::common::iorw;
+    use std::mem;
+    use std::os::raw::c_ulong;
+
+    pub use crate::v1::{
+        EventRequest, HandleConfig, HandleRequest, LineEdgeEvent, LineInfo, LineInfoChangeEvent,
+        LineValues,
+    };
+
+    /// Get the [`LineInfo`] for a line.
+    pub const GPIO_GET_LINEINFO_IOCTL: c_ulong = iorw!(0x02, LineInfo);
+
+    /// Request lines, as described by a [`HandleRequest`].
+    pub const GPIO_GET_LINEHANDLE_IOCTL: c_ulong = iorw!(0x03, HandleRequest);
+
+    /// Request a line with edge detection, as described by an [`EventRequest`].
+    pub const GPIO_GET_LINEEVENT_IOCTL: c_ulong = iorw!(0x04, EventRequest);
+
+    /// Get the [`LineValues`] of requested lines.
+    pub const GPIOHANDLE_GET_LINE_VALUES_IOCTL: c_ulong = iorw!(0x08, LineValues);
+
+    /// Set the [`LineValues`] of requested lines.
+    pub const GPIOHANDLE_SET_LINE_VALUES_IOCTL: c_ulong = iorw!(0x09, LineValues);
+
+    /// Reconfigure requested lines, as described by a [`HandleConfig`].
+    pub const GPIOHANDLE_SET_CONFIG_IOCTL: c_ulong = iorw!(0x0A, HandleConfig);
+
+    /// Get the [`LineInfo`] for a line and watch it for changes.
+    pub const GPIO_GET_LINEINFO_WATCH_IOCTL: c_ulong = iorw!(0x0B, LineInfo);
+}
+
+/// The request codes and structures of GPIO uAPI v2.
+#[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
+pub mod v2 {
+    use super::IOCTL_MAGIC;
+    use crate::common::iorw;
+    use std::mem;
+    use std::os::raw::c_ulong;
+
+    pub use crate::v2::{
+        LineAttribute, LineConfig, LineConfigAttribute, LineEdgeEvent, LineInfo,
+        LineInfoChangeEvent, LineRequest, LineValues,
+    };
+
+    /// Get the [`LineInfo`] for a line.
+    pub const GPIO_V2_GET_LINEINFO_IOCTL: c_ulong = iorw!(0x05, LineInfo);
+
+    /// Get the [`LineInfo`] for a line and watch it for changes.
+    pub const GPIO_V2_GET_LINEINFO_WATCH_IOCTL: c_ulong = iorw!(0x06, LineInfo);
+
+    /// Request lines, as described by a [`LineRequest`].
+    pub const GPIO_V2_GET_LINE_IOCTL: c_ulong = iorw!(0x07, LineRequest);
+
+    /// Reconfigure requested lines, as described by a [`LineConfig`].
+    pub const GPIO_V2_LINE_SET_CONFIG_IOCTL: c_ulong = iorw!(0x0D, LineConfig);
+
+    /// Get the [`LineValues`] of requested lines.
+    pub const GPIO_V2_LINE_GET_VALUES_IOCTL: c_ulong = iorw!(0x0E, LineValues);
+
+    /// Set the [`LineValues`] of requested lines.
+    pub const GPIO_V2_LINE_SET_VALUES_IOCTL: c_ulong = iorw!(0x0F, LineValues);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // values as per the kernel gpio.h on x86_64
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn request_codes() {
+        assert_eq!(GPIO_GET_CHIPINFO_IOCTL, 0x8044_b401);
+        assert_eq!(GPIO_GET_LINEINFO_UNWATCH_IOCTL, 0xc004_b40c);
+        #[cfg(feature = "uapi_v1")]
+        {
+            assert_eq!(v1::GPIO_GET_LINEINFO_IOCTL, 0xc048_b402);
+            assert_eq!(v1::GPIO_GET_LINEHANDLE_IOCTL, 0xc16c_b403);
+            assert_eq!(v1::GPIO_GET_LINEEVENT_IOCTL, 0xc030_b404);
+            assert_eq!(v1::GPIOHANDLE_GET_LINE_VALUES_IOCTL, 0xc040_b408);
+            assert_eq!(v1::GPIOHANDLE_SET_LINE_VALUES_IOCTL, 0xc040_b409);
+            assert_eq!(v1::GPIOHANDLE_SET_CONFIG_IOCTL, 0xc054_b40a);
+            assert_eq!(v1::GPIO_GET_LINEINFO_WATCH_IOCTL, 0xc048_b40b);
+        }
+        #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
+        {
+            assert_eq!(v2::GPIO_V2_GET_LINEINFO_IOCTL, 0xc100_b405);
+            assert_eq!(v2::GPIO_V2_GET_LINEINFO_WATCH_IOCTL, 0xc100_b406);
+            assert_eq!(v2::GPIO_V2_GET_LINE_IOCTL, 0xc250_b407);
+            assert_eq!(v2::GPIO_V2_LINE_SET_CONFIG_IOCTL, 0xc110_b40d);
+            assert_eq!(v2::GPIO_V2_LINE_GET_VALUES_IOCTL, 0xc010_b40e);
+            assert_eq!(v2::GPIO_V2_LINE_SET_VALUES_IOCTL, 0xc010_b40f);
+        }
+    }
+}
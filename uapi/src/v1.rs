@@ -7,20 +7,15 @@ use std::fs::File;
 use std::mem;
 use std::os::unix::prelude::{AsRawFd, FromRawFd};
 
+use crate::raw::v1::{
+    GPIOHANDLE_GET_LINE_VALUES_IOCTL, GPIOHANDLE_SET_CONFIG_IOCTL,
+    GPIOHANDLE_SET_LINE_VALUES_IOCTL, GPIO_GET_LINEEVENT_IOCTL, GPIO_GET_LINEHANDLE_IOCTL,
+    GPIO_GET_LINEINFO_IOCTL, GPIO_GET_LINEINFO_WATCH_IOCTL,
+};
+
 // common to ABI v1 and v2.
 pub use super::common::*;
 
-#[repr(u8)]
-enum Ioctl {
-    GetLineInfo = 2,
-    GetLineHandle = 3,
-    GetLineEvent = 4,
-    GetLineValues = 8,
-    SetLineValues = 9,
-    SetConfig = 0xA,
-    WatchLineInfo = 0xB,
-}
-
 /// Information about a certain GPIO line.
 #[repr(C)]
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -91,7 +86,7 @@ pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct contains raw byte arrays and bitfields that are safe to decode.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineInfo, LineInfo), &li) } {
+    match unsafe { libc::ioctl(cf.as_raw_fd(), GPIO_GET_LINEINFO_IOCTL, &li) } {
         0 => Ok(li),
         _ => Err(Error::from_errno()),
     }
@@ -112,7 +107,7 @@ pub fn watch_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct contains raw byte arrays and bitfields that are safe to decode.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::WatchLineInfo, LineInfo), &li) } {
+    match unsafe { libc::ioctl(cf.as_raw_fd(), GPIO_GET_LINEINFO_WATCH_IOCTL, &li) } {
         0 => Ok(li),
         _ => Err(Error::from_errno()),
     }
@@ -163,6 +158,22 @@ impl LineInfoChangeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read a LineInfoChangeEvent from a byte buffer.
+    ///
+    /// The buffer may be from any source, such as a fuzzer or a transport other
+    /// than the chip File, and need not be aligned, as the content is copied
+    /// from the buffer and validated before being returned.
+    /// Unlike events read from the kernel, the padding is also checked to be zeroed.
+    pub fn from_bytes(b: &[u8]) -> Result<LineInfoChangeEvent> {
+        let mut buf = [0_u64; mem::size_of::<LineInfoChangeEvent>() / 8];
+        copy_bytes("LineInfoChangeEvent", b, &mut buf)?;
+        let evt = LineInfoChangeEvent::from_slice(&buf)?;
+        if !evt.padding.is_zeroed() {
+            return Err(Error::from(ValidationError::new("padding", "not zeroed")));
+        }
+        Ok(evt.clone())
+    }
+
     /// The number of u64 words required to store a LineInfoChangeEvent.
     pub fn u64_size() -> usize {
         mem::size_of::<LineInfoChangeEvent>() / 8
@@ -249,7 +260,7 @@ bitflags! {
 pub fn get_line_handle(cf: &File, hr: HandleRequest) -> Result<File> {
     // SAFETY: hr is consumed and the returned file is drawn from the returned fd.
     unsafe {
-        match libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineHandle, HandleRequest), &hr) {
+        match libc::ioctl(cf.as_raw_fd(), GPIO_GET_LINEHANDLE_IOCTL, &hr) {
             0 => Ok(File::from_raw_fd(hr.fd)),
             _ => Err(Error::from_errno()),
         }
@@ -284,7 +295,7 @@ pub struct HandleConfig {
 pub fn set_line_config(lf: &File, hc: HandleConfig) -> Result<()> {
     // SAFETY: hc is consumed.
     unsafe {
-        match libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetConfig, HandleConfig), &hc) {
+        match libc::ioctl(lf.as_raw_fd(), GPIOHANDLE_SET_CONFIG_IOCTL, &hc) {
             0 => Ok(()),
             _ => Err(Error::from_errno()),
         }
@@ -369,7 +380,7 @@ pub fn get_line_values(lf: &File, vals: &mut LineValues) -> Result<()> {
     match unsafe {
         libc::ioctl(
             lf.as_raw_fd(),
-            iorw!(Ioctl::GetLineValues, LineValues),
+            GPIOHANDLE_GET_LINE_VALUES_IOCTL,
             vals.0.as_mut_ptr(),
         )
     } {
@@ -388,7 +399,7 @@ pub fn set_line_values(lf: &File, vals: &LineValues) -> Result<()> {
     match unsafe {
         libc::ioctl(
             lf.as_raw_fd(),
-            iorw!(Ioctl::SetLineValues, LineValues),
+            GPIOHANDLE_SET_LINE_VALUES_IOCTL,
             vals.0.as_ptr(),
         )
     } {
@@ -448,13 +459,39 @@ bitflags! {
 pub fn get_line_event(cf: &File, er: EventRequest) -> Result<File> {
     // SAFETY: er is consumed and the returned file is drawn from the returned fd.
     unsafe {
-        match libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineEvent, EventRequest), &er) {
+        match libc::ioctl(cf.as_raw_fd(), GPIO_GET_LINEEVENT_IOCTL, &er) {
             0 => Ok(File::from_raw_fd(er.fd)),
             _ => Err(Error::from_errno()),
         }
     }
 }
 
+/// Read the edge events available from a line request into a buffer.
+///
+/// Blocks until at least one event is available, then reads as many events
+/// as fit in the buffer with a single read.
+///
+/// The returned iterator validates each event in turn and returns a reference
+/// into the buffer, so the events are not copied.
+/// An error reading the request is returned as the only item.
+///
+/// The events are read into the 8 byte aligned portion of the buffer, so a
+/// buffer that is not aligned requires up to 7 additional bytes.
+///
+/// * `lf` - The request file returned by [`get_line_event`].
+/// * `buf` - The buffer to read the events into.
+pub fn read_events<'a>(
+    lf: &File,
+    buf: &'a mut [u8],
+) -> impl Iterator<Item = Result<&'a LineEdgeEvent>> + 'a {
+    read_records(
+        lf,
+        buf,
+        LineEdgeEvent::u64_size(),
+        LineEdgeEvent::from_slice,
+    )
+}
+
 /// Information about an edge event on a requested line.
 #[repr(C)]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -492,6 +529,17 @@ impl LineEdgeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read a LineEdgeEvent from a byte buffer.
+    ///
+    /// The buffer may be from any source, such as a fuzzer or a transport other
+    /// than the line request File, and need not be aligned, as the content is copied
+    /// from the buffer and validated before being returned.
+    pub fn from_bytes(b: &[u8]) -> Result<LineEdgeEvent> {
+        let mut buf = [0_u64; mem::size_of::<LineEdgeEvent>() / 8];
+        copy_bytes("LineEdgeEvent", b, &mut buf)?;
+        LineEdgeEvent::from_slice(&buf).cloned()
+    }
+
     /// The number of u64 words required to store a LineEdgeEvent.
     pub fn u64_size() -> usize {
         mem::size_of::<LineEdgeEvent>() / 8
@@ -553,6 +601,42 @@ mod tests {
                 assert!(a.validate().is_ok());
             }
         }
+
+        #[test]
+        fn from_bytes() {
+            use super::{Error, LineInfoChangeKind, UnderReadError};
+
+            fn put(b: &mut [u8], offset: usize, v: u32) {
+                b[offset..offset + 4].copy_from_slice(&v.to_ne_bytes());
+            }
+
+            // offset by one to be unaligned
+            let mut buf = [0_u8; 104 + 1];
+            let b = &mut buf[1..];
+            b[72..80].copy_from_slice(&1234_u64.to_ne_bytes());
+            put(b, 80, 1);
+            let e = LineInfoChangeEvent::from_bytes(b).unwrap();
+            assert_eq!(e.timestamp_ns, 1234);
+            assert_eq!(e.kind, LineInfoChangeKind::Requested);
+
+            assert_eq!(
+                LineInfoChangeEvent::from_bytes(&b[..103]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineInfoChangeEvent", 104, 103))
+            );
+
+            put(b, 84, 1);
+            match LineInfoChangeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "padding"),
+                e => panic!("unexpected error: {}", e),
+            }
+            put(b, 84, 0);
+
+            put(b, 80, 7);
+            match LineInfoChangeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "kind"),
+                e => panic!("unexpected error: {}", e),
+            }
+        }
     }
 
     mod handle_request {
@@ -629,6 +713,35 @@ mod tests {
                 assert!(a.validate().is_ok());
             }
         }
+
+        #[test]
+        fn from_bytes() {
+            use super::{Error, LineEdgeEventKind, UnderReadError};
+
+            fn put(b: &mut [u8], offset: usize, v: u32) {
+                b[offset..offset + 4].copy_from_slice(&v.to_ne_bytes());
+            }
+
+            // offset by one to be unaligned
+            let mut buf = [0_u8; 16 + 1];
+            let b = &mut buf[1..];
+            b[0..8].copy_from_slice(&1234_u64.to_ne_bytes());
+            put(b, 8, 2);
+            let e = LineEdgeEvent::from_bytes(b).unwrap();
+            assert_eq!(e.timestamp_ns, 1234);
+            assert_eq!(e.kind, LineEdgeEventKind::FallingEdge);
+
+            assert_eq!(
+                LineEdgeEvent::from_bytes(&b[..15]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineEdgeEvent", 16, 15))
+            );
+
+            put(b, 8, 7);
+            match LineEdgeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "kind"),
+                e => panic!("unexpected error: {}", e),
+            }
+        }
     }
 
     mod line_values {
@@ -10,19 +10,14 @@ use std::mem;
 use std::os::unix::prelude::{AsRawFd, FromRawFd};
 use std::time::Duration;
 
+use crate::raw::v2::{
+    GPIO_V2_GET_LINEINFO_IOCTL, GPIO_V2_GET_LINEINFO_WATCH_IOCTL, GPIO_V2_GET_LINE_IOCTL,
+    GPIO_V2_LINE_GET_VALUES_IOCTL, GPIO_V2_LINE_SET_CONFIG_IOCTL, GPIO_V2_LINE_SET_VALUES_IOCTL,
+};
+
 // common to ABI v1 and v2.
 pub use super::common::*;
 
-#[repr(u8)]
-enum Ioctl {
-    GetLineInfo = 5,
-    WatchLineInfo = 6,
-    GetLine = 7,
-    SetLineConfig = 0xD,
-    GetLineValues = 0xE,
-    SetLineValues = 0xF,
-}
-
 bitflags! {
     /// Flags indicating the configuration of a line.
     #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
@@ -64,6 +59,9 @@ bitflags! {
         const EVENT_CLOCK_REALTIME = 2048;
 
         /// The line events contain **HTE** timestamps.
+        ///
+        /// Requires Linux 5.19 or later, and a hardware timestamp
+        /// provider for the line.
         const EVENT_CLOCK_HTE = 4096;
     }
 }
@@ -169,7 +167,7 @@ impl LineValues {
 #[inline]
 pub fn get_line_values(lf: &File, lv: &mut LineValues) -> Result<()> {
     // SAFETY: returned struct contains raw byte arrays and bitfields that are safe to decode.
-    match unsafe { libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::GetLineValues, LineValues), lv) } {
+    match unsafe { libc::ioctl(lf.as_raw_fd(), GPIO_V2_LINE_GET_VALUES_IOCTL, lv) } {
         0 => Ok(()),
         _ => Err(Error::from_errno()),
     }
@@ -184,7 +182,7 @@ pub fn get_line_values(lf: &File, lv: &mut LineValues) -> Result<()> {
 #[inline]
 pub fn set_line_values(lf: &File, lv: &LineValues) -> Result<()> {
     // SAFETY: lv is not modified.
-    match unsafe { libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetLineValues, LineValues), lv) } {
+    match unsafe { libc::ioctl(lf.as_raw_fd(), GPIO_V2_LINE_SET_VALUES_IOCTL, lv) } {
         0 => Ok(()),
         _ => Err(Error::from_errno()),
     }
@@ -427,7 +425,7 @@ impl LineConfig {
 pub fn set_line_config(lf: &File, lc: LineConfig) -> Result<()> {
     // SAFETY: lc is consumed.
     unsafe {
-        match libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetLineConfig, LineConfig), &lc) {
+        match libc::ioctl(lf.as_raw_fd(), GPIO_V2_LINE_SET_CONFIG_IOCTL, &lc) {
             0 => Ok(()),
             _ => Err(Error::from_errno()),
         }
@@ -480,7 +478,7 @@ pub struct LineRequest {
 pub fn get_line(cf: &File, lr: LineRequest) -> Result<File> {
     // SAFETY: lr is consumed and the returned file is drawn from the returned fd.
     unsafe {
-        match libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLine, LineRequest), &lr) {
+        match libc::ioctl(cf.as_raw_fd(), GPIO_V2_GET_LINE_IOCTL, &lr) {
             0 => Ok(File::from_raw_fd(lr.fd)),
             _ => Err(Error::from_errno()),
         }
@@ -571,7 +569,7 @@ pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct is explicitly validated before being returned.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineInfo, LineInfo), &li) } {
+    match unsafe { libc::ioctl(cf.as_raw_fd(), GPIO_V2_GET_LINEINFO_IOCTL, &li) } {
         0 => li.validate().map(|_| li).map_err(Error::from),
         _ => Err(Error::from_errno()),
     }
@@ -593,7 +591,7 @@ pub fn watch_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct is explicitly validated before being returned.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::WatchLineInfo, LineInfo), &li) } {
+    match unsafe { libc::ioctl(cf.as_raw_fd(), GPIO_V2_GET_LINEINFO_WATCH_IOCTL, &li) } {
         0 => li.validate().map(|_| li).map_err(Error::from),
         _ => Err(Error::from_errno()),
     }
@@ -639,17 +637,62 @@ impl LineInfoChangeEvent {
 
     /// Check that a LineInfoChangeEvent read from the kernel is valid in Rust.
     fn validate(&self) -> ValidationResult {
+        self.info
+            .validate()
+            .map_err(|e| ValidationError::new(format!("info.{}", e.field), e.msg))?;
         self.kind
             .validate()
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read an info change event from a byte buffer.
+    ///
+    /// The buffer may be from any source, such as a fuzzer or a transport other
+    /// than the chip File, and need not be aligned, as the content is copied
+    /// from the buffer and validated before being returned.
+    /// Unlike events read from the kernel, the padding is also checked to be zeroed.
+    pub fn from_bytes(b: &[u8]) -> Result<LineInfoChangeEvent> {
+        let mut buf = [0_u64; mem::size_of::<LineInfoChangeEvent>() / 8];
+        copy_bytes("LineInfoChangeEvent", b, &mut buf)?;
+        let evt = LineInfoChangeEvent::from_slice(&buf)?;
+        if !evt.padding.is_zeroed() {
+            return Err(Error::from(ValidationError::new("padding", "not zeroed")));
+        }
+        Ok(evt.clone())
+    }
+
     /// The number of u64 words required to store a LineInfoChangeEvent.
     pub fn u64_size() -> usize {
         mem::size_of::<LineInfoChangeEvent>() / 8
     }
 }
 
+/// Read the edge events available from a line request into a buffer.
+///
+/// Blocks until at least one event is available, then reads as many events
+/// as fit in the buffer with a single read.
+///
+/// The returned iterator validates each event in turn and returns a reference
+/// into the buffer, so the events are not copied.
+/// An error reading the request is returned as the only item.
+///
+/// The events are read into the 8 byte aligned portion of the buffer, so a
+/// buffer that is not aligned requires up to 7 additional bytes.
+///
+/// * `lf` - The request file returned by [`get_line`].
+/// * `buf` - The buffer to read the events into.
+pub fn read_events<'a>(
+    lf: &File,
+    buf: &'a mut [u8],
+) -> impl Iterator<Item = Result<&'a LineEdgeEvent>> + 'a {
+    read_records(
+        lf,
+        buf,
+        LineEdgeEvent::u64_size(),
+        LineEdgeEvent::from_slice,
+    )
+}
+
 /// Information about an edge event on a requested line.
 #[repr(C)]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -662,6 +705,9 @@ pub struct LineEdgeEvent {
     ///
     /// If the [`LineFlags::EVENT_CLOCK_REALTIME`] flag is set then the
     /// timestamp is read from **CLOCK_REALTIME**.
+    ///
+    /// If the [`LineFlags::EVENT_CLOCK_HTE`] flag is set then the timestamp
+    /// is provided by the hardware timestamp engine.
     pub timestamp_ns: u64,
 
     /// The event trigger identifier.
@@ -711,6 +757,21 @@ impl LineEdgeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read an edge event from a byte buffer.
+    ///
+    /// The buffer may be from any source, such as a fuzzer or a transport other
+    /// than the line request File, and need not be aligned, as the content is copied
+    /// from the buffer and validated before being returned.
+    pub fn from_bytes(b: &[u8]) -> Result<LineEdgeEvent> {
+        let mut buf = [0_u64; mem::size_of::<LineEdgeEvent>() / 8];
+        copy_bytes("LineEdgeEvent", b, &mut buf)?;
+        let evt = LineEdgeEvent::from_slice(&buf)?;
+        if !evt.padding.is_zeroed() {
+            return Err(Error::from(ValidationError::new("padding", "not zeroed")));
+        }
+        Ok(evt.clone())
+    }
+
     /// The number of u64 words required to store a LineEdgeEvent.
     pub fn u64_size() -> usize {
         mem::size_of::<LineEdgeEvent>() / 8
@@ -937,6 +998,49 @@ mod tests {
                 assert!(a.validate().is_ok());
             }
         }
+
+        #[test]
+        fn from_bytes() {
+            use super::{Error, LineInfoChangeKind, UnderReadError};
+
+            fn put(b: &mut [u8], offset: usize, v: u32) {
+                b[offset..offset + 4].copy_from_slice(&v.to_ne_bytes());
+            }
+
+            // offset by one to be unaligned
+            let mut buf = [0_u8; 288 + 1];
+            let b = &mut buf[1..];
+            b[256..264].copy_from_slice(&1234_u64.to_ne_bytes());
+            put(b, 264, 3);
+            let e = LineInfoChangeEvent::from_bytes(b).unwrap();
+            assert_eq!(e.timestamp_ns, 1234);
+            assert_eq!(e.kind, LineInfoChangeKind::Reconfigured);
+
+            assert_eq!(
+                LineInfoChangeEvent::from_bytes(&b[..287]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineInfoChangeEvent", 288, 287))
+            );
+
+            put(b, 268, 1);
+            match LineInfoChangeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "padding"),
+                e => panic!("unexpected error: {}", e),
+            }
+            put(b, 268, 0);
+            put(b, 68, 11);
+            match LineInfoChangeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "info.num_attrs"),
+                e => panic!("unexpected error: {}", e),
+            }
+            put(b, 68, 0);
+
+            put(b, 264, 7);
+            match LineInfoChangeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "kind"),
+                e => panic!("unexpected error: {}", e),
+            }
+        }
+
         #[test]
         fn size() {
             assert_eq!(
@@ -980,6 +1084,49 @@ mod tests {
             }
         }
 
+        #[test]
+        fn from_bytes() {
+            use super::{Error, LineEdgeEventKind, UnderReadError};
+
+            fn put(b: &mut [u8], offset: usize, v: u32) {
+                b[offset..offset + 4].copy_from_slice(&v.to_ne_bytes());
+            }
+
+            // offset by one to be unaligned
+            let mut buf = [0_u8; 48 + 1];
+            let b = &mut buf[1..];
+            b[0..8].copy_from_slice(&1234_u64.to_ne_bytes());
+            put(b, 8, 2);
+            let e = LineEdgeEvent::from_bytes(b).unwrap();
+            assert_eq!(e.timestamp_ns, 1234);
+            assert_eq!(e.kind, LineEdgeEventKind::FallingEdge);
+            put(b, 12, 3);
+            put(b, 16, 5);
+            put(b, 20, 2);
+            let e = LineEdgeEvent::from_bytes(b).unwrap();
+            assert_eq!(e.offset, 3);
+            assert_eq!(e.seqno, 5);
+            assert_eq!(e.line_seqno, 2);
+
+            assert_eq!(
+                LineEdgeEvent::from_bytes(&b[..47]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineEdgeEvent", 48, 47))
+            );
+
+            put(b, 44, 1);
+            match LineEdgeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "padding"),
+                e => panic!("unexpected error: {}", e),
+            }
+            put(b, 44, 0);
+
+            put(b, 8, 7);
+            match LineEdgeEvent::from_bytes(b).unwrap_err() {
+                Error::Validation(e) => assert_eq!(e.field, "kind"),
+                e => panic!("unexpected error: {}", e),
+            }
+        }
+
         #[test]
         fn size() {
             assert_eq!(
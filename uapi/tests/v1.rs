@@ -16,6 +16,7 @@ mod v1 {
     mod get_line_handle;
     mod get_line_info;
     mod get_line_values;
+    mod read_events;
     mod set_line_config;
     mod set_line_values;
     mod unwatch_line_info;
@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::*;
+use crate::common::wait_propagation_delay;
+use std::mem;
+
+#[test]
+fn multiple_events() {
+    let s = Simpleton::new(4);
+    let f = fs::File::open(s.dev_path()).unwrap();
+    let offset = 2;
+    let er = EventRequest {
+        consumer: "read_events".into(),
+        offset,
+        eventflags: EventRequestFlags::BOTH_EDGES,
+        ..Default::default()
+    };
+    let l = get_line_event(&f, er).unwrap();
+
+    for _ in 0..2 {
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+    }
+
+    // room for 3 events, allowing for alignment
+    let mut buf = vec![0_u8; 3 * mem::size_of::<LineEdgeEvent>() + 7];
+    let events: Vec<&LineEdgeEvent> = read_events(&l, &mut buf[1..])
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(events.len(), 3);
+    for (idx, event) in events.iter().enumerate() {
+        let kind = if idx % 2 == 0 {
+            LineEdgeEventKind::RisingEdge
+        } else {
+            LineEdgeEventKind::FallingEdge
+        };
+        assert_eq!(event.kind, kind);
+    }
+
+    let events: Vec<&LineEdgeEvent> = read_events(&l, &mut buf).collect::<Result<_>>().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, LineEdgeEventKind::FallingEdge);
+    assert!(!has_event(&l).unwrap());
+
+    // too small for an event
+    let mut buf = vec![0_u8; mem::size_of::<LineEdgeEvent>() - 1];
+    let mut iter = read_events(&l, &mut buf);
+    assert!(matches!(iter.next(), Some(Err(Error::Os(_)))));
+    assert!(iter.next().is_none());
+}
@@ -15,6 +15,7 @@ mod v2 {
     mod get_line;
     mod get_line_info;
     mod get_line_values;
+    mod read_events;
     mod set_line_config;
     mod set_line_values;
     mod unwatch_line_info;